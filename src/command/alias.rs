@@ -48,10 +48,16 @@ command!(
         )
         .arg(
             Arg::new("expanded")
-                .help("what the short version of the command should expand to")
+                .help(
+                    "what the short version of the command should expand to. Can be given as \
+                     several words, optionally after an =, like: alias crash = pods -r Crash"
+                )
                 .required(false)
+                .multiple_values(true)
+                .allow_hyphen_values(true)
                 .requires("alias")
         )
+        .trailing_var_arg(true)
         .after_help(
             "An alias is a substitution rule.  When click encounters an alias at the start of a
 command, it will substitue the expanded version for what was typed.
@@ -60,6 +66,10 @@ As with Bash: The first word of the expansion is tested for aliases, but a word
 an alias being expanded is not expanded a second time.  So one can alias logs to \"logs -e\", for
 instance, without causing infinite expansion.
 
+An alias can take arguments: $1 to $9 in the expansion are replaced by the words typed after the
+alias, and $@ by all of them. Any words that aren't used are added to the end of the expansion, as
+they are for aliases without arguments.
+
 Examples:
   # Display current aliases
   alias
@@ -71,7 +81,13 @@ Examples:
   alias pn \"pods -r nginx\"
 
   # alias el to run logs and grep for ERROR
-  alias el \"logs | grep ERROR\""
+  alias el \"logs | grep ERROR\"
+
+  # alias crash to list pods with Crash in their status, the = is optional
+  alias crash = pods -r Crash
+
+  # alias lg so that 'lg main ERROR' shows the logs of the main container, grepped for ERROR
+  alias lg \"logs $1 | grep $2\""
         ),
     vec!["alias", "aliases"],
     noop_complete!(),
@@ -79,7 +95,27 @@ Examples:
     |matches, env, writer| {
         if matches.is_present("alias") {
             let alias = matches.value_of("alias").unwrap(); // safe, checked above
-            let expanded = matches.value_of("expanded").unwrap(); // safe, required with alias
+                                                            // safe, required with alias
+            let mut words: Vec<&str> = matches.values_of("expanded").unwrap().collect();
+            if words.len() > 1 && words[0] == "=" {
+                words.remove(0);
+            }
+            let expanded = if words.len() == 1 {
+                words[0].to_string()
+            } else {
+                // keep words that were quoted together as one word
+                let quoted: Vec<String> = words
+                    .iter()
+                    .map(|word| {
+                        if word.contains(char::is_whitespace) {
+                            format!("\"{}\"", word)
+                        } else {
+                            word.to_string()
+                        }
+                    })
+                    .collect();
+                quoted.join(" ")
+            };
             env.add_alias(config::Alias {
                 alias: alias.to_owned(),
                 expanded: expanded.to_owned(),
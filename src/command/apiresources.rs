@@ -0,0 +1,196 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use regex::Regex;
+use rustyline::completion::Pair as RustlinePair;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    completer,
+    crd::ApiResource,
+    env::Env,
+    output::ClickWriter,
+    table::CellSpec,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// If resource should be listed: it's in group (if given), is namespaced or not as asked, and its
+/// name, short names or kind match regex (if given)
+fn matches(
+    resource: &ApiResource,
+    group: Option<&str>,
+    namespaced: Option<bool>,
+    regex: Option<&Regex>,
+) -> bool {
+    group.is_none_or(|group| resource.group == group)
+        && namespaced.is_none_or(|namespaced| resource.namespaced == namespaced)
+        && regex.is_none_or(|regex| {
+            regex.is_match(&resource.name)
+                || regex.is_match(&resource.kind)
+                || resource
+                    .short_names
+                    .iter()
+                    .any(|short| regex.is_match(short))
+        })
+}
+
+command!(
+    ApiResources,
+    "api-resources",
+    "List every kind of resource the cluster serves, with its group, version, kind, whether it's \
+     namespaced and the verbs it supports",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("regex")
+                .help("Only list resources whose name, short names or kind match this regex")
+                .validator(|s| Regex::new(s).map(|_| ()).map_err(|e| e.to_string()))
+                .index(1),
+        )
+        .arg(
+            Arg::new("group")
+                .short('g')
+                .long("api-group")
+                .help("Only list resources in this api group. Use \"\" for the core group")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("namespaced")
+                .long("namespaced")
+                .help("Only list namespaced resources")
+                .conflicts_with("cluster")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("cluster")
+                .long("cluster-scoped")
+                .help("Only list resources that aren't namespaced")
+                .takes_value(false),
+        )
+        .after_help(
+            "Resources come from the api server's discovery information: the core group, and the \
+preferred version of every other group, including those defined by CRDs and aggregated apis. \
+Subresources, like pods/log, aren't listed. Groups that can't be read (like an aggregated api whose \
+service is down) are listed after the table.
+
+Examples:
+  # everything the cluster serves
+  api-resources
+
+  # what's in the networking.k8s.io group
+  api-resources -g networking.k8s.io
+
+  # cluster scoped resources to do with storage
+  api-resources --cluster-scoped storage",
+        )
+    },
+    vec!["api-resources"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let regex = matches.value_of("regex").map(|s| Regex::new(s).unwrap()); // validated
+        let group = matches.value_of("group");
+        let namespaced = if matches.is_present("namespaced") {
+            Some(true)
+        } else if matches.is_present("cluster") {
+            Some(false)
+        } else {
+            None
+        };
+        let mut discovery = crate::crd::discover_resources(env)?;
+        discovery
+            .resources
+            .sort_by(|a, b| (&a.group, &a.name).cmp(&(&b.group, &b.name)));
+        let rows: Vec<Vec<CellSpec<'_>>> = discovery
+            .resources
+            .iter()
+            .filter(|resource| self::matches(resource, group, namespaced, regex.as_ref()))
+            .map(|resource| {
+                vec![
+                    resource.name.as_str().into(),
+                    resource.short_names.join(",").into(),
+                    resource.group.as_str().into(),
+                    resource.version.as_str().into(),
+                    resource.kind.as_str().into(),
+                    resource.namespaced.to_string().into(),
+                    resource.verbs.join(",").into(),
+                ]
+            })
+            .collect();
+        if rows.is_empty() {
+            clickwriteln!(writer, "No resources found");
+        } else {
+            crate::table::print_table(
+                vec![
+                    "Name",
+                    "Short Names",
+                    "Group",
+                    "Version",
+                    "Kind",
+                    "Namespaced",
+                    "Verbs",
+                ],
+                rows,
+                env,
+                writer,
+            );
+        }
+        if !discovery.failed.is_empty() {
+            clickwriteln!(
+                writer,
+                "{}",
+                env.styles.warning(&format!(
+                    "Could not read the resources of: {}",
+                    discovery.failed.join(", ")
+                ))
+            );
+        }
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let ingresses = ApiResource {
+            group: "networking.k8s.io".to_string(),
+            version: "v1".to_string(),
+            kind: "Ingress".to_string(),
+            name: "ingresses".to_string(),
+            singular_name: "ingress".to_string(),
+            short_names: vec!["ing".to_string()],
+            namespaced: true,
+            verbs: vec!["get".to_string(), "list".to_string()],
+        };
+        assert!(matches(&ingresses, None, None, None));
+        assert!(matches(
+            &ingresses,
+            Some("networking.k8s.io"),
+            Some(true),
+            None
+        ));
+        assert!(!matches(&ingresses, Some(""), None, None));
+        assert!(!matches(&ingresses, None, Some(false), None));
+        let regex = Regex::new("^ing$").unwrap();
+        assert!(matches(&ingresses, None, None, Some(&regex)));
+        let regex = Regex::new("Service").unwrap();
+        assert!(!matches(&ingresses, None, None, Some(&regex)));
+    }
+}
@@ -0,0 +1,690 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::Utc;
+use clap::{Arg, Command as ClapCommand};
+use k8s_openapi::{
+    api::{autoscaling::v2 as api_autoscaling, core::v1 as api},
+    ListOptional,
+};
+
+use crate::{
+    command::command_def::{
+        all_namespaces_arg, columns_arg, exec_match, field_selector_arg, filter_arg,
+        filter_pattern_arg, hide_arg, label_selector_arg, no_headers_arg, output_arg, quiet_arg,
+        show_arg, sort_arg, sort_by_arg, start_clap, watch_arg, Cmd, DEFAULT_WATCH_SECS,
+    },
+    command::events::get_event_ts,
+    command::{
+        format_duration, list_namespace, list_options, run_list_command, time_since, Extractor,
+    },
+    completer,
+    describe::describe_table,
+    env::Env,
+    error::ClickError,
+    kobj::{KObj, ObjType},
+    output::ClickWriter,
+    table::{format_timestamp, CellSpec},
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::Ordering;
+
+lazy_static! {
+    static ref HPA_EXTRACTORS: HashMap<String, Extractor<api_autoscaling::HorizontalPodAutoscaler>> = {
+        let mut m: HashMap<String, Extractor<api_autoscaling::HorizontalPodAutoscaler>> =
+            HashMap::new();
+        m.insert("Reference".to_owned(), hpa_reference);
+        m.insert("Targets".to_owned(), hpa_targets);
+        m.insert("Min Pods".to_owned(), hpa_min_pods);
+        m.insert("Max Pods".to_owned(), hpa_max_pods);
+        m.insert("Replicas".to_owned(), hpa_replicas);
+        m
+    };
+}
+
+const COL_MAP: &[(&str, &str)] = &[
+    ("name", "Name"),
+    ("reference", "Reference"),
+    ("targets", "Targets"),
+    ("minpods", "Min Pods"),
+    ("maxpods", "Max Pods"),
+    ("replicas", "Replicas"),
+    ("age", "Age"),
+];
+
+const COL_FLAGS: &[&str] = &{ extract_first!(COL_MAP) };
+
+const EXTRA_COL_MAP: &[(&str, &str)] = &[("labels", "Labels"), ("namespace", "Namespace")];
+
+const EXTRA_COL_FLAGS: &[&str] = &{ extract_first!(EXTRA_COL_MAP) };
+
+fn hpa_to_kobj(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> KObj {
+    let meta = &hpa.metadata;
+    KObj {
+        name: meta.name.clone().unwrap_or_else(|| "<Unknown>".into()),
+        namespace: meta.namespace.clone(),
+        typ: ObjType::HorizontalPodAutoscaler,
+    }
+}
+
+/// What the hpa scales, like Deployment/web
+fn reference_str(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> Option<String> {
+    hpa.spec.as_ref().map(|spec| {
+        format!(
+            "{}/{}",
+            spec.scale_target_ref.kind, spec.scale_target_ref.name
+        )
+    })
+}
+
+/// The current value and target of each metric, like kubectl shows them: 95%/80%, 120/100
+fn targets_str(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> Option<String> {
+    let metrics = metric_values(hpa);
+    if metrics.is_empty() {
+        return None;
+    }
+    let targets: Vec<String> = metrics
+        .iter()
+        .map(|metric| format!("{}/{}", metric.current, metric.target))
+        .collect();
+    Some(targets.join(", "))
+}
+
+fn hpa_reference(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> Option<CellSpec<'_>> {
+    reference_str(hpa).map(|reference| reference.into())
+}
+
+fn hpa_targets(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> Option<CellSpec<'_>> {
+    targets_str(hpa).map(|targets| targets.into())
+}
+
+fn hpa_min_pods(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> Option<CellSpec<'_>> {
+    hpa.spec
+        .as_ref()
+        .map(|spec| CellSpec::new_int(spec.min_replicas.unwrap_or(1).into()))
+}
+
+fn hpa_max_pods(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> Option<CellSpec<'_>> {
+    hpa.spec
+        .as_ref()
+        .map(|spec| CellSpec::new_int(spec.max_replicas.into()))
+}
+
+fn hpa_replicas(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> Option<CellSpec<'_>> {
+    hpa.status
+        .as_ref()
+        .map(|status| CellSpec::new_int(status.current_replicas.unwrap_or(0).into()))
+}
+
+list_command!(
+    HorizontalPodAutoscalers,
+    "horizontalpodautoscalers",
+    "Get horizontal pod autoscalers (in current namespace if set)",
+    super::COL_FLAGS,
+    super::EXTRA_COL_FLAGS,
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("regex")
+                .short('r')
+                .long("regex")
+                .help("Filter hpas by the specified regex")
+                .takes_value(true),
+        )
+        .arg(all_namespaces_arg())
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
+        .arg(show_arg(EXTRA_COL_FLAGS, true))
+        .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
+        .arg(
+            Arg::new("reverse")
+                .short('R')
+                .long("reverse")
+                .help("Reverse the order of the returned list")
+                .takes_value(false),
+        )
+    },
+    vec!["horizontalpodautoscalers", "hpas"],
+    noop_complete!(),
+    [(
+        "regex".to_string(),
+        completer::hpa_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter(),
+    |matches, env, writer| {
+        let (request, _response_body) = match list_namespace(&matches, env) {
+            Some(ns) => {
+                api_autoscaling::HorizontalPodAutoscaler::list_namespaced_horizontal_pod_autoscaler(
+                    ns,
+                    list_options(&matches),
+                )?
+            }
+            None => api_autoscaling::HorizontalPodAutoscaler::list_horizontal_pod_autoscaler_for_all_namespaces(
+                list_options(&matches),
+            )?,
+        };
+        let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
+        run_list_command(
+            matches,
+            env,
+            writer,
+            cols,
+            request,
+            COL_MAP,
+            Some(EXTRA_COL_MAP),
+            Some(&HPA_EXTRACTORS),
+            hpa_to_kobj,
+        )
+    }
+);
+
+/// A metric an hpa scales on, with its current value and target, in the same terms (utilization,
+/// average value or value)
+#[derive(Debug, PartialEq)]
+struct MetricValue {
+    name: String,
+    current: String,
+    target: String,
+}
+
+fn target_str(target: &api_autoscaling::MetricTarget) -> String {
+    match (
+        target.average_utilization,
+        target.average_value.as_ref(),
+        target.value.as_ref(),
+    ) {
+        (Some(utilization), _, _) => format!("{}%", utilization),
+        (_, Some(average), _) => format!("{} (avg)", average.0),
+        (_, _, Some(value)) => value.0.clone(),
+        _ => "<none>".to_string(),
+    }
+}
+
+/// The current value of a metric, in the terms of its target
+fn current_str(
+    target: &api_autoscaling::MetricTarget,
+    current: Option<&api_autoscaling::MetricValueStatus>,
+) -> String {
+    let current = match current {
+        Some(current) => current,
+        None => return "<unknown>".to_string(),
+    };
+    let value = match target.type_.as_str() {
+        "Utilization" => current
+            .average_utilization
+            .map(|utilization| format!("{}%", utilization)),
+        "AverageValue" => current
+            .average_value
+            .as_ref()
+            .map(|average| format!("{} (avg)", average.0)),
+        _ => current.value.as_ref().map(|value| value.0.clone()),
+    };
+    value.unwrap_or_else(|| "<unknown>".to_string())
+}
+
+/// The name of a metric, and its target
+fn metric_spec_parts(
+    spec: &api_autoscaling::MetricSpec,
+) -> Option<(String, &api_autoscaling::MetricTarget)> {
+    if let Some(resource) = spec.resource.as_ref() {
+        Some((resource.name.clone(), &resource.target))
+    } else if let Some(resource) = spec.container_resource.as_ref() {
+        Some((
+            format!("{} ({})", resource.name, resource.container),
+            &resource.target,
+        ))
+    } else if let Some(pods) = spec.pods.as_ref() {
+        Some((format!("pods/{}", pods.metric.name), &pods.target))
+    } else if let Some(object) = spec.object.as_ref() {
+        Some((
+            format!(
+                "{} on {}/{}",
+                object.metric.name, object.described_object.kind, object.described_object.name
+            ),
+            &object.target,
+        ))
+    } else {
+        spec.external.as_ref().map(|external| {
+            (
+                format!("external/{}", external.metric.name),
+                &external.target,
+            )
+        })
+    }
+}
+
+/// The name of a metric in the status, to match it up with its spec, and its current value
+fn metric_status_parts(
+    status: &api_autoscaling::MetricStatus,
+) -> Option<(String, &api_autoscaling::MetricValueStatus)> {
+    if let Some(resource) = status.resource.as_ref() {
+        Some((resource.name.clone(), &resource.current))
+    } else if let Some(resource) = status.container_resource.as_ref() {
+        Some((
+            format!("{} ({})", resource.name, resource.container),
+            &resource.current,
+        ))
+    } else if let Some(pods) = status.pods.as_ref() {
+        Some((format!("pods/{}", pods.metric.name), &pods.current))
+    } else if let Some(object) = status.object.as_ref() {
+        Some((
+            format!(
+                "{} on {}/{}",
+                object.metric.name, object.described_object.kind, object.described_object.name
+            ),
+            &object.current,
+        ))
+    } else {
+        status.external.as_ref().map(|external| {
+            (
+                format!("external/{}", external.metric.name),
+                &external.current,
+            )
+        })
+    }
+}
+
+/// Each metric the hpa scales on, with its current value (if the hpa has read it) and target
+fn metric_values(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> Vec<MetricValue> {
+    let statuses: Vec<(String, &api_autoscaling::MetricValueStatus)> = hpa
+        .status
+        .as_ref()
+        .and_then(|status| status.current_metrics.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(metric_status_parts)
+        .collect();
+    hpa.spec
+        .as_ref()
+        .and_then(|spec| spec.metrics.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(metric_spec_parts)
+        .map(|(name, target)| {
+            let current = statuses
+                .iter()
+                .find(|(status_name, _)| *status_name == name)
+                .map(|(_, current)| *current);
+            MetricValue {
+                current: current_str(target, current),
+                target: target_str(target),
+                name,
+            }
+        })
+        .collect()
+}
+
+/// The hpa's replicas: what there are, what it wants, and the range it can scale in
+fn replicas_str(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> String {
+    let status = hpa.status.as_ref();
+    let spec = hpa.spec.as_ref();
+    format!(
+        "{} current, {} desired (min {}, max {})",
+        status
+            .and_then(|status| status.current_replicas)
+            .unwrap_or(0),
+        status.map(|status| status.desired_replicas).unwrap_or(0),
+        spec.and_then(|spec| spec.min_replicas).unwrap_or(1),
+        spec.map(|spec| spec.max_replicas).unwrap_or(0),
+    )
+}
+
+/// The hpa's conditions, one per line, like AbleToScale: True (ReadyForNewScale)
+fn conditions_str(hpa: &api_autoscaling::HorizontalPodAutoscaler) -> String {
+    let lines: Vec<String> = hpa
+        .status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .into_iter()
+        .flatten()
+        .map(|condition| {
+            let mut line = format!("{}: {}", condition.type_, condition.status);
+            if let Some(reason) = condition.reason.as_ref() {
+                line.push_str(&format!(" ({})", reason));
+            }
+            line
+        })
+        .collect();
+    if lines.is_empty() {
+        "<none>".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+/// The most recent events about the hpa, oldest first, as (age, reason, message)
+fn recent_events(events: &mut [api::Event], count: usize) -> Vec<(String, String, String)> {
+    events.sort_by_key(get_event_ts);
+    let skip = events.len().saturating_sub(count);
+    events
+        .iter()
+        .skip(skip)
+        .map(|event| {
+            (
+                get_event_ts(event)
+                    .map(|ts| format_duration(time_since(ts)))
+                    .unwrap_or_else(|| "<unknown>".to_string()),
+                event.reason.clone().unwrap_or_default(),
+                event.message.clone().unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+/// How many of the hpa's events to show
+const EVENT_COUNT: usize = 10;
+
+fn print_hpa_status(env: &Env, obj: &KObj, writer: &mut ClickWriter) -> Result<(), ClickError> {
+    let hpa: api_autoscaling::HorizontalPodAutoscaler = obj.read_kind(env, "scaling")?;
+    let namespace = obj.namespace.as_deref().unwrap_or_default();
+
+    let mut table = describe_table();
+    table.add_row(vec![
+        "HPA:".to_string(),
+        format!("{}/{}", namespace, obj.name()),
+    ]);
+    table.add_row(vec![
+        "Target:".to_string(),
+        reference_str(&hpa).unwrap_or_else(|| "<none>".to_string()),
+    ]);
+    table.add_row(vec!["Replicas:".to_string(), replicas_str(&hpa)]);
+    table.add_row(vec![
+        "Last Scaled:".to_string(),
+        hpa.status
+            .as_ref()
+            .and_then(|status| status.last_scale_time.as_ref())
+            .map(|time| format!("{} ago", format_duration(time_since(time.0))))
+            .unwrap_or_else(|| "<never>".to_string()),
+    ]);
+    table.add_row(vec!["Conditions:".to_string(), conditions_str(&hpa)]);
+    clickwriteln!(writer, "{table}");
+
+    let metrics = metric_values(&hpa);
+    clickwriteln!(writer, "");
+    if metrics.is_empty() {
+        clickwriteln!(writer, "No metrics");
+    } else {
+        let rows = metrics
+            .iter()
+            .map(|metric| {
+                vec![
+                    metric.name.as_str().into(),
+                    metric.current.as_str().into(),
+                    metric.target.as_str().into(),
+                ]
+            })
+            .collect();
+        crate::table::print_table(vec!["Metric", "Current", "Target"], rows, env, writer);
+    }
+
+    let selector = format!(
+        "involvedObject.kind=HorizontalPodAutoscaler,involvedObject.name={}",
+        obj.name()
+    );
+    let (request, _) = api::Event::list_namespaced_event(
+        namespace,
+        ListOptional {
+            field_selector: Some(&selector),
+            ..Default::default()
+        },
+    )?;
+    clickwriteln!(writer, "");
+    // not being able to read events shouldn't stop the rest being shown
+    match env.run_on_context(|c| c.execute_list::<api::Event>(request)) {
+        Ok(mut events) => {
+            let events = recent_events(&mut events.items, EVENT_COUNT);
+            if events.is_empty() {
+                clickwriteln!(writer, "No recent events");
+            } else {
+                let rows = events
+                    .into_iter()
+                    .map(|(age, reason, message)| vec![age.into(), reason.into(), message.into()])
+                    .collect();
+                crate::table::print_table(vec!["Age", "Reason", "Message"], rows, env, writer);
+            }
+        }
+        Err(_) => clickwriteln!(writer, "<could not read events>"),
+    }
+    Ok(())
+}
+
+command!(
+    Scaling,
+    "scaling",
+    "Show the live status of the active horizontal pod autoscaler(s): metrics against their \
+     targets, current and desired replicas, and recent scaling events",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("interval")
+                .short('i')
+                .long("interval")
+                .value_name("SECS")
+                .help("How often to refresh, in seconds (default 2)")
+                .validator(|s| match s.parse::<u64>() {
+                    Ok(secs) if secs > 0 => Ok(()),
+                    _ => Err("must be a number of seconds greater than 0".to_string()),
+                })
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("once")
+                .long("once")
+                .help("Show the status once, instead of refreshing it")
+                .takes_value(false),
+        )
+        .after_help(
+            "The status is refreshed until ctrl-c is pressed, to watch an autoscaler react to \
+load. The hpa controller reads metrics every 15 seconds by default, so values change at about \
+that rate. Current values are shown in the same terms as their targets (utilization, an average \
+value, or a value), and as <unknown> when the hpa couldn't read them, which its conditions usually \
+explain. The last 10 events about the hpa show when and why it scaled.
+
+Examples:
+  # watch the selected hpa
+  scaling
+
+  # refresh every 10 seconds
+  scaling -i 10",
+        )
+    },
+    vec!["scaling"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        use crossterm::{cursor::MoveTo, execute, terminal::Clear, terminal::ClearType};
+
+        let interval = matches
+            .value_of("interval")
+            .map(|secs| secs.parse().unwrap()) // validated
+            .unwrap_or(DEFAULT_WATCH_SECS);
+        if matches.is_present("once") || !writer.is_tty() {
+            return env.apply_to_selection(
+                writer,
+                Some(&env.click_config.range_separator),
+                |obj, writer| print_hpa_status(env, obj, writer),
+            );
+        }
+
+        env.ctrlcbool.store(false, Ordering::SeqCst);
+        let refresh = |writer: &mut ClickWriter| -> Result<(), ClickError> {
+            execute!(writer, Clear(ClearType::All), MoveTo(0, 0))?;
+            clickwriteln!(
+                writer,
+                "Every {}s, ctrl-c to stop. Last updated: {}\n",
+                interval,
+                format_timestamp(Utc::now(), "%H:%M:%S", env)
+            );
+            // an error reading one hpa, or a blip talking to the api server, shouldn't stop the
+            // refreshing, so show it in place of the status and try again next time
+            env.apply_to_selection(
+                writer,
+                Some(&env.click_config.range_separator),
+                |obj, writer| {
+                    if let Err(e) = print_hpa_status(env, obj, writer) {
+                        clickwriteln!(writer, "{}", env.styles.danger(&e.to_string()));
+                    }
+                    Ok(())
+                },
+            )?;
+            writer.flush()?;
+            env.show_warnings();
+            Ok(())
+        };
+        let result = loop {
+            if let Err(e) = refresh(writer) {
+                break Err(e);
+            }
+            // sleep in small steps so ctrl-c is noticed quickly
+            for _ in 0..(interval * 10) {
+                if env.ctrlcbool.load(Ordering::SeqCst) {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+            if env.ctrlcbool.load(Ordering::SeqCst) {
+                break Ok(());
+            }
+        };
+        env.ctrlcbool.store(false, Ordering::SeqCst);
+        result
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_metric_values() {
+        let hpa: api_autoscaling::HorizontalPodAutoscaler = serde_json::from_value(json!({
+            "metadata": { "name": "web" },
+            "spec": {
+                "scaleTargetRef": { "kind": "Deployment", "name": "web" },
+                "minReplicas": 2,
+                "maxReplicas": 10,
+                "metrics": [
+                    {
+                        "type": "Resource",
+                        "resource": {
+                            "name": "cpu",
+                            "target": { "type": "Utilization", "averageUtilization": 80 },
+                        },
+                    },
+                    {
+                        "type": "Pods",
+                        "pods": {
+                            "metric": { "name": "requests_per_second" },
+                            "target": { "type": "AverageValue", "averageValue": "100" },
+                        },
+                    },
+                    {
+                        "type": "External",
+                        "external": {
+                            "metric": { "name": "queue_length" },
+                            "target": { "type": "Value", "value": "30" },
+                        },
+                    },
+                ],
+            },
+            "status": {
+                "currentReplicas": 3,
+                "desiredReplicas": 5,
+                "currentMetrics": [
+                    {
+                        "type": "Resource",
+                        "resource": {
+                            "name": "cpu",
+                            "current": { "averageUtilization": 95, "averageValue": "950m" },
+                        },
+                    },
+                    {
+                        "type": "Pods",
+                        "pods": {
+                            "metric": { "name": "requests_per_second" },
+                            "current": { "averageValue": "120" },
+                        },
+                    },
+                ],
+                "conditions": [
+                    { "type": "AbleToScale", "status": "True", "reason": "ReadyForNewScale" },
+                    { "type": "ScalingLimited", "status": "False" },
+                ],
+            },
+        }))
+        .unwrap();
+        let metric = |name: &str, current: &str, target: &str| MetricValue {
+            name: name.to_string(),
+            current: current.to_string(),
+            target: target.to_string(),
+        };
+        assert_eq!(
+            metric_values(&hpa),
+            vec![
+                metric("cpu", "95%", "80%"),
+                metric("pods/requests_per_second", "120 (avg)", "100 (avg)"),
+                metric("external/queue_length", "<unknown>", "30"),
+            ]
+        );
+        assert_eq!(
+            targets_str(&hpa).unwrap(),
+            "95%/80%, 120 (avg)/100 (avg), <unknown>/30"
+        );
+        assert_eq!(reference_str(&hpa).unwrap(), "Deployment/web");
+        assert_eq!(replicas_str(&hpa), "3 current, 5 desired (min 2, max 10)");
+        assert_eq!(
+            conditions_str(&hpa),
+            "AbleToScale: True (ReadyForNewScale)\nScalingLimited: False"
+        );
+    }
+
+    #[test]
+    fn test_recent_events() {
+        let mut events: Vec<api::Event> = serde_json::from_value(json!([
+            {
+                "metadata": { "name": "b" },
+                "involvedObject": {},
+                "reason": "SuccessfulRescale",
+                "message": "New size: 5; reason: cpu resource utilization above target",
+                "lastTimestamp": "2023-01-01T00:10:00Z",
+            },
+            {
+                "metadata": { "name": "a" },
+                "involvedObject": {},
+                "reason": "SuccessfulRescale",
+                "message": "New size: 3",
+                "lastTimestamp": "2023-01-01T00:00:00Z",
+            },
+        ]))
+        .unwrap();
+        let recent = recent_events(&mut events, 1);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(
+            recent[0].2,
+            "New size: 5; reason: cpu resource utilization above target"
+        );
+        assert_eq!(recent_events(&mut events, 10)[0].2, "New size: 3");
+    }
+}
@@ -0,0 +1,417 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::{offset::Utc, DateTime, Duration, NaiveDateTime, TimeZone};
+use clap::{Arg, Command as ClapCommand};
+use k8s_openapi::{api::core::v1 as api, List, ListOptional};
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use rustls::{Certificate, ClientConfig, ClientConnection, ServerName};
+use rustyline::completion::Pair as RustlinePair;
+use yasna::{models::ObjectIdentifier, ASN1Error, ASN1ErrorKind, BERReader, Tag, TagClass};
+
+use crate::{
+    command::command_def::{exec_match, label_selector_arg, start_clap, Cmd},
+    command::format_duration,
+    completer,
+    env::Env,
+    error::ClickError,
+    output::ClickWriter,
+    table::{CellSpec, ColorType},
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Certificates expiring sooner than this are shown as a warning, and sooner than DANGER_DAYS (or
+/// already expired) as danger
+const WARN_DAYS: i64 = 30;
+const DANGER_DAYS: i64 = 7;
+
+/// The port kubelets serve on, if a node doesn't say
+const DEFAULT_KUBELET_PORT: i32 = 10250;
+
+/// What's shown of a certificate
+#[derive(Debug, PartialEq)]
+struct CertInfo {
+    subject: String,
+    issuer: String,
+    not_before: DateTime<Utc>,
+    not_after: DateTime<Utc>,
+}
+
+/// The short name of an attribute in a distinguished name, like CN, or its oid if it's not one of
+/// the usual ones
+fn attribute_name(oid: &ObjectIdentifier) -> String {
+    match oid.components().as_slice() {
+        [2, 5, 4, 3] => "CN".to_string(),
+        [2, 5, 4, 6] => "C".to_string(),
+        [2, 5, 4, 7] => "L".to_string(),
+        [2, 5, 4, 8] => "ST".to_string(),
+        [2, 5, 4, 10] => "O".to_string(),
+        [2, 5, 4, 11] => "OU".to_string(),
+        components => components
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join("."),
+    }
+}
+
+/// A distinguished name, like O=system:masters, CN=kube-apiserver
+fn read_name(reader: BERReader) -> Result<String, ASN1Error> {
+    let rdns = reader.collect_sequence_of(|reader| {
+        reader.collect_set_of(|reader| {
+            reader.read_sequence(|reader| {
+                let oid = reader.next().read_oid()?;
+                // the value is one of several string types, all of which are fine as utf8 here
+                let value = reader.next().read_tagged_der()?;
+                Ok(format!(
+                    "{}={}",
+                    attribute_name(&oid),
+                    String::from_utf8_lossy(value.value())
+                ))
+            })
+        })
+    })?;
+    Ok(rdns.into_iter().flatten().collect::<Vec<_>>().join(", "))
+}
+
+/// A UTCTime (with a two digit year) or GeneralizedTime, as used for the validity of certificates
+fn read_time(reader: BERReader) -> Result<DateTime<Utc>, ASN1Error> {
+    let der = reader.read_tagged_der()?;
+    let tag = der.tag();
+    let text = String::from_utf8_lossy(der.value());
+    let text = match (tag.tag_class, tag.tag_number) {
+        // years 50 to 99 are 1950 to 1999 in certificates
+        (TagClass::Universal, 23) if &text[..] < "50" => format!("20{}", text),
+        (TagClass::Universal, 23) => format!("19{}", text),
+        (TagClass::Universal, 24) => text.into_owned(),
+        _ => return Err(ASN1Error::new(ASN1ErrorKind::Invalid)),
+    };
+    NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%SZ")
+        .map(|time| Utc.from_utc_datetime(&time))
+        .map_err(|_| ASN1Error::new(ASN1ErrorKind::Invalid))
+}
+
+/// Read the names and validity out of a DER encoded X.509 certificate
+fn parse_cert(der: &[u8]) -> Result<CertInfo, ASN1Error> {
+    yasna::parse_der(der, |reader| {
+        reader.read_sequence(|reader| {
+            let info = reader.next().read_sequence(|reader| {
+                reader.read_optional(|reader| {
+                    reader.read_tagged(Tag::context(0), |reader| reader.read_i64())
+                })?;
+                reader.next().read_der()?; // serial number
+                reader.next().read_der()?; // signature algorithm
+                let issuer = read_name(reader.next())?;
+                let (not_before, not_after) = reader.next().read_sequence(|reader| {
+                    Ok((read_time(reader.next())?, read_time(reader.next())?))
+                })?;
+                let subject = read_name(reader.next())?;
+                reader.next().read_der()?; // public key
+
+                // unique ids and extensions
+                for _ in 0..3 {
+                    reader.read_optional(|reader| reader.read_der())?;
+                }
+                Ok(CertInfo {
+                    subject,
+                    issuer,
+                    not_before,
+                    not_after,
+                })
+            })?;
+            reader.next().read_der()?; // signature algorithm
+            reader.next().read_der()?; // signature
+            Ok(info)
+        })
+    })
+}
+
+/// Accepts any certificate, keeping the chain the server sent, so expired and self-signed ones
+/// can be looked at too. Nothing is sent over the connection once it's set up
+#[derive(Default)]
+struct ChainRecorder {
+    chain: Mutex<Vec<Certificate>>,
+}
+
+impl ServerCertVerifier for ChainRecorder {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let mut chain = self.chain.lock().unwrap();
+        chain.push(end_entity.clone());
+        chain.extend(intermediates.iter().cloned());
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+/// Connect to host:port and get the certificate chain it serves
+fn fetch_chain(host: &str, port: u16, timeout_secs: u32) -> Result<Vec<CertInfo>, ClickError> {
+    let err = |e: String| ClickError::CommandError(format!("{}:{}: {}", host, port, e));
+    let recorder = Arc::new(ChainRecorder::default());
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(recorder.clone())
+        .with_no_client_auth();
+    // ipv6 addresses in urls are in brackets, which aren't part of the address
+    let name = host.trim_start_matches('[').trim_end_matches(']');
+    let server_name = ServerName::try_from(name).map_err(|e| err(format!("{:?}", e)))?;
+    let mut conn =
+        ClientConnection::new(Arc::new(config), server_name).map_err(|e| err(e.to_string()))?;
+    let addr: SocketAddr = (name, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| err("could not resolve host".to_string()))?;
+    let timeout = std::time::Duration::from_secs(timeout_secs.into());
+    let mut sock = TcpStream::connect_timeout(&addr, timeout)?;
+    sock.set_read_timeout(Some(timeout))?;
+    sock.set_write_timeout(Some(timeout))?;
+    while conn.is_handshaking() {
+        conn.complete_io(&mut sock)
+            .map_err(|e| err(e.to_string()))?;
+    }
+    let chain = recorder.chain.lock().unwrap();
+    chain
+        .iter()
+        .map(|cert| parse_cert(&cert.0).map_err(|e| err(format!("bad certificate: {}", e))))
+        .collect()
+}
+
+fn expiry_cell<'a>(not_after: DateTime<Utc>, now: DateTime<Utc>) -> CellSpec<'a> {
+    let left = not_after - now;
+    let txt = if left < Duration::zero() {
+        format!("expired {} ago", format_duration(-left))
+    } else {
+        format!("in {}", format_duration(left))
+    };
+    if left < Duration::days(DANGER_DAYS) {
+        CellSpec::with_colors(txt.into(), Some(ColorType::Danger.into()), None)
+    } else if left < Duration::days(WARN_DAYS) {
+        CellSpec::with_colors(txt.into(), Some(ColorType::Warn.into()), None)
+    } else {
+        txt.into()
+    }
+}
+
+/// A row for each certificate in the chain an endpoint serves, or one with why it couldn't be
+/// fetched
+fn chain_rows<'a>(
+    endpoint: String,
+    chain: Result<Vec<CertInfo>, ClickError>,
+    now: DateTime<Utc>,
+) -> Vec<Vec<CellSpec<'a>>> {
+    match chain {
+        Ok(chain) => chain
+            .into_iter()
+            .enumerate()
+            .map(|(depth, cert)| {
+                vec![
+                    if depth == 0 {
+                        endpoint.clone().into()
+                    } else {
+                        "".into()
+                    },
+                    CellSpec::new_int(depth as i64),
+                    cert.subject.into(),
+                    cert.issuer.into(),
+                    cert.not_before.format("%Y-%m-%d").to_string().into(),
+                    cert.not_after.format("%Y-%m-%d %H:%M").to_string().into(),
+                    expiry_cell(cert.not_after, now),
+                ]
+            })
+            .collect(),
+        Err(e) => vec![vec![
+            endpoint.into(),
+            "".into(),
+            CellSpec::with_colors(e.to_string().into(), Some(ColorType::Danger.into()), None),
+            "".into(),
+            "".into(),
+            "".into(),
+            "".into(),
+        ]],
+    }
+}
+
+/// The address and port to reach a node's kubelet on
+fn kubelet_address(node: &api::Node) -> Option<(String, u16)> {
+    let status = node.status.as_ref()?;
+    let address = status
+        .addresses
+        .iter()
+        .flatten()
+        .find(|address| address.type_ == "InternalIP")
+        .or_else(|| status.addresses.iter().flatten().next())?;
+    let port = status
+        .daemon_endpoints
+        .as_ref()
+        .and_then(|endpoints| endpoints.kubelet_endpoint.as_ref())
+        .map(|endpoint| endpoint.port)
+        .filter(|port| *port > 0)
+        .unwrap_or(DEFAULT_KUBELET_PORT);
+    Some((address.address.clone(), port as u16))
+}
+
+command!(
+    Certs,
+    "certs",
+    "Show the certificate chains served by the api server, and optionally the kubelets, and when \
+     they expire",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("nodes")
+                .short('n')
+                .long("nodes")
+                .help("Also check the certificate of the kubelet on each node")
+                .takes_value(false),
+        )
+        .arg(label_selector_arg().requires("nodes"))
+        .after_help(
+            "Each endpoint is connected to directly, and the certificates it sends are shown, the \
+server's own first (at depth 0), then any intermediates it sends with it. Certificates aren't \
+verified, so expired and self-signed ones can be checked too, and nothing is sent once the \
+connection is set up. Expiry is shown as a warning within 30 days and as danger within 7.
+
+Kubelets are reached at their node's internal IP and kubelet port, which may not be possible from \
+outside the cluster.
+
+Examples:
+  # when do the api server's certificates expire
+  certs
+
+  # check the kubelets on the worker nodes too
+  certs -n -l node-role.kubernetes.io/worker",
+        )
+    },
+    vec!["certs"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let context = env.context.as_ref().ok_or_else(|| {
+            ClickError::CommandError("Need an active context to check certificates".to_string())
+        })?;
+        let timeout = env.click_config.connect_timeout_secs;
+        let endpoint = context.endpoint();
+        let host = endpoint
+            .host_str()
+            .ok_or_else(|| ClickError::CommandError(format!("No host in {}", endpoint)))?;
+        let port = endpoint.port_or_known_default().unwrap_or(443);
+        let now = Utc::now();
+        let mut rows = chain_rows(
+            format!("api server {}:{}", host, port),
+            fetch_chain(host, port, timeout),
+            now,
+        );
+        if matches.is_present("nodes") {
+            let (request, _) = api::Node::list_node(ListOptional {
+                label_selector: matches.value_of("label"),
+                ..Default::default()
+            })?;
+            let nodes: List<api::Node> = env.run_on_context(|c| c.execute_list(request))?;
+            for node in nodes.items.iter() {
+                let name = node.metadata.name.as_deref().unwrap_or("<No Name>");
+                let chain = match kubelet_address(node) {
+                    Some((address, port)) => fetch_chain(&address, port, timeout),
+                    None => Err(ClickError::CommandError(
+                        "node has no addresses".to_string(),
+                    )),
+                };
+                rows.extend(chain_rows(format!("kubelet {}", name), chain, now));
+            }
+        }
+        crate::table::print_table(
+            vec![
+                "Endpoint",
+                "Depth",
+                "Subject",
+                "Issuer",
+                "Not Before",
+                "Not After",
+                "Expires",
+            ],
+            rows,
+            env,
+            writer,
+        );
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CERT: &str = "\
+MIIBeTCCAR6gAwIBAgIBAjAKBggqhkjOPQQDAjAVMRMwEQYDVQQDDAprdWJlcm5l\
+dGVzMB4XDTI0MDEwMTAwMDAwMFoXDTI1MDEwMTAwMDAwMFowMjEXMBUGA1UECgwO\
+c3lzdGVtOm1hc3RlcnMxFzAVBgNVBAMMDmt1YmUtYXBpc2VydmVyMFkwEwYHKoZI\
+zj0CAQYIKoZIzj0DAQcDQgAEfHGvZSLozOGB/vzOen1ED0fvWgE6W5V4Z83h6KEc\
+1rDkOpOxC4axkbkZ9ensbEQVSHZQfXmas5KY67x5NbeduqNCMEAwHQYDVR0OBBYE\
+FGP0H1mTCeGoQQyBKSWq5Q2NPtlnMB8GA1UdIwQYMBaAFNjRrBay8Af6cVFM/88Z\
+ucHMako4MAoGCCqGSM49BAMCA0kAMEYCIQD8/d/n7Cj/NclnkTQFV7EgnIOX+TD+\
+sD541JrOH0loewIhANk2yM82cQ8zFlluGXVSQwvojskOgCgL+BB5Rmzle7UQ\
+";
+
+    #[test]
+    fn test_parse_cert() {
+        let der = base64::decode(CERT).unwrap();
+        assert_eq!(
+            parse_cert(&der).unwrap(),
+            CertInfo {
+                subject: "O=system:masters, CN=kube-apiserver".to_string(),
+                issuer: "CN=kubernetes".to_string(),
+                not_before: Utc.ymd(2024, 1, 1).and_hms(0, 0, 0),
+                not_after: Utc.ymd(2025, 1, 1).and_hms(0, 0, 0),
+            }
+        );
+        assert!(parse_cert(b"not a cert").is_err());
+    }
+
+    #[test]
+    fn test_kubelet_address() {
+        let node: api::Node = serde_json::from_value(json!({
+            "metadata": { "name": "node-1" },
+            "status": {
+                "addresses": [
+                    { "type": "Hostname", "address": "node-1" },
+                    { "type": "InternalIP", "address": "10.0.0.5" },
+                ],
+                "daemonEndpoints": { "kubeletEndpoint": { "Port": 10255 } },
+            },
+        }))
+        .unwrap();
+        assert_eq!(
+            kubelet_address(&node),
+            Some(("10.0.0.5".to_string(), 10255))
+        );
+        let node: api::Node = serde_json::from_value(json!({
+            "metadata": { "name": "node-2" },
+            "status": { "addresses": [{ "type": "Hostname", "address": "node-2" }] },
+        }))
+        .unwrap();
+        assert_eq!(kubelet_address(&node), Some(("node-2".to_string(), 10250)));
+    }
+}
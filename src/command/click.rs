@@ -15,19 +15,23 @@
 use chrono::offset::Utc;
 use clap::{Arg, Command as ClapCommand};
 use comfy_table::Table;
+use k8s_openapi::apimachinery::pkg::version::Info;
+use regex::Regex;
 use rustyline::completion::Pair as RustlinePair;
 
 use crate::{
     command::command_def::{exec_match, identity, start_clap, Cmd},
     completer, config,
     env::Env,
+    error::ClickError,
     output::ClickWriter,
+    styles::strip_ansi,
     table::CellSpec,
 };
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::{stderr, Write};
+use std::io::{stderr, IsTerminal, Write};
 
 command!(
     Clear,
@@ -43,6 +47,39 @@ command!(
     }
 );
 
+command!(
+    Back,
+    "back",
+    "Go back to the context, namespace and selection you had before the last command that \
+     changed them",
+    identity,
+    vec!["back"],
+    noop_complete!(),
+    no_named_complete!(),
+    |_, env, writer| {
+        if !env.back() {
+            clickwriteln!(writer, "Nowhere to go back to");
+        }
+        Ok(())
+    }
+);
+
+command!(
+    Forward,
+    "forward",
+    "Go forward to where you were before the last back",
+    identity,
+    vec!["forward"],
+    noop_complete!(),
+    no_named_complete!(),
+    |_, env, writer| {
+        if !env.forward() {
+            clickwriteln!(writer, "Nowhere to go forward to");
+        }
+        Ok(())
+    }
+);
+
 fn print_contexts(env: &Env, writer: &mut ClickWriter) {
     let mut contexts: Vec<&String> = env.config.contexts.keys().collect();
     contexts.sort();
@@ -126,6 +163,152 @@ command!(
     }
 );
 
+command!(
+    ConfigCmd,
+    "config",
+    "Show or edit click's config file. Without an action, prints the file",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("action")
+                .help(
+                    "show: print the config file (the default), path: print where it is, edit: \
+                     open it in your editor (see 'set editor') and load it when the editor exits",
+                )
+                .index(1)
+                .possible_values(["show", "path", "edit"]),
+        )
+        .after_help(
+            "The config file holds click's options, like editor, pager, colors, timeouts, the \
+confirm policy, default_output and aliases. Options changed with 'set' are saved to it when click \
+exits.",
+        )
+    },
+    vec!["config"],
+    vec![&completer::configaction_values_completer],
+    no_named_complete!(),
+    |matches, env, writer| {
+        // make sure the file has everything that's been set so far
+        env.save_click_config();
+        let path = env.click_config_path().to_path_buf();
+        match matches.value_of("action").unwrap_or("show") {
+            "path" => {
+                clickwriteln!(writer, "{}", path.display());
+            }
+            "edit" => {
+                let editor = env.editor()?;
+                let expr = if editor.contains(' ') {
+                    let mut eargs: Vec<&str> = editor.split_whitespace().collect();
+                    eargs.push(path.to_str().unwrap());
+                    duct::cmd(eargs[0], &eargs[1..])
+                } else {
+                    cmd!(editor, &path)
+                };
+                expr.run()?;
+                env.reload_click_config()?;
+                clickwriteln!(writer, "Loaded {}", path.display());
+            }
+            _ => {
+                let contents = std::fs::read_to_string(&path)?;
+                clickwriteln!(writer, "# {}", path.display());
+                clickwrite!(writer, "{}", contents);
+            }
+        }
+        Ok(())
+    }
+);
+
+fn print_bookmarks(env: &Env, writer: &mut ClickWriter) {
+    if env.click_config.bookmarks.is_empty() {
+        clickwriteln!(writer, "No bookmarks. Save one with 'bookmark save <NAME>'");
+        return;
+    }
+    let rows = env
+        .click_config
+        .bookmarks
+        .iter()
+        .map(|(name, bookmark)| {
+            let object = match bookmark.object {
+                Some(ref obj) => format!("{}/{}", obj.kind, obj.name),
+                None => "none".to_string(),
+            };
+            vec![
+                name.clone().into(),
+                bookmark.context.as_deref().unwrap_or("none").into(),
+                bookmark.namespace.as_deref().unwrap_or("none").into(),
+                object.into(),
+            ]
+        })
+        .collect();
+    crate::table::print_table(
+        vec!["Name", "Context", "Namespace", "Object"],
+        rows,
+        env,
+        writer,
+    );
+}
+
+command!(
+    BookmarkCmd,
+    "bookmark",
+    "Save the current context, namespace and selected object under a name, and go back to it \
+     later. With no action, lists bookmarks",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("action")
+                .help(
+                    "list: list bookmarks (the default), save: save where you are as NAME, go: go \
+                     to the bookmark NAME, delete: delete the bookmark NAME",
+                )
+                .index(1)
+                .possible_values(["list", "save", "go", "delete"]),
+        )
+        .arg(
+            Arg::new("name")
+                .help("The name of the bookmark")
+                .value_name("NAME")
+                .index(2)
+                .required_if_eq_any(&[("action", "save"), ("action", "go"), ("action", "delete")]),
+        )
+        .after_help(
+            "Bookmarks are saved in the click config, so they're kept between sessions.
+
+Examples:
+  # bookmark the selected pod
+  bookmark save api
+
+  # go back to it later, switching context and namespace if needed
+  bookmark go api",
+        )
+    },
+    vec!["bookmark", "bm"],
+    vec![
+        &completer::bookmarkaction_values_completer,
+        &completer::bookmark_completer
+    ],
+    no_named_complete!(),
+    |matches, env, writer| {
+        let name = matches.value_of("name").unwrap_or_default();
+        match matches.value_of("action").unwrap_or("list") {
+            "save" => {
+                env.save_bookmark(name)?;
+                clickwriteln!(writer, "Saved bookmark {}", name);
+            }
+            "go" => env.go_to_bookmark(name)?,
+            "delete" => {
+                if env.click_config.bookmarks.remove(name).is_none() {
+                    return Err(ClickError::CommandError(format!(
+                        "No bookmark named {}",
+                        name
+                    )));
+                }
+                env.save_click_config();
+            }
+            _ => print_bookmarks(env, writer),
+        }
+        Ok(())
+    }
+);
+
 command!(
     Quit,
     "quit",
@@ -165,14 +348,193 @@ command!(
     }
 );
 
+command!(
+    Highlight,
+    "highlight",
+    "Highlight matches of a regex in logs and in the cells of tables, without filtering anything \
+     out. With no regex, print the current one",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("regex")
+                .help("The regex to highlight (example: 'req-[0-9a-f]+')")
+                .required(false)
+                .index(1),
+        )
+        .arg(
+            Arg::new("clear")
+                .short('c')
+                .long("clear")
+                .help("Stop highlighting")
+                .conflicts_with("regex")
+                .takes_value(false),
+        )
+    },
+    vec!["highlight"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        if matches.is_present("clear") {
+            env.highlight = None;
+            clickwriteln!(writer, "Cleared highlight");
+        } else if let Some(pattern) = matches.value_of("regex") {
+            match Regex::new(pattern) {
+                Ok(regex) => {
+                    env.highlight = Some(regex);
+                    clickwriteln!(writer, "Highlighting matches of '{}'", pattern);
+                }
+                Err(e) => {
+                    return Err(ClickError::CommandError(format!("Invalid regex: {}", e)));
+                }
+            }
+        } else {
+            match env.highlight.as_ref() {
+                Some(regex) => clickwriteln!(writer, "Highlighting matches of '{}'", regex),
+                None => clickwriteln!(writer, "Not highlighting anything"),
+            }
+        }
+        Ok(())
+    }
+);
+
+command!(
+    Grep,
+    "grep",
+    "Print the lines of the last command's output that match a regex, without running the command \
+     again",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("regex")
+                .help("The regex to search for")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("ignore-case")
+                .short('i')
+                .long("ignore-case")
+                .help("Ignore case when matching")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("invert")
+                .short('v')
+                .long("invert-match")
+                .help("Print the lines that don't match instead")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("header")
+                .short('H')
+                .long("header")
+                .help("Always print the first line, like the header of a table")
+                .takes_value(false),
+        )
+        .after_help(
+            "The output of grep isn't saved, so running grep again searches the same output. \
+Colors are removed before matching, and matches are highlighted.
+
+Examples:
+  # list the pods, then only show the ones that are crashing, keeping the table header
+  pods
+  grep -H CrashLoop
+
+  # show the lines of the last logs that don't mention health checks
+  grep -v -i healthz",
+        )
+    },
+    vec!["grep"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let pattern = matches.value_of("regex").unwrap(); // required
+        let pattern = if matches.is_present("ignore-case") {
+            format!("(?i){}", pattern)
+        } else {
+            pattern.to_string()
+        };
+        let regex = Regex::new(&pattern)
+            .map_err(|e| ClickError::CommandError(format!("Invalid regex: {}", e)))?;
+        let invert = matches.is_present("invert");
+        let highlight = env.colors() && writer.is_tty() && !invert;
+        for (i, line) in env.last_output().lines().enumerate() {
+            let line = strip_ansi(line);
+            if (i == 0 && matches.is_present("header")) || regex.is_match(&line) != invert {
+                if highlight {
+                    clickwriteln!(writer, "{}", env.styles.highlight_matches(&line, &regex));
+                } else {
+                    clickwriteln!(writer, "{}", line);
+                }
+            }
+        }
+        Ok(())
+    }
+);
+
+command!(
+    Pick,
+    "pick",
+    "Pick an object from the last list with a fuzzy finder: type a few characters to narrow the \
+     list, move with the arrow keys, and hit Enter to select the highlighted object",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("query")
+                .help("Start the finder with this already typed")
+                .required(false)
+                .index(1),
+        )
+        .after_help(
+            "Set pick_threshold to open the finder automatically after a list command that \
+returns more objects than that, for example:
+  set pick_threshold 50",
+        )
+    },
+    vec!["pick"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        if env.last_objs().is_empty() {
+            return Err(ClickError::CommandError(
+                "No objects to pick from, run a list command like 'pods' first".to_string(),
+            ));
+        }
+        if !std::io::stdout().is_terminal() {
+            return Err(ClickError::CommandError(
+                "pick needs a terminal to run in".to_string(),
+            ));
+        }
+        if !env.pick_object(matches.value_of("query").unwrap_or(""))? {
+            clickwriteln!(writer, "Nothing picked");
+        }
+        Ok(())
+    }
+);
+
 pub const SET_OPTS: &[&str] = &[
     "completion_type",
     "edit_mode",
     "editor",
+    "pager",
     "kubectl_binary",
     "terminal",
     "range_separator",
     "describe_include_events",
+    "colors",
+    "absolute_times",
+    "use_pager",
+    "default_output",
+    "connect_timeout_secs",
+    "read_timeout_secs",
+    "status_glyphs",
+    "timezone",
+    "history_size",
+    "prompt",
+    "theme",
+    "theme_color",
+    "pick_threshold",
+    "confirm",
+    "dangerous_contexts",
+    "notify_after_secs",
+    "notify",
 ];
 
 command!(
@@ -202,7 +564,45 @@ Example:
   set -- range_separator \"---- {name} [{namespace}] ----\"
 
   # set edit_mode
-  set edit_mode emacs",
+  set edit_mode emacs
+
+  # use a shorter prompt (needs the '--' since the value contains a -)
+  set -- prompt \"{bold}{context_short:.20}{reset} {green}{namespace}{reset} {selection} > \"
+
+The prompt is a template with these values: {context}, {context_short} (the part of the context
+after the last / or :), {namespace}, {kind} and {name} of the selected object, and {selection}
+(the selected object's name, or a description of the selected range). :.N after a value, like
+{context:.20}, truncates it to N characters. These color tokens change the color of what follows:
+{black}, {red}, {green}, {yellow}, {blue}, {magenta}, {cyan}, {white}, {bold}, and {reset}.
+
+The theme can be 'dark' (the default) or 'light', which uses darker colors that are easier to read
+on a light background. 'set theme_color element=color' overrides the color of one element, where
+element is one of: config_val, context_table, danger, info, prompt_context, prompt_namespace,
+prompt_none, prompt_range, success, table_header, or warning, and color is a color name (like red
+or dark_blue), a number from 0 to 255, an rgb color like #ff8000, or 'none'. For example:
+  set theme_color prompt_context=dark_magenta
+
+Overrides are cleared with 'unset theme_colors'.
+
+confirm controls when destructive commands like delete ask for confirmation: 'always' (the
+default), 'dangerous' to only ask in contexts matching one of dangerous_contexts, or 'never'.
+dangerous_contexts is a comma separated list of patterns, where * matches anything and ? matches
+one character. For example:
+  set dangerous_contexts prod*,*-live
+  set confirm dangerous
+
+default_output is the output format list commands use when they're run without -o, like wide or
+yaml. The timeouts are in seconds, and apply from the next request.
+
+notify_after_secs makes click let you know when a command that took at least that many seconds
+finishes, so you can look away while it runs. Time spent answering prompts, like confirming a
+delete, doesn't count. notify is how: 'bell' (the default) rings the
+terminal bell, and 'desktop' sends a desktop notification (with notify-send, or osascript on
+macOS). For example:
+  set notify_after_secs 30
+  set notify desktop
+
+Options are saved to click's config file (see 'config') when click exits.",
         )
     },
     vec!["set"],
@@ -238,6 +638,9 @@ Example:
             "editor" => {
                 env.set_editor(Some(value));
             }
+            "pager" => {
+                env.click_config.pager = Some(value.to_string());
+            }
             "terminal" => {
                 env.set_terminal(Some(value));
             }
@@ -257,6 +660,167 @@ Example:
                     failed = true;
                 }
             },
+            "colors" => match value.parse() {
+                Ok(b) => {
+                    env.click_config.colors = b;
+                    env.no_color = false;
+                }
+                Err(_) => {
+                    clickwriteln!(writer, "colors must be set to 'true' or 'false'");
+                    failed = true;
+                }
+            },
+            "absolute_times" => match value.parse() {
+                Ok(b) => env.click_config.absolute_times = b,
+                Err(_) => {
+                    clickwriteln!(writer, "absolute_times must be set to 'true' or 'false'");
+                    failed = true;
+                }
+            },
+            "use_pager" => match value.parse() {
+                Ok(b) => env.click_config.use_pager = b,
+                Err(_) => {
+                    clickwriteln!(writer, "use_pager must be set to 'true' or 'false'");
+                    failed = true;
+                }
+            },
+            "default_output" => match value.parse::<crate::output::OutputFormat>() {
+                Ok(_) => env.click_config.default_output = Some(value.to_string()),
+                Err(e) => {
+                    clickwriteln!(writer, "Invalid default_output: {}", e);
+                    failed = true;
+                }
+            },
+            "connect_timeout_secs" | "read_timeout_secs" => match value.parse() {
+                Ok(secs) => {
+                    if option == "connect_timeout_secs" {
+                        env.click_config.connect_timeout_secs = secs;
+                    } else {
+                        env.click_config.read_timeout_secs = secs;
+                    }
+                    env.reload_context();
+                }
+                Err(_) => {
+                    clickwriteln!(writer, "{} must be a number of seconds", option);
+                    failed = true;
+                }
+            },
+            "timezone" => match crate::table::parse_timezone(value) {
+                Ok(_) => env.click_config.timezone = Some(value.to_string()),
+                Err(e) => {
+                    clickwriteln!(writer, "{}", e);
+                    failed = true;
+                }
+            },
+            "prompt" => {
+                if let Err(e) = env.set_prompt_template(Some(value)) {
+                    clickwriteln!(writer, "{}", e);
+                    failed = true;
+                }
+            }
+            "history_size" => match value.parse() {
+                Ok(size) => {
+                    env.click_config.history_size = size;
+                    env.need_new_editor = true;
+                }
+                Err(_) => {
+                    clickwriteln!(writer, "history_size must be a number of lines");
+                    failed = true;
+                }
+            },
+            "theme" => match value {
+                "dark" | "light" => {
+                    env.click_config.theme = if value == "dark" {
+                        config::Theme::Dark
+                    } else {
+                        config::Theme::Light
+                    };
+                    env.apply_theme().unwrap_or(()); // overrides were checked when set
+                }
+                _ => {
+                    clickwriteln!(writer, "Invalid theme.  Possible values are: [dark, light]");
+                    failed = true;
+                }
+            },
+            "theme_color" => match value.split_once('=') {
+                Some((element, color)) => {
+                    let mut colors = env.click_config.theme_colors.clone();
+                    colors.insert(element.trim().to_string(), color.trim().to_string());
+                    match env.styles.set_theme(env.click_config.theme, &colors) {
+                        Ok(()) => {
+                            env.click_config.theme_colors = colors;
+                            env.apply_theme().unwrap_or(());
+                        }
+                        Err(e) => {
+                            // put back the old colors
+                            env.apply_theme().unwrap_or(());
+                            clickwriteln!(writer, "{}", e);
+                            failed = true;
+                        }
+                    }
+                }
+                None => {
+                    clickwriteln!(writer, "theme_color must be set to element=color");
+                    failed = true;
+                }
+            },
+            "pick_threshold" => match value.parse() {
+                Ok(threshold) => env.click_config.pick_threshold = Some(threshold),
+                Err(_) => {
+                    clickwriteln!(writer, "pick_threshold must be a number of objects");
+                    failed = true;
+                }
+            },
+            "confirm" => match value {
+                "always" => env.click_config.confirm = config::ConfirmPolicy::Always,
+                "dangerous" => env.click_config.confirm = config::ConfirmPolicy::Dangerous,
+                "never" => env.click_config.confirm = config::ConfirmPolicy::Never,
+                _ => {
+                    clickwriteln!(
+                        writer,
+                        "Invalid confirm.  Possible values are: [always, dangerous, never]"
+                    );
+                    failed = true;
+                }
+            },
+            "dangerous_contexts" => {
+                env.click_config.dangerous_contexts = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            "notify_after_secs" => match value.parse() {
+                Ok(secs) => env.click_config.notify_after_secs = Some(secs),
+                Err(_) => {
+                    clickwriteln!(writer, "notify_after_secs must be a number of seconds");
+                    failed = true;
+                }
+            },
+            "notify" => match value {
+                "bell" => env.click_config.notify = config::NotifyMethod::Bell,
+                "desktop" => env.click_config.notify = config::NotifyMethod::Desktop,
+                _ => {
+                    clickwriteln!(
+                        writer,
+                        "Invalid notify.  Possible values are: [bell, desktop]"
+                    );
+                    failed = true;
+                }
+            },
+            "status_glyphs" => match value {
+                "none" => env.click_config.status_glyphs = config::StatusGlyphs::None,
+                "unicode" => env.click_config.status_glyphs = config::StatusGlyphs::Unicode,
+                "ascii" => env.click_config.status_glyphs = config::StatusGlyphs::Ascii,
+                _ => {
+                    clickwriteln!(
+                        writer,
+                        "Invalid status_glyphs.  Possible values are: [none, unicode, ascii]"
+                    );
+                    failed = true;
+                }
+            },
             _ => {
                 // this shouldn't happen
                 write!(stderr(), "Invalid option\n").unwrap_or(());
@@ -270,7 +834,22 @@ Example:
     }
 );
 
-pub const UNSET_OPTS: &[&str] = &["editor", "kubectl_binary", "terminal", "range_separator"];
+pub const UNSET_OPTS: &[&str] = &[
+    "editor",
+    "pager",
+    "default_output",
+    "connect_timeout_secs",
+    "read_timeout_secs",
+    "kubectl_binary",
+    "terminal",
+    "range_separator",
+    "timezone",
+    "prompt",
+    "theme_colors",
+    "pick_threshold",
+    "dangerous_contexts",
+    "notify_after_secs",
+];
 
 command!(
     UnSetCmd,
@@ -295,6 +874,20 @@ command!(
             "editor" => {
                 env.set_editor(None);
             }
+            "pager" => {
+                env.click_config.pager = None;
+            }
+            "default_output" => {
+                env.click_config.default_output = None;
+            }
+            "connect_timeout_secs" => {
+                env.click_config.connect_timeout_secs = crate::config::default_connect_timeout();
+                env.reload_context();
+            }
+            "read_timeout_secs" => {
+                env.click_config.read_timeout_secs = crate::config::default_read_timeout();
+                env.reload_context();
+            }
             "terminal" => {
                 env.set_terminal(None);
             }
@@ -304,6 +897,26 @@ command!(
             "range_separator" => {
                 env.click_config.range_separator = crate::config::default_range_sep();
             }
+            "timezone" => {
+                env.click_config.timezone = None;
+            }
+            "prompt" => {
+                // can't fail, there's no template to check
+                env.set_prompt_template(None).unwrap_or(());
+            }
+            "theme_colors" => {
+                env.click_config.theme_colors.clear();
+                env.apply_theme().unwrap_or(());
+            }
+            "pick_threshold" => {
+                env.click_config.pick_threshold = None;
+            }
+            "dangerous_contexts" => {
+                env.click_config.dangerous_contexts = crate::config::default_dangerous_contexts();
+            }
+            "notify_after_secs" => {
+                env.click_config.notify_after_secs = None;
+            }
             _ => {
                 // this shouldn't happen
                 write!(stderr(), "Invalid option\n").unwrap_or(());
@@ -330,3 +943,89 @@ command!(
         Ok(())
     }
 );
+
+/// The Kubernetes version click's models of resources come from, set by the k8s-openapi feature
+/// in Cargo.toml
+const MODELED_VERSION: (u32, u32) = (1, 23);
+
+/// Parse a major or minor version number from /version. Some providers add to them, like 27+
+fn version_number(num: &str) -> Option<u32> {
+    let digits: String = num.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// A warning if a server at version major.minor is newer or older than the models click uses
+fn version_skew_warning(major: &str, minor: &str) -> Option<String> {
+    let server = (version_number(major)?, version_number(minor)?);
+    let (modeled_major, modeled_minor) = MODELED_VERSION;
+    let direction = match server.cmp(&MODELED_VERSION) {
+        std::cmp::Ordering::Equal => return None,
+        std::cmp::Ordering::Greater => {
+            "newer than the one click's resources are modeled on, so fields added since may be \
+             missing from its output"
+        }
+        std::cmp::Ordering::Less => {
+            "older than the one click's resources are modeled on, so some resources or fields \
+             click uses may not exist on it"
+        }
+    };
+    Some(format!(
+        "The server ({}.{}) is {} ({}.{})",
+        server.0, server.1, direction, modeled_major, modeled_minor
+    ))
+}
+
+command!(
+    Version,
+    "version",
+    "Print the version of click, and of the api server of the current context",
+    identity,
+    vec!["version"],
+    noop_complete!(),
+    no_named_complete!(),
+    |_, env, writer| {
+        clickwriteln!(
+            writer,
+            "Click Version:  {} (resources modeled on Kubernetes {}.{})",
+            crate_version!(),
+            MODELED_VERSION.0,
+            MODELED_VERSION.1
+        );
+        if env.context.is_none() {
+            clickwriteln!(writer, "Server Version: <no active context>");
+            return Ok(());
+        }
+        let (request, _) = k8s_openapi::get_code_version()?;
+        let info: Info = env.run_on_context(|c| c.read_object(request))?;
+        clickwriteln!(
+            writer,
+            "Server Version: {} ({}, {}, built {})",
+            info.git_version,
+            info.go_version,
+            info.platform,
+            info.build_date
+        );
+        if let Some(warning) = version_skew_warning(&info.major, &info.minor) {
+            clickwriteln!(writer, "{}", env.styles.warning(&warning));
+        }
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_skew_warning() {
+        assert_eq!(version_skew_warning("1", "23"), None);
+        assert_eq!(version_skew_warning("1", "23+"), None);
+        assert!(version_skew_warning("1", "27+")
+            .unwrap()
+            .starts_with("The server (1.27) is newer"));
+        assert!(version_skew_warning("1", "20")
+            .unwrap()
+            .starts_with("The server (1.20) is older"));
+        assert_eq!(version_skew_warning("", ""), None);
+    }
+}
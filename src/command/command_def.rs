@@ -56,6 +56,35 @@ pub fn try_complete(prefix: &str, extra_cols: &[&str]) -> Vec<RustlinePair> {
     v
 }
 
+/// Commands that act on the active object(s). The name of an object from the last list can be
+/// given as their first argument, like `logs web-1`, to select it first
+const SELECTION_COMMANDS: [&str; 19] = [
+    "children",
+    "containers",
+    "copy",
+    "debug",
+    "delete",
+    "describe",
+    "diff",
+    "dump",
+    "events",
+    "exec",
+    "logs",
+    "network",
+    "owner",
+    "port-forward",
+    "quota-check",
+    "scaling",
+    "security",
+    "storage",
+    "wait",
+];
+
+/// Does cmd act on the active object(s)
+pub fn takes_selection(cmd: &dyn Cmd) -> bool {
+    SELECTION_COMMANDS.contains(&cmd.get_name())
+}
+
 macro_rules! extract_first {
     ($map: ident) => {{
         let mut result: [&str; $map.len()] = [""; $map.len()];
@@ -135,12 +164,13 @@ where
     let mut cmd = clap.borrow_mut();
     let matches = cmd.try_get_matches_from_mut(args);
     match matches {
-        Ok(matches) => func(matches, env, writer),
+        Ok(matches) => {
+            env.set_command_name(cmd.get_name());
+            func(matches, env, writer)
+        }
         Err(e) => {
             if e.kind() == clap::ErrorKind::DisplayHelp {
-                cmd.print_help().expect("Couldn't print help");
-                // todo: switch back in the same way as write_help
-                //clickwriteln!(writer, "{}", e);
+                cmd.write_help(writer)?;
                 Ok(())
             } else if e.kind() == clap::ErrorKind::DisplayVersion {
                 clickwriteln!(writer, "{}", e);
@@ -253,10 +283,8 @@ macro_rules! command {
                 $name
             }
 
-            // TODO: switch back to command.write_help when
-            // https://github.com/clap-rs/clap/issues/3480 is resolved
             fn write_help(&self, writer: &mut ClickWriter) {
-                if let Err(res) = self.clap.borrow_mut().print_help() {
+                if let Err(res) = self.clap.borrow_mut().write_help(writer) {
                     clickwriteln!(writer, "Couldn't print help: {}", res);
                 }
             }
@@ -283,9 +311,9 @@ macro_rules! command {
                 let mut args = app.get_arguments();
                 // see if an opt we have matches what we've typed so far
                 let arg_opt = args.find(|arg| {
-                    // first see if the long flag matches
-                    if let Some(long) = arg.get_long() {
-                        if long == &opt[2..] {
+                    // first see if the long flag, or one of its aliases, matches
+                    if let Some(longs) = arg.get_long_and_visible_aliases() {
+                        if longs.contains(&&opt[2..]) {
                             // strip off -- prefix we get passed
                             return true;
                         }
@@ -443,6 +471,163 @@ pub fn sort_arg<'a>(cols: &'a [&'a str], extra_cols: Option<&'a [&'a str]>) -> A
     }
 }
 
+/// get a clap arg for sorting by a column or a path into the objects
+pub fn sort_by_arg<'a>() -> Arg<'a> {
+    Arg::new("sort-by")
+        .long("sort-by")
+        .value_name("COLUMN_OR_PATH")
+        .help(
+            "Sort by the specified column or path into the objects (example: --sort-by \
+             .status.startTime). Use -R to sort descending",
+        )
+        .takes_value(true)
+        .conflicts_with("sort")
+}
+
+/// How often --watch refreshes by default
+pub const DEFAULT_WATCH_SECS: u64 = 2;
+
+/// get a clap arg for refreshing a list until ctrl-c is pressed
+pub fn watch_arg<'a>() -> Arg<'a> {
+    Arg::new("watch")
+        .short('w')
+        .long("watch")
+        .value_name("SECS")
+        .help(
+            "Keep refreshing the list (every 2 seconds, or the specified number of seconds) \
+             until ctrl-c is pressed, highlighting new and changed rows",
+        )
+        .takes_value(true)
+        .min_values(0)
+        .max_values(1)
+}
+
+/// get a clap arg for picking which columns to show, in what order
+pub fn columns_arg<'a>() -> Arg<'a> {
+    Arg::new("columns")
+        .long("columns")
+        .value_name("COLUMNS")
+        .help(
+            "Comma separated list (case-insensitive) of the columns to show, in the order to show \
+             them in (example: --columns name,node,status). Defaults can be set for each command \
+             in the list_columns section of click's config",
+        )
+        .takes_value(true)
+        .use_value_delimiter(true)
+}
+
+/// get a clap arg for hiding some of the default columns
+pub fn hide_arg<'a>() -> Arg<'a> {
+    Arg::new("hide")
+        .long("hide")
+        .value_name("COLUMNS")
+        .help("Comma separated list (case-insensitive) of columns to hide (example: --hide age)")
+        .takes_value(true)
+        .use_value_delimiter(true)
+}
+
+/// get a clap arg for showing all the labels of each object
+pub fn show_labels_arg<'a>() -> Arg<'a> {
+    Arg::new("show-labels")
+        .long("show-labels")
+        .help("Show all the labels of each object as a column (same as --show labels)")
+        .takes_value(false)
+}
+
+/// get a clap arg for showing the values of specific labels as columns
+pub fn label_columns_arg<'a>() -> Arg<'a> {
+    Arg::new("label-columns")
+        .short('L')
+        .long("label-columns")
+        .value_name("LABELS")
+        .help(
+            "Comma separated list of labels to show the value of as columns (example: -L app,tier)",
+        )
+        .takes_value(true)
+        .use_value_delimiter(true)
+}
+
+/// get a clap arg for listing objects in every namespace, not just the current one
+pub fn all_namespaces_arg<'a>() -> Arg<'a> {
+    Arg::new("all-namespaces")
+        .short('A')
+        .long("all-namespaces")
+        .help("List objects in all namespaces, with a Namespace column, even if a namespace is set")
+        .takes_value(false)
+}
+
+/// get a clap arg for only listing objects that match a label selector
+pub fn label_selector_arg<'a>() -> Arg<'a> {
+    Arg::new("label")
+        .short('l')
+        .long("label")
+        .value_name("SELECTOR")
+        .help(
+            "Only list objects matching the label selector, which is sent to the api server \
+             (examples: -l app=nginx, -l 'app=web,tier in (frontend,api)', -l '!canary')",
+        )
+        .takes_value(true)
+}
+
+/// get a clap arg for only listing objects that match a field selector
+pub fn field_selector_arg<'a>() -> Arg<'a> {
+    Arg::new("field-selector")
+        .long("field-selector")
+        .value_name("SELECTOR")
+        .help(
+            "Only list objects matching the field selector, which is sent to the api server. \
+             Which fields can be used depends on the kind of object (examples: \
+             --field-selector status.phase!=Running, --field-selector spec.nodeName=node-3)",
+        )
+        .takes_value(true)
+}
+
+/// get a clap arg for only listing objects whose names match a regex
+pub fn filter_arg<'a>() -> Arg<'a> {
+    Arg::new("filter")
+        .long("filter")
+        .value_name("REGEX")
+        .help(
+            "Only list objects whose names match REGEX. Unlike --regex, this only looks at the \
+             name, and it also applies to -o yaml and json output. Can also be given as /REGEX/ \
+             after the command (example: pods /^web-/)",
+        )
+        .takes_value(true)
+}
+
+/// get a clap arg for giving the --filter regex as /REGEX/
+pub fn filter_pattern_arg<'a>() -> Arg<'a> {
+    Arg::new("filter-pattern")
+        .value_name("/REGEX/")
+        .help("Same as --filter REGEX")
+        .index(1)
+        .conflicts_with("filter")
+        .validator(|pattern| {
+            if pattern.len() >= 2 && pattern.starts_with('/') && pattern.ends_with('/') {
+                Ok(())
+            } else {
+                Err("expected a regex between slashes, like /^web-/".to_string())
+            }
+        })
+}
+
+/// get a clap arg for leaving the header off the table
+pub fn no_headers_arg<'a>() -> Arg<'a> {
+    Arg::new("no-headers")
+        .long("no-headers")
+        .help("Don't print the table header, so the output can be piped into other commands")
+        .takes_value(false)
+}
+
+/// get a clap arg for only printing the names of the returned objects
+pub fn quiet_arg<'a>() -> Arg<'a> {
+    Arg::new("quiet")
+        .short('q')
+        .long("quiet")
+        .help("Only print the names of the returned objects, one per line")
+        .takes_value(false)
+}
+
 static SHOW_HELP: &str =
     "Comma separated list (case-insensitive) of extra columns to show in output. \
      Use '--show all' to show all available columns.";
@@ -466,3 +651,67 @@ pub fn show_arg<'a>(extra_cols: &'a [&'a str], labels: bool) -> Arg<'a> {
         arg.help(SHOW_HELP)
     }
 }
+
+static OUTPUT_HELP: &str = "Output format. One of: table (default), wide, csv, tsv, markdown, yaml, \
+     json, \
+     jsonpath=<template>, custom-columns=<spec>, template=<template>, template-file=<path>. \
+     wide shows all extra columns, csv and tsv print the table columns as separated values, markdown (or md) \
+     prints them as a markdown table. json can be followed by a path to only print part of the output \
+     (example: -o json .items[0].metadata.name). jsonpath takes a kubectl style template \
+     (example: -o jsonpath='{.items[*].metadata.name}'). custom-columns takes a comma separated \
+     list of TITLE:path (example: -o custom-columns=NAME:.metadata.name,NODE:.spec.nodeName). \
+     template takes a handlebars style template, supporting {{path}}, {{#each path}}, \
+     {{#if path}} and {{else}} (example: -o template='{{#each items}}{{metadata.name}}:\
+     {{#each spec.containers}} {{image}}{{/each}}\\n{{/each}}'). For list commands, this can \
+     also be the name of an output view from the output_views section of click's config";
+/// get a clap arg for selecting the output format. The format is validated when it's used, since
+/// the optional second value is a filter
+pub fn output_arg<'a>() -> Arg<'a> {
+    Arg::new("output")
+        .short('o')
+        .long("output")
+        .value_name("FORMAT")
+        .help(OUTPUT_HELP)
+        .takes_value(true)
+        .min_values(1)
+        .max_values(2)
+}
+
+/// How a mutating command should preview its changes instead of making them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DryRun {
+    /// Just print the request that would be sent
+    Client,
+    /// Send the request with dryRun=All, so the api server validates it but persists nothing
+    Server,
+}
+
+impl DryRun {
+    pub fn from_matches(matches: &ArgMatches) -> Option<DryRun> {
+        matches.value_of("dry-run").map(|val| {
+            if val.eq_ignore_ascii_case("server") {
+                DryRun::Server
+            } else {
+                DryRun::Client
+            }
+        })
+    }
+}
+
+/// get a clap arg for previewing a mutating command. A bare --dry-run means --dry-run=client
+pub fn dry_run_arg<'a>() -> Arg<'a> {
+    Arg::new("dry-run")
+        .long("dry-run")
+        .help(
+            "Don't change anything. With 'client' (the default), print the request that would be \
+             sent. With 'server', send it with the dryRun option, so the api server validates it \
+             without persisting anything",
+        )
+        .takes_value(true)
+        .possible_values(["client", "server"])
+        .ignore_case(true)
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .default_missing_value("client")
+}
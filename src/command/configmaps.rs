@@ -16,8 +16,12 @@ use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::api::core::v1 as api;
 
 use crate::{
-    command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{run_list_command, Extractor},
+    command::command_def::{
+        all_namespaces_arg, columns_arg, exec_match, field_selector_arg, filter_arg,
+        filter_pattern_arg, hide_arg, label_selector_arg, no_headers_arg, output_arg, quiet_arg,
+        show_arg, sort_arg, sort_by_arg, start_clap, watch_arg, Cmd,
+    },
+    command::{list_namespace, list_options, run_list_command, Extractor},
     completer,
     env::Env,
     kobj::{KObj, ObjType},
@@ -40,7 +44,7 @@ const COL_MAP: &[(&str, &str)] = &[("name", "Name"), ("data", "Data"), ("age", "
 
 const COL_FLAGS: &[&str] = &{ extract_first!(COL_MAP) };
 
-const EXTRA_COL_MAP: &[(&str, &str)] = &[("labels", "Labels")];
+const EXTRA_COL_MAP: &[(&str, &str)] = &[("labels", "Labels"), ("namespace", "Namespace")];
 
 const EXTRA_COL_FLAGS: &[&str] = &{ extract_first!(EXTRA_COL_MAP) };
 
@@ -81,8 +85,20 @@ list_command!(
                 .help("Filter confimaps by the specified regex")
                 .takes_value(true)
         )
+        .arg(all_namespaces_arg())
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
         .arg(show_arg(EXTRA_COL_FLAGS, true))
         .arg(sort_arg(COL_FLAGS, None))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
         .arg(
             Arg::new("reverse")
                 .short('R')
@@ -92,11 +108,15 @@ list_command!(
         ),
     vec!["cm", "configmaps"],
     noop_complete!(),
-    [].into_iter(),
+    [(
+        "regex".to_string(),
+        completer::configmap_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter(),
     |matches, env, writer| {
-        let (request, _response_body) = match &env.namespace {
-            Some(ns) => api::ConfigMap::list_namespaced_config_map(ns, Default::default())?,
-            None => api::ConfigMap::list_config_map_for_all_namespaces(Default::default())?,
+        let (request, _response_body) = match list_namespace(&matches, env) {
+            Some(ns) => api::ConfigMap::list_namespaced_config_map(ns, list_options(&matches))?,
+            None => api::ConfigMap::list_config_map_for_all_namespaces(list_options(&matches))?,
         };
         let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
 
@@ -19,10 +19,10 @@ use rustyline::completion::Pair as RustlinePair;
 use crate::{
     command::command_def::{exec_match, start_clap, Cmd},
     completer,
-    crd::GetAPIGroupResourcesResponse,
+    crd::ApiResource,
     env::Env,
     error::ClickError,
-    k8s_table::{get_k8s_table, GetTableResponse},
+    k8s_table::{get_k8s_table, K8sTable},
     output::ClickWriter,
 };
 
@@ -30,57 +30,15 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::Write;
 
-struct CrdApiDesc {
-    group_version: String,
-    name: String,
-    namespaced: bool,
-}
-
-impl CrdApiDesc {
-    fn url(&self, namespace: Option<&str>) -> String {
-        if self.namespaced && namespace.is_some() {
-            format!(
-                "/apis/{}/namespaces/{}/{}",
-                self.group_version,
-                namespace.as_ref().unwrap(), // safe: checked
-                self.name
-            )
-        } else {
-            format!("/apis/{}/{}", self.group_version, self.name)
-        }
-    }
-}
-
-// If the server indicates that it knows about crds named 'name', return the description we can use
-// to access them. Otherwise, return None
-fn find_desc_for(env: &mut Env, name: &str) -> Result<Option<CrdApiDesc>, ClickError> {
-    let groups = crate::crd::get_api_groups(env)?;
-    for group in groups.iter() {
-        let version = match group.preferred_version.as_ref() {
-            Some(pv) => Some(pv.group_version.as_str()),
-            None => group.versions.first().map(|v| v.group_version.as_str()),
-        };
-        if let Some(group_version) = version {
-            let (group_req, _) = crate::crd::get_api_group_resources(group_version)?;
-            match env.run_on_context::<_, GetAPIGroupResourcesResponse>(|c| c.read(group_req))? {
-                GetAPIGroupResourcesResponse::Ok(resp) => {
-                    for resource in resp.resources.iter() {
-                        if resource.name == name || resource.singular_name == name {
-                            return Ok(Some(CrdApiDesc {
-                                group_version: group_version.to_string(),
-                                name: resource.name.clone(),
-                                namespaced: resource.namespaced,
-                            }));
-                        }
-                    }
-                }
-                GetAPIGroupResourcesResponse::Other(_) => {
-                    println!("Error"); // TODO: Print something more useful
-                }
-            }
-        }
-    }
-    Ok(None)
+// If the server indicates that it knows about crd created resources called 'name', return the
+// description we can use to access them. Otherwise, return None
+fn find_desc_for(env: &mut Env, name: &str) -> Result<Option<ApiResource>, ClickError> {
+    let discovery = crate::crd::discover_resources(env)?;
+    // the core group's resources have their own commands, and kobjs can't read them as crds
+    Ok(discovery
+        .resources
+        .into_iter()
+        .find(|resource| !resource.group.is_empty() && resource.is_named(name)))
 }
 
 command!(
@@ -101,20 +59,16 @@ command!(
         let api_desc = find_desc_for(env, name)?;
         match api_desc {
             Some(desc) => {
-                let (request, _) = get_k8s_table(&desc.url(env.namespace.as_deref()))?;
-                match env.run_on_context::<_, GetTableResponse>(|c| c.read(request))? {
-                    GetTableResponse::Ok(resp) => {
-                        let kobjs = resp.print_to(
-                            env,
-                            env.namespace.is_none(),
-                            &desc.name,
-                            &desc.group_version,
-                            writer,
-                        );
-                        env.set_last_objs(kobjs);
-                    }
-                    GetTableResponse::Other(_) => println!("Other error"),
-                }
+                let request = get_k8s_table(&desc.list_path(env.namespace.as_deref()))?;
+                let table: K8sTable = env.run_on_context(|c| c.read_object(request))?;
+                let kobjs = table.print_to(
+                    env,
+                    env.namespace.is_none(),
+                    &desc.name,
+                    &desc.group_version(),
+                    writer,
+                );
+                env.set_last_objs(kobjs);
             }
             None => {
                 clickwriteln!(
@@ -16,8 +16,12 @@ use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::api::apps::v1 as apps_api;
 
 use crate::{
-    command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{run_list_command, Extractor},
+    command::command_def::{
+        all_namespaces_arg, columns_arg, exec_match, field_selector_arg, filter_arg,
+        filter_pattern_arg, hide_arg, label_selector_arg, no_headers_arg, output_arg, quiet_arg,
+        show_arg, sort_arg, sort_by_arg, start_clap, watch_arg, Cmd,
+    },
+    command::{list_namespace, list_options, run_list_command, Extractor},
     completer,
     env::Env,
     kobj::{KObj, ObjType},
@@ -154,8 +158,20 @@ list_command!(
                 .help("Filter daemonsets by the specified regex")
                 .takes_value(true)
         )
+        .arg(all_namespaces_arg())
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
         .arg(show_arg(EXTRA_COL_FLAGS, true))
         .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
         .arg(
             Arg::new("reverse")
                 .short('R')
@@ -165,11 +181,19 @@ list_command!(
         ),
     vec!["ds", "daemonsets"],
     noop_complete!(),
-    [].into_iter(),
+    [(
+        "regex".to_string(),
+        completer::daemonset_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter(),
     |matches, env, writer| {
-        let (request, _response_body) = match &env.namespace {
-            Some(ns) => apps_api::DaemonSet::list_namespaced_daemon_set(ns, Default::default())?,
-            None => apps_api::DaemonSet::list_daemon_set_for_all_namespaces(Default::default())?,
+        let (request, _response_body) = match list_namespace(&matches, env) {
+            Some(ns) => {
+                apps_api::DaemonSet::list_namespaced_daemon_set(ns, list_options(&matches))?
+            }
+            None => {
+                apps_api::DaemonSet::list_daemon_set_for_all_namespaces(list_options(&matches))?
+            }
         };
         let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
 
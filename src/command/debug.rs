@@ -0,0 +1,322 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use k8s_openapi::{api::core::v1 as api, apimachinery::pkg::apis::meta::v1::Patch};
+use rustyline::completion::Pair as RustlinePair;
+use serde_json::json;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    command::exec::run_kubectl,
+    completer,
+    env::{Env, ObjectSelection},
+    error::ClickError,
+    kobj::KObj,
+    output::ClickWriter,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, stderr, IsTerminal, Write};
+use std::process::Command;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+const DEFAULT_IMAGE: &str = "busybox";
+
+/// How long to wait for the debug container to start, which can include pulling its image
+const START_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Reasons a waiting container won't start without something changing
+const STUCK_REASONS: [&str; 4] = [
+    "ErrImagePull",
+    "ImagePullBackOff",
+    "InvalidImageName",
+    "CreateContainerConfigError",
+];
+
+/// Ephemeral containers can't be removed, so each debug session needs a new name. Pick the first
+/// of debugger, debugger-1, debugger-2... that the pod doesn't have yet
+fn debug_container_name(existing: &[String]) -> String {
+    let mut name = "debugger".to_string();
+    let mut n = 0;
+    while existing.contains(&name) {
+        n += 1;
+        name = format!("debugger-{}", n);
+    }
+    name
+}
+
+fn read_pod(env: &Env, obj: &KObj, ns: &str) -> Result<api::Pod, ClickError> {
+    let (request, _) = api::Pod::read_namespaced_pod(obj.name(), ns, Default::default())?;
+    env.run_on_context(|c| c.read_object(request))
+}
+
+/// The status of the ephemeral container called name in pod, if it has one yet
+fn ephemeral_status<'a>(pod: &'a api::Pod, name: &str) -> Option<&'a api::ContainerStatus> {
+    pod.status
+        .as_ref()?
+        .ephemeral_container_statuses
+        .as_ref()?
+        .iter()
+        .find(|status| status.name == name)
+}
+
+/// Wait for the debug container to be running. Errors if it exits, or can't start
+fn wait_for_start(env: &Env, obj: &KObj, ns: &str, name: &str) -> Result<(), ClickError> {
+    let deadline = Instant::now() + START_TIMEOUT;
+    env.ctrlcbool.store(false, Ordering::SeqCst);
+    loop {
+        let pod = read_pod(env, obj, ns)?;
+        let state = ephemeral_status(&pod, name).and_then(|status| status.state.as_ref());
+        if let Some(state) = state {
+            if state.running.is_some() {
+                return Ok(());
+            }
+            if let Some(terminated) = state.terminated.as_ref() {
+                return Err(ClickError::CommandError(format!(
+                    "Debug container exited ({}, exit code {})",
+                    terminated.reason.as_deref().unwrap_or("no reason"),
+                    terminated.exit_code
+                )));
+            }
+            if let Some(waiting) = state.waiting.as_ref() {
+                let reason = waiting.reason.as_deref().unwrap_or("");
+                if STUCK_REASONS.contains(&reason) {
+                    return Err(ClickError::CommandError(format!(
+                        "Debug container can't start: {} {}",
+                        reason,
+                        waiting.message.as_deref().unwrap_or("")
+                    )));
+                }
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(ClickError::CommandError(
+                "Timed out waiting for the debug container to start".to_string(),
+            ));
+        }
+        // noticing ctrl-c quickly
+        for _ in 0..5 {
+            if env.ctrlcbool.load(Ordering::SeqCst) {
+                env.ctrlcbool.store(false, Ordering::SeqCst);
+                return Err(ClickError::CommandError(
+                    "Stopped waiting for the debug container to start".to_string(),
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+fn confirm_debug(env: &Env, obj: &KObj, writer: &mut ClickWriter) -> bool {
+    clickwrite!(
+        writer,
+        "Add a debug container to pod {} (it stays until the pod is deleted) [y/N]? ",
+        obj.name()
+    );
+    writer.flush().expect("Could not flush output");
+    if let Ok(conf) = env.read_response() {
+        conf.trim() == "y" || conf.trim() == "yes"
+    } else {
+        writeln!(
+            stderr(),
+            "Could not read response, not adding debug container."
+        )
+        .unwrap_or(());
+        false
+    }
+}
+
+fn do_debug(
+    env: &Env,
+    obj: &KObj,
+    context: &str,
+    image: &str,
+    target: Option<&str>,
+    cmd: Option<Vec<&str>>,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let ns = obj.namespace.as_deref().ok_or_else(|| {
+        ClickError::CommandError(format!("No namespace for {}, cannot debug it", obj.name()))
+    })?;
+    let pod = read_pod(env, obj, ns)?;
+    let existing: Vec<String> = pod
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.ephemeral_containers.as_ref())
+        .map(|conts| conts.iter().map(|cont| cont.name.clone()).collect())
+        .unwrap_or_default();
+    let name = debug_container_name(&existing);
+    if env.needs_confirmation() && !confirm_debug(env, obj, writer) {
+        clickwriteln!(writer, "Not adding debug container");
+        return Ok(());
+    }
+
+    let tty = io::stdin().is_terminal();
+    let mut container = json!({
+        "name": name,
+        "image": image,
+        "stdin": true,
+        "tty": tty,
+    });
+    if let Some(target) = target {
+        container["targetContainerName"] = json!(target);
+    }
+    if let Some(cmd) = cmd {
+        container["command"] = json!(cmd);
+    }
+    let patch = Patch::StrategicMerge(json!({
+        "spec": { "ephemeralContainers": [container] }
+    }));
+    let (request, _) = api::Pod::patch_namespaced_pod_ephemeralcontainers(
+        obj.name(),
+        ns,
+        &patch,
+        Default::default(),
+    )?;
+    env.run_on_context(|c| c.read_object::<api::Pod>(request))?;
+    clickwriteln!(
+        writer,
+        "Added debug container {}, waiting for it to start",
+        name
+    );
+    writer.flush()?;
+    wait_for_start(env, obj, ns, &name)?;
+
+    let kubectl_binary = env
+        .click_config
+        .kubectl_binary
+        .as_deref()
+        .unwrap_or("kubectl");
+    let mut command = Command::new(kubectl_binary);
+    command
+        .arg("--namespace")
+        .arg(ns)
+        .arg("--context")
+        .arg(context)
+        .arg("attach")
+        .arg(if tty { "-it" } else { "-i" })
+        .arg(obj.name())
+        .arg("-c")
+        .arg(&name);
+    run_kubectl(env, &mut command, kubectl_binary)
+}
+
+command!(
+    Debug,
+    "debug",
+    "Add an ephemeral debug container to the active pod, and attach to it",
+    |clap: ClapCommand<'static>| clap
+        .arg(
+            Arg::new("image")
+                .long("image")
+                .value_name("IMAGE")
+                .help("The image to run in the debug container (default busybox)")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new("target")
+                .long("target")
+                .value_name("CONTAINER")
+                .help(
+                    "Share the process namespace of this container, so its processes can be \
+                     seen (and its filesystem reached through /proc/PID/root)"
+                )
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new("command")
+                .help("The command to run, instead of the image's default")
+                .multiple_values(true) // required for trailing_var_arg
+                .index(1)
+        )
+        .after_help(
+            "Debug containers are for pods whose images have no shell or tools, so exec doesn't \
+help. The container is added through the ephemeralcontainers subresource, which needs \
+Kubernetes 1.23 or later. Ephemeral containers can't be removed, they stay (stopped, once \
+the shell exits) until the pod is deleted, so each debug session adds a new one.
+
+Examples:
+  # get a busybox shell in the selected pod
+  debug
+
+  # see the processes of the app container, with a different image
+  debug --image nicolaka/netshoot --target app
+
+  # run a specific command (flags for the command go after --)
+  debug -- sh -c 'wget -qO- localhost:8080/health'"
+        ),
+    vec!["debug"],
+    noop_complete!(),
+    [(
+        "target".to_string(),
+        completer::container_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter()
+    .collect(),
+    |matches, env, writer| {
+        let context = env.context.as_ref().ok_or_else(|| {
+            ClickError::CommandError("Need an active context in order to debug.".to_string())
+        })?;
+        let obj = match env.current_selection() {
+            ObjectSelection::Single(obj) if obj.is_pod() => obj,
+            ObjectSelection::Single(_) => {
+                return Err(ClickError::CommandError(
+                    "Debug only possible on pods".to_string(),
+                ))
+            }
+            ObjectSelection::Range(_) => {
+                return Err(ClickError::CommandError(
+                    "Debug one pod at a time, select a single pod".to_string(),
+                ))
+            }
+            ObjectSelection::None => {
+                return Err(ClickError::CommandError(
+                    "No objects currently active".to_string(),
+                ))
+            }
+        };
+        do_debug(
+            env,
+            obj,
+            &context.name,
+            matches.value_of("image").unwrap_or(DEFAULT_IMAGE),
+            matches.value_of("target"),
+            matches.values_of("command").map(|cmd| cmd.collect()),
+            writer,
+        )
+    },
+    true // gather up the trailing args into the command
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_container_name() {
+        assert_eq!(debug_container_name(&[]), "debugger");
+        assert_eq!(
+            debug_container_name(&["debugger".to_string()]),
+            "debugger-1"
+        );
+        assert_eq!(
+            debug_container_name(&["debugger".to_string(), "debugger-1".to_string()]),
+            "debugger-2"
+        );
+        assert_eq!(debug_container_name(&["other".to_string()]), "debugger");
+    }
+}
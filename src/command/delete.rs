@@ -14,14 +14,15 @@
 
 use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::{
-    api::apps::v1 as api_apps, api::batch::v1 as api_batch, api::core::v1 as api,
-    api::storage::v1 as api_storage, http::Request, DeleteOptional, DeleteResponse,
+    api::apps::v1 as api_apps, api::autoscaling::v2 as api_autoscaling,
+    api::batch::v1 as api_batch, api::core::v1 as api, api::storage::v1 as api_storage,
+    http::Request, DeleteOptional, DeleteResponse,
 };
 use rustyline::completion::Pair as RustlinePair;
 use serde::de::DeserializeOwned;
 
 use crate::{
-    command::command_def::{exec_match, start_clap, Cmd},
+    command::command_def::{dry_run_arg, exec_match, start_clap, Cmd, DryRun},
     command::{uppercase_first, valid_u32},
     completer,
     env::Env,
@@ -34,21 +35,41 @@ use crate::{
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::io::{self, stderr, Write};
+use std::io::{stderr, Write};
+
+/// Print the request a client side dry run would have sent
+fn print_request(writer: &mut ClickWriter, request: &Request<Vec<u8>>) {
+    clickwriteln!(writer, "{} {}", request.method(), request.uri());
+    let body = request.body();
+    if !body.is_empty() {
+        clickwriteln!(writer, "{}", String::from_utf8_lossy(body));
+    }
+}
 
 fn send_delete<D: DeserializeOwned + Debug>(
     env: &Env,
     writer: &mut ClickWriter,
     request: Request<Vec<u8>>,
+    dry_run: Option<DryRun>,
 ) -> Result<(), ClickError> {
+    if dry_run == Some(DryRun::Client) {
+        clickwriteln!(writer, "Would send (dry run):");
+        print_request(writer, &request);
+        return Ok(());
+    }
+    let suffix = if dry_run == Some(DryRun::Server) {
+        " (server dry run)"
+    } else {
+        ""
+    };
     let r = env.run_on_context::<_, DeleteResponse<D>>(|c| c.read(request))?;
     match r {
         DeleteResponse::OkStatus(_) | DeleteResponse::OkValue(_) => {
-            clickwriteln!(writer, "Deleted");
+            clickwriteln!(writer, "Deleted{}", suffix);
             Ok(())
         }
         DeleteResponse::Accepted(_) => {
-            clickwriteln!(writer, "Delete request accepted");
+            clickwriteln!(writer, "Delete request accepted{}", suffix);
             Ok(())
         }
         DeleteResponse::Other(res) => match res {
@@ -77,6 +98,7 @@ fn delete_obj(
     writer: &mut ClickWriter,
     obj: &KObj,
     options: DeleteOptional,
+    dry_run: Option<DryRun>,
 ) -> Result<(), ClickError> {
     match obj.namespace.as_ref() {
         Some(ns) => match obj.typ {
@@ -87,7 +109,7 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api::ConfigMap>(env, writer, req)
+                send_delete::<api::ConfigMap>(env, writer, req, dry_run)
             }
             ObjType::DaemonSet => {
                 let req = api_apps::DaemonSet::delete_namespaced_daemon_set(
@@ -96,7 +118,7 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api_apps::DaemonSet>(env, writer, req)
+                send_delete::<api_apps::DaemonSet>(env, writer, req, dry_run)
             }
             ObjType::Deployment => {
                 let req = api_apps::Deployment::delete_namespaced_deployment(
@@ -105,13 +127,32 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api_apps::Deployment>(env, writer, req)
+                send_delete::<api_apps::Deployment>(env, writer, req, dry_run)
+            }
+            ObjType::HorizontalPodAutoscaler => {
+                let req =
+                    api_autoscaling::HorizontalPodAutoscaler::delete_namespaced_horizontal_pod_autoscaler(
+                        obj.name.as_str(),
+                        ns.as_str(),
+                        options,
+                    )?
+                    .0;
+                send_delete::<api_autoscaling::HorizontalPodAutoscaler>(env, writer, req, dry_run)
             }
             ObjType::Job => {
                 let req =
                     api_batch::Job::delete_namespaced_job(obj.name.as_str(), ns.as_str(), options)?
                         .0;
-                send_delete::<api_batch::Job>(env, writer, req)
+                send_delete::<api_batch::Job>(env, writer, req, dry_run)
+            }
+            ObjType::CronJob => {
+                let req = api_batch::CronJob::delete_namespaced_cron_job(
+                    obj.name.as_str(),
+                    ns.as_str(),
+                    options,
+                )?
+                .0;
+                send_delete::<api_batch::CronJob>(env, writer, req, dry_run)
             }
             ObjType::Namespace => {
                 clickwriteln!(
@@ -120,7 +161,7 @@ fn delete_obj(
                      Deleting anyway"
                 );
                 let req = api::Namespace::delete_namespace(obj.name.as_str(), options)?.0;
-                send_delete::<api::Namespace>(env, writer, req)
+                send_delete::<api::Namespace>(env, writer, req, dry_run)
             }
             ObjType::Node => {
                 clickwriteln!(
@@ -129,7 +170,7 @@ fn delete_obj(
                          Deleting anyway"
                 );
                 let req = api::Node::delete_node(obj.name.as_str(), options)?.0;
-                send_delete::<api::Node>(env, writer, req)
+                send_delete::<api::Node>(env, writer, req, dry_run)
             }
             ObjType::PersistentVolume => {
                 clickwriteln!(
@@ -139,12 +180,21 @@ fn delete_obj(
                 );
                 let req =
                     api::PersistentVolume::delete_persistent_volume(obj.name.as_str(), options)?.0;
-                send_delete::<api::PersistentVolume>(env, writer, req)
+                send_delete::<api::PersistentVolume>(env, writer, req, dry_run)
+            }
+            ObjType::PersistentVolumeClaim => {
+                let req = api::PersistentVolumeClaim::delete_namespaced_persistent_volume_claim(
+                    obj.name.as_str(),
+                    ns.as_str(),
+                    options,
+                )?
+                .0;
+                send_delete::<api::PersistentVolumeClaim>(env, writer, req, dry_run)
             }
             ObjType::Pod { .. } => {
                 let req =
                     api::Pod::delete_namespaced_pod(obj.name.as_str(), ns.as_str(), options)?.0;
-                send_delete::<api::Pod>(env, writer, req)
+                send_delete::<api::Pod>(env, writer, req, dry_run)
             }
             ObjType::Crd { .. } => Err(ClickError::CommandError(
                 "Can't delete CRDs yet".to_string(),
@@ -156,7 +206,7 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api_apps::ReplicaSet>(env, writer, req)
+                send_delete::<api_apps::ReplicaSet>(env, writer, req, dry_run)
             }
             ObjType::StatefulSet => {
                 let req = api_apps::StatefulSet::delete_namespaced_stateful_set(
@@ -165,13 +215,13 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api_apps::StatefulSet>(env, writer, req)
+                send_delete::<api_apps::StatefulSet>(env, writer, req, dry_run)
             }
             ObjType::Secret => {
                 let req =
                     api::Secret::delete_namespaced_secret(obj.name.as_str(), ns.as_str(), options)?
                         .0;
-                send_delete::<api::Secret>(env, writer, req)
+                send_delete::<api::Secret>(env, writer, req, dry_run)
             }
             ObjType::Service => {
                 let req = api::Service::delete_namespaced_service(
@@ -180,7 +230,7 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api::Service>(env, writer, req)
+                send_delete::<api::Service>(env, writer, req, dry_run)
             }
             ObjType::StorageClass => {
                 clickwriteln!(
@@ -190,7 +240,7 @@ fn delete_obj(
                 );
                 let req =
                     api_storage::StorageClass::delete_storage_class(obj.name.as_str(), options)?.0;
-                send_delete::<api_storage::StorageClass>(env, writer, req)
+                send_delete::<api_storage::StorageClass>(env, writer, req, dry_run)
             }
             #[cfg(feature = "argorollouts")]
             ObjType::Rollout => Err(ClickError::CommandError(
@@ -200,21 +250,21 @@ fn delete_obj(
         None => match obj.typ {
             ObjType::Node => {
                 let req = api::Node::delete_node(obj.name.as_str(), options)?.0;
-                send_delete::<api::Node>(env, writer, req)
+                send_delete::<api::Node>(env, writer, req, dry_run)
             }
             ObjType::Namespace => {
                 let req = api::Namespace::delete_namespace(obj.name.as_str(), options)?.0;
-                send_delete::<api::Namespace>(env, writer, req)
+                send_delete::<api::Namespace>(env, writer, req, dry_run)
             }
             ObjType::PersistentVolume => {
                 let req =
                     api::PersistentVolume::delete_persistent_volume(obj.name.as_str(), options)?.0;
-                send_delete::<api::PersistentVolume>(env, writer, req)
+                send_delete::<api::PersistentVolume>(env, writer, req, dry_run)
             }
             ObjType::StorageClass => {
                 let req =
                     api_storage::StorageClass::delete_storage_class(obj.name.as_str(), options)?.0;
-                send_delete::<api_storage::StorageClass>(env, writer, req)
+                send_delete::<api_storage::StorageClass>(env, writer, req, dry_run)
             }
             _ => {
                 let msg = format!("Object {} has no namespace. Cannot delete", obj.name());
@@ -228,15 +278,19 @@ fn confirm_delete(
     env: &Env,
     obj: &KObj,
     options: DeleteOptional,
+    dry_run: Option<DryRun>,
     writer: &mut ClickWriter,
 ) -> Result<(), ClickError> {
     let name = obj.name();
+    // a dry run doesn't change anything, so there's nothing to confirm
+    if dry_run.is_some() || !env.needs_confirmation() {
+        return delete_obj(env, writer, obj, options, dry_run);
+    }
     clickwrite!(writer, "Delete {} {} [y/N]? ", obj.type_str(), name);
-    io::stdout().flush().expect("Could not flush stdout");
-    let mut conf = String::new();
-    if io::stdin().read_line(&mut conf).is_ok() {
+    writer.flush().expect("Could not flush output");
+    if let Ok(conf) = env.read_response() {
         if conf.trim() == "y" || conf.trim() == "yes" {
-            delete_obj(env, writer, obj, options)?;
+            delete_obj(env, writer, obj, options, dry_run)?;
         } else {
             clickwriteln!(writer, "Not deleting");
         }
@@ -249,12 +303,13 @@ fn confirm_delete(
 command!(
     Delete,
     "delete",
-    "Delete the active object (will ask for confirmation)",
+    "Delete the active object (will ask for confirmation, unless the confirm option says not to)",
     |clap: ClapCommand<'static>| {
         clap.arg(
             Arg::new("grace")
                 .short('g')
                 .long("gracePeriod")
+                .value_name("SECONDS")
                 .help("The duration in seconds before the object should be deleted.")
                 .validator(valid_u32)
                 .takes_value(true),
@@ -290,6 +345,18 @@ command!(
                 .conflicts_with("grace")
                 .conflicts_with("now"),
         )
+        .arg(dry_run_arg())
+        .after_help(
+            "Examples:
+  # delete the selected pod, giving it 5 seconds to shut down
+  delete -g 5
+
+  # delete a deployment, but leave its pods running
+  delete --cascade orphan
+
+  # see what deleting the selected range would do, without deleting anything
+  delete --dry-run=server",
+        )
     },
     vec!["delete"],
     noop_complete!(),
@@ -311,16 +378,24 @@ command!(
             uppercase_first(lower.as_str())
         });
 
+        let dry_run = DryRun::from_matches(&matches);
+        let dry_run_all = ["All".to_string()];
+
         let delete_options: DeleteOptional = DeleteOptional {
             propagation_policy: propagation_policy.as_deref(),
             grace_period_seconds: grace,
+            dry_run: if dry_run == Some(DryRun::Server) {
+                Some(&dry_run_all)
+            } else {
+                None
+            },
             ..Default::default()
         };
 
         env.apply_to_selection(
             writer,
             Some(&env.click_config.range_separator),
-            |obj, writer| confirm_delete(env, obj, delete_options, writer),
+            |obj, writer| confirm_delete(env, obj, delete_options, dry_run, writer),
         )
     }
 );
@@ -16,7 +16,7 @@ use clap::{Arg, Command as ClapCommand};
 use rustyline::completion::Pair as RustlinePair;
 
 use crate::{
-    command::command_def::{exec_match, start_clap, Cmd},
+    command::command_def::{exec_match, output_arg, start_clap, Cmd},
     completer,
     env::Env,
     output::ClickWriter,
@@ -58,6 +58,41 @@ command!(
                 .takes_value(true)
                 .possible_values(&["true", "false"]),
         )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help(
+                    "Show everything, like annotations, all conditions, tolerations, volumes \
+                     and each container's resources and mounts. Without this only the most \
+                     useful fields are shown",
+                )
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("reveal")
+                .long("reveal")
+                .help(
+                    "Decode and show the values of secrets, rather than just their sizes. What's \
+                     shown isn't kept for the grep command",
+                )
+                .takes_value(false),
+        )
+        .arg(output_arg())
+        .after_help(
+            "Examples:
+  # describe the selected object, including its events
+  describe
+
+  # show everything, like annotations and tolerations, without the events
+  describe -v --events false
+
+  # print the object's node name
+  describe -o json .spec.nodeName
+
+  # show the decoded values of the selected secret
+  describe --reveal",
+        )
     },
     vec!["describe"],
     noop_complete!(),
@@ -67,6 +102,9 @@ command!(
         if let Some(b) = matches.value_of("include_events") {
             include_events = b.parse().unwrap(); // safe, validated to be true/false
         }
+        if matches.is_present("reveal") {
+            env.mark_output_sensitive();
+        }
         env.apply_to_selection(
             writer,
             Some(&env.click_config.range_separator),
@@ -74,7 +112,7 @@ command!(
                 obj.describe(&matches, env, writer)?;
                 if include_events {
                     clickwriteln!(writer, "\nEvents:");
-                    print_events_for_obj(obj, env, writer)
+                    print_events_for_obj(obj, env, None, writer)
                 } else {
                     Ok(())
                 }
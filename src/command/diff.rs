@@ -0,0 +1,184 @@
+// Copyright 2022 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use humantime::parse_duration;
+use rustyline::completion::Pair as RustlinePair;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    command::valid_duration,
+    completer,
+    diff::{unified_diff, DiffLine},
+    env::Env,
+    error::ClickError,
+    kobj::KObj,
+    output::ClickWriter,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+const DIFF_CONTEXT: usize = 3;
+
+// fetch the object as yaml, without fields that change on every write and just add noise
+fn snapshot(obj: &KObj, env: &Env) -> Result<String, ClickError> {
+    let mut value = env.run_on_context(|c| c.get_value(obj.read_request()?))?;
+    if let Some(metadata) = value.get_mut("metadata").and_then(|m| m.as_object_mut()) {
+        metadata.remove("managedFields");
+    }
+    Ok(serde_yaml::to_string(&value)?)
+}
+
+fn snapshot_key(obj: &KObj) -> String {
+    format!(
+        "{}/{}/{}",
+        obj.type_str(),
+        obj.namespace.as_deref().unwrap_or(""),
+        obj.name()
+    )
+}
+
+fn print_diff(
+    old: &str,
+    new: &str,
+    obj: &KObj,
+    env: &Env,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let lines = unified_diff(old, new, DIFF_CONTEXT);
+    if lines.is_empty() {
+        clickwriteln!(writer, "No changes to {} {}", obj.type_str(), obj.name());
+        return Ok(());
+    }
+    let color = env.colors() && writer.is_tty();
+    clickwriteln!(writer, "--- {} (before)", obj.name());
+    clickwriteln!(writer, "+++ {} (after)", obj.name());
+    for line in lines.iter() {
+        match line {
+            DiffLine::Hunk(hunk) if color => {
+                clickwriteln!(writer, "{}", env.styles.diff_hunk(hunk))
+            }
+            DiffLine::Hunk(hunk) => clickwriteln!(writer, "{}", hunk),
+            DiffLine::Context(text) => clickwriteln!(writer, " {}", text),
+            DiffLine::Add(text) if color => {
+                clickwriteln!(writer, "{}", env.styles.diff_add(&format!("+{}", text)))
+            }
+            DiffLine::Add(text) => clickwriteln!(writer, "+{}", text),
+            DiffLine::Remove(text) if color => {
+                clickwriteln!(writer, "{}", env.styles.diff_remove(&format!("-{}", text)))
+            }
+            DiffLine::Remove(text) => clickwriteln!(writer, "-{}", text),
+        }
+    }
+    Ok(())
+}
+
+// sleep for dur, returning false if interrupted by ctrl-c
+fn wait_for(dur: Duration, env: &Env) -> bool {
+    let start = Instant::now();
+    env.ctrlcbool.store(false, Ordering::SeqCst);
+    while start.elapsed() < dur {
+        if env.ctrlcbool.load(Ordering::SeqCst) {
+            env.ctrlcbool.store(false, Ordering::SeqCst);
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    true
+}
+
+command!(
+    Diff,
+    "diff",
+    "Show what changed in the active object(s). The first run saves a snapshot of the object, \
+     and each following run shows a diff of the yaml against the last snapshot and then updates \
+     it. With --wait, take a snapshot, wait for the given time, and show what changed.",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("wait")
+                .short('w')
+                .long("wait")
+                .help(
+                    "Take a snapshot, wait this long, and then show the changes \
+                     (i.e. 30s, 5m, 1h). Ctrl-C stops waiting.",
+                )
+                .validator(valid_duration)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("reset")
+                .short('r')
+                .long("reset")
+                .help("Discard any saved snapshot and take a new one")
+                .takes_value(false),
+        )
+    },
+    vec!["diff"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let wait = matches.value_of("wait").map(|w| parse_duration(w).unwrap()); // safe, validated above
+        let reset = matches.is_present("reset");
+        if let Some(wait) = wait {
+            let mut snapshots = vec![];
+            env.apply_to_selection(writer, None, |obj, _| {
+                snapshots.push((obj.clone(), snapshot(obj, env)?));
+                Ok(())
+            })?;
+            clickwriteln!(
+                writer,
+                "Waiting {} for changes (Ctrl-C to stop)",
+                humantime::format_duration(wait)
+            );
+            writer.flush()?;
+            if !wait_for(wait, env) {
+                clickwriteln!(writer, "Stopped waiting, showing changes so far");
+            }
+            for (obj, old) in snapshots.iter() {
+                let new = snapshot(obj, env)?;
+                print_diff(old, &new, obj, env, writer)?;
+                env.replace_snapshot(&snapshot_key(obj), new);
+            }
+            Ok(())
+        } else {
+            env.apply_to_selection(
+                writer,
+                Some(&env.click_config.range_separator),
+                |obj, writer| {
+                    let key = snapshot_key(obj);
+                    if reset {
+                        env.remove_snapshot(&key);
+                    }
+                    let new = snapshot(obj, env)?;
+                    match env.replace_snapshot(&key, new.clone()) {
+                        Some(old) => print_diff(&old, &new, obj, env, writer),
+                        None => {
+                            clickwriteln!(
+                                writer,
+                                "Saved a snapshot of {} {}, run diff again to see what changed",
+                                obj.type_str(),
+                                obj.name()
+                            );
+                            Ok(())
+                        }
+                    }
+                },
+            )
+        }
+    }
+);
@@ -0,0 +1,276 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use rustyline::completion::Pair as RustlinePair;
+use serde_json::Value;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    completer,
+    env::{Env, ObjectSelection},
+    error::ClickError,
+    kobj::{KObj, ObjType},
+    output::ClickWriter,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The files to write for a configmap or secret, as key and contents. Secret values, and the
+/// binaryData of configmaps, are base64 encoded, so they're decoded
+fn data_files(value: &Value, is_secret: bool) -> Result<Vec<(String, Vec<u8>)>, ClickError> {
+    let mut files = vec![];
+    let encoded = |field: &str| value.pointer(field).and_then(Value::as_object);
+    let (plain, base64_fields) = if is_secret {
+        (None, encoded("/data"))
+    } else {
+        (encoded("/data"), encoded("/binaryData"))
+    };
+    for (key, val) in plain.into_iter().flatten() {
+        files.push((
+            key.clone(),
+            val.as_str().unwrap_or_default().as_bytes().to_vec(),
+        ));
+    }
+    for (key, val) in base64_fields.into_iter().flatten() {
+        let decoded = base64::decode(val.as_str().unwrap_or_default()).map_err(|e| {
+            ClickError::CommandError(format!("Could not decode the value of {}: {}", key, e))
+        })?;
+        files.push((key.clone(), decoded));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The path to write key to in dir. Kubernetes only allows letters, digits, -, _ and . in keys,
+/// but check there's no way out of dir anyway
+fn key_path(dir: &Path, key: &str) -> Result<PathBuf, ClickError> {
+    if key.is_empty() || key == "." || key == ".." || key.contains(['/', '\\']) {
+        return Err(ClickError::CommandError(format!(
+            "Can't write key {} to a file",
+            key
+        )));
+    }
+    Ok(dir.join(key))
+}
+
+/// Make files and directories only the user can read, for secrets
+#[cfg(unix)]
+fn make_private(options: &mut fs::OpenOptions, builder: &mut fs::DirBuilder) {
+    use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+    options.mode(0o600);
+    builder.mode(0o700);
+}
+
+#[cfg(not(unix))]
+fn make_private(_options: &mut fs::OpenOptions, _builder: &mut fs::DirBuilder) {}
+
+/// Write contents to a new file at path. With force, a file already there is removed first, but
+/// not a symlink, which could point somewhere that shouldn't get the contents
+fn write_new(
+    options: &fs::OpenOptions,
+    path: &Path,
+    contents: &[u8],
+    force: bool,
+) -> Result<(), ClickError> {
+    if force {
+        match fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                return Err(ClickError::CommandError(format!(
+                    "{} is a symlink, not overwriting it",
+                    path.display()
+                )))
+            }
+            Ok(_) => fs::remove_file(path)?,
+            Err(_) => {}
+        }
+    }
+    options.open(path)?.write_all(contents)?;
+    Ok(())
+}
+
+fn dump_obj(
+    env: &Env,
+    obj: &KObj,
+    dir: &Path,
+    force: bool,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let is_secret = match obj.typ {
+        ObjType::ConfigMap => false,
+        ObjType::Secret => true,
+        _ => {
+            return Err(ClickError::CommandError(format!(
+                "{} is a {}, only ConfigMaps and Secrets can be dumped",
+                obj.name(),
+                obj.type_str()
+            )))
+        }
+    };
+    let value = env.run_on_context(|c| c.get_value(obj.read_request()?))?;
+    let files = data_files(&value, is_secret)?;
+    let paths = files
+        .iter()
+        .map(|(key, _)| key_path(dir, key))
+        .collect::<Result<Vec<PathBuf>, ClickError>>()?;
+    if !force {
+        if let Some(existing) = paths.iter().find(|path| path.exists()) {
+            return Err(ClickError::CommandError(format!(
+                "{} already exists, use --force to overwrite it",
+                existing.display()
+            )));
+        }
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    if is_secret {
+        make_private(&mut options, &mut builder);
+    }
+    builder.create(dir)?;
+    for (path, (_, contents)) in paths.iter().zip(files.iter()) {
+        write_new(&options, path, contents, force)?;
+    }
+    clickwriteln!(
+        writer,
+        "Wrote {} key{} of {} {} to {}",
+        files.len(),
+        if files.len() == 1 { "" } else { "s" },
+        obj.type_str(),
+        obj.name(),
+        dir.display()
+    );
+    Ok(())
+}
+
+command!(
+    Dump,
+    "dump",
+    "Write each key of the active ConfigMap(s) or Secret(s) to a file in a directory",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("dir")
+                .help("The directory to write to, which is created if it doesn't exist")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Overwrite files that already exist")
+                .takes_value(false),
+        )
+        .after_help(
+            "Each key is written to a file named after it, so the contents can be compared with \
+the files they were made from. Secret values (and binaryData of ConfigMaps) are decoded. With a \
+range selected, each object's keys go in a directory named after it, inside the given one. \
+Directories made for secrets, and the files written, can only be read by you (on unix).
+
+Examples:
+  # write the selected configmap's keys to /tmp/config, to compare with the repo's version
+  dump /tmp/config
+
+  # write the keys of the selected secrets to ~/secrets/NAME, replacing earlier dumps
+  dump --force ~/secrets",
+        )
+    },
+    vec!["dump"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let dir = matches.value_of("dir").unwrap(); // required
+        let dir = match (dir.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(dir),
+        };
+        let force = matches.is_present("force");
+        match env.current_selection() {
+            ObjectSelection::Single(obj) => dump_obj(env, obj, &dir, force, writer),
+            ObjectSelection::Range(_) => env.apply_to_selection(
+                writer,
+                Some(&env.click_config.range_separator),
+                |obj, writer| dump_obj(env, obj, &dir.join(obj.name()), force, writer),
+            ),
+            ObjectSelection::None => Err(ClickError::CommandError(
+                "No objects currently active".to_string(),
+            )),
+        }
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_data_files() {
+        let configmap = json!({
+            "data": { "app.yaml": "port: 8080\n" },
+            "binaryData": { "logo.png": "iVBO" },
+        });
+        assert_eq!(
+            data_files(&configmap, false).unwrap(),
+            vec![
+                ("app.yaml".to_string(), b"port: 8080\n".to_vec()),
+                ("logo.png".to_string(), vec![0x89, 0x50, 0x4e]),
+            ]
+        );
+        let secret = json!({ "data": { "password": "aHVudGVyMg==" } });
+        assert_eq!(
+            data_files(&secret, true).unwrap(),
+            vec![("password".to_string(), b"hunter2".to_vec())]
+        );
+        assert!(data_files(&json!({ "data": { "bad": "!!" } }), true).is_err());
+        assert!(data_files(&json!({}), true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_write_new() {
+        let dir = tempdir::TempDir::new("click-dump").unwrap();
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        let mut builder = fs::DirBuilder::new();
+        make_private(&mut options, &mut builder);
+        let path = dir.path().join("password");
+        write_new(&options, &path, b"hunter2", false).unwrap();
+        assert!(write_new(&options, &path, b"hunter3", false).is_err());
+        write_new(&options, &path, b"hunter3", true).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"hunter3");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            let link = dir.path().join("link");
+            std::os::unix::fs::symlink(&path, &link).unwrap();
+            assert!(write_new(&options, &link, b"elsewhere", true).is_err());
+            assert_eq!(fs::read(&path).unwrap(), b"hunter3");
+        }
+    }
+
+    #[test]
+    fn test_key_path() {
+        let dir = PathBuf::from("/tmp/config");
+        assert!(key_path(&dir, "../escape").is_err());
+        assert!(key_path(&dir, "..").is_err());
+        assert_eq!(key_path(&dir, "app.yaml").unwrap(), dir.join("app.yaml"));
+    }
+}
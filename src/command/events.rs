@@ -13,20 +13,21 @@
 // limitations under the License.
 
 use chrono::{offset::Utc, DateTime};
-use clap::Command as ClapCommand;
+use clap::{Arg, Command as ClapCommand};
 use comfy_table::{Cell, Table};
-use k8s_openapi::ListOptional;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::WatchEvent;
 use k8s_openapi::{api::core::v1 as api, http::Request, List};
+use k8s_openapi::{ListOptional, WatchOptional};
+use regex::Regex;
 use rustyline::completion::Pair as RustlinePair;
 
-use crate::command::format_duration;
 use crate::{
     command::command_def::{exec_match, start_clap, Cmd},
-    command::time_since,
+    command::stream_watch,
     completer,
     env::{Env, ObjectSelection},
     error::ClickError,
-    kobj::KObj,
+    kobj::{KObj, ObjType},
     output::ClickWriter,
 };
 
@@ -37,7 +38,7 @@ use std::io::Write;
 
 // try and get a timestamp for the event. we look in last_timestamp, and if that's not present, just
 // event_time
-fn get_event_ts(event: &api::Event) -> Option<DateTime<Utc>> {
+pub fn get_event_ts(event: &api::Event) -> Option<DateTime<Utc>> {
     match &event.last_timestamp {
         Some(ts) => Some(ts.0),
         None => event.event_time.as_ref().map(|ts| ts.0),
@@ -53,42 +54,154 @@ fn event_cmp(e1: &api::Event, e2: &api::Event) -> cmp::Ordering {
     }
 }
 
+/// Filters on the events to print, from the flags of the events command
+#[derive(Default)]
+pub struct EventFilter {
+    /// only events of this type (like Warning), case-insensitive
+    type_: Option<String>,
+    /// only events whose reason matches this
+    reason: Option<Regex>,
+    /// only events for objects of this kind, case-insensitive
+    kind: Option<String>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &api::Event) -> bool {
+        let type_matches = self.type_.as_ref().is_none_or(|type_| {
+            event
+                .type_
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(type_))
+        });
+        let reason_matches = self
+            .reason
+            .as_ref()
+            .is_none_or(|reason| reason.is_match(event.reason.as_deref().unwrap_or("")));
+        let kind_matches = self.kind.as_ref().is_none_or(|kind| {
+            event
+                .involved_object
+                .kind
+                .as_deref()
+                .is_some_and(|k| k.eq_ignore_ascii_case(kind))
+        });
+        type_matches && reason_matches && kind_matches
+    }
+}
+
+/// Split the value of --for into a kind and name. The kind is optional
+fn parse_for(for_obj: &str) -> (Option<&str>, &str) {
+    match for_obj.split_once('/') {
+        Some((kind, name)) => (Some(kind), name),
+        None => (None, for_obj),
+    }
+}
+
+/// The kind of obj as it appears in the involvedObject of its events. Custom resources are only
+/// known by their plural type, so there's no kind for them
+fn involved_kind(obj: &KObj) -> Option<&str> {
+    match obj.typ {
+        ObjType::Crd { .. } => None,
+        _ => Some(obj.type_str()),
+    }
+}
+
+/// A field selector for events about objects in namespace, of kind and called name, for each of
+/// those that's given. Selecting on the server means only the events wanted are sent, rather than
+/// all the events in a namespace
+fn involved_object_selector(
+    namespace: Option<&str>,
+    kind: Option<&str>,
+    name: Option<&str>,
+) -> Option<String> {
+    let mut selectors = vec![];
+    if let Some(ns) = namespace {
+        selectors.push(format!("involvedObject.namespace={}", ns));
+    }
+    if let Some(kind) = kind {
+        selectors.push(format!("involvedObject.kind={}", kind));
+    }
+    if let Some(name) = name {
+        selectors.push(format!("involvedObject.name={}", name));
+    }
+    if selectors.is_empty() {
+        None
+    } else {
+        Some(selectors.join(","))
+    }
+}
+
 pub fn print_events_for_obj(
     obj: &KObj,
     env: &Env,
+    sort_by: Option<&str>,
     writer: &mut ClickWriter,
 ) -> Result<(), ClickError> {
-    let mut opts: ListOptional = Default::default();
-    let mut include_namespace = false;
-    let (request, _body) = if let Some(ns) = obj.namespace.as_ref() {
-        let fs = format!(
-            "involvedObject.name={},involvedObject.namespace={}",
-            obj.name(),
-            ns
-        );
-        opts.field_selector = Some(&fs);
-        api::Event::list_namespaced_event(ns, opts)?
-    } else {
-        include_namespace = true;
-        let fs = format!("involvedObject.name={}", obj.name(),);
-        opts.field_selector = Some(&fs);
-        api::Event::list_event_for_all_namespaces(opts)?
+    print_filtered_events_for_obj(obj, env, sort_by, &EventFilter::default(), writer)
+}
+
+fn print_filtered_events_for_obj(
+    obj: &KObj,
+    env: &Env,
+    sort_by: Option<&str>,
+    filter: &EventFilter,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let fs = involved_object_selector(
+        obj.namespace.as_deref(),
+        involved_kind(obj),
+        Some(obj.name()),
+    );
+    let opts = ListOptional {
+        field_selector: fs.as_deref(),
+        ..Default::default()
+    };
+    // events about objects without a namespace, like nodes, can be in any namespace
+    let include_namespace = obj.namespace.is_none();
+    let (request, _body) = match obj.namespace.as_ref() {
+        Some(ns) => api::Event::list_namespaced_event(ns, opts)?,
+        None => api::Event::list_event_for_all_namespaces(opts)?,
     };
-    print_events(request, env, writer, include_namespace, false)
+    print_events(
+        request,
+        env,
+        writer,
+        include_namespace,
+        false,
+        sort_by,
+        filter,
+    )
 }
 
-fn print_events_no_obj(env: &Env, writer: &mut ClickWriter) -> Result<(), ClickError> {
-    let mut opts: ListOptional = Default::default();
+/// Print the events in the current namespace, or in all namespaces if there isn't one. If name is
+/// given, only print the events for objects with that name
+fn print_events_no_obj(
+    env: &Env,
+    sort_by: Option<&str>,
+    name: Option<&str>,
+    filter: &EventFilter,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
     let mut include_namespace = false;
+    let fs = involved_object_selector(env.namespace.as_deref(), None, name);
+    let opts = ListOptional {
+        field_selector: fs.as_deref(),
+        ..Default::default()
+    };
     let (request, _body) = if let Some(ns) = env.namespace.as_ref() {
-        let fs = format!("involvedObject.namespace={}", ns);
-        opts.field_selector = Some(&fs);
         api::Event::list_namespaced_event(ns, opts)?
     } else {
         include_namespace = true;
         api::Event::list_event_for_all_namespaces(opts)?
     };
-    print_events(request, env, writer, include_namespace, true)
+    print_events(
+        request,
+        env,
+        writer,
+        include_namespace,
+        true,
+        sort_by,
+        filter,
+    )
 }
 
 fn print_events(
@@ -97,10 +210,24 @@ fn print_events(
     writer: &mut ClickWriter,
     include_namespace: bool,
     include_object: bool,
+    sort_by: Option<&str>,
+    filter: &EventFilter,
 ) -> Result<(), ClickError> {
-    let mut event_list: List<api::Event> = env.run_on_context(|c| c.execute_list(request))?;
+    let mut event_list: List<api::Event> = match sort_by {
+        Some(path) => {
+            let mut value = env.run_on_context(|c| c.get_value(request))?;
+            crate::jsonpath::sort_items(path, &mut value)?;
+            env.run_on_context(|c| c.list_from_value(value))?
+        }
+        None => {
+            let mut event_list: List<api::Event> =
+                env.run_on_context(|c| c.execute_list(request))?;
+            event_list.items.sort_by(event_cmp);
+            event_list
+        }
+    };
+    event_list.items.retain(|event| filter.matches(event));
     if !event_list.items.is_empty() {
-        event_list.items.sort_by(event_cmp);
         let mut table = Table::new();
         let mut titles = if include_namespace {
             vec!["Namespace"]
@@ -123,16 +250,23 @@ fn print_events(
                 ));
             }
             let timestr = match get_event_ts(event) {
-                Some(ts) => format_duration(time_since(ts)),
+                Some(ts) => crate::table::format_time(ts, env),
                 None => "unknown".to_string(),
             };
             row.push(Cell::new(&timestr));
-            row.push(Cell::new(event.type_.as_deref().unwrap_or("unknown")));
+            let type_cell = Cell::new(event.type_.as_deref().unwrap_or("unknown"));
+            let type_cell = match event.type_.as_deref() {
+                Some("Warning") if env.colors() => type_cell.fg(env.styles.warning_color()),
+                _ => type_cell,
+            };
+            row.push(type_cell);
             row.push(Cell::new(event.reason.as_deref().unwrap_or("unknown")));
             if include_object {
-                row.push(Cell::new(
-                    event.involved_object.name.as_deref().unwrap_or("unknown"),
-                ));
+                row.push(Cell::new(format!(
+                    "{}/{}",
+                    event.involved_object.kind.as_deref().unwrap_or("unknown"),
+                    event.involved_object.name.as_deref().unwrap_or("unknown")
+                )));
             }
             row.push(Cell::new(event.message.as_deref().unwrap_or("<none>")));
             table.add_row(row);
@@ -144,23 +278,278 @@ fn print_events(
     Ok(())
 }
 
+/// Print an event on one line, for events --watch. Warnings are shown in the warning color
+fn print_event_line(
+    event: &api::Event,
+    include_namespace: bool,
+    env: &Env,
+    writer: &mut ClickWriter,
+) {
+    let time = get_event_ts(event).unwrap_or_else(Utc::now);
+    let mut line = crate::table::format_timestamp(time, "%H:%M:%S", env);
+    if include_namespace {
+        line.push_str(&format!(
+            "  {}",
+            event.metadata.namespace.as_deref().unwrap_or("unknown")
+        ));
+    }
+    line.push_str(&format!(
+        "  {}/{}  {}  {}  {}",
+        event.involved_object.kind.as_deref().unwrap_or("unknown"),
+        event.involved_object.name.as_deref().unwrap_or("unknown"),
+        event.type_.as_deref().unwrap_or("unknown"),
+        event.reason.as_deref().unwrap_or("unknown"),
+        event.message.as_deref().unwrap_or("<none>")
+    ));
+    if env.colors() && writer.is_tty() && event.type_.as_deref() == Some("Warning") {
+        clickwriteln!(writer, "{}", env.styles.warning(&line));
+    } else {
+        clickwriteln!(writer, "{}", line);
+    }
+}
+
+/// Print events as they happen until ctrl-c is pressed. Only events in namespace (or in all
+/// namespaces if it's None) and for objects of kind and called name (if given) are watched
+fn watch_events(
+    env: &Env,
+    namespace: Option<&str>,
+    kind: Option<&str>,
+    name: Option<&str>,
+    filter: &EventFilter,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let fs = involved_object_selector(namespace, kind, name);
+    let field_selector = fs.as_deref();
+
+    // the list is only fetched for its resource version, so the watch starts from now
+    let opts = ListOptional {
+        field_selector,
+        limit: Some(1),
+        ..Default::default()
+    };
+    let (request, _) = match namespace {
+        Some(ns) => api::Event::list_namespaced_event(ns, opts)?,
+        None => api::Event::list_event_for_all_namespaces(opts)?,
+    };
+    let list: List<api::Event> = env.run_on_context(|c| c.execute_list(request))?;
+    let opts = WatchOptional {
+        field_selector,
+        resource_version: list.metadata.resource_version.as_deref(),
+        ..Default::default()
+    };
+    let (request, _) = match namespace {
+        Some(ns) => api::Event::watch_namespaced_event(ns, opts)?,
+        None => api::Event::watch_event_for_all_namespaces(opts)?,
+    };
+
+    clickwriteln!(writer, "Watching for events, ctrl-c to stop");
+    writer.flush()?;
+    stream_watch(env, request, |event: Option<WatchEvent<api::Event>>| {
+        match event {
+            // an event that happens again is modified, with a new count and timestamp
+            Some(WatchEvent::Added(event)) | Some(WatchEvent::Modified(event)) => {
+                if filter.matches(&event) {
+                    print_event_line(&event, namespace.is_none(), env, writer);
+                }
+            }
+            Some(_) => {}
+            None => writer.flush()?,
+        }
+        Ok(true)
+    })?;
+    Ok(())
+}
+
 command!(
     Events,
     "events",
-    "Get events for the active pod",
-    crate::command::command_def::identity,
+    "Get events for the active object(s), or the current namespace if nothing is active",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("sort-by")
+                .long("sort-by")
+                .value_name("PATH")
+                .help(
+                    "Sort by the specified path into the events instead of by time (example: \
+                     --sort-by .count)",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("type")
+                .long("type")
+                .value_name("TYPE")
+                .help("Only print events of this type, like Warning or Normal (case-insensitive)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("reason")
+                .long("reason")
+                .value_name("REGEX")
+                .help(
+                    "Only print events whose reason matches REGEX (example: --reason 'Fail|Kill')",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("for")
+                .long("for")
+                .value_name("KIND/NAME")
+                .help(
+                    "Print the events for the objects named NAME (of kind KIND, if given) in the \
+                     current namespace, instead of for the selected object (example: --for \
+                     deployment/web)",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("watch")
+                .short('w')
+                .long("watch")
+                .help(
+                    "Print new events as they happen, until ctrl-c is pressed. Warnings are shown \
+                     in the warning color",
+                )
+                .conflicts_with("sort-by")
+                .takes_value(false),
+        )
+        .after_help(
+            "Examples:
+  # print the events of the selected object
+  events
+
+  # print the events sorted by how many times they happened
+  events --sort-by .count
+
+  # print the warnings in the current namespace about scheduling or pulling images
+  clear && events --type warning --reason 'Scheduling|Pull'
+
+  # print the events for a deployment without selecting it
+  events --for deployment/web
+
+  # follow the warnings in the current namespace during a rollout
+  clear && events --watch --type warning",
+        )
+    },
     vec!["events"],
     noop_complete!(),
     no_named_complete!(),
-    |_matches, env, writer| {
-        if let ObjectSelection::None = env.current_selection() {
-            print_events_no_obj(env, writer)
+    |matches, env, writer| {
+        let sort_by = matches.value_of("sort-by");
+        let reason = match matches.value_of("reason") {
+            Some(pattern) => Some(Regex::new(pattern).map_err(|e| {
+                ClickError::CommandError(format!("Invalid reason regex {}: {}", pattern, e))
+            })?),
+            None => None,
+        };
+        let (kind, name) = match matches.value_of("for").map(parse_for) {
+            Some((kind, name)) => (kind, Some(name)),
+            None => (None, None),
+        };
+        let filter = EventFilter {
+            type_: matches.value_of("type").map(str::to_string),
+            reason,
+            kind: kind.map(str::to_string),
+        };
+        if matches.is_present("watch") {
+            match env.current_selection() {
+                ObjectSelection::Single(obj) if name.is_none() => watch_events(
+                    env,
+                    obj.namespace.as_deref(),
+                    involved_kind(obj),
+                    Some(obj.name()),
+                    &filter,
+                    writer,
+                ),
+                ObjectSelection::Range(_) if name.is_none() => Err(ClickError::CommandError(
+                    "Can't watch the events of a range, select a single object".to_string(),
+                )),
+                _ => watch_events(env, env.namespace.as_deref(), None, name, &filter, writer),
+            }
+        } else if name.is_some() || matches!(env.current_selection(), ObjectSelection::None) {
+            print_events_no_obj(env, sort_by, name, &filter, writer)
         } else {
             env.apply_to_selection(
                 writer,
                 Some(&env.click_config.range_separator),
-                |obj, writer| print_events_for_obj(obj, env, writer),
+                |obj, writer| print_filtered_events_for_obj(obj, env, sort_by, &filter, writer),
             )
         }
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(type_: &str, reason: &str, kind: &str) -> api::Event {
+        serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "web-1.abc" },
+            "involvedObject": { "kind": kind, "name": "web-1" },
+            "type": type_,
+            "reason": reason,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let warning = event("Warning", "FailedScheduling", "Pod");
+        let normal = event("Normal", "Pulled", "Pod");
+        assert!(EventFilter::default().matches(&warning));
+
+        let filter = EventFilter {
+            type_: Some("warning".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&warning));
+        assert!(!filter.matches(&normal));
+
+        let filter = EventFilter {
+            reason: Some(Regex::new("Fail|Kill").unwrap()),
+            kind: Some("pod".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&warning));
+        assert!(!filter.matches(&normal));
+        assert!(!filter.matches(&event("Warning", "FailedCreate", "ReplicaSet")));
+    }
+
+    #[test]
+    fn test_involved_object_selector() {
+        let deployment = KObj {
+            name: "web".to_string(),
+            namespace: Some("prod".to_string()),
+            typ: ObjType::Deployment,
+        };
+        assert_eq!(
+            involved_object_selector(
+                deployment.namespace.as_deref(),
+                involved_kind(&deployment),
+                Some(deployment.name())
+            )
+            .as_deref(),
+            Some("involvedObject.namespace=prod,involvedObject.kind=Deployment,involvedObject.name=web")
+        );
+        let crd = KObj {
+            name: "web".to_string(),
+            namespace: None,
+            typ: ObjType::Crd {
+                _type: "certificates".to_string(),
+                group_version: "cert-manager.io/v1".to_string(),
+            },
+        };
+        assert_eq!(involved_kind(&crd), None);
+        assert_eq!(
+            involved_object_selector(None, None, Some("web")).as_deref(),
+            Some("involvedObject.name=web")
+        );
+        assert_eq!(involved_object_selector(None, None, None), None);
+    }
+
+    #[test]
+    fn test_parse_for() {
+        assert_eq!(parse_for("deployment/web"), (Some("deployment"), "web"));
+        assert_eq!(parse_for("web-1"), (None, "web-1"));
+    }
+}
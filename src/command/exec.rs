@@ -26,14 +26,83 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use std::process::Command;
+use std::sync::atomic::Ordering;
 
 /// a clap validator for boolean
 fn valid_bool(s: &str) -> Result<(), String> {
     s.parse::<bool>().map(|_| ()).map_err(|e| e.to_string())
 }
 
+/// a clap validator for --stdin, which also allows the t of -it
+fn valid_stdin(s: &str) -> Result<(), String> {
+    if s == "t" {
+        Ok(())
+    } else {
+        valid_bool(s)
+    }
+}
+
+/// Was -it or -ti given, like with kubectl. Since -i and -t take optional values, clap sees these
+/// as -i with the value t, or -t with the value i
+fn is_it(stdin: Option<&str>, terminal: Option<&str>) -> bool {
+    stdin == Some("t") || terminal == Some("i")
+}
+
+/// Should the command get a tty: if asked with -T or -it, and otherwise if click's stdin is a
+/// terminal, since kubectl can't make a tty without one
+fn want_tty(tty_val: Option<Option<&str>>, it: bool, stdin_is_terminal: bool) -> bool {
+    match tty_val {
+        _ if it => true,
+        Some(Some(v)) => v.parse::<bool>().unwrap(), // already validated
+        Some(None) => true,
+        None => stdin_is_terminal,
+    }
+}
+
+/// Run kubectl in the foreground, with click's stdin and stdout, and wait for it to exit
+pub fn run_kubectl(
+    env: &Env,
+    command: &mut Command,
+    kubectl_binary: &str,
+) -> Result<(), ClickError> {
+    let status = command.status();
+    // with a tty, kubectl puts the terminal in raw mode and passes on ctrl-c and window size
+    // changes itself. without one, a ctrl-c goes to both kubectl and click, and shouldn't stop
+    // the next command
+    env.ctrlcbool.store(false, Ordering::SeqCst);
+    match status {
+        Ok(s) => {
+            if s.success() {
+                Ok(())
+            } else {
+                Err(ClickError::CommandError(
+                    "kubectl exited abnormally".to_string(),
+                ))
+            }
+        }
+        Err(e) => {
+            if let io::ErrorKind::NotFound = e.kind() {
+                let msg = if kubectl_binary.starts_with('/') {
+                    format!(
+                        "Could not find kubectl binary: '{}'. Does it exist?",
+                        kubectl_binary
+                    )
+                } else {
+                    format!(
+                        "Could not find kubectl binary: '{}'. Is it in your PATH?",
+                        kubectl_binary
+                    )
+                };
+                Err(ClickError::CommandError(msg))
+            } else {
+                Err(ClickError::Io(e))
+            }
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn do_exec(
     env: &Env,
@@ -96,35 +165,7 @@ fn do_exec(
         } else {
             command.arg("--").args(cmd)
         };
-        match command.status() {
-            Ok(s) => {
-                if s.success() {
-                    Ok(())
-                } else {
-                    Err(ClickError::CommandError(
-                        "kubectl exited abnormally".to_string(),
-                    ))
-                }
-            }
-            Err(e) => {
-                if let io::ErrorKind::NotFound = e.kind() {
-                    let msg = if kubectl_binary.starts_with('/') {
-                        format!(
-                            "Could not find kubectl binary: '{}'. Does it exist?",
-                            kubectl_binary
-                        )
-                    } else {
-                        format!(
-                            "Could not find kubectl binary: '{}'. Is it in your PATH?",
-                            kubectl_binary
-                        )
-                    };
-                    Err(ClickError::CommandError(msg))
-                } else {
-                    Err(ClickError::Io(e))
-                }
-            }
-        }
+        run_kubectl(env, command, kubectl_binary)
     }
 }
 
@@ -144,6 +185,7 @@ command!(
             Arg::new("container")
                 .short('c')
                 .long("container")
+                .value_name("CONTAINER")
                 .help("Exec in the specified container")
                 .takes_value(true)
         )
@@ -151,6 +193,7 @@ command!(
             Arg::new("terminal")
                 .short('t')
                 .long("terminal")
+                .value_name("TERMINAL")
                 .help(
                     "Run the command in a new terminal.  With --terminal ARG, ARG is used as the \
                      terminal command, otherwise the default is used ('set terminal <value>' to \
@@ -164,7 +207,10 @@ command!(
             Arg::new("tty")
                 .short('T')
                 .long("tty")
-                .help("If stdin is a TTY. Contrary to kubectl, this defaults to TRUE")
+                .help(
+                    "Allocate a TTY for the command. Contrary to kubectl, this defaults to TRUE \
+                     when click's stdin is a terminal"
+                )
                 .validator(valid_bool)
                 .takes_value(true)
                 .min_values(0)
@@ -174,9 +220,26 @@ command!(
                 .short('i')
                 .long("stdin")
                 .help("Pass stdin to the container. Contrary to kubectl, this defaults to TRUE")
-                .validator(valid_bool)
+                .validator(valid_stdin)
                 .takes_value(true)
                 .min_values(0)
+        )
+        .after_help(
+            "Examples:
+  # open a shell in the selected pod
+  exec bash
+
+  # the same, with kubectl's flags (-it is accepted, though it's the default)
+  exec -it bash
+
+  # run a command in the sidecar container (flags for the command go after --)
+  exec -c sidecar -- ls -l /data
+
+  # open a shell in a new terminal window for each pod of the selected range
+  exec -t bash
+
+With a TTY, the command runs like it does with kubectl: the terminal is put in raw mode, and \
+ctrl-c, ctrl-z and window size changes go to the command in the container."
         ),
     vec!["exec"],
     noop_complete!(),
@@ -191,17 +254,16 @@ command!(
             ClickError::CommandError("Need an active context in order to exec.".to_string())
         })?;
         let cmd: Vec<&str> = matches.values_of("command").unwrap().collect(); // safe as required
-        let tty = if matches.is_present("tty") {
-            if let Some(v) = matches.value_of("tty") {
-                // already validated
-                v.parse::<bool>().unwrap()
-            } else {
-                true
-            }
+        let it = is_it(matches.value_of("stdin"), matches.value_of("terminal"));
+        let tty_val = if matches.is_present("tty") {
+            Some(matches.value_of("tty"))
         } else {
-            true
+            None
         };
-        let stdin = if matches.is_present("stdin") {
+        let tty = want_tty(tty_val, it, io::stdin().is_terminal());
+        let stdin = if it {
+            true
+        } else if matches.is_present("stdin") {
             if let Some(v) = matches.value_of("stdin") {
                 // already validated
                 v.parse::<bool>().unwrap()
@@ -229,8 +291,8 @@ command!(
                         &cmd,
                         it_arg,
                         &matches.value_of("container"),
-                        &matches.value_of("terminal"),
-                        matches.is_present("terminal"),
+                        &matches.value_of("terminal").filter(|_| !it),
+                        matches.is_present("terminal") && !it,
                         writer,
                     )
                 } else {
@@ -243,3 +305,22 @@ command!(
     },
     true // exec wants to gather up all it's training args into one big exec call
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tty() {
+        assert!(is_it(Some("t"), None));
+        assert!(is_it(None, Some("i")));
+        assert!(!is_it(Some("true"), None));
+        assert!(!is_it(None, Some("xterm -e")));
+
+        assert!(want_tty(None, false, true));
+        assert!(!want_tty(None, false, false));
+        assert!(want_tty(None, true, false));
+        assert!(want_tty(Some(None), false, false));
+        assert!(!want_tty(Some(Some("false")), false, true));
+    }
+}
@@ -0,0 +1,504 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use comfy_table::{presets, ContentArrangement, Table};
+use k8s_openapi::http::Request;
+use rustyline::completion::Pair as RustlinePair;
+use serde_json::{Map, Value};
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    completer,
+    env::Env,
+    error::ClickError,
+    output::ClickWriter,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Where the openapi schema of context is cached, in the directory click's config is in. Context
+/// names can be things like arns, so anything that might not be ok in a file name is replaced
+fn cache_path(config_dir: &Path, context: &str) -> PathBuf {
+    let file_name: String = context
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    config_dir
+        .join("openapi")
+        .join(format!("{}.json", file_name))
+}
+
+/// The openapi schema of the current context. It's fetched from the api server the first time, and
+/// after that read from the cache, so explain works without a connection to the cluster. With
+/// refresh, it's always fetched, to pick up new CRDs or an upgrade of the cluster
+fn load_schema(
+    env: &Env,
+    refresh: bool,
+    writer: &mut ClickWriter,
+) -> Result<Rc<Value>, ClickError> {
+    let context = match env.context.as_ref() {
+        Some(context) => context.name.clone(),
+        None => return Err(ClickError::CommandError("No active context".to_string())),
+    };
+    if !refresh {
+        if let Some(schema) = env.openapi_schema(&context) {
+            return Ok(schema);
+        }
+    }
+    let config_dir = env.click_config_path().parent().unwrap_or(Path::new("."));
+    let path = cache_path(config_dir, &context);
+    if !refresh && path.exists() {
+        let schema: Value = serde_json::from_reader(BufReader::new(File::open(&path)?))?;
+        let schema = Rc::new(schema);
+        env.set_openapi_schema(&context, schema.clone());
+        return Ok(schema);
+    }
+
+    clickwriteln!(writer, "Fetching the openapi schema of {}...", context);
+    let request = Request::get("/openapi/v2")
+        .body(vec![])
+        .map_err(|e| ClickError::RequestError(k8s_openapi::RequestError::Http(e)))?;
+    let schema = env.run_on_context(|c| c.get_value(request))?;
+    // not being able to cache it just means fetching it again next time
+    let cached = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, schema.to_string()));
+    if let Err(e) = cached {
+        clickwriteln!(
+            writer,
+            "{}",
+            env.styles.warning(&format!(
+                "Could not cache the schema in {}: {}",
+                path.display(),
+                e
+            ))
+        );
+    }
+    let schema = Rc::new(schema);
+    env.set_openapi_schema(&context, schema.clone());
+    Ok(schema)
+}
+
+/// How stable a version like v1, v2beta1 or v1alpha1 is, so the most stable one can be picked by
+/// default: GA before beta before alpha, then newest first
+fn version_rank(version: &str) -> (u8, u32, u32) {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let major_len = version.chars().take_while(char::is_ascii_digit).count();
+    let major = version[..major_len].parse().unwrap_or(0);
+    let rest = &version[major_len..];
+    let (stability, minor) = if let Some(minor) = rest.strip_prefix("beta") {
+        (1, minor)
+    } else if let Some(minor) = rest.strip_prefix("alpha") {
+        (0, minor)
+    } else {
+        (2, rest)
+    };
+    (stability, major, minor.parse().unwrap_or(0))
+}
+
+/// If name is kind, ignoring case, or its plural, like pods or networkpolicies
+fn kind_matches(kind: &str, name: &str) -> bool {
+    let kind = kind.to_lowercase();
+    let name = name.to_lowercase();
+    name == kind
+        || name == format!("{}s", kind)
+        || name == format!("{}es", kind)
+        || kind
+            .strip_suffix('y')
+            .is_some_and(|stem| name == format!("{}ies", stem))
+}
+
+/// The definition of the kind called name, as (definition name, group/version). With api_version,
+/// only that version of it, otherwise the most stable version, preferring the core group
+fn find_definition<'a>(
+    definitions: &'a Map<String, Value>,
+    name: &str,
+    api_version: Option<&str>,
+) -> Option<(&'a str, String)> {
+    let mut candidates = vec![];
+    for (def_name, definition) in definitions.iter() {
+        let gvks = definition
+            .get("x-kubernetes-group-version-kind")
+            .and_then(Value::as_array);
+        for gvk in gvks.into_iter().flatten() {
+            let field = |field: &str| gvk.get(field).and_then(Value::as_str).unwrap_or("");
+            if !kind_matches(field("kind"), name) {
+                continue;
+            }
+            let group_version = if field("group").is_empty() {
+                field("version").to_string()
+            } else {
+                format!("{}/{}", field("group"), field("version"))
+            };
+            if api_version.is_none_or(|api_version| api_version == group_version) {
+                candidates.push((
+                    field("group").is_empty(),
+                    version_rank(field("version")),
+                    def_name.as_str(),
+                    group_version,
+                ));
+            }
+        }
+    }
+    candidates
+        .into_iter()
+        .max_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)))
+        .map(|(_, _, def_name, group_version)| (def_name, group_version))
+}
+
+/// The name of the definition a field's schema refers to, directly or for the items of an array
+/// or the values of a map
+fn ref_name(schema: &Value) -> Option<&str> {
+    schema
+        .get("$ref")
+        .or_else(|| schema.pointer("/items/$ref"))
+        .or_else(|| schema.pointer("/additionalProperties/$ref"))
+        .and_then(Value::as_str)
+        .and_then(|r| r.strip_prefix("#/definitions/"))
+}
+
+/// The type of a field, as kubectl explain shows it: string, []Object, map[string]string...
+fn type_str(definitions: &Map<String, Value>, schema: &Value) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        let name = reference
+            .strip_prefix("#/definitions/")
+            .unwrap_or(reference);
+        // some types, like Quantity or IntOrString, are strings in json
+        return match definitions
+            .get(name)
+            .and_then(|definition| definition.get("type"))
+            .and_then(Value::as_str)
+        {
+            Some(typ) if typ != "object" => typ.to_string(),
+            _ => "Object".to_string(),
+        };
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("array") => format!(
+            "[]{}",
+            schema
+                .get("items")
+                .map(|items| type_str(definitions, items))
+                .unwrap_or_else(|| "Object".to_string())
+        ),
+        Some("object") => match schema.get("additionalProperties") {
+            Some(values) => format!("map[string]{}", type_str(definitions, values)),
+            None => "Object".to_string(),
+        },
+        Some(typ) => typ.to_string(),
+        None => "Object".to_string(),
+    }
+}
+
+/// What explain shows for a path: the schema of the field it ends at (or the kind, for a path of
+/// just a kind), and the definition whose fields are listed under it, if it has any
+struct Explained<'a> {
+    schema: &'a Value,
+    definition: Option<&'a Value>,
+}
+
+/// Walk fields down from the definition def_name
+fn explain<'a>(
+    definitions: &'a Map<String, Value>,
+    def_name: &str,
+    fields: &[&str],
+) -> Result<Explained<'a>, ClickError> {
+    let root = definitions.get(def_name).ok_or_else(|| {
+        ClickError::CommandError(format!("No definition {} in the schema", def_name))
+    })?;
+    let mut explained = Explained {
+        schema: root,
+        definition: Some(root),
+    };
+    let mut path = vec![];
+    for field in fields.iter() {
+        path.push(*field);
+        let schema = explained
+            .definition
+            .and_then(|definition| definition.pointer(&format!("/properties/{}", field)))
+            .ok_or_else(|| {
+                ClickError::CommandError(format!("Field {} does not exist", path.join(".")))
+            })?;
+        explained = Explained {
+            schema,
+            definition: ref_name(schema).and_then(|name| definitions.get(name)),
+        };
+    }
+    Ok(explained)
+}
+
+fn description(schema: &Value) -> &str {
+    schema
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or("<empty>")
+}
+
+command!(
+    Explain,
+    "explain",
+    "Show the documentation of a kind of resource, or of one of its fields, like pod.spec.affinity",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("path")
+                .help("The kind, or a field of it, like deployment or pod.spec.containers.ports")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("api_version")
+                .long("api-version")
+                .help("The version of the kind to explain, like autoscaling/v2 or v1")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("refresh")
+                .short('r')
+                .long("refresh")
+                .help("Fetch the schema from the api server again, instead of using the cached one")
+                .takes_value(false),
+        )
+        .after_help(
+            "The documentation comes from the cluster's openapi schema, so it matches the \
+version of Kubernetes the cluster runs, and includes resources defined by CRDs (if they have a \
+schema). The schema is fetched the first time explain is used with a context, and cached in the \
+directory click's config is in, so it works without a connection after that. Use --refresh after \
+upgrading the cluster or installing new CRDs.
+
+Kinds can be given by name or plural, in any case. If a kind is in more than one group or version, \
+the most stable version is explained, preferring the core group, unless --api-version is given.
+
+Examples:
+  # what can go in a pod's affinity
+  explain pod.spec.affinity
+
+  # a specific version of the hpa api
+  explain --api-version autoscaling/v2 horizontalpodautoscaler.spec.metrics",
+        )
+    },
+    vec!["explain"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let path = matches.value_of("path").unwrap(); // required
+        let mut parts = path.split('.').filter(|part| !part.is_empty());
+        let kind = parts.next().ok_or_else(|| {
+            ClickError::CommandError("Need a kind of resource to explain".to_string())
+        })?;
+        let fields: Vec<&str> = parts.collect();
+        let api_version = matches.value_of("api_version");
+
+        let schema = load_schema(env, matches.is_present("refresh"), writer)?;
+        let definitions = schema
+            .get("definitions")
+            .and_then(Value::as_object)
+            .ok_or_else(|| ClickError::CommandError("The schema has no definitions".to_string()))?;
+        let (def_name, group_version) = find_definition(definitions, kind, api_version)
+            .ok_or_else(|| {
+                ClickError::CommandError(format!(
+                    "Couldn't find {}{} in the schema. Try explain --refresh if it's new",
+                    kind,
+                    api_version
+                        .map(|version| format!(" in {}", version))
+                        .unwrap_or_default()
+                ))
+            })?;
+        let explained = explain(definitions, def_name, &fields)?;
+
+        let mut table = Table::new();
+        table.load_preset(presets::NOTHING);
+        table.set_content_arrangement(ContentArrangement::Dynamic);
+        table.add_row(vec![
+            "Kind:",
+            def_name.rsplit('.').next().unwrap_or(def_name),
+        ]);
+        table.add_row(vec!["Version:", group_version.as_str()]);
+        if !fields.is_empty() {
+            table.add_row(vec![
+                "Field:".to_string(),
+                format!(
+                    "{} <{}>",
+                    fields.join("."),
+                    type_str(definitions, explained.schema)
+                ),
+            ]);
+        }
+        // fields that are a reference to another definition often only have its description
+        let field_description = match explained.schema.get("description") {
+            Some(_) => description(explained.schema),
+            None => explained.definition.map_or("<empty>", description),
+        };
+        table.add_row(vec!["Description:", field_description]);
+        clickwriteln!(writer, "{table}");
+
+        let properties = explained
+            .definition
+            .and_then(|definition| definition.get("properties"))
+            .and_then(Value::as_object);
+        if let Some(properties) = properties {
+            let required: Vec<&str> = explained
+                .definition
+                .and_then(|definition| definition.get("required"))
+                .and_then(Value::as_array)
+                .map(|required| required.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            let mut table = Table::new();
+            table.load_preset(presets::NOTHING);
+            table.set_content_arrangement(ContentArrangement::Dynamic);
+            table.set_header(vec!["Field", "Type", "Description"]);
+            for (name, field) in properties.iter() {
+                let mut typ = type_str(definitions, field);
+                if required.contains(&name.as_str()) {
+                    typ.push_str(", required");
+                }
+                table.add_row(vec![name.as_str(), typ.as_str(), description(field)]);
+            }
+            clickwriteln!(writer, "\n{table}");
+        }
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_explain() {
+        let schema = json!({
+            "definitions": {
+                "io.k8s.api.core.v1.Pod": {
+                    "description": "Pod is a collection of containers",
+                    "properties": {
+                        "metadata": { "$ref": "#/definitions/io.k8s.ObjectMeta" },
+                        "spec": {
+                            "$ref": "#/definitions/io.k8s.api.core.v1.PodSpec",
+                            "description": "Specification of the desired behavior of the pod",
+                        },
+                    },
+                    "x-kubernetes-group-version-kind": [
+                        { "group": "", "kind": "Pod", "version": "v1" },
+                    ],
+                },
+                "io.k8s.api.core.v1.PodSpec": {
+                    "required": ["containers"],
+                    "properties": {
+                        "containers": {
+                            "type": "array",
+                            "items": { "$ref": "#/definitions/io.k8s.api.core.v1.Container" },
+                        },
+                        "nodeSelector": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string" },
+                        },
+                        "overhead": {
+                            "type": "object",
+                            "additionalProperties": { "$ref": "#/definitions/io.k8s.Quantity" },
+                        },
+                    },
+                },
+                "io.k8s.api.core.v1.Container": {
+                    "properties": { "name": { "type": "string", "description": "Name" } },
+                },
+                "io.k8s.Quantity": { "type": "string" },
+                "io.k8s.api.policy.v1beta1.PodDisruptionBudget": {
+                    "x-kubernetes-group-version-kind": [
+                        { "group": "policy", "kind": "PodDisruptionBudget", "version": "v1beta1" },
+                    ],
+                },
+                "io.k8s.api.policy.v1.PodDisruptionBudget": {
+                    "x-kubernetes-group-version-kind": [
+                        { "group": "policy", "kind": "PodDisruptionBudget", "version": "v1" },
+                    ],
+                },
+            },
+        });
+        let definitions = schema["definitions"].as_object().unwrap();
+
+        assert_eq!(
+            find_definition(definitions, "pods", None),
+            Some(("io.k8s.api.core.v1.Pod", "v1".to_string()))
+        );
+        assert_eq!(
+            find_definition(definitions, "poddisruptionbudget", None),
+            Some((
+                "io.k8s.api.policy.v1.PodDisruptionBudget",
+                "policy/v1".to_string()
+            ))
+        );
+        assert_eq!(
+            find_definition(definitions, "PodDisruptionBudget", Some("policy/v1beta1")),
+            Some((
+                "io.k8s.api.policy.v1beta1.PodDisruptionBudget",
+                "policy/v1beta1".to_string()
+            ))
+        );
+        assert_eq!(find_definition(definitions, "pod", Some("v2")), None);
+
+        let spec = explain(definitions, "io.k8s.api.core.v1.Pod", &["spec"]).unwrap();
+        assert_eq!(type_str(definitions, spec.schema), "Object");
+        let spec_fields = spec.definition.unwrap()["properties"].as_object().unwrap();
+        assert_eq!(
+            type_str(definitions, &spec_fields["containers"]),
+            "[]Object"
+        );
+        assert_eq!(
+            type_str(definitions, &spec_fields["nodeSelector"]),
+            "map[string]string"
+        );
+        assert_eq!(
+            type_str(definitions, &spec_fields["overhead"]),
+            "map[string]string"
+        );
+
+        let name = explain(
+            definitions,
+            "io.k8s.api.core.v1.Pod",
+            &["spec", "containers", "name"],
+        )
+        .unwrap();
+        assert_eq!(description(name.schema), "Name");
+        assert!(name.definition.is_none());
+        assert!(explain(definitions, "io.k8s.api.core.v1.Pod", &["spec", "bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_version_rank() {
+        let mut versions = vec!["v1beta1", "v2", "v1alpha1", "v1", "v2beta2"];
+        versions.sort_by_key(|version| version_rank(version));
+        assert_eq!(versions, vec!["v1alpha1", "v1beta1", "v2beta2", "v1", "v2"]);
+    }
+
+    #[test]
+    fn test_cache_path() {
+        assert_eq!(
+            cache_path(Path::new("/home/me/.click"), "arn:aws:eks:cluster/prod"),
+            PathBuf::from("/home/me/.click/openapi/arn_aws_eks_cluster_prod.json")
+        );
+    }
+}
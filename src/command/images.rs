@@ -0,0 +1,190 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use k8s_openapi::{api::core::v1 as api, List, ListOptional};
+use regex::Regex;
+use rustyline::completion::Pair as RustlinePair;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    command::resources::workload_of,
+    completer,
+    env::Env,
+    error::ClickError,
+    output::ClickWriter,
+    table::CellSpec,
+};
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+
+/// Where an image is used: how many containers run it, and the workloads (namespace/Kind/name)
+/// they're in
+#[derive(Debug, Default, PartialEq)]
+struct ImageUse {
+    containers: usize,
+    workloads: BTreeSet<String>,
+}
+
+/// Every image the pods use, in their containers and init containers, with where it's used
+fn image_uses(pods: &[api::Pod]) -> BTreeMap<&str, ImageUse> {
+    let mut uses: BTreeMap<&str, ImageUse> = BTreeMap::new();
+    for pod in pods.iter() {
+        let spec = match pod.spec.as_ref() {
+            Some(spec) => spec,
+            None => continue,
+        };
+        let workload = format!(
+            "{}/{}",
+            pod.metadata.namespace.as_deref().unwrap_or("<none>"),
+            workload_of(pod)
+        );
+        let containers = spec
+            .containers
+            .iter()
+            .chain(spec.init_containers.iter().flatten());
+        for image in containers.filter_map(|container| container.image.as_deref()) {
+            let image_use = uses.entry(image).or_default();
+            image_use.containers += 1;
+            image_use.workloads.insert(workload.clone());
+        }
+    }
+    uses
+}
+
+command!(
+    Images,
+    "images",
+    "List every container image in use in the cluster, with how many containers run it and the \
+     workloads using it",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("regex")
+                .help("Only list images that match this regex, like nginx:1.19 or ^quay.io/")
+                .validator(|s| Regex::new(s).map(|_| ()).map_err(|e| e.to_string()))
+                .index(1),
+        )
+        .arg(
+            Arg::new("namespace")
+                .short('n')
+                .long("namespace-only")
+                .help("Only look at the pods in the current namespace")
+                .takes_value(false),
+        )
+        .after_help(
+            "Images are listed as pods specify them, so the same image by tag and by digest \
+shows up twice. Init containers count too, and finished pods are left out. Workloads are shown as \
+namespace/Kind/name, from the controller of each pod, or as namespace/Pod/name for pods that have \
+none.
+
+Examples:
+  # is anything still running a vulnerable tag?
+  images log4j:2.14
+
+  # the images from quay.io in the current namespace
+  images -n ^quay.io/",
+        )
+    },
+    vec!["images"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let regex = matches.value_of("regex").map(|s| Regex::new(s).unwrap()); // validated
+        let options = ListOptional {
+            field_selector: Some("status.phase!=Succeeded,status.phase!=Failed"),
+            ..Default::default()
+        };
+        let (request, _) = if matches.is_present("namespace") {
+            let namespace = env
+                .namespace
+                .as_deref()
+                .ok_or_else(|| ClickError::CommandError("No namespace is set".to_string()))?;
+            api::Pod::list_namespaced_pod(namespace, options)?
+        } else {
+            api::Pod::list_pod_for_all_namespaces(options)?
+        };
+        let pods: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+        let uses = image_uses(&pods.items);
+        let rows: Vec<Vec<CellSpec<'_>>> = uses
+            .iter()
+            .filter(|(image, _)| regex.as_ref().is_none_or(|regex| regex.is_match(image)))
+            .map(|(image, image_use)| {
+                let workloads: Vec<&str> = image_use.workloads.iter().map(String::as_str).collect();
+                vec![
+                    (*image).into(),
+                    CellSpec::new_int(image_use.containers as i64),
+                    workloads.join("\n").into(),
+                ]
+            })
+            .collect();
+        if rows.is_empty() {
+            clickwriteln!(writer, "No images found");
+            return Ok(());
+        }
+        crate::table::print_table(vec!["Image", "Containers", "Workloads"], rows, env, writer);
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_image_uses() {
+        let pods: Vec<api::Pod> = serde_json::from_value(json!([
+            {
+                "metadata": {
+                    "name": "web-5d4f8-abcde",
+                    "namespace": "prod",
+                    "labels": { "pod-template-hash": "5d4f8" },
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "ReplicaSet",
+                        "name": "web-5d4f8",
+                        "uid": "1234",
+                        "controller": true,
+                    }],
+                },
+                "spec": {
+                    "containers": [
+                        { "name": "web", "image": "web:1.2" },
+                        { "name": "proxy", "image": "envoy:1.20" },
+                    ],
+                    "initContainers": [{ "name": "migrate", "image": "web:1.2" }],
+                },
+            },
+            {
+                "metadata": { "name": "debug", "namespace": "dev" },
+                "spec": { "containers": [{ "name": "debug", "image": "envoy:1.20" }] },
+            },
+        ]))
+        .unwrap();
+        let uses = image_uses(&pods);
+        let images: Vec<&str> = uses.keys().copied().collect();
+        assert_eq!(images, vec!["envoy:1.20", "web:1.2"]);
+        assert_eq!(uses["web:1.2"].containers, 2);
+        assert_eq!(
+            uses["web:1.2"].workloads.iter().collect::<Vec<_>>(),
+            vec!["prod/Deployment/web"]
+        );
+        assert_eq!(
+            uses["envoy:1.20"].workloads.iter().collect::<Vec<_>>(),
+            vec!["dev/Pod/debug", "prod/Deployment/web"]
+        );
+    }
+}
@@ -16,8 +16,14 @@ use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::api::batch::v1 as batch_api;
 
 use crate::{
-    command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{keyval_string, run_list_command, time_since, Extractor},
+    command::command_def::{
+        all_namespaces_arg, columns_arg, exec_match, field_selector_arg, filter_arg,
+        filter_pattern_arg, hide_arg, label_selector_arg, no_headers_arg, output_arg, quiet_arg,
+        show_arg, sort_arg, sort_by_arg, start_clap, watch_arg, Cmd,
+    },
+    command::{
+        keyval_string, list_namespace, list_options, run_list_command, time_since, Extractor,
+    },
     completer,
     env::Env,
     kobj::{KObj, ObjType},
@@ -54,6 +60,7 @@ const EXTRA_COL_MAP: &[(&str, &str)] = &[
     ("images", "Images"),
     ("selector", "Selector"),
     ("labels", "Labels"),
+    ("namespace", "Namespace"),
 ];
 
 const EXTRA_COL_FLAGS: &[&str] = &{ extract_first!(EXTRA_COL_MAP) };
@@ -161,8 +168,20 @@ list_command!(
                 .help("Filter jobs by the specified regex")
                 .takes_value(true)
         )
+        .arg(all_namespaces_arg())
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
         .arg(show_arg(EXTRA_COL_FLAGS, true))
         .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
         .arg(
             Arg::new("reverse")
                 .short('R')
@@ -172,11 +191,15 @@ list_command!(
         ),
     vec!["job", "jobs"],
     noop_complete!(),
-    [].into_iter(),
+    [(
+        "regex".to_string(),
+        completer::job_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter(),
     |matches, env, writer| {
-        let (request, _response_body) = match &env.namespace {
-            Some(ns) => batch_api::Job::list_namespaced_job(ns, Default::default())?,
-            None => batch_api::Job::list_job_for_all_namespaces(Default::default())?,
+        let (request, _response_body) = match list_namespace(&matches, env) {
+            Some(ns) => batch_api::Job::list_namespaced_job(ns, list_options(&matches))?,
+            None => batch_api::Job::list_job_for_all_namespaces(list_options(&matches))?,
         };
         let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
 
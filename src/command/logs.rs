@@ -13,34 +13,41 @@
 // limitations under the License.
 
 use chrono::offset::{Local, Utc};
-use chrono::DateTime;
+use chrono::{DateTime, SecondsFormat};
 use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::api::core::v1 as api;
-
-use reqwest::blocking::Response;
+use k8s_openapi::http::Request;
+use regex::Regex;
+use reqwest::{blocking::Response, StatusCode};
 use rustyline::completion::Pair as RustlinePair;
 use strfmt::strfmt;
 
 use crate::{
     command::command_def::{exec_match, start_clap, Cmd},
-    command::{parse_duration, valid_date, valid_duration, valid_u32},
+    command::{parse_duration, valid_duration, valid_u32},
     completer,
-    env::Env,
+    env::{Env, ObjectSelection},
     error::ClickError,
     kobj::{KObj, ObjType},
     output::ClickWriter,
+    table::{configured_timezone, format_timestamp, parse_time},
 };
 
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::fs::File;
 use std::io::{BufRead, BufReader, Read, Write};
 use std::path::PathBuf;
 use std::sync::atomic::Ordering;
-use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
 use std::thread;
 use std::time::Duration;
 
+/// How many tenths of a second to wait between tries to reconnect a followed log stream
+const RECONNECT_WAIT_STEPS: u32 = 20;
+
 // logs helper commands
 fn pick_container<'a>(obj: &'a KObj, writer: &mut ClickWriter) -> &'a str {
     match obj.typ {
@@ -54,22 +61,484 @@ fn pick_container<'a>(obj: &'a KObj, writer: &mut ClickWriter) -> &'a str {
     }
 }
 
-#[allow(clippy::ptr_arg)]
+/// How --local-time shows the timestamp at the start of each line
+const LOCAL_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// Split the RFC3339 timestamp the api server puts at the start of line (with timestamps=true)
+/// from the rest of it
+fn split_timestamp(line: &str) -> Option<(DateTime<Utc>, &str)> {
+    line.split_once(' ').and_then(|(ts, rest)| {
+        DateTime::parse_from_rfc3339(ts)
+            .ok()
+            .map(|ts| (ts.with_timezone(&Utc), rest))
+    })
+}
+
+/// Rewrite the timestamp at the start of line in the configured timezone, the local one by
+/// default. Lines that don't start with a timestamp are left alone
+fn localize_timestamp<'a>(line: &'a str, env: &Env) -> Cow<'a, str> {
+    match split_timestamp(line) {
+        Some((ts, rest)) => {
+            format!("{} {}", format_timestamp(ts, LOCAL_TIME_FORMAT, env), rest).into()
+        }
+        None => line.into(),
+    }
+}
+
+/// Which lines of logs to show, from --grep and --invert
+struct LineFilter {
+    regex: Regex,
+    invert: bool,
+}
+
+impl LineFilter {
+    /// Should line be shown. The whole line is matched, including its timestamp if it has one
+    fn keeps(&self, line: &str) -> bool {
+        self.regex.is_match(line) != self.invert
+    }
+}
+
+/// Does filter, if there is one, keep line
+fn keep_line(filter: Option<&LineFilter>, line: &str) -> bool {
+    filter.is_none_or(|filter| filter.keeps(line))
+}
+
+/// How many rotated log files --max-size keeps, as PATH.1 (the newest) to PATH.5
+const ROTATED_FILES: u32 = 5;
+
+/// Parse a size for --max-size: a number of bytes, optionally followed by K, M or G (or Ki, Mi,
+/// Gi), which are all powers of 1024
+fn parse_size(s: &str) -> Result<u64, String> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let mult: u64 = match unit {
+        "" => 1,
+        "K" | "k" | "Ki" => 1 << 10,
+        "M" | "Mi" => 1 << 20,
+        "G" | "Gi" => 1 << 30,
+        _ => return Err(format!("Invalid size unit '{}', use K, M or G", unit)),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|num| num.checked_mul(mult))
+        .filter(|size| *size > 0)
+        .ok_or_else(|| format!("Invalid size '{}'", s))
+}
+
+/// A file logs are written to. With a max_size, it's rotated before it would grow past that: the
+/// file is moved to PATH.1 (and PATH.1 to PATH.2 and so on, dropping the oldest) and a new one
+/// started
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: Option<u64>,
+}
+
+impl LogFile {
+    fn create(path: PathBuf, max_size: Option<u64>) -> Result<LogFile, ClickError> {
+        let file = File::create(&path)?;
+        Ok(LogFile {
+            path,
+            file,
+            size: 0,
+            max_size,
+        })
+    }
+
+    fn rotated_path(&self, n: u32) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> Result<(), ClickError> {
+        self.file.flush()?;
+        for n in (1..ROTATED_FILES).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(n + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Write buf, which should be whole lines so they aren't split across files
+    fn write(&mut self, buf: &[u8]) -> Result<(), ClickError> {
+        let len = buf.len() as u64;
+        if self
+            .max_size
+            .is_some_and(|max| self.size > 0 && self.size + len > max)
+        {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ClickError> {
+        self.file.flush().map_err(ClickError::from)
+    }
+}
+
+/// The path to write obj's logs to, from the --output template
+fn output_path(output: &str, obj: &KObj) -> Result<PathBuf, ClickError> {
+    let mut fmtvars = HashMap::new();
+    fmtvars.insert("name".to_string(), obj.name());
+    fmtvars.insert(
+        "namespace".to_string(),
+        obj.namespace.as_deref().unwrap_or("[none]"),
+    );
+    let ltime = Local::now().to_rfc3339();
+    fmtvars.insert("time".to_string(), &ltime);
+    strfmt(output, &fmtvars)
+        .map(PathBuf::from)
+        .map_err(|e| ClickError::CommandError(format!("Can't generate output path: {}", e)))
+}
+
 fn write_logs_to_file(
     env: &Env,
-    path: &PathBuf,
+    mut file: LogFile,
     mut reader: BufReader<Response>,
+    local_time: bool,
+    filter: Option<&LineFilter>,
 ) -> Result<(), ClickError> {
-    let mut file = std::fs::File::create(path)?;
+    // rotating only happens between lines, so go line by line for that too
+    if local_time || filter.is_some() || file.max_size.is_some() {
+        let mut line = String::new();
+        while !env.ctrlcbool.load(Ordering::SeqCst) && reader.read_line(&mut line)? > 0 {
+            if keep_line(filter, &line) {
+                if local_time {
+                    file.write(localize_timestamp(&line, env).as_bytes())?;
+                } else {
+                    file.write(line.as_bytes())?;
+                }
+            }
+            line.clear();
+        }
+        return file.flush();
+    }
     let mut buffer = [0; 1024];
     while !env.ctrlcbool.load(Ordering::SeqCst) {
         let amt = reader.read(&mut buffer[..])?;
         if amt == 0 {
             break;
         }
-        file.write_all(&buffer[0..amt])?;
+        file.write(&buffer[0..amt])?;
     }
-    file.flush().map_err(ClickError::from)
+    file.flush()
+}
+
+/// Read lines from reader in a thread, so waiting for them doesn't stop ctrl-c being noticed. The
+/// channel is disconnected when the stream ends
+fn read_lines(mut reader: BufReader<Response>) -> Receiver<String> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        loop {
+            let mut line = String::new();
+            if let Ok(amt) = reader.read_line(&mut line) {
+                if amt > 0 {
+                    if sender.send(line).is_err() {
+                        // probably user hit ctrl-c, just stop
+                        break;
+                    }
+                } else {
+                    break;
+                }
+            } else {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Print a note about the log stream, like it ending, in the warning color
+fn log_note(env: &Env, writer: &mut ClickWriter, note: &str) {
+    let note = format!("--- {} ---", note);
+    if env.colors() && writer.is_tty() {
+        clickwriteln!(writer, "{}", env.styles.warning(&note));
+    } else {
+        clickwriteln!(writer, "{}", note);
+    }
+    writer.flush().unwrap_or(());
+}
+
+/// Why container won't log any more, if it won't: the pod has finished, or the container has
+/// exited and the pod's restartPolicy won't start it again
+fn finished_reason(pod: &api::Pod, container: &str) -> Option<String> {
+    let status = pod.status.as_ref()?;
+    if let Some(phase @ ("Succeeded" | "Failed")) = status.phase.as_deref() {
+        return Some(format!("pod has {}", phase.to_lowercase()));
+    }
+    let terminated = status
+        .container_statuses
+        .iter()
+        .chain(status.init_container_statuses.iter())
+        .flatten()
+        .find(|status| status.name == container)
+        .and_then(|status| status.state.as_ref())
+        .and_then(|state| state.terminated.as_ref())?;
+    let policy = pod
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.restart_policy.as_deref())
+        .unwrap_or("Always");
+    match policy {
+        "Never" => Some(format!("{} has exited and won't be restarted", container)),
+        "OnFailure" if terminated.exit_code == 0 => Some(format!("{} has completed", container)),
+        _ => None,
+    }
+}
+
+/// read_namespaced_pod_log can't ask for logs since a time, so add sinceTime to request
+fn with_since_time(
+    request: Request<Vec<u8>>,
+    since: DateTime<Utc>,
+) -> Result<Request<Vec<u8>>, ClickError> {
+    let (mut parts, body) = request.into_parts();
+    let since: String = url::form_urlencoded::byte_serialize(
+        since.to_rfc3339_opts(SecondsFormat::Secs, true).as_bytes(),
+    )
+    .collect();
+    let separator = if parts.uri.query().is_some() {
+        '&'
+    } else {
+        '?'
+    };
+    parts.uri = format!("{}{}sinceTime={}", parts.uri, separator, since)
+        .parse()
+        .map_err(|e| ClickError::CommandError(format!("Invalid log url: {}", e)))?;
+    Ok(Request::from_parts(parts, body))
+}
+
+/// Open a followed log stream again after it ended, like when the container restarted or the
+/// connection dropped. This keeps trying until it works, ctrl-c is pressed (returning None), or the
+/// pod is gone or won't log any more. The new stream starts from last, the time of the last line
+/// shown, so nothing logged in between is missed. The api server only takes whole seconds, so
+/// lines up to last are sent again, and should be dropped
+fn reconnect_logs<'a>(
+    obj: &'a KObj,
+    env: &Env,
+    mut opts: api::ReadNamespacedPodLogOptional<'a>,
+    last: Option<DateTime<Utc>>,
+    writer: &mut ClickWriter,
+) -> Result<Option<BufReader<Response>>, ClickError> {
+    let ended = Utc::now();
+    log_note(
+        env,
+        writer,
+        &format!(
+            "log stream ended at {}, reconnecting (ctrl-c to stop)",
+            format_timestamp(ended, "%H:%M:%S", env)
+        ),
+    );
+    opts.tail_lines = None;
+    opts.since_seconds = None;
+    let container = opts.container.unwrap_or_default();
+    loop {
+        // give a restarted container a moment to start, noticing ctrl-c quickly
+        for _ in 0..RECONNECT_WAIT_STEPS {
+            if env.ctrlcbool.load(Ordering::SeqCst) {
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        match obj.read_kind::<api::Pod>(env, "logs") {
+            Ok(pod) => {
+                if let Some(reason) = finished_reason(&pod, container) {
+                    log_note(env, writer, &format!("{}, not reconnecting", reason));
+                    return Ok(None);
+                }
+            }
+            Err(e) if e.is_status(StatusCode::NOT_FOUND) => {
+                log_note(env, writer, "pod is gone, not reconnecting");
+                return Ok(None);
+            }
+            // try the logs anyway, they'll say what's wrong
+            Err(_) => {}
+        }
+        let gap = Utc::now().signed_duration_since(ended);
+        let (request, _) =
+            api::Pod::read_namespaced_pod_log(obj.name(), obj.namespace.as_ref().unwrap(), opts)?;
+        let request = with_since_time(request, last.unwrap_or(ended))?;
+        match env.run_on_context(|c| c.execute_reader(request, None)) {
+            Ok(reader) => {
+                log_note(
+                    env,
+                    writer,
+                    &format!("reconnected after {}s", gap.num_seconds()),
+                );
+                return Ok(Some(BufReader::new(reader)));
+            }
+            Err(e) if e.is_status(StatusCode::NOT_FOUND) => {
+                log_note(env, writer, "pod is gone, not reconnecting");
+                return Ok(None);
+            }
+            // the container is probably still starting, try again
+            Err(_) => {}
+        }
+    }
+}
+
+/// Print a line of logs, which includes its newline, after the tag of its source if there is one
+fn print_log_line(
+    env: &Env,
+    writer: &mut ClickWriter,
+    tag: Option<&str>,
+    line: &str,
+    local_time: bool,
+    color: bool,
+) {
+    let line = if local_time {
+        localize_timestamp(line, env)
+    } else {
+        line.into()
+    };
+    let line = match env.highlight.as_ref() {
+        Some(regex) if color => env
+            .styles
+            .highlight_matches(&line, regex)
+            .into_owned()
+            .into(),
+        _ => line,
+    };
+    match tag {
+        Some(tag) if color => clickwrite!(writer, "{} {}", env.styles.log_source(tag), line),
+        Some(tag) => clickwrite!(writer, "{} {}", tag, line),
+        None => clickwrite!(writer, "{}", line),
+    }
+    // the last line of a stream that ended might not have one
+    if !line.ends_with('\n') {
+        clickwriteln!(writer, "");
+    }
+}
+
+/// The tag that marks the lines of one container's logs when showing several at once
+fn source_tag(pod: &KObj, container: &str) -> String {
+    format!("[{}/{}]", pod.name(), container)
+}
+
+/// Show the logs of several containers at once, each line tagged with the container it's from.
+/// Lines are shown as they arrive from any of the containers, until all the log streams end or
+/// ctrl-c is pressed
+fn multi_logs(
+    env: &Env,
+    sources: &[(KObj, String)],
+    opts: api::ReadNamespacedPodLogOptional,
+    timeout: Option<Duration>,
+    local_time: bool,
+    filter: Option<&LineFilter>,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let color = env.colors() && writer.is_tty();
+    let tags: Vec<String> = sources
+        .iter()
+        .map(|(pod, container)| source_tag(pod, container))
+        .collect();
+    // each stream is read in its own thread, sending the lines it reads with its index, and None
+    // when it ends
+    let (sender, receiver) = channel::<(usize, Option<String>)>();
+    let mut open = 0;
+    for (index, (pod, container)) in sources.iter().enumerate() {
+        let mut opts = opts;
+        opts.container = Some(container);
+        let (request, _) =
+            api::Pod::read_namespaced_pod_log(pod.name(), pod.namespace.as_ref().unwrap(), opts)?;
+        let reader = match env.run_on_context(|c| c.execute_reader(request, timeout)) {
+            Ok(reader) => reader,
+            Err(e) => {
+                log_note(
+                    env,
+                    writer,
+                    &format!("{} can't get logs: {}", tags[index], e),
+                );
+                continue;
+            }
+        };
+        open += 1;
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            loop {
+                let mut line = String::new();
+                match reader.read_line(&mut line) {
+                    Ok(amt) if amt > 0 => {
+                        if sender.send((index, Some(line))).is_err() {
+                            return; // stopped with ctrl-c
+                        }
+                    }
+                    _ => break,
+                }
+            }
+            sender.send((index, None)).unwrap_or(());
+        });
+    }
+    env.ctrlcbool.store(false, Ordering::SeqCst);
+    while open > 0 && !env.ctrlcbool.load(Ordering::SeqCst) {
+        match receiver.recv_timeout(Duration::new(1, 0)) {
+            Ok((index, Some(line))) => {
+                if keep_line(filter, &line) {
+                    print_log_line(env, writer, Some(&tags[index]), &line, local_time, color)
+                }
+            }
+            Ok((index, None)) => {
+                open -= 1;
+                if opts.follow == Some(true) {
+                    log_note(env, writer, &format!("{} log stream ended", tags[index]));
+                }
+            }
+            // no new lines for a bit, make sure what we have is shown
+            Err(_) => writer.flush().unwrap_or(()),
+        }
+    }
+    Ok(())
+}
+
+/// The init container worth looking at the logs of: the one that's running or waiting, since init
+/// containers run in order and that's the one the pod is stuck on. If they've all finished, the
+/// last one
+fn current_init_container(pod: &api::Pod) -> Option<&str> {
+    let statuses = pod.status.as_ref()?.init_container_statuses.as_ref();
+    let unfinished = statuses.and_then(|statuses| {
+        statuses.iter().find(|status| {
+            status
+                .state
+                .as_ref()
+                .is_none_or(|state| state.terminated.is_none())
+        })
+    });
+    match unfinished {
+        Some(status) => Some(status.name.as_str()),
+        // without statuses, the spec still says what the init containers are
+        None => pod
+            .spec
+            .as_ref()?
+            .init_containers
+            .as_ref()?
+            .last()
+            .map(|cont| cont.name.as_str()),
+    }
+}
+
+/// Read the pod obj, and pick the init container to get logs from
+fn pick_init_container(obj: &KObj, env: &Env) -> Result<String, ClickError> {
+    let (request, _) = api::Pod::read_namespaced_pod(
+        obj.name(),
+        obj.namespace.as_deref().unwrap_or_default(),
+        Default::default(),
+    )?;
+    let pod: api::Pod = env.run_on_context(|c| c.read_object(request))?;
+    current_init_container(&pod)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            ClickError::CommandError(format!("Pod {} has no init containers", obj.name()))
+        })
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -82,10 +551,21 @@ fn do_logs<'a>(
     editor: bool,
     editor_opt: Option<&str>,
     timeout: Option<Duration>,
+    reconnect: bool,
+    local_time: bool,
+    filter: Option<&LineFilter>,
+    max_size: Option<u64>,
     writer: &mut ClickWriter,
 ) -> Result<(), ClickError> {
+    let follow = opts.follow == Some(true);
     let cont = cont_opt.unwrap_or_else(|| pick_container(obj, writer));
     opts.container = Some(cont);
+    let reconnect = reconnect && follow && opts.previous.is_none();
+    // a reconnected stream picks up after the last line shown, which needs the time of each line
+    let strip_timestamps = reconnect && opts.timestamps != Some(true);
+    if reconnect {
+        opts.timestamps = Some(true);
+    }
 
     let (request, _resp) =
         api::Pod::read_namespaced_pod_log(obj.name(), obj.namespace.as_ref().unwrap(), opts)?;
@@ -93,45 +573,21 @@ fn do_logs<'a>(
     let logs_reader_res = env.run_on_context(|c| c.execute_reader(request, timeout));
     match logs_reader_res {
         Ok(lreader) => {
-            let mut reader = BufReader::new(lreader);
+            let reader = BufReader::new(lreader);
             env.ctrlcbool.store(false, Ordering::SeqCst);
-            if let Some(output) = output_opt {
-                let mut fmtvars = HashMap::new();
-                fmtvars.insert("name".to_string(), obj.name());
-                fmtvars.insert(
-                    "namespace".to_string(),
-                    obj.namespace.as_deref().unwrap_or("[none]"),
-                );
-                let ltime = Local::now().to_rfc3339();
-                fmtvars.insert("time".to_string(), &ltime);
-                match strfmt(output, &fmtvars) {
-                    Ok(file_path) => {
-                        let pbuf = file_path.into();
-                        write_logs_to_file(env, &pbuf, reader)?;
-                        println!("Wrote logs to {}", pbuf.to_str().unwrap());
-                        Ok(())
-                    }
-                    Err(e) => Err(ClickError::CommandError(format!(
-                        "Can't generate output path: {}",
-                        e
-                    ))),
-                }
+            // when following, logs are saved to the file as they're shown instead
+            if let Some(output) = output_opt.filter(|_| !follow) {
+                let path = output_path(output, obj)?;
+                let file = LogFile::create(path.clone(), max_size)?;
+                write_logs_to_file(env, file, reader, local_time, filter)?;
+                println!("Wrote logs to {}", path.display());
+                Ok(())
             } else if editor {
                 // We're opening in an editor, save to a temp
                 let editor = if let Some(v) = editor_opt {
                     v.to_owned()
-                } else if let Some(ref e) = env.click_config.editor {
-                    e.clone()
                 } else {
-                    match std::env::var("EDITOR") {
-                        Ok(ed) => ed,
-                        Err(e) => {
-                            return Err(ClickError::CommandError(format!(
-                                "Could not get EDITOR environment variable: {}",
-                                e
-                            )));
-                        }
-                    }
+                    env.editor()?
                 };
                 let tmpdir = match env.tempdir {
                     Ok(ref td) => td,
@@ -148,7 +604,8 @@ fn do_logs<'a>(
                     cont,
                     Local::now().to_rfc3339()
                 ));
-                write_logs_to_file(env, &file_path, reader)?;
+                let file = LogFile::create(file_path.clone(), None)?;
+                write_logs_to_file(env, file, reader, local_time, filter)?;
 
                 clickwriteln!(writer, "Logs downloaded, starting editor");
                 let expr = if editor.contains(' ') {
@@ -162,37 +619,78 @@ fn do_logs<'a>(
                 expr.start()?;
                 Ok(())
             } else {
-                let (sender, receiver) = channel();
-                thread::spawn(move || {
-                    loop {
-                        let mut line = String::new();
-                        if let Ok(amt) = reader.read_line(&mut line) {
-                            if amt > 0 {
-                                if sender.send(line).is_err() {
-                                    // probably user hit ctrl-c, just stop
+                let highlight = env.colors() && writer.is_tty();
+                let mut tee = match output_opt {
+                    Some(output) => {
+                        let path = output_path(output, obj)?;
+                        clickwriteln!(writer, "Saving logs to {}", path.display());
+                        Some(LogFile::create(path, max_size)?)
+                    }
+                    None => None,
+                };
+                let mut receiver = read_lines(reader);
+                // the time of the last line shown, and after reconnecting, of the last line shown
+                // before, which the new stream repeats lines up to
+                let mut last = None;
+                let mut resume_after = None;
+                loop {
+                    let mut ended = false;
+                    while !env.ctrlcbool.load(Ordering::SeqCst) {
+                        match receiver.recv_timeout(Duration::new(1, 0)) {
+                            Ok(line) => {
+                                let (time, text) = match split_timestamp(&line) {
+                                    Some((time, text)) => (Some(time), text),
+                                    None => (None, line.as_str()),
+                                };
+                                if let (Some(time), Some(after)) = (time, resume_after) {
+                                    if time <= after {
+                                        continue;
+                                    }
+                                    resume_after = None;
+                                }
+                                last = time.or(last);
+                                let line = if strip_timestamps { text } else { &line };
+                                if keep_line(filter, line) {
+                                    if let Some(file) = tee.as_mut() {
+                                        let saved = if local_time {
+                                            localize_timestamp(line, env)
+                                        } else {
+                                            line.into()
+                                        };
+                                        file.write(saved.as_bytes())?;
+                                    }
+                                    print_log_line(env, writer, None, line, local_time, highlight)
+                                }
+                            }
+                            Err(e) => {
+                                if let RecvTimeoutError::Disconnected = e {
+                                    ended = true;
                                     break;
                                 }
-                            } else {
-                                break;
+                                // no new lines for a bit, make sure what we have is shown (and
+                                // saved)
+                                writer.flush().unwrap_or(());
+                                if let Some(file) = tee.as_mut() {
+                                    file.flush()?;
+                                }
                             }
-                        } else {
-                            break;
                         }
                     }
-                });
-                while !env.ctrlcbool.load(Ordering::SeqCst) {
-                    match receiver.recv_timeout(Duration::new(1, 0)) {
-                        Ok(line) => {
-                            clickwrite!(writer, "{}", line); // newlines already in line
-                        }
-                        Err(e) => {
-                            if let RecvTimeoutError::Disconnected = e {
-                                break;
-                            }
+                    if !(ended && reconnect) {
+                        break;
+                    }
+                    match reconnect_logs(obj, env, opts, last, writer)? {
+                        Some(reader) => {
+                            receiver = read_lines(reader);
+                            resume_after = last;
                         }
+                        None => break,
                     }
                 }
-                Ok(())
+                match tee.as_mut() {
+                    Some(file) => file.flush(),
+                    None => Ok(()),
+                }
             }
         }
         Err(e) => Err(e),
@@ -211,19 +709,53 @@ command!(
                     .required(false)
                     .index(1),
             )
+            .arg(
+                Arg::new("all-containers")
+                    .short('a')
+                    .long("all-containers")
+                    .help("Get logs from all the containers of the pod")
+                    .conflicts_with_all(&["container", "init", "editor", "output"])
+                    .takes_value(false),
+            )
+            .arg(
+                Arg::new("init")
+                    .long("init")
+                    .value_name("NAME")
+                    .help(
+                        "Get logs from an init container. Without NAME, picks the one that's \
+                         running or stuck waiting (or the last one, if they've all finished)",
+                    )
+                    .conflicts_with("container")
+                    .takes_value(true)
+                    .min_values(0),
+            )
             .arg(
                 Arg::new("follow")
                     .short('f')
                     .long("follow")
-                    .help("Follow the logs as new records arrive (stop with ^C)")
+                    .help(
+                        "Follow the logs as new records arrive (stop with ^C). If the stream \
+                         ends, like when the container restarts, it's reconnected",
+                    )
                     .conflicts_with("editor")
                     .conflicts_with("output")
                     .takes_value(false),
             )
+            .arg(
+                Arg::new("no-reconnect")
+                    .long("no-reconnect")
+                    .help(
+                        "With --follow, stop when the log stream ends (like when the container \
+                         restarts) instead of reconnecting",
+                    )
+                    .requires("follow")
+                    .takes_value(false),
+            )
             .arg(
                 Arg::new("tail")
                     .short('t')
                     .long("tail")
+                    .value_name("LINES")
                     .validator(valid_u32)
                     .help("Number of lines from the end of the logs to show")
                     .takes_value(true),
@@ -238,6 +770,7 @@ command!(
             .arg(
                 Arg::new("since")
                     .long("since")
+                    .value_name("DURATION")
                     .conflicts_with("sinceTime")
                     .validator(valid_duration)
                     .help(
@@ -249,11 +782,14 @@ command!(
             .arg(
                 Arg::new("sinceTime")
                     .long("since-time")
+                    .value_name("DATE")
                     .conflicts_with("since")
-                    .validator(valid_date)
+                    .validator(|s| parse_time(s, Utc::now(), None).map(|_| ()))
                     .help(
-                        "Only return logs newer than specified RFC3339 date. Eg:
- 1996-12-19T16:39:57-08:00",
+                        "Only return logs newer than this time. This can be RFC3339 (like \
+                         2024-05-12T10:00:00Z), or a date and time (2024-05-12 10:00) or just a \
+                         time (10:00, the last time it was 10:00) in the local timezone (or the \
+                         one set with 'set timezone')",
                     )
                     .takes_value(true),
             )
@@ -266,10 +802,39 @@ command!(
                     )
                     .takes_value(false),
             )
+            .arg(
+                Arg::new("local-time")
+                    .short('L')
+                    .long("local-time")
+                    .help(
+                        "Like --timestamps, but show the timestamps in the local timezone (or the \
+                         one set with 'set timezone'), to line them up with events and metrics",
+                    )
+                    .takes_value(false),
+            )
+            .arg(
+                Arg::new("grep")
+                    .long("grep")
+                    .value_name("REGEX")
+                    .help(
+                        "Only show lines that match REGEX. This works while following, and with \
+                         --output and --editor",
+                    )
+                    .validator(|s| Regex::new(s).map(|_| ()).map_err(|e| e.to_string()))
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::new("invert")
+                    .long("invert")
+                    .help("Only show lines that don't match --grep")
+                    .requires("grep")
+                    .takes_value(false),
+            )
             .arg(
                 Arg::new("editor")
                     .long("editor")
                     .short('e')
+                    .value_name("EDITOR")
                     .conflicts_with("follow")
                     .conflicts_with("output")
                     .help(
@@ -285,14 +850,27 @@ command!(
                 Arg::new("output")
                     .long("output")
                     .short('o')
+                    .value_name("PATH")
                     .conflicts_with("editor")
-                    .conflicts_with("follow")
                     .help(
                         "Write output to a file at the specified path instead of printing it. \
+                         With --follow, the logs are printed and also saved there as they arrive. \
                          This path can be templated with {name}, {namespace}, and {time} to write \
                          individual files for each pod in a range. (See 'help ranges').",
                     )
                     .takes_value(true),
+            )
+            .arg(
+                Arg::new("max-size")
+                    .long("max-size")
+                    .value_name("SIZE")
+                    .requires("output")
+                    .help(
+                        "Rotate the --output file when it would grow past SIZE (like 50M): it's \
+                         moved to PATH.1, the older ones to PATH.2 and so on, up to PATH.5",
+                    )
+                    .validator(|s| parse_size(s).map(|_| ()))
+                    .takes_value(true),
             );
         k8s_if_ge_1_17! {
             let ret = ret.arg(
@@ -304,7 +882,39 @@ command!(
                     .takes_value(false)
             );
         }
-        ret
+        ret.after_help(
+            "Examples:
+  # print the last 100 lines of the logs of the nginx container
+  logs nginx --tail 100
+
+  # follow the logs, only showing lines that contain ERROR
+  logs -f --grep ERROR
+
+  # follow the logs, without the health check noise
+  logs -f --grep 'GET /healthz' --invert
+
+  # follow the logs of every container of the selected range of pods, interleaved, with each line
+  # tagged with the pod and container it's from
+  logs -f -a
+
+  # the last 10 minutes of logs, with timestamps in local time
+  logs --since 10m -L
+
+  # logs from around an incident that started at 10:00 today
+  logs --since-time 09:55 --tail 1000
+
+  # print the logs of the last run of a container that crashed
+  logs -p
+
+  # see what the init container a pod is stuck on is doing
+  logs --init
+
+  # save the logs of every pod in the selected range to its own file
+  logs -o /tmp/{name}.log
+
+  # follow the logs, saving them too, in files of at most 50MiB
+  logs -f -o /tmp/web.log --max-size 50M",
+        )
     },
     vec!["logs"],
     vec![&completer::container_completer],
@@ -340,10 +950,17 @@ command!(
             };
             opts.since_seconds = Some(dur);
         }
-        if matches.is_present("sinceTime") {
-            let specified =
-                DateTime::parse_from_rfc3339(matches.value_of("sinceTime").unwrap()).unwrap();
-            let dur = Utc::now().signed_duration_since(specified.with_timezone(&Utc));
+        if let Some(since_time) = matches.value_of("sinceTime") {
+            let now = Utc::now();
+            let since = parse_time(since_time, now, configured_timezone(env))
+                .map_err(ClickError::CommandError)?;
+            let dur = now.signed_duration_since(since);
+            if dur.num_seconds() <= 0 {
+                return Err(ClickError::CommandError(format!(
+                    "--since-time {} is in the future",
+                    since_time
+                )));
+            }
             opts.since_seconds = Some(dur.num_seconds());
         }
         let timeout = if matches.is_present("follow") {
@@ -351,24 +968,80 @@ command!(
         } else {
             Some(Duration::new(20, 0)) // TODO what's a reasonable timeout here?
         };
-        if matches.is_present("timestamps") {
+        if matches.is_present("timestamps") || matches.is_present("local-time") {
             opts.timestamps = Some(true);
         }
 
+        let filter = matches.value_of("grep").map(|pattern| LineFilter {
+            regex: Regex::new(pattern).unwrap(), // validated
+            invert: matches.is_present("invert"),
+        });
+        let all_containers = matches.is_present("all-containers");
+        let pods: Vec<KObj> = match env.current_selection() {
+            ObjectSelection::Single(obj) => vec![obj.clone()],
+            ObjectSelection::Range(objs) => objs.clone(),
+            ObjectSelection::None => vec![],
+        };
+        // following more than one container, or asking for all of them, shows them all at once
+        if (pods.len() > 1 && opts.follow == Some(true)) || (all_containers && !pods.is_empty()) {
+            if matches.is_present("output") {
+                return Err(ClickError::CommandError(
+                    "--output can't be used when showing several containers at once".to_string(),
+                ));
+            }
+            let mut sources = vec![];
+            for pod in pods.into_iter() {
+                let containers = match &pod.typ {
+                    ObjType::Pod { containers } if all_containers => containers.clone(),
+                    ObjType::Pod { .. } => match matches.value_of("init") {
+                        None if matches.is_present("init") => vec![pick_init_container(&pod, env)?],
+                        Some(init) => vec![init.to_string()],
+                        None => vec![matches
+                            .value_of("container")
+                            .unwrap_or_else(|| pick_container(&pod, writer))
+                            .to_string()],
+                    },
+                    _ => {
+                        return Err(ClickError::CommandError(
+                            "Logs only available on a pod".to_string(),
+                        ))
+                    }
+                };
+                sources.extend(containers.into_iter().map(|cont| (pod.clone(), cont)));
+            }
+            return multi_logs(
+                env,
+                &sources,
+                opts,
+                timeout,
+                matches.is_present("local-time"),
+                filter.as_ref(),
+                writer,
+            );
+        }
+
         env.apply_to_selection(
             writer,
             Some(&env.click_config.range_separator),
             |obj, writer| {
                 if obj.is_pod() {
+                    let init = match matches.value_of("init") {
+                        None if matches.is_present("init") => Some(pick_init_container(obj, env)?),
+                        init => init.map(str::to_string),
+                    };
                     do_logs(
                         obj,
                         env,
                         opts,
-                        matches.value_of("container"),
+                        init.as_deref().or_else(|| matches.value_of("container")),
                         matches.value_of("output"),
                         matches.is_present("editor"),
                         matches.value_of("editor"),
                         timeout,
+                        !matches.is_present("no-reconnect"),
+                        matches.is_present("local-time"),
+                        filter.as_ref(),
+                        matches.value_of("max-size").map(|s| parse_size(s).unwrap()), // validated
                         writer,
                     )
                 } else {
@@ -380,3 +1053,217 @@ command!(
         )
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(init_statuses: serde_json::Value) -> api::Pod {
+        serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "web-1" },
+            "spec": {
+                "initContainers": [
+                    { "name": "fetch" },
+                    { "name": "migrate" },
+                ],
+                "containers": [{ "name": "web" }],
+            },
+            "status": { "initContainerStatuses": init_statuses },
+        }))
+        .unwrap()
+    }
+
+    fn status(name: &str, state: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "name": name, "image": "", "imageID": "", "ready": false, "restartCount": 0,
+            "state": state,
+        })
+    }
+
+    #[test]
+    fn test_print_log_line() {
+        let env = Env::new(
+            crate::config::get_test_config(),
+            crate::config::ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        let mut writer = ClickWriter::with_buffer(vec![], false);
+        writer.start_capture();
+        let pod = KObj {
+            name: "web-1".to_string(),
+            namespace: Some("prod".to_string()),
+            typ: ObjType::Pod {
+                containers: vec!["app".to_string()],
+            },
+        };
+        let tag = source_tag(&pod, "app");
+        print_log_line(&env, &mut writer, Some(&tag), "started\n", false, false);
+        print_log_line(&env, &mut writer, Some(&tag), "no newline", false, false);
+        print_log_line(&env, &mut writer, None, "untagged\n", false, false);
+        assert_eq!(
+            String::from_utf8(writer.take_capture().unwrap()).unwrap(),
+            "[web-1/app] started\n[web-1/app] no newline\nuntagged\n"
+        );
+    }
+
+    #[test]
+    fn test_finished_reason() {
+        let pod = |phase: &str, policy: &str, state: serde_json::Value| -> api::Pod {
+            serde_json::from_value(serde_json::json!({
+                "metadata": { "name": "web-1" },
+                "spec": { "containers": [{ "name": "web" }], "restartPolicy": policy },
+                "status": { "phase": phase, "containerStatuses": [status("web", state)] },
+            }))
+            .unwrap()
+        };
+        let exited = |code: i32| serde_json::json!({ "terminated": { "exitCode": code } });
+        let running = serde_json::json!({ "running": {} });
+        assert_eq!(
+            finished_reason(&pod("Running", "Always", exited(1)), "web"),
+            None
+        );
+        assert_eq!(
+            finished_reason(&pod("Running", "OnFailure", exited(1)), "web"),
+            None
+        );
+        assert_eq!(
+            finished_reason(&pod("Running", "OnFailure", exited(0)), "web"),
+            Some("web has completed".to_string())
+        );
+        assert_eq!(
+            finished_reason(&pod("Running", "Never", exited(1)), "web"),
+            Some("web has exited and won't be restarted".to_string())
+        );
+        assert_eq!(
+            finished_reason(&pod("Running", "Never", running.clone()), "web"),
+            None
+        );
+        assert_eq!(
+            finished_reason(&pod("Failed", "Always", running), "web"),
+            Some("pod has failed".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_since_time() {
+        let (request, _) = api::Pod::read_namespaced_pod_log(
+            "web-1",
+            "prod",
+            api::ReadNamespacedPodLogOptional {
+                follow: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let since = DateTime::parse_from_rfc3339("2023-04-05T06:07:08.5+00:00").unwrap();
+        let request = with_since_time(request, since.with_timezone(&Utc)).unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "/api/v1/namespaces/prod/pods/web-1/log?&follow=true&sinceTime=2023-04-05T06%3A07%3A08Z"
+        );
+    }
+
+    #[test]
+    fn test_split_timestamp() {
+        let (time, rest) = split_timestamp("2023-04-05T06:07:08.123456789Z started\n").unwrap();
+        assert_eq!(time.timestamp(), 1680674828);
+        assert_eq!(rest, "started\n");
+        assert!(split_timestamp("started at 2023-04-05T06:07:08Z\n").is_none());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1000"), Ok(1000));
+        assert_eq!(parse_size("4K"), Ok(4096));
+        assert_eq!(parse_size("50M"), Ok(50 << 20));
+        assert_eq!(parse_size("2Gi"), Ok(2 << 30));
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("10MB").is_err());
+    }
+
+    #[test]
+    fn test_log_file_rotation() {
+        let dir = tempdir::TempDir::new("click-logs").unwrap();
+        let path = dir.path().join("app.log");
+        let mut file = LogFile::create(path.clone(), Some(10)).unwrap();
+        for n in 0..7 {
+            file.write(format!("line {}\n", n).as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+        let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+        // each 7 byte line would take the file past 10 bytes, so each is in a file of its own
+        assert_eq!(read("app.log"), "line 6\n");
+        assert_eq!(read("app.log.1"), "line 5\n");
+        assert_eq!(read("app.log.5"), "line 1\n");
+        assert!(!dir.path().join("app.log.6").exists());
+    }
+
+    #[test]
+    fn test_line_filter() {
+        let filter = LineFilter {
+            regex: Regex::new("ERROR|WARN").unwrap(),
+            invert: false,
+        };
+        assert!(filter.keeps("ERROR: disk full\n"));
+        assert!(!filter.keeps("INFO: started\n"));
+        let inverted = LineFilter {
+            invert: true,
+            ..filter
+        };
+        assert!(!inverted.keeps("WARN: slow request\n"));
+        assert!(inverted.keeps("INFO: started\n"));
+        assert!(keep_line(None, "anything\n"));
+        assert!(!keep_line(Some(&inverted), "ERROR: disk full\n"));
+    }
+
+    #[test]
+    fn test_localize_timestamp() {
+        let cc = crate::config::ClickConfig {
+            timezone: Some("+02:00".to_string()),
+            ..Default::default()
+        };
+        let env = Env::new(
+            crate::config::get_test_config(),
+            cc,
+            PathBuf::from("/tmp/click.config"),
+        );
+        assert_eq!(
+            localize_timestamp("2022-03-01T10:00:00.123456789Z started\n", &env),
+            "2022-03-01 12:00:00.123 started\n"
+        );
+        assert_eq!(
+            localize_timestamp("no timestamp here\n", &env),
+            "no timestamp here\n"
+        );
+    }
+
+    #[test]
+    fn test_current_init_container() {
+        let done = serde_json::json!({ "terminated": { "exitCode": 0 } });
+        let stuck = pod(serde_json::json!([
+            status("fetch", done.clone()),
+            status("migrate", serde_json::json!({ "running": {} })),
+        ]));
+        assert_eq!(current_init_container(&stuck), Some("migrate"));
+
+        let finished = pod(serde_json::json!([
+            status("fetch", done.clone()),
+            status("migrate", done),
+        ]));
+        assert_eq!(current_init_container(&finished), Some("migrate"));
+
+        let waiting = pod(serde_json::json!([status(
+            "fetch",
+            serde_json::json!({ "waiting": { "reason": "ImagePullBackOff" } })
+        )]));
+        assert_eq!(current_init_container(&waiting), Some("fetch"));
+
+        let no_init: api::Pod = serde_json::from_value(serde_json::json!({
+            "spec": { "containers": [{ "name": "web" }] },
+            "status": {},
+        }))
+        .unwrap();
+        assert_eq!(current_init_container(&no_init), None);
+    }
+}
@@ -17,50 +17,74 @@ use chrono::{DateTime, Duration};
 use clap::ArgMatches;
 use humantime::parse_duration;
 use k8s_openapi::{
-    apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    apimachinery::pkg::apis::meta::v1::{ObjectMeta, WatchEvent},
     http::{self, Request},
     List, ListOptional, ListResponse, ListableResource, Metadata, RequestError, ResponseBody,
 };
 use regex::Regex;
 use serde::Deserialize;
+use serde_json::Value;
 
+use crate::config::OutputView;
 use crate::env::Env;
 use crate::error::ClickError;
 use crate::kobj::KObj;
-use crate::output::ClickWriter;
-use crate::table::CellSpec;
+use crate::output::{ClickWriter, OutputFormat};
+use crate::table::{CellSpec, ColorType};
+use crate::values::val_str;
 
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
-use std::io::{stderr, Write};
+use std::io::{stderr, BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Arc;
 
 #[macro_use]
 pub mod command_def;
 
 pub mod alias; // commands for alias/unalias
+pub mod apiresources; // command to list the resources the cluster serves
+pub mod autoscalers; // commands relating to horizontal pod autoscalers
+pub mod certs; // command to check when the cluster's certificates expire
 pub mod click; // commands internal to click (setting config values, etc)
 pub mod configmaps; // commands relating to configmaps
 pub mod copy; // command to copy files to/from pods
 pub mod crds; // commands to query crd created objects
 pub mod daemonsets; // commands for daemonsets
+pub mod debug; // command to debug a pod with an ephemeral container
 pub mod delete; // command to delete objects
 pub mod deployments; // command to list deployments
 pub mod describe; // the describe command
+pub mod diff; // command to diff object versions
+pub mod dump; // command to write configmaps and secrets to files
 pub mod events; // commands to print events
 pub mod exec; // command to exec into pods
+pub mod explain; // command to show the documentation of resources and their fields
+pub mod images; // command to list the images in use
 pub mod jobs; // commands relating to jobs
 pub mod logs; // command to get pod logs
 pub mod namespaces; // commands relating to namespaces
+pub mod network; // command to show the networking details of pods
 pub mod nodes; // commands relating to nodes
+pub mod owners; // commands to walk owner references
+pub mod plugin; // external click-* commands found on the PATH
 pub mod pods; //commands relating to pods
 pub mod portforwards; // commands for forwarding ports
+pub mod problems; // command to show what's unhealthy
 pub mod replicasets; // commands relating to relicasets
+pub mod resources; // command to sum up the resources pods ask for
 pub mod secrets; // commands for secrets
+pub mod security; // command to show the security settings of pods
 pub mod services; // commands for services
 pub mod statefulsets; // commands for statefulsets
 pub mod storage; // commands relating to storage objects (like storageclass)
+pub mod token; // command to decode service account tokens
+pub mod top; // command to show resource usage from the metrics api
+pub mod volumeclaims; // commands relating to persistent volume claims
 pub mod volumes; // commands relating to volumes
+pub mod wait; // command to wait for objects to be ready or deleted
 
 #[cfg(feature = "argorollouts")]
 pub mod rollouts;
@@ -78,8 +102,43 @@ fn mapped_val(key: &str, map: &[(&'static str, &'static str)]) -> Option<&'stati
     None
 }
 
+/// Columns named with this prefix show the value of the label named by the rest of the column
+const LABEL_COL_PREFIX: &str = "label:";
+
 #[allow(clippy::too_many_arguments)] // factoring this out into structs just makes it worse
 pub fn run_list_command<T, F>(
+    matches: ArgMatches,
+    env: &mut Env,
+    writer: &mut ClickWriter,
+    cols: Vec<&str>,
+    request: Request<Vec<u8>>,
+    col_map: &[(&'static str, &'static str)],
+    extra_col_map: Option<&[(&'static str, &'static str)]>,
+    extractors: Option<&HashMap<String, Extractor<T>>>,
+    get_kobj: F,
+) -> Result<(), ClickError>
+where
+    T: ListableResource + Metadata<Ty = ObjectMeta> + for<'de> Deserialize<'de> + Debug,
+    F: Fn(&T) -> KObj,
+{
+    run_filtered_list_command(
+        matches,
+        env,
+        writer,
+        cols,
+        request,
+        col_map,
+        extra_col_map,
+        extractors,
+        get_kobj,
+        None,
+    )
+}
+
+/// Like run_list_command, but only lists the items that item_filter returns true for, if it's
+/// given. This is for filters the api server can't do, like on the computed status of a pod
+#[allow(clippy::too_many_arguments)]
+pub fn run_filtered_list_command<T, F>(
     matches: ArgMatches,
     env: &mut Env,
     writer: &mut ClickWriter,
@@ -89,12 +148,13 @@ pub fn run_list_command<T, F>(
     extra_col_map: Option<&[(&'static str, &'static str)]>,
     extractors: Option<&HashMap<String, Extractor<T>>>,
     get_kobj: F,
+    item_filter: Option<&dyn Fn(&T) -> bool>,
 ) -> Result<(), ClickError>
 where
     T: ListableResource + Metadata<Ty = ObjectMeta> + for<'de> Deserialize<'de> + Debug,
     F: Fn(&T) -> KObj,
 {
-    let regex = match crate::table::get_regex(&matches) {
+    let mut regex = match crate::table::get_regex(&matches) {
         Ok(r) => r,
         Err(s) => {
             writeln!(stderr(), "{}", s).unwrap_or(());
@@ -102,11 +162,73 @@ where
         }
     };
 
-    let list_res = env.run_on_context::<_, List<T>>(|c| c.execute_list(request));
-    if list_res.is_err() {
-        env.clear_last_objs();
+    // a view from the config provides defaults for the format, columns, sort and filter
+    let view = output_view(&matches, env);
+    let format = match view.as_ref() {
+        Some(view) => match view.format.as_deref() {
+            Some(format) => format.parse().map_err(|e| {
+                ClickError::CommandError(format!("Invalid format in view {}: {}", view.name, e))
+            })?,
+            None => OutputFormat::default(),
+        },
+        None => list_output_format(&matches, env)?,
+    };
+    if let (None, Some(pattern)) = (&regex, view.as_ref().and_then(|v| v.regex.as_ref())) {
+        regex =
+            Some(Regex::new(pattern).map_err(|e| {
+                ClickError::CommandError(format!("Invalid regex {}: {}", pattern, e))
+            })?);
+    }
+
+    // --sort-by takes either a column, or a path to sort the raw items by
+    let sort_by = if matches.is_valid_arg("sort-by") {
+        matches.value_of("sort-by")
+    } else {
+        None
+    }
+    .or_else(|| match matches.value_of("sort") {
+        Some(_) => None, // an explicit --sort overrides the view
+        None => view.as_ref().and_then(|v| v.sort.as_deref()),
+    });
+    let (sort_name, sort_path) = match sort_by {
+        Some(s) if s.starts_with('.') || s.starts_with('{') || s.starts_with('$') => {
+            (None, Some(s))
+        }
+        Some(s) => (Some(s), None),
+        None => (matches.value_of("sort"), None),
+    };
+
+    let name_filter = name_filter(&matches)?;
+
+    if !format.is_table() {
+        // fetch the raw value so we print everything the api server sent back
+        let value_res = env.run_on_context(|c| c.get_value(request));
+        if value_res.is_err() {
+            env.clear_last_objs();
+        }
+        let mut value = value_res?;
+        if let (Some(filter), Some(Value::Array(items))) =
+            (name_filter.as_ref(), value.get_mut("items"))
+        {
+            items.retain(|item| filter.is_match(&val_str("/metadata/name", item, "")));
+        }
+        if let (Some(item_filter), Some(Value::Array(items))) =
+            (item_filter, value.get_mut("items"))
+        {
+            items.retain(|item| {
+                serde_json::from_value::<T>(item.clone()).map_or(true, |item| item_filter(&item))
+            });
+        }
+        if let Some(path) = sort_path {
+            crate::jsonpath::sort_items(path, &mut value)?;
+        }
+        print_full_output(&format, &value, env, writer)?;
+        // the objects were printed in full, so there's nothing to warn about if some can't be read
+        let (list, _): (List<T>, _) = crate::k8s::list_from_value(value)?;
+        let kobjs: Vec<KObj> = list.items.iter().map(get_kobj).collect();
+        env.set_last_objs(kobjs);
+        return Ok(());
     }
-    let list = list_res?;
 
     let mut flags: Vec<&str> = if matches.is_valid_arg("show") {
         match matches.values_of("show") {
@@ -116,42 +238,91 @@ where
     } else {
         vec![]
     };
+    if let Some(view) = view.as_ref() {
+        flags.extend(view.show.iter().map(|s| s.as_str()));
+    }
+    if format == OutputFormat::Wide {
+        flags.push("all");
+    }
 
-    let sort = matches.value_of("sort").map(|s| {
-        let colname = s.to_lowercase();
-        if let Some(col) = mapped_val(&colname, col_map) {
-            command_def::SortCol(col)
-        } else if let Some(ecm) = extra_col_map {
-            let mut func = None;
-            for (flag, col) in ecm.iter() {
-                if flag.eq(&colname) {
-                    flags.push(flag);
-                    func = Some(command_def::SortCol(col));
+    let sort = match sort_name {
+        Some(s) => {
+            let colname = s.to_lowercase();
+            let col = mapped_val(&colname, col_map).or_else(|| {
+                // extra cols need to be shown to be sorted by
+                extra_col_map.and_then(|ecm| {
+                    ecm.iter()
+                        .find(|(flag, _)| flag.eq(&colname))
+                        .map(|(flag, col)| {
+                            flags.push(flag);
+                            *col
+                        })
+                })
+            });
+            match col {
+                Some(col) => Some(command_def::SortCol(col)),
+                None => {
+                    return Err(ClickError::CommandError(format!(
+                        "Can't sort by '{}', it's not a column or a path (paths start with .)",
+                        s
+                    )))
                 }
             }
-            match func {
-                Some(f) => f,
-                None => panic!("Shouldn't be allowed to ask to sort by: {}", colname),
-            }
-        } else {
-            panic!("Shouldn't be allowed to ask to sort by: {}", colname);
         }
-    });
+        None => None,
+    };
 
     if let Some(ecm) = extra_col_map {
-        // if we're not in a namespace, we want to add a namespace col if it's in extra_col_map
-        if env.namespace.is_none() && mapped_val("namespace", ecm).is_some() {
+        // if we're listing all namespaces, we want to add a namespace col if it's in extra_col_map
+        if list_namespace(&matches, env).is_none() && mapped_val("namespace", ecm).is_some() {
             flags.push("namespace");
         }
 
-        let labels_present = if matches.is_valid_arg("labels") {
-            matches.is_present("labels")
-        } else {
-            false
-        };
+        let labels_present = ["labels", "show-labels"]
+            .iter()
+            .any(|arg| matches.is_valid_arg(arg) && matches.is_present(arg));
         command_def::add_extra_cols(&mut cols, labels_present, flags, ecm);
     }
 
+    // a column for each label asked for via --label-columns
+    let label_cols: Vec<String> = if matches.is_valid_arg("label-columns") {
+        match matches.values_of("label-columns") {
+            Some(keys) => keys
+                .map(|key| format!("{}{}", LABEL_COL_PREFIX, key))
+                .collect(),
+            None => vec![],
+        }
+    } else {
+        vec![]
+    };
+    let cols: Vec<&str> = cols
+        .into_iter()
+        .chain(label_cols.iter().map(|col| col.as_str()))
+        .collect();
+    let cols = select_columns(cols, &matches, env, col_map, extra_col_map)?;
+
+    let keep = |item: &T| {
+        name_filter
+            .as_ref()
+            .is_none_or(|filter| filter.is_match(extract_name(item).as_deref().unwrap_or("")))
+            && item_filter.is_none_or(|item_filter| item_filter(item))
+    };
+
+    let reverse = matches.is_present("reverse") || view.as_ref().is_some_and(|v| v.reverse);
+    if let Some(interval) = watch_interval(&matches)? {
+        check_watch_format(&format)?;
+        return watch_list(
+            env, writer, &cols, request, sort_path, &keep, extractors, regex, sort, reverse,
+            interval, get_kobj,
+        );
+    }
+
+    let list_res = fetch_list(env, request, sort_path, &keep);
+    if list_res.is_err() {
+        env.clear_last_objs();
+    }
+    let list = list_res?;
+
     handle_list_result(
         env,
         writer,
@@ -160,11 +331,275 @@ where
         extractors,
         regex,
         sort,
-        matches.is_present("reverse"),
+        reverse,
+        &format,
+        matches.is_valid_arg("no-headers") && matches.is_present("no-headers"),
+        matches.is_valid_arg("quiet") && matches.is_present("quiet"),
         get_kobj,
     )
 }
 
+// find the column named name (case-insensitive), which can be a column flag (like nodeip) or its
+// title (like "Node IP")
+fn find_column<'a>(
+    name: &str,
+    cols: &[&'a str],
+    col_map: &[(&'static str, &'static str)],
+    extra_col_map: Option<&[(&'static str, &'static str)]>,
+) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    col_map
+        .iter()
+        .chain(extra_col_map.unwrap_or(&[]).iter())
+        .find(|(flag, col)| *flag == name || col.to_lowercase() == name)
+        .map(|(_, col)| *col)
+        .or_else(|| cols.iter().find(|col| col.to_lowercase() == name).copied())
+}
+
+// Apply --columns and --hide (or the defaults for the command from the config) to cols
+fn select_columns<'a>(
+    cols: Vec<&'a str>,
+    matches: &ArgMatches,
+    env: &Env,
+    col_map: &[(&'static str, &'static str)],
+    extra_col_map: Option<&[(&'static str, &'static str)]>,
+) -> Result<Vec<&'a str>, ClickError> {
+    // keyed by the command's name, rather than the alias it was run with
+    let defaults = env.click_config.list_columns.get(env.command_name());
+    let arg_values = |arg: &str| -> Option<Vec<String>> {
+        if matches.is_valid_arg(arg) {
+            matches
+                .values_of(arg)
+                .map(|vals| vals.map(|v| v.to_string()).collect())
+        } else {
+            None
+        }
+    };
+    let columns = arg_values("columns")
+        .or_else(|| defaults.map(|d| d.columns.clone()))
+        .unwrap_or_default();
+    let hide = arg_values("hide")
+        .or_else(|| defaults.map(|d| d.hide.clone()))
+        .unwrap_or_default();
+
+    let mut cols = if columns.is_empty() {
+        cols
+    } else {
+        let mut selected = vec![];
+        for name in columns.iter() {
+            match find_column(name, &cols, col_map, extra_col_map) {
+                Some(col) => selected.push(col),
+                None => {
+                    return Err(ClickError::CommandError(format!(
+                        "Unknown column '{}'. Valid columns are: {}",
+                        name,
+                        col_map
+                            .iter()
+                            .chain(extra_col_map.unwrap_or(&[]).iter())
+                            .map(|(flag, _)| *flag)
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    )))
+                }
+            }
+        }
+        selected
+    };
+    for name in hide.iter() {
+        match find_column(name, &cols, col_map, extra_col_map) {
+            Some(col) => cols.retain(|c| *c != col),
+            None => {
+                return Err(ClickError::CommandError(format!(
+                    "Can't hide '{}', it's not a column",
+                    name
+                )))
+            }
+        }
+    }
+    Ok(cols)
+}
+
+/// Get the output view from the config named by the --output arg, if any. Built in formats take
+/// precedence over views with the same name
+fn output_view(matches: &ArgMatches, env: &Env) -> Option<OutputView> {
+    if !matches.is_valid_arg("output") {
+        return None;
+    }
+    let mut values = matches.values_of("output")?;
+    let name = values.next()?;
+    if values.next().is_some() || name.parse::<OutputFormat>().is_ok() {
+        return None;
+    }
+    env.click_config
+        .output_views
+        .iter()
+        .find(|view| view.name == name)
+        .cloned()
+}
+
+/// Get the output format requested via the --output arg. Returns the default (table) format for
+/// commands that don't have an --output arg
+pub fn output_format(matches: &ArgMatches) -> Result<OutputFormat, ClickError> {
+    if !matches.is_valid_arg("output") {
+        return Ok(OutputFormat::default());
+    }
+    match matches.values_of("output") {
+        Some(mut values) => {
+            let first = values.next().unwrap(); // safe, clap requires at least one value
+            let format = match values.next() {
+                // allow '-o json .items[0]' as well as '-o jsonpath={.items[0]}'
+                Some(arg) if !first.contains('=') => OutputFormat::new(first, Some(arg)),
+                Some(_) => Err(format!("Output format {} takes only one argument", first)),
+                None => first.parse::<OutputFormat>(),
+            };
+            format.map_err(ClickError::CommandError)
+        }
+        None => Ok(OutputFormat::default()),
+    }
+}
+
+/// The namespace a list command should list objects in: the current one, unless
+/// --all-namespaces was given. None means all namespaces
+pub fn list_namespace<'a>(matches: &ArgMatches, env: &'a Env) -> Option<&'a str> {
+    if matches.is_valid_arg("all-namespaces") && matches.is_present("all-namespaces") {
+        None
+    } else {
+        env.namespace.as_deref()
+    }
+}
+
+/// Get the options to send with the request of a list command, from the args every list command
+/// takes
+pub fn list_options<'a>(matches: &'a ArgMatches) -> ListOptional<'a> {
+    ListOptional {
+        label_selector: matches.value_of("label"),
+        field_selector: matches.value_of("field-selector"),
+        ..Default::default()
+    }
+}
+
+/// Get the regex from --filter or /REGEX/, that the names of listed objects must match
+pub fn name_filter(matches: &ArgMatches) -> Result<Option<Regex>, ClickError> {
+    let pattern = if matches.is_valid_arg("filter") {
+        matches.value_of("filter").or_else(|| {
+            matches
+                .value_of("filter-pattern")
+                .map(|p| &p[1..p.len() - 1]) // validated to be between slashes
+        })
+    } else {
+        None
+    };
+    pattern
+        .map(|pattern| {
+            Regex::new(pattern)
+                .map_err(|e| ClickError::CommandError(format!("Invalid filter {}: {}", pattern, e)))
+        })
+        .transpose()
+}
+
+/// Get the output format for a list command: the one requested via --output, or the
+/// default_output from the config if there's no --output arg
+fn list_output_format(matches: &ArgMatches, env: &Env) -> Result<OutputFormat, ClickError> {
+    if matches.is_valid_arg("output") && !matches.is_present("output") {
+        if let Some(default) = env.click_config.default_output.as_deref() {
+            return default.parse().map_err(|e| {
+                ClickError::CommandError(format!("Invalid default_output {}: {}", default, e))
+            });
+        }
+    }
+    output_format(matches)
+}
+
+/// Print a raw value returned from the api server in the specified (non-table) format
+pub fn print_full_output(
+    format: &OutputFormat,
+    value: &serde_json::Value,
+    env: &Env,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    match format {
+        OutputFormat::Yaml => writer.print_yaml(value)?,
+        OutputFormat::Json(None) => {
+            writer.pretty_color_json(value)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Json(Some(filter)) => {
+            for selected in crate::jsonpath::select(filter, value)?.into_iter() {
+                match selected {
+                    // print strings raw, so they're easy to use in scripts
+                    serde_json::Value::String(s) => clickwriteln!(writer, "{}", s),
+                    _ => {
+                        writer.pretty_color_json(selected)?;
+                        writeln!(writer)?;
+                    }
+                }
+            }
+        }
+        OutputFormat::JsonPath(template) => {
+            let template = crate::jsonpath::Template::parse(template)?;
+            clickwriteln!(writer, "{}", template.render(value));
+        }
+        OutputFormat::CustomColumns(columns) => print_custom_columns(columns, value, env, writer)?,
+        OutputFormat::Template(template) => {
+            let template = crate::template::Template::parse(template)?;
+            clickwrite!(writer, "{}", template.render(value));
+        }
+        OutputFormat::Table
+        | OutputFormat::Wide
+        | OutputFormat::Csv
+        | OutputFormat::Tsv
+        | OutputFormat::Markdown => {
+            return Err(ClickError::CommandError(
+                "Table output not supported for raw values".to_string(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Print a table with the specified (title, path) columns. If value is a list, there is a row
+/// (numbered so it can be selected) for each item, otherwise a single row for the object
+fn print_custom_columns(
+    columns: &[(String, String)],
+    value: &serde_json::Value,
+    env: &Env,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let templates = columns
+        .iter()
+        .map(|(_, path)| crate::jsonpath::Template::parse(path))
+        .collect::<Result<Vec<_>, ClickError>>()?;
+    let (items, include_index) = match value.get("items").and_then(|items| items.as_array()) {
+        Some(items) => (items.iter().collect(), true),
+        None => (vec![value], false),
+    };
+
+    let mut titles: Vec<&str> = if include_index { vec!["####"] } else { vec![] };
+    titles.extend(columns.iter().map(|(title, _)| title.as_str()));
+
+    let rows: Vec<RowSpec> = items
+        .into_iter()
+        .map(|item| {
+            let mut row: RowSpec = if include_index {
+                vec![CellSpec::new_index()]
+            } else {
+                vec![]
+            };
+            for template in templates.iter() {
+                let rendered = template.render(item);
+                if rendered.is_empty() {
+                    row.push("<none>".into());
+                } else {
+                    row.push(rendered.into());
+                }
+            }
+            row
+        })
+        .collect();
+    crate::table::print_table(titles, rows, env, writer);
+    Ok(())
+}
+
 /// Uppercase the first letter of the given str
 pub fn uppercase_first(s: &str) -> String {
     let mut cs = s.chars();
@@ -179,13 +614,6 @@ fn valid_duration(s: &str) -> Result<(), String> {
     parse_duration(s).map(|_| ()).map_err(|e| e.to_string())
 }
 
-/// a clap validator for rfc3339 dates
-fn valid_date(s: &str) -> Result<(), String> {
-    DateTime::parse_from_rfc3339(s)
-        .map(|_| ())
-        .map_err(|e| e.to_string())
-}
-
 /// a clap validator for u32
 pub fn valid_u32(s: &str) -> Result<(), String> {
     s.parse::<u32>().map(|_| ()).map_err(|e| e.to_string())
@@ -212,6 +640,9 @@ pub fn handle_list_result<'a, T, F>(
     regex: Option<Regex>,
     sort: Option<command_def::SortCol>,
     reverse: bool,
+    format: &OutputFormat,
+    no_headers: bool,
+    quiet: bool,
     get_kobj: F,
 ) -> Result<(), ClickError>
 where
@@ -223,9 +654,41 @@ where
     let mut titles: Vec<&str> = vec!["####"];
     titles.reserve(cols.len());
     for col in cols.iter() {
-        titles.push(col);
+        titles.push(col.strip_prefix(LABEL_COL_PREFIX).unwrap_or(col));
     }
 
+    sort_specs(&mut specs, &cols, sort, writer);
+
+    let (kobjs, rows): (Vec<KObj>, Vec<RowSpec>) = if reverse {
+        specs.into_iter().rev().unzip()
+    } else {
+        specs.into_iter().unzip()
+    };
+
+    if quiet {
+        for kobj in kobjs.iter() {
+            clickwriteln!(writer, "{}", kobj.name());
+        }
+    } else if let Some(sep) = format.separator() {
+        crate::table::print_delimited(&titles[1..], rows, sep, !no_headers, env, writer);
+    } else if *format == OutputFormat::Markdown {
+        crate::table::print_markdown(&titles[1..], rows, env, writer);
+    } else if no_headers {
+        crate::table::print_table_no_headers(rows, env, writer);
+    } else {
+        crate::table::print_table(titles, rows, env, writer);
+    }
+    env.set_last_objs(kobjs);
+    Ok(())
+}
+
+// sort specs built by build_specs by the specified column
+fn sort_specs(
+    specs: &mut [(KObj, RowSpec)],
+    cols: &[&str],
+    sort: Option<command_def::SortCol>,
+    writer: &mut ClickWriter,
+) {
     if let Some(command_def::SortCol(colname)) = sort {
         let index = cols.iter().position(|&c| c == colname);
         match index {
@@ -240,18 +703,270 @@ where
             ),
         }
     }
+}
 
-    let (kobjs, rows): (Vec<KObj>, Vec<RowSpec>) = if reverse {
-        specs.into_iter().rev().unzip()
-    } else {
-        specs.into_iter().unzip()
+/// Fetch a list. If sort_path is set, the items are sorted by the value at that path
+fn fetch_list<T>(
+    env: &Env,
+    request: Request<Vec<u8>>,
+    sort_path: Option<&str>,
+    keep: &dyn Fn(&T) -> bool,
+) -> Result<List<T>, ClickError>
+where
+    T: ListableResource + Metadata<Ty = ObjectMeta> + for<'de> Deserialize<'de> + Debug,
+{
+    let mut list: List<T> = match sort_path {
+        Some(path) => {
+            let mut value = env.run_on_context(|c| c.get_value(request))?;
+            crate::jsonpath::sort_items(path, &mut value)?;
+            env.run_on_context(|c| c.list_from_value(value))?
+        }
+        None => env.run_on_context(|c| c.execute_list(request))?,
     };
+    list.items.retain(|item| keep(item));
+    Ok(list)
+}
 
-    crate::table::print_table(titles, rows, env, writer);
+// copy a request so it can be sent again
+fn copy_request(request: &Request<Vec<u8>>) -> Request<Vec<u8>> {
+    let mut builder = Request::builder()
+        .method(request.method().clone())
+        .uri(request.uri().clone());
+    for (name, value) in request.headers().iter() {
+        builder = builder.header(name, value);
+    }
+    builder.body(request.body().clone()).unwrap() // safe, the parts came from a valid request
+}
+
+/// --watch redraws a table, so it can't be used with formats like json or yaml
+pub fn check_watch_format(format: &OutputFormat) -> Result<(), ClickError> {
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => Ok(()),
+        _ => Err(ClickError::CommandError(
+            "--watch only works with table output (the default, or -o wide)".to_string(),
+        )),
+    }
+}
+
+/// Get the refresh interval requested via --watch, if any
+fn watch_interval(matches: &ArgMatches) -> Result<Option<u64>, ClickError> {
+    if !(matches.is_valid_arg("watch") && matches.is_present("watch")) {
+        return Ok(None);
+    }
+    match matches.value_of("watch") {
+        Some(secs) => match secs.parse::<u64>() {
+            Ok(secs) if secs > 0 => Ok(Some(secs)),
+            _ => Err(ClickError::CommandError(format!(
+                "Invalid watch interval '{}', must be a number of seconds greater than 0",
+                secs
+            ))),
+        },
+        None => Ok(Some(command_def::DEFAULT_WATCH_SECS)),
+    }
+}
+
+/// Keep re-fetching and printing a list every interval seconds until the user hits ctrl-c. Rows
+/// that are new since the last refresh are shown in the success color, and changed cells in the
+/// warning color. Objects that went away are listed below the table.
+#[allow(clippy::too_many_arguments)]
+fn watch_list<T, F>(
+    env: &mut Env,
+    writer: &mut ClickWriter,
+    cols: &[&str],
+    request: Request<Vec<u8>>,
+    sort_path: Option<&str>,
+    keep: &dyn Fn(&T) -> bool,
+    extractors: Option<&HashMap<String, Extractor<T>>>,
+    regex: Option<Regex>,
+    sort: Option<command_def::SortCol>,
+    reverse: bool,
+    interval: u64,
+    get_kobj: F,
+) -> Result<(), ClickError>
+where
+    T: ListableResource + Metadata<Ty = ObjectMeta> + for<'de> Deserialize<'de> + Debug,
+    F: Fn(&T) -> KObj,
+{
+    use crossterm::{cursor::MoveTo, execute, terminal::Clear, terminal::ClearType};
+
+    let mut titles: Vec<&str> = vec!["####"];
+    titles.extend(
+        cols.iter()
+            .map(|col| col.strip_prefix(LABEL_COL_PREFIX).unwrap_or(col)),
+    );
+
+    let key = |kobj: &KObj| {
+        format!(
+            "{}/{}",
+            kobj.namespace.as_deref().unwrap_or(""),
+            kobj.name()
+        )
+    };
+    let mut previous: Option<HashMap<String, Vec<String>>> = None;
+    let mut kobjs = vec![];
+    env.ctrlcbool.store(false, Ordering::SeqCst);
+    while !env.ctrlcbool.load(Ordering::SeqCst) {
+        // an error is shown in place of the table, and the next refresh tries again
+        let fetched = fetch_list(env, copy_request(&request), sort_path, keep);
+        if writer.is_tty() {
+            execute!(writer, Clear(ClearType::All), MoveTo(0, 0))?;
+        }
+        clickwriteln!(
+            writer,
+            "Every {}s, ctrl-c to stop. Last updated: {}",
+            interval,
+            chrono::Local::now().format("%H:%M:%S")
+        );
+        match fetched {
+            Ok(list) => {
+                let mut specs =
+                    build_specs(cols, &list, extractors, true, regex.clone(), &get_kobj);
+                sort_specs(
+                    &mut specs,
+                    cols,
+                    sort.as_ref().map(|s| command_def::SortCol(s.0)),
+                    writer,
+                );
+                if reverse {
+                    specs.reverse();
+                }
+
+                let mut current = HashMap::new();
+                for (kobj, row) in specs.iter_mut() {
+                    let texts: Vec<String> = row.iter().map(|cell| cell.text(0, env)).collect();
+                    match previous.as_ref().map(|prev| prev.get(&key(kobj))) {
+                        Some(None) => {
+                            for cell in row.iter_mut().skip(1) {
+                                cell.fg = Some(ColorType::Success.into());
+                            }
+                        }
+                        Some(Some(prev_texts)) => {
+                            for (i, cell) in row.iter_mut().enumerate().skip(1) {
+                                // times change on every refresh, so don't count as changes
+                                if !cell.is_time() && prev_texts.get(i) != texts.get(i) {
+                                    cell.fg = Some(ColorType::Warn.into());
+                                }
+                            }
+                        }
+                        None => {} // first time through, nothing to compare to
+                    }
+                    current.insert(key(kobj), texts);
+                }
+
+                let (new_kobjs, rows): (Vec<KObj>, Vec<RowSpec>) = specs.into_iter().unzip();
+                crate::table::print_table(titles.clone(), rows, env, writer);
+                if let Some(prev) = previous.as_ref() {
+                    let mut removed: Vec<&String> =
+                        prev.keys().filter(|k| !current.contains_key(*k)).collect();
+                    removed.sort();
+                    for name in removed.into_iter() {
+                        clickwriteln!(writer, "{}", env.styles.danger(&format!("- {}", name)));
+                    }
+                }
+                kobjs = new_kobjs;
+                previous = Some(current);
+            }
+            Err(e) => {
+                clickwriteln!(writer, "{}", env.styles.danger(&format!("Error: {}", e)));
+            }
+        }
+        writer.flush()?;
+        env.show_warnings();
+
+        // sleep in small steps so ctrl-c is noticed quickly
+        for _ in 0..(interval * 10) {
+            if env.ctrlcbool.load(Ordering::SeqCst) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+    }
+    env.ctrlcbool.store(false, Ordering::SeqCst);
     env.set_last_objs(kobjs);
     Ok(())
 }
 
+/// Send a watch request and call on_event with each event the api server sends, until the user
+/// hits ctrl-c, the api server ends the watch, or on_event returns false. on_event is called with
+/// None when no events have arrived for a second, so output can be flushed. Returns true if the
+/// user stopped the watch
+pub fn stream_watch<T, F>(
+    env: &Env,
+    request: Request<Vec<u8>>,
+    mut on_event: F,
+) -> Result<bool, ClickError>
+where
+    T: for<'de> Deserialize<'de>,
+    F: FnMut(Option<WatchEvent<T>>) -> Result<bool, ClickError>,
+{
+    let reader = env.run_on_context(|c| c.execute_watch(request))?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let receiver = read_watch_lines(BufReader::new(reader), stop.clone());
+    env.ctrlcbool.store(false, Ordering::SeqCst);
+    let result = loop {
+        if env.ctrlcbool.load(Ordering::SeqCst) {
+            env.ctrlcbool.store(false, Ordering::SeqCst);
+            break Ok(true);
+        }
+        let event = match receiver.recv_timeout(std::time::Duration::from_secs(1)) {
+            // an event that can't be read is skipped, with a warning
+            Ok(line) => match env.run_on_context(|c| Ok(c.watch_event_from_str::<T>(&line)))? {
+                Some(WatchEvent::ErrorStatus(status)) => {
+                    break Err(ClickError::CommandError(format!(
+                        "Watch failed: {}",
+                        status.message.as_deref().unwrap_or("no message returned")
+                    )));
+                }
+                Some(event) => Some(event),
+                None => continue,
+            },
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break Ok(false),
+        };
+        match on_event(event) {
+            Ok(true) => {}
+            res => break res,
+        }
+    };
+    stop.store(true, Ordering::SeqCst);
+    result
+}
+
+/// Read the lines of a watch in a thread, since the reads block and ctrl-c has to be checked for.
+/// Reads of a watch time out when it's quiet, which is when the thread checks stop, so it drops
+/// the response, closing the connection, soon after the watch is stopped
+fn read_watch_lines(
+    mut reader: BufReader<reqwest::blocking::Response>,
+    stop: Arc<AtomicBool>,
+) -> Receiver<String> {
+    let (sender, receiver) = channel();
+    std::thread::spawn(move || {
+        // kept over timed out reads, which can happen part way through a line
+        let mut line = vec![];
+        while !stop.load(Ordering::SeqCst) {
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {
+                    let text = String::from_utf8_lossy(&line).into_owned();
+                    line.clear();
+                    if sender.send(text).is_err() {
+                        break; // the watch was stopped
+                    }
+                }
+                Err(e) if is_timeout(&e) => {}
+                Err(_) => break,
+            }
+        }
+    });
+    receiver
+}
+
+fn is_timeout(e: &std::io::Error) -> bool {
+    e.get_ref()
+        .and_then(|e| e.downcast_ref::<reqwest::Error>())
+        .is_some_and(reqwest::Error::is_timeout)
+}
+
 // row building
 
 /* Build row specs and a kobj vec from data returned from k8s.
@@ -294,6 +1009,9 @@ where
                 "Labels" => row.push(extract_labels(item).into()),
                 "Name" => row.push(extract_name(item).into()),
                 "Namespace" => row.push(extract_namespace(item).into()),
+                _ if col.starts_with(LABEL_COL_PREFIX) => {
+                    row.push(extract_label(item, &col[LABEL_COL_PREFIX.len()..]).into())
+                }
                 _ => match extractors {
                     Some(extractors) => match extractors.get(*col) {
                         Some(extractor) => row.push(extractor(item).into()),
@@ -345,6 +1063,17 @@ pub fn extract_labels<T: Metadata<Ty = ObjectMeta>>(obj: &T) -> Option<Cow<'_, s
         .map(|labels| keyval_string(labels.iter(), None).into())
 }
 
+pub fn extract_label<'a, T: Metadata<Ty = ObjectMeta>>(
+    obj: &'a T,
+    key: &str,
+) -> Option<Cow<'a, str>> {
+    obj.metadata()
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get(key))
+        .map(|val| val.as_str().into())
+}
+
 // utility functions
 fn row_matches<'a>(row: &[CellSpec<'a>], regex: &Regex) -> bool {
     let mut has_match = false;
@@ -426,15 +1155,12 @@ where
 
 /// Get a read request for a custom url
 // used by features, so without them isn't used
-// type is from k8s_openapi, so we can't change it
-#[allow(dead_code, clippy::type_complexity)]
-pub fn get_read_request_for_url<T: k8s_openapi::Response>(
-    url: String,
-) -> Result<(Request<Vec<u8>>, fn(_: http::StatusCode) -> ResponseBody<T>), RequestError> {
+#[allow(dead_code)]
+pub fn get_read_request_for_url(url: String) -> Result<Request<Vec<u8>>, RequestError> {
     let request = http::Request::get(url);
     let body = vec![];
     match request.body(body) {
-        Ok(request) => Ok((request, ResponseBody::new)),
+        Ok(request) => Ok(request),
         Err(err) => Err(RequestError::Http(err)),
     }
 }
@@ -463,3 +1189,72 @@ pub fn get_list_request_for_url<T: ListableResource + for<'de> serde::Deserializ
         Err(err) => Err(RequestError::Http(err)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Command as ClapCommand;
+
+    fn filter_matches(args: &[&str]) -> clap::Result<ArgMatches> {
+        ClapCommand::new("pods")
+            .arg(command_def::filter_arg())
+            .arg(command_def::filter_pattern_arg())
+            .try_get_matches_from(args)
+    }
+
+    #[test]
+    fn test_name_filter() {
+        let matches = filter_matches(&["pods", "--filter", "^web-"]).unwrap();
+        let filter = name_filter(&matches).unwrap().unwrap();
+        assert!(filter.is_match("web-1"));
+        assert!(!filter.is_match("api-web-1"));
+
+        let matches = filter_matches(&["pods", "/^web-/"]).unwrap();
+        let filter = name_filter(&matches).unwrap().unwrap();
+        assert_eq!(filter.as_str(), "^web-");
+
+        let matches = filter_matches(&["pods"]).unwrap();
+        assert!(name_filter(&matches).unwrap().is_none());
+
+        assert!(filter_matches(&["pods", "web"]).is_err());
+        assert!(filter_matches(&["pods", "--filter", "a", "/b/"]).is_err());
+        let matches = filter_matches(&["pods", "--filter", "("]).unwrap();
+        assert!(name_filter(&matches).is_err());
+    }
+
+    #[test]
+    fn test_select_columns_config() {
+        let cc = crate::config::ClickConfig::from_reader(
+            "list_columns:\n  hpas:\n    hide: [age]\n".as_bytes(),
+        )
+        .unwrap();
+        let mut env = Env::new(
+            crate::config::get_test_config(),
+            cc,
+            std::path::PathBuf::from("/tmp/click.config"),
+        );
+        let clap = ClapCommand::new("hpas")
+            .arg(command_def::columns_arg())
+            .arg(command_def::hide_arg());
+        let col_map = [("name", "Name"), ("age", "Age")];
+        let cols = vec!["Name", "Age"];
+
+        // the config is keyed by the command's name, whatever alias it's run as
+        env.set_command_name("hpas");
+        let matches = clap.clone().try_get_matches_from(["hpa"]).unwrap();
+        let selected = select_columns(cols.clone(), &matches, &env, &col_map, None).unwrap();
+        assert_eq!(selected, vec!["Name"]);
+
+        let matches = clap
+            .clone()
+            .try_get_matches_from(["hpa", "--hide", "name"])
+            .unwrap();
+        let selected = select_columns(cols.clone(), &matches, &env, &col_map, None).unwrap();
+        assert_eq!(selected, vec!["Age"]);
+
+        env.set_command_name("deployments");
+        let matches = clap.try_get_matches_from(["deployments"]).unwrap();
+        let selected = select_columns(cols, &matches, &env, &col_map, None).unwrap();
+        assert_eq!(selected, vec!["Name", "Age"]);
+    }
+}
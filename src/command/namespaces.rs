@@ -17,7 +17,10 @@ use k8s_openapi::api::core::v1 as api;
 use rustyline::completion::Pair as RustlinePair;
 
 use crate::{
-    command::command_def::{exec_match, sort_arg, start_clap, Cmd},
+    command::command_def::{
+        columns_arg, exec_match, hide_arg, no_headers_arg, output_arg, quiet_arg, sort_arg,
+        sort_by_arg, start_clap, watch_arg, Cmd,
+    },
     command::{run_list_command, Extractor},
     completer,
     env::Env,
@@ -94,6 +97,13 @@ command!(
                 .takes_value(true),
         )
         .arg(sort_arg(COL_FLAGS, None))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
         .arg(
             Arg::new("reverse")
                 .short('R')
@@ -104,7 +114,12 @@ command!(
     },
     vec!["namespaces"],
     noop_complete!(),
-    no_named_complete!(),
+    [(
+        "regex".to_string(),
+        completer::namespace_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter()
+    .collect(),
     |matches, env, writer| {
         let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
         let (request, _response_body) = api::Namespace::list_namespace(Default::default())?;
@@ -0,0 +1,299 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::Command as ClapCommand;
+use k8s_openapi::api::core::v1 as api;
+use rustyline::completion::Pair as RustlinePair;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    completer,
+    describe::describe_table,
+    env::Env,
+    error::ClickError,
+    kobj::KObj,
+    output::ClickWriter,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+
+fn or_none(lines: Vec<String>) -> String {
+    if lines.is_empty() {
+        "<none>".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+/// The networking details of a pod, as rows of a description
+fn pod_network_rows(pod: &api::Pod) -> Vec<(&'static str, String)> {
+    let spec = pod.spec.as_ref();
+    let status = pod.status.as_ref();
+    let mut ips: Vec<String> = status
+        .and_then(|status| status.pod_ips.as_ref())
+        .map(|ips| ips.iter().filter_map(|ip| ip.ip.clone()).collect())
+        .unwrap_or_default();
+    if ips.is_empty() {
+        ips.extend(status.and_then(|status| status.pod_ip.clone()));
+    }
+    let host_network = spec.and_then(|spec| spec.host_network).unwrap_or(false);
+    let mut rows = vec![
+        (
+            "Pod IPs:",
+            if ips.is_empty() {
+                "<none>".to_string()
+            } else {
+                ips.join(", ")
+            },
+        ),
+        (
+            "Host IP:",
+            status
+                .and_then(|status| status.host_ip.clone())
+                .unwrap_or_else(|| "<none>".to_string()),
+        ),
+        ("Host Network:", host_network.to_string()),
+        (
+            "DNS Policy:",
+            spec.and_then(|spec| spec.dns_policy.clone())
+                .unwrap_or_else(|| "ClusterFirst".to_string()),
+        ),
+    ];
+
+    if let Some(config) = spec.and_then(|spec| spec.dns_config.as_ref()) {
+        let mut lines = vec![];
+        if let Some(nameservers) = config.nameservers.as_ref() {
+            lines.push(format!("nameservers: {}", nameservers.join(", ")));
+        }
+        if let Some(searches) = config.searches.as_ref() {
+            lines.push(format!("search: {}", searches.join(" ")));
+        }
+        if let Some(options) = config.options.as_ref() {
+            let options: Vec<String> = options
+                .iter()
+                .map(|option| match (&option.name, &option.value) {
+                    (Some(name), Some(value)) => format!("{}:{}", name, value),
+                    (Some(name), None) => name.clone(),
+                    _ => String::new(),
+                })
+                .collect();
+            lines.push(format!("options: {}", options.join(" ")));
+        }
+        rows.push(("DNS Config:", or_none(lines)));
+    }
+
+    if let Some(aliases) = spec.and_then(|spec| spec.host_aliases.as_ref()) {
+        let lines = aliases
+            .iter()
+            .map(|alias| {
+                format!(
+                    "{} {}",
+                    alias.ip.as_deref().unwrap_or("<none>"),
+                    alias
+                        .hostnames
+                        .iter()
+                        .flatten()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )
+            })
+            .collect();
+        rows.push(("Host Aliases:", or_none(lines)));
+    }
+
+    let mut ports = vec![];
+    for container in spec.iter().flat_map(|spec| spec.containers.iter()) {
+        for port in container.ports.iter().flatten() {
+            let mut line = format!(
+                "{}: {}/{}",
+                container.name,
+                port.container_port,
+                port.protocol.as_deref().unwrap_or("TCP")
+            );
+            if let Some(name) = port.name.as_ref() {
+                line.push_str(&format!(" ({})", name));
+            }
+            if let Some(host_port) = port.host_port {
+                line.push_str(&format!(", host port {}", host_port));
+            }
+            ports.push(line);
+        }
+    }
+    rows.push(("Ports:", or_none(ports)));
+    rows
+}
+
+/// The networking details of the node a pod is on, as rows of a description
+fn node_network_rows(node: &api::Node) -> Vec<(&'static str, String)> {
+    let addresses = node
+        .status
+        .as_ref()
+        .and_then(|status| status.addresses.as_ref())
+        .map(|addresses| {
+            addresses
+                .iter()
+                .map(|address| format!("{}: {}", address.type_, address.address))
+                .collect()
+        })
+        .unwrap_or_default();
+    let spec = node.spec.as_ref();
+    let mut cidrs: Vec<String> = spec
+        .and_then(|spec| spec.pod_cidrs.clone())
+        .unwrap_or_default();
+    if cidrs.is_empty() {
+        cidrs.extend(spec.and_then(|spec| spec.pod_cidr.clone()));
+    }
+    vec![
+        ("Node Addresses:", or_none(addresses)),
+        ("Node Pod CIDRs:", or_none(cidrs)),
+    ]
+}
+
+fn print_pod_network(env: &Env, obj: &KObj, writer: &mut ClickWriter) -> Result<(), ClickError> {
+    let pod: api::Pod = obj.read_kind(env, "network")?;
+    let mut table = describe_table();
+    table.add_row(vec![
+        "Pod:".to_string(),
+        format!(
+            "{}/{}",
+            obj.namespace.as_deref().unwrap_or_default(),
+            obj.name()
+        ),
+    ]);
+    for (title, value) in pod_network_rows(&pod) {
+        table.add_row(vec![title.to_string(), value]);
+    }
+    let node_name = pod.spec.as_ref().and_then(|spec| spec.node_name.as_deref());
+    table.add_row(vec![
+        "Node:".to_string(),
+        node_name.unwrap_or("<not scheduled>").to_string(),
+    ]);
+    if let Some(node_name) = node_name {
+        let (request, _) = api::Node::read_node(node_name, Default::default())?;
+        match env.run_on_context(|c| c.read_object::<api::Node>(request)) {
+            Ok(node) => {
+                for (title, value) in node_network_rows(&node) {
+                    table.add_row(vec![title.to_string(), value]);
+                }
+            }
+            // plenty of users can see pods but not nodes, so show what there is anyway
+            _ => {
+                table.add_row(vec![
+                    "Node Addresses:".to_string(),
+                    "<could not read node>".to_string(),
+                ]);
+            }
+        }
+    }
+    clickwriteln!(writer, "{table}");
+    Ok(())
+}
+
+command!(
+    Network,
+    "network",
+    "Show the networking details of the active pod(s): IPs, host network and DNS settings, ports, \
+     and the addresses of the node",
+    |clap: ClapCommand<'static>| {
+        clap.after_help(
+            "Examples:
+  # where does the selected pod live on the network
+  network",
+        )
+    },
+    vec!["network"],
+    noop_complete!(),
+    no_named_complete!(),
+    |_matches, env, writer| {
+        env.apply_to_selection(
+            writer,
+            Some(&env.click_config.range_separator),
+            |obj, writer| print_pod_network(env, obj, writer),
+        )
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pod_network_rows() {
+        let pod: api::Pod = serde_json::from_value(json!({
+            "metadata": { "name": "web" },
+            "spec": {
+                "dnsPolicy": "None",
+                "dnsConfig": {
+                    "nameservers": ["1.1.1.1"],
+                    "searches": ["svc.cluster.local"],
+                    "options": [{ "name": "ndots", "value": "2" }],
+                },
+                "containers": [{
+                    "name": "web",
+                    "ports": [
+                        { "containerPort": 8080, "name": "http" },
+                        { "containerPort": 53, "protocol": "UDP", "hostPort": 53 },
+                    ],
+                }],
+            },
+            "status": {
+                "hostIP": "10.0.0.5",
+                "podIP": "10.1.2.3",
+                "podIPs": [{ "ip": "10.1.2.3" }, { "ip": "fd00::3" }],
+            },
+        }))
+        .unwrap();
+        assert_eq!(
+            pod_network_rows(&pod),
+            vec![
+                ("Pod IPs:", "10.1.2.3, fd00::3".to_string()),
+                ("Host IP:", "10.0.0.5".to_string()),
+                ("Host Network:", "false".to_string()),
+                ("DNS Policy:", "None".to_string()),
+                (
+                    "DNS Config:",
+                    "nameservers: 1.1.1.1\nsearch: svc.cluster.local\noptions: ndots:2".to_string()
+                ),
+                (
+                    "Ports:",
+                    "web: 8080/TCP (http)\nweb: 53/UDP, host port 53".to_string()
+                ),
+            ]
+        );
+
+        let node: api::Node = serde_json::from_value(json!({
+            "metadata": { "name": "node-1" },
+            "spec": { "podCIDR": "10.1.2.0/24" },
+            "status": { "addresses": [
+                { "type": "InternalIP", "address": "10.0.0.5" },
+                { "type": "Hostname", "address": "node-1" },
+            ]},
+        }))
+        .unwrap();
+        assert_eq!(
+            node_network_rows(&node),
+            vec![
+                (
+                    "Node Addresses:",
+                    "InternalIP: 10.0.0.5\nHostname: node-1".to_string()
+                ),
+                ("Node Pod CIDRs:", "10.1.2.0/24".to_string()),
+            ]
+        );
+    }
+}
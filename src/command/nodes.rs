@@ -13,21 +13,29 @@
 // limitations under the License.
 
 use clap::{Arg, Command as ClapCommand};
-use k8s_openapi::api::core::v1 as api;
+use k8s_openapi::{
+    api::core::v1 as api, apimachinery::pkg::api::resource::Quantity, List, ListOptional,
+};
+use regex::Regex;
 
 use crate::{
-    command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{run_list_command, Extractor},
+    command::command_def::{
+        columns_arg, exec_match, field_selector_arg, filter_arg, filter_pattern_arg, hide_arg,
+        label_columns_arg, label_selector_arg, no_headers_arg, output_arg, quiet_arg, show_arg,
+        show_labels_arg, sort_arg, sort_by_arg, start_clap, watch_arg, Cmd,
+    },
+    command::pods::pod_resource,
+    command::{list_options, run_list_command, Extractor},
     completer,
     env::Env,
     kobj::{KObj, ObjType},
     output::ClickWriter,
-    table::CellSpec,
+    table::{format_raw_quantity, percent_cell, raw_quantity, CellSpec, QuantityKind},
 };
 
 use std::borrow::Cow;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
 
 lazy_static! {
@@ -66,7 +74,7 @@ const EXTRA_COL_MAP: &[(&str, &str)] = &[
 
 const EXTRA_COL_FLAGS: &[&str] = &{ extract_first!(EXTRA_COL_MAP) };
 
-fn node_to_kobj(node: &api::Node) -> KObj {
+pub fn node_to_kobj(node: &api::Node) -> KObj {
     KObj {
         name: node
             .metadata
@@ -171,7 +179,31 @@ fn node_state<'a>(node: &'a api::Node) -> Option<CellSpec<'a>> {
         }
         None => state.into(),
     };
-    Some(CellSpec::with_colors(state, Some(fg.into()), None))
+    Some(CellSpec::new_status(state, fg))
+}
+
+/// What's wrong with a node: NotReady if its Ready condition isn't True, the types of any other
+/// conditions that are True (like MemoryPressure or DiskPressure), and SchedulingDisabled if it's
+/// cordoned. Empty if the node is healthy
+pub fn node_problems(node: &api::Node) -> Vec<&str> {
+    let mut problems = vec![];
+    let conditions = node
+        .status
+        .as_ref()
+        .and_then(|stat| stat.conditions.as_ref());
+    match conditions.and_then(|conds| conds.iter().find(|c| c.type_ == "Ready")) {
+        Some(ready) if ready.status == "True" => {}
+        _ => problems.push("NotReady"),
+    }
+    for cond in conditions.into_iter().flatten() {
+        if cond.type_ != "Ready" && cond.status == "True" {
+            problems.push(cond.type_.as_str());
+        }
+    }
+    if node.spec.as_ref().and_then(|spec| spec.unschedulable) == Some(true) {
+        problems.push("SchedulingDisabled");
+    }
+    problems
 }
 
 fn node_version(node: &api::Node) -> Option<CellSpec<'_>> {
@@ -191,7 +223,6 @@ list_command!(
     |clap: ClapCommand<'static>| {
         clap.arg(
             Arg::new("labels")
-                .short('L')
                 .long("labels")
                 .help("include labels in output (deprecated, use --show labels")
                 .takes_value(false),
@@ -203,8 +234,21 @@ list_command!(
                 .help("Filter returned value by the specified regex")
                 .takes_value(true),
         )
+        .arg(show_labels_arg())
+        .arg(label_columns_arg())
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
         .arg(show_arg(EXTRA_COL_FLAGS, true))
         .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
         .arg(
             Arg::new("reverse")
                 .short('R')
@@ -215,10 +259,14 @@ list_command!(
     },
     vec!["nodes"],
     noop_complete!(),
-    [].into_iter(),
+    [(
+        "regex".to_string(),
+        completer::node_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter(),
     |matches, env, writer| {
         let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
-        let (request, _response_body) = api::Node::list_node(Default::default())?;
+        let (request, _response_body) = api::Node::list_node(list_options(&matches))?;
 
         run_list_command(
             matches,
@@ -233,3 +281,510 @@ list_command!(
         )
     }
 );
+
+/// What the pods on a node request, and are limited to, compared to what it has allocatable
+#[derive(Debug, Default, PartialEq)]
+struct Allocation {
+    pods: f64,
+    cpu_requests: f64,
+    cpu_limits: f64,
+    memory_requests: f64,
+    memory_limits: f64,
+    allocatable_pods: f64,
+    allocatable_cpu: f64,
+    allocatable_memory: f64,
+}
+
+impl Allocation {
+    /// How much of the node's cpu (or memory) is requested, as a fraction, for sorting
+    fn requested(&self, resource: &str) -> f64 {
+        let (requests, allocatable) = match resource {
+            "cpu" => (self.cpu_requests, self.allocatable_cpu),
+            _ => (self.memory_requests, self.allocatable_memory),
+        };
+        if allocatable > 0.0 {
+            requests / allocatable
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Add up the requests and limits of pods on each node. pods should only have pods that hold on to
+/// resources, so not finished ones
+fn node_allocations<'a>(
+    nodes: &'a [api::Node],
+    pods: &[api::Pod],
+) -> Vec<(&'a api::Node, Allocation)> {
+    nodes
+        .iter()
+        .map(|node| {
+            let allocatable = |resource: &str| {
+                node.status
+                    .as_ref()
+                    .and_then(|status| status.allocatable.as_ref())
+                    .and_then(|allocatable| allocatable.get(resource))
+                    .map(raw_quantity)
+                    .unwrap_or(0.0)
+            };
+            let mut allocation = Allocation {
+                allocatable_pods: allocatable("pods"),
+                allocatable_cpu: allocatable("cpu"),
+                allocatable_memory: allocatable("memory"),
+                ..Default::default()
+            };
+            let on_node = pods.iter().filter(|pod| {
+                pod.spec.as_ref().and_then(|spec| spec.node_name.as_ref())
+                    == node.metadata.name.as_ref()
+            });
+            for pod in on_node {
+                allocation.pods += 1.0;
+                allocation.cpu_requests += pod_resource(pod, false, "cpu");
+                allocation.cpu_limits += pod_resource(pod, true, "cpu");
+                allocation.memory_requests += pod_resource(pod, false, "memory");
+                allocation.memory_limits += pod_resource(pod, true, "memory");
+            }
+            (node, allocation)
+        })
+        .collect()
+}
+
+/// Sort by name, or most requested first, by the share of cpu or memory requested
+fn sort_allocations(allocations: &mut [(&api::Node, Allocation)], sort: &str) {
+    match sort {
+        "cpu" | "memory" => {
+            allocations.sort_by(|(_, a), (_, b)| b.requested(sort).total_cmp(&a.requested(sort)))
+        }
+        _ => allocations.sort_by(|(a, _), (b, _)| a.metadata.name.cmp(&b.metadata.name)),
+    }
+}
+
+command!(
+    NodeAllocation,
+    "allocation",
+    "Show how much of each node's cpu and memory the pods on it request, and are limited to",
+    |clap: ClapCommand<'static>| {
+        clap.arg(label_selector_arg())
+            .arg(
+                Arg::new("sort")
+                    .short('s')
+                    .long("sort")
+                    .help(
+                        "Sort by name, or most requested first by the share of cpu or memory \
+                         requested (default name)",
+                    )
+                    .possible_values(["name", "cpu", "memory"])
+                    .ignore_case(true)
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::new("reverse")
+                    .short('R')
+                    .long("reverse")
+                    .help("Reverse the order of the output")
+                    .takes_value(false),
+            )
+            .after_help(
+                "This is what the scheduler goes by, like the Allocated resources section of \
+'kubectl describe node', for every node at once. Requests are what pods are guaranteed, and limits \
+over 100% mean the node is overcommitted. Percentages are of what the node has allocatable for pods, \
+in yellow from 75% and red from 90%. Finished pods are left out, since they don't hold on to \
+anything. The nodes are numbered, so they can be selected afterwards.
+
+Nodes with little requested but high usage (see 'top nodes') run pods that ask for too little, \
+while nodes with a lot requested but little usage run pods that ask for too much.
+
+Examples:
+  # the nodes with the most memory requested
+  allocation --sort memory
+
+  # the worker nodes, least cpu requested first
+  allocation -l node-role.kubernetes.io/worker --sort cpu -R",
+            )
+    },
+    vec!["allocation"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let (request, _) = api::Node::list_node(ListOptional {
+            label_selector: matches.value_of("label"),
+            ..Default::default()
+        })?;
+        let nodes: List<api::Node> = env.run_on_context(|c| c.execute_list(request))?;
+        let (request, _) = api::Pod::list_pod_for_all_namespaces(ListOptional {
+            field_selector: Some("status.phase!=Succeeded,status.phase!=Failed"),
+            ..Default::default()
+        })?;
+        let pods: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+        let mut allocations = node_allocations(&nodes.items, &pods.items);
+        let sort = matches
+            .value_of("sort")
+            .unwrap_or("name")
+            .to_ascii_lowercase();
+        sort_allocations(&mut allocations, &sort);
+        if matches.is_present("reverse") {
+            allocations.reverse();
+        }
+        let cpu = |amount: f64| format_raw_quantity(amount, QuantityKind::Cpu);
+        let memory = |amount: f64| format_raw_quantity(amount, QuantityKind::Bytes);
+        let rows = allocations
+            .iter()
+            .map(|(node, alloc)| {
+                vec![
+                    CellSpec::new_index(),
+                    node.metadata.name.as_deref().unwrap_or("<Unknown>").into(),
+                    format!("{}/{}", alloc.pods, alloc.allocatable_pods).into(),
+                    cpu(alloc.cpu_requests).into(),
+                    percent_cell(alloc.cpu_requests, alloc.allocatable_cpu),
+                    cpu(alloc.cpu_limits).into(),
+                    percent_cell(alloc.cpu_limits, alloc.allocatable_cpu),
+                    memory(alloc.memory_requests).into(),
+                    percent_cell(alloc.memory_requests, alloc.allocatable_memory),
+                    memory(alloc.memory_limits).into(),
+                    percent_cell(alloc.memory_limits, alloc.allocatable_memory),
+                ]
+            })
+            .collect();
+        crate::table::print_table(
+            vec![
+                "####", "Name", "Pods", "CPU Req", "CPU Req%", "CPU Lim", "CPU Lim%", "Mem Req",
+                "Mem Req%", "Mem Lim", "Mem Lim%",
+            ],
+            rows,
+            env,
+            writer,
+        );
+        env.set_last_objs(
+            allocations
+                .iter()
+                .map(|(node, _)| node_to_kobj(node))
+                .collect::<Vec<KObj>>(),
+        );
+        Ok(())
+    }
+);
+
+/// What a group of nodes has allocatable in total, what's requested of it, and how many more pods
+/// of a given size would fit on them
+#[derive(Debug, Default, PartialEq)]
+struct Capacity {
+    nodes: usize,
+    total: Allocation,
+    fits: u64,
+}
+
+impl Capacity {
+    fn add(&mut self, alloc: &Allocation, pod_size: Option<(f64, f64)>) {
+        self.nodes += 1;
+        self.add_totals(alloc);
+        if let Some((cpu, memory)) = pod_size {
+            self.fits += pods_fitting(alloc, cpu, memory);
+        }
+    }
+
+    fn add_totals(&mut self, alloc: &Allocation) {
+        self.total.pods += alloc.pods;
+        self.total.cpu_requests += alloc.cpu_requests;
+        self.total.cpu_limits += alloc.cpu_limits;
+        self.total.memory_requests += alloc.memory_requests;
+        self.total.memory_limits += alloc.memory_limits;
+        self.total.allocatable_pods += alloc.allocatable_pods;
+        self.total.allocatable_cpu += alloc.allocatable_cpu;
+        self.total.allocatable_memory += alloc.allocatable_memory;
+    }
+}
+
+/// How many pods requesting cpu and memory fit in what's left unrequested on a node. A pod has to
+/// fit on one node, so what's left on several small ones can't be added up
+fn pods_fitting(alloc: &Allocation, cpu: f64, memory: f64) -> u64 {
+    let free = |allocatable: f64, requested: f64, size: f64| {
+        if size > 0.0 {
+            ((allocatable - requested).max(0.0) / size).floor()
+        } else {
+            f64::INFINITY
+        }
+    };
+    free(alloc.allocatable_cpu, alloc.cpu_requests, cpu)
+        .min(free(
+            alloc.allocatable_memory,
+            alloc.memory_requests,
+            memory,
+        ))
+        .min((alloc.allocatable_pods - alloc.pods).max(0.0)) as u64
+}
+
+/// Add up the capacity of nodes by the value of their label, leaving out cordoned nodes, since
+/// nothing new can be scheduled on them
+fn capacity_by_group(
+    allocations: &[(&api::Node, Allocation)],
+    label: &str,
+    pod_size: Option<(f64, f64)>,
+) -> BTreeMap<String, Capacity> {
+    let mut groups: BTreeMap<String, Capacity> = BTreeMap::new();
+    for (node, alloc) in allocations.iter() {
+        if node.spec.as_ref().and_then(|spec| spec.unschedulable) == Some(true) {
+            continue;
+        }
+        let group = node
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(label))
+            .cloned()
+            .unwrap_or_else(|| "<none>".to_string());
+        groups.entry(group).or_default().add(alloc, pod_size);
+    }
+    groups
+}
+
+fn validate_quantity(s: &str) -> Result<(), String> {
+    lazy_static! {
+        static ref QUANTITY: Regex =
+            Regex::new(r"^[0-9]+(\.[0-9]+)?(m|k|M|G|T|P|E|Ki|Mi|Gi|Ti|Pi|Ei)?$").unwrap();
+    }
+    if QUANTITY.is_match(s) {
+        Ok(())
+    } else {
+        Err(format!("{} is not a quantity, like 500m or 2Gi", s))
+    }
+}
+
+command!(
+    ClusterCapacity,
+    "capacity",
+    "Sum up the cpu and memory the nodes have allocatable, what's requested and what's left, by \
+     zone (or another node label)",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("group_by")
+                .short('g')
+                .long("group-by")
+                .help("The node label to group nodes by")
+                .default_value("topology.kubernetes.io/zone")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("cpu")
+                .short('c')
+                .long("cpu")
+                .help("The cpu a new pod would request, to show how many of them would fit")
+                .validator(validate_quantity)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("memory")
+                .short('m')
+                .long("memory")
+                .help("The memory a new pod would request, to show how many of them would fit")
+                .validator(validate_quantity)
+                .takes_value(true),
+        )
+        .after_help(
+            "This answers 'can the cluster take another deploy?'. Nodes are grouped by the value \
+of a label, the zone by default, or something like a nodepool label (cloud.google.com/gke-nodepool, \
+eks.amazonaws.com/nodegroup or agentpool). Nodes without the label are grouped as <none>. \
+Cordoned nodes are left out, since nothing new can go on them, as are finished pods, since they \
+don't hold on to anything.
+
+Free is what's allocatable but not requested, which is what the scheduler goes by. With --cpu \
+and/or --memory, Fits is how many pods requesting that many more would fit. Each pod has to fit on \
+a single node, so this is added up node by node, rather than from the free totals. Taints, \
+affinity and other constraints aren't taken into account.
+
+Examples:
+  # how much room is left in each zone
+  capacity
+
+  # how many more 2 core, 4Gi pods would fit in each nodepool
+  capacity -g cloud.google.com/gke-nodepool --cpu 2 --memory 4Gi",
+        )
+    },
+    vec!["capacity"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let label = matches.value_of("group_by").unwrap(); // has a default
+        let quantity = |arg: &str| {
+            matches
+                .value_of(arg)
+                .map(|q| raw_quantity(&Quantity(q.to_string())))
+        };
+        let pod_size = match (quantity("cpu"), quantity("memory")) {
+            (None, None) => None,
+            (cpu, memory) => Some((cpu.unwrap_or(0.0), memory.unwrap_or(0.0))),
+        };
+        let (request, _) = api::Node::list_node(Default::default())?;
+        let nodes: List<api::Node> = env.run_on_context(|c| c.execute_list(request))?;
+        let (request, _) = api::Pod::list_pod_for_all_namespaces(ListOptional {
+            field_selector: Some("status.phase!=Succeeded,status.phase!=Failed"),
+            ..Default::default()
+        })?;
+        let pods: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+        let allocations = node_allocations(&nodes.items, &pods.items);
+        let groups = capacity_by_group(&allocations, label, pod_size);
+        let mut total = Capacity::default();
+        for capacity in groups.values() {
+            total.nodes += capacity.nodes;
+            total.fits += capacity.fits;
+            total.add_totals(&capacity.total);
+        }
+
+        let cpu = |amount: f64| format_raw_quantity(amount, QuantityKind::Cpu);
+        let memory = |amount: f64| format_raw_quantity(amount, QuantityKind::Bytes);
+        let row = |group: &str, capacity: &Capacity| {
+            let alloc = &capacity.total;
+            let mut row = vec![
+                group.to_string().into(),
+                CellSpec::new_int(capacity.nodes as i64),
+                format!("{}/{}", alloc.pods, alloc.allocatable_pods).into(),
+                cpu(alloc.allocatable_cpu).into(),
+                cpu(alloc.cpu_requests).into(),
+                percent_cell(alloc.cpu_requests, alloc.allocatable_cpu),
+                cpu((alloc.allocatable_cpu - alloc.cpu_requests).max(0.0)).into(),
+                memory(alloc.allocatable_memory).into(),
+                memory(alloc.memory_requests).into(),
+                percent_cell(alloc.memory_requests, alloc.allocatable_memory),
+                memory((alloc.allocatable_memory - alloc.memory_requests).max(0.0)).into(),
+            ];
+            if pod_size.is_some() {
+                row.push(CellSpec::new_int(capacity.fits as i64));
+            }
+            row
+        };
+        let mut rows: Vec<Vec<CellSpec<'_>>> = groups
+            .iter()
+            .map(|(group, capacity)| row(group, capacity))
+            .collect();
+        rows.push(row("Total", &total));
+        let mut titles = vec![
+            label,
+            "Nodes",
+            "Pods",
+            "CPU Alloc",
+            "CPU Req",
+            "CPU Req%",
+            "CPU Free",
+            "Mem Alloc",
+            "Mem Req",
+            "Mem Req%",
+            "Mem Free",
+        ];
+        if pod_size.is_some() {
+            titles.push("Fits");
+        }
+        crate::table::print_table(titles, rows, env, writer);
+        let cordoned = allocations.len() - total.nodes;
+        if cordoned > 0 {
+            clickwriteln!(
+                writer,
+                "{} cordoned node{} left out",
+                cordoned,
+                if cordoned == 1 { "" } else { "s" }
+            );
+        }
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_node_allocations() {
+        let nodes: Vec<api::Node> = serde_json::from_value(json!([
+            {
+                "metadata": { "name": "node-1" },
+                "status": { "allocatable": { "cpu": "4", "memory": "8Gi", "pods": "110" } },
+            },
+            {
+                "metadata": { "name": "node-2" },
+                "status": { "allocatable": { "cpu": "2", "memory": "8Gi", "pods": "110" } },
+            },
+        ]))
+        .unwrap();
+        let pods: Vec<api::Pod> = serde_json::from_value(json!([
+            {
+                "metadata": { "name": "web" },
+                "spec": { "nodeName": "node-1", "containers": [{
+                    "name": "web",
+                    "resources": { "requests": { "cpu": "1", "memory": "6Gi" } },
+                }]},
+            },
+            {
+                "metadata": { "name": "db" },
+                "spec": { "nodeName": "node-2", "containers": [{
+                    "name": "db",
+                    "resources": { "requests": { "cpu": "1500m" }, "limits": { "cpu": "3" } },
+                }]},
+            },
+            {
+                "metadata": { "name": "pending" },
+                "spec": { "containers": [{ "name": "pending" }] },
+            },
+        ]))
+        .unwrap();
+        let mut allocations = node_allocations(&nodes, &pods);
+        assert_eq!(allocations[0].1.pods, 1.0);
+        assert_eq!(allocations[0].1.requested("cpu"), 0.25);
+        assert_eq!(allocations[0].1.requested("memory"), 0.75);
+        assert_eq!(allocations[1].1.cpu_limits, 3.0);
+
+        sort_allocations(&mut allocations, "cpu");
+        assert_eq!(allocations[0].0.metadata.name.as_deref(), Some("node-2"));
+        sort_allocations(&mut allocations, "memory");
+        assert_eq!(allocations[0].0.metadata.name.as_deref(), Some("node-1"));
+        sort_allocations(&mut allocations, "name");
+        assert_eq!(allocations[0].0.metadata.name.as_deref(), Some("node-1"));
+    }
+
+    #[test]
+    fn test_capacity_by_group() {
+        let nodes: Vec<api::Node> = serde_json::from_value(json!([
+            {
+                "metadata": { "name": "a-1", "labels": { "zone": "a" } },
+                "status": { "allocatable": { "cpu": "4", "memory": "8Gi", "pods": "110" } },
+            },
+            {
+                "metadata": { "name": "a-2", "labels": { "zone": "a" } },
+                "status": { "allocatable": { "cpu": "4", "memory": "8Gi", "pods": "110" } },
+            },
+            {
+                "metadata": { "name": "b-1", "labels": { "zone": "b" } },
+                "spec": { "unschedulable": true },
+                "status": { "allocatable": { "cpu": "4", "memory": "8Gi", "pods": "110" } },
+            },
+            {
+                "metadata": { "name": "other" },
+                "status": { "allocatable": { "cpu": "2", "memory": "4Gi", "pods": "2" } },
+            },
+        ]))
+        .unwrap();
+        let pods: Vec<api::Pod> = serde_json::from_value(json!([{
+            "metadata": { "name": "web" },
+            "spec": { "nodeName": "a-1", "containers": [{
+                "name": "web",
+                "resources": { "requests": { "cpu": "3", "memory": "1Gi" } },
+            }]},
+        }]))
+        .unwrap();
+        let allocations = node_allocations(&nodes, &pods);
+        let groups = capacity_by_group(&allocations, "zone", Some((1.0, 1024.0 * 1024.0 * 1024.0)));
+        assert_eq!(groups.keys().collect::<Vec<_>>(), vec!["<none>", "a"]);
+        let zone_a = &groups["a"];
+        assert_eq!(zone_a.nodes, 2);
+        assert_eq!(zone_a.total.allocatable_cpu, 8.0);
+        assert_eq!(zone_a.total.cpu_requests, 3.0);
+        // 1 on a-1, which has a core left, and 4 on a-2
+        assert_eq!(zone_a.fits, 5);
+        // limited by pod slots
+        assert_eq!(groups["<none>"].fits, 2);
+
+        let alloc = &allocations[1].1;
+        assert_eq!(pods_fitting(alloc, 0.0, 3.0 * 1024.0 * 1024.0 * 1024.0), 2);
+        assert!(validate_quantity("500m").is_ok());
+        assert!(validate_quantity("2Gi").is_ok());
+        assert!(validate_quantity("lots").is_err());
+    }
+}
@@ -0,0 +1,426 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use k8s_openapi::{
+    api::apps::v1 as api_apps,
+    api::batch::v1 as api_batch,
+    api::core::v1 as api,
+    apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference},
+    List, ListOptional, Metadata,
+};
+use rustyline::completion::Pair as RustlinePair;
+use serde_json::Value;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    command::pods::pod_to_kobj,
+    completer,
+    env::{Env, ObjectSelection},
+    error::ClickError,
+    kobj::{KObj, ObjType},
+    output::ClickWriter,
+    table::CellSpec,
+    values::val_str_opt,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// The objects currently selected, or an error if there aren't any
+fn selected_objs(env: &Env) -> Result<Vec<KObj>, ClickError> {
+    match env.current_selection() {
+        ObjectSelection::Single(obj) => Ok(vec![obj.clone()]),
+        ObjectSelection::Range(objs) => Ok(objs.clone()),
+        ObjectSelection::None => Err(ClickError::CommandError(
+            "No objects currently active".to_string(),
+        )),
+    }
+}
+
+/// The object an owner reference points to, if it's of a kind click can select. Owners are in the
+/// same namespace as what they own, apart from cluster scoped ones like nodes
+fn owner_to_kobj(owner: &OwnerReference, namespace: Option<&str>) -> Option<KObj> {
+    let (typ, namespaced) = match owner.kind.as_str() {
+        "DaemonSet" => (ObjType::DaemonSet, true),
+        "Deployment" => (ObjType::Deployment, true),
+        "Job" => (ObjType::Job, true),
+        "CronJob" => (ObjType::CronJob, true),
+        "Node" => (ObjType::Node, false),
+        "ReplicaSet" => (ObjType::ReplicaSet, true),
+        "StatefulSet" => (ObjType::StatefulSet, true),
+        #[cfg(feature = "argorollouts")]
+        "Rollout" => (ObjType::Rollout, true),
+        _ => return None,
+    };
+    Some(KObj {
+        name: owner.name.clone(),
+        namespace: if namespaced {
+            namespace.map(str::to_string)
+        } else {
+            None
+        },
+        typ,
+    })
+}
+
+/// The owner worth following out of an object's owner references: the controller, if there is
+/// one, otherwise the first
+fn pick_owner(owners: &[OwnerReference]) -> Option<&OwnerReference> {
+    owners
+        .iter()
+        .find(|owner| owner.controller == Some(true))
+        .or_else(|| owners.first())
+}
+
+/// Does item have an owner reference to the object with uid
+pub fn is_owned_by<T: Metadata<Ty = ObjectMeta>>(item: &T, uid: &str) -> bool {
+    item.metadata()
+        .owner_references
+        .iter()
+        .flatten()
+        .any(|owner| owner.uid == uid)
+}
+
+/// Is item controlled by the object with uid. Jobs a CronJob starts are found this way, since a
+/// CronJob has no selector to ask for them with
+fn is_controlled_by<T: Metadata<Ty = ObjectMeta>>(item: &T, uid: &str) -> bool {
+    item.metadata()
+        .owner_references
+        .iter()
+        .flatten()
+        .any(|owner| owner.controller == Some(true) && owner.uid == uid)
+}
+
+/// Select objs: a single object is selected as if it had been picked from a list, more than one
+/// as a range
+fn select(env: &mut Env, objs: Vec<KObj>) {
+    if objs.len() == 1 {
+        env.set_last_objs(objs);
+        env.set_current(0);
+    } else {
+        env.set_last_objs(objs.clone());
+        env.set_range(objs);
+    }
+}
+
+command!(
+    Owner,
+    "owner",
+    "Select the owner of the active object(s), like the ReplicaSet of a pod, or the Deployment of \
+     a ReplicaSet",
+    |clap: ClapCommand<'static>| {
+        clap.after_help(
+            "Objects are owned through their ownerReferences. When an object has more than one \
+owner, its controller is picked. The owners of a range of objects are selected as a range, or as a \
+single object if they all have the same one.
+
+Examples:
+  # go from the selected pod to its deployment
+  owner
+  owner
+
+  # and back down to its pods again
+  children
+  children",
+        )
+    },
+    vec!["owner"],
+    noop_complete!(),
+    no_named_complete!(),
+    |_matches, env, writer| {
+        let mut owners: Vec<KObj> = vec![];
+        for obj in selected_objs(env)?.iter() {
+            let value = env.run_on_context(|c| c.get_value(obj.read_request()?))?;
+            let refs: Vec<OwnerReference> = match value.pointer("/metadata/ownerReferences") {
+                Some(refs) => serde_json::from_value(refs.clone())?,
+                None => vec![],
+            };
+            let owner = match pick_owner(&refs) {
+                Some(owner) => owner,
+                None => {
+                    clickwriteln!(writer, "{} {} has no owner", obj.type_str(), obj.name());
+                    continue;
+                }
+            };
+            match owner_to_kobj(owner, obj.namespace.as_deref()) {
+                Some(kobj) if !owners.contains(&kobj) => owners.push(kobj),
+                Some(_) => {} // owned by the same thing as an earlier object
+                None => clickwriteln!(
+                    writer,
+                    "{} {} is owned by {} {}, which can't be selected",
+                    obj.type_str(),
+                    obj.name(),
+                    owner.kind,
+                    owner.name
+                ),
+            }
+        }
+        if owners.is_empty() {
+            return Err(ClickError::CommandError("No owner to select".to_string()));
+        }
+        select(env, owners);
+        Ok(())
+    }
+);
+
+/// A label selector from the matchLabels of an object's selector, to ask for fewer objects than
+/// all of those in the namespace
+fn match_labels_selector(value: &Value) -> Option<String> {
+    let labels = value
+        .pointer("/spec/selector/matchLabels")
+        .and_then(Value::as_object)?;
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(key, val)| format!("{}={}", key, val.as_str().unwrap_or_default()))
+        .collect();
+    Some(labels.join(","))
+}
+
+/// The uids of the objects that directly own the pods of obj, a workload. Deployments (and
+/// rollouts) own their pods through ReplicaSets, which are looked up, while other workloads own
+/// their pods themselves
+pub fn pod_owner_uids(env: &Env, obj: &KObj) -> Result<Vec<String>, ClickError> {
+    let value = env.run_on_context(|c| c.get_value(obj.read_request()?))?;
+    let uid = val_str_opt("/metadata/uid", &value).unwrap_or_default();
+    match obj.typ {
+        ObjType::Deployment => {}
+        #[cfg(feature = "argorollouts")]
+        ObjType::Rollout => {}
+        _ => return Ok(vec![uid]),
+    }
+    let namespace = obj.namespace.as_deref().unwrap_or_default();
+    let selector = match_labels_selector(&value);
+    let opts = ListOptional {
+        label_selector: selector.as_deref(),
+        ..Default::default()
+    };
+    let (request, _) = api_apps::ReplicaSet::list_namespaced_replica_set(namespace, opts)?;
+    let list: List<api_apps::ReplicaSet> = env.run_on_context(|c| c.execute_list(request))?;
+    Ok(list
+        .items
+        .iter()
+        .filter(|rs| is_owned_by(*rs, &uid))
+        .filter_map(|rs| rs.metadata.uid.clone())
+        .collect())
+}
+
+/// The objects owned by obj, which is described by value. Deployments (and rollouts) own
+/// ReplicaSets, CronJobs own Jobs, and the other workloads own pods
+fn children_of(env: &Env, obj: &KObj, value: &Value) -> Result<Vec<KObj>, ClickError> {
+    let uid = val_str_opt("/metadata/uid", value).unwrap_or_default();
+    let namespace = obj.namespace.as_deref().unwrap_or_default();
+    let selector = match_labels_selector(value);
+    let opts = ListOptional {
+        label_selector: selector.as_deref(),
+        ..Default::default()
+    };
+    let to_kobj = |metadata: &ObjectMeta, typ: ObjType| KObj {
+        name: metadata.name.clone().unwrap_or_default(),
+        namespace: metadata.namespace.clone(),
+        typ,
+    };
+    match obj.typ {
+        ObjType::Deployment => {}
+        #[cfg(feature = "argorollouts")]
+        ObjType::Rollout => {}
+        ObjType::CronJob => {
+            let (request, _) = api_batch::Job::list_namespaced_job(namespace, opts)?;
+            let list: List<api_batch::Job> = env.run_on_context(|c| c.execute_list(request))?;
+            return Ok(list
+                .items
+                .iter()
+                .filter(|job| is_controlled_by(*job, &uid))
+                .map(|job| to_kobj(&job.metadata, ObjType::Job))
+                .collect());
+        }
+        ObjType::DaemonSet | ObjType::Job | ObjType::ReplicaSet | ObjType::StatefulSet => {
+            let (request, _) = api::Pod::list_namespaced_pod(namespace, opts)?;
+            let list: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+            return Ok(list
+                .items
+                .iter()
+                .filter(|pod| is_owned_by(*pod, &uid))
+                .map(pod_to_kobj)
+                .collect());
+        }
+        _ => {
+            return Err(ClickError::CommandError(format!(
+                "Can't find the children of a {}",
+                obj.type_str()
+            )))
+        }
+    }
+    let (request, _) = api_apps::ReplicaSet::list_namespaced_replica_set(namespace, opts)?;
+    let list: List<api_apps::ReplicaSet> = env.run_on_context(|c| c.execute_list(request))?;
+    Ok(list
+        .items
+        .iter()
+        .filter(|rs| is_owned_by(*rs, &uid))
+        .map(|rs| to_kobj(&rs.metadata, ObjType::ReplicaSet))
+        .collect())
+}
+
+command!(
+    Children,
+    "children",
+    "Select the objects owned by the active object(s), like the ReplicaSets of a Deployment, or the \
+     pods of a ReplicaSet",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("list")
+                .short('l')
+                .long("list")
+                .help("Only list the children (numbered, to select later), don't select them")
+                .takes_value(false),
+        )
+        .after_help(
+            "Deployments own ReplicaSets, CronJobs own the Jobs they start, and ReplicaSets, \
+StatefulSets, DaemonSets and Jobs own pods. The children are listed, numbered like after a list \
+command, and selected: as a single object if there's only one, otherwise as a range.
+
+Examples:
+  # go from the selected deployment to its ReplicaSets, then pick the first one
+  children -l
+  0
+
+  # follow the logs of every pod of the selected StatefulSet
+  children
+  logs -f",
+        )
+    },
+    vec!["children"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let mut children: Vec<KObj> = vec![];
+        for obj in selected_objs(env)?.iter() {
+            let value = env.run_on_context(|c| c.get_value(obj.read_request()?))?;
+            children.extend(children_of(env, obj, &value)?);
+        }
+        if children.is_empty() {
+            clickwriteln!(writer, "No children");
+            return Ok(());
+        }
+        let rows = children
+            .iter()
+            .map(|child| {
+                vec![
+                    CellSpec::new_index(),
+                    child.type_str().to_string().into(),
+                    child.name().into(),
+                ]
+            })
+            .collect();
+        crate::table::print_table(vec!["####", "Kind", "Name"], rows, env, writer);
+        if matches.is_present("list") {
+            env.set_last_objs(children);
+        } else {
+            select(env, children);
+        }
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn owner_ref(kind: &str, name: &str, controller: Option<bool>) -> OwnerReference {
+        serde_json::from_value(json!({
+            "apiVersion": "apps/v1",
+            "kind": kind,
+            "name": name,
+            "uid": format!("{}-uid", name),
+            "controller": controller,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pick_owner() {
+        let refs = vec![
+            owner_ref("ConfigMap", "settings", None),
+            owner_ref("ReplicaSet", "web-5d4f8", Some(true)),
+        ];
+        assert_eq!(pick_owner(&refs).unwrap().name, "web-5d4f8");
+        assert_eq!(pick_owner(&refs[..1]).unwrap().name, "settings");
+        assert!(pick_owner(&[]).is_none());
+    }
+
+    #[test]
+    fn test_owner_to_kobj() {
+        assert_eq!(
+            owner_to_kobj(&owner_ref("ReplicaSet", "web-5d4f8", None), Some("prod")),
+            Some(KObj {
+                name: "web-5d4f8".to_string(),
+                namespace: Some("prod".to_string()),
+                typ: ObjType::ReplicaSet,
+            })
+        );
+        // mirror pods of static pods are owned by their node
+        assert_eq!(
+            owner_to_kobj(&owner_ref("Node", "node-1", None), Some("kube-system")),
+            Some(KObj {
+                name: "node-1".to_string(),
+                namespace: None,
+                typ: ObjType::Node,
+            })
+        );
+        // jobs started by a cronjob
+        assert_eq!(
+            owner_to_kobj(&owner_ref("CronJob", "backup", None), Some("prod")),
+            Some(KObj {
+                name: "backup".to_string(),
+                namespace: Some("prod".to_string()),
+                typ: ObjType::CronJob,
+            })
+        );
+        assert_eq!(
+            owner_to_kobj(&owner_ref("ConfigMap", "settings", None), Some("prod")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_controlled_by() {
+        let job: api_batch::Job = serde_json::from_value(json!({
+            "metadata": {
+                "name": "backup-27845040",
+                "ownerReferences": [
+                    owner_ref("CronJob", "backup", Some(true)),
+                    owner_ref("ConfigMap", "settings", None),
+                ],
+            },
+        }))
+        .unwrap();
+        assert!(is_controlled_by(&job, "backup-uid"));
+        assert!(!is_controlled_by(&job, "settings-uid"));
+        assert!(is_owned_by(&job, "settings-uid"));
+    }
+
+    #[test]
+    fn test_match_labels_selector() {
+        let deployment = json!({
+            "spec": { "selector": { "matchLabels": { "app": "web", "tier": "front" } } }
+        });
+        assert_eq!(
+            match_labels_selector(&deployment).as_deref(),
+            Some("app=web,tier=front")
+        );
+        assert_eq!(match_labels_selector(&json!({})), None);
+    }
+}
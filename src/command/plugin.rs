@@ -0,0 +1,273 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Plugins are executables named click-<name> found on the PATH, which can be run from click as
+//! <name>. See PLUGINHELP in command_processor.rs for what they're passed
+
+use rustyline::completion::Pair as RustlinePair;
+use serde_json::{json, Value};
+
+use crate::{
+    command::command_def::Cmd,
+    env::{Env, ObjectSelection},
+    error::ClickError,
+    kobj::KObj,
+    output::ClickWriter,
+};
+
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const PLUGIN_PREFIX: &str = "click-";
+
+#[derive(Clone)]
+pub struct Plugin {
+    // Cmd needs static strs. Plugins are found once at startup, so leaking these is fine
+    name: &'static str,
+    about: &'static str,
+    path: PathBuf,
+}
+
+impl Plugin {
+    fn new(name: String, path: PathBuf) -> Plugin {
+        let about = format!("Plugin ({})", path.display());
+        Plugin {
+            name: Box::leak(name.into_boxed_str()),
+            about: Box::leak(about.into_boxed_str()),
+            path,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// The name of the command for the plugin at path, if it's named like a plugin
+fn plugin_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name().and_then(OsStr::to_str)?;
+    // allow click-foo.exe and the like
+    let stem = match file_name.rsplit_once('.') {
+        Some((stem, _)) if cfg!(windows) => stem,
+        _ => file_name,
+    };
+    stem.strip_prefix(PLUGIN_PREFIX)
+        .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace))
+        .map(str::to_string)
+}
+
+/// Find the plugins in the directories of path (a PATH style list). Like the shell, the first
+/// executable with a given name wins. Plugins named the same as a command in commands are skipped,
+/// since the command would always be run instead
+fn find_plugins_in(path: &OsStr, commands: &[Box<dyn Cmd>]) -> Vec<Plugin> {
+    let mut plugins: Vec<Plugin> = vec![];
+    for dir in std::env::split_paths(path) {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue, // PATH often has dirs that don't exist
+        };
+        let mut found: Vec<(String, PathBuf)> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter_map(|path| plugin_name(&path).map(|name| (name, path)))
+            .filter(|(_, path)| is_executable(path))
+            .collect();
+        found.sort();
+        for (name, path) in found.into_iter() {
+            if !plugins.iter().any(|p| p.name == name) && !commands.iter().any(|c| c.is(&name)) {
+                plugins.push(Plugin::new(name, path));
+            }
+        }
+    }
+    plugins.sort_by(|a, b| a.name.cmp(b.name));
+    plugins
+}
+
+/// Find the plugins on the PATH
+pub fn find_plugins(commands: &[Box<dyn Cmd>]) -> Vec<Plugin> {
+    match std::env::var_os("PATH") {
+        Some(path) => find_plugins_in(&path, commands),
+        None => vec![],
+    }
+}
+
+fn obj_json(obj: &KObj) -> Value {
+    json!({
+        "kind": obj.type_str(),
+        "name": obj.name(),
+        "namespace": obj.namespace,
+    })
+}
+
+/// The json sent to a plugin on stdin, describing click's current state
+fn state_json(env: &Env) -> Value {
+    let selection: Vec<Value> = match env.current_selection() {
+        ObjectSelection::Single(obj) => vec![obj_json(obj)],
+        ObjectSelection::Range(objs) => objs.iter().map(obj_json).collect(),
+        ObjectSelection::None => vec![],
+    };
+    json!({
+        "context": env.context.as_ref().map(|c| c.name.as_str()),
+        "namespace": env.namespace,
+        "kubeconfig": env.config.source_file,
+        "selection": selection,
+    })
+}
+
+impl Cmd for Plugin {
+    fn exec(
+        &self,
+        env: &mut Env,
+        args: &mut dyn Iterator<Item = &str>,
+        writer: &mut ClickWriter,
+    ) -> Result<(), ClickError> {
+        let args: Vec<&str> = args.collect();
+        let mut expr = duct::cmd(&self.path, args)
+            .env(
+                "CLICK_CONTEXT",
+                env.context.as_ref().map(|c| c.name.as_str()).unwrap_or(""),
+            )
+            .env("CLICK_NAMESPACE", env.namespace.as_deref().unwrap_or(""))
+            .env("KUBECONFIG", env.config.source_file.as_str());
+        // only a single object can be described in plain variables, ranges are in the json
+        if let ObjectSelection::Single(obj) = env.current_selection() {
+            expr = expr
+                .env("CLICK_OBJECT_KIND", obj.type_str())
+                .env("CLICK_OBJECT_NAME", obj.name())
+                .env(
+                    "CLICK_OBJECT_NAMESPACE",
+                    obj.namespace.as_deref().unwrap_or(""),
+                );
+        }
+        let reader = expr
+            .stdin_bytes(state_json(env).to_string())
+            .unchecked()
+            .reader()?;
+        std::io::copy(&mut &reader, writer)?;
+        match reader.try_wait()? {
+            Some(output) if !output.status.success() => Err(ClickError::CommandError(format!(
+                "Plugin {} failed: {}",
+                self.name, output.status
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn is(&self, l: &str) -> bool {
+        l == self.name
+    }
+
+    fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn try_complete(&self, _index: usize, _prefix: &str, _env: &Env) -> Vec<RustlinePair> {
+        vec![]
+    }
+
+    fn try_completed_named(
+        &self,
+        _index: usize,
+        _opt: &str,
+        _prefix: &str,
+        _env: &Env,
+    ) -> Vec<RustlinePair> {
+        vec![]
+    }
+
+    fn complete_option(&self, _prefix: &str) -> Vec<RustlinePair> {
+        vec![]
+    }
+
+    fn write_help(&self, writer: &mut ClickWriter) {
+        clickwriteln!(
+            writer,
+            "{} runs the plugin {}, passing it any arguments as they are. Try '{} --help' for \
+             the plugin's own help, and see 'help plugins' for how plugins work",
+            self.name,
+            self.path.display(),
+            self.name
+        );
+    }
+
+    fn about(&self) -> &'static str {
+        self.about
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_name() {
+        assert_eq!(
+            plugin_name(Path::new("/usr/bin/click-top")),
+            Some("top".to_string())
+        );
+        assert_eq!(
+            plugin_name(Path::new("click-node-shell")),
+            Some("node-shell".to_string())
+        );
+        assert_eq!(plugin_name(Path::new("/usr/bin/click-")), None);
+        assert_eq!(plugin_name(Path::new("/usr/bin/click")), None);
+        assert_eq!(plugin_name(Path::new("/usr/bin/kubectl-click-foo")), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_plugins_in() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let first = tempdir::TempDir::new("click-plugins").unwrap();
+        let second = tempdir::TempDir::new("click-plugins").unwrap();
+        let add = |dir: &Path, name: &str, mode: u32| {
+            let path = dir.join(name);
+            std::fs::write(&path, "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        };
+        add(first.path(), "click-top", 0o755);
+        add(first.path(), "click-notes", 0o644); // not executable
+        add(first.path(), "click-logs", 0o755); // the logs command wins
+        add(second.path(), "click-top", 0o755); // the first one on the path wins
+        add(second.path(), "click-shell", 0o755);
+        add(second.path(), "kubectl-foo", 0o755);
+
+        let path =
+            std::env::join_paths([first.path(), Path::new("/does/not/exist"), second.path()])
+                .unwrap();
+        let commands: Vec<Box<dyn Cmd>> = vec![Box::new(crate::command::logs::Logs::new())];
+        let plugins = find_plugins_in(&path, &commands);
+        let found: Vec<(&str, &Path)> = plugins
+            .iter()
+            .map(|plugin| (plugin.name, plugin.path.as_path()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("shell", second.path().join("click-shell").as_path()),
+                ("top", first.path().join("click-top").as_path()),
+            ]
+        );
+    }
+}
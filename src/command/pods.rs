@@ -12,23 +12,34 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use chrono::Utc;
 use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::api::core::v1 as api;
-use k8s_openapi::ListOptional;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::WatchEvent;
+use k8s_openapi::{List, ListOptional, WatchOptional};
 
 use crate::{
-    command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{run_list_command, Extractor},
+    command::command_def::{
+        all_namespaces_arg, columns_arg, exec_match, field_selector_arg, filter_arg,
+        filter_pattern_arg, hide_arg, label_columns_arg, label_selector_arg, no_headers_arg,
+        output_arg, quiet_arg, show_arg, show_labels_arg, sort_arg, sort_by_arg, start_clap,
+        watch_arg, Cmd,
+    },
+    command::owners::{is_owned_by, pod_owner_uids},
+    command::{
+        build_specs, check_watch_format, list_namespace, list_options, name_filter, output_format,
+        run_filtered_list_command, stream_watch, Extractor,
+    },
     completer,
     env::{Env, ObjectSelection},
     error::ClickError,
     kobj::{KObj, ObjType},
     output::ClickWriter,
     styles::Styles,
-    table::{CellSpec, ColorType},
+    table::{format_quantity, format_timestamp, raw_quantity, CellSpec, ColorType, QuantityKind},
 };
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io::Write;
 use std::{cell::RefCell, collections::BTreeMap};
 
@@ -69,7 +80,7 @@ const EXTRA_COL_MAP: &[(&str, &str)] = &[
 
 const EXTRA_COL_FLAGS: &[&str] = &{ extract_first!(EXTRA_COL_MAP) };
 
-fn pod_to_kobj(pod: &api::Pod) -> KObj {
+pub fn pod_to_kobj(pod: &api::Pod) -> KObj {
     let containers = match &pod.spec {
         Some(spec) => spec
             .containers
@@ -86,36 +97,35 @@ fn pod_to_kobj(pod: &api::Pod) -> KObj {
     }
 }
 
-// Check if a pod has a waiting container
-fn has_waiting(pod: &api::Pod) -> bool {
-    match pod
-        .status
+// If a pod has a waiting container, get the reason it's waiting (like CrashLoopBackOff), or
+// ContainerCreating if there's no reason
+fn waiting_reason(pod: &api::Pod) -> Option<&str> {
+    pod.status
         .as_ref()
         .and_then(|stat| stat.container_statuses.as_ref())
-    {
-        Some(stats) => {
-            stats.iter().any(|cs| {
-                match cs.state.as_ref() {
-                    Some(state) => {
-                        state.waiting.is_some()
-                            || (
-                                // if all 3 are None, default is waiting
-                                state.running.is_none() && state.terminated.is_none()
-                            )
-                    }
-                    None => false,
-                }
+        .and_then(|stats| {
+            stats.iter().find_map(|cs| {
+                cs.state
+                    .as_ref()
+                    .and_then(|state| match state.waiting.as_ref() {
+                        Some(waiting) => {
+                            Some(waiting.reason.as_deref().unwrap_or("ContainerCreating"))
+                        }
+                        // if all 3 are None, default is waiting
+                        None if state.running.is_none() && state.terminated.is_none() => {
+                            Some("ContainerCreating")
+                        }
+                        None => None,
+                    })
             })
-        }
-        None => false,
-    }
+        })
 }
 
 fn phase_style_color(phase: &str) -> ColorType {
     match phase {
         "Running" | "Active" => ColorType::Success,
         "Terminated" | "Terminating" => ColorType::Danger,
-        "Pending" | "ContainerCreating" => ColorType::Warn,
+        "Pending" | "ContainerCreating" | "PodInitializing" => ColorType::Warn,
         "Succeeded" => ColorType::Info,
         "Failed" => ColorType::Danger,
         "Unknown" => ColorType::Danger,
@@ -144,8 +154,44 @@ fn pod_nominated_node(pod: &api::Pod) -> Option<CellSpec<'_>> {
         })
 }
 
+/// How much of resource a container has as its limit if limits is true, otherwise what it requests.
+/// 0 if it doesn't say
+pub fn container_resource(container: &api::Container, limits: bool, resource: &str) -> f64 {
+    container
+        .resources
+        .as_ref()
+        .and_then(|resources| {
+            if limits {
+                resources.limits.as_ref()
+            } else {
+                resources.requests.as_ref()
+            }
+        })
+        .and_then(|amounts| amounts.get(resource))
+        .map(raw_quantity)
+        .unwrap_or(0.0)
+}
+
+/// How much of resource (like cpu or memory, as a raw_quantity) a pod asks for, from its
+/// containers' limits if limits is true, otherwise their requests. Init containers run one at a
+/// time before the others start, so this is the larger of the biggest init container's amount and
+/// the sum of the other containers'
+pub fn pod_resource(pod: &api::Pod, limits: bool, resource: &str) -> f64 {
+    let amount = |container: &api::Container| container_resource(container, limits, resource);
+    let spec = match pod.spec.as_ref() {
+        Some(spec) => spec,
+        None => return 0.0,
+    };
+    let containers: f64 = spec.containers.iter().map(amount).sum();
+    spec.init_containers
+        .iter()
+        .flatten()
+        .map(amount)
+        .fold(containers, f64::max)
+}
+
 // get the number of ready containers and total containers as ready/total
-fn ready_counts(pod: &api::Pod) -> Option<CellSpec<'_>> {
+pub fn ready_counts(pod: &api::Pod) -> Option<CellSpec<'_>> {
     pod.status.as_ref().map(|stat| {
         let mut count = 0;
         let mut ready = 0;
@@ -177,7 +223,7 @@ fn pod_readiness_gates(pod: &api::Pod) -> Option<CellSpec<'_>> {
     })
 }
 
-fn restart_count(pod: &api::Pod) -> Option<CellSpec<'_>> {
+pub fn restart_count(pod: &api::Pod) -> Option<CellSpec<'_>> {
     pod.status.as_ref().and_then(|stat| {
         stat.container_statuses.as_ref().map(|container_statuses| {
             let count = container_statuses
@@ -215,20 +261,277 @@ fn last_restart(pod: &api::Pod) -> Option<CellSpec<'_>> {
     })
 }
 
-fn pod_status(pod: &api::Pod) -> Option<CellSpec<'_>> {
-    let status = if pod.metadata.deletion_timestamp.is_some() {
+// the status shown in the Status column
+fn status_str(pod: &api::Pod) -> &str {
+    if pod.metadata.deletion_timestamp.is_some() {
         // Was deleted
         "Terminating"
-    } else if has_waiting(pod) {
-        "ContainerCreating"
+    } else if let Some(reason) = waiting_reason(pod) {
+        reason
     } else {
         pod.status
             .as_ref()
             .and_then(|stat| stat.phase.as_deref())
             .unwrap_or("Unknown")
+    }
+}
+
+/// The statuses --status can find a pod by: the one in the Status column, its phase, the reason
+/// for its status (like Evicted), NotReady if a container isn't ready, and the reasons any
+/// container is waiting or was terminated (like CrashLoopBackOff or OOMKilled)
+pub fn pod_statuses(pod: &api::Pod) -> Vec<&str> {
+    let mut statuses = vec![status_str(pod)];
+    if let Some(stat) = pod.status.as_ref() {
+        statuses.extend(stat.phase.as_deref());
+        statuses.extend(stat.reason.as_deref());
+        let container_statuses = stat.container_statuses.iter().flatten();
+        let not_ready = stat.phase.as_deref() != Some("Succeeded")
+            && (stat.container_statuses.is_none()
+                || container_statuses.clone().any(|cs| !cs.ready));
+        if not_ready {
+            statuses.push("NotReady");
+        }
+        for cs in container_statuses.chain(stat.init_container_statuses.iter().flatten()) {
+            if let Some(state) = cs.state.as_ref() {
+                statuses.extend(state.waiting.as_ref().and_then(|w| w.reason.as_deref()));
+                statuses.extend(state.terminated.as_ref().and_then(|t| t.reason.as_deref()));
+            }
+        }
+    }
+    statuses
+}
+
+/// Is the pod having problems: not running, or running without all its containers ready. Pods that
+/// ran to completion are fine
+pub fn pod_is_unhealthy(pod: &api::Pod) -> bool {
+    let statuses = pod_statuses(pod);
+    statuses[0] != "Succeeded" && (statuses[0] != "Running" || statuses.contains(&"NotReady"))
+}
+
+pub fn pod_status(pod: &api::Pod) -> Option<CellSpec<'_>> {
+    let status = status_str(pod);
+    Some(CellSpec::new_status(
+        status.into(),
+        phase_style_color(status),
+    ))
+}
+
+/// The type of workload a --owned-by KIND can be
+fn owner_type(kind: &str) -> Option<ObjType> {
+    match kind.to_lowercase().as_str() {
+        "deployment" | "deployments" | "deploy" => Some(ObjType::Deployment),
+        "replicaset" | "replicasets" | "rs" => Some(ObjType::ReplicaSet),
+        "statefulset" | "statefulsets" | "sts" => Some(ObjType::StatefulSet),
+        "daemonset" | "daemonsets" | "ds" => Some(ObjType::DaemonSet),
+        "job" | "jobs" => Some(ObjType::Job),
+        #[cfg(feature = "argorollouts")]
+        "rollout" | "rollouts" => Some(ObjType::Rollout),
+        _ => None,
+    }
+}
+
+/// Parse the KIND/NAME of --owned-by into the owner's type and name
+fn parse_owner(owner: &str) -> Result<(ObjType, &str), ClickError> {
+    let (kind, name) = owner.split_once('/').ok_or_else(|| {
+        ClickError::CommandError(format!(
+            "Invalid owner {}, expected KIND/NAME (like deployment/web)",
+            owner
+        ))
+    })?;
+    match owner_type(kind) {
+        Some(typ) if !name.is_empty() => Ok((typ, name)),
+        Some(_) => Err(ClickError::CommandError(format!(
+            "No name given in owner {}",
+            owner
+        ))),
+        None => Err(ClickError::CommandError(format!(
+            "Pods can't be owned by a {}. Use a deployment, replicaset, statefulset, daemonset or \
+             job",
+            kind
+        ))),
+    }
+}
+
+/// Is obj a workload that owns pods
+fn owns_pods(obj: &KObj) -> bool {
+    match obj.typ {
+        ObjType::Deployment
+        | ObjType::ReplicaSet
+        | ObjType::StatefulSet
+        | ObjType::DaemonSet
+        | ObjType::Job => true,
+        #[cfg(feature = "argorollouts")]
+        ObjType::Rollout => true,
+        _ => false,
+    }
+}
+
+/// How many of the latest changes pods --watch shows below the table
+const MAX_CHANGES: usize = 20;
+
+/// Describe how the pod called name changed, if it changed in a way worth showing: it was added,
+/// deleted, its status changed or it restarted. old is the pod as it was, if it was seen before
+fn pod_change(name: &str, old: Option<&api::Pod>, event: &WatchEvent<api::Pod>) -> Option<String> {
+    match (event, old) {
+        (WatchEvent::Added(pod), None) | (WatchEvent::Modified(pod), None) => {
+            Some(format!("{} added ({})", name, status_str(pod)))
+        }
+        (WatchEvent::Added(pod), Some(old)) | (WatchEvent::Modified(pod), Some(old)) => {
+            let restarts = |pod: &api::Pod| {
+                pod.status
+                    .iter()
+                    .flat_map(|stat| stat.container_statuses.iter().flatten())
+                    .fold(0, |acc, cs| acc + cs.restart_count)
+            };
+            if status_str(old) != status_str(pod) {
+                Some(format!(
+                    "{}: {} → {}",
+                    name,
+                    status_str(old),
+                    status_str(pod)
+                ))
+            } else if restarts(pod) > restarts(old) {
+                Some(format!("{}: restarted ({} restarts)", name, restarts(pod)))
+            } else {
+                None
+            }
+        }
+        (WatchEvent::Deleted(_), _) => Some(format!("{} deleted", name)),
+        _ => None,
+    }
+}
+
+/// Print the table of watched pods and the latest changes, returning the kobjs of the rows
+fn print_watched_pods(
+    pods: &BTreeMap<String, api::Pod>,
+    cols: &[&str],
+    keep: &dyn Fn(&api::Pod) -> bool,
+    changes: &VecDeque<String>,
+    env: &Env,
+    writer: &mut ClickWriter,
+) -> Result<Vec<KObj>, ClickError> {
+    use crossterm::{cursor::MoveTo, execute, terminal::Clear, terminal::ClearType};
+
+    let list = List {
+        items: pods.values().filter(|pod| keep(pod)).cloned().collect(),
+        metadata: Default::default(),
     };
-    let fg = phase_style_color(status);
-    Some(CellSpec::with_colors(status.into(), Some(fg.into()), None))
+    let specs = build_specs(cols, &list, Some(&POD_EXTRACTORS), true, None, pod_to_kobj);
+    let (kobjs, rows): (Vec<KObj>, Vec<Vec<CellSpec>>) = specs.into_iter().unzip();
+    if writer.is_tty() {
+        execute!(writer, Clear(ClearType::All), MoveTo(0, 0))?;
+    }
+    clickwriteln!(
+        writer,
+        "Watching pods, ctrl-c to stop. Last updated: {}",
+        format_timestamp(Utc::now(), "%H:%M:%S", env)
+    );
+    let mut titles = vec!["####"];
+    titles.extend(cols);
+    crate::table::print_table(titles, rows, env, writer);
+    if !changes.is_empty() {
+        clickwriteln!(writer, "\nChanges:");
+        for change in changes.iter() {
+            clickwriteln!(writer, "{}", change);
+        }
+    }
+    writer.flush()?;
+    Ok(kobjs)
+}
+
+/// Watch pods with the watch api until ctrl-c is pressed. On a terminal the table is redrawn
+/// whenever a pod changes, with the latest status changes below it. Otherwise the table is printed
+/// once, followed by a line for each change
+fn watch_pods(
+    env: &mut Env,
+    writer: &mut ClickWriter,
+    namespace: Option<&str>,
+    opts: ListOptional,
+    keep: &dyn Fn(&api::Pod) -> bool,
+) -> Result<(), ClickError> {
+    let (request, _) = match namespace {
+        Some(ns) => api::Pod::list_namespaced_pod(ns, opts)?,
+        None => api::Pod::list_pod_for_all_namespaces(opts)?,
+    };
+    let list: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+    // start watching from the list, so only changes after it are sent
+    let watch_opts = WatchOptional {
+        label_selector: opts.label_selector,
+        field_selector: opts.field_selector,
+        resource_version: list.metadata.resource_version.as_deref(),
+        ..Default::default()
+    };
+    let (request, _) = match namespace {
+        Some(ns) => api::Pod::watch_namespaced_pod(ns, watch_opts)?,
+        None => api::Pod::watch_pod_for_all_namespaces(watch_opts)?,
+    };
+
+    let mut cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
+    if namespace.is_none() {
+        cols.push("Namespace");
+    }
+    let key = |pod: &api::Pod| {
+        let meta = &pod.metadata;
+        match namespace {
+            Some(_) => meta.name.clone().unwrap_or_default(),
+            None => format!(
+                "{}/{}",
+                meta.namespace.as_deref().unwrap_or(""),
+                meta.name.as_deref().unwrap_or("")
+            ),
+        }
+    };
+    let mut pods: BTreeMap<String, api::Pod> =
+        list.items.into_iter().map(|pod| (key(&pod), pod)).collect();
+    let mut changes: VecDeque<String> = VecDeque::new();
+    let tty = writer.is_tty();
+    let mut kobjs = print_watched_pods(&pods, &cols, keep, &changes, env, writer)?;
+
+    stream_watch(env, request, |event: Option<WatchEvent<api::Pod>>| {
+        let event = match event {
+            Some(event) => event,
+            None => return Ok(true),
+        };
+        let pod = match &event {
+            WatchEvent::Added(pod) | WatchEvent::Modified(pod) | WatchEvent::Deleted(pod) => pod,
+            _ => return Ok(true), // bookmarks
+        };
+        let name = key(pod);
+        let old = pods.get(&name);
+        if keep(pod) || old.is_some_and(keep) {
+            if let Some(change) = pod_change(&name, old, &event) {
+                let change = format!(
+                    "{} {}",
+                    format_timestamp(Utc::now(), "%H:%M:%S", env),
+                    change
+                );
+                if !tty {
+                    clickwriteln!(writer, "{}", change);
+                }
+                changes.push_back(change);
+                if changes.len() > MAX_CHANGES {
+                    changes.pop_front();
+                }
+            }
+        }
+        match event {
+            WatchEvent::Deleted(_) => {
+                pods.remove(&name);
+            }
+            WatchEvent::Added(pod) | WatchEvent::Modified(pod) => {
+                pods.insert(name, pod);
+            }
+            _ => {}
+        }
+        if tty {
+            kobjs = print_watched_pods(&pods, &cols, keep, &changes, env, writer)?;
+        } else {
+            writer.flush()?;
+        }
+        Ok(true)
+    })?;
+    env.set_last_objs(kobjs);
+    Ok(())
 }
 
 list_command!(
@@ -240,34 +543,71 @@ list_command!(
     |clap: ClapCommand<'static>| {
         clap.arg(
             Arg::new("labels")
-                .short('L')
                 .long("labels")
                 .help("include labels in output (deprecated, use --show labels)")
                 .takes_value(false),
         )
-        .arg(
-            Arg::new("label")
-                .short('l')
-                .long("label")
-                .help("Get pods with specified label selector (example: app=nginx)")
-                .takes_value(true),
-        )
         .arg(
             Arg::new("node")
                 .short('n')
                 .long("node")
-                .help("Only fetch pods on the specified node.")
+                .visible_alias("on-node")
+                .value_name("NODE")
+                .help(
+                    "Only fetch pods scheduled on the specified node. Without this, pods on the \
+                     selected node are listed if a node is selected",
+                )
                 .takes_value(true),
         )
         .arg(
             Arg::new("regex")
                 .short('r')
                 .long("regex")
+                .value_name("REGEX")
                 .help("Filter returned value by the specified regex")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new("owned-by")
+                .long("owned-by")
+                .value_name("KIND/NAME")
+                .help(
+                    "Only list the pods of a deployment, replicaset, statefulset, daemonset or \
+                     job in the current namespace, following ownerReferences through \
+                     ReplicaSets. Without this, the pods of the selected workload are listed if \
+                     one is selected",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("status")
+                .long("status")
+                .value_name("STATUSES")
+                .help(
+                    "Only list pods with one of the comma separated statuses (case-insensitive). \
+                     Besides the Status column and phase, this matches the reason for the pod's \
+                     status (like Evicted), NotReady if a container isn't ready, and why any \
+                     container is waiting or was terminated (like CrashLoopBackOff or OOMKilled)",
+                )
+                .takes_value(true)
+                .use_value_delimiter(true),
+        )
+        .arg(show_labels_arg())
+        .arg(label_columns_arg())
+        .arg(all_namespaces_arg())
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
         .arg(show_arg(EXTRA_COL_FLAGS, true))
         .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
         .arg(
             Arg::new("reverse")
                 .short('R')
@@ -275,38 +615,147 @@ list_command!(
                 .help("Reverse the order of the returned list")
                 .takes_value(false),
         )
+        .after_help(
+            "With --watch and no interval, pods are watched with the watch api: the table is redrawn \
+as soon as a pod changes, and the pods' status changes are listed below it with the time they \
+happened. With an interval, the list is refreshed every SECS seconds instead.
+
+Examples:
+  # list the pods whose names contain web, with the node they're on
+  pods -r web --show node
+
+  # list the pods of the nginx app, newest first
+  pods -l app=nginx --sort-by .status.startTime -R
+
+  # print the names of the pods on node-1, one per line
+  pods --on-node node-1 -q
+
+  # list the pods that aren't running
+  pods --field-selector status.phase!=Running
+
+  # list the broken pods
+  pods --status CrashLoopBackOff,Evicted,NotReady
+
+  # list the pods of the web deployment
+  pods --owned-by deployment/web
+
+  # watch the pods, seeing them go from ContainerCreating to Running (or CrashLoopBackOff)
+  pods --watch
+
+  # print all the pods as yaml
+  pods -o yaml",
+        )
     },
     vec!["pods"],
     noop_complete!(),
-    [].into_iter(),
+    [
+        (
+            "regex".to_string(),
+            completer::pod_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+        ),
+        (
+            "node".to_string(),
+            completer::node_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+        ),
+    ]
+    .into_iter(),
     |matches, env, writer| {
-        let mut opts: ListOptional = ListOptional::<'_> {
-            label_selector: matches.value_of("label"),
-            ..Default::default()
-        };
-        let mut field_sel = None;
+        let mut opts = list_options(&matches);
+        let mut node_sel = None;
+        // the workload to list the pods of
+        let mut owner = None;
+        match matches.value_of("owned-by") {
+            Some(owned_by) => {
+                let (typ, name) = parse_owner(owned_by)?;
+                let namespace = env.namespace.clone().ok_or_else(|| {
+                    ClickError::CommandError("Set a namespace to use --owned-by".to_string())
+                })?;
+                owner = Some(KObj {
+                    name: name.to_string(),
+                    namespace: Some(namespace),
+                    typ,
+                });
+            }
+            None if matches.value_of("node").is_none() => {
+                if let ObjectSelection::Single(obj) = env.current_selection() {
+                    if owns_pods(obj) && obj.namespace.is_some() {
+                        owner = Some(obj.clone());
+                    }
+                }
+            }
+            None => {}
+        }
         match matches.value_of("node") {
             Some(nodeval) => {
-                field_sel = Some(format!("spec.nodeName={}", nodeval));
+                node_sel = Some(format!("spec.nodeName={}", nodeval));
             }
             None => {
                 if let ObjectSelection::Single(obj) = env.current_selection() {
                     if obj.is(ObjType::Node) {
-                        field_sel = Some(format!("spec.nodeName={}", obj.name()));
+                        node_sel = Some(format!("spec.nodeName={}", obj.name()));
                     }
                 }
             }
         }
-        opts.field_selector = field_sel.as_deref();
+        // the node is added to any --field-selector
+        let field_sel = match (node_sel, opts.field_selector) {
+            (Some(node_sel), Some(field_sel)) => Some(format!("{},{}", node_sel, field_sel)),
+            (node_sel, _) => node_sel,
+        };
+        if field_sel.is_some() {
+            opts.field_selector = field_sel.as_deref();
+        }
 
-        let (request, _response_body) = match &env.namespace {
+        let owner_uids = match owner.as_ref() {
+            Some(owner) => Some(pod_owner_uids(env, owner)?),
+            None => None,
+        };
+        // a workload's pods are in its namespace
+        let namespace = match owner.as_ref() {
+            Some(owner) => owner.namespace.as_deref(),
+            None => list_namespace(&matches, env),
+        };
+        let (request, _response_body) = match namespace {
             Some(ns) => api::Pod::list_namespaced_pod(ns, opts)?,
             None => api::Pod::list_pod_for_all_namespaces(opts)?,
         };
 
         let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
 
-        run_list_command(
+        // a '|' needs quotes to not be a pipe, but allow it since it reads well
+        let wanted: Option<Vec<String>> = matches.values_of("status").map(|vals| {
+            vals.flat_map(|val| val.split('|'))
+                .map(|status| status.trim().to_lowercase())
+                .filter(|status| !status.is_empty())
+                .collect()
+        });
+        let keep = |pod: &api::Pod| {
+            let has_status = wanted.as_ref().is_none_or(|wanted| {
+                pod_statuses(pod)
+                    .iter()
+                    .any(|status| wanted.contains(&status.to_lowercase()))
+            });
+            has_status
+                && owner_uids
+                    .as_ref()
+                    .is_none_or(|uids| uids.iter().any(|uid| is_owned_by(pod, uid)))
+        };
+
+        // --watch without an interval uses the watch api instead of refreshing the whole list
+        if matches.is_present("watch") && matches.value_of("watch").is_none() {
+            check_watch_format(&output_format(&matches)?)?;
+            let name_filter = name_filter(&matches)?;
+            let keep = |pod: &api::Pod| {
+                keep(pod)
+                    && name_filter.as_ref().is_none_or(|filter| {
+                        filter.is_match(pod.metadata.name.as_deref().unwrap_or(""))
+                    })
+            };
+            let namespace = namespace.map(str::to_string);
+            return watch_pods(env, writer, namespace.as_deref(), opts, &keep);
+        }
+
+        run_filtered_list_command(
             matches,
             env,
             writer,
@@ -316,6 +765,7 @@ list_command!(
             Some(EXTRA_COL_MAP),
             Some(&POD_EXTRACTORS),
             pod_to_kobj,
+            Some(&keep),
         )
     }
 );
@@ -324,7 +774,7 @@ list_command!(
 command!(
     Containers,
     "containers",
-    "Print information about the containers of the active pod",
+    "Print information about the containers (and init containers) of the active pod",
     |clap: ClapCommand<'static>| {
         clap.arg(
             Arg::new("volumes")
@@ -366,107 +816,130 @@ fn print_containers(
         obj.namespace.as_ref().unwrap(),
         Default::default(),
     )?;
-    match env
-        .run_on_context(|c| c.read::<api::ReadNamespacedPodResponse>(request))
-        .unwrap()
-    {
-        api::ReadNamespacedPodResponse::Ok(pod) => match pod
-            .status
-            .and_then(|status| status.container_statuses)
+    let pod: api::Pod = env.run_on_context(|c| c.read_object(request))?;
+    let status = pod.status.unwrap_or_default();
+    let init_statuses = status.init_container_statuses.unwrap_or_default();
+    let container_statuses = status.container_statuses.unwrap_or_default();
+    if init_statuses.is_empty() && container_statuses.is_empty() {
+        return Err(ClickError::CommandError(
+            "No container info returned from api server".to_string(),
+        ));
+    }
+    // init containers run first, so they're shown first
+    let all_statuses = init_statuses
+        .iter()
+        .map(|cont| (cont, true))
+        .chain(container_statuses.iter().map(|cont| (cont, false)));
+    for (cont, init) in all_statuses {
+        clickwrite!(
+            writer,
+            "Name:\t{}{}\n",
+            env.styles.bold(cont.name.as_str()),
+            if init { " (init container)" } else { "" }
+        );
+        clickwrite!(
+            writer,
+            "  ID:\t\t{}\n",
+            cont.container_id.as_deref().unwrap_or("<none>")
+        );
+        clickwrite!(writer, "  Image:\t{}\n", cont.image_id);
+        print_state_string("State", &cont.state, &env.styles, writer);
+        // why the container last stopped, if it's restarted
+        if cont
+            .last_state
+            .as_ref()
+            .is_some_and(|state| state != &api::ContainerState::default())
         {
-            Some(container_statuses) => {
-                for cont in container_statuses.iter() {
-                    clickwrite!(writer, "Name:\t{}\n", env.styles.bold(cont.name.as_str()));
-                    clickwrite!(
-                        writer,
-                        "  ID:\t\t{}\n",
-                        cont.container_id.as_deref().unwrap_or("<none>")
-                    );
-                    clickwrite!(writer, "  Image:\t{}\n", cont.image_id);
-                    print_state_string(&cont.state, &env.styles, writer);
-                    clickwrite!(writer, "  Ready:\t{}\n", cont.ready);
-                    clickwrite!(writer, "  Restarts:\t{}\n", cont.restart_count);
-
-                    // find the spec for this container
-                    if let Some(spec) = pod.spec.as_ref() {
-                        let cont_spec = spec.containers.iter().find(|cs| cs.name == cont.name);
-                        if let Some(cont_spec) = cont_spec {
-                            // print resources
-                            clickwrite!(writer, "  Resources:\n");
-                            match cont_spec.resources.as_ref() {
-                                Some(resources) => {
-                                    clickwrite!(writer, "    Requests:\n");
-                                    let empty = BTreeMap::new();
-                                    let requests = resources.requests.as_ref().unwrap_or(&empty);
-                                    for (resource, quant) in requests.iter() {
-                                        clickwrite!(writer, "      {}:\t{}\n", resource, quant.0)
-                                    }
-                                    if requests.is_empty() {
-                                        clickwrite!(writer, "      <none>\n");
-                                    }
-                                    clickwrite!(writer, "    Limits:\n");
-                                    let limits = resources.limits.as_ref().unwrap_or(&empty);
-                                    for (resource, quant) in limits.iter() {
-                                        clickwrite!(writer, "      {}:\t{}\n", resource, quant.0)
-                                    }
-                                    if limits.is_empty() {
-                                        clickwrite!(writer, "      <none>\n");
-                                    }
-                                }
-                                None => {
-                                    clickwrite!(writer, "    <Unknown>\n");
-                                }
-                            }
+            print_state_string("Last State", &cont.last_state, &env.styles, writer);
+        }
+        clickwrite!(writer, "  Ready:\t{}\n", cont.ready);
+        clickwrite!(writer, "  Restarts:\t{}\n", cont.restart_count);
 
-                            if volumes {
-                                // print volumes
-                                clickwrite!(writer, "  Volumes:\n");
-                                let empty = vec![];
-                                let volume_mounts =
-                                    cont_spec.volume_mounts.as_ref().unwrap_or(&empty);
-                                if !volume_mounts.is_empty() {
-                                    for vol in volume_mounts.iter() {
-                                        clickwrite!(writer, "   {}\n", vol.name);
-                                        clickwrite!(writer, "    Path:\t{}\n", vol.mount_path);
-                                        clickwrite!(
-                                            writer,
-                                            "    Sub-Path:\t{}\n",
-                                            vol.sub_path.as_deref().unwrap_or("<none>")
-                                        );
-                                        clickwrite!(
-                                            writer,
-                                            "    Read-Only:\t{}\n",
-                                            vol.read_only.unwrap_or(false)
-                                        );
-                                    }
-                                } else {
-                                    clickwrite!(writer, "    No Volumes\n");
-                                }
-                            }
+        // find the spec for this container
+        if let Some(spec) = pod.spec.as_ref() {
+            let specs = if init {
+                spec.init_containers.as_deref().unwrap_or_default()
+            } else {
+                &spec.containers
+            };
+            let cont_spec = specs.iter().find(|cs| cs.name == cont.name);
+            if let Some(cont_spec) = cont_spec {
+                // print resources
+                clickwrite!(writer, "  Resources:\n");
+                match cont_spec.resources.as_ref() {
+                    Some(resources) => {
+                        clickwrite!(writer, "    Requests:\n");
+                        let empty = BTreeMap::new();
+                        let requests = resources.requests.as_ref().unwrap_or(&empty);
+                        for (resource, quant) in requests.iter() {
+                            clickwrite!(
+                                writer,
+                                "      {}:\t{}\n",
+                                resource,
+                                format_quantity(quant, QuantityKind::for_resource(resource))
+                            )
                         }
+                        if requests.is_empty() {
+                            clickwrite!(writer, "      <none>\n");
+                        }
+                        clickwrite!(writer, "    Limits:\n");
+                        let limits = resources.limits.as_ref().unwrap_or(&empty);
+                        for (resource, quant) in limits.iter() {
+                            clickwrite!(
+                                writer,
+                                "      {}:\t{}\n",
+                                resource,
+                                format_quantity(quant, QuantityKind::for_resource(resource))
+                            )
+                        }
+                        if limits.is_empty() {
+                            clickwrite!(writer, "      <none>\n");
+                        }
+                    }
+                    None => {
+                        clickwrite!(writer, "    <Unknown>\n");
                     }
+                }
 
-                    clickwrite!(writer, "\n");
+                if volumes {
+                    // print volumes
+                    clickwrite!(writer, "  Volumes:\n");
+                    let empty = vec![];
+                    let volume_mounts = cont_spec.volume_mounts.as_ref().unwrap_or(&empty);
+                    if !volume_mounts.is_empty() {
+                        for vol in volume_mounts.iter() {
+                            clickwrite!(writer, "   {}\n", vol.name);
+                            clickwrite!(writer, "    Path:\t{}\n", vol.mount_path);
+                            clickwrite!(
+                                writer,
+                                "    Sub-Path:\t{}\n",
+                                vol.sub_path.as_deref().unwrap_or("<none>")
+                            );
+                            clickwrite!(
+                                writer,
+                                "    Read-Only:\t{}\n",
+                                vol.read_only.unwrap_or(false)
+                            );
+                        }
+                    } else {
+                        clickwrite!(writer, "    No Volumes\n");
+                    }
                 }
-                Ok(())
             }
-            None => Err(ClickError::CommandError(
-                "No container info returned from api server".to_string(),
-            )),
-        },
-        api::ReadNamespacedPodResponse::Other(o) => Err(ClickError::CommandError(format!(
-            "Error getting pod info: {:?}",
-            o
-        ))),
+        }
+
+        clickwrite!(writer, "\n");
     }
+    Ok(())
 }
 
 fn print_state_string(
+    label: &str,
     state: &Option<api::ContainerState>,
     styles: &Styles,
     writer: &mut ClickWriter,
 ) {
-    clickwrite!(writer, "  State:\t");
+    clickwrite!(writer, "  {}:\t", label);
     match state {
         Some(state) => {
             if let Some(running) = state.running.as_ref() {
@@ -505,3 +978,121 @@ fn print_state_string(
         None => clickwrite!(writer, "Unknown"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(status: serde_json::Value) -> api::Pod {
+        serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "web-1" },
+            "status": status,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pod_statuses() {
+        let running = pod(serde_json::json!({
+            "phase": "Running",
+            "containerStatuses": [{
+                "name": "web", "image": "web", "imageID": "", "ready": true, "restartCount": 0,
+                "state": { "running": {} },
+            }],
+        }));
+        assert_eq!(pod_statuses(&running), vec!["Running", "Running"]);
+
+        let crashing = pod(serde_json::json!({
+            "phase": "Running",
+            "containerStatuses": [{
+                "name": "web", "image": "web", "imageID": "", "ready": false, "restartCount": 5,
+                "state": { "waiting": { "reason": "CrashLoopBackOff" } },
+            }],
+        }));
+        let statuses = pod_statuses(&crashing);
+        assert!(statuses.contains(&"CrashLoopBackOff"));
+        assert!(statuses.contains(&"NotReady"));
+
+        let evicted = pod(serde_json::json!({ "phase": "Failed", "reason": "Evicted" }));
+        assert!(pod_statuses(&evicted).contains(&"Evicted"));
+
+        let done = pod(serde_json::json!({ "phase": "Succeeded", "containerStatuses": [] }));
+        assert!(!pod_statuses(&done).contains(&"NotReady"));
+
+        assert!(!pod_is_unhealthy(&running));
+        assert!(pod_is_unhealthy(&crashing));
+        assert!(pod_is_unhealthy(&evicted));
+        assert!(!pod_is_unhealthy(&done));
+    }
+
+    #[test]
+    fn test_pod_change() {
+        let pending = pod(serde_json::json!({ "phase": "Pending" }));
+        let running = pod(serde_json::json!({
+            "phase": "Running",
+            "containerStatuses": [{
+                "name": "web", "image": "web", "imageID": "", "ready": true, "restartCount": 0,
+                "state": { "running": {} },
+            }],
+        }));
+        let mut restarted = running.clone();
+        restarted
+            .status
+            .as_mut()
+            .unwrap()
+            .container_statuses
+            .as_mut()
+            .unwrap()[0]
+            .restart_count = 1;
+
+        let added = WatchEvent::Added(pending.clone());
+        assert_eq!(
+            pod_change("web-1", None, &added),
+            Some("web-1 added (Pending)".to_string())
+        );
+        let modified = WatchEvent::Modified(running.clone());
+        assert_eq!(
+            pod_change("web-1", Some(&pending), &modified),
+            Some("web-1: Pending → Running".to_string())
+        );
+        assert_eq!(pod_change("web-1", Some(&running), &modified), None);
+        assert_eq!(
+            pod_change("web-1", Some(&running), &WatchEvent::Modified(restarted)),
+            Some("web-1: restarted (1 restarts)".to_string())
+        );
+        assert_eq!(
+            pod_change(
+                "web-1",
+                Some(&running),
+                &WatchEvent::Deleted(running.clone())
+            ),
+            Some("web-1 deleted".to_string())
+        );
+    }
+
+    #[test]
+    fn test_owned_by() {
+        assert_eq!(
+            parse_owner("deploy/web").unwrap(),
+            (ObjType::Deployment, "web")
+        );
+        assert_eq!(
+            parse_owner("StatefulSet/db").unwrap(),
+            (ObjType::StatefulSet, "db")
+        );
+        assert!(parse_owner("web").is_err());
+        assert!(parse_owner("deployment/").is_err());
+        assert!(parse_owner("service/web").is_err());
+
+        let job = KObj {
+            name: "migrate".to_string(),
+            namespace: Some("default".to_string()),
+            typ: ObjType::Job,
+        };
+        assert!(owns_pods(&job));
+        assert!(!owns_pods(&KObj {
+            typ: ObjType::CronJob,
+            ..job
+        }));
+    }
+}
@@ -19,22 +19,209 @@ use rustyline::completion::Pair as RustlinePair;
 use crate::{
     command::command_def::{exec_match, start_clap, Cmd},
     completer,
-    env::{self, Env},
+    env::{self, Env, ObjectSelection},
     error::ClickError,
+    kobj::ObjType,
     output::ClickWriter,
 };
 
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::{self, stderr, Read, Write};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
+
+/// Does a port spec give a fixed local port, rather than letting a random one be picked (with 0:REMOTE
+/// or :REMOTE)
+fn has_fixed_local_port(spec: &str) -> bool {
+    match spec.split_once(':') {
+        Some((local, _)) => !local.is_empty() && local != "0",
+        None => true,
+    }
+}
+
+/// Get the addresses a port forward is listening on, and where they go, from kubectl's output
+fn forwarding_addresses(output: &str) -> Vec<&str> {
+    let mut addresses: Vec<&str> = vec![];
+    let forwarding = output
+        .lines()
+        .filter_map(|line| line.strip_prefix("Forwarding from "))
+        // kubectl listens on ipv6 as well, with the same ports
+        .filter(|addr| !addr.starts_with('['));
+    // a restarted forward prints the same addresses again
+    for addr in forwarding {
+        if !addresses.contains(&addr) {
+            addresses.push(addr);
+        }
+    }
+    addresses
+}
+
+/// Run kubectl port-forward to target, adding what it prints to output
+fn spawn_kubectl(
+    kubectl_binary: &str,
+    context: &str,
+    target: &str,
+    ns: &str,
+    ports: &[String],
+    output: Arc<Mutex<String>>,
+) -> io::Result<Child> {
+    let mut child = Command::new(kubectl_binary)
+        .arg("--namespace")
+        .arg(ns)
+        .arg("--context")
+        .arg(context)
+        .arg("port-forward")
+        .arg(target)
+        .args(ports.iter())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdout = child.stdout.take().unwrap();
+    thread::spawn(move || {
+        let mut buffer = [0; 128];
+        loop {
+            match stdout.read(&mut buffer[..]) {
+                Ok(read) => {
+                    if read > 0 {
+                        let readstr = String::from_utf8_lossy(&buffer[0..read]);
+                        let mut res = output.lock().unwrap();
+                        res.push_str(&*readstr);
+                    } else {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    write!(stderr(), "Error reading child output: {}", e).unwrap_or(());
+                    break;
+                }
+            }
+        }
+    });
+    Ok(child)
+}
+
+/// Start kubectl again whenever it exits, until the forward is stopped. For a service, kubectl
+/// picks a ready pod each time it starts, so this moves the forward to another pod if its pod dies
+fn restart_on_exit(pf: &env::PortForward, kubectl_binary: String, context: String, ns: String) {
+    let child = pf.child.clone();
+    let stopped = pf.stopped.clone();
+    let output = pf.output.clone();
+    let target = pf.pod.clone();
+    let ports = pf.ports.clone();
+    // where the output of the current run of kubectl starts
+    let mut run_start = 0;
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(1));
+        let mut child = child.lock().unwrap();
+        // checked with the lock held, so a forward being stopped can't be restarted
+        if stopped.load(Ordering::SeqCst) {
+            break;
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            let mut out = output.lock().unwrap();
+            // if kubectl never got to forwarding, like when the local port is taken, it would
+            // just fail again
+            if !out[run_start..].contains("Forwarding from ") {
+                out.push_str(&format!("kubectl exited ({}), not restarting\n", status));
+                break;
+            }
+            out.push_str(&format!(
+                "kubectl exited ({}), restarting to pick a ready pod\n",
+                status
+            ));
+            run_start = out.len();
+            drop(out);
+            match spawn_kubectl(
+                &kubectl_binary,
+                &context,
+                &target,
+                &ns,
+                &ports,
+                output.clone(),
+            ) {
+                Ok(new_child) => *child = new_child,
+                Err(e) => {
+                    output
+                        .lock()
+                        .unwrap()
+                        .push_str(&format!("Couldn't restart kubectl: {}\n", e));
+                    break;
+                }
+            }
+        }
+    });
+}
+
+fn start_port_forward(
+    env: &mut Env,
+    context: &str,
+    target: &str,
+    ns: &str,
+    ports: &[&str],
+    restart: bool,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let kubectl_binary = env
+        .click_config
+        .kubectl_binary
+        .as_deref()
+        .unwrap_or("kubectl")
+        .to_string();
+    let pvec: Vec<String> = ports.iter().map(|s| (*s).to_owned()).collect();
+    let output = Arc::new(Mutex::new(String::new()));
+    match spawn_kubectl(&kubectl_binary, context, target, ns, &pvec, output.clone()) {
+        Ok(child) => {
+            clickwriteln!(
+                writer,
+                "Forwarding port(s) {} to {}",
+                pvec.join(", "),
+                target
+            );
+            let pf = env::PortForward {
+                child: Arc::new(Mutex::new(child)),
+                pod: target.to_string(),
+                namespace: ns.to_string(),
+                ports: pvec,
+                output,
+                stopped: Arc::new(AtomicBool::new(false)),
+            };
+            if restart {
+                restart_on_exit(&pf, kubectl_binary, context.to_string(), ns.to_string());
+            }
+            env.add_port_forward(pf);
+            Ok(())
+        }
+        Err(e) => match e.kind() {
+            io::ErrorKind::NotFound => {
+                let msg = if kubectl_binary.starts_with('/') {
+                    format!(
+                        "Could not find kubectl binary '{}'. Does it exist?",
+                        kubectl_binary
+                    )
+                } else {
+                    format!(
+                        "Could not find kubectl binary '{}'. Is it in your PATH?",
+                        kubectl_binary
+                    )
+                };
+                Err(ClickError::CommandError(msg))
+            }
+            _ => Err(ClickError::CommandError(format!(
+                "Couldn't execute kubectl, not forwarding. Error is: {}",
+                e
+            ))),
+        },
+    }
+}
 
 command!(
     PortForward,
     "port-forward",
-    "Forward one (or more) local ports to the currently active pod",
+    "Forward one (or more) local ports to the currently active pod or service, or to each pod of \
+     the selected range",
     |clap: ClapCommand<'static>| clap
         .arg(
             Arg::new("ports")
@@ -74,7 +261,18 @@ Examples:
   port-forward 0:3456
 
   # Forwards a random port locally to port 3456 on the pod
-  port-forward :3456"
+  port-forward :3456
+
+  # Forward a random local port to port 8080 of each pod in the selected range
+  port-forward :8080
+
+  # With a service selected, forward local port 8080 to its port 80
+  port-forward 8080:80
+
+Each pod gets its own forward, which 'forwards' lists, along with the local ports picked. Forwards \
+to a range of pods need random local ports, since each pod needs different ones. With a service \
+selected, the ports are the service's, and kubectl picks a ready pod behind it. If that pod goes \
+away, kubectl is restarted to pick another one."
         ),
     vec!["pf", "port-forward"],
     noop_complete!(),
@@ -82,19 +280,38 @@ Examples:
     |matches, env, writer| {
         let ports: Vec<_> = matches.values_of("ports").unwrap().collect(); // unwrap safe, required
 
-        let (pod, ns) = {
-            let epod = env.current_pod();
-            match epod {
-                Some(p) => (
-                    p.name().to_string(),
-                    p.namespace.as_ref().unwrap().to_string(),
-                ),
-                None => {
-                    write!(stderr(), "No active pod").unwrap_or(());
-                    return Ok(()); // TODO: Return error
-                }
+        // a forward to a service is to svc/NAME, which kubectl resolves to a ready pod
+        let mut restart = false;
+        let pods: Vec<(String, String)> = match env.current_selection() {
+            ObjectSelection::Single(obj) if obj.is_pod() => {
+                vec![(obj.name().to_string(), obj.namespace.clone().unwrap())]
             }
+            ObjectSelection::Single(obj) if obj.is(ObjType::Service) => {
+                restart = true;
+                vec![(
+                    format!("svc/{}", obj.name()),
+                    obj.namespace.clone().unwrap(),
+                )]
+            }
+            ObjectSelection::Range(objs) => objs
+                .iter()
+                .filter(|obj| obj.is_pod())
+                .map(|obj| (obj.name().to_string(), obj.namespace.clone().unwrap()))
+                .collect(),
+            _ => vec![],
         };
+        if pods.is_empty() {
+            return Err(ClickError::CommandError(
+                "No active pod or service".to_string(),
+            ));
+        }
+        if pods.len() > 1 && ports.iter().any(|port| has_fixed_local_port(port)) {
+            return Err(ClickError::CommandError(
+                "Can't forward the same local port to more than one pod, use :REMOTE (like \
+                 :8080) for random local ports"
+                    .to_string(),
+            ));
+        }
 
         let context = if let Some(ref context) = env.context {
             context.name.clone()
@@ -102,87 +319,10 @@ Examples:
             return Err(ClickError::CommandError("No active context".to_string()));
         };
 
-        let kubectl_binary = env
-            .click_config
-            .kubectl_binary
-            .as_deref()
-            .unwrap_or("kubectl");
-        match Command::new(kubectl_binary)
-            .arg("--namespace")
-            .arg(ns)
-            .arg("--context")
-            .arg(context)
-            .arg("port-forward")
-            .arg(&pod)
-            .args(ports.iter())
-            .stdout(Stdio::piped())
-            .spawn()
-        {
-            Ok(mut child) => {
-                let mut stdout = child.stdout.take().unwrap();
-                let output = Arc::new(Mutex::new(String::new()));
-                let output_clone = output.clone();
-
-                thread::spawn(move || {
-                    let mut buffer = [0; 128];
-                    loop {
-                        match stdout.read(&mut buffer[..]) {
-                            Ok(read) => {
-                                if read > 0 {
-                                    let readstr = String::from_utf8_lossy(&buffer[0..read]);
-                                    let mut res = output_clone.lock().unwrap();
-                                    res.push_str(&*readstr);
-                                } else {
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                write!(stderr(), "Error reading child output: {}", e).unwrap_or(());
-                                break;
-                            }
-                        }
-                    }
-                });
-
-                let pvec: Vec<String> = ports.iter().map(|s| (*s).to_owned()).collect();
-                clickwriteln!(writer, "Forwarding port(s): {}", pvec.join(", "));
-
-                env.add_port_forward(env::PortForward {
-                    child,
-                    pod,
-                    ports: pvec,
-                    output,
-                });
-            }
-            Err(e) => match e.kind() {
-                io::ErrorKind::NotFound => {
-                    if kubectl_binary.starts_with('/') {
-                        writeln!(
-                            stderr(),
-                            "Could not find kubectl binary '{}'. Does it exist?",
-                            kubectl_binary
-                        )
-                        .unwrap_or(());
-                    } else {
-                        writeln!(
-                            stderr(),
-                            "Could not find kubectl binary '{}'. Is it in your PATH.",
-                            kubectl_binary
-                        )
-                        .unwrap_or(());
-                    }
-                }
-                _ => {
-                    write!(
-                        stderr(),
-                        "Couldn't execute kubectl, not forwarding.  Error is: {}",
-                        e
-                    )
-                    .unwrap_or(());
-                }
-            },
+        for (pod, ns) in pods.iter() {
+            start_port_forward(env, &context, pod, ns, &ports, restart, writer)?;
         }
-        Ok(()) // TODO: Return errors above if things fail
+        Ok(())
     }
 );
 
@@ -190,14 +330,25 @@ Examples:
 fn print_pfs(pfs: std::slice::IterMut<env::PortForward>, writer: &mut ClickWriter) {
     let mut table = Table::new();
     let mut empty = true;
-    table.set_header(vec!["####", "Pod", "Ports", "Status"]);
+    table.set_header(vec![
+        "####",
+        "Target",
+        "Namespace",
+        "Ports",
+        "Forwarding",
+        "Status",
+    ]);
     for (i, pf) in pfs.enumerate() {
         let mut row = Vec::new();
         row.push(Cell::new(format!("{}", i).as_str()).set_alignment(CellAlignment::Right));
         row.push(Cell::new(pf.pod.as_str()));
+        row.push(Cell::new(pf.namespace.as_str()));
         row.push(Cell::new(pf.ports.join(", ").as_str()));
+        row.push(Cell::new(
+            forwarding_addresses(&pf.output.lock().unwrap()).join("\n"),
+        ));
 
-        let status = match pf.child.try_wait() {
+        let status = match pf.child.lock().unwrap().try_wait() {
             Ok(Some(stat)) => format!("Exited with code {}", stat),
             Ok(None) => "Running".to_string(),
             Err(e) => format!("Error: {}", e),
@@ -217,6 +368,18 @@ fn print_pfs(pfs: std::slice::IterMut<env::PortForward>, writer: &mut ClickWrite
     }
 }
 
+/// Ask if something should be stopped. Returns true if it should be
+fn confirm_stop(env: &Env, what: &str, writer: &mut ClickWriter) -> bool {
+    clickwrite!(writer, "Stop {} [y/N]? ", what);
+    io::stdout().flush().expect("Could not flush stdout");
+    if let Ok(conf) = env.read_response() {
+        conf.trim() == "y" || conf.trim() == "yes"
+    } else {
+        clickwriteln!(writer, "Could not read response, not stopping.");
+        false
+    }
+}
+
 command!(
     PortForwards,
     "port-forwards",
@@ -231,67 +394,90 @@ command!(
         )
         .arg(
             Arg::new("index")
-                .help("Index (from 'port-forwards list') of port forward to take action on")
-                .validator(|s: &str| s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                .help(
+                    "Index (from 'port-forwards list') of port forward to take action on. 'stop \
+                     all' stops every port forward"
+                )
+                .validator(|s: &str| {
+                    if s == "all" {
+                        Ok(())
+                    } else {
+                        s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string())
+                    }
+                })
                 .required(false)
                 .index(2)
         )
         .after_help(
             "Example:
-  # List all active port forwards
-  pfs
+  # List all active port forwards, and the local ports they listen on
+  forwards
 
   # Stop item number 3 in list from above command
-  pfs stop 3"
+  pfs stop 3
+
+  # Stop all the port forwards
+  pfs stop all"
         ),
-    vec!["pfs", "port-forwards"],
+    vec!["pfs", "port-forwards", "forwards"],
     vec![&completer::portforwardaction_values_completer],
     no_named_complete!(),
     |matches, env, writer| {
         let stop = matches.is_present("action") && matches.value_of("action").unwrap() == "stop";
         let output =
             matches.is_present("action") && matches.value_of("action").unwrap() == "output";
-        if let Some(index) = matches.value_of("index") {
+        if matches.value_of("index") == Some("all") {
+            if !stop {
+                return Err(ClickError::CommandError(
+                    "'all' only works with stop".to_string(),
+                ));
+            }
+            let count = env.get_port_forwards().count();
+            if count == 0 {
+                clickwriteln!(writer, "No active port forwards");
+            } else if confirm_stop(env, &format!("all {} port forwards", count), writer) {
+                env.stop_all_forwards();
+                clickwriteln!(writer, "Stopped");
+            } else {
+                clickwriteln!(writer, "Not stopping");
+            }
+        } else if let Some(index) = matches.value_of("index") {
             let i = index.parse::<usize>().unwrap();
-            match env.get_port_forward(i) {
+            let description = match env.get_port_forward(i) {
                 Some(pf) => {
-                    if stop {
-                        clickwrite!(writer, "Stop port-forward: ");
-                    }
-                    clickwrite!(writer, "Pod: {}, Port(s): {}", pf.pod, pf.ports.join(", "));
-
+                    let description = format!("Pod: {}, Port(s): {}", pf.pod, pf.ports.join(", "));
                     if output {
-                        clickwriteln!(writer, " Output:{}", *pf.output.lock().unwrap());
+                        clickwriteln!(
+                            writer,
+                            "{} Output:{}",
+                            description,
+                            *pf.output.lock().unwrap()
+                        );
+                    } else if !stop {
+                        clickwriteln!(writer, "{}", description);
                     }
+                    description
                 }
                 None => {
-                    clickwriteln!(writer, "Invalid index (try without args to get a list)");
-                    return Ok(()); // TODO: Return error
+                    return Err(ClickError::CommandError(
+                        "Invalid index (try without args to get a list)".to_string(),
+                    ));
                 }
-            }
+            };
 
             if stop {
-                clickwrite!(writer, "  [y/N]? ");
-                io::stdout().flush().expect("Could not flush stdout");
-                let mut conf = String::new();
-                if io::stdin().read_line(&mut conf).is_ok() {
-                    if conf.trim() == "y" || conf.trim() == "yes" {
-                        match env.stop_port_forward(i) {
-                            Ok(()) => {
-                                clickwriteln!(writer, "Stopped");
-                            }
-                            Err(e) => {
-                                write!(stderr(), "Failed to stop: {}", e).unwrap_or(());
-                            }
+                if confirm_stop(env, &format!("port-forward {}", description), writer) {
+                    match env.stop_port_forward(i) {
+                        Ok(()) => {
+                            clickwriteln!(writer, "Stopped");
+                        }
+                        Err(e) => {
+                            write!(stderr(), "Failed to stop: {}", e).unwrap_or(());
                         }
-                    } else {
-                        clickwriteln!(writer, "Not stopping");
                     }
                 } else {
-                    clickwriteln!(writer, "Could not read response, not stopping.");
+                    clickwriteln!(writer, "Not stopping");
                 }
-            } else {
-                clickwrite!(writer, "\n"); // just flush the above description
             }
         } else {
             print_pfs(env.get_port_forwards(), writer);
@@ -299,3 +485,28 @@ command!(
         Ok(())
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_fixed_local_port() {
+        assert!(has_fixed_local_port("5000"));
+        assert!(has_fixed_local_port("8080:9090"));
+        assert!(!has_fixed_local_port(":3456"));
+        assert!(!has_fixed_local_port("0:3456"));
+    }
+
+    #[test]
+    fn test_forwarding_addresses() {
+        let output = "Forwarding from 127.0.0.1:54321 -> 3456\n\
+                      Forwarding from [::1]:54321 -> 3456\n\
+                      Handling connection for 54321\n\
+                      Forwarding from 127.0.0.1:54321 -> 3456\n";
+        assert_eq!(
+            forwarding_addresses(output),
+            vec!["127.0.0.1:54321 -> 3456"]
+        );
+    }
+}
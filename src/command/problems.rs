@@ -0,0 +1,234 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::offset::Utc;
+use clap::{Arg, Command as ClapCommand};
+use humantime::parse_duration;
+use k8s_openapi::{api::core::v1 as api, List, ListOptional};
+use rustyline::completion::Pair as RustlinePair;
+
+use crate::{
+    command::command_def::{all_namespaces_arg, exec_match, start_clap, Cmd},
+    command::events::get_event_ts,
+    command::nodes::{node_problems, node_to_kobj},
+    command::pods::{pod_is_unhealthy, pod_status, pod_to_kobj, ready_counts, restart_count},
+    completer,
+    env::Env,
+    kobj::KObj,
+    output::ClickWriter,
+    table::{CellSpec, ColorType},
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// At most this many warning events are printed, the most recent ones
+const MAX_EVENTS: usize = 20;
+
+fn print_pods(pods: &[&api::Pod], all_namespaces: bool, env: &Env, writer: &mut ClickWriter) {
+    if pods.is_empty() {
+        clickwriteln!(writer, "No unhealthy pods");
+        return;
+    }
+    let mut titles = vec!["####"];
+    if all_namespaces {
+        titles.push("Namespace");
+    }
+    titles.extend(["Name", "Ready", "Status", "Restarts"]);
+    let rows = pods
+        .iter()
+        .map(|pod| {
+            let mut row = vec![CellSpec::new_index()];
+            if all_namespaces {
+                row.push(pod.metadata.namespace.as_deref().into());
+            }
+            row.push(pod.metadata.name.as_deref().into());
+            row.push(ready_counts(pod).into());
+            row.push(pod_status(pod).into());
+            row.push(restart_count(pod).into());
+            row
+        })
+        .collect();
+    crate::table::print_table(titles, rows, env, writer);
+}
+
+/// Print the nodes with problems. They're numbered from first_index, after the pods
+fn print_nodes(
+    nodes: &[(&api::Node, Vec<&str>)],
+    first_index: usize,
+    env: &Env,
+    writer: &mut ClickWriter,
+) {
+    if nodes.is_empty() {
+        clickwriteln!(writer, "No nodes with problems");
+        return;
+    }
+    let rows = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, (node, problems))| {
+            vec![
+                (first_index + index).into(),
+                node.metadata.name.as_deref().into(),
+                CellSpec::new_status(problems.join(", ").into(), ColorType::Danger),
+            ]
+        })
+        .collect();
+    crate::table::print_table(vec!["####", "Node", "Problems"], rows, env, writer);
+}
+
+fn print_warnings(
+    events: &[&api::Event],
+    all_namespaces: bool,
+    env: &Env,
+    writer: &mut ClickWriter,
+) {
+    if events.is_empty() {
+        clickwriteln!(writer, "No recent warning events");
+        return;
+    }
+    let mut titles = vec![];
+    if all_namespaces {
+        titles.push("Namespace");
+    }
+    titles.extend(["Last Seen", "Object", "Reason", "Message"]);
+    let rows = events
+        .iter()
+        .map(|event| {
+            let mut row: Vec<CellSpec> = vec![];
+            if all_namespaces {
+                row.push(event.metadata.namespace.as_deref().into());
+            }
+            let last_seen = match get_event_ts(event) {
+                Some(ts) => crate::table::format_time(ts, env),
+                None => "unknown".to_string(),
+            };
+            row.push(last_seen.into());
+            row.push(
+                format!(
+                    "{}/{}",
+                    event.involved_object.kind.as_deref().unwrap_or("unknown"),
+                    event.involved_object.name.as_deref().unwrap_or("unknown")
+                )
+                .into(),
+            );
+            row.push(event.reason.as_deref().into());
+            row.push(event.message.as_deref().into());
+            row
+        })
+        .collect();
+    crate::table::print_table(titles, rows, env, writer);
+}
+
+command!(
+    Problems,
+    "problems",
+    "Show what needs attention: unhealthy pods, nodes with problems, and recent warning events",
+    |clap: ClapCommand<'static>| {
+        clap.arg(all_namespaces_arg())
+            .arg(
+                Arg::new("since")
+                    .long("since")
+                    .value_name("DURATION")
+                    .help("Show warning events from this far back (default 1h)")
+                    .validator(|s| parse_duration(s).map(|_| ()).map_err(|e| e.to_string()))
+                    .takes_value(true),
+            )
+            .after_help(
+                "Unhealthy pods are ones that aren't running, or are running without all their \
+containers ready (pods that ran to completion are fine). Nodes have problems if they aren't Ready, \
+have a condition like MemoryPressure or DiskPressure, or are cordoned. Pods and nodes are \
+numbered, so they can be selected like after a list command.
+
+Examples:
+  # what's wrong in the current namespace
+  problems
+
+  # what's wrong anywhere in the cluster in the last 10 minutes
+  problems -A --since 10m",
+            )
+    },
+    vec!["problems"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let namespace = if matches.is_present("all-namespaces") {
+            None
+        } else {
+            env.namespace.clone()
+        };
+        let since = parse_duration(matches.value_of("since").unwrap_or("1h")).unwrap(); // validated
+        let since =
+            chrono::Duration::from_std(since).unwrap_or_else(|_| chrono::Duration::hours(1));
+
+        let (request, _) = match namespace.as_deref() {
+            Some(ns) => api::Pod::list_namespaced_pod(ns, ListOptional::default())?,
+            None => api::Pod::list_pod_for_all_namespaces(ListOptional::default())?,
+        };
+        let pod_list: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+        let (request, _) = api::Node::list_node(ListOptional::default())?;
+        let node_list: List<api::Node> = env.run_on_context(|c| c.execute_list(request))?;
+        let (request, _) = match namespace.as_deref() {
+            Some(ns) => api::Event::list_namespaced_event(ns, ListOptional::default())?,
+            None => api::Event::list_event_for_all_namespaces(ListOptional::default())?,
+        };
+        let event_list: List<api::Event> = env.run_on_context(|c| c.execute_list(request))?;
+
+        let pods: Vec<&api::Pod> = pod_list
+            .items
+            .iter()
+            .filter(|pod| pod_is_unhealthy(pod))
+            .collect();
+        let nodes: Vec<(&api::Node, Vec<&str>)> = node_list
+            .items
+            .iter()
+            .map(|node| (node, node_problems(node)))
+            .filter(|(_, problems)| !problems.is_empty())
+            .collect();
+        let cutoff = Utc::now() - since;
+        let mut events: Vec<&api::Event> = event_list
+            .items
+            .iter()
+            .filter(|event| event.type_.as_deref() == Some("Warning"))
+            .filter(|event| get_event_ts(event).is_some_and(|ts| ts >= cutoff))
+            .collect();
+        events.sort_by_key(|event| get_event_ts(event));
+        let skipped = events.len().saturating_sub(MAX_EVENTS);
+        let events = &events[skipped..];
+
+        let all_namespaces = namespace.is_none();
+        clickwriteln!(writer, "Unhealthy Pods:");
+        print_pods(&pods, all_namespaces, env, writer);
+        clickwriteln!(writer, "\nNodes:");
+        print_nodes(&nodes, pods.len(), env, writer);
+        clickwriteln!(writer, "\nWarning Events:");
+        if skipped > 0 {
+            clickwriteln!(
+                writer,
+                "({} older warnings not shown, see 'events --type warning')",
+                skipped
+            );
+        }
+        print_warnings(events, all_namespaces, env, writer);
+
+        let kobjs: Vec<KObj> = pods
+            .iter()
+            .map(|pod| pod_to_kobj(pod))
+            .chain(nodes.iter().map(|(node, _)| node_to_kobj(node)))
+            .collect();
+        env.set_last_objs(kobjs);
+        Ok(())
+    }
+);
@@ -0,0 +1,1053 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use k8s_openapi::{
+    api::core::v1 as api, apimachinery::pkg::api::resource::Quantity, List, ListOptional,
+};
+use rustyline::completion::Pair as RustlinePair;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    command::pods::pod_resource,
+    completer,
+    env::Env,
+    error::ClickError,
+    output::ClickWriter,
+    table::{format_raw_quantity, percent_cell, raw_quantity, CellSpec, ColorType, QuantityKind},
+};
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::Write;
+
+/// What a group of pods asks for in total
+#[derive(Debug, Default, PartialEq)]
+struct Totals {
+    pods: usize,
+    cpu_requests: f64,
+    cpu_limits: f64,
+    memory_requests: f64,
+    memory_limits: f64,
+}
+
+impl Totals {
+    fn add(&mut self, pod: &api::Pod) {
+        self.add_times(pod, 1);
+    }
+
+    /// Add n copies of pod, like the replicas of a workload
+    fn add_times(&mut self, pod: &api::Pod, n: usize) {
+        let times = n as f64;
+        self.pods += n;
+        self.cpu_requests += pod_resource(pod, false, "cpu") * times;
+        self.cpu_limits += pod_resource(pod, true, "cpu") * times;
+        self.memory_requests += pod_resource(pod, false, "memory") * times;
+        self.memory_limits += pod_resource(pod, true, "memory") * times;
+    }
+
+    /// The total for a resource named the way ResourceQuotas name them, like requests.cpu, or
+    /// just cpu for its requests. None for resources that aren't totalled, like counts of services
+    fn for_quota(&self, resource: &str) -> Option<f64> {
+        match resource {
+            "pods" => Some(self.pods as f64),
+            "cpu" | "requests.cpu" => Some(self.cpu_requests),
+            "limits.cpu" => Some(self.cpu_limits),
+            "memory" | "requests.memory" => Some(self.memory_requests),
+            "limits.memory" => Some(self.memory_limits),
+            _ => None,
+        }
+    }
+
+    fn row(&self, workload: String) -> Vec<CellSpec<'_>> {
+        vec![
+            workload.into(),
+            CellSpec::new_int(self.pods as i64),
+            format_raw_quantity(self.cpu_requests, QuantityKind::Cpu).into(),
+            format_raw_quantity(self.cpu_limits, QuantityKind::Cpu).into(),
+            format_raw_quantity(self.memory_requests, QuantityKind::Bytes).into(),
+            format_raw_quantity(self.memory_limits, QuantityKind::Bytes).into(),
+        ]
+    }
+}
+
+/// The workload a pod belongs to, like Deployment/web, from its controller. Pods of a Deployment
+/// are owned by one of its ReplicaSets, whose name is the Deployment's plus the pod-template-hash
+pub fn workload_of(pod: &api::Pod) -> String {
+    let owner = pod
+        .metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .find(|owner| owner.controller == Some(true));
+    match owner {
+        Some(owner) if owner.kind == "ReplicaSet" => {
+            let hash = pod
+                .metadata
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get("pod-template-hash"));
+            match hash.and_then(|hash| owner.name.strip_suffix(&format!("-{}", hash))) {
+                Some(deployment) => format!("Deployment/{}", deployment),
+                None => format!("ReplicaSet/{}", owner.name),
+            }
+        }
+        Some(owner) => format!("{}/{}", owner.kind, owner.name),
+        None => format!(
+            "Pod/{}",
+            pod.metadata.name.as_deref().unwrap_or("<No Name>")
+        ),
+    }
+}
+
+fn totals_by_workload(pods: &[api::Pod]) -> BTreeMap<String, Totals> {
+    let mut totals: BTreeMap<String, Totals> = BTreeMap::new();
+    for pod in pods.iter() {
+        totals.entry(workload_of(pod)).or_default().add(pod);
+    }
+    totals
+}
+
+/// A row for each resource a quota limits: what the pods ask for in total (for the resources
+/// that's worked out for), what the quota says is used, and its hard limit
+fn quota_rows<'a>(quota: &'a api::ResourceQuota, totals: &Totals) -> Vec<Vec<CellSpec<'a>>> {
+    let name = quota.metadata.name.as_deref().unwrap_or("<No Name>");
+    let status = quota.status.as_ref();
+    let hard = status.and_then(|status| status.hard.as_ref());
+    let used = status.and_then(|status| status.used.as_ref());
+    hard.into_iter()
+        .flatten()
+        .map(|(resource, hard)| {
+            let kind = QuantityKind::for_resource(resource);
+            let hard = raw_quantity(hard);
+            let used = used
+                .and_then(|used| used.get(resource))
+                .map(raw_quantity)
+                .unwrap_or(0.0);
+            vec![
+                name.into(),
+                resource.as_str().into(),
+                totals
+                    .for_quota(resource)
+                    .map(|total| format_raw_quantity(total, kind))
+                    .unwrap_or_else(|| "-".to_string())
+                    .into(),
+                format_raw_quantity(used, kind).into(),
+                format_raw_quantity(hard, kind).into(),
+                percent_cell(used, hard),
+            ]
+        })
+        .collect()
+}
+
+command!(
+    Resources,
+    "resources",
+    "Sum up what the pods in the current namespace request and are limited to, by workload, and \
+     compare it to the namespace's ResourceQuotas",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("no-quotas")
+                .long("no-quotas")
+                .help("Don't read and show the ResourceQuotas")
+                .takes_value(false),
+        )
+        .after_help(
+            "Pods are grouped by the workload that controls them (a Deployment, StatefulSet, \
+DaemonSet, Job...), or on their own if nothing does. Finished pods are left out, since they don't \
+count against quotas or hold on to anything on their nodes. A pod's requests (and limits) are its \
+containers' added up, or its biggest init container's, if that's more.
+
+For each ResourceQuota in the namespace, each resource it limits is shown with what the pods ask for \
+in total (for cpu, memory and pods), what the quota says is used and its hard limit, and how much \
+of that limit is used, in yellow from 75% and red from 90%. Quotas with scopes only count some pods, \
+so their used amount can be less than the total.
+
+Examples:
+  # what does the current namespace need, and how close is it to its quota
+  resources",
+        )
+    },
+    vec!["resources"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let namespace = env.namespace.clone().ok_or_else(|| {
+            ClickError::CommandError("Need a namespace to sum up resources in".to_string())
+        })?;
+        let (request, _) = api::Pod::list_namespaced_pod(
+            &namespace,
+            ListOptional {
+                field_selector: Some("status.phase!=Succeeded,status.phase!=Failed"),
+                ..Default::default()
+            },
+        )?;
+        let pods: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+        let by_workload = totals_by_workload(&pods.items);
+        let mut total = Totals::default();
+        for pod in pods.items.iter() {
+            total.add(pod);
+        }
+        let mut rows: Vec<Vec<CellSpec<'_>>> = by_workload
+            .iter()
+            .map(|(workload, totals)| totals.row(workload.clone()))
+            .collect();
+        rows.push(total.row("Total".to_string()));
+        crate::table::print_table(
+            vec![
+                "Workload", "Pods", "CPU Req", "CPU Lim", "Mem Req", "Mem Lim",
+            ],
+            rows,
+            env,
+            writer,
+        );
+
+        if matches.is_present("no-quotas") {
+            return Ok(());
+        }
+        let (request, _) =
+            api::ResourceQuota::list_namespaced_resource_quota(&namespace, Default::default())?;
+        let quotas: List<api::ResourceQuota> = env.run_on_context(|c| c.execute_list(request))?;
+        clickwriteln!(writer, "");
+        if quotas.items.is_empty() {
+            clickwriteln!(writer, "No ResourceQuotas in {}", namespace);
+            return Ok(());
+        }
+        let rows = quotas
+            .items
+            .iter()
+            .flat_map(|quota| quota_rows(quota, &total))
+            .collect();
+        crate::table::print_table(
+            vec!["Quota", "Resource", "Pods Total", "Used", "Hard", "Used%"],
+            rows,
+            env,
+            writer,
+        );
+        Ok(())
+    }
+);
+
+/// The kinds of object quota-check can work out the pods of
+const WORKLOAD_KINDS: [&str; 7] = [
+    "Pod",
+    "Deployment",
+    "ReplicaSet",
+    "StatefulSet",
+    "DaemonSet",
+    "Job",
+    "CronJob",
+];
+
+/// A workload to check against quotas: its Kind/name, the spec of its pods, and how many it runs.
+/// None means one on each node, for DaemonSets
+struct PlannedWorkload {
+    key: String,
+    /// What workload_of calls its pods, when that's not key, for ReplicaSets a Deployment manages
+    controller_key: Option<String>,
+    namespace: Option<String>,
+    spec: api::PodSpec,
+    replicas: Option<i32>,
+}
+
+impl PlannedWorkload {
+    /// If pod is one the workload already has, which its planned pods replace
+    fn replaces(&self, pod: &api::Pod) -> bool {
+        let (kind, name) = self.key.split_once('/').unwrap_or_default();
+        match kind {
+            // a pod is counted under its controller, so look for it by name
+            "Pod" => pod.metadata.name.as_deref() == Some(name),
+            // the pods of a CronJob belong to the Jobs it starts, which are named for it and the
+            // minute they were scheduled for
+            "CronJob" => workload_of(pod)
+                .strip_prefix("Job/")
+                .and_then(|job| job.strip_prefix(name))
+                .and_then(|job| job.strip_prefix('-'))
+                .is_some_and(|scheduled| {
+                    !scheduled.is_empty() && scheduled.chars().all(|c| c.is_ascii_digit())
+                }),
+            _ => workload_of(pod) == *self.controller_key.as_ref().unwrap_or(&self.key),
+        }
+    }
+}
+
+/// The pod spec and number of pods of a workload (or pod), from its json
+fn planned_workload(value: &Value) -> Result<PlannedWorkload, ClickError> {
+    let kind = value.get("kind").and_then(Value::as_str).unwrap_or("");
+    let name = value
+        .pointer("/metadata/name")
+        .or_else(|| value.pointer("/metadata/generateName"))
+        .and_then(Value::as_str)
+        .unwrap_or("<none>");
+    let count = |pointer: &str| {
+        value
+            .pointer(pointer)
+            .and_then(Value::as_i64)
+            .map(|count| count as i32)
+            .or(Some(1))
+    };
+    let (spec_pointer, replicas) = match kind {
+        "Pod" => ("/spec", Some(1)),
+        "Deployment" | "ReplicaSet" | "StatefulSet" => {
+            ("/spec/template/spec", count("/spec/replicas"))
+        }
+        "DaemonSet" => ("/spec/template/spec", None),
+        "Job" => ("/spec/template/spec", count("/spec/parallelism")),
+        "CronJob" => (
+            "/spec/jobTemplate/spec/template/spec",
+            count("/spec/jobTemplate/spec/parallelism"),
+        ),
+        _ => {
+            return Err(ClickError::CommandError(format!(
+                "{}/{} is not a workload, can only check Pods, Deployments, ReplicaSets, \
+                 StatefulSets, DaemonSets, Jobs and CronJobs",
+                kind, name
+            )))
+        }
+    };
+    let controller_key = match kind {
+        "ReplicaSet" => value
+            .pointer("/metadata/ownerReferences")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .find(|owner| owner.get("controller").and_then(Value::as_bool) == Some(true))
+            .filter(|owner| owner.get("kind").and_then(Value::as_str) == Some("Deployment"))
+            .and_then(|owner| owner.get("name").and_then(Value::as_str))
+            .map(|deployment| format!("Deployment/{}", deployment)),
+        _ => None,
+    };
+    let spec = value
+        .pointer(spec_pointer)
+        .cloned()
+        .ok_or_else(|| ClickError::CommandError(format!("{}/{} has no pod spec", kind, name)))?;
+    Ok(PlannedWorkload {
+        key: format!("{}/{}", kind, name),
+        controller_key,
+        namespace: value
+            .pointer("/metadata/namespace")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        spec: serde_json::from_value(spec)?,
+        replicas,
+    })
+}
+
+/// The objects in a manifest, which can have several yaml documents, or be a List
+fn manifest_objects(contents: &str) -> Result<Vec<Value>, ClickError> {
+    let mut objects = vec![];
+    for document in serde_yaml::Deserializer::from_str(contents) {
+        let value = Value::deserialize(document)?;
+        if value.is_null() {
+            continue;
+        }
+        match value.get("items").and_then(Value::as_array) {
+            Some(items) if value.get("kind").and_then(Value::as_str) == Some("List") => {
+                objects.extend(items.iter().cloned())
+            }
+            _ => objects.push(value),
+        }
+    }
+    Ok(objects)
+}
+
+/// What the LimitRanges in a namespace say about each container: the limits and requests filled
+/// in for those it doesn't set, and the bounds it has to be in
+#[derive(Debug, Default)]
+struct ContainerLimits {
+    default: BTreeMap<String, Quantity>,
+    default_request: BTreeMap<String, Quantity>,
+    min: BTreeMap<String, Quantity>,
+    max: BTreeMap<String, Quantity>,
+    max_ratio: BTreeMap<String, Quantity>,
+}
+
+fn container_limits(ranges: &[api::LimitRange]) -> ContainerLimits {
+    let mut limits = ContainerLimits::default();
+    let items = ranges
+        .iter()
+        .filter_map(|range| range.spec.as_ref())
+        .flat_map(|spec| spec.limits.iter())
+        .filter(|item| item.type_ == "Container");
+    for item in items {
+        let merge = |into: &mut BTreeMap<String, Quantity>,
+                     from: &Option<BTreeMap<String, Quantity>>| {
+            for (resource, amount) in from.iter().flatten() {
+                into.entry(resource.clone())
+                    .or_insert_with(|| amount.clone());
+            }
+        };
+        merge(&mut limits.default, &item.default);
+        merge(&mut limits.default_request, &item.default_request);
+        merge(&mut limits.min, &item.min);
+        merge(&mut limits.max, &item.max);
+        merge(&mut limits.max_ratio, &item.max_limit_request_ratio);
+    }
+    limits
+}
+
+/// Fill in the limits and requests a container would get when it's created, in the order the api
+/// server does: a missing request is its own limit, if it has one, then the LimitRange defaults (a
+/// default limit is also the default request, if there's no default request). Returns what was
+/// filled in, and where it came from
+fn apply_defaults(container: &mut api::Container, limits: &ContainerLimits) -> Vec<String> {
+    let mut filled = vec![];
+    let resources = container.resources.get_or_insert_with(Default::default);
+    let container_limits = resources.limits.get_or_insert_with(Default::default);
+    let requests = resources.requests.get_or_insert_with(Default::default);
+    for (resource, amount) in container_limits.iter() {
+        if !requests.contains_key(resource) {
+            requests.insert(resource.clone(), amount.clone());
+            filled.push(format!("requests.{} (its limit)", resource));
+        }
+    }
+    for (resource, amount) in limits.default.iter() {
+        if !container_limits.contains_key(resource) {
+            container_limits.insert(resource.clone(), amount.clone());
+            filled.push(format!("limits.{} (LimitRange)", resource));
+        }
+    }
+    let defaults = limits.default_request.iter().chain(limits.default.iter());
+    for (resource, amount) in defaults {
+        if !requests.contains_key(resource) {
+            requests.insert(resource.clone(), amount.clone());
+            filled.push(format!("requests.{} (LimitRange)", resource));
+        }
+    }
+    filled
+}
+
+/// The ways a container (with defaults applied) breaks the LimitRanges' bounds, which would get
+/// its pods rejected
+fn limit_violations(container: &api::Container, limits: &ContainerLimits) -> Vec<String> {
+    let resources = container.resources.as_ref();
+    let amount = |limit: bool, resource: &str| {
+        resources
+            .and_then(|resources| {
+                if limit {
+                    resources.limits.as_ref()
+                } else {
+                    resources.requests.as_ref()
+                }
+            })
+            .and_then(|amounts| amounts.get(resource))
+    };
+    let mut violations = vec![];
+    for (resource, min) in limits.min.iter() {
+        match amount(false, resource) {
+            Some(request) if raw_quantity(request) < raw_quantity(min) => violations.push(format!(
+                "{}: {} request {} is below the minimum of {}",
+                container.name, resource, request.0, min.0
+            )),
+            None => violations.push(format!(
+                "{}: no {} request, but the minimum is {}",
+                container.name, resource, min.0
+            )),
+            _ => {}
+        }
+    }
+    for (resource, max) in limits.max.iter() {
+        match amount(true, resource) {
+            Some(limit) if raw_quantity(limit) > raw_quantity(max) => violations.push(format!(
+                "{}: {} limit {} is above the maximum of {}",
+                container.name, resource, limit.0, max.0
+            )),
+            None => violations.push(format!(
+                "{}: no {} limit, but the maximum is {}",
+                container.name, resource, max.0
+            )),
+            _ => {}
+        }
+    }
+    for (resource, ratio) in limits.max_ratio.iter() {
+        if let (Some(limit), Some(request)) = (amount(true, resource), amount(false, resource)) {
+            let request = raw_quantity(request);
+            if request > 0.0 && raw_quantity(limit) / request > raw_quantity(ratio) {
+                violations.push(format!(
+                    "{}: {} limit is more than {} times its request",
+                    container.name, resource, ratio.0
+                ));
+            }
+        }
+    }
+    violations
+}
+
+fn signed_quantity(amount: f64, kind: QuantityKind) -> String {
+    if amount >= 0.0 {
+        format!("+{}", format_raw_quantity(amount, kind))
+    } else {
+        format!("-{}", format_raw_quantity(-amount, kind))
+    }
+}
+
+/// A row for each resource a quota limits that pods count towards: what's used now, the change
+/// from replacing the current pods of the workloads with the planned ones, what would be used
+/// after, and if that's within the hard limit. The bool is false if any of it isn't
+fn quota_check_rows<'a>(
+    quota: &'a api::ResourceQuota,
+    current: &Totals,
+    planned: &Totals,
+) -> (Vec<Vec<CellSpec<'a>>>, bool) {
+    let name = quota.metadata.name.as_deref().unwrap_or("<No Name>");
+    let status = quota.status.as_ref();
+    let hard = status.and_then(|status| status.hard.as_ref());
+    let used = status.and_then(|status| status.used.as_ref());
+    let mut fits = true;
+    let rows = hard
+        .into_iter()
+        .flatten()
+        .filter_map(|(resource, hard)| {
+            let change = planned.for_quota(resource)? - current.for_quota(resource)?;
+            let kind = QuantityKind::for_resource(resource);
+            let hard = raw_quantity(hard);
+            let used = used
+                .and_then(|used| used.get(resource))
+                .map(raw_quantity)
+                .unwrap_or(0.0);
+            let after = used + change;
+            let fit_cell = if after <= hard {
+                CellSpec::with_colors("yes".into(), Some(ColorType::Success.into()), None)
+            } else {
+                fits = false;
+                CellSpec::with_colors("NO".into(), Some(ColorType::Danger.into()), None)
+            };
+            Some(vec![
+                name.into(),
+                resource.as_str().into(),
+                format_raw_quantity(used, kind).into(),
+                signed_quantity(change, kind).into(),
+                format_raw_quantity(after, kind).into(),
+                format_raw_quantity(hard, kind).into(),
+                percent_cell(after, hard),
+                fit_cell,
+            ])
+        })
+        .collect();
+    (rows, fits)
+}
+
+/// A pod to add up for quotas, from a workload's spec with defaults applied
+fn pod_with_spec(spec: api::PodSpec) -> api::Pod {
+    api::Pod {
+        spec: Some(spec),
+        ..Default::default()
+    }
+}
+
+/// Apply the LimitRange defaults to the workload's containers, and print them with what was
+/// filled in and any bounds they break. Returns true if there were no violations
+fn print_planned_containers(
+    workload: &mut PlannedWorkload,
+    limits: &ContainerLimits,
+    pods: i32,
+    env: &Env,
+    writer: &mut ClickWriter,
+) -> bool {
+    clickwriteln!(
+        writer,
+        "{}: {} pod{}",
+        workload.key,
+        pods,
+        if pods == 1 { "" } else { "s" }
+    );
+    let spec = &mut workload.spec;
+    let containers = spec
+        .init_containers
+        .iter_mut()
+        .flatten()
+        .chain(spec.containers.iter_mut());
+    let mut rows = vec![];
+    let mut violations = vec![];
+    for container in containers {
+        let filled = apply_defaults(container, limits);
+        violations.extend(limit_violations(container, limits));
+        let resources = container.resources.as_ref();
+        let amount = |limit: bool, resource: &str| -> CellSpec<'_> {
+            resources
+                .and_then(|resources| {
+                    if limit {
+                        resources.limits.as_ref()
+                    } else {
+                        resources.requests.as_ref()
+                    }
+                })
+                .and_then(|amounts| amounts.get(resource))
+                .map(|amount| amount.0.clone())
+                .unwrap_or_else(|| "-".to_string())
+                .into()
+        };
+        rows.push(vec![
+            container.name.clone().into(),
+            amount(false, "cpu"),
+            amount(true, "cpu"),
+            amount(false, "memory"),
+            amount(true, "memory"),
+            if filled.is_empty() {
+                "-".into()
+            } else {
+                filled.join("\n").into()
+            },
+        ]);
+    }
+    crate::table::print_table(
+        vec![
+            "Container",
+            "CPU Req",
+            "CPU Lim",
+            "Mem Req",
+            "Mem Lim",
+            "Filled In",
+        ],
+        rows,
+        env,
+        writer,
+    );
+    for violation in violations.iter() {
+        clickwriteln!(writer, "{}", env.styles.danger(violation));
+    }
+    violations.is_empty()
+}
+
+command!(
+    QuotaCheck,
+    "quota-check",
+    "Check if a manifest, or the active workload(s), would fit in the namespace's ResourceQuotas, \
+     and what its LimitRanges would fill in",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("file")
+                .short('f')
+                .long("file")
+                .help("A manifest to check (yaml or json), instead of the active workload(s)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("replicas")
+                .short('r')
+                .long("replicas")
+                .help("Check with this many pods for each workload, instead of its replicas")
+                .validator(|s| match s.parse::<i32>() {
+                    Ok(replicas) if replicas >= 0 => Ok(()),
+                    _ => Err("must be a number of pods, 0 or more".to_string()),
+                })
+                .takes_value(true),
+        )
+        .after_help(
+            "This shows what would happen when the workloads are applied: the limits and \
+requests the namespace's LimitRanges fill in for containers that don't set them (a container \
+without a request also gets its limit as its request), any of the LimitRanges' bounds the \
+containers break, and for each ResourceQuota how the cpu, memory and pod counts it limits would \
+change.
+
+Pods the workloads already have in the namespace are counted as replaced, so checking a workload \
+that exists shows the effect of the change (like more replicas). Extra pods started during a \
+rolling update, quota scopes and Pod level LimitRanges aren't taken into account. Manifests can \
+have several documents, and objects that aren't workloads (like Services) are skipped.
+
+Examples:
+  # would this deployment fit?
+  quota-check -f web.yaml
+
+  # could the selected deployment scale to 10 replicas?
+  quota-check --replicas 10",
+        )
+    },
+    vec!["quota-check"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let values: Vec<Value> = match matches.value_of("file") {
+            // skip things like Services and ConfigMaps that are applied along with workloads
+            Some(file) => manifest_objects(&fs::read_to_string(file)?)?
+                .into_iter()
+                .filter(|value| match value.get("kind").and_then(Value::as_str) {
+                    Some(kind) => WORKLOAD_KINDS.contains(&kind),
+                    None => true,
+                })
+                .collect(),
+            None => {
+                let mut values = vec![];
+                env.apply_to_selection(writer, None, |obj, _| {
+                    values.push(env.run_on_context(|c| c.get_value(obj.read_request()?))?);
+                    Ok(())
+                })?;
+                values
+            }
+        };
+        let mut workloads = values
+            .iter()
+            .map(planned_workload)
+            .collect::<Result<Vec<PlannedWorkload>, ClickError>>()?;
+        if workloads.is_empty() {
+            return Err(ClickError::CommandError(
+                "No workloads in the manifest".to_string(),
+            ));
+        }
+        let namespaces: BTreeSet<&str> = workloads
+            .iter()
+            .filter_map(|workload| workload.namespace.as_deref())
+            .collect();
+        let namespace = match namespaces.len() {
+            0 => env.namespace.clone().ok_or_else(|| {
+                ClickError::CommandError("Need a namespace to check quotas in".to_string())
+            })?,
+            1 => namespaces.into_iter().next().unwrap().to_string(),
+            _ => {
+                return Err(ClickError::CommandError(
+                    "The workloads are in different namespaces, check them one at a time"
+                        .to_string(),
+                ))
+            }
+        };
+        let replicas = matches
+            .value_of("replicas")
+            .map(|replicas| replicas.parse::<i32>().unwrap()); // validated
+
+        let (request, _) =
+            api::LimitRange::list_namespaced_limit_range(&namespace, Default::default())?;
+        let ranges: List<api::LimitRange> = env.run_on_context(|c| c.execute_list(request))?;
+        let limits = container_limits(&ranges.items);
+        let node_count = if replicas.is_none() && workloads.iter().any(|w| w.replicas.is_none()) {
+            let (request, _) = api::Node::list_node(Default::default())?;
+            let nodes: List<api::Node> = env.run_on_context(|c| c.execute_list(request))?;
+            nodes.items.len() as i32
+        } else {
+            0
+        };
+
+        let mut planned = Totals::default();
+        let mut allowed = true;
+        for (i, workload) in workloads.iter_mut().enumerate() {
+            if i > 0 {
+                clickwriteln!(writer, "");
+            }
+            let pods = replicas.or(workload.replicas).unwrap_or(node_count);
+            allowed &= print_planned_containers(workload, &limits, pods, env, writer);
+            let pod = pod_with_spec(workload.spec.clone());
+            planned.add_times(&pod, pods.max(0) as usize);
+        }
+
+        let (request, _) = api::Pod::list_namespaced_pod(
+            &namespace,
+            ListOptional {
+                field_selector: Some("status.phase!=Succeeded,status.phase!=Failed"),
+                ..Default::default()
+            },
+        )?;
+        let pods: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+        let mut current = Totals::default();
+        for pod in pods.items.iter() {
+            if workloads.iter().any(|workload| workload.replaces(pod)) {
+                current.add(pod);
+            }
+        }
+
+        let (request, _) =
+            api::ResourceQuota::list_namespaced_resource_quota(&namespace, Default::default())?;
+        let quotas: List<api::ResourceQuota> = env.run_on_context(|c| c.execute_list(request))?;
+        clickwriteln!(writer, "");
+        if quotas.items.is_empty() {
+            clickwriteln!(writer, "No ResourceQuotas in {}", namespace);
+        } else {
+            let mut rows = vec![];
+            for quota in quotas.items.iter() {
+                let (quota_rows, fits) = quota_check_rows(quota, &current, &planned);
+                rows.extend(quota_rows);
+                allowed &= fits;
+            }
+            crate::table::print_table(
+                vec![
+                    "Quota", "Resource", "Used", "Change", "After", "Hard", "After%", "Fits",
+                ],
+                rows,
+                env,
+                writer,
+            );
+        }
+        if allowed {
+            clickwriteln!(
+                writer,
+                "{}",
+                env.styles.success(&format!("Fits in {}", namespace))
+            );
+        } else {
+            clickwriteln!(
+                writer,
+                "{}",
+                env.styles
+                    .danger(&format!("Would not fit in {}", namespace))
+            );
+        }
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pod(name: &str, owner: Option<(&str, &str)>, cpu: &str) -> api::Pod {
+        let owners = match owner {
+            Some((kind, owner)) => json!([{
+                "apiVersion": "apps/v1",
+                "kind": kind,
+                "name": owner,
+                "uid": "1234",
+                "controller": true,
+            }]),
+            None => json!([]),
+        };
+        serde_json::from_value(json!({
+            "metadata": {
+                "name": name,
+                "labels": { "pod-template-hash": "5d4f8" },
+                "ownerReferences": owners,
+            },
+            "spec": { "containers": [{
+                "name": "app",
+                "resources": {
+                    "requests": { "cpu": cpu, "memory": "1Gi" },
+                    "limits": { "memory": "2Gi" },
+                },
+            }]},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_totals_by_workload() {
+        let pods = vec![
+            pod("web-5d4f8-abcde", Some(("ReplicaSet", "web-5d4f8")), "250m"),
+            pod("web-5d4f8-fghij", Some(("ReplicaSet", "web-5d4f8")), "250m"),
+            pod("bare-rs-xyz", Some(("ReplicaSet", "bare-rs")), "100m"),
+            pod("db-0", Some(("StatefulSet", "db")), "1"),
+            pod("debug", None, "100m"),
+        ];
+        let totals = totals_by_workload(&pods);
+        let workloads: Vec<&str> = totals.keys().map(String::as_str).collect();
+        assert_eq!(
+            workloads,
+            vec![
+                "Deployment/web",
+                "Pod/debug",
+                "ReplicaSet/bare-rs",
+                "StatefulSet/db"
+            ]
+        );
+        let web = &totals["Deployment/web"];
+        assert_eq!(web.pods, 2);
+        assert_eq!(web.cpu_requests, 0.5);
+        assert_eq!(web.memory_limits, 4.0 * 1024.0 * 1024.0 * 1024.0);
+        assert_eq!(web.for_quota("requests.cpu"), Some(0.5));
+        assert_eq!(web.for_quota("cpu"), Some(0.5));
+        assert_eq!(web.for_quota("pods"), Some(2.0));
+        assert_eq!(web.for_quota("limits.cpu"), Some(0.0));
+        assert_eq!(web.for_quota("services"), None);
+    }
+
+    #[test]
+    fn test_totals_add_times() {
+        let mut totals = Totals::default();
+        totals.add_times(&pod("web", None, "250m"), i32::MAX as usize);
+        assert_eq!(totals.pods, i32::MAX as usize);
+        assert_eq!(totals.cpu_requests, 0.25 * i32::MAX as f64);
+        assert_eq!(
+            totals.for_quota("limits.memory"),
+            Some(2.0 * 1024.0 * 1024.0 * 1024.0 * i32::MAX as f64)
+        );
+        totals.add_times(&pod("web", None, "250m"), 0);
+        assert_eq!(totals.pods, i32::MAX as usize);
+    }
+
+    #[test]
+    fn test_planned_workload() {
+        let deployment = json!({
+            "kind": "Deployment",
+            "metadata": { "name": "web", "namespace": "prod" },
+            "spec": {
+                "replicas": 3,
+                "template": { "spec": { "containers": [{ "name": "app" }] } },
+            },
+        });
+        let workload = planned_workload(&deployment).unwrap();
+        assert_eq!(workload.key, "Deployment/web");
+        assert_eq!(workload.namespace.as_deref(), Some("prod"));
+        assert_eq!(workload.replicas, Some(3));
+        assert_eq!(workload.spec.containers[0].name, "app");
+
+        let cronjob = json!({
+            "kind": "CronJob",
+            "metadata": { "name": "backup" },
+            "spec": { "jobTemplate": { "spec": { "template": { "spec": {
+                "containers": [{ "name": "dump" }],
+            }}}}},
+        });
+        let workload = planned_workload(&cronjob).unwrap();
+        assert_eq!(workload.replicas, Some(1));
+        assert_eq!(workload.namespace, None);
+
+        let daemonset = json!({
+            "kind": "DaemonSet",
+            "metadata": { "name": "agent" },
+            "spec": { "template": { "spec": { "containers": [] } } },
+        });
+        assert_eq!(planned_workload(&daemonset).unwrap().replicas, None);
+        assert!(planned_workload(&json!({ "kind": "Service", "metadata": {} })).is_err());
+    }
+
+    #[test]
+    fn test_replaces() {
+        let replicaset = json!({
+            "kind": "ReplicaSet",
+            "metadata": {
+                "name": "web-5d4f8",
+                "ownerReferences": [{ "kind": "Deployment", "name": "web", "controller": true }],
+            },
+            "spec": { "template": { "spec": { "containers": [] } } },
+        });
+        let workload = planned_workload(&replicaset).unwrap();
+        assert_eq!(workload.key, "ReplicaSet/web-5d4f8");
+        assert!(workload.replaces(&pod(
+            "web-5d4f8-abcde",
+            Some(("ReplicaSet", "web-5d4f8")),
+            "1"
+        )));
+        assert!(!workload.replaces(&pod(
+            "api-5d4f8-abcde",
+            Some(("ReplicaSet", "api-5d4f8")),
+            "1"
+        )));
+
+        let cronjob = json!({
+            "kind": "CronJob",
+            "metadata": { "name": "backup" },
+            "spec": { "jobTemplate": { "spec": { "template": { "spec": { "containers": [] } } } } },
+        });
+        let workload = planned_workload(&cronjob).unwrap();
+        assert!(workload.replaces(&pod(
+            "backup-27845040-x",
+            Some(("Job", "backup-27845040")),
+            "1"
+        )));
+        assert!(!workload.replaces(&pod("backup-db-x", Some(("Job", "backup-db")), "1")));
+
+        let single = json!({
+            "kind": "Pod",
+            "metadata": { "name": "web-5d4f8-abcde" },
+            "spec": { "containers": [] },
+        });
+        let workload = planned_workload(&single).unwrap();
+        assert!(workload.replaces(&pod(
+            "web-5d4f8-abcde",
+            Some(("ReplicaSet", "web-5d4f8")),
+            "1"
+        )));
+        assert!(!workload.replaces(&pod(
+            "web-5d4f8-fghij",
+            Some(("ReplicaSet", "web-5d4f8")),
+            "1"
+        )));
+    }
+
+    #[test]
+    fn test_manifest_objects() {
+        let manifest = "kind: Service\n---\n---\nkind: List\nitems:\n- kind: Pod\n- kind: Job\n";
+        let kinds: Vec<Value> = manifest_objects(manifest)
+            .unwrap()
+            .into_iter()
+            .map(|value| value["kind"].clone())
+            .collect();
+        assert_eq!(kinds, vec![json!("Service"), json!("Pod"), json!("Job")]);
+    }
+
+    fn limit_range() -> api::LimitRange {
+        serde_json::from_value(json!({
+            "metadata": { "name": "defaults" },
+            "spec": { "limits": [{
+                "type": "Container",
+                "default": { "cpu": "500m", "memory": "512Mi" },
+                "defaultRequest": { "cpu": "100m" },
+                "max": { "memory": "1Gi" },
+                "maxLimitRequestRatio": { "cpu": "4" },
+            }, {
+                "type": "Pod",
+                "max": { "cpu": "1m" },
+            }]},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_apply_defaults() {
+        let limits = container_limits(&[limit_range()]);
+        assert!(!limits.max.contains_key("cpu")); // pod limits are ignored
+        let mut container: api::Container = serde_json::from_value(json!({
+            "name": "app",
+            "resources": { "limits": { "memory": "2Gi" } },
+        }))
+        .unwrap();
+        let filled = apply_defaults(&mut container, &limits);
+        assert_eq!(
+            filled,
+            vec![
+                "requests.memory (its limit)",
+                "limits.cpu (LimitRange)",
+                "requests.cpu (LimitRange)",
+            ]
+        );
+        let resources = container.resources.as_ref().unwrap();
+        let requests = resources.requests.as_ref().unwrap();
+        assert_eq!(requests["cpu"].0, "100m");
+        assert_eq!(requests["memory"].0, "2Gi");
+        assert_eq!(resources.limits.as_ref().unwrap()["cpu"].0, "500m");
+        // 2Gi is over the max, and 500m is 5 times 100m
+        assert_eq!(
+            limit_violations(&container, &limits),
+            vec![
+                "app: memory limit 2Gi is above the maximum of 1Gi",
+                "app: cpu limit is more than 4 times its request",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quota_check_rows() {
+        let quota: api::ResourceQuota = serde_json::from_value(json!({
+            "metadata": { "name": "compute" },
+            "status": {
+                "hard": { "requests.cpu": "2", "pods": "10", "services": "5" },
+                "used": { "requests.cpu": "1500m", "pods": "4" },
+            },
+        }))
+        .unwrap();
+        let mut current = Totals::default();
+        current.add(&pod("web-5d4f8-abcde", None, "250m"));
+        let mut planned = Totals::default();
+        let (rows, fits) = quota_check_rows(&quota, &current, &planned);
+        assert_eq!(rows.len(), 2); // services aren't counted
+        assert!(fits);
+        for _ in 0..3 {
+            planned.add(&pod("web-5d4f8-abcde", None, "250m"));
+        }
+        // 1.5 - 0.25 + 0.75 = 2 cpus, which just fits
+        assert!(quota_check_rows(&quota, &current, &planned).1);
+        planned.add(&pod("web-5d4f8-abcde", None, "250m"));
+        assert!(!quota_check_rows(&quota, &current, &planned).1);
+    }
+}
@@ -16,14 +16,20 @@
 use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::{
     apimachinery::pkg::apis::meta::v1::ObjectMeta, http, ListOptional, ListResponse,
-    ListableResource, Metadata, NamespaceResourceScope, RequestError, Resource, Response,
-    ResponseBody, ResponseError,
+    ListableResource, Metadata, NamespaceResourceScope, RequestError, Resource, ResponseBody,
 };
-use serde_json::{value::from_value, Error, Value};
+use serde_json::{value::from_value, Value};
 
 use crate::{
-    command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{get_list_request_for_url, get_read_request_for_url, run_list_command, Extractor},
+    command::command_def::{
+        all_namespaces_arg, columns_arg, exec_match, field_selector_arg, filter_arg,
+        filter_pattern_arg, hide_arg, label_selector_arg, no_headers_arg, output_arg, quiet_arg,
+        show_arg, sort_arg, sort_by_arg, start_clap, watch_arg, Cmd,
+    },
+    command::{
+        get_list_request_for_url, get_read_request_for_url, list_namespace, list_options,
+        run_list_command, Extractor,
+    },
     completer,
     env::Env,
     kobj::{KObj, ObjType},
@@ -107,8 +113,20 @@ list_command!(
                 .help("Filter statefulsets by the specified regex")
                 .takes_value(true)
         )
+        .arg(all_namespaces_arg())
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
         .arg(show_arg(EXTRA_COL_FLAGS, true))
         .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
         .arg(
             Arg::new("reverse")
                 .short('R')
@@ -120,9 +138,9 @@ list_command!(
     noop_complete!(),
     [].into_iter(),
     |matches, env, writer| {
-        let (request, _response_body) = match &env.namespace {
-            Some(ns) => RolloutValue::list_namespaced_rollout(ns, Default::default())?,
-            None => RolloutValue::list_rollout_for_all_namespaces(Default::default())?,
+        let (request, _response_body) = match list_namespace(&matches, env) {
+            Some(ns) => RolloutValue::list_namespaced_rollout(ns, list_options(&matches))?,
+            None => RolloutValue::list_rollout_for_all_namespaces(list_options(&matches))?,
         };
         let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
 
@@ -195,42 +213,6 @@ impl Resource for RolloutValue {
     type Scope = NamespaceResourceScope;
 }
 
-#[derive(Debug)]
-pub enum ReadNamespacedRolloutValueResponse {
-    Ok(Box<RolloutValue>),
-    Other(Result<Option<Value>, Error>),
-}
-
-impl Response for ReadNamespacedRolloutValueResponse {
-    fn try_from_parts(
-        status_code: http::StatusCode,
-        buf: &[u8],
-    ) -> Result<(Self, usize), ResponseError> {
-        match status_code {
-            http::StatusCode::OK => {
-                let result = match serde_json::from_slice(buf) {
-                    Ok(value) => value,
-                    Err(err) if err.is_eof() => return Err(ResponseError::NeedMoreData),
-                    Err(err) => return Err(ResponseError::Json(err)),
-                };
-                Ok((ReadNamespacedRolloutValueResponse::Ok(result), buf.len()))
-            }
-            _ => {
-                let (result, read) = if buf.is_empty() {
-                    (Ok(None), 0)
-                } else {
-                    match serde_json::from_slice(buf) {
-                        Ok(value) => (Ok(Some(value)), buf.len()),
-                        Err(err) if err.is_eof() => return Err(ResponseError::NeedMoreData),
-                        Err(err) => (Err(err), 0),
-                    }
-                };
-                Ok((ReadNamespacedRolloutValueResponse::Other(result), read))
-            }
-        }
-    }
-}
-
 impl RolloutValue {
     #[allow(clippy::type_complexity)] // type is from k8s_openapi
     pub fn list_namespaced_rollout(
@@ -264,18 +246,11 @@ impl RolloutValue {
         get_list_request_for_url(url, optional)
     }
 
-    #[allow(clippy::type_complexity)] // type is from k8s_openapi
     pub fn read_namespaced_rollout(
         name: &str,
         namespace: &str,
         _optional: ListOptional<'_>,
-    ) -> Result<
-        (
-            http::Request<Vec<u8>>,
-            fn(_: http::StatusCode) -> ResponseBody<ReadNamespacedRolloutValueResponse>,
-        ),
-        RequestError,
-    > {
+    ) -> Result<http::Request<Vec<u8>>, RequestError> {
         let url = format!(
             "/apis/argoproj.io/v1alpha1/namespaces/{}/rollouts/{}",
             namespace, name
@@ -0,0 +1,454 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::Command as ClapCommand;
+use k8s_openapi::api::core::v1 as api;
+use rustyline::completion::Pair as RustlinePair;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    completer,
+    describe::describe_table,
+    env::Env,
+    error::ClickError,
+    kobj::KObj,
+    output::ClickWriter,
+    table::{CellSpec, ColorType},
+};
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+/// The annotations seccomp and AppArmor profiles were set with before they had fields. AppArmor
+/// still only has an annotation in the api versions click supports
+const SECCOMP_POD_ANNOTATION: &str = "seccomp.security.alpha.kubernetes.io/pod";
+const SECCOMP_CONTAINER_ANNOTATION: &str = "container.seccomp.security.alpha.kubernetes.io/";
+const APPARMOR_CONTAINER_ANNOTATION: &str = "container.apparmor.security.beta.kubernetes.io/";
+
+fn annotation<'a>(pod: &'a api::Pod, key: &str) -> Option<&'a str> {
+    pod.metadata
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(key))
+        .map(String::as_str)
+}
+
+/// A seccomp profile like RuntimeDefault or Localhost/profiles/audit.json, from its field or, if
+/// that's not set, the annotation
+fn seccomp_str(profile: Option<&api::SeccompProfile>, annotation: Option<&str>) -> Option<String> {
+    match profile {
+        Some(profile) => Some(match profile.localhost_profile.as_ref() {
+            Some(localhost) => format!("{}/{}", profile.type_, localhost),
+            None => profile.type_.clone(),
+        }),
+        None => annotation.map(str::to_string),
+    }
+}
+
+fn or_unset<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "<unset>".to_string())
+}
+
+/// The pod-security.kubernetes.io labels of a namespace, which set the Pod Security Admission
+/// levels its pods are held to, like enforce=restricted
+fn pod_security_levels(labels: Option<&BTreeMap<String, String>>) -> String {
+    let levels: Vec<String> = labels
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| {
+            key.strip_prefix("pod-security.kubernetes.io/")
+                .map(|mode| format!("{}={}", mode, value))
+        })
+        .collect();
+    if levels.is_empty() {
+        "<none>".to_string()
+    } else {
+        levels.join(", ")
+    }
+}
+
+/// The pod wide security settings, as rows of a description
+fn pod_security_rows(pod: &api::Pod) -> Vec<(&'static str, String)> {
+    let spec = pod.spec.as_ref();
+    let context = spec.and_then(|spec| spec.security_context.as_ref());
+    let host_namespaces: Vec<&str> = [
+        ("network", spec.and_then(|spec| spec.host_network)),
+        ("pid", spec.and_then(|spec| spec.host_pid)),
+        ("ipc", spec.and_then(|spec| spec.host_ipc)),
+    ]
+    .iter()
+    .filter(|(_, host)| *host == Some(true))
+    .map(|(namespace, _)| *namespace)
+    .collect();
+    vec![
+        (
+            "Service Account:",
+            spec.and_then(|spec| spec.service_account_name.clone())
+                .unwrap_or_else(|| "default".to_string()),
+        ),
+        (
+            "Run As User:",
+            or_unset(context.and_then(|context| context.run_as_user)),
+        ),
+        (
+            "Run As Group:",
+            or_unset(context.and_then(|context| context.run_as_group)),
+        ),
+        (
+            "Run As Non Root:",
+            or_unset(context.and_then(|context| context.run_as_non_root)),
+        ),
+        (
+            "FS Group:",
+            or_unset(context.and_then(|context| context.fs_group)),
+        ),
+        (
+            "Supplemental Groups:",
+            or_unset(
+                context
+                    .and_then(|context| context.supplemental_groups.as_ref())
+                    .filter(|groups| !groups.is_empty())
+                    .map(|groups| {
+                        groups
+                            .iter()
+                            .map(i64::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    }),
+            ),
+        ),
+        (
+            "Seccomp:",
+            or_unset(seccomp_str(
+                context.and_then(|context| context.seccomp_profile.as_ref()),
+                annotation(pod, SECCOMP_POD_ANNOTATION),
+            )),
+        ),
+        (
+            "Host Namespaces:",
+            if host_namespaces.is_empty() {
+                "<none>".to_string()
+            } else {
+                host_namespaces.join(", ")
+            },
+        ),
+    ]
+}
+
+/// The security settings a container runs with: its own, or the pod's where it doesn't set them
+#[derive(Debug, PartialEq)]
+struct ContainerSecurity {
+    name: String,
+    user: Option<i64>,
+    group: Option<i64>,
+    non_root: Option<bool>,
+    privileged: bool,
+    /// allowPrivilegeEscalation, which is true unless it's turned off
+    escalation: bool,
+    read_only_root: bool,
+    seccomp: Option<String>,
+    apparmor: Option<String>,
+    added: Vec<String>,
+    dropped: Vec<String>,
+}
+
+impl ContainerSecurity {
+    fn new(pod: &api::Pod, container: &api::Container, init: bool) -> ContainerSecurity {
+        let pod_context = pod
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.security_context.as_ref());
+        let context = container.security_context.as_ref();
+        let capabilities = context.and_then(|context| context.capabilities.as_ref());
+        let privileged = context
+            .and_then(|context| context.privileged)
+            .unwrap_or(false);
+        ContainerSecurity {
+            name: if init {
+                format!("{} (init)", container.name)
+            } else {
+                container.name.clone()
+            },
+            user: context
+                .and_then(|context| context.run_as_user)
+                .or_else(|| pod_context.and_then(|context| context.run_as_user)),
+            group: context
+                .and_then(|context| context.run_as_group)
+                .or_else(|| pod_context.and_then(|context| context.run_as_group)),
+            non_root: context
+                .and_then(|context| context.run_as_non_root)
+                .or_else(|| pod_context.and_then(|context| context.run_as_non_root)),
+            privileged,
+            escalation: privileged
+                || context
+                    .and_then(|context| context.allow_privilege_escalation)
+                    .unwrap_or(true),
+            read_only_root: context
+                .and_then(|context| context.read_only_root_filesystem)
+                .unwrap_or(false),
+            seccomp: seccomp_str(
+                context.and_then(|context| context.seccomp_profile.as_ref()),
+                annotation(
+                    pod,
+                    &format!("{}{}", SECCOMP_CONTAINER_ANNOTATION, container.name),
+                ),
+            )
+            .or_else(|| {
+                seccomp_str(
+                    pod_context.and_then(|context| context.seccomp_profile.as_ref()),
+                    annotation(pod, SECCOMP_POD_ANNOTATION),
+                )
+            }),
+            apparmor: annotation(
+                pod,
+                &format!("{}{}", APPARMOR_CONTAINER_ANNOTATION, container.name),
+            )
+            .map(str::to_string),
+            added: capabilities
+                .and_then(|capabilities| capabilities.add.clone())
+                .unwrap_or_default(),
+            dropped: capabilities
+                .and_then(|capabilities| capabilities.drop.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn row(&self) -> Vec<CellSpec<'_>> {
+        let opt = |value: Option<String>| value.unwrap_or_else(|| "-".to_string()).into();
+        let list = |list: &[String]| {
+            if list.is_empty() {
+                "-".into()
+            } else {
+                list.join(", ").into()
+            }
+        };
+        vec![
+            self.name.as_str().into(),
+            opt(self.user.map(|user| user.to_string())),
+            opt(self.group.map(|group| group.to_string())),
+            opt(self.non_root.map(|non_root| non_root.to_string())),
+            if self.privileged {
+                CellSpec::with_colors("true".into(), Some(ColorType::Danger.into()), None)
+            } else {
+                "false".into()
+            },
+            self.escalation.to_string().into(),
+            self.read_only_root.to_string().into(),
+            opt(self.seccomp.clone()),
+            opt(self.apparmor.clone()),
+            list(&self.added),
+            list(&self.dropped),
+        ]
+    }
+}
+
+fn container_securities(pod: &api::Pod) -> Vec<ContainerSecurity> {
+    let spec = match pod.spec.as_ref() {
+        Some(spec) => spec,
+        None => return vec![],
+    };
+    let init = spec
+        .init_containers
+        .iter()
+        .flatten()
+        .map(|container| ContainerSecurity::new(pod, container, true));
+    init.chain(
+        spec.containers
+            .iter()
+            .map(|container| ContainerSecurity::new(pod, container, false)),
+    )
+    .collect()
+}
+
+fn print_pod_security(env: &Env, obj: &KObj, writer: &mut ClickWriter) -> Result<(), ClickError> {
+    let pod: api::Pod = obj.read_kind(env, "security")?;
+    let namespace = obj.namespace.as_deref().unwrap_or_default();
+
+    let mut table = describe_table();
+    table.add_row(vec![
+        "Pod:".to_string(),
+        format!("{}/{}", namespace, obj.name()),
+    ]);
+    let (request, _) = api::Namespace::read_namespace(namespace, Default::default())?;
+    let levels = match env.run_on_context(|c| c.read_object::<api::Namespace>(request)) {
+        Ok(ns) => pod_security_levels(ns.metadata.labels.as_ref()),
+        // not everyone that can see pods can see their namespace
+        _ => "<could not read namespace>".to_string(),
+    };
+    table.add_row(vec!["Pod Security:".to_string(), levels]);
+    for (title, value) in pod_security_rows(&pod) {
+        table.add_row(vec![title.to_string(), value]);
+    }
+    clickwriteln!(writer, "{table}");
+
+    let securities = container_securities(&pod);
+    let rows = securities.iter().map(ContainerSecurity::row).collect();
+    crate::table::print_table(
+        vec![
+            "Container",
+            "User",
+            "Group",
+            "Non Root",
+            "Privileged",
+            "Escalation",
+            "RO Root",
+            "Seccomp",
+            "AppArmor",
+            "Caps Added",
+            "Caps Dropped",
+        ],
+        rows,
+        env,
+        writer,
+    );
+    Ok(())
+}
+
+command!(
+    Security,
+    "security",
+    "Show the security settings of the active pod(s) and their containers: users and groups, \
+     privileges, capabilities, and seccomp and AppArmor profiles",
+    |clap: ClapCommand<'static>| {
+        clap.after_help(
+            "Containers are shown with the settings they run with, which are the pod's where they \
+don't set their own. Escalation is allowPrivilegeEscalation, which is true unless turned off (and \
+always for privileged containers). Pod Security is the Pod Security Admission levels set on the \
+pod's namespace. A - means nothing is set, so the container runtime's default is used.
+
+Examples:
+  # what does the selected pod run as, and with which capabilities
+  security",
+        )
+    },
+    vec!["security"],
+    noop_complete!(),
+    no_named_complete!(),
+    |_matches, env, writer| {
+        env.apply_to_selection(
+            writer,
+            Some(&env.click_config.range_separator),
+            |obj, writer| print_pod_security(env, obj, writer),
+        )
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pod_security() {
+        let pod: api::Pod = serde_json::from_value(json!({
+            "metadata": {
+                "name": "web",
+                "annotations": {
+                    "container.apparmor.security.beta.kubernetes.io/web": "runtime/default",
+                },
+            },
+            "spec": {
+                "serviceAccountName": "web",
+                "hostNetwork": true,
+                "securityContext": {
+                    "runAsUser": 1000,
+                    "fsGroup": 2000,
+                    "seccompProfile": { "type": "RuntimeDefault" },
+                },
+                "initContainers": [{
+                    "name": "sysctl",
+                    "securityContext": { "privileged": true, "runAsUser": 0 },
+                }],
+                "containers": [{
+                    "name": "web",
+                    "securityContext": {
+                        "allowPrivilegeEscalation": false,
+                        "readOnlyRootFilesystem": true,
+                        "runAsNonRoot": true,
+                        "capabilities": { "add": ["NET_BIND_SERVICE"], "drop": ["ALL"] },
+                        "seccompProfile": {
+                            "type": "Localhost",
+                            "localhostProfile": "profiles/web.json",
+                        },
+                    },
+                }],
+            },
+        }))
+        .unwrap();
+        assert_eq!(
+            pod_security_rows(&pod),
+            vec![
+                ("Service Account:", "web".to_string()),
+                ("Run As User:", "1000".to_string()),
+                ("Run As Group:", "<unset>".to_string()),
+                ("Run As Non Root:", "<unset>".to_string()),
+                ("FS Group:", "2000".to_string()),
+                ("Supplemental Groups:", "<unset>".to_string()),
+                ("Seccomp:", "RuntimeDefault".to_string()),
+                ("Host Namespaces:", "network".to_string()),
+            ]
+        );
+        assert_eq!(
+            container_securities(&pod),
+            vec![
+                ContainerSecurity {
+                    name: "sysctl (init)".to_string(),
+                    user: Some(0),
+                    group: None,
+                    non_root: None,
+                    privileged: true,
+                    escalation: true,
+                    read_only_root: false,
+                    seccomp: Some("RuntimeDefault".to_string()),
+                    apparmor: None,
+                    added: vec![],
+                    dropped: vec![],
+                },
+                ContainerSecurity {
+                    name: "web".to_string(),
+                    user: Some(1000),
+                    group: None,
+                    non_root: Some(true),
+                    privileged: false,
+                    escalation: false,
+                    read_only_root: true,
+                    seccomp: Some("Localhost/profiles/web.json".to_string()),
+                    apparmor: Some("runtime/default".to_string()),
+                    added: vec!["NET_BIND_SERVICE".to_string()],
+                    dropped: vec!["ALL".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pod_security_levels() {
+        let labels: BTreeMap<String, String> = [
+            ("kubernetes.io/metadata.name", "prod"),
+            ("pod-security.kubernetes.io/enforce", "baseline"),
+            ("pod-security.kubernetes.io/warn", "restricted"),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        assert_eq!(
+            pod_security_levels(Some(&labels)),
+            "enforce=baseline, warn=restricted"
+        );
+        assert_eq!(pod_security_levels(None), "<none>");
+    }
+}
@@ -16,8 +16,12 @@ use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::api::storage::v1 as api_storage;
 
 use crate::{
-    command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{run_list_command, Extractor},
+    command::command_def::{
+        columns_arg, exec_match, field_selector_arg, filter_arg, filter_pattern_arg, hide_arg,
+        label_selector_arg, no_headers_arg, output_arg, quiet_arg, show_arg, sort_arg, sort_by_arg,
+        start_clap, watch_arg, Cmd,
+    },
+    command::{list_options, run_list_command, Extractor},
     completer,
     env::Env,
     kobj::{KObj, ObjType},
@@ -86,8 +90,19 @@ list_command!(
                 .help("Filter returned value by the specified regex")
                 .takes_value(true),
         )
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
         .arg(show_arg(EXTRA_COL_FLAGS, true))
         .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
         .arg(
             Arg::new("reverse")
                 .short('R')
@@ -98,11 +113,15 @@ list_command!(
     },
     vec!["storageclass", "storageclasses"],
     noop_complete!(),
-    [].into_iter(),
+    [(
+        "regex".to_string(),
+        completer::storageclass_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter(),
     |matches, env, writer| {
         let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
         let (request, _response_body) =
-            api_storage::StorageClass::list_storage_class(Default::default())?;
+            api_storage::StorageClass::list_storage_class(list_options(&matches))?;
 
         run_list_command(
             matches,
@@ -0,0 +1,419 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::{offset::Utc, DateTime, TimeZone};
+use clap::{Arg, Command as ClapCommand};
+use rustyline::completion::Pair as RustlinePair;
+use serde_json::Value;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    command::format_duration,
+    completer,
+    describe::DESCRIBE_TIME_FORMAT,
+    env::{Env, ObjectSelection},
+    error::ClickError,
+    kobj::{KObj, ObjType},
+    output::ClickWriter,
+    table::format_timestamp,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::process::Command;
+
+/// Where service account tokens are mounted in pods
+const DEFAULT_TOKEN_PATH: &str = "/var/run/secrets/kubernetes.io/serviceaccount/token";
+
+/// Claims that are shown in their own rows, so aren't listed with the others
+const KNOWN_CLAIMS: &[&str] = &["iss", "sub", "aud", "exp", "iat", "nbf", "kubernetes.io"];
+
+/// The claims of tokens made for service account secrets, before tokens were bound to pods
+const LEGACY_CLAIM_PREFIX: &str = "kubernetes.io/serviceaccount/";
+
+/// Decode a JWT into its header and claims. The signature isn't checked, there's nothing to check
+/// it with here, and the token is never sent anywhere
+fn decode_token(token: &str) -> Result<(Value, Value), ClickError> {
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    if parts.len() != 3 {
+        return Err(ClickError::CommandError(
+            "Not a token, expected three parts separated by dots".to_string(),
+        ));
+    }
+    let decode = |part: &str, what: &str| -> Result<Value, ClickError> {
+        let bytes = base64::decode_config(part, base64::URL_SAFE_NO_PAD).map_err(|e| {
+            ClickError::CommandError(format!("Could not decode the token's {}: {}", what, e))
+        })?;
+        serde_json::from_slice(&bytes).map_err(|e| {
+            ClickError::CommandError(format!("The token's {} isn't valid json: {}", what, e))
+        })
+    };
+    Ok((decode(parts[0], "header")?, decode(parts[1], "claims")?))
+}
+
+fn claim_time(claims: &Value, claim: &str) -> Option<DateTime<Utc>> {
+    claims
+        .get(claim)
+        .and_then(Value::as_i64)
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+}
+
+/// The namespace/name of the service account a token is for, from the claims of bound tokens or
+/// those of legacy secret tokens
+fn service_account(claims: &Value) -> Option<String> {
+    let str_at = |path: &str| claims.pointer(path).and_then(Value::as_str);
+    let legacy = |claim: &str| {
+        claims
+            .get(format!("{}{}", LEGACY_CLAIM_PREFIX, claim))
+            .and_then(Value::as_str)
+    };
+    let namespace = str_at("/kubernetes.io/namespace").or_else(|| legacy("namespace"))?;
+    let name =
+        str_at("/kubernetes.io/serviceaccount/name").or_else(|| legacy("service-account.name"))?;
+    Some(format!("{}/{}", namespace, name))
+}
+
+/// What a token is bound to: the pod (or secret, or node) it stops working without
+fn bound_to(claims: &Value) -> Option<String> {
+    for kind in ["Pod", "Secret", "Node"] {
+        let name = claims
+            .pointer(&format!("/kubernetes.io/{}/name", kind.to_lowercase()))
+            .and_then(Value::as_str);
+        if let Some(name) = name {
+            return Some(format!("{}/{}", kind, name));
+        }
+    }
+    claims
+        .get(format!("{}secret.name", LEGACY_CLAIM_PREFIX))
+        .and_then(Value::as_str)
+        .map(|name| format!("Secret/{} (legacy token)", name))
+}
+
+fn expiry_str(expires: Option<DateTime<Utc>>, now: DateTime<Utc>) -> String {
+    match expires {
+        Some(expires) if expires > now => {
+            format!("expires in {}", format_duration(expires - now))
+        }
+        Some(expires) => format!("expired {} ago", format_duration(now - expires)),
+        None => "never expires".to_string(),
+    }
+}
+
+/// Any claims without their own row, like jti, as claim: value
+fn other_claims(claims: &Value) -> Vec<String> {
+    claims
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(claim, _)| {
+            !KNOWN_CLAIMS.contains(&claim.as_str()) && !claim.starts_with(LEGACY_CLAIM_PREFIX)
+        })
+        .map(|(claim, value)| match value {
+            Value::String(value) => format!("{}: {}", claim, value),
+            value => format!("{}: {}", claim, value),
+        })
+        .collect()
+}
+
+fn print_token(token: &str, env: &Env, writer: &mut ClickWriter) -> Result<(), ClickError> {
+    let (header, claims) = decode_token(token)?;
+    let now = Utc::now();
+    let str_claim = |value: &Value, name: &str| {
+        value
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or("<none>")
+            .to_string()
+    };
+    let time = |time: Option<DateTime<Utc>>| {
+        time.map(|time| format_timestamp(time, DESCRIBE_TIME_FORMAT, env))
+            .unwrap_or_else(|| "<none>".to_string())
+    };
+    let audiences = match claims.get("aud") {
+        Some(Value::Array(audiences)) => audiences
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<&str>>()
+            .join(", "),
+        Some(Value::String(audience)) => audience.clone(),
+        _ => "<none>".to_string(),
+    };
+    let expires = claim_time(&claims, "exp");
+    let mut rows = vec![
+        ("Algorithm:", str_claim(&header, "alg")),
+        ("Key ID:", str_claim(&header, "kid")),
+        ("Issuer:", str_claim(&claims, "iss")),
+        ("Subject:", str_claim(&claims, "sub")),
+        ("Audiences:", audiences),
+        (
+            "Service Account:",
+            service_account(&claims).unwrap_or_else(|| "<none>".to_string()),
+        ),
+        (
+            "Bound To:",
+            bound_to(&claims).unwrap_or_else(|| "<nothing>".to_string()),
+        ),
+        ("Issued At:", time(claim_time(&claims, "iat"))),
+        (
+            "Expires:",
+            match expires {
+                Some(_) => format!("{} ({})", time(expires), expiry_str(expires, now)),
+                None => expiry_str(expires, now),
+            },
+        ),
+    ];
+    // the kubelet keeps using a token past this, but it should have been refreshed by now
+    if let Some(warn_after) = claims
+        .get("kubernetes.io")
+        .and_then(|k8s| claim_time(k8s, "warnafter"))
+    {
+        rows.push(("Warn After:", time(Some(warn_after))));
+    }
+    let others = other_claims(&claims);
+    if !others.is_empty() {
+        rows.push(("Other Claims:", others.join("\n")));
+    }
+
+    let mut table = comfy_table::Table::new();
+    table.load_preset(comfy_table::presets::NOTHING);
+    table.set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+    for (title, value) in rows {
+        table.add_row(vec![title.to_string(), value]);
+    }
+    clickwriteln!(writer, "{table}");
+    Ok(())
+}
+
+/// Read the token mounted at path in a pod, by running cat in it with kubectl
+fn read_pod_token(
+    env: &Env,
+    pod: &KObj,
+    path: &str,
+    container: Option<&str>,
+) -> Result<String, ClickError> {
+    let context = env.context.as_ref().ok_or_else(|| {
+        ClickError::CommandError("Need an active context to read a pod's token".to_string())
+    })?;
+    let namespace = pod
+        .namespace
+        .as_deref()
+        .ok_or_else(|| ClickError::CommandError(format!("No namespace for {}", pod.name())))?;
+    let kubectl_binary = env
+        .click_config
+        .kubectl_binary
+        .as_deref()
+        .unwrap_or("kubectl");
+    let mut command = Command::new(kubectl_binary);
+    command
+        .arg("--namespace")
+        .arg(namespace)
+        .arg("--context")
+        .arg(&context.name)
+        .arg("exec")
+        .arg(pod.name());
+    if let Some(container) = container {
+        command.arg("-c").arg(container);
+    }
+    command.arg("--").arg("cat").arg(path);
+    match command.output() {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        Ok(output) => Err(ClickError::CommandError(format!(
+            "Could not read {} in {}: {}",
+            path,
+            pod.name(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Err(ClickError::CommandError(format!(
+            "Could not find kubectl binary: '{}'. Is it in your PATH?",
+            kubectl_binary
+        ))),
+        Err(e) => Err(ClickError::Io(e)),
+    }
+}
+
+/// The token of a service account token secret (or any secret with a token key)
+fn read_secret_token(env: &Env, secret: &KObj) -> Result<String, ClickError> {
+    let value = env.run_on_context(|c| c.get_value(secret.read_request()?))?;
+    let encoded = value
+        .pointer("/data/token")
+        .and_then(Value::as_str)
+        .ok_or_else(|| {
+            ClickError::CommandError(format!("Secret {} has no token key", secret.name()))
+        })?;
+    let decoded = base64::decode(encoded).map_err(|e| {
+        ClickError::CommandError(format!("Could not decode the token in the secret: {}", e))
+    })?;
+    Ok(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+command!(
+    InspectToken,
+    "inspect-token",
+    "Decode a service account token and show its claims and when it expires",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("file")
+                .short('f')
+                .long("file")
+                .value_name("FILE")
+                .help(
+                    "Read the token from this local file, or from stdin if FILE is -. If not \
+                     given, the token of the active secret or pod is decoded",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("path")
+                .short('p')
+                .long("path")
+                .help("Where the token is mounted in the active pod")
+                .default_value(DEFAULT_TOKEN_PATH)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("container")
+                .short('c')
+                .long("container")
+                .help("The container of the active pod to read the token in")
+                .takes_value(true),
+        )
+        .after_help(
+            "The token is decoded here, and never sent anywhere, so its signature isn't checked. \
+The token comes from the file if one's given, otherwise from the token key of the active secret, \
+or for an active pod, from the file its service account token (or a projected volume, with \
+--path) is mounted at, which is read with kubectl exec. Tokens can't be given on the command line, \
+since they'd be saved in the history, but can be pasted in with -f - (end with ctrl-d).
+
+Examples:
+  # which service account is the selected pod using, and when does its token expire
+  inspect-token
+
+  # a token from a projected volume, in the sidecar container
+  inspect-token -c sidecar -p /var/run/secrets/tokens/vault-token
+
+  # a token copied from somewhere else
+  inspect-token -f -",
+        )
+    },
+    vec!["inspect-token"],
+    noop_complete!(),
+    [(
+        "container".to_string(),
+        completer::container_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter()
+    .collect(),
+    |matches, env, writer| {
+        if let Some(file) = matches.value_of("file") {
+            let token = if file == "-" {
+                io::read_to_string(io::stdin())?
+            } else {
+                fs::read_to_string(file)?
+            };
+            return print_token(&token, env, writer);
+        }
+        let path = matches.value_of("path").unwrap(); // has a default
+        let container = matches.value_of("container");
+        let read_token = |obj: &KObj| match obj.typ {
+            ObjType::Secret => read_secret_token(env, obj),
+            ObjType::Pod { .. } => read_pod_token(env, obj, path, container),
+            _ => Err(ClickError::CommandError(format!(
+                "{} is a {}, tokens can only be read from secrets and pods",
+                obj.name(),
+                obj.type_str()
+            ))),
+        };
+        match env.current_selection() {
+            ObjectSelection::None => Err(ClickError::CommandError(
+                "No token given, and no secret or pod is active".to_string(),
+            )),
+            _ => env.apply_to_selection(
+                writer,
+                Some(&env.click_config.range_separator),
+                |obj, writer| print_token(&read_token(obj)?, env, writer),
+            ),
+        }
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encode(value: &Value) -> String {
+        base64::encode_config(value.to_string(), base64::URL_SAFE_NO_PAD)
+    }
+
+    #[test]
+    fn test_decode_token() {
+        let claims = json!({
+            "aud": ["https://kubernetes.default.svc"],
+            "exp": 1715511600,
+            "iat": 1715508000,
+            "iss": "https://kubernetes.default.svc.cluster.local",
+            "jti": "1234",
+            "kubernetes.io": {
+                "namespace": "prod",
+                "pod": { "name": "web-abcde", "uid": "1" },
+                "serviceaccount": { "name": "web", "uid": "2" },
+                "warnafter": 1715511000,
+            },
+            "sub": "system:serviceaccount:prod:web",
+        });
+        let token = format!(
+            "{}.{}.c2ln",
+            encode(&json!({ "alg": "RS256", "kid": "key-1" })),
+            encode(&claims)
+        );
+        let (header, decoded) = decode_token(&token).unwrap();
+        assert_eq!(header["kid"], "key-1");
+        assert_eq!(decoded, claims);
+        assert_eq!(service_account(&claims), Some("prod/web".to_string()));
+        assert_eq!(bound_to(&claims), Some("Pod/web-abcde".to_string()));
+        assert_eq!(other_claims(&claims), vec!["jti: 1234"]);
+
+        let expires = claim_time(&claims, "exp");
+        let now = Utc.timestamp_opt(1715508000, 0).unwrap();
+        assert_eq!(expiry_str(expires, now), "expires in 1h 0m");
+        let later = Utc.timestamp_opt(1715515200, 0).unwrap();
+        assert_eq!(expiry_str(expires, later), "expired 1h 0m ago");
+        assert_eq!(expiry_str(None, now), "never expires");
+
+        assert!(decode_token("not-a-token").is_err());
+        assert!(decode_token("a.b.c").is_err());
+    }
+
+    #[test]
+    fn test_legacy_token_claims() {
+        let claims = json!({
+            "iss": "kubernetes/serviceaccount",
+            "kubernetes.io/serviceaccount/namespace": "prod",
+            "kubernetes.io/serviceaccount/secret.name": "web-token-xyz",
+            "kubernetes.io/serviceaccount/service-account.name": "web",
+            "kubernetes.io/serviceaccount/service-account.uid": "2",
+            "sub": "system:serviceaccount:prod:web",
+        });
+        assert_eq!(service_account(&claims), Some("prod/web".to_string()));
+        assert_eq!(
+            bound_to(&claims),
+            Some("Secret/web-token-xyz (legacy token)".to_string())
+        );
+        assert!(other_claims(&claims).is_empty());
+    }
+}
@@ -0,0 +1,607 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use k8s_openapi::{
+    api::core::v1 as api,
+    apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::ObjectMeta},
+    http::{self, StatusCode},
+    List, ListOptional, RequestError,
+};
+use rustyline::completion::Pair as RustlinePair;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    command::command_def::{all_namespaces_arg, exec_match, label_selector_arg, start_clap, Cmd},
+    command::list_namespace,
+    command::nodes::node_to_kobj,
+    command::pods::{container_resource, pod_to_kobj},
+    completer,
+    env::Env,
+    error::ClickError,
+    kobj::KObj,
+    output::ClickWriter,
+    table::{format_raw_quantity, percent, percent_cell, raw_quantity, CellSpec, QuantityKind},
+};
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+const METRICS_API: &str = "/apis/metrics.k8s.io/v1beta1";
+
+/// The resources the metrics api reports usage of, in the order usage arrays hold them
+const CPU: usize = 0;
+const MEMORY: usize = 1;
+const RESOURCES: [&str; 2] = ["cpu", "memory"];
+
+#[derive(Debug, Deserialize)]
+struct MetricsList<T> {
+    items: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PodMetrics {
+    metadata: ObjectMeta,
+    containers: Vec<ContainerMetrics>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContainerMetrics {
+    name: String,
+    usage: BTreeMap<String, Quantity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeMetrics {
+    metadata: ObjectMeta,
+    usage: BTreeMap<String, Quantity>,
+}
+
+fn resource_amounts(amounts: Option<&BTreeMap<String, Quantity>>) -> [f64; 2] {
+    RESOURCES.map(|resource| {
+        amounts
+            .and_then(|amounts| amounts.get(resource))
+            .map(raw_quantity)
+            .unwrap_or(0.0)
+    })
+}
+
+impl ContainerMetrics {
+    fn usage(&self) -> [f64; 2] {
+        resource_amounts(Some(&self.usage))
+    }
+}
+
+/// Get metrics from the metrics api at path (under METRICS_API). The api is served by
+/// metrics-server, which isn't installed in every cluster, so say so if it's missing
+fn get_metrics<T: DeserializeOwned>(
+    env: &Env,
+    path: &str,
+    label_selector: Option<&str>,
+) -> Result<Vec<T>, ClickError> {
+    let mut query_pairs =
+        url::form_urlencoded::Serializer::new(format!("{}{}?", METRICS_API, path));
+    if let Some(selector) = label_selector {
+        query_pairs.append_pair("labelSelector", selector);
+    }
+    let request = http::Request::get(query_pairs.finish())
+        .body(vec![])
+        .map_err(RequestError::Http)?;
+    let response = env.run_on_context(|c| c.execute(request))?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(ClickError::CommandError(
+            "The metrics api (metrics.k8s.io) isn't available, is metrics-server installed in \
+             this cluster?"
+                .to_string(),
+        ));
+    }
+    if !response.status().is_success() {
+        return Err(ClickError::CommandError(format!(
+            "Could not get metrics: {} {}",
+            response.status(),
+            String::from_utf8_lossy(response.body())
+        )));
+    }
+    let list: MetricsList<T> = serde_json::from_slice(response.body())?;
+    Ok(list.items)
+}
+
+/// What a pod (or one of its containers) is using, and what it requests and is limited to
+#[derive(Debug, PartialEq)]
+struct Usage {
+    obj: KObj,
+    container: Option<String>,
+    used: [f64; 2],
+    requests: [f64; 2],
+    limits: [f64; 2],
+}
+
+fn add(a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+/// Match up pod metrics with the pods they're for, to compare usage with requests and limits.
+/// Only running containers show up in metrics, so init containers aren't counted
+fn pod_usages(pods: &[api::Pod], metrics: &[PodMetrics], containers: bool) -> Vec<Usage> {
+    let pods: HashMap<(Option<&str>, Option<&str>), &api::Pod> = pods
+        .iter()
+        .map(|pod| {
+            (
+                (
+                    pod.metadata.namespace.as_deref(),
+                    pod.metadata.name.as_deref(),
+                ),
+                pod,
+            )
+        })
+        .collect();
+    let mut usages = vec![];
+    for pod_metrics in metrics.iter() {
+        let key = (
+            pod_metrics.metadata.namespace.as_deref(),
+            pod_metrics.metadata.name.as_deref(),
+        );
+        let pod = match pods.get(&key) {
+            Some(pod) => pod,
+            // deleted since its metrics were gathered
+            None => continue,
+        };
+        let obj = pod_to_kobj(pod);
+        let spec_containers = pod
+            .spec
+            .as_ref()
+            .map(|spec| spec.containers.as_slice())
+            .unwrap_or_default();
+        let container_usages = pod_metrics.containers.iter().map(|metrics| {
+            let spec = spec_containers.iter().find(|c| c.name == metrics.name);
+            let amounts = |limits: bool| {
+                RESOURCES.map(|resource| {
+                    spec.map(|spec| container_resource(spec, limits, resource))
+                        .unwrap_or(0.0)
+                })
+            };
+            Usage {
+                obj: obj.clone(),
+                container: Some(metrics.name.clone()),
+                used: metrics.usage(),
+                requests: amounts(false),
+                limits: amounts(true),
+            }
+        });
+        if containers {
+            usages.extend(container_usages);
+        } else {
+            let zero = [0.0, 0.0];
+            let mut pod_usage = Usage {
+                obj: obj.clone(),
+                container: None,
+                used: zero,
+                requests: zero,
+                limits: zero,
+            };
+            for usage in container_usages {
+                pod_usage.used = add(pod_usage.used, usage.used);
+                pod_usage.requests = add(pod_usage.requests, usage.requests);
+                pod_usage.limits = add(pod_usage.limits, usage.limits);
+            }
+            usages.push(pod_usage);
+        }
+    }
+    usages
+}
+
+/// Sort usages by name (then container), or biggest first by cpu or memory
+fn sort_usages(usages: &mut [Usage], sort: &str) {
+    let by_name = |a: &Usage, b: &Usage| {
+        (&a.obj.namespace, &a.obj.name, &a.container).cmp(&(
+            &b.obj.namespace,
+            &b.obj.name,
+            &b.container,
+        ))
+    };
+    match sort {
+        "cpu" => usages.sort_by(|a, b| b.used[CPU].total_cmp(&a.used[CPU])),
+        "memory" => usages.sort_by(|a, b| b.used[MEMORY].total_cmp(&a.used[MEMORY])),
+        _ => usages.sort_by(by_name),
+    }
+}
+
+/// cpu in millicores, like kubectl top, since most pods use a small part of a core
+fn cpu_str(cores: f64) -> String {
+    format!("{:.0}m", cores * 1000.0)
+}
+
+/// What a node is using, and what it has allocatable for pods
+#[derive(Debug, PartialEq)]
+struct NodeUsage {
+    obj: KObj,
+    used: [f64; 2],
+    allocatable: [f64; 2],
+}
+
+impl NodeUsage {
+    fn percent(&self, resource: usize) -> f64 {
+        percent(self.used[resource], self.allocatable[resource]).unwrap_or(0.0)
+    }
+}
+
+/// Match up node metrics with the nodes they're for, to compare usage with what's allocatable
+fn node_usages(nodes: &[api::Node], metrics: &[NodeMetrics]) -> Vec<NodeUsage> {
+    metrics
+        .iter()
+        .filter_map(|node_metrics| {
+            let node = nodes
+                .iter()
+                .find(|node| node.metadata.name == node_metrics.metadata.name)?;
+            let allocatable = node
+                .status
+                .as_ref()
+                .and_then(|status| status.allocatable.as_ref());
+            Some(NodeUsage {
+                obj: node_to_kobj(node),
+                used: resource_amounts(Some(&node_metrics.usage)),
+                allocatable: resource_amounts(allocatable),
+            })
+        })
+        .collect()
+}
+
+/// Sort nodes by name, or hottest first by the share of their cpu or memory in use
+fn sort_node_usages(usages: &mut [NodeUsage], sort: &str) {
+    match sort {
+        "cpu" => usages.sort_by(|a, b| b.percent(CPU).total_cmp(&a.percent(CPU))),
+        "memory" => usages.sort_by(|a, b| b.percent(MEMORY).total_cmp(&a.percent(MEMORY))),
+        _ => usages.sort_by(|a, b| a.obj.name.cmp(&b.obj.name)),
+    }
+}
+
+fn top_nodes(
+    env: &mut Env,
+    label_selector: Option<&str>,
+    sort: &str,
+    reverse: bool,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let metrics: Vec<NodeMetrics> = get_metrics(env, "/nodes", label_selector)?;
+    let (request, _) = api::Node::list_node(ListOptional {
+        label_selector,
+        ..Default::default()
+    })?;
+    let nodes: List<api::Node> = env.run_on_context(|c| c.execute_list(request))?;
+    let mut usages = node_usages(&nodes.items, &metrics);
+    sort_node_usages(&mut usages, sort);
+    if reverse {
+        usages.reverse();
+    }
+    let rows = usages
+        .iter()
+        .map(|usage| {
+            vec![
+                CellSpec::new_index(),
+                usage.obj.name().into(),
+                cpu_str(usage.used[CPU]).into(),
+                percent_cell(usage.used[CPU], usage.allocatable[CPU]),
+                format_raw_quantity(usage.used[MEMORY], QuantityKind::Bytes).into(),
+                percent_cell(usage.used[MEMORY], usage.allocatable[MEMORY]),
+            ]
+        })
+        .collect();
+    crate::table::print_table(
+        vec!["####", "Name", "CPU", "CPU%", "Memory", "Memory%"],
+        rows,
+        env,
+        writer,
+    );
+    env.set_last_objs(
+        usages
+            .into_iter()
+            .map(|usage| usage.obj)
+            .collect::<Vec<KObj>>(),
+    );
+    Ok(())
+}
+
+fn print_pod_usages(
+    usages: Vec<Usage>,
+    show_namespace: bool,
+    containers: bool,
+    env: &mut Env,
+    writer: &mut ClickWriter,
+) {
+    let mut titles = vec!["####"];
+    if show_namespace {
+        titles.push("Namespace");
+    }
+    titles.push("Name");
+    if containers {
+        titles.push("Container");
+    }
+    titles.extend(["CPU", "CPU/Req", "CPU/Lim", "Memory", "Mem/Req", "Mem/Lim"]);
+    let rows = usages
+        .iter()
+        .map(|usage| {
+            let mut row: Vec<CellSpec<'_>> = vec![CellSpec::new_index()];
+            if show_namespace {
+                row.push(usage.obj.namespace.clone().unwrap_or_default().into());
+            }
+            row.push(usage.obj.name().into());
+            if containers {
+                row.push(usage.container.clone().unwrap_or_default().into());
+            }
+            row.extend([
+                cpu_str(usage.used[CPU]).into(),
+                percent_cell(usage.used[CPU], usage.requests[CPU]),
+                percent_cell(usage.used[CPU], usage.limits[CPU]),
+                format_raw_quantity(usage.used[MEMORY], QuantityKind::Bytes).into(),
+                percent_cell(usage.used[MEMORY], usage.requests[MEMORY]),
+                percent_cell(usage.used[MEMORY], usage.limits[MEMORY]),
+            ]);
+            row
+        })
+        .collect();
+    crate::table::print_table(titles, rows, env, writer);
+    env.set_last_objs(
+        usages
+            .into_iter()
+            .map(|usage| usage.obj)
+            .collect::<Vec<KObj>>(),
+    );
+}
+
+fn top_pods(
+    env: &mut Env,
+    namespace: Option<&str>,
+    label_selector: Option<&str>,
+    containers: bool,
+    sort: &str,
+    reverse: bool,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let (path, pods_request) = match namespace {
+        Some(ns) => (
+            format!("/namespaces/{}/pods", ns),
+            api::Pod::list_namespaced_pod(
+                ns,
+                ListOptional {
+                    label_selector,
+                    ..Default::default()
+                },
+            )?
+            .0,
+        ),
+        None => (
+            "/pods".to_string(),
+            api::Pod::list_pod_for_all_namespaces(ListOptional {
+                label_selector,
+                ..Default::default()
+            })?
+            .0,
+        ),
+    };
+    let metrics: Vec<PodMetrics> = get_metrics(env, &path, label_selector)?;
+    let pods: List<api::Pod> = env.run_on_context(|c| c.execute_list(pods_request))?;
+    let mut usages = pod_usages(&pods.items, &metrics, containers);
+    sort_usages(&mut usages, sort);
+    if reverse {
+        usages.reverse();
+    }
+    print_pod_usages(usages, namespace.is_none(), containers, env, writer);
+    Ok(())
+}
+
+command!(
+    Top,
+    "top",
+    "Show the cpu and memory usage of pods or nodes, from the metrics api",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("what")
+                .help("What to show the usage of")
+                .possible_values(["pods", "nodes"])
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("containers")
+                .short('c')
+                .long("containers")
+                .help("Show the usage of each container, rather than each pod (pods only)")
+                .takes_value(false),
+        )
+        .arg(all_namespaces_arg())
+        .arg(label_selector_arg())
+        .arg(
+            Arg::new("sort")
+                .short('s')
+                .long("sort")
+                .help(
+                    "Sort by name, or biggest first by cpu or memory usage (for nodes, by the \
+                     share of allocatable in use) (default name)",
+                )
+                .possible_values(["name", "cpu", "memory"])
+                .ignore_case(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("reverse")
+                .short('R')
+                .long("reverse")
+                .help("Reverse the order of the output")
+                .takes_value(false),
+        )
+        .after_help(
+            "Usage comes from the metrics api (metrics.k8s.io), which is served by metrics-server, \
+so that needs to be installed in the cluster. CPU/Req and CPU/Lim (and Mem/Req and Mem/Lim) show \
+usage as a percentage of what's requested and the limit, or - if there isn't one. The pods are \
+numbered, so they can be selected afterwards.
+
+For nodes, CPU% and Memory% are usage as a percentage of what the node has allocatable for pods, in \
+yellow from 75% and red from 90%.
+
+Examples:
+  # the pods using the most memory in the current namespace
+  top pods --sort memory
+
+  # the usage of each container of the web pods
+  top pods -c -l app=web
+
+  # the hottest nodes, by memory
+  top nodes --sort memory",
+        )
+    },
+    vec!["top"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let namespace = list_namespace(&matches, env).map(str::to_string);
+        let sort = matches
+            .value_of("sort")
+            .unwrap_or("name")
+            .to_ascii_lowercase();
+        if matches.value_of("what") == Some("nodes") {
+            if matches.is_present("containers") || matches.is_present("all-namespaces") {
+                return Err(ClickError::CommandError(
+                    "--containers and --all-namespaces only apply to pods".to_string(),
+                ));
+            }
+            return top_nodes(
+                env,
+                matches.value_of("label"),
+                &sort,
+                matches.is_present("reverse"),
+                writer,
+            );
+        }
+        top_pods(
+            env,
+            namespace.as_deref(),
+            matches.value_of("label"),
+            matches.is_present("containers"),
+            &sort,
+            matches.is_present("reverse"),
+            writer,
+        )
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn usages(containers: bool) -> Vec<Usage> {
+        let pods: Vec<api::Pod> = serde_json::from_value(json!([
+            {
+                "metadata": { "name": "web", "namespace": "default" },
+                "spec": { "containers": [
+                    {
+                        "name": "app",
+                        "resources": {
+                            "requests": { "cpu": "500m", "memory": "1Gi" },
+                            "limits": { "memory": "2Gi" },
+                        },
+                    },
+                    { "name": "proxy", "resources": { "requests": { "cpu": "100m" } } },
+                ]},
+            },
+            {
+                "metadata": { "name": "db", "namespace": "default" },
+                "spec": { "containers": [{ "name": "db" }] },
+            },
+        ]))
+        .unwrap();
+        let metrics: Vec<PodMetrics> = serde_json::from_value(json!([
+            {
+                "metadata": { "name": "web", "namespace": "default" },
+                "containers": [
+                    { "name": "app", "usage": { "cpu": "250000000n", "memory": "512Mi" } },
+                    { "name": "proxy", "usage": { "cpu": "50m", "memory": "10Mi" } },
+                ],
+            },
+            {
+                "metadata": { "name": "db", "namespace": "default" },
+                "containers": [{ "name": "db", "usage": { "cpu": "1", "memory": "100Mi" } }],
+            },
+            {
+                "metadata": { "name": "gone", "namespace": "default" },
+                "containers": [{ "name": "gone", "usage": { "cpu": "1", "memory": "1Mi" } }],
+            },
+        ]))
+        .unwrap();
+        pod_usages(&pods, &metrics, containers)
+    }
+
+    #[test]
+    fn test_pod_usages() {
+        let mut pods = usages(false);
+        assert_eq!(pods.len(), 2);
+        assert_eq!(pods[0].obj.name(), "web");
+        assert_eq!(pods[0].used, [0.3, 522.0 * 1024.0 * 1024.0]);
+        assert_eq!(pods[0].requests, [0.6, 1024.0 * 1024.0 * 1024.0]);
+        assert_eq!(pods[0].limits, [0.0, 2.0 * 1024.0 * 1024.0 * 1024.0]);
+        assert_eq!(
+            percent(pods[0].used[CPU], pods[0].requests[CPU]),
+            Some(50.0)
+        );
+        assert_eq!(percent(pods[0].used[CPU], pods[0].limits[CPU]), None);
+        assert_eq!(cpu_str(pods[0].used[CPU]), "300m");
+
+        sort_usages(&mut pods, "cpu");
+        assert_eq!(pods[0].obj.name(), "db");
+        sort_usages(&mut pods, "name");
+        assert_eq!(pods[0].obj.name(), "db");
+        sort_usages(&mut pods, "memory");
+        assert_eq!(pods[0].obj.name(), "web");
+
+        let containers = usages(true);
+        let names: Vec<&str> = containers
+            .iter()
+            .filter_map(|usage| usage.container.as_deref())
+            .collect();
+        assert_eq!(names, vec!["app", "proxy", "db"]);
+        assert_eq!(containers[1].requests, [0.1, 0.0]);
+    }
+
+    #[test]
+    fn test_node_usages() {
+        let nodes: Vec<api::Node> = serde_json::from_value(json!([
+            {
+                "metadata": { "name": "node-1" },
+                "status": { "allocatable": { "cpu": "4", "memory": "8Gi" } },
+            },
+            {
+                "metadata": { "name": "node-2" },
+                "status": { "allocatable": { "cpu": "2", "memory": "8Gi" } },
+            },
+        ]))
+        .unwrap();
+        let metrics: Vec<NodeMetrics> = serde_json::from_value(json!([
+            { "metadata": { "name": "node-1" }, "usage": { "cpu": "3", "memory": "2Gi" } },
+            { "metadata": { "name": "node-2" }, "usage": { "cpu": "1900m", "memory": "4Gi" } },
+        ]))
+        .unwrap();
+        let mut usages = node_usages(&nodes, &metrics);
+        assert_eq!(usages[0].percent(CPU), 75.0);
+        assert_eq!(usages[1].percent(MEMORY), 50.0);
+
+        sort_node_usages(&mut usages, "cpu");
+        assert_eq!(usages[0].obj.name(), "node-2");
+        sort_node_usages(&mut usages, "memory");
+        assert_eq!(usages[0].obj.name(), "node-2");
+        sort_node_usages(&mut usages, "name");
+        assert_eq!(usages[0].obj.name(), "node-1");
+        assert_eq!(usages[0].obj.namespace, None);
+    }
+}
@@ -0,0 +1,585 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use k8s_openapi::{
+    api::core::v1 as api, apimachinery::pkg::api::resource::Quantity, http, List, RequestError,
+};
+use serde::Deserialize;
+
+use crate::{
+    command::command_def::{
+        all_namespaces_arg, columns_arg, exec_match, field_selector_arg, filter_arg,
+        filter_pattern_arg, hide_arg, label_selector_arg, no_headers_arg, output_arg, quiet_arg,
+        show_arg, sort_arg, sort_by_arg, start_clap, watch_arg, Cmd,
+    },
+    command::volumes::short_access_modes,
+    command::{list_namespace, list_options, run_list_command, Extractor},
+    completer,
+    describe::describe_table,
+    env::Env,
+    error::ClickError,
+    kobj::{KObj, ObjType},
+    output::ClickWriter,
+    table::{format_raw_quantity, percent_str, raw_quantity, CellSpec, QuantityKind},
+};
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+lazy_static! {
+    static ref CLAIM_EXTRACTORS: HashMap<String, Extractor<api::PersistentVolumeClaim>> = {
+        let mut m: HashMap<String, Extractor<api::PersistentVolumeClaim>> = HashMap::new();
+        m.insert("Status".to_owned(), claim_status);
+        m.insert("Volume".to_owned(), claim_volume);
+        m.insert("Capacity".to_owned(), claim_capacity);
+        m.insert("Access Modes".to_owned(), claim_access_modes);
+        m.insert("Storage Class".to_owned(), claim_storage_class);
+        m.insert("Volume Mode".to_owned(), claim_volume_mode);
+        m
+    };
+}
+
+const COL_MAP: &[(&str, &str)] = &[
+    ("name", "Name"),
+    ("status", "Status"),
+    ("volume", "Volume"),
+    ("capacity", "Capacity"),
+    ("accessmodes", "Access Modes"),
+    ("storageclass", "Storage Class"),
+    ("age", "Age"),
+];
+
+const COL_FLAGS: &[&str] = &{ extract_first!(COL_MAP) };
+
+const EXTRA_COL_MAP: &[(&str, &str)] = &[
+    ("labels", "Labels"),
+    ("namespace", "Namespace"),
+    ("volumemode", "Volume Mode"),
+];
+
+const EXTRA_COL_FLAGS: &[&str] = &{ extract_first!(EXTRA_COL_MAP) };
+
+fn pvc_to_kobj(claim: &api::PersistentVolumeClaim) -> KObj {
+    let meta = &claim.metadata;
+    KObj {
+        name: meta.name.clone().unwrap_or_else(|| "<Unknown>".into()),
+        namespace: meta.namespace.clone(),
+        typ: ObjType::PersistentVolumeClaim,
+    }
+}
+
+fn claim_status(claim: &api::PersistentVolumeClaim) -> Option<CellSpec<'_>> {
+    claim
+        .status
+        .as_ref()
+        .and_then(|stat| stat.phase.as_ref().map(|p| p.as_str().into()))
+}
+
+fn claim_volume(claim: &api::PersistentVolumeClaim) -> Option<CellSpec<'_>> {
+    claim
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.volume_name.as_ref().map(|v| v.as_str().into()))
+}
+
+fn claim_capacity(claim: &api::PersistentVolumeClaim) -> Option<CellSpec<'_>> {
+    claim.status.as_ref().and_then(|stat| {
+        stat.capacity.as_ref().and_then(|capacity| {
+            capacity
+                .get("storage")
+                .map(|q| CellSpec::new_quantity(Quantity(q.0.clone()), QuantityKind::Bytes))
+        })
+    })
+}
+
+fn claim_access_modes(claim: &api::PersistentVolumeClaim) -> Option<CellSpec<'_>> {
+    claim.status.as_ref().and_then(|stat| {
+        stat.access_modes
+            .as_ref()
+            .map(|access_modes| short_access_modes(access_modes).into())
+    })
+}
+
+fn claim_storage_class(claim: &api::PersistentVolumeClaim) -> Option<CellSpec<'_>> {
+    claim.spec.as_ref().and_then(|spec| {
+        spec.storage_class_name
+            .as_ref()
+            .map(|sc| sc.as_str().into())
+    })
+}
+
+fn claim_volume_mode(claim: &api::PersistentVolumeClaim) -> Option<CellSpec<'_>> {
+    claim
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.volume_mode.as_ref().map(|mode| mode.as_str().into()))
+}
+
+list_command!(
+    PersistentVolumeClaims,
+    "persistentvolumeclaims",
+    "Get persistent volume claims (in current namespace if set)",
+    super::COL_FLAGS,
+    super::EXTRA_COL_FLAGS,
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("regex")
+                .short('r')
+                .long("regex")
+                .help("Filter pvcs by the specified regex")
+                .takes_value(true),
+        )
+        .arg(all_namespaces_arg())
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
+        .arg(show_arg(EXTRA_COL_FLAGS, true))
+        .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
+        .arg(
+            Arg::new("reverse")
+                .short('R')
+                .long("reverse")
+                .help("Reverse the order of the returned list")
+                .takes_value(false),
+        )
+    },
+    vec!["persistentvolumeclaims", "pvcs"],
+    noop_complete!(),
+    [(
+        "regex".to_string(),
+        completer::pvc_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter(),
+    |matches, env, writer| {
+        let (request, _response_body) = match list_namespace(&matches, env) {
+            Some(ns) => api::PersistentVolumeClaim::list_namespaced_persistent_volume_claim(
+                ns,
+                list_options(&matches),
+            )?,
+            None => api::PersistentVolumeClaim::list_persistent_volume_claim_for_all_namespaces(
+                list_options(&matches),
+            )?,
+        };
+        let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
+        run_list_command(
+            matches,
+            env,
+            writer,
+            cols,
+            request,
+            COL_MAP,
+            Some(EXTRA_COL_MAP),
+            Some(&CLAIM_EXTRACTORS),
+            pvc_to_kobj,
+        )
+    }
+);
+
+/// The parts of the kubelet's stats summary (/stats/summary) with the usage of pods' volumes
+#[derive(Debug, Deserialize)]
+struct StatsSummary {
+    #[serde(default)]
+    pods: Vec<PodStats>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PodStats {
+    #[serde(default)]
+    volume: Vec<VolumeStats>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VolumeStats {
+    pvc_ref: Option<PvcRef>,
+    used_bytes: Option<f64>,
+    capacity_bytes: Option<f64>,
+    available_bytes: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct PvcRef {
+    name: String,
+    namespace: String,
+}
+
+/// The usage of a claim in a node's stats summary, if a pod on the node has it mounted
+fn claim_usage(summary: StatsSummary, namespace: &str, claim: &str) -> Option<VolumeStats> {
+    summary
+        .pods
+        .into_iter()
+        .flat_map(|pod| pod.volume.into_iter())
+        .find(|volume| {
+            volume
+                .pvc_ref
+                .as_ref()
+                .is_some_and(|pvc| pvc.namespace == namespace && pvc.name == claim)
+        })
+}
+
+fn usage_str(stats: &VolumeStats) -> String {
+    let bytes = |amount: Option<f64>| {
+        amount
+            .map(|amount| format_raw_quantity(amount, QuantityKind::Bytes))
+            .unwrap_or_else(|| "?".to_string())
+    };
+    let mut usage = format!(
+        "{} used, {} available, of {}",
+        bytes(stats.used_bytes),
+        bytes(stats.available_bytes),
+        bytes(stats.capacity_bytes)
+    );
+    if let (Some(used), Some(capacity)) = (stats.used_bytes, stats.capacity_bytes) {
+        if let Some(percent) = percent_str(used, capacity) {
+            usage.push_str(&format!(" ({})", percent));
+        }
+    }
+    usage
+}
+
+/// The pods that mount a claim, out of the pods in its namespace
+fn mounting_pods<'a>(claim: &str, pods: &'a [api::Pod]) -> Vec<&'a api::Pod> {
+    pods.iter()
+        .filter(|pod| {
+            pod.spec
+                .iter()
+                .flat_map(|spec| spec.volumes.iter().flatten())
+                .any(|volume| {
+                    volume
+                        .persistent_volume_claim
+                        .as_ref()
+                        .is_some_and(|source| source.claim_name == claim)
+                })
+        })
+        .collect()
+}
+
+fn quantity_str(quantities: Option<&BTreeMap<String, Quantity>>) -> String {
+    quantities
+        .and_then(|quantities| quantities.get("storage"))
+        .map(|q| format_raw_quantity(raw_quantity(q), QuantityKind::Bytes))
+        .unwrap_or_else(|| "<none>".to_string())
+}
+
+/// Rows describing a claim and, if it could be read, the volume it's bound to
+fn claim_rows(
+    claim: &api::PersistentVolumeClaim,
+    volume: Option<&api::PersistentVolume>,
+) -> Vec<(&'static str, String)> {
+    let spec = claim.spec.as_ref();
+    let status = claim.status.as_ref();
+    let none = || "<none>".to_string();
+    let mut rows = vec![
+        (
+            "Status:",
+            status
+                .and_then(|stat| stat.phase.clone())
+                .unwrap_or_else(none),
+        ),
+        (
+            "Storage Class:",
+            spec.and_then(|spec| spec.storage_class_name.clone())
+                .unwrap_or_else(none),
+        ),
+        (
+            "Access Modes:",
+            status
+                .and_then(|stat| stat.access_modes.as_ref())
+                .or_else(|| spec.and_then(|spec| spec.access_modes.as_ref()))
+                .map(|modes| short_access_modes(modes))
+                .unwrap_or_else(none),
+        ),
+        (
+            "Requested:",
+            quantity_str(spec.and_then(|spec| {
+                spec.resources
+                    .as_ref()
+                    .and_then(|resources| resources.requests.as_ref())
+            })),
+        ),
+        (
+            "Capacity:",
+            quantity_str(status.and_then(|stat| stat.capacity.as_ref())),
+        ),
+        (
+            "Volume:",
+            spec.and_then(|spec| spec.volume_name.clone())
+                .unwrap_or_else(|| "<not bound>".to_string()),
+        ),
+    ];
+    if let Some(volume) = volume {
+        let spec = volume.spec.as_ref();
+        rows.push((
+            "Volume Status:",
+            volume
+                .status
+                .as_ref()
+                .and_then(|stat| stat.phase.clone())
+                .unwrap_or_else(none),
+        ));
+        rows.push((
+            "Volume Capacity:",
+            quantity_str(spec.and_then(|spec| spec.capacity.as_ref())),
+        ));
+        rows.push((
+            "Reclaim Policy:",
+            spec.and_then(|spec| spec.persistent_volume_reclaim_policy.clone())
+                .unwrap_or_else(none),
+        ));
+    }
+    rows
+}
+
+/// Ask the kubelet on node how much of the claim is used
+fn read_claim_usage(
+    env: &Env,
+    node: &str,
+    namespace: &str,
+    claim: &str,
+) -> Result<Option<VolumeStats>, ClickError> {
+    let path = format!("/api/v1/nodes/{}/proxy/stats/summary", node);
+    let request = http::Request::get(path)
+        .body(vec![])
+        .map_err(RequestError::Http)?;
+    let response = env.run_on_context(|c| c.execute(request))?;
+    if !response.status().is_success() {
+        return Err(ClickError::CommandError(format!(
+            "Could not get stats from {}: {}",
+            node,
+            response.status()
+        )));
+    }
+    let summary: StatsSummary = serde_json::from_slice(response.body())?;
+    Ok(claim_usage(summary, namespace, claim))
+}
+
+fn print_storage(env: &Env, obj: &KObj, writer: &mut ClickWriter) -> Result<(), ClickError> {
+    let claim: api::PersistentVolumeClaim = obj.read_kind(env, "storage")?;
+    let namespace = obj.namespace.as_deref().unwrap_or_default();
+    let volume_name = claim
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.volume_name.as_deref());
+    // PersistentVolumes are cluster scoped, so users that can only see their namespace may not be
+    // able to read it. Show the claim anyway
+    let volume = match volume_name {
+        Some(volume_name) => {
+            let (request, _) =
+                api::PersistentVolume::read_persistent_volume(volume_name, Default::default())?;
+            env.run_on_context(|c| c.read_object::<api::PersistentVolume>(request))
+                .ok()
+        }
+        None => None,
+    };
+
+    let (request, _) = api::Pod::list_namespaced_pod(namespace, Default::default())?;
+    let pods: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+    let mounting = mounting_pods(obj.name(), &pods.items);
+
+    let mut table = describe_table();
+    table.add_row(vec![
+        "Claim:".to_string(),
+        format!("{}/{}", namespace, obj.name()),
+    ]);
+    for (title, value) in claim_rows(&claim, volume.as_ref()) {
+        table.add_row(vec![title.to_string(), value]);
+    }
+    let nodes: Vec<&str> = mounting
+        .iter()
+        .filter_map(|pod| pod.spec.as_ref().and_then(|spec| spec.node_name.as_deref()))
+        .collect();
+    let mounted_by: Vec<String> = mounting
+        .iter()
+        .map(|pod| {
+            let name = pod.metadata.name.as_deref().unwrap_or("<No Name>");
+            match pod.spec.as_ref().and_then(|spec| spec.node_name.as_deref()) {
+                Some(node) => format!("{} (on {})", name, node),
+                None => format!("{} (not scheduled)", name),
+            }
+        })
+        .collect();
+    table.add_row(vec![
+        "Mounted By:".to_string(),
+        if mounted_by.is_empty() {
+            "<none>".to_string()
+        } else {
+            mounted_by.join("\n")
+        },
+    ]);
+    // the kubelet only reports usage for volumes that are mounted, so try each node the claim is
+    // mounted on until one has it
+    let usage = if nodes.is_empty() {
+        "<not mounted>".to_string()
+    } else {
+        nodes
+            .iter()
+            .find_map(|node| {
+                read_claim_usage(env, node, namespace, obj.name())
+                    .ok()
+                    .flatten()
+            })
+            .map(|stats| usage_str(&stats))
+            .unwrap_or_else(|| "<not available from kubelet stats>".to_string())
+    };
+    table.add_row(vec!["Usage:".to_string(), usage]);
+    clickwriteln!(writer, "{table}");
+    Ok(())
+}
+
+command!(
+    Storage,
+    "storage",
+    "Show the storage behind the active PersistentVolumeClaim(s): the volume it's bound to, its \
+     storage class and access modes, the pods mounting it, and how full it is",
+    |clap: ClapCommand<'static>| {
+        clap.after_help(
+            "How much of the volume is used comes from the stats of the kubelets on the nodes \
+running the pods that mount it, so it's only shown for claims that are mounted, and needs \
+permission to proxy to nodes. Not all volume types report their usage.
+
+Examples:
+  # is the selected claim full?
+  storage",
+        )
+    },
+    vec!["storage"],
+    noop_complete!(),
+    no_named_complete!(),
+    |_matches, env, writer| {
+        env.apply_to_selection(
+            writer,
+            Some(&env.click_config.range_separator),
+            |obj, writer| print_storage(env, obj, writer),
+        )
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_claim_usage() {
+        let summary = || -> StatsSummary {
+            serde_json::from_value(json!({
+                "node": { "nodeName": "node-1" },
+                "pods": [
+                    { "podRef": { "name": "web", "namespace": "prod" } },
+                    {
+                        "podRef": { "name": "db-0", "namespace": "prod" },
+                        "volume": [
+                            { "name": "tmp", "usedBytes": 4096 },
+                            {
+                                "name": "data",
+                                "pvcRef": { "name": "data-db-0", "namespace": "prod" },
+                                "usedBytes": 966367641.6,
+                                "capacityBytes": 1073741824,
+                                "availableBytes": 107374182.4,
+                            },
+                        ],
+                    },
+                ],
+            }))
+            .unwrap()
+        };
+        let stats = claim_usage(summary(), "prod", "data-db-0").unwrap();
+        assert_eq!(
+            usage_str(&stats),
+            "921.60 MiB used, 102.40 MiB available, of 1.00 GiB (90%)"
+        );
+        assert_eq!(claim_usage(summary(), "dev", "data-db-0"), None);
+    }
+
+    #[test]
+    fn test_claim_rows() {
+        let claim: api::PersistentVolumeClaim = serde_json::from_value(json!({
+            "metadata": { "name": "data-db-0", "namespace": "prod" },
+            "spec": {
+                "storageClassName": "gp2",
+                "accessModes": ["ReadWriteOnce"],
+                "resources": { "requests": { "storage": "1Gi" } },
+                "volumeName": "pvc-1234",
+            },
+            "status": {
+                "phase": "Bound",
+                "accessModes": ["ReadWriteOnce"],
+                "capacity": { "storage": "2Gi" },
+            },
+        }))
+        .unwrap();
+        let volume: api::PersistentVolume = serde_json::from_value(json!({
+            "metadata": { "name": "pvc-1234" },
+            "spec": {
+                "capacity": { "storage": "2Gi" },
+                "persistentVolumeReclaimPolicy": "Delete",
+            },
+            "status": { "phase": "Bound" },
+        }))
+        .unwrap();
+        assert_eq!(
+            claim_rows(&claim, Some(&volume)),
+            vec![
+                ("Status:", "Bound".to_string()),
+                ("Storage Class:", "gp2".to_string()),
+                ("Access Modes:", "RWO".to_string()),
+                ("Requested:", "1.00 GiB".to_string()),
+                ("Capacity:", "2.00 GiB".to_string()),
+                ("Volume:", "pvc-1234".to_string()),
+                ("Volume Status:", "Bound".to_string()),
+                ("Volume Capacity:", "2.00 GiB".to_string()),
+                ("Reclaim Policy:", "Delete".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mounting_pods() {
+        let pods: Vec<api::Pod> = serde_json::from_value(json!([
+            {
+                "metadata": { "name": "db-0" },
+                "spec": {
+                    "containers": [],
+                    "volumes": [{
+                        "name": "data",
+                        "persistentVolumeClaim": { "claimName": "data-db-0" },
+                    }],
+                },
+            },
+            {
+                "metadata": { "name": "web" },
+                "spec": {
+                    "containers": [],
+                    "volumes": [{ "name": "tmp", "emptyDir": {} }],
+                },
+            },
+        ]))
+        .unwrap();
+        let names: Vec<&str> = mounting_pods("data-db-0", &pods)
+            .iter()
+            .filter_map(|pod| pod.metadata.name.as_deref())
+            .collect();
+        assert_eq!(names, vec!["db-0"]);
+    }
+}
@@ -16,13 +16,17 @@ use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::{api::core::v1 as api, apimachinery::pkg::api::resource::Quantity};
 
 use crate::{
-    command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{run_list_command, Extractor},
+    command::command_def::{
+        columns_arg, exec_match, field_selector_arg, filter_arg, filter_pattern_arg, hide_arg,
+        label_selector_arg, no_headers_arg, output_arg, quiet_arg, show_arg, sort_arg, sort_by_arg,
+        start_clap, watch_arg, Cmd,
+    },
+    command::{list_options, run_list_command, Extractor},
     completer,
     env::Env,
     kobj::{KObj, ObjType},
     output::ClickWriter,
-    table::CellSpec,
+    table::{CellSpec, QuantityKind},
 };
 
 use std::cell::RefCell;
@@ -76,28 +80,32 @@ fn volume_capacity(volume: &api::PersistentVolume) -> Option<CellSpec<'_>> {
         spec.capacity.as_ref().and_then(|capacity| {
             capacity.get("storage").as_ref().map(|q| {
                 let quant = Quantity(q.0.clone());
-                quant.into()
+                CellSpec::new_quantity(quant, QuantityKind::Bytes)
             })
         })
     })
 }
 
+/// Access modes the way kubectl abbreviates them, like RWO for ReadWriteOnce
+pub fn short_access_modes(access_modes: &[String]) -> String {
+    access_modes
+        .iter()
+        .map(|mode| match mode.as_str() {
+            "ReadWriteOnce" => "RWO",
+            "ReadOnlyMany" => "ROX",
+            "ReadWriteMany" => "RWX",
+            "ReadWriteOncePod" => "RWOP",
+            _ => "Unknown",
+        })
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
 fn volume_access_modes(volume: &api::PersistentVolume) -> Option<CellSpec<'_>> {
     volume.spec.as_ref().and_then(|spec| {
-        spec.access_modes.as_ref().map(|access_modes| {
-            access_modes
-                .iter()
-                .map(|mode| match mode.as_str() {
-                    "ReadWriteOnce" => "RWO",
-                    "ReadOnlyMany" => "ROX",
-                    "ReadWriteMany" => "RWX",
-                    "ReadWriteOncePod" => "RWOP",
-                    _ => "Unknown",
-                })
-                .collect::<Vec<&str>>()
-                .join(", ")
-                .into()
-        })
+        spec.access_modes
+            .as_ref()
+            .map(|access_modes| short_access_modes(access_modes).into())
     })
 }
 
@@ -170,8 +178,19 @@ list_command!(
                 .help("Filter pvs by the specified regex")
                 .takes_value(true),
         )
+        .arg(label_selector_arg())
+        .arg(field_selector_arg())
+        .arg(filter_arg())
+        .arg(filter_pattern_arg())
         .arg(show_arg(EXTRA_COL_FLAGS, true))
         .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(sort_by_arg())
+        .arg(output_arg())
+        .arg(no_headers_arg())
+        .arg(quiet_arg())
+        .arg(watch_arg())
+        .arg(columns_arg())
+        .arg(hide_arg())
         .arg(
             Arg::new("reverse")
                 .short('R')
@@ -182,10 +201,14 @@ list_command!(
     },
     vec!["persistentvolumes", "pvs"],
     noop_complete!(),
-    no_named_complete!(),
+    [(
+        "regex".to_string(),
+        completer::volume_name_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter(),
     |matches, env, writer| {
         let (request, _response_body) =
-            api::PersistentVolume::list_persistent_volume(Default::default())?;
+            api::PersistentVolume::list_persistent_volume(list_options(&matches))?;
         let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
         run_list_command(
             matches,
@@ -0,0 +1,286 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use humantime::parse_duration;
+use k8s_openapi::{apimachinery::pkg::apis::meta::v1::WatchEvent, http::StatusCode, WatchOptional};
+use rustyline::completion::Pair as RustlinePair;
+use serde_json::Value;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    command::stream_watch,
+    completer,
+    env::{Env, ObjectSelection},
+    error::ClickError,
+    kobj::KObj,
+    output::ClickWriter,
+    values::val_str_opt,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::Instant;
+
+/// What the wait command waits for
+#[derive(Debug, PartialEq)]
+enum WaitFor {
+    /// A condition in the object's status with type typ to have the given status
+    Condition {
+        typ: String,
+        status: String,
+    },
+    Delete,
+}
+
+impl WaitFor {
+    fn parse(s: &str) -> Result<WaitFor, String> {
+        if s.eq_ignore_ascii_case("delete") {
+            return Ok(WaitFor::Delete);
+        }
+        let condition = s
+            .strip_prefix("condition=")
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| "must be condition=TYPE, condition=TYPE=STATUS or delete".to_string())?;
+        let (typ, status) = condition.split_once('=').unwrap_or((condition, "True"));
+        Ok(WaitFor::Condition {
+            typ: typ.to_string(),
+            status: status.to_string(),
+        })
+    }
+
+    /// Does the object in value satisfy this. Types and statuses are compared ignoring case, like
+    /// kubectl does
+    fn is_met(&self, value: &Value) -> bool {
+        match self {
+            WaitFor::Condition { typ, status } => value
+                .pointer("/status/conditions")
+                .and_then(Value::as_array)
+                .is_some_and(|conditions| {
+                    conditions.iter().any(|condition| {
+                        let matches = |field: &str, want: &str| {
+                            condition[field]
+                                .as_str()
+                                .is_some_and(|val| val.eq_ignore_ascii_case(want))
+                        };
+                        matches("type", typ) && matches("status", status)
+                    })
+                }),
+            WaitFor::Delete => false,
+        }
+    }
+
+    fn done_msg(&self) -> &'static str {
+        match self {
+            WaitFor::Condition { .. } => "condition met",
+            WaitFor::Delete => "deleted",
+        }
+    }
+}
+
+/// Wait until obj satisfies wait_for, or the deadline passes. The object is read first, so there's
+/// no waiting if it's already done, then watched from the version that was read
+fn wait_for(
+    env: &Env,
+    obj: &KObj,
+    wait_for: &WaitFor,
+    deadline: Instant,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let what = format!("{}/{}", obj.type_str(), obj.name());
+    loop {
+        let response = env.run_on_context(|c| c.execute(obj.read_request()?))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            if *wait_for == WaitFor::Delete {
+                clickwriteln!(writer, "{} {}", what, wait_for.done_msg());
+                return Ok(());
+            }
+            return Err(ClickError::CommandError(format!("{} not found", what)));
+        }
+        if !response.status().is_success() {
+            return Err(ClickError::CommandError(format!(
+                "Could not read {}: {}",
+                what,
+                response.status()
+            )));
+        }
+        let value: Value = serde_json::from_slice(response.body())?;
+        if wait_for.is_met(&value) {
+            clickwriteln!(writer, "{} {}", what, wait_for.done_msg());
+            return Ok(());
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(ClickError::CommandError(format!(
+                "Timed out waiting for {}",
+                what
+            )));
+        }
+
+        let resource_version = val_str_opt("/metadata/resourceVersion", &value);
+        let options = WatchOptional {
+            resource_version: resource_version.as_deref(),
+            timeout_seconds: Some(remaining.as_secs().max(1) as i64),
+            ..Default::default()
+        };
+        let mut done = false;
+        let stopped = stream_watch(
+            env,
+            obj.watch_request(options)?,
+            |event: Option<WatchEvent<Value>>| {
+                match event {
+                    Some(WatchEvent::Added(value)) | Some(WatchEvent::Modified(value)) => {
+                        done = wait_for.is_met(&value);
+                    }
+                    Some(WatchEvent::Deleted(_)) => {
+                        if *wait_for != WaitFor::Delete {
+                            return Err(ClickError::CommandError(format!("{} was deleted", what)));
+                        }
+                        done = true;
+                    }
+                    _ => {}
+                }
+                Ok(!done && Instant::now() < deadline)
+            },
+        )?;
+        if done {
+            clickwriteln!(writer, "{} {}", what, wait_for.done_msg());
+            return Ok(());
+        }
+        if stopped {
+            return Err(ClickError::CommandError(format!(
+                "Stopped waiting for {}",
+                what
+            )));
+        }
+        // the deadline passed, or the api server ended the watch. going around again reads the
+        // object once more, and either gives up or watches again
+    }
+}
+
+command!(
+    Wait,
+    "wait",
+    "Wait for the active object(s) to have a condition, or to be deleted",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("for")
+                .long("for")
+                .value_name("CONDITION")
+                .help(
+                    "What to wait for: condition=TYPE (to be True), condition=TYPE=STATUS, \
+                     or delete",
+                )
+                .validator(|s| WaitFor::parse(s).map(|_| ()))
+                .required(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .value_name("DURATION")
+                .help("Give up, with an error, after this long (default 30s)")
+                .validator(|s| parse_duration(s).map(|_| ()).map_err(|e| e.to_string()))
+                .takes_value(true),
+        )
+        .after_help(
+            "With a range selected, each object is waited for in turn, all within the one timeout. \
+Waiting fails if the timeout passes or ctrl-c is pressed, so a script run with 'click --script' \
+stops there.
+
+Examples:
+  # wait for the selected pod to be ready
+  wait --for=condition=Ready
+
+  # wait up to 5 minutes for the selected deployment to finish rolling out
+  wait --for=condition=Available --timeout 5m
+
+  # wait for the selected pods to go away
+  wait --for=delete",
+        )
+    },
+    vec!["wait"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let wait = WaitFor::parse(matches.value_of("for").unwrap()).unwrap(); // required and validated
+        let timeout = parse_duration(matches.value_of("timeout").unwrap_or("30s")).unwrap(); // validated
+        let deadline = Instant::now() + timeout;
+        let objs = match env.current_selection() {
+            ObjectSelection::Single(obj) => vec![obj.clone()],
+            ObjectSelection::Range(objs) => objs.clone(),
+            ObjectSelection::None => {
+                return Err(ClickError::CommandError(
+                    "No objects currently active".to_string(),
+                ))
+            }
+        };
+        for obj in objs.iter() {
+            wait_for(env, obj, &wait, deadline, writer)?;
+        }
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_wait_for() {
+        assert_eq!(WaitFor::parse("delete"), Ok(WaitFor::Delete));
+        assert_eq!(
+            WaitFor::parse("condition=Ready"),
+            Ok(WaitFor::Condition {
+                typ: "Ready".to_string(),
+                status: "True".to_string()
+            })
+        );
+        assert_eq!(
+            WaitFor::parse("condition=Progressing=False"),
+            Ok(WaitFor::Condition {
+                typ: "Progressing".to_string(),
+                status: "False".to_string()
+            })
+        );
+        assert!(WaitFor::parse("condition=").is_err());
+        assert!(WaitFor::parse("Ready").is_err());
+    }
+
+    #[test]
+    fn test_is_met() {
+        let pod = json!({
+            "status": {
+                "conditions": [
+                    {"type": "Initialized", "status": "True"},
+                    {"type": "Ready", "status": "False"},
+                ]
+            }
+        });
+        assert!(WaitFor::parse("condition=initialized")
+            .unwrap()
+            .is_met(&pod));
+        assert!(!WaitFor::parse("condition=Ready").unwrap().is_met(&pod));
+        assert!(WaitFor::parse("condition=Ready=false")
+            .unwrap()
+            .is_met(&pod));
+        assert!(!WaitFor::parse("condition=Available").unwrap().is_met(&pod));
+        assert!(!WaitFor::Delete.is_met(&pod));
+        assert!(!WaitFor::parse("condition=Ready")
+            .unwrap()
+            .is_met(&json!({})));
+    }
+}
@@ -12,25 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::command::command_def::Cmd;
+use crate::command::command_def::{takes_selection, Cmd};
+use crate::command::plugin::{find_plugins, Plugin};
 use crate::completer::ClickHelper;
+use crate::config::NotifyMethod;
 use crate::error::ClickError;
 use crate::kobj::KObj;
 use crate::output::ClickWriter;
-use crate::parser::{try_parse_csl, try_parse_range, Parser};
-use crate::values::val_str;
+use crate::parser::{split_chain, try_parse_csl, try_parse_range, ChainOp, Parser};
 
 use rustyline::config as rustyconfig;
 use rustyline::error::ReadlineError;
+use rustyline::history::History;
 use rustyline::Editor;
 
 use crate::env::Env;
 
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 /// Things the can come after a | or > char in input
 #[derive(Debug, PartialEq)]
@@ -69,10 +72,17 @@ fn build_parser_expr(line: &str, range: Range<usize>) -> Result<(&str, RightExpr
         let right = match sep {
             b'|' => RightExpr::Pipe(&rest[sepcnt..]),
             b'>' => {
+                let filename = rest[sepcnt..].trim();
+                if filename.is_empty() {
+                    return Err(ClickError::ParseErr(format!(
+                        "Parse error at {}: missing file name after >",
+                        range.start
+                    )));
+                }
                 if sepcnt == 1 {
-                    RightExpr::Redir(rest[sepcnt..].trim())
+                    RightExpr::Redir(filename)
                 } else {
-                    RightExpr::Append(rest[sepcnt..].trim())
+                    RightExpr::Append(filename)
                 }
             }
             _ => {
@@ -86,24 +96,133 @@ fn build_parser_expr(line: &str, range: Range<usize>) -> Result<(&str, RightExpr
     }
 }
 
+// get the path to redirect output to. Quotes around the name are removed, and a leading ~ is
+// expanded to the home directory
+fn redir_path(filename: &str) -> PathBuf {
+    let filename = match filename.as_bytes().first() {
+        Some(b'"') | Some(b'\'') if filename.len() > 1 && filename.ends_with(&filename[..1]) => {
+            &filename[1..filename.len() - 1]
+        }
+        _ => filename,
+    };
+    match (filename.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(filename),
+    }
+}
+
+/// Did a line that took took run long enough to notify about, with notify_after_secs set to
+/// after_secs
+fn should_notify(after_secs: Option<u64>, took: Duration) -> bool {
+    after_secs.is_some_and(|secs| took >= Duration::from_secs(secs))
+}
+
+fn notify_message(line: &str, failed: bool) -> String {
+    format!(
+        "{} '{}'",
+        if failed { "Failed:" } else { "Finished:" },
+        line
+    )
+}
+
+/// Quote s as an AppleScript string
+fn applescript_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Let the user know that line finished, after it took long enough that they might have looked
+/// away
+fn notify_finished(method: NotifyMethod, line: &str, failed: bool) {
+    let message = notify_message(line, failed);
+    if method == NotifyMethod::Desktop {
+        let expr = if cfg!(target_os = "macos") {
+            let script = format!(
+                "display notification {} with title \"click\"",
+                applescript_string(&message)
+            );
+            cmd!("osascript", "-e", script)
+        } else {
+            cmd!("notify-send", "click", &message)
+        };
+        if expr.stdout_null().stderr_null().run().is_ok() {
+            return;
+        }
+    }
+    // ring the bell
+    print!("\x07");
+    std::io::stdout().flush().unwrap_or(());
+}
+
 pub fn alias_expand_line(env: &Env, line: &str) -> String {
-    let expa = env.try_expand_alias(line, None);
-    let mut alias_stack = vec![expa];
-    #[allow(clippy::while_let_loop)] // needed due to borrow restrictions
+    let mut line = line.to_string();
+    let mut expanded_aliases: Vec<String> = vec![];
     loop {
-        let expa = match alias_stack.last().unwrap().expansion {
-            Some(prev) => {
-                // previous thing expanded an alias, so try and expand that too
-                env.try_expand_alias(prev.expanded.as_str(), Some(prev.alias.as_str()))
+        let expa = env.try_expand_alias(&line, None);
+        match expa.expansion {
+            // an alias that's already been expanded isn't expanded again, to avoid loops
+            Some(alias) if !expanded_aliases.contains(&alias.alias) => {
+                expanded_aliases.push(alias.alias.clone());
+                line = expand_alias_args(&alias.expanded, expa.rest);
             }
-            None => break,
-        };
-        alias_stack.push(expa);
+            _ => break,
+        }
+    }
+    line
+}
+
+/// Substitute the words in rest for $1 to $9 and $@ in an alias expansion. Words that aren't used,
+/// along with any pipe or redirect, are added to the end. If there are no $ arguments the
+/// expansion is just followed by rest
+fn expand_alias_args(expanded: &str, rest: &str) -> String {
+    let uses_args = expanded
+        .as_bytes()
+        .windows(2)
+        .any(|w| w[0] == b'$' && (w[1] == b'@' || (b'1'..=b'9').contains(&w[1])));
+    if !uses_args {
+        return format!("{}{}", expanded, rest);
+    }
+    // the args are the words before any pipe or redirect
+    let args: Vec<&str> = rest
+        .split_whitespace()
+        .take_while(|word| !word.starts_with('|') && !word.starts_with('>'))
+        .collect();
+    let mut used = 0;
+    let mut line = String::new();
+    let mut chars = expanded.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('$', Some('@')) => {
+                chars.next();
+                line.push_str(&args.join(" "));
+                used = args.len();
+            }
+            ('$', Some(d)) if ('1'..='9').contains(d) => {
+                let index = d.to_digit(10).unwrap() as usize; // safe, checked above
+                chars.next();
+                if let Some(arg) = args.get(index - 1) {
+                    line.push_str(arg);
+                }
+                used = used.max(index.min(args.len()));
+            }
+            _ => line.push(c),
+        }
     }
-    // At this point, all the "real" stuff is in the chain of "rest" memebers of the
-    // alias_stack, let's gather them up
-    let rests: Vec<&str> = alias_stack.iter().rev().map(|ea| ea.rest).collect();
-    rests.concat()
+    // add whatever comes after the last used arg
+    let remaining = match used.checked_sub(1).map(|i| args[i]) {
+        Some(last) => &rest[last.as_ptr() as usize - rest.as_ptr() as usize + last.len()..],
+        None => rest,
+    };
+    line.push_str(remaining);
+    line
 }
 
 fn parse_line(line: &str) -> Result<(&str, RightExpr), ClickError> {
@@ -118,40 +237,80 @@ fn parse_line(line: &str) -> Result<(&str, RightExpr), ClickError> {
 }
 
 // see comment on ClickCompleter::new for why a raw pointer is needed
-fn get_editor(config: rustyconfig::Config, hist_path: &Path) -> Editor<ClickHelper> {
+fn get_editor(
+    config: rustyconfig::Config,
+    hist_path: &Path,
+    plugins: Vec<Plugin>,
+) -> Editor<ClickHelper> {
     let mut rl = Editor::<ClickHelper>::with_config(config).expect("Could not make editor");
     rl.set_helper(Some(ClickHelper::new(
-        CommandProcessor::get_command_vec(),
+        CommandProcessor::get_command_vec(plugins),
         vec![
             "completion",
             "edit_mode",
+            "foreach",
+            "history",
             "shell",
             "pipes",
             "redirection",
+            "plugins",
             "ranges",
+            "source",
         ],
     )));
     rl.load_history(hist_path).unwrap_or_default();
+    dedup_history(rl.history_mut());
     rl
 }
 
+/// Remove all but the most recent copy of each line in the history, so older sessions don't fill
+/// it up with repeats of the same commands
+fn dedup_history(history: &mut History) {
+    let entries: Vec<String> = history.iter().cloned().collect();
+    let deduped = dedup_entries(entries);
+    if deduped.len() != history.len() {
+        history.clear();
+        for entry in deduped.into_iter() {
+            history.add(entry);
+        }
+    }
+}
+
+fn dedup_entries(entries: Vec<String>) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    let mut deduped: Vec<String> = entries
+        .into_iter()
+        .rev()
+        .filter(|entry| seen.insert(entry.clone()))
+        .collect();
+    deduped.reverse();
+    deduped
+}
+
 pub struct CommandProcessor {
     env: Rc<Env>,
     rl: Editor<ClickHelper>,
     hist_path: PathBuf,
     commands: Vec<Box<dyn Cmd>>,
+    // found when click starts, and kept to give the completer of a new editor
+    plugins: Vec<Plugin>,
+    // did the last line fail to run
+    failed: bool,
 }
 
 impl CommandProcessor {
     pub fn new(env: Env, hist_path: PathBuf) -> CommandProcessor {
-        let commands = CommandProcessor::get_command_vec();
+        let plugins = find_plugins(&CommandProcessor::get_command_vec(vec![]));
+        let commands = CommandProcessor::get_command_vec(plugins.clone());
         let env = Rc::new(env);
-        let rl = get_editor(env.get_rustyline_conf(), &hist_path);
+        let rl = get_editor(env.get_rustyline_conf(), &hist_path, plugins.clone());
         CommandProcessor {
             env,
             rl,
             hist_path,
             commands,
+            plugins,
+            failed: false,
         }
     }
 
@@ -162,55 +321,94 @@ impl CommandProcessor {
         commands: Vec<Box<dyn Cmd>>,
     ) -> CommandProcessor {
         let env = Rc::new(env);
-        let rl = get_editor(env.get_rustyline_conf(), &hist_path);
+        let rl = get_editor(env.get_rustyline_conf(), &hist_path, vec![]);
         CommandProcessor {
             env,
             rl,
             hist_path,
             commands,
+            plugins: vec![],
+            failed: false,
         }
     }
 
-    fn get_command_vec() -> Vec<Box<dyn Cmd>> {
-        let commands: Vec<Box<dyn Cmd>> = vec![
+    /// The built in commands, followed by plugins
+    fn get_command_vec(plugins: Vec<Plugin>) -> Vec<Box<dyn Cmd>> {
+        let mut commands: Vec<Box<dyn Cmd>> = vec![
             Box::new(crate::command::alias::Alias::new()),
             Box::new(crate::command::alias::Unalias::new()),
+            Box::new(crate::command::apiresources::ApiResources::new()),
+            Box::new(crate::command::click::Back::new()),
+            Box::new(crate::command::click::BookmarkCmd::new()),
+            Box::new(crate::command::nodes::ClusterCapacity::new()),
+            Box::new(crate::command::certs::Certs::new()),
+            Box::new(crate::command::owners::Children::new()),
             Box::new(crate::command::click::Clear::new()),
+            Box::new(crate::command::click::ConfigCmd::new()),
             Box::new(crate::command::click::Context::new()),
             Box::new(crate::command::click::Contexts::new()),
             Box::new(crate::command::click::EnvCmd::new()),
+            Box::new(crate::command::click::Forward::new()),
+            Box::new(crate::command::click::Grep::new()),
+            Box::new(crate::command::click::Highlight::new()),
+            Box::new(crate::command::click::Pick::new()),
             Box::new(crate::command::click::Quit::new()),
             Box::new(crate::command::click::Range::new()),
             Box::new(crate::command::click::SetCmd::new()),
             Box::new(crate::command::click::UnSetCmd::new()),
             Box::new(crate::command::click::UtcCmd::new()),
+            Box::new(crate::command::click::Version::new()),
             Box::new(crate::command::configmaps::ConfigMaps::new()),
             Box::new(crate::command::copy::Copy::new()),
             Box::new(crate::command::crds::Crd::new()),
             Box::new(crate::command::daemonsets::DaemonSets::new()),
+            Box::new(crate::command::debug::Debug::new()),
             Box::new(crate::command::delete::Delete::new()),
             Box::new(crate::command::deployments::Deployments::new()),
             Box::new(crate::command::describe::Describe::new()),
+            Box::new(crate::command::diff::Diff::new()),
+            Box::new(crate::command::dump::Dump::new()),
             Box::new(crate::command::events::Events::new()),
             Box::new(crate::command::exec::Exec::new()),
+            Box::new(crate::command::explain::Explain::new()),
+            Box::new(crate::command::autoscalers::HorizontalPodAutoscalers::new()),
+            Box::new(crate::command::images::Images::new()),
+            Box::new(crate::command::token::InspectToken::new()),
             Box::new(crate::command::jobs::Jobs::new()),
             Box::new(crate::command::logs::Logs::new()),
             Box::new(crate::command::namespaces::Namespace::new()),
             Box::new(crate::command::namespaces::Namespaces::new()),
+            Box::new(crate::command::network::Network::new()),
+            Box::new(crate::command::nodes::NodeAllocation::new()),
             Box::new(crate::command::nodes::Nodes::new()),
+            Box::new(crate::command::owners::Owner::new()),
             Box::new(crate::command::pods::Containers::new()),
             Box::new(crate::command::pods::Pods::new()),
+            Box::new(crate::command::problems::Problems::new()),
             Box::new(crate::command::portforwards::PortForward::new()),
             Box::new(crate::command::portforwards::PortForwards::new()),
             Box::new(crate::command::replicasets::ReplicaSets::new()),
+            Box::new(crate::command::resources::Resources::new()),
+            Box::new(crate::command::resources::QuotaCheck::new()),
+            Box::new(crate::command::autoscalers::Scaling::new()),
             Box::new(crate::command::secrets::Secrets::new()),
+            Box::new(crate::command::security::Security::new()),
             Box::new(crate::command::services::Services::new()),
             Box::new(crate::command::statefulsets::StatefulSets::new()),
+            Box::new(crate::command::volumeclaims::Storage::new()),
             Box::new(crate::command::storage::StorageClasses::new()),
+            Box::new(crate::command::top::Top::new()),
+            Box::new(crate::command::volumeclaims::PersistentVolumeClaims::new()),
             Box::new(crate::command::volumes::PersistentVolumes::new()),
+            Box::new(crate::command::wait::Wait::new()),
             #[cfg(feature = "argorollouts")]
             Box::new(crate::command::rollouts::Rollouts::new()),
         ];
+        commands.extend(
+            plugins
+                .into_iter()
+                .map(|plugin| Box::new(plugin) as Box<dyn Cmd>),
+        );
         commands
     }
 
@@ -218,7 +416,13 @@ impl CommandProcessor {
         while !self.env.quit {
             let mut writer = ClickWriter::new();
             if self.env.need_new_editor {
-                self.rl = get_editor(self.env.get_rustyline_conf(), &self.hist_path);
+                // save first, so this session's history is loaded into the new editor
+                self.save_history();
+                self.rl = get_editor(
+                    self.env.get_rustyline_conf(),
+                    &self.hist_path,
+                    self.plugins.clone(),
+                );
                 Rc::get_mut(&mut self.env).unwrap().need_new_editor = false;
             }
 
@@ -233,7 +437,9 @@ impl CommandProcessor {
             }
             match readline {
                 Ok(line) => {
+                    Rc::get_mut(&mut self.env).unwrap().take_new_objs();
                     self.process_line(line.as_str(), writer);
+                    self.maybe_pick(line.as_str());
                 }
                 Err(ReadlineError::Interrupted) => {} // don't exit on Ctrl-C
                 Err(ReadlineError::Eof) => {
@@ -246,16 +452,42 @@ impl CommandProcessor {
                 }
             }
         }
+        self.save_history();
         let env = Rc::get_mut(&mut self.env).unwrap();
         env.save_click_config();
+        env.stop_all_forwards();
+    }
+
+    // open the picker if line printed a list to the terminal that's longer than pick_threshold
+    fn maybe_pick(&mut self, line: &str) {
+        let env = Rc::get_mut(&mut self.env).unwrap();
+        let threshold = match env.click_config.pick_threshold {
+            Some(threshold) => threshold,
+            None => return,
+        };
+        if !env.take_new_objs() || env.last_objs().len() <= threshold || self.failed {
+            return;
+        }
+        let expanded_line = alias_expand_line(env, line);
+        if !matches!(parse_line(&expanded_line), Ok((_, RightExpr::None)))
+            || !std::io::stdout().is_terminal()
+        {
+            return;
+        }
+        if let Err(e) = env.pick_object("") {
+            println!("Couldn't open the picker: {}", e);
+        }
+    }
+
+    fn save_history(&mut self) {
+        dedup_history(self.rl.history_mut());
         if let Err(e) = self.rl.save_history(self.hist_path.as_path()) {
             println!("Couldn't save command history: {}", e);
         }
-        env.stop_all_forwards();
     }
 
     /// Process the line.  Returns the result of finish_output on the writer
-    pub fn process_line(&mut self, line: &str, mut writer: ClickWriter) -> Option<Vec<u8>> {
+    pub fn process_line(&mut self, line: &str, writer: ClickWriter) -> Option<Vec<u8>> {
         if line.is_empty() {
             return writer.finish_output();
         }
@@ -273,34 +505,117 @@ impl CommandProcessor {
         } else {
             &line[first_non_whitespace..]
         };
-        let expanded_line = alias_expand_line(&self.env, lstr);
-        match parse_line(&expanded_line) {
+        // time spent answering prompts doesn't count, the user was there for that
+        self.env.take_prompt_time();
+        let start = Instant::now();
+        let output = self.run_line(lstr, writer);
+        let took = start.elapsed().saturating_sub(self.env.take_prompt_time());
+        if should_notify(self.env.click_config.notify_after_secs, took) {
+            notify_finished(self.env.click_config.notify, lstr, self.failed);
+        }
+        output
+    }
+
+    /// Run a line without adding it to the history. Returns the result of finish_output on the
+    /// writer, and sets failed if the line couldn't be run
+    fn run_line(&mut self, lstr: &str, writer: ClickWriter) -> Option<Vec<u8>> {
+        self.failed = false;
+        let chain = split_chain(lstr);
+        let cmd = match chain.len() {
+            0 => return writer.finish_output(),
+            1 => chain[0].1,
+            _ => return self.run_chain(chain, writer, true),
+        };
+        let expanded_line = alias_expand_line(&self.env, cmd);
+        let chain = split_chain(&expanded_line);
+        match chain.len() {
+            0 => writer.finish_output(),
+            1 => self.run_expanded_line(chain[0].1, writer),
+            // an alias for a chain, like 'context prod && pods'. What the alias expands to isn't
+            // expanded again
+            _ => self.run_chain(chain, writer, false),
+        }
+    }
+
+    /// Run commands chained with ; and &&, skipping commands after an && if the command before
+    /// failed. failed is left set as it was by the last command that ran, like in a shell
+    fn run_chain(
+        &mut self,
+        chain: Vec<(ChainOp, &str)>,
+        writer: ClickWriter,
+        expand_aliases: bool,
+    ) -> Option<Vec<u8>> {
+        let mut output: Option<Vec<u8>> = None;
+        for (op, cmd) in chain.into_iter() {
+            if self.env.quit {
+                break;
+            }
+            if op == ChainOp::IfSucceeded && self.failed {
+                continue;
+            }
+            let res = if expand_aliases {
+                self.run_line(cmd, writer.new_like())
+            } else {
+                self.run_expanded_line(cmd, writer.new_like())
+            };
+            if let Some(res) = res {
+                output.get_or_insert_with(Vec::new).extend(res);
+            }
+        }
+        if let Some(res) = writer.finish_output() {
+            output.get_or_insert_with(Vec::new).extend(res);
+        }
+        output
+    }
+
+    /// Run a line that's already had aliases expanded, and that has no ; or && in it
+    fn run_expanded_line(
+        &mut self,
+        expanded_line: &str,
+        mut writer: ClickWriter,
+    ) -> Option<Vec<u8>> {
+        self.failed = false;
+        if let Some(rest) = expanded_line.strip_prefix("foreach ") {
+            return self.foreach(rest, writer);
+        }
+        match parse_line(expanded_line) {
             Ok((left, right)) => {
                 // set up output
                 match right {
-                    RightExpr::None => {} // do nothing
+                    RightExpr::None => {
+                        if self.env.click_config.use_pager {
+                            writer.enable_pager(self.env.pager());
+                        }
+                    }
                     RightExpr::Pipe(cmd) => {
                         if let Err(e) = writer.setup_pipe(cmd) {
                             println!("{}", e);
+                            self.failed = true;
                             return writer.finish_output();
                         }
                     }
-                    RightExpr::Redir(filename) => match File::create(filename) {
+                    RightExpr::Redir(filename) => match File::create(redir_path(filename)) {
                         Ok(out_file) => {
                             writer.set_output_file(out_file);
                         }
                         Err(ref e) => {
                             println!("Can't open output file: {}", e);
+                            self.failed = true;
                             return writer.finish_output();
                         }
                     },
                     RightExpr::Append(filename) => {
-                        match OpenOptions::new().append(true).create(true).open(filename) {
+                        match OpenOptions::new()
+                            .append(true)
+                            .create(true)
+                            .open(redir_path(filename))
+                        {
                             Ok(out_file) => {
                                 writer.set_output_file(out_file);
                             }
                             Err(ref e) => {
                                 println!("Can't open output file: {}", e);
+                                self.failed = true;
                                 return writer.finish_output();
                             }
                         }
@@ -309,6 +624,7 @@ impl CommandProcessor {
                 let parts_vec: Vec<String> = Parser::new(left).map(|x| x.2).collect();
                 let mut parts = parts_vec.iter().map(|s| &**s);
                 let env = Rc::get_mut(&mut self.env).unwrap();
+                let from = env.location();
                 if let Some(cmdstr) = parts.next() {
                     // There was something typed
                     if let Ok(num) = (cmdstr as &str).parse::<usize>() {
@@ -339,39 +655,168 @@ impl CommandProcessor {
                             env.set_range(objs);
                         }
                     } else if let Some(cmd) = self.commands.iter().find(|&c| c.is(cmdstr)) {
-                        // found a matching command
-                        if let Err(e) = cmd.exec(env, &mut parts, &mut writer) {
-                            match e {
-                                ClickError::Reqwest(_, Some(val)) => {
-                                    let reason = val_str("/reason", &val, "no reason given");
-                                    let msg = val_str("/message", &val, "no message returned");
-                                    clickwriteln!(
-                                        writer,
-                                        "Error executing request. Reason: {}, Message: {}",
-                                        reason,
-                                        msg
-                                    )
-                                }
-                                _ => clickwriteln!(writer, "{}", e),
-                            };
+                        // found a matching command. keep what it prints for grep, unless it's
+                        // grep, so the same output can be grepped again
+                        if cmd.get_name() != "grep" {
+                            writer.start_capture();
+                        }
+                        let mut parts = parts.peekable();
+                        if takes_selection(cmd.as_ref()) {
+                            // like logs web-1, which selects web-1 from the last list first
+                            if let Some(num) = parts.peek().and_then(|arg| env.item_index(arg)) {
+                                env.set_current(num);
+                                parts.next();
+                            }
+                        }
+                        let res = cmd.exec(env, &mut parts, &mut writer);
+                        env.show_warnings();
+                        if let Some(output) = writer.take_capture() {
+                            if env.take_output_sensitive() {
+                                env.set_last_output(String::new());
+                            } else {
+                                env.set_last_output(String::from_utf8_lossy(&output).into_owned());
+                            }
+                        }
+                        if let Err(e) = res {
+                            self.failed = true;
+                            clickwriteln!(writer, "{}", e);
                         }
                     } else if cmdstr == "help" {
                         self.show_help(&mut parts, &mut writer);
+                    } else if cmdstr == "source" {
+                        // like help, source needs the commands, so it isn't a command itself
+                        self.source(&mut parts, &mut writer);
+                    } else if let Some(num) = env.item_index(cmdstr) {
+                        // the name of an object from the last list selects it, like its number
+                        env.set_current(num);
                     } else {
                         clickwriteln!(writer, "Unknown command");
+                        self.failed = true;
                     }
                 }
+                Rc::get_mut(&mut self.env).unwrap().moved_from(from);
 
                 // reset output
                 writer.finish_output()
             }
             Err(err) => {
                 println!("{}", err);
+                self.failed = true;
                 None
             }
         }
     }
 
+    /// Run a list command, then run a command on every object it returned, like:
+    /// foreach pods -l app=worker | delete
+    fn foreach(&mut self, line: &str, mut writer: ClickWriter) -> Option<Vec<u8>> {
+        let (list, cmd) = match parse_line(line) {
+            Ok((list, RightExpr::Pipe(cmd))) => (list.trim(), cmd.trim()),
+            Ok(_) => {
+                clickwriteln!(writer, "Usage: foreach <LIST COMMAND> | <COMMAND>");
+                self.failed = true;
+                return writer.finish_output();
+            }
+            Err(e) => {
+                clickwriteln!(writer, "{}", e);
+                self.failed = true;
+                return writer.finish_output();
+            }
+        };
+        // clear the last list, so we don't run on old objects if list doesn't return any
+        Rc::get_mut(&mut self.env).unwrap().clear_last_objs();
+        self.run_line(list, ClickWriter::new());
+        if self.failed {
+            return writer.finish_output();
+        }
+        let objs = self.env.last_objs().to_vec();
+        if objs.is_empty() {
+            clickwriteln!(
+                writer,
+                "No objects returned by '{}', not running '{}'",
+                list,
+                cmd
+            );
+            return writer.finish_output();
+        }
+        Rc::get_mut(&mut self.env).unwrap().set_range(objs);
+        self.run_line(cmd, writer)
+    }
+
+    fn source(&mut self, parts: &mut dyn Iterator<Item = &str>, writer: &mut ClickWriter) {
+        let mut keep_going = false;
+        let mut path = None;
+        for part in parts {
+            match part {
+                "-k" | "--keep-going" => keep_going = true,
+                _ if part.starts_with('-') => {
+                    clickwriteln!(
+                        writer,
+                        "Unknown option {} for source. Usage: source [-k|--keep-going] <FILE>",
+                        part
+                    );
+                    self.failed = true;
+                    return;
+                }
+                _ if path.is_none() => path = Some(PathBuf::from(part)),
+                _ => {
+                    clickwriteln!(writer, "source takes only one file");
+                    self.failed = true;
+                    return;
+                }
+            }
+        }
+        match path {
+            Some(path) => {
+                if let Err(e) = self.run_script(&path, keep_going) {
+                    clickwriteln!(writer, "{}", e);
+                    self.failed = true;
+                }
+            }
+            None => {
+                clickwriteln!(writer, "Usage: source [-k|--keep-going] <FILE>");
+                self.failed = true;
+            }
+        }
+    }
+
+    /// Run each line of the file at path as a click command. Empty lines and lines starting with #
+    /// are ignored. Stops at the first command that fails unless keep_going is true
+    pub fn run_script(&mut self, path: &Path, keep_going: bool) -> Result<(), ClickError> {
+        let script = std::fs::read_to_string(path)?;
+        let mut failures = 0;
+        for (num, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            println!("{}{}", self.env.prompt, line);
+            self.run_line(line, ClickWriter::new());
+            if self.failed {
+                failures += 1;
+                if !keep_going {
+                    return Err(ClickError::CommandError(format!(
+                        "Stopping script, line {} of {} failed",
+                        num + 1,
+                        path.display()
+                    )));
+                }
+            }
+            if self.env.quit {
+                break;
+            }
+        }
+        if failures > 0 {
+            Err(ClickError::CommandError(format!(
+                "{} command(s) in {} failed",
+                failures,
+                path.display()
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
     fn show_help(&mut self, parts: &mut dyn Iterator<Item = &str>, writer: &mut ClickWriter) {
         // help isn't a command as it needs access to the commands vec
         if let Some(hcmd) = parts.next() {
@@ -389,6 +834,18 @@ impl CommandProcessor {
                     "edit_mode" => {
                         clickwriteln!(writer, "{}", EDITMODEHELP);
                     }
+                    "history" => {
+                        clickwriteln!(writer, "{}", HISTORYHELP);
+                    }
+                    "foreach" => {
+                        clickwriteln!(writer, "{}", FOREACHHELP);
+                    }
+                    "source" | "scripts" => {
+                        clickwriteln!(writer, "{}", SOURCEHELP);
+                    }
+                    "plugins" => {
+                        clickwriteln!(writer, "{}", PLUGINHELP);
+                    }
                     "ranges" => {
                         clickwriteln!(writer, "{}", RANGEHELP);
                     }
@@ -430,6 +887,21 @@ impl CommandProcessor {
                 "  edit_mode           Available edit_mode values for \
                  the 'set' command, and what they mean"
             );
+            clickwriteln!(
+                writer,
+                "  foreach             Running a command on every object a \
+                 list command returns"
+            );
+            clickwriteln!(
+                writer,
+                "  history             Searching and reusing commands \
+                 from previous sessions"
+            );
+            clickwriteln!(
+                writer,
+                "  plugins             Adding commands to click with click-* \
+                 executables"
+            );
             clickwriteln!(
                 writer,
                 "  ranges              Selecting and operating on multiple \
@@ -440,6 +912,11 @@ impl CommandProcessor {
                 "  shell               Redirecting and piping click \
                  output to shell commands"
             );
+            clickwriteln!(
+                writer,
+                "  source              Running a file of click commands, \
+                 like a runbook"
+            );
         }
     }
 }
@@ -454,18 +931,104 @@ Examples:\n\
  # Save logs to logs.txt:\n\
  logs my-cont > /tmp/logs.txt\n\n\
  # Append log lines that contain \"foo bar\" to logs.txt\n\
- logs the-cont | grep \"foo bar\" >> /tmp/logs.txt";
+ logs the-cont | grep \"foo bar\" >> /tmp/logs.txt\n\n\
+Commands can be chained on one line with ; (always run the next command) and && (only run the \
+next command if the last one succeeded). A redirect or pipe applies only to the command it follows.\n\n\
+Examples:\n\
+ # switch to prod, and only list pods if that worked\n\
+ context prod && namespace payments && pods\n\n\
+ # list pods and then events, even if listing pods fails\n\
+ pods ; events";
 
 static COMPLETIONHELP: &str = "There are two completion types: list or circular.
 - list: complete the next full match (like in Vim by default) (do: set completion list)
 - circular: complete until the longest match. If there is more than one match, \
-it will list all matches (like in Bash/Readline). (do: set completion circular)";
+it will list all matches (like in Bash/Readline). (do: set completion circular)
+
+Object names are completed too. At the start of the line, the names of the objects returned by \
+the last list command are completed, and typing a name selects that object, just like typing its \
+number. Commands that act on the active object, like 'logs' and 'describe', complete those names \
+as their first argument too, and select the named object before running. The --regex option of \
+list commands like 'pods' completes the names of objects of that kind, using the last list if it \
+returned that kind and otherwise fetching them.";
 
 static EDITMODEHELP: &str = "There are two edit modes: vi or emacs.
 This controls the style of editing and the standard keymaps to the mode used by the \
 associated editor.
 - 'vi' Hit ESC while editing to edit the line using common vi keybindings (do: 'set edit_mode vi')
-- 'emacs' Use standard readline/bash/emacs keybindings (do: 'set edit_mode emacs')";
+- 'emacs' Use standard readline/bash/emacs keybindings (do: 'set edit_mode emacs')
+
+The mode is saved in your click config as 'editmode: Vi' or 'editmode: Emacs', so it's used for \
+every session.
+
+In vi mode, lines start in insert mode. ESC switches to normal mode, where these keys work:
+  h/l          move left/right a character
+  w/b/e        move to the next word, previous word, or end of the word (W/B/E for big words)
+  0/^/$        move to the start of the line, the first non-blank, or the end of the line
+  f/F/t/T      move to (or just before) the next/previous occurrence of a character, ; repeats
+  j/k          move to the next/previous history entry
+  x/X          delete the character under/before the cursor
+  d/c/y        delete, change, or yank, followed by a movement (dd, cc, and yy act on the line)
+  p/P          paste after/before the cursor
+  r            replace the character under the cursor
+  u            undo
+  i/a/I/A      go back to insert mode before/after the cursor, or at the start/end of the line
+  Enter        run the line";
+
+static FOREACHHELP: &str = "foreach <LIST COMMAND> | <COMMAND>
+Run a command that lists objects, like 'pods -l app=worker', select everything it returned as a \
+range, and then run COMMAND on each of them. For example:
+  foreach pods -l app=worker | delete
+  foreach deployments -r frontend | describe
+
+The list is printed first, so you can see what COMMAND will run on. COMMAND runs just as it does \
+for any range (see 'help ranges'), so it reports which objects it succeeded and failed on at the \
+end. Only the first | separates the list and the command, anything after another | is piped to \
+the shell as usual. If the list returns nothing, COMMAND isn't run at all.";
+
+static SOURCEHELP: &str = "source [-k|--keep-going] <FILE>
+Run each line of FILE as a click command, printing each command before running it. Empty lines \
+and lines starting with # are ignored. Click stops at the first command that fails, unless \
+-k/--keep-going is given, in which case it runs everything and reports how many commands failed.
+
+Scripts can also be run from the command line with 'click --script FILE', which exits with a \
+non-zero status if the script fails.
+
+Example runbook:
+  # check on the web pods
+  context prod
+  namespace web
+  pods -r frontend
+  0
+  logs --tail 100 | grep ERROR";
+
+static PLUGINHELP: &str = "Any executable on your PATH named click-<name> can be run from click \
+as <name>, and shows up in 'help' as a plugin. Commands built in to click take precedence over \
+plugins with the same name. Plugins are found when click starts.
+
+Arguments typed after the name are passed to the plugin as they are, and its output can be piped \
+and redirected like any other command's. Plugins are told about click's current state with these \
+environment variables:
+  CLICK_CONTEXT           The current context (empty if there isn't one)
+  CLICK_NAMESPACE         The current namespace (empty if there isn't one)
+  KUBECONFIG              The kubernetes config file(s) click is using
+  CLICK_OBJECT_KIND       The kind (like Pod) of the selected object, if one object is selected
+  CLICK_OBJECT_NAME       The name of the selected object
+  CLICK_OBJECT_NAMESPACE  The namespace of the selected object
+They're also sent as json on stdin, which includes every object of a selected range:
+  {\"context\": \"prod\", \"namespace\": \"web\", \"kubeconfig\": \"/home/me/.kube/config\",
+   \"selection\": [{\"kind\": \"Pod\", \"name\": \"web-1\", \"namespace\": \"web\"}]}";
+
+static HISTORYHELP: &str = "Commands are saved to ~/.config/click/history when click exits, \
+and loaded again on startup. Only the most recent copy of a repeated command is kept, and at most \
+history_size commands are saved (do: set history_size 5000). Commands that start with a space \
+are not saved.
+- Up/Down: move through previous commands
+- Ctrl-R: search backwards through history for commands containing what you type. Press Ctrl-R \
+again to find an older match, Enter to run the match, or Ctrl-G to give up and restore the line
+- Ctrl-S: search forwards, after going back too far with Ctrl-R (note that many terminals use \
+Ctrl-S to pause output unless configured otherwise)
+Search is available in both the emacs and vi edit modes.";
 
 // TODO: Something better than raw escapes maybe?
 static RANGEHELP: &str = "\u{001b}[33;1mRANGES\u{001b}[0m
@@ -493,8 +1056,13 @@ start..      (start to end of list)
 3..     # select items 3 and higher
 ..      # select everything in the list
 
+Ranges can also be written N-M, which includes M, and * selects everything:
+2-9     # select items 2 through 9
+*       # select everything in the list
+
 \u{001b}[32mComma Separated List\u{001b}[0m
 You can specify a list of items to select like: '1,3,12' to select items 1, 3, and 12.
+Items can be N-M ranges too, so '1,4-6' selects items 1, 4, 5, and 6.
 Note that if you want to include spaces, you'll need to quote the string like:
 \"1, 3,  12\"
 
@@ -508,6 +1076,9 @@ item in the range in turn:
 
 containers, describe, delete, events, exec, logs
 
+When the command finishes, Click prints how many objects it succeeded and failed on, along with the
+names of any that failed.
+
 \u{001b}[33;1mRANGE SEPARATOR\u{001b}[0m
 When printing output for the above commands over a range, Click will print a header for each item.
 The format is defined by the range separator. You can view the current separator with the 'env'
@@ -645,8 +1216,12 @@ mod tests {
 Other help topics (type 'help [TOPIC]' for details)
   completion          Available completion_type values for the 'set' command, and what they mean
   edit_mode           Available edit_mode values for the 'set' command, and what they mean
+  foreach             Running a command on every object a list command returns
+  history             Searching and reusing commands from previous sessions
+  plugins             Adding commands to click with click-* executables
   ranges              Selecting and operating on multiple objects at once
-  shell               Redirecting and piping click output to shell commands\n"
+  shell               Redirecting and piping click output to shell commands
+  source              Running a file of click commands, like a runbook\n"
                 .as_bytes()
         );
     }
@@ -660,6 +1235,66 @@ Other help topics (type 'help [TOPIC]' for details)
         assert_eq!(res, "Unknown command\n".as_bytes());
     }
 
+    #[test]
+    fn chained_commands() {
+        let mut p = get_processor();
+        let writer = ClickWriter::with_buffer(vec![], false);
+        let res = p.process_line("testcmd ; blah", writer).unwrap();
+        assert_eq!(res, "Called with no argsUnknown command\n".as_bytes());
+        assert!(p.failed);
+
+        let writer = ClickWriter::with_buffer(vec![], false);
+        let res = p.process_line("blah ; testcmd", writer).unwrap();
+        assert_eq!(res, "Unknown command\nCalled with no args".as_bytes());
+        assert!(!p.failed);
+
+        let writer = ClickWriter::with_buffer(vec![], false);
+        let res = p.process_line("blah && testcmd ; testcmd", writer).unwrap();
+        assert_eq!(res, "Unknown command\nCalled with no args".as_bytes());
+
+        let writer = ClickWriter::with_buffer(vec![], false);
+        let res = p.process_line("testcmd && testcmd", writer).unwrap();
+        assert_eq!(res, "Called with no argsCalled with no args".as_bytes());
+    }
+
+    #[test]
+    fn last_output() {
+        let mut p = get_processor();
+        let writer = ClickWriter::with_buffer(vec![], false);
+        p.process_line("testcmd foo", writer);
+        assert_eq!(p.env.last_output(), "Called with foo");
+
+        // things that aren't commands don't replace it
+        let writer = ClickWriter::with_buffer(vec![], false);
+        p.process_line("blah", writer);
+        assert_eq!(p.env.last_output(), "Called with foo");
+    }
+
+    #[test]
+    fn source_unknown_option() {
+        let mut p = get_processor();
+        let buf = Vec::new();
+        let writer = ClickWriter::with_buffer(buf, false);
+        let res = p
+            .process_line("source --bogus script.click", writer)
+            .unwrap();
+        assert_eq!(
+            res,
+            "Unknown option --bogus for source. Usage: source [-k|--keep-going] <FILE>\n"
+                .as_bytes()
+        );
+        assert!(p.failed);
+    }
+
+    #[test]
+    fn command_help_to_writer() {
+        let mut writer = ClickWriter::with_buffer(Vec::new(), false);
+        crate::command::delete::Delete::new().write_help(&mut writer);
+        let help = String::from_utf8(writer.finish_output().unwrap()).unwrap();
+        assert!(help.contains("-g, --gracePeriod <SECONDS>"));
+        assert!(help.contains("Examples:"));
+    }
+
     #[test]
     fn exec() {
         let mut p = get_processor();
@@ -883,6 +1518,98 @@ Other help topics (type 'help [TOPIC]' for details)
         assert_eq!(r.1, RightExpr::Append("b"));
     }
 
+    #[test]
+    fn test_notify() {
+        assert!(!should_notify(None, Duration::from_secs(100)));
+        assert!(!should_notify(Some(10), Duration::from_millis(9999)));
+        assert!(should_notify(Some(10), Duration::from_secs(10)));
+        assert!(should_notify(Some(0), Duration::ZERO));
+
+        assert_eq!(notify_message("pods", false), "Finished: 'pods'");
+        assert_eq!(notify_message("logs -f", true), "Failed: 'logs -f'");
+
+        assert_eq!(applescript_string("pods"), "\"pods\"");
+        assert_eq!(
+            applescript_string(r#"Finished: 'pods | grep "a\b"'"#),
+            r#""Finished: 'pods | grep \"a\\b\"'""#
+        );
+        assert_eq!(applescript_string("ünïcode"), "\"ünïcode\"");
+    }
+
+    #[test]
+    fn test_redir_path() {
+        assert_eq!(redir_path("/tmp/out.txt"), PathBuf::from("/tmp/out.txt"));
+        assert_eq!(redir_path("\"my file.txt\""), PathBuf::from("my file.txt"));
+        assert_eq!(redir_path("'x'"), PathBuf::from("x"));
+        assert_eq!(redir_path("\""), PathBuf::from("\""));
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(redir_path("~/out.txt"), home.join("out.txt"));
+        }
+
+        let p = build_parser_expr("a >  ", std::ops::Range { start: 2, end: 5 });
+        assert_eq!(
+            p.err().unwrap().to_string(),
+            "Parse Error: Parse error at 2: missing file name after >"
+        );
+    }
+
+    #[test]
+    fn test_foreach() {
+        let mut p = get_processor();
+        let buf = Vec::new();
+        let writer = ClickWriter::with_buffer(buf, false);
+        let res = p.process_line("foreach testcmd", writer).unwrap();
+        assert_eq!(
+            res,
+            "Usage: foreach <LIST COMMAND> | <COMMAND>\n".as_bytes()
+        );
+        assert!(p.failed);
+
+        // testcmd doesn't return any objects, so the command isn't run
+        let buf = Vec::new();
+        let writer = ClickWriter::with_buffer(buf, false);
+        let res = p.process_line("foreach testcmd | testcmd", writer).unwrap();
+        assert_eq!(
+            res,
+            "No objects returned by 'testcmd', not running 'testcmd'\n".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_run_script() {
+        let mut p = get_processor();
+        let path = PathBuf::from("/tmp/click.test.script");
+        std::fs::write(&path, "# a comment\n\ntestcmd\n  badcmd\ntestcmd\n").unwrap();
+        assert_eq!(
+            p.run_script(&path, false).unwrap_err().to_string(),
+            "Error running command: Stopping script, line 4 of /tmp/click.test.script failed"
+        );
+        assert_eq!(
+            p.run_script(&path, true).unwrap_err().to_string(),
+            "Error running command: 1 command(s) in /tmp/click.test.script failed"
+        );
+
+        std::fs::write(&path, "testcmd\n# badcmd\n").unwrap();
+        assert!(p.run_script(&path, false).is_ok());
+        assert_eq!(p.rl.history().len(), 0);
+    }
+
+    #[test]
+    fn test_dedup_entries() {
+        let entries = vec!["pods", "logs", "pods", "describe", "logs"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            dedup_entries(entries),
+            vec![
+                "pods".to_string(),
+                "describe".to_string(),
+                "logs".to_string()
+            ]
+        );
+    }
+
     #[test]
     fn hist_ignore() {
         let mut p = get_processor();
@@ -947,4 +1674,43 @@ Other help topics (type 'help [TOPIC]' for details)
 
         assert_eq!(alias_expand_line(&env, "z x"), "xpand arg x");
     }
+
+    #[test]
+    fn test_alias_args() {
+        let mut cc = ClickConfig::default();
+        cc.aliases.push(Alias {
+            alias: "lg".to_string(),
+            expanded: "logs $1 | grep $2".to_string(),
+        });
+        cc.aliases.push(Alias {
+            alias: "all".to_string(),
+            expanded: "pods -r \"$@\" -R".to_string(),
+        });
+        cc.aliases.push(Alias {
+            alias: "a".to_string(),
+            expanded: "b".to_string(),
+        });
+        cc.aliases.push(Alias {
+            alias: "b".to_string(),
+            expanded: "a".to_string(),
+        });
+        let env = Env::new(get_test_config(), cc, PathBuf::from("/tmp/click.config"));
+
+        assert_eq!(
+            alias_expand_line(&env, "lg main ERROR"),
+            "logs main | grep ERROR"
+        );
+        assert_eq!(
+            alias_expand_line(&env, "lg main ERROR -v > out"),
+            "logs main | grep ERROR -v > out"
+        );
+        assert_eq!(alias_expand_line(&env, "lg main"), "logs main | grep ");
+        assert_eq!(
+            alias_expand_line(&env, "all foo bar | wc"),
+            "pods -r \"foo bar\" -R | wc"
+        );
+
+        // aliases that expand to each other stop
+        assert_eq!(alias_expand_line(&env, "a x"), "a x");
+    }
 }
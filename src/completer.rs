@@ -12,7 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use k8s_openapi::{api::core::v1 as api, List};
+use k8s_openapi::{
+    api::{
+        apps::v1 as apps_api, autoscaling::v2 as autoscaling_api, batch::v1 as batch_api,
+        core::v1 as api, storage::v1 as api_storage,
+    },
+    apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    http::Request,
+    List, ListOptional, ListableResource, Metadata,
+};
 use rustyline::{
     completion::{Completer, Pair},
     highlight::Highlighter,
@@ -20,11 +28,13 @@ use rustyline::{
     validate::Validator,
     Context, Helper, Result,
 };
+use serde::Deserialize;
 
-use crate::command::command_def::Cmd;
+use crate::command::command_def::{takes_selection, Cmd};
 use crate::env::Env;
-use crate::kobj::ObjType;
+use crate::kobj::{KObj, ObjType};
 
+use std::fmt::Debug;
 use std::rc::Rc;
 
 pub struct ClickHelper {
@@ -49,7 +59,11 @@ impl Hinter for ClickHelper {
 
 // get a vec with strings that could complete commands or aliases
 fn get_command_completion_strings(commands: &[Box<dyn Cmd>], env: Option<&Rc<Env>>) -> Vec<String> {
-    let mut v = vec!["help".to_string()];
+    let mut v = vec![
+        "foreach".to_string(),
+        "help".to_string(),
+        "source".to_string(),
+    ];
     for cmd in commands.iter() {
         v.push(cmd.get_name().to_string());
     }
@@ -151,15 +165,50 @@ impl ClickHelper {
             // here the last thing typed wasn't a '-' option, so we ask the command to
             // do completion
             if let Some(ref env) = self.env {
-                match last_opt {
-                    Some(opt) => {
-                        let opts = cmd.try_completed_named(pos, opt, prefix, env);
-                        (cmd_len, opts)
-                    }
-                    None => {
-                        let opts = cmd.try_complete(pos, prefix, env);
-                        (cmd_len, opts)
-                    }
+                // commands that act on the selection can be given the name of an object from the
+                // last list first, which isn't one of their own arguments
+                let takes_selection = takes_selection(cmd.as_ref());
+                let named_obj = takes_selection
+                    && pos > 0
+                    && line
+                        .split_whitespace()
+                        .skip(1)
+                        .find(|arg| !arg.starts_with('-'))
+                        .is_some_and(|arg| env.item_index(arg).is_some());
+                let pos = if named_obj { pos - 1 } else { pos };
+                let mut opts = match last_opt {
+                    Some(opt) => cmd.try_completed_named(pos, opt, prefix, env),
+                    None => cmd.try_complete(pos, prefix, env),
+                };
+                if takes_selection && !named_obj && pos == 0 && last_opt.is_none() {
+                    opts.extend(
+                        last_objs_completer(prefix, env)
+                            .into_iter()
+                            .map(|pair| Pair {
+                                replacement: pair.replacement[prefix.len()..].to_string(),
+                                display: pair.display,
+                            }),
+                    );
+                }
+                if opts.iter().any(|pair| !pair.display.starts_with(prefix)) {
+                    // a fuzzy match that doesn't start with what was typed, its replacement is the
+                    // whole value so replace the prefix too
+                    let opts = opts
+                        .into_iter()
+                        .map(|pair| {
+                            if pair.display.starts_with(prefix) {
+                                Pair {
+                                    replacement: format!("{}{}", prefix, pair.replacement),
+                                    display: pair.display,
+                                }
+                            } else {
+                                pair
+                            }
+                        })
+                        .collect();
+                    (cmd_len - prefix.len(), opts)
+                } else {
+                    (cmd_len, opts)
                 }
             } else {
                 (0, vec![])
@@ -216,21 +265,21 @@ impl ClickHelper {
             })
             .collect()
     }
-}
 
-/// Does the long option (an Option<str>) from clap match
-pub fn long_matches(long: &Option<&str>, prefix: &str) -> bool {
-    match long {
-        Some(lstr) => lstr.starts_with(prefix),
-        None => false,
-    }
-}
-
-impl Completer for ClickHelper {
-    type Candidate = Pair;
-    fn complete(&self, line: &str, pos: usize, _ctx: &Context) -> Result<(usize, Vec<Pair>)> {
+    fn complete_line(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        if let Some(rest) = line.strip_prefix("foreach ") {
+            // complete the list command, or the command after the |, as if it was typed alone
+            let rest = match rest.find('|') {
+                Some(idx) => &rest[idx + 1..],
+                None => rest,
+            };
+            let rest = rest.trim_start();
+            let offset = line.len() - rest.len();
+            let (start, pairs) = self.complete_line(rest, pos.saturating_sub(offset));
+            return (start + offset, pairs);
+        }
         if pos == 0 {
-            Ok((0, self.completion_vec()))
+            (0, self.completion_vec())
         } else if line.contains(char::is_whitespace) {
             // we do have a space, so now see if the first thing typed is a command, and
             // complete on it
@@ -240,54 +289,279 @@ impl Completer for ClickHelper {
                 .env
                 .as_ref()
                 .map(|e| crate::command_processor::alias_expand_line(e, line));
-            Ok(self.complete_exact_command(expanded.as_deref().unwrap_or(line), line.len()))
+            self.complete_exact_command(expanded.as_deref().unwrap_or(line), line.len())
         } else {
             // no command with space, so just complete commands
             let mut v = Vec::new();
             self.get_command_completions(line, &mut v);
-            Ok((0, v))
+            if let Some(ref env) = self.env {
+                // typing the name of an object from the last list selects it
+                v.extend(last_objs_completer(line, env));
+            }
+            (0, v)
         }
     }
 }
 
+/// Does the long option (an Option<str>) from clap match
+pub fn long_matches(long: &Option<&str>, prefix: &str) -> bool {
+    match long {
+        Some(lstr) => lstr.starts_with(prefix),
+        None => false,
+    }
+}
+
+impl Completer for ClickHelper {
+    type Candidate = Pair;
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context) -> Result<(usize, Vec<Pair>)> {
+        Ok(self.complete_line(line, pos))
+    }
+}
+
 // Individual completers are below
-pub fn context_complete(prefix: &str, env: &Env) -> Vec<Pair> {
-    let mut v = Vec::new();
-    for context in env.config.contexts.keys() {
-        if let Some(rest) = context.strip_prefix(prefix) {
-            v.push(Pair {
-                display: context.to_string(),
-                replacement: rest.to_string(),
-            })
-        }
+
+/// Does pattern match candidate fuzzily, that is, do all the chars of pattern appear in candidate
+/// in order. Case is ignored
+pub fn fuzzy_match(candidate: &str, pattern: &str) -> bool {
+    let mut chars = candidate.chars().flat_map(char::to_lowercase);
+    pattern
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|p| chars.any(|c| c == p))
+}
+
+/// Complete names that start with prefix, or if there aren't any, names that fuzzily match
+/// prefix. Names containing prefix come before other fuzzy matches. Fuzzy matches have the whole
+/// name as the replacement, and the completer replaces what was typed with it
+fn fuzzy_name_pairs(prefix: &str, names: Vec<String>) -> Vec<Pair> {
+    let pairs = name_pairs(prefix, names.iter().cloned());
+    if !pairs.is_empty() || prefix.is_empty() {
+        return pairs;
     }
-    v
+    let lower_prefix = prefix.to_lowercase();
+    let mut matches: Vec<String> = names
+        .into_iter()
+        .filter(|name| fuzzy_match(name, prefix))
+        .collect();
+    matches.sort_by_cached_key(|name| (!name.to_lowercase().contains(&lower_prefix), name.clone()));
+    matches.dedup();
+    matches
+        .into_iter()
+        .map(|name| Pair {
+            display: name.clone(),
+            replacement: name,
+        })
+        .collect()
+}
+
+pub fn context_complete(prefix: &str, env: &Env) -> Vec<Pair> {
+    fuzzy_name_pairs(prefix, env.config.contexts.keys().cloned().collect())
 }
 
 pub fn namespace_completer(prefix: &str, env: &Env) -> Vec<Pair> {
     let (request, _response_body) = api::Namespace::list_namespace(Default::default()).unwrap();
-    match env.run_on_context::<_, List<api::Namespace>>(|c| c.execute_list(request)) {
-        Ok(nslist) => nslist
+    let names = list_names::<api::Namespace>(env, request);
+    fuzzy_name_pairs(prefix, names)
+}
+
+// turn any names that start with prefix into completions
+fn name_pairs<I: IntoIterator<Item = String>>(prefix: &str, names: I) -> Vec<Pair> {
+    let mut v: Vec<Pair> = names
+        .into_iter()
+        .filter_map(|name| {
+            name.strip_prefix(prefix).map(|rest| {
+                let replacement = rest.to_string();
+                Pair {
+                    display: name.clone(),
+                    replacement,
+                }
+            })
+        })
+        .collect();
+    v.sort_unstable_by(|a, b| a.display.cmp(&b.display));
+    v.dedup_by(|a, b| a.display == b.display);
+    v
+}
+
+/// Complete names of the objects returned by the last list command. Unlike the other completers
+/// the replacement is the whole name, as this is used to complete the start of the line
+pub fn last_objs_completer(prefix: &str, env: &Env) -> Vec<Pair> {
+    let names = env.last_objs().iter().map(|obj| obj.name().to_string());
+    name_pairs(prefix, names)
+        .into_iter()
+        .map(|pair| Pair {
+            replacement: format!("{} ", pair.display),
+            display: pair.display,
+        })
+        .collect()
+}
+
+// names of cached objects of the kind matched by is_kind, or None if the last list didn't
+// return any objects of that kind
+fn cached_names<F: Fn(&KObj) -> bool>(env: &Env, is_kind: F) -> Option<Vec<String>> {
+    let names: Vec<String> = env
+        .last_objs()
+        .iter()
+        .filter(|obj| is_kind(obj))
+        .map(|obj| obj.name().to_string())
+        .collect();
+    if names.is_empty() {
+        None
+    } else {
+        Some(names)
+    }
+}
+
+fn list_names<T>(env: &Env, request: Request<Vec<u8>>) -> Vec<String>
+where
+    T: ListableResource + Metadata<Ty = ObjectMeta> + for<'de> Deserialize<'de> + Debug,
+{
+    // the names are all that's needed, so don't warn about objects that can't be fully read
+    let list = env.run_on_context::<_, List<T>>(|c| {
+        let list = c.execute_list(request);
+        c.take_warnings();
+        list
+    });
+    match list {
+        Ok(list) => list
             .items
             .into_iter()
-            .filter_map(|ns| {
-                ns.metadata.name.and_then(|name| {
-                    if let Some(rep) = name.strip_prefix(prefix) {
-                        let replacement = rep.to_string();
-                        Some(Pair {
-                            display: name,
-                            replacement,
-                        })
-                    } else {
-                        None
-                    }
-                })
-            })
+            .filter_map(|item| item.metadata().name.clone())
             .collect(),
         Err(_) => vec![],
     }
 }
 
+/// Define a completer for the names of objects of one kind. If the last list command returned
+/// objects of that kind their names are used, otherwise the objects are listed
+macro_rules! name_completer {
+    // namespaced objects, listed in the current namespace if there is one
+    ($name: ident, $objtype: pat, $typ: ty, $list_namespaced: ident, $list_all: ident) => {
+        pub fn $name(prefix: &str, env: &Env) -> Vec<Pair> {
+            let names = cached_names(env, |obj| matches!(obj.typ, $objtype)).unwrap_or_else(|| {
+                let request = match &env.namespace {
+                    Some(ns) => <$typ>::$list_namespaced(ns, ListOptional::default()),
+                    None => <$typ>::$list_all(ListOptional::default()),
+                };
+                match request {
+                    Ok((request, _)) => list_names::<$typ>(env, request),
+                    Err(_) => vec![],
+                }
+            });
+            name_pairs(prefix, names)
+        }
+    };
+    // cluster wide objects
+    ($name: ident, $objtype: pat, $typ: ty, $list: ident) => {
+        pub fn $name(prefix: &str, env: &Env) -> Vec<Pair> {
+            let names = cached_names(env, |obj| matches!(obj.typ, $objtype)).unwrap_or_else(|| {
+                match <$typ>::$list(ListOptional::default()) {
+                    Ok((request, _)) => list_names::<$typ>(env, request),
+                    Err(_) => vec![],
+                }
+            });
+            name_pairs(prefix, names)
+        }
+    };
+}
+
+name_completer!(
+    pod_name_completer,
+    ObjType::Pod { .. },
+    api::Pod,
+    list_namespaced_pod,
+    list_pod_for_all_namespaces
+);
+name_completer!(
+    configmap_name_completer,
+    ObjType::ConfigMap,
+    api::ConfigMap,
+    list_namespaced_config_map,
+    list_config_map_for_all_namespaces
+);
+name_completer!(
+    daemonset_name_completer,
+    ObjType::DaemonSet,
+    apps_api::DaemonSet,
+    list_namespaced_daemon_set,
+    list_daemon_set_for_all_namespaces
+);
+name_completer!(
+    deployment_name_completer,
+    ObjType::Deployment,
+    apps_api::Deployment,
+    list_namespaced_deployment,
+    list_deployment_for_all_namespaces
+);
+name_completer!(
+    job_name_completer,
+    ObjType::Job,
+    batch_api::Job,
+    list_namespaced_job,
+    list_job_for_all_namespaces
+);
+name_completer!(
+    replicaset_name_completer,
+    ObjType::ReplicaSet,
+    apps_api::ReplicaSet,
+    list_namespaced_replica_set,
+    list_replica_set_for_all_namespaces
+);
+name_completer!(
+    secret_name_completer,
+    ObjType::Secret,
+    api::Secret,
+    list_namespaced_secret,
+    list_secret_for_all_namespaces
+);
+name_completer!(
+    service_name_completer,
+    ObjType::Service,
+    api::Service,
+    list_namespaced_service,
+    list_service_for_all_namespaces
+);
+name_completer!(
+    statefulset_name_completer,
+    ObjType::StatefulSet,
+    apps_api::StatefulSet,
+    list_namespaced_stateful_set,
+    list_stateful_set_for_all_namespaces
+);
+name_completer!(node_name_completer, ObjType::Node, api::Node, list_node);
+name_completer!(
+    namespace_name_completer,
+    ObjType::Namespace,
+    api::Namespace,
+    list_namespace
+);
+name_completer!(
+    volume_name_completer,
+    ObjType::PersistentVolume,
+    api::PersistentVolume,
+    list_persistent_volume
+);
+name_completer!(
+    hpa_name_completer,
+    ObjType::HorizontalPodAutoscaler,
+    autoscaling_api::HorizontalPodAutoscaler,
+    list_namespaced_horizontal_pod_autoscaler,
+    list_horizontal_pod_autoscaler_for_all_namespaces
+);
+name_completer!(
+    pvc_name_completer,
+    ObjType::PersistentVolumeClaim,
+    api::PersistentVolumeClaim,
+    list_namespaced_persistent_volume_claim,
+    list_persistent_volume_claim_for_all_namespaces
+);
+name_completer!(
+    storageclass_name_completer,
+    ObjType::StorageClass,
+    api_storage::StorageClass,
+    list_storage_class
+);
+
 pub fn container_completer(prefix: &str, env: &Env) -> Vec<Pair> {
     let mut v = vec![];
     if let Some(pod) = env.current_pod() {
@@ -328,7 +602,109 @@ possible_values_completer!(
     crate::command::click::UNSET_OPTS
 );
 
+possible_values_completer!(configaction_values_completer, ["show", "path", "edit"]);
+
+possible_values_completer!(
+    bookmarkaction_values_completer,
+    ["list", "save", "go", "delete"]
+);
+
+pub fn bookmark_completer(prefix: &str, env: &Env) -> Vec<Pair> {
+    env.click_config
+        .bookmarks
+        .keys()
+        .filter_map(|name| {
+            name.strip_prefix(prefix).map(|rest| Pair {
+                display: name.clone(),
+                replacement: rest.to_string(),
+            })
+        })
+        .collect()
+}
+
 possible_values_completer!(
     portforwardaction_values_completer,
     ["list", "output", "stop"]
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("kube-system", "ksys"));
+        assert!(fuzzy_match("kube-system", "SYS"));
+        assert!(fuzzy_match("kube-system", ""));
+        assert!(!fuzzy_match("kube-system", "sk"));
+        assert!(!fuzzy_match("default", "defaults"));
+    }
+
+    #[test]
+    fn test_fuzzy_name_pairs() {
+        let names = vec![
+            "kube-system".to_string(),
+            "kube-public".to_string(),
+            "monitoring".to_string(),
+            "prism".to_string(),
+            "system-monitor".to_string(),
+        ];
+
+        // prefix matches only complete the rest of the name
+        let pairs = fuzzy_name_pairs("kube-", names.clone());
+        let found: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|p| (p.display.as_str(), p.replacement.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("kube-public", "public"), ("kube-system", "system")]
+        );
+
+        // fuzzy matches replace everything, with substring matches first
+        let pairs = fuzzy_name_pairs("sm", names.clone());
+        let found: Vec<&str> = pairs.iter().map(|p| p.replacement.as_str()).collect();
+        assert_eq!(found, vec!["prism", "kube-system", "system-monitor"]);
+        let pairs = fuzzy_name_pairs("sysmon", names);
+        let found: Vec<&str> = pairs.iter().map(|p| p.replacement.as_str()).collect();
+        assert_eq!(found, vec!["system-monitor"]);
+    }
+
+    #[test]
+    fn test_complete_selection_arg() {
+        let mut env = Env::new(
+            crate::config::get_test_config(),
+            crate::config::ClickConfig::default(),
+            std::path::PathBuf::from("/tmp/click.config"),
+        );
+        let pod = |name: &str| KObj {
+            name: name.to_string(),
+            namespace: Some("prod".to_string()),
+            typ: ObjType::Pod {
+                containers: vec!["app".to_string()],
+            },
+        };
+        env.set_last_objs(vec![pod("web-1"), pod("web-2"), pod("db-0")]);
+        let mut helper = ClickHelper::new(
+            vec![
+                Box::new(crate::command::logs::Logs::new()),
+                Box::new(crate::command::click::Range::new()),
+            ],
+            vec![],
+        );
+        helper.set_env(Some(Rc::new(env)));
+        let complete = |line: &str| {
+            let (start, pairs) = helper.complete_line(line, line.len());
+            let found: Vec<String> = pairs.into_iter().map(|pair| pair.replacement).collect();
+            (start, found)
+        };
+        assert_eq!(
+            complete("logs we"),
+            (7, vec!["b-1 ".to_string(), "b-2 ".to_string()])
+        );
+        // a named object isn't the command's first argument, and is only completed first
+        assert_eq!(complete("logs web-1 we"), (13, vec![]));
+        // commands that don't act on the selection don't complete names
+        assert_eq!(complete("range we"), (8, vec![]));
+    }
+}
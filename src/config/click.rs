@@ -16,11 +16,13 @@
 use atomicwrites::{AllowOverwrite, AtomicFile};
 use rustyline::config as rustyconfig;
 
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fs::File;
 use std::io::Read;
 
 use crate::error::ClickError;
+use crate::kobj::{KObj, ObjType};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub struct Alias {
@@ -28,6 +30,68 @@ pub struct Alias {
     pub expanded: String,
 }
 
+/// A saved context, namespace and object, which 'bookmark go' returns to
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Bookmark {
+    pub context: Option<String>,
+    pub namespace: Option<String>,
+    pub object: Option<BookmarkObject>,
+}
+
+/// The object a bookmark returns to. Its kind is saved by name, so the config loads whichever
+/// features click was built with
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct BookmarkObject {
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+    /// a pod's containers, which don't change over its life
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub containers: Vec<String>,
+    /// a custom resource's api group and version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_version: Option<String>,
+}
+
+impl From<&KObj> for BookmarkObject {
+    fn from(obj: &KObj) -> Self {
+        let (containers, group_version) = match &obj.typ {
+            ObjType::Pod { containers } => (containers.clone(), None),
+            ObjType::Crd { group_version, .. } => (vec![], Some(group_version.clone())),
+            _ => (vec![], None),
+        };
+        BookmarkObject {
+            kind: obj.type_str().to_string(),
+            name: obj.name.clone(),
+            namespace: obj.namespace.clone(),
+            containers,
+            group_version,
+        }
+    }
+}
+
+impl BookmarkObject {
+    /// The object to select, or None if this click doesn't know its kind (like a Rollout saved by
+    /// a click built with argorollouts)
+    pub fn to_kobj(&self) -> Option<KObj> {
+        let typ = match (self.kind.as_str(), &self.group_version) {
+            (kind, Some(group_version)) => ObjType::Crd {
+                _type: kind.to_string(),
+                group_version: group_version.clone(),
+            },
+            ("Pod", None) => ObjType::Pod {
+                containers: self.containers.clone(),
+            },
+            (kind, None) => ObjType::from_kind(kind)?,
+        };
+        Some(KObj {
+            name: self.name.clone(),
+            namespace: self.namespace.clone(),
+            typ,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum EditMode {
     Emacs,
@@ -90,15 +154,168 @@ impl From<&CompletionType> for String {
     }
 }
 
+/// A named output format for list commands, which can be used like `pods -o name`
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct OutputView {
+    pub name: String,
+    /// the format to print in, any value that can be passed to -o (defaults to table)
+    #[serde(default)]
+    pub format: Option<String>,
+    /// extra columns to show, like the values of --show
+    #[serde(default)]
+    pub show: Vec<String>,
+    /// a column or path to sort by, like the value of --sort-by
+    #[serde(default)]
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub reverse: bool,
+    /// only show objects matching this regex
+    #[serde(default)]
+    pub regex: Option<String>,
+}
+
+/// Default columns for a list command, used when --columns or --hide aren't given
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ListColumns {
+    /// the columns to show, in order, like the value of --columns
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// columns to hide, like the value of --hide
+    #[serde(default)]
+    pub hide: Vec<String>,
+}
+
+/// Glyphs to show in front of statuses, like the pod and node status, to show their health at a
+/// glance
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub enum StatusGlyphs {
+    #[default]
+    None,
+    Unicode,
+    Ascii,
+}
+
+impl fmt::Display for StatusGlyphs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                StatusGlyphs::None => "None",
+                StatusGlyphs::Unicode => "Unicode",
+                StatusGlyphs::Ascii => "Ascii",
+            }
+        )
+    }
+}
+
+/// The color theme. Light uses darker colors that are easier to read on a light background
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Theme::Dark => "Dark",
+                Theme::Light => "Light",
+            }
+        )
+    }
+}
+
+/// When destructive commands like delete ask for confirmation. Dangerous means only in contexts
+/// that match one of dangerous_contexts
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub enum ConfirmPolicy {
+    #[default]
+    Always,
+    Dangerous,
+    Never,
+}
+
+impl fmt::Display for ConfirmPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ConfirmPolicy::Always => "Always",
+                ConfirmPolicy::Dangerous => "Dangerous",
+                ConfirmPolicy::Never => "Never",
+            }
+        )
+    }
+}
+
+/// How to tell the user a long running command finished. Desktop falls back to the bell if a
+/// desktop notification can't be sent
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub enum NotifyMethod {
+    #[default]
+    Bell,
+    Desktop,
+}
+
+impl fmt::Display for NotifyMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                NotifyMethod::Bell => "Bell",
+                NotifyMethod::Desktop => "Desktop",
+            }
+        )
+    }
+}
+
+pub fn default_dangerous_contexts() -> Vec<String> {
+    vec!["prod*".to_string()]
+}
+
+/// Does text match pattern, where * in pattern matches any number of characters and ? matches
+/// exactly one
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // where the last * was, and the position in text it's matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // let the last * match one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
 pub fn default_range_sep() -> String {
     "--- {name} ---".to_string()
 }
 
-fn default_connect_timeout() -> u32 {
+pub fn default_connect_timeout() -> u32 {
     10
 }
 
-fn default_read_timeout() -> u32 {
+pub fn default_read_timeout() -> u32 {
     20
 }
 
@@ -106,11 +323,25 @@ fn default_describe_include_events() -> bool {
     true
 }
 
+fn default_colors() -> bool {
+    true
+}
+
+fn default_use_pager() -> bool {
+    true
+}
+
+fn default_history_size() -> usize {
+    1000
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ClickConfig {
     pub namespace: Option<String>,
     pub context: Option<String>,
     pub editor: Option<String>,
+    /// pager to send long output through. $PAGER (or less) is used when this isn't set
+    pub pager: Option<String>,
     pub terminal: Option<String>,
     pub kubectl_binary: Option<String>,
     #[serde(default = "EditMode::default")]
@@ -119,6 +350,13 @@ pub struct ClickConfig {
     pub completiontype: CompletionType,
     #[serde(default = "Vec::new")]
     pub aliases: Vec<Alias>,
+    #[serde(default = "Vec::new")]
+    pub output_views: Vec<OutputView>,
+    #[serde(default = "BTreeMap::new")]
+    pub bookmarks: BTreeMap<String, Bookmark>,
+    /// default columns for list commands, by command name (like pods)
+    #[serde(default = "BTreeMap::new")]
+    pub list_columns: BTreeMap<String, ListColumns>,
     #[serde(default = "default_range_sep")]
     pub range_separator: String,
 
@@ -129,6 +367,51 @@ pub struct ClickConfig {
 
     #[serde(default = "default_describe_include_events")]
     pub describe_include_events: bool,
+
+    #[serde(default = "default_colors")]
+    pub colors: bool,
+
+    #[serde(default)]
+    pub absolute_times: bool,
+
+    #[serde(default = "default_use_pager")]
+    pub use_pager: bool,
+
+    /// output format for list commands run without -o, like wide or yaml
+    pub default_output: Option<String>,
+
+    #[serde(default = "StatusGlyphs::default")]
+    pub status_glyphs: StatusGlyphs,
+
+    /// timezone to show times in: local (the default), utc, or an offset like +05:30
+    pub timezone: Option<String>,
+
+    /// maximum number of lines of command history to keep
+    #[serde(default = "default_history_size")]
+    pub history_size: usize,
+
+    /// template for the prompt, like '[{context_short}] [{namespace}] > '
+    pub prompt: Option<String>,
+
+    #[serde(default = "Theme::default")]
+    pub theme: Theme,
+    /// colors that override the theme, by element (like prompt_context: blue)
+    #[serde(default = "BTreeMap::new")]
+    pub theme_colors: BTreeMap<String, String>,
+
+    /// open the picker when a list command returns more than this many objects
+    pub pick_threshold: Option<usize>,
+
+    #[serde(default = "ConfirmPolicy::default")]
+    pub confirm: ConfirmPolicy,
+    /// patterns (with * and ?) for contexts where the Dangerous confirm policy asks
+    #[serde(default = "default_dangerous_contexts")]
+    pub dangerous_contexts: Vec<String>,
+
+    /// notify (see notify) when a command takes at least this many seconds
+    pub notify_after_secs: Option<u64>,
+    #[serde(default = "NotifyMethod::default")]
+    pub notify: NotifyMethod,
 }
 
 impl Default for ClickConfig {
@@ -137,15 +420,34 @@ impl Default for ClickConfig {
             namespace: None,
             context: None,
             editor: None,
+            pager: None,
             terminal: None,
             kubectl_binary: None,
             editmode: EditMode::default(),
             completiontype: CompletionType::default(),
             aliases: vec![],
+            output_views: vec![],
+            bookmarks: BTreeMap::new(),
+            list_columns: BTreeMap::new(),
             range_separator: default_range_sep(),
             connect_timeout_secs: default_connect_timeout(),
             read_timeout_secs: default_read_timeout(),
             describe_include_events: true,
+            colors: true,
+            absolute_times: false,
+            use_pager: true,
+            default_output: None,
+            status_glyphs: StatusGlyphs::default(),
+            timezone: None,
+            history_size: default_history_size(),
+            prompt: None,
+            theme: Theme::default(),
+            theme_colors: BTreeMap::new(),
+            pick_threshold: None,
+            confirm: ConfirmPolicy::default(),
+            dangerous_contexts: default_dangerous_contexts(),
+            notify_after_secs: None,
+            notify: NotifyMethod::default(),
         }
     }
 }
@@ -175,9 +477,28 @@ impl ClickConfig {
             }
             CompletionType::List => config.completion_type(rustyconfig::CompletionType::List),
         };
+        config = config
+            .max_history_size(self.history_size)
+            .history_ignore_dups(true);
         config.build()
     }
 
+    /// Should destructive commands ask for confirmation in the context with the given name
+    pub fn needs_confirmation(&self, context: Option<&str>) -> bool {
+        match self.confirm {
+            ConfirmPolicy::Always => true,
+            ConfirmPolicy::Never => false,
+            ConfirmPolicy::Dangerous => {
+                // with no context nothing can be deleted, so it doesn't matter
+                context.is_none_or(|context| {
+                    self.dangerous_contexts
+                        .iter()
+                        .any(|pattern| glob_match(pattern, context))
+                })
+            }
+        }
+    }
+
     /// Save this config to specified path.  It's safe to call this from multiple running instances
     /// of Click, since we use an AtomicFile
     pub fn save_to_file(&self, path: &str) -> Result<(), ClickError> {
@@ -198,13 +519,34 @@ pub mod tests {
 namespace: ns
 context: ctx
 editor: emacs
+pager: most
 kubectl_binary: /opt/bin/kubectl
 terminal: alacritty -e
 editmode: Vi
 completiontype: List
+status_glyphs: Ascii
+timezone: '+05:30'
+history_size: 500
+default_output: wide
+prompt: '{red}{context_short}{reset}:{namespace} > '
+theme: Light
+theme_colors:
+  table_header: dark_cyan
+pick_threshold: 50
+confirm: Dangerous
+dangerous_contexts: ['prod*', '*-live']
 aliases:
   - alias: pn
-    expanded: pods --sort node";
+    expanded: pods --sort node
+output_views:
+  - name: placement
+    show: [node, ip]
+    sort: node
+  - name: names
+    format: jsonpath={.items[*].metadata.name}
+list_columns:
+  pods:
+    hide: [age]";
 
     pub fn get_parsed_test_click_config() -> ClickConfig {
         ClickConfig::from_reader(TEST_CONFIG.as_bytes()).unwrap()
@@ -218,6 +560,7 @@ aliases:
         assert_eq!(config.namespace, Some("ns".to_owned()));
         assert_eq!(config.context, Some("ctx".to_owned()));
         assert_eq!(config.editor, Some("emacs".to_owned()));
+        assert_eq!(config.pager, Some("most".to_owned()));
         assert_eq!(config.terminal, Some("alacritty -e".to_owned()));
         assert_eq!(config.kubectl_binary, Some("/opt/bin/kubectl".to_owned()));
         assert_eq!(config.editmode, EditMode::Vi);
@@ -227,8 +570,45 @@ aliases:
         let a = config.aliases.get(0).unwrap();
         assert_eq!(a.alias, "pn");
         assert_eq!(a.expanded, "pods --sort node");
+        assert_eq!(config.output_views.len(), 2);
+        let v = &config.output_views[0];
+        assert_eq!(v.name, "placement");
+        assert_eq!(v.format, None);
+        assert_eq!(v.show, vec!["node".to_string(), "ip".to_string()]);
+        assert_eq!(v.sort, Some("node".to_string()));
+        assert!(!v.reverse);
+        assert_eq!(
+            config.list_columns.get("pods").unwrap().hide,
+            vec!["age".to_string()]
+        );
+        assert!(config.list_columns.get("pods").unwrap().columns.is_empty());
+        assert_eq!(
+            config.output_views[1].format,
+            Some("jsonpath={.items[*].metadata.name}".to_string())
+        );
         assert_eq!(config.connect_timeout_secs, default_connect_timeout());
         assert_eq!(config.read_timeout_secs, default_read_timeout());
+        assert_eq!(config.default_output, Some("wide".to_string()));
+        assert!(config.colors);
+        assert!(!config.absolute_times);
+        assert!(config.use_pager);
+        assert_eq!(config.status_glyphs, StatusGlyphs::Ascii);
+        assert_eq!(config.timezone, Some("+05:30".to_string()));
+        assert_eq!(config.history_size, 500);
+        assert_eq!(
+            config.prompt,
+            Some("{red}{context_short}{reset}:{namespace} > ".to_string())
+        );
+        assert_eq!(config.theme, Theme::Light);
+        assert_eq!(config.pick_threshold, Some(50));
+        assert_eq!(config.confirm, ConfirmPolicy::Dangerous);
+        assert!(config.needs_confirmation(Some("prod-east")));
+        assert!(config.needs_confirmation(Some("eu-live")));
+        assert!(!config.needs_confirmation(Some("staging")));
+        assert_eq!(
+            config.theme_colors.get("table_header"),
+            Some(&"dark_cyan".to_string())
+        );
     }
 
     #[test]
@@ -240,6 +620,97 @@ aliases:
         assert_eq!(config.read_timeout_secs, default_read_timeout());
         assert_eq!(config.connect_timeout_secs, default_connect_timeout());
         assert_eq!(config.range_separator, default_range_sep());
+        assert_eq!(config.status_glyphs, StatusGlyphs::None);
+        assert_eq!(config.history_size, default_history_size());
+        assert_eq!(config.theme, Theme::Dark);
+        assert_eq!(config.pick_threshold, None);
+        assert_eq!(config.pager, None);
+        assert_eq!(config.default_output, None);
+        assert_eq!(config.confirm, ConfirmPolicy::Always);
+        assert_eq!(config.dangerous_contexts, default_dangerous_contexts());
+        assert!(config.needs_confirmation(Some("staging")));
+    }
+
+    #[test]
+    fn test_timeouts_config() {
+        let config = ClickConfig::from_reader("read_timeout_secs: 60\n".as_bytes()).unwrap();
+        assert_eq!(config.read_timeout_secs, 60);
+        assert_eq!(config.connect_timeout_secs, default_connect_timeout());
+    }
+
+    #[test]
+    fn test_bookmark_object() {
+        let pod = KObj {
+            name: "api-7f9c".to_string(),
+            namespace: Some("payments".to_string()),
+            typ: ObjType::Pod {
+                containers: vec!["api".to_string()],
+            },
+        };
+        let crd = KObj {
+            name: "web".to_string(),
+            namespace: None,
+            typ: ObjType::Crd {
+                _type: "Certificate".to_string(),
+                group_version: "cert-manager.io/v1".to_string(),
+            },
+        };
+        let cronjob = KObj {
+            name: "backup".to_string(),
+            namespace: Some("ops".to_string()),
+            typ: ObjType::CronJob,
+        };
+        for obj in [pod, crd, cronjob] {
+            assert_eq!(BookmarkObject::from(&obj).to_kobj(), Some(obj));
+        }
+
+        let config = ClickConfig::from_reader(
+            "bookmarks:
+  api:
+    context: prod
+    namespace: payments
+    object:
+      kind: Pod
+      name: api-7f9c
+      namespace: payments
+      containers: [api]
+  canary:
+    context: prod
+    namespace: payments
+    object:
+      kind: Rollout
+      name: canary
+      namespace: payments
+  unknown:
+    context: ~
+    namespace: ~
+    object:
+      kind: Widget
+      name: w
+      namespace: ~
+"
+            .as_bytes(),
+        )
+        .unwrap();
+        let api = config.bookmarks["api"].object.as_ref().unwrap();
+        assert_eq!(api.to_kobj().unwrap().type_str(), "Pod");
+        let canary = config.bookmarks["canary"].object.as_ref().unwrap();
+        assert_eq!(canary.to_kobj().is_some(), cfg!(feature = "argorollouts"));
+        let unknown = config.bookmarks["unknown"].object.as_ref().unwrap();
+        assert_eq!(unknown.to_kobj(), None);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("prod*", "prod"));
+        assert!(glob_match("prod*", "production-us"));
+        assert!(!glob_match("prod*", "preprod"));
+        assert!(glob_match("*prod*", "preprod-1"));
+        assert!(glob_match("us-?-prod", "us-1-prod"));
+        assert!(!glob_match("us-?-prod", "us-12-prod"));
+        assert!(glob_match("a*b*c", "aXXbYYbc"));
+        assert!(!glob_match("a*b", "aXXbY"));
+        assert!(glob_match("*", ""));
     }
 
     #[test]
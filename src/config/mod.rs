@@ -16,11 +16,20 @@ mod click;
 mod kube;
 mod kubefile;
 
+pub use self::click::default_connect_timeout;
+pub use self::click::default_dangerous_contexts;
 pub use self::click::default_range_sep;
+pub use self::click::default_read_timeout;
 pub use self::click::Alias;
 pub use self::click::ClickConfig;
 pub use self::click::CompletionType;
+pub use self::click::ConfirmPolicy;
 pub use self::click::EditMode;
+pub use self::click::NotifyMethod;
+pub use self::click::OutputView;
+pub use self::click::StatusGlyphs;
+pub use self::click::Theme;
+pub use self::click::{Bookmark, BookmarkObject};
 
 #[cfg(test)]
 pub use self::kube::tests::get_test_config;
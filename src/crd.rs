@@ -15,88 +15,143 @@
 // code to deal with discovering and quering endpoints created by crds
 
 use k8s_openapi::{
-    apimachinery::pkg::apis::meta::v1::{APIGroup, APIResourceList},
-    http::{Request, StatusCode},
-    GetAPIVersionsResponse, RequestError, Response, ResponseBody, ResponseError,
+    apimachinery::pkg::apis::meta::v1::{APIGroup, APIGroupList, APIResourceList},
+    http::Request,
+    RequestError,
 };
 
 use crate::{env::Env, error::ClickError};
 
 pub fn get_api_groups(env: &mut Env) -> Result<Vec<APIGroup>, ClickError> {
     let (request, _) = k8s_openapi::get_api_versions()?;
-    match env.run_on_context::<_, GetAPIVersionsResponse>(|c| c.read(request))? {
-        GetAPIVersionsResponse::Ok(groups) => Ok(groups.groups),
-        GetAPIVersionsResponse::Other(_) => Err(ClickError::CommandError(
-            "Could not fetch api groups".to_string(),
-        )),
-    }
+    let groups: APIGroupList = env.run_on_context(|c| c.read_object(request))?;
+    Ok(groups.groups)
 }
 
-#[allow(clippy::type_complexity)] // type from k8s_openapi
-pub fn get_api_group_resources(
-    group_version: &str,
-) -> Result<
-    (
-        Request<Vec<u8>>,
-        fn(k8s_openapi::http::StatusCode) -> ResponseBody<GetAPIGroupResourcesResponse>,
-    ),
-    RequestError,
-> {
-    let url = format!("/apis/{}", group_version);
-    let request = Request::get(url);
-    let body = vec![];
-    match request.body(body) {
-        Ok(request) => Ok((request, ResponseBody::new)),
-        Err(err) => Err(RequestError::Http(err)),
+/// A kind of resource the server serves, as found by discovery
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiResource {
+    /// The api group, empty for the core group
+    pub group: String,
+    pub version: String,
+    pub kind: String,
+    /// The plural name used in urls, like deployments
+    pub name: String,
+    pub singular_name: String,
+    pub short_names: Vec<String>,
+    pub namespaced: bool,
+    pub verbs: Vec<String>,
+}
+
+impl ApiResource {
+    pub fn group_version(&self) -> String {
+        if self.group.is_empty() {
+            self.version.clone()
+        } else {
+            format!("{}/{}", self.group, self.version)
+        }
+    }
+
+    /// If this resource goes by name: its plural, singular or short name, or its kind
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name == name
+            || self.singular_name == name
+            || self.short_names.iter().any(|short| short == name)
+            || self.kind.eq_ignore_ascii_case(name)
+    }
+
+    /// The path to list these resources at, in namespace if they're namespaced and one is given
+    pub fn list_path(&self, namespace: Option<&str>) -> String {
+        let prefix = if self.group.is_empty() {
+            format!("/api/{}", self.version)
+        } else {
+            format!("/apis/{}", self.group_version())
+        };
+        match namespace {
+            Some(namespace) if self.namespaced => {
+                format!("{}/namespaces/{}/{}", prefix, namespace, self.name)
+            }
+            _ => format!("{}/{}", prefix, self.name),
+        }
     }
 }
 
-#[derive(Debug)]
-pub enum GetAPIGroupResourcesResponse {
-    Ok(APIResourceList),
-    Other(Result<Option<serde_json::Value>, serde_json::Error>),
+/// The resources in a list from discovery. Subresources, like pods/log, are left out
+fn api_resources(list: APIResourceList) -> Vec<ApiResource> {
+    let (group, version) = match list.group_version.split_once('/') {
+        Some((group, version)) => (group.to_string(), version.to_string()),
+        None => (String::new(), list.group_version.clone()),
+    };
+    list.resources
+        .into_iter()
+        .filter(|resource| !resource.name.contains('/'))
+        .map(|resource| ApiResource {
+            group: group.clone(),
+            version: version.clone(),
+            kind: resource.kind,
+            name: resource.name,
+            singular_name: resource.singular_name,
+            short_names: resource.short_names.unwrap_or_default(),
+            namespaced: resource.namespaced,
+            verbs: resource.verbs,
+        })
+        .collect()
 }
 
-impl Response for GetAPIGroupResourcesResponse {
-    fn try_from_parts(status_code: StatusCode, buf: &[u8]) -> Result<(Self, usize), ResponseError> {
-        match status_code {
-            StatusCode::OK => {
-                let result = match serde_json::from_slice(buf) {
-                    Ok(value) => value,
-                    Err(ref err) if err.is_eof() => return Err(ResponseError::NeedMoreData),
-                    Err(err) => return Err(ResponseError::Json(err)),
-                };
-                Ok((GetAPIGroupResourcesResponse::Ok(result), buf.len()))
-            }
-            _ => {
-                let (result, read) = if buf.is_empty() {
-                    (Ok(None), 0)
-                } else {
-                    match crate::serde_json::from_slice(buf) {
-                        Ok(value) => (Ok(Some(value)), buf.len()),
-                        Err(ref err) if err.is_eof() => return Err(ResponseError::NeedMoreData),
-                        Err(err) => (Err(err), 0),
-                    }
-                };
-                Ok((GetAPIGroupResourcesResponse::Other(result), read))
+/// What discovery found: the resources served, and the group versions that couldn't be read
+/// (like an aggregated api whose service is down)
+pub struct Discovery {
+    pub resources: Vec<ApiResource>,
+    pub failed: Vec<String>,
+}
+
+/// Discover every resource the server serves, in the core group and the preferred version of
+/// each other group
+pub fn discover_resources(env: &mut Env) -> Result<Discovery, ClickError> {
+    let mut discovery = Discovery {
+        resources: vec![],
+        failed: vec![],
+    };
+    let (request, _) = k8s_openapi::get_core_v1_api_resources()?;
+    match env.run_on_context(|c| c.read_object(request)) {
+        Ok(list) => discovery.resources.extend(api_resources(list)),
+        Err(ClickError::ApiStatus(..)) => discovery.failed.push("v1".to_string()),
+        Err(e) => return Err(e),
+    }
+    for group in get_api_groups(env)?.iter() {
+        let version = match group.preferred_version.as_ref() {
+            Some(pv) => Some(pv.group_version.as_str()),
+            None => group.versions.first().map(|v| v.group_version.as_str()),
+        };
+        if let Some(group_version) = version {
+            let request = get_api_group_resources(group_version)?;
+            // an aggregated api whose service is down fails, but the rest can still be found
+            match env.run_on_context(|c| c.read_object(request)) {
+                Ok(list) => discovery.resources.extend(api_resources(list)),
+                Err(ClickError::ApiStatus(..)) => discovery.failed.push(group_version.to_string()),
+                Err(e) => return Err(e),
             }
         }
     }
+    Ok(discovery)
+}
+
+pub fn get_api_group_resources(group_version: &str) -> Result<Request<Vec<u8>>, RequestError> {
+    let url = format!("/apis/{}", group_version);
+    let request = Request::get(url);
+    let body = vec![];
+    match request.body(body) {
+        Ok(request) => Ok(request),
+        Err(err) => Err(RequestError::Http(err)),
+    }
 }
 
-#[allow(clippy::type_complexity)] // type from k8s_openapi
 pub fn read_namespaced_resource(
     name: &str,
     namespace: &str,
     _type: &str,
     group_version: &str,
-) -> Result<
-    (
-        Request<Vec<u8>>,
-        fn(k8s_openapi::http::StatusCode) -> ResponseBody<ReadResourceValueResponse>,
-    ),
-    RequestError,
-> {
+) -> Result<Request<Vec<u8>>, RequestError> {
     let url = format!(
         "/apis/{}/namespaces/{}/{}/{}",
         group_version, namespace, _type, name
@@ -104,40 +159,67 @@ pub fn read_namespaced_resource(
     let request = Request::get(url);
     let body = vec![];
     match request.body(body) {
-        Ok(request) => Ok((request, ResponseBody::new)),
+        Ok(request) => Ok(request),
         Err(err) => Err(RequestError::Http(err)),
     }
 }
 
-#[derive(Debug)]
-pub enum ReadResourceValueResponse {
-    Ok(serde_json::Value),
-    Other(Result<Option<serde_json::Value>, serde_json::Error>),
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
 
-impl Response for ReadResourceValueResponse {
-    fn try_from_parts(status_code: StatusCode, buf: &[u8]) -> Result<(Self, usize), ResponseError> {
-        match status_code {
-            StatusCode::OK => {
-                let result = match serde_json::from_slice(buf) {
-                    Ok(value) => value,
-                    Err(ref err) if err.is_eof() => return Err(ResponseError::NeedMoreData),
-                    Err(err) => return Err(ResponseError::Json(err)),
-                };
-                Ok((ReadResourceValueResponse::Ok(result), buf.len()))
-            }
-            _ => {
-                let (result, read) = if buf.is_empty() {
-                    (Ok(None), 0)
-                } else {
-                    match crate::serde_json::from_slice(buf) {
-                        Ok(value) => (Ok(Some(value)), buf.len()),
-                        Err(ref err) if err.is_eof() => return Err(ResponseError::NeedMoreData),
-                        Err(err) => (Err(err), 0),
-                    }
-                };
-                Ok((ReadResourceValueResponse::Other(result), read))
-            }
-        }
+    #[test]
+    fn test_api_resources() {
+        let list: APIResourceList = serde_json::from_value(json!({
+            "groupVersion": "apps/v1",
+            "resources": [
+                {
+                    "name": "deployments",
+                    "singularName": "deployment",
+                    "shortNames": ["deploy"],
+                    "kind": "Deployment",
+                    "namespaced": true,
+                    "verbs": ["get", "list", "watch"],
+                },
+                {
+                    "name": "deployments/scale",
+                    "singularName": "",
+                    "kind": "Scale",
+                    "namespaced": true,
+                    "verbs": ["get", "patch"],
+                },
+            ],
+        }))
+        .unwrap();
+        let resources = api_resources(list);
+        assert_eq!(resources.len(), 1);
+        let deployments = &resources[0];
+        assert_eq!(deployments.group_version(), "apps/v1");
+        assert!(deployments.is_named("deploy"));
+        assert!(deployments.is_named("deployment"));
+        assert!(deployments.is_named("Deployment"));
+        assert!(!deployments.is_named("scale"));
+        assert_eq!(
+            deployments.list_path(Some("prod")),
+            "/apis/apps/v1/namespaces/prod/deployments"
+        );
+        assert_eq!(deployments.list_path(None), "/apis/apps/v1/deployments");
+
+        let list: APIResourceList = serde_json::from_value(json!({
+            "groupVersion": "v1",
+            "resources": [{
+                "name": "nodes",
+                "singularName": "node",
+                "kind": "Node",
+                "namespaced": false,
+                "verbs": ["get", "list"],
+            }],
+        }))
+        .unwrap();
+        let nodes = &api_resources(list)[0];
+        assert_eq!(nodes.group, "");
+        assert_eq!(nodes.group_version(), "v1");
+        assert_eq!(nodes.list_path(Some("prod")), "/api/v1/nodes");
     }
 }
@@ -27,19 +27,10 @@ pub fn crd_describe(
     writer: &mut ClickWriter,
 ) -> Result<(), ClickError> {
     //let ns = self.namespace.as_ref().unwrap();
-    let (request, _) = crate::crd::read_namespaced_resource(name, namespace, _type, group_version)?;
-    match env
-        .run_on_context(|c| c.read::<crate::crd::ReadResourceValueResponse>(request))
-        .unwrap()
-    {
-        crate::crd::ReadResourceValueResponse::Ok(t) => {
-            if !super::maybe_full_describe_output(matches, &t, writer) {
-                clickwriteln!(writer, "{} {}", _type, super::NOTSUPPORTED);
-            }
-        }
-        crate::crd::ReadResourceValueResponse::Other(e) => {
-            clickwriteln!(writer, "Error getting response: {:?}", e);
-        }
-    };
+    let request = crate::crd::read_namespaced_resource(name, namespace, _type, group_version)?;
+    let value = env.run_on_context(|c| c.get_value(request))?;
+    if !super::maybe_full_describe_output(matches, &value, env, writer) {
+        clickwriteln!(writer, "{} {}", _type, super::NOTSUPPORTED);
+    }
     Ok(())
 }
@@ -0,0 +1,197 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// the replicasets of a deployment need another request, so handle them here
+
+use crate::{env::Env, error::ClickError};
+
+use k8s_openapi::{
+    api::apps::v1 as api_apps, apimachinery::pkg::apis::meta::v1::ObjectMeta, List, ListOptional,
+};
+
+/// The annotation the deployment controller keeps the revision of deployments and their
+/// replicasets in. The new replicaset has the same revision as its deployment
+const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
+
+fn revision(meta: &ObjectMeta) -> Option<&str> {
+    meta.annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(REVISION_ANNOTATION))
+        .map(String::as_str)
+}
+
+/// A replicaset, like: web-5d4f8 (revision 3): 2/3 ready, app:1.2
+fn replicaset_str(rs: &api_apps::ReplicaSet) -> String {
+    let desired = rs.spec.as_ref().and_then(|spec| spec.replicas).unwrap_or(1);
+    let ready = rs
+        .status
+        .as_ref()
+        .and_then(|status| status.ready_replicas)
+        .unwrap_or(0);
+    let images: Vec<&str> = rs
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.template.as_ref())
+        .and_then(|template| template.spec.as_ref())
+        .map(|spec| {
+            spec.containers
+                .iter()
+                .filter_map(|container| container.image.as_deref())
+                .collect()
+        })
+        .unwrap_or_default();
+    format!(
+        "{} (revision {}): {}/{} ready, {}",
+        rs.metadata.name.as_deref().unwrap_or("<No Name>"),
+        revision(&rs.metadata).unwrap_or("<unknown>"),
+        ready,
+        desired,
+        images.join(", ")
+    )
+}
+
+/// Split a deployment's replicasets into the new one, with the deployment's revision, and the old
+/// ones, newest first. Unless verbose, only old replicasets that still have pods are included
+fn split_replicasets<'a>(
+    deployment_revision: Option<&str>,
+    replicasets: &'a [api_apps::ReplicaSet],
+    verbose: bool,
+) -> (
+    Option<&'a api_apps::ReplicaSet>,
+    Vec<&'a api_apps::ReplicaSet>,
+) {
+    let mut new = None;
+    let mut old = vec![];
+    for rs in replicasets.iter() {
+        if deployment_revision.is_some() && revision(&rs.metadata) == deployment_revision {
+            new = Some(rs);
+            continue;
+        }
+        let pods = rs
+            .status
+            .as_ref()
+            .map(|status| status.replicas)
+            .unwrap_or(0);
+        let wanted = rs.spec.as_ref().and_then(|spec| spec.replicas).unwrap_or(0);
+        if verbose || pods > 0 || wanted > 0 {
+            old.push(rs);
+        }
+    }
+    let revision_num = |rs: &api_apps::ReplicaSet| {
+        revision(&rs.metadata)
+            .and_then(|rev| rev.parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+    old.sort_by_key(|rs| std::cmp::Reverse(revision_num(rs)));
+    (new, old)
+}
+
+/// Add a deployment's new replicaset, that it's rolling out to, and its old ones to a deployment
+/// description
+pub fn describe_deployment_replicasets(
+    deployment: &api_apps::Deployment,
+    env: &Env,
+    verbose: bool,
+    table: &mut comfy_table::Table,
+) -> Result<(), ClickError> {
+    let namespace = deployment.metadata.namespace.as_deref().unwrap_or_default();
+    let selector = deployment
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.selector.match_labels.as_ref())
+        .map(|labels| {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, val)| format!("{}={}", key, val))
+                .collect();
+            labels.join(",")
+        });
+    let (request, _) = api_apps::ReplicaSet::list_namespaced_replica_set(
+        namespace,
+        ListOptional {
+            label_selector: selector.as_deref(),
+            ..Default::default()
+        },
+    )?;
+    let list: List<api_apps::ReplicaSet> = env.run_on_context(|c| c.execute_list(request))?;
+    // the selector might match replicasets of other deployments too
+    let owned: Vec<api_apps::ReplicaSet> = list
+        .items
+        .into_iter()
+        .filter(|rs| {
+            rs.metadata.owner_references.iter().flatten().any(|owner| {
+                Some(&owner.uid) == deployment.metadata.uid.as_ref() && owner.kind == "Deployment"
+            })
+        })
+        .collect();
+    let (new, old) = split_replicasets(revision(&deployment.metadata), &owned, verbose);
+    table.add_row(vec![
+        "New ReplicaSet:".to_string(),
+        new.map(replicaset_str)
+            .unwrap_or_else(|| "<none>".to_string()),
+    ]);
+    let old_str = if old.is_empty() {
+        "<none>".to_string()
+    } else {
+        let lines: Vec<String> = old.into_iter().map(replicaset_str).collect();
+        lines.join("\n")
+    };
+    table.add_row(vec!["Old ReplicaSets:".to_string(), old_str]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rs(name: &str, revision: &str, replicas: i32, ready: i32) -> api_apps::ReplicaSet {
+        serde_json::from_value(json!({
+            "metadata": {
+                "name": name,
+                "annotations": { REVISION_ANNOTATION: revision },
+            },
+            "spec": {
+                "replicas": replicas,
+                "selector": {},
+                "template": { "spec": { "containers": [{ "name": "app", "image": format!("app:{}", revision) }] } },
+            },
+            "status": { "replicas": replicas, "readyReplicas": ready },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_split_replicasets() {
+        let replicasets = vec![
+            rs("web-1", "1", 0, 0),
+            rs("web-3", "3", 2, 1),
+            rs("web-2", "2", 2, 2),
+        ];
+        let (new, old) = split_replicasets(Some("3"), &replicasets, false);
+        assert_eq!(
+            new.map(replicaset_str).as_deref(),
+            Some("web-3 (revision 3): 1/2 ready, app:3")
+        );
+        let old: Vec<String> = old.into_iter().map(replicaset_str).collect();
+        assert_eq!(old, vec!["web-2 (revision 2): 2/2 ready, app:2"]);
+
+        let (_, old) = split_replicasets(Some("3"), &replicasets, true);
+        let old: Vec<&str> = old
+            .iter()
+            .filter_map(|rs| rs.metadata.name.as_deref())
+            .collect();
+        assert_eq!(old, vec!["web-2", "web-1"]);
+    }
+}
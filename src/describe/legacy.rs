@@ -15,14 +15,18 @@
 //!  Utility functions for the Describe command, used to output
 //!  information for supported kubernetes object types
 
+use crate::env::Env;
 use crate::error::ClickError;
+use crate::table::{format_quantity, format_raw_quantity, format_timestamp, QuantityKind};
+
+use super::DESCRIBE_TIME_FORMAT;
 use crate::values::{val_str, val_str_opt, val_u64};
 
-use chrono::offset::Local;
 use chrono::offset::Utc;
 use chrono::DateTime;
 use crossterm::style::Stylize;
 use k8s_openapi::api::{apps::v1 as api_apps, core::v1 as api};
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 use serde_json::Value;
 
 use std::borrow::Cow;
@@ -39,7 +43,6 @@ pub enum DescItem<'a> {
     },
     KeyValStr {
         parent: &'a str,
-        secret_vals: bool,
     },
     MetadataValStr {
         path: &'a str,
@@ -51,65 +54,67 @@ pub enum DescItem<'a> {
         func: &'a (dyn Fn(&Value) -> Cow<str>),
         default: &'a str,
     },
+    /// only shown by describe -v
+    Verbose(Box<DescItem<'a>>),
 }
 
 /// get key/vals out of a value
-/// If secret_vals is true, the actual vals are hidden and we show only the size of the value
-fn keyval_str<'a>(v: &'a Value, parent: &str, secret_vals: bool) -> Cow<'a, str> {
-    match v.pointer(parent) {
-        Some(p) => {
-            if let Some(keyvals) = p.as_object() {
-                let iter = keyvals.into_iter().map(|(key, val)| {
-                    let is_service_token = if secret_vals && key == "token" {
-                        let typ = v.pointer("/type").and_then(|t| t.as_str()).unwrap_or("");
-                        typ == "kubernetes.io/service-account-token"
-                    } else {
-                        false
-                    };
-
-                    let computed_val: Cow<'a, str> = if is_service_token {
-                        match ::base64::decode(val.as_str().unwrap()) {
-                            Ok(dec) => str::from_utf8(&dec[..])
-                                .map(|s| s.to_string())
-                                .unwrap_or_else(|_e| "Invalid utf-8 data".to_string())
-                                .into(),
-                            Err(_) => "Could not decode secret".into(),
-                        }
-                    } else if secret_vals {
-                        match ::base64::decode(val.as_str().unwrap()) {
-                            Ok(dec) => format!("{} bytes", dec.len()).into(),
-                            Err(_) => "Could not decode secret".into(),
-                        }
-                    } else {
-                        val.as_str().unwrap_or("<unknown>").into()
-                    };
-
-                    (key.as_str(), computed_val)
-                });
-                crate::command::keyval_string(iter, Some(&super::DESCRIBE_SKIP_KEYS)).into()
-            } else {
-                "<none>".into()
-            }
+fn keyval_str<'a>(v: &'a Value, parent: &str) -> Cow<'a, str> {
+    match v.pointer(parent).and_then(Value::as_object) {
+        Some(keyvals) => {
+            let iter = keyvals
+                .into_iter()
+                .map(|(key, val)| (key.as_str(), val.as_str().unwrap_or("<unknown>")));
+            crate::command::keyval_string(iter, Some(&super::DESCRIBE_SKIP_KEYS)).into()
         }
         None => "<none>".into(),
     }
 }
 
+/// The keys of a secret's data, with the size of each value, or with the decoded values if reveal
+/// is set. Values that aren't text are never shown
+fn secret_data_str(data: Option<&Value>, reveal: bool) -> String {
+    let keyvals = match data.and_then(Value::as_object) {
+        Some(keyvals) => keyvals,
+        None => return "<none>".to_string(),
+    };
+    let iter = keyvals.iter().map(|(key, val)| {
+        let decoded = match base64::decode(val.as_str().unwrap_or_default()) {
+            Ok(decoded) => decoded,
+            Err(_) => return (key.as_str(), "Could not decode secret".to_string()),
+        };
+        let shown = match str::from_utf8(&decoded) {
+            Ok(text) if reveal => text.to_string(),
+            Err(_) if reveal => format!("<{} bytes of binary data>", decoded.len()),
+            _ => format!("{} bytes", decoded.len()),
+        };
+        (key.as_str(), shown)
+    });
+    crate::command::keyval_string(iter, None)
+}
+
 /// Generic describe function
 /// TODO: Document
-pub fn describe_object<'a, I>(v: &Value, fields: I, table: &mut comfy_table::Table)
-where
+pub fn describe_object<'a, I>(
+    v: &Value,
+    fields: I,
+    env: &Env,
+    verbose: bool,
+    table: &mut comfy_table::Table,
+) where
     I: Iterator<Item = (&'a str, DescItem<'a>)>,
 {
     let metadata = v.get("metadata").unwrap();
     for (title, item) in fields {
+        let item = match item {
+            DescItem::Verbose(item) if verbose => *item,
+            DescItem::Verbose(_) => continue,
+            item => item,
+        };
         let val = match item {
             DescItem::ValStr { path, default } => val_str(path, v, default),
             DescItem::Valu64 { path, default } => val_u64(path, v, default).to_string().into(),
-            DescItem::KeyValStr {
-                parent,
-                secret_vals,
-            } => keyval_str(v, parent, secret_vals),
+            DescItem::KeyValStr { parent } => keyval_str(v, parent),
             DescItem::MetadataValStr { path, default } => val_str(path, metadata, default),
             DescItem::ObjectCreated => {
                 let created: DateTime<Utc> = DateTime::from_str(&val_str(
@@ -118,7 +123,12 @@ where
                     "<No CreationTime>",
                 ))
                 .unwrap();
-                format!("{} ({})", created, created.with_timezone(&Local)).into()
+                format!(
+                    "{} ({})",
+                    created,
+                    format_timestamp(created, DESCRIBE_TIME_FORMAT, env)
+                )
+                .into()
             }
             DescItem::CustomFunc {
                 ref path,
@@ -134,6 +144,7 @@ where
                     None => default.into(),
                 }
             }
+            DescItem::Verbose(_) => unreachable!(), // unwrapped above
         };
         table.add_row(vec![title, val.as_ref()]);
     }
@@ -142,6 +153,8 @@ where
 /// Utility function for describe to print out value
 pub fn describe_format_pod(
     pod: &api::Pod,
+    env: &Env,
+    verbose: bool,
     table: &mut comfy_table::Table,
 ) -> Result<(), ClickError> {
     let v = serde_json::value::to_value(pod).unwrap();
@@ -168,12 +181,21 @@ pub fn describe_format_pod(
             },
         ),
         (
-            "IP:",
-            DescItem::ValStr {
-                path: "/status/podIP",
+            "IPs:",
+            DescItem::CustomFunc {
+                path: Some("/status"),
+                func: &pod_ips_str,
                 default: "<No PodIP>",
             },
         ),
+        (
+            "Owner:",
+            DescItem::CustomFunc {
+                path: Some("/metadata/ownerReferences"),
+                func: &owners_str,
+                default: "<none>",
+            },
+        ),
         ("Created at:", DescItem::ObjectCreated),
         (
             "Status:",
@@ -183,33 +205,256 @@ pub fn describe_format_pod(
                 default: "<No Phase>",
             },
         ),
+        (
+            "Containers:",
+            DescItem::CustomFunc {
+                path: Some("/status"),
+                func: &get_container_statuses_str,
+                default: "<none>",
+            },
+        ),
         (
             "Labels:",
             DescItem::KeyValStr {
                 parent: "/metadata/labels",
-                secret_vals: false,
             },
         ),
         (
             "Annotations:",
-            DescItem::KeyValStr {
+            DescItem::Verbose(Box::new(DescItem::KeyValStr {
                 parent: "/metadata/annotations",
-                secret_vals: false,
+            })),
+        ),
+        (
+            "Probes:",
+            DescItem::CustomFunc {
+                path: Some("/spec"),
+                func: &probes_str,
+                default: "<none>",
             },
         ),
+        (
+            "Container Specs:",
+            DescItem::Verbose(Box::new(DescItem::CustomFunc {
+                path: Some("/spec"),
+                func: &container_specs_str,
+                default: "<none>",
+            })),
+        ),
         (
             "Volumes:",
-            DescItem::CustomFunc {
+            DescItem::Verbose(Box::new(DescItem::CustomFunc {
                 path: Some("/spec/volumes"),
                 func: &get_volume_str,
                 default: "<No Volumes>",
-            },
+            })),
+        ),
+        (
+            "Conditions:",
+            DescItem::Verbose(Box::new(DescItem::CustomFunc {
+                path: Some("/status/conditions"),
+                func: &get_conditions_str,
+                default: "<none>",
+            })),
+        ),
+        (
+            "Tolerations:",
+            DescItem::Verbose(Box::new(DescItem::CustomFunc {
+                path: Some("/spec/tolerations"),
+                func: &get_tolerations_str,
+                default: "<none>",
+            })),
         ),
     ];
-    describe_object(&v, fields.into_iter(), table);
+    describe_object(&v, fields.into_iter(), env, verbose, table);
     Ok(())
 }
 
+/// The pod's IPs (both of them in a dual-stack cluster), from a pod status
+fn pod_ips_str(v: &Value) -> Cow<'_, str> {
+    let ips: Vec<&str> = v
+        .pointer("/podIPs")
+        .and_then(Value::as_array)
+        .map(|ips| {
+            ips.iter()
+                .filter_map(|ip| ip.pointer("/ip").and_then(Value::as_str))
+                .collect()
+        })
+        .unwrap_or_default();
+    if !ips.is_empty() {
+        return ips.join(", ").into();
+    }
+    val_str("/podIP", v, "<No PodIP>")
+}
+
+/// Get the Kind/name of each owner in an ownerReferences array
+fn owners_str(v: &Value) -> Cow<'_, str> {
+    let owners: Vec<String> = v
+        .as_array()
+        .map(|owners| {
+            owners
+                .iter()
+                .map(|owner| {
+                    format!(
+                        "{}/{}",
+                        val_str("/kind", owner, "<No Kind>"),
+                        val_str("/name", owner, "<No Name>")
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if owners.is_empty() {
+        return "<none>".into();
+    }
+    owners.join(", ").into()
+}
+
+/// Format a map of resource name to quantity on one line, like cpu=100m, memory=128Mi
+fn resource_list_str(v: &Value) -> Option<String> {
+    let resources = v.as_object().filter(|resources| !resources.is_empty())?;
+    let list: Vec<String> = resources
+        .iter()
+        .map(|(resource, quant)| format!("{}={}", resource, quant.as_str().unwrap_or("0")))
+        .collect();
+    Some(list.join(", "))
+}
+
+/// A port in a probe, which can be a number or the name of a container port
+fn probe_port_str(v: &Value) -> String {
+    match v.get("port") {
+        Some(Value::Number(port)) => port.to_string(),
+        Some(Value::String(port)) => port.clone(),
+        _ => "<No Port>".to_string(),
+    }
+}
+
+/// Describe a probe like kubectl does: what it checks, then its timing, like
+/// http-get http://:8080/healthz delay=0s timeout=1s period=10s #success=1 #failure=3
+fn probe_str(probe: &Value) -> String {
+    let action = if let Some(http) = probe.get("httpGet") {
+        format!(
+            "http-get {}://{}:{}{}",
+            val_str("/scheme", http, "HTTP").to_lowercase(),
+            val_str("/host", http, ""),
+            probe_port_str(http),
+            val_str("/path", http, "")
+        )
+    } else if let Some(tcp) = probe.get("tcpSocket") {
+        format!(
+            "tcp-socket {}:{}",
+            val_str("/host", tcp, ""),
+            probe_port_str(tcp)
+        )
+    } else if let Some(grpc) = probe.get("grpc") {
+        format!("grpc {}", probe_port_str(grpc))
+    } else if let Some(command) = probe.pointer("/exec/command").and_then(Value::as_array) {
+        let command: Vec<&str> = command.iter().filter_map(Value::as_str).collect();
+        format!("exec [{}]", command.join(" "))
+    } else {
+        "<unknown>".to_string()
+    };
+    let field = |name: &str, default: u64| val_u64(&format!("/{}", name), probe, default);
+    format!(
+        "{} delay={}s timeout={}s period={}s #success={} #failure={}",
+        action,
+        field("initialDelaySeconds", 0),
+        field("timeoutSeconds", 1),
+        field("periodSeconds", 10),
+        field("successThreshold", 1),
+        field("failureThreshold", 3)
+    )
+}
+
+/// Each init container and container in a pod spec, and whether it's an init container
+fn spec_containers(v: &Value) -> impl Iterator<Item = (&Value, bool)> {
+    let containers = |path: &str| {
+        v.pointer(path)
+            .and_then(Value::as_array)
+            .map(|containers| containers.iter())
+            .unwrap_or_default()
+    };
+    let init_containers = containers("/initContainers").map(|container| (container, true));
+    init_containers.chain(containers("/containers").map(|container| (container, false)))
+}
+
+fn container_title(container: &Value, init: bool) -> String {
+    format!(
+        "{}{}:",
+        val_str("/name", container, "<No Name>"),
+        if init { " (init)" } else { "" }
+    )
+}
+
+/// Get the liveness, readiness and startup probes of each container in a pod spec that has any
+fn probes_str(v: &Value) -> Cow<'_, str> {
+    let probes: Vec<String> = spec_containers(v)
+        .filter_map(|(container, init)| {
+            let mut lines = vec![container_title(container, init)];
+            for (title, field) in [
+                ("Liveness", "livenessProbe"),
+                ("Readiness", "readinessProbe"),
+                ("Startup", "startupProbe"),
+            ] {
+                if let Some(probe) = container.get(field) {
+                    lines.push(format!("  {}: {}", title, probe_str(probe)));
+                }
+            }
+            (lines.len() > 1).then(|| lines.join("\n"))
+        })
+        .collect();
+    if probes.is_empty() {
+        return "<none>".into();
+    }
+    probes.join("\n").into()
+}
+
+/// Get the image, resources and volume mounts of each init container and container in a pod spec
+fn container_specs_str(v: &Value) -> Cow<'_, str> {
+    let specs: Vec<String> = spec_containers(v)
+        .map(|(container, init)| {
+            let mut lines = vec![
+                container_title(container, init),
+                format!("  Image: {}", val_str("/image", container, "<No Image>")),
+            ];
+            for (title, path) in [
+                ("Requests", "/resources/requests"),
+                ("Limits", "/resources/limits"),
+            ] {
+                if let Some(list) = container.pointer(path).and_then(resource_list_str) {
+                    lines.push(format!("  {}: {}", title, list));
+                }
+            }
+            let mounts = container
+                .pointer("/volumeMounts")
+                .and_then(Value::as_array)
+                .filter(|mounts| !mounts.is_empty());
+            if let Some(mounts) = mounts {
+                lines.push("  Mounts:".to_string());
+                for mount in mounts.iter() {
+                    let mut line = format!(
+                        "    {} from {}",
+                        val_str("/mountPath", mount, "<No Path>"),
+                        val_str("/name", mount, "<No Name>")
+                    );
+                    if let Some(sub_path) = val_str_opt("/subPath", mount) {
+                        line.push_str(&format!(" [{}]", sub_path));
+                    }
+                    if mount.pointer("/readOnly").and_then(Value::as_bool) == Some(true) {
+                        line.push_str(" (ro)");
+                    }
+                    lines.push(line);
+                }
+            }
+            lines.join("\n")
+        })
+        .collect();
+    if specs.is_empty() {
+        return "<none>".into();
+    }
+    specs.join("\n").into()
+}
+
 /// Check if value has read only set
 fn get_read_only(v: &Value) -> &'static str {
     if let Some(read_only) = v.get("readOnly") {
@@ -365,9 +610,98 @@ fn pod_phase(v: &Value) -> Cow<str> {
     }
 }
 
+/// Describe a container state (the state or lastState of a container status), like "Waiting:
+/// CrashLoopBackOff" or "Terminated: OOMKilled, exit code 137". None if the state is empty
+fn container_state_str(state: &Value) -> Option<String> {
+    if let Some(running) = state.get("running") {
+        let mut buf = "Running".green().to_string();
+        if let Some(started) = val_str_opt("/startedAt", running) {
+            buf.push_str(&format!(" since {}", started));
+        }
+        Some(buf)
+    } else if let Some(terminated) = state.get("terminated") {
+        let code = terminated
+            .pointer("/exitCode")
+            .and_then(Value::as_i64)
+            .unwrap_or(0);
+        let title = if code == 0 {
+            "Terminated".green()
+        } else {
+            "Terminated".red()
+        };
+        let mut buf = format!(
+            "{}: {}, exit code {}",
+            title,
+            val_str("/reason", terminated, "<No Reason>"),
+            code
+        );
+        if let Some(finished) = val_str_opt("/finishedAt", terminated) {
+            buf.push_str(&format!(" at {}", finished));
+        }
+        Some(buf)
+    } else if let Some(waiting) = state.get("waiting") {
+        let mut buf = format!(
+            "{}: {}",
+            "Waiting".yellow(),
+            val_str("/reason", waiting, "<No Reason>")
+        );
+        if let Some(message) = val_str_opt("/message", waiting) {
+            buf.push_str(&format!(" ({})", message));
+        }
+        Some(buf)
+    } else {
+        None
+    }
+}
+
+/// Get the ready state, restarts, current state and last termination of each init container and
+/// container in a pod status. The phase of a pod can be Running while its containers are crashing
+/// or can't pull their image, or Pending while an init container hangs, this shows why
+fn get_container_statuses_str(v: &Value) -> Cow<'_, str> {
+    let statuses = |path: &str| {
+        v.pointer(path)
+            .and_then(Value::as_array)
+            .map(|statuses| statuses.iter())
+            .unwrap_or_default()
+    };
+    let init_statuses = statuses("/initContainerStatuses").map(|status| (status, true));
+    let lines: Vec<String> = init_statuses
+        .chain(statuses("/containerStatuses").map(|status| (status, false)))
+        .map(|(status, init)| {
+            let ready = if status.pointer("/ready").and_then(Value::as_bool) == Some(true) {
+                "ready".green()
+            } else {
+                "not ready".red()
+            };
+            let restarts = val_u64("/restartCount", status, 0);
+            let mut lines = vec![format!(
+                "{}{}: {}, {} restart{}",
+                val_str("/name", status, "<No Name>"),
+                if init { " (init)" } else { "" },
+                ready,
+                restarts,
+                if restarts == 1 { "" } else { "s" }
+            )];
+            if let Some(state) = status.get("state").and_then(container_state_str) {
+                lines.push(format!("  {}", state));
+            }
+            if let Some(last) = status.get("lastState").and_then(container_state_str) {
+                lines.push(format!("  Last: {}", last));
+            }
+            lines.join("\n")
+        })
+        .collect();
+    if lines.is_empty() {
+        return "<none>".into();
+    }
+    lines.join("\n").into()
+}
+
 /// Utility function for describe to print out value
 pub fn describe_format_node(
     node: &api::Node,
+    env: &Env,
+    verbose: bool,
     table: &mut comfy_table::Table,
 ) -> Result<(), ClickError> {
     let v = serde_json::value::to_value(&node).unwrap();
@@ -383,15 +717,13 @@ pub fn describe_format_node(
             "Labels:",
             DescItem::KeyValStr {
                 parent: "/metadata/labels",
-                secret_vals: false,
             },
         ),
         (
             "Annotations:",
-            DescItem::KeyValStr {
+            DescItem::Verbose(Box::new(DescItem::KeyValStr {
                 parent: "/metadata/annotations",
-                secret_vals: false,
-            },
+            })),
         ),
         ("Created at:", DescItem::ObjectCreated),
         (
@@ -409,18 +741,130 @@ pub fn describe_format_node(
                 default: "<N/A>",
             },
         ),
+        (
+            "Versions:",
+            DescItem::CustomFunc {
+                path: Some("/status/nodeInfo"),
+                func: &node_versions_str,
+                default: "<none>",
+            },
+        ),
+        (
+            "Capacity:",
+            DescItem::Verbose(Box::new(DescItem::CustomFunc {
+                path: Some("/status/capacity"),
+                func: &resources_str,
+                default: "<none>",
+            })),
+        ),
+        (
+            "Allocatable:",
+            DescItem::CustomFunc {
+                path: Some("/status/allocatable"),
+                func: &resources_str,
+                default: "<none>",
+            },
+        ),
         (
             "System Info:",
-            DescItem::KeyValStr {
+            DescItem::Verbose(Box::new(DescItem::KeyValStr {
                 parent: "/status/nodeInfo",
-                secret_vals: false,
-            },
+            })),
+        ),
+        (
+            "Conditions:",
+            DescItem::Verbose(Box::new(DescItem::CustomFunc {
+                path: Some("/status/conditions"),
+                func: &get_conditions_str,
+                default: "<none>",
+            })),
+        ),
+        (
+            "Taints:",
+            DescItem::Verbose(Box::new(DescItem::CustomFunc {
+                path: Some("/spec/taints"),
+                func: &get_taints_str,
+                default: "<none>",
+            })),
+        ),
+        (
+            "Images:",
+            DescItem::Verbose(Box::new(DescItem::CustomFunc {
+                path: Some("/status/images"),
+                func: &node_images_str,
+                default: "<none>",
+            })),
         ),
     ];
-    describe_object(&v, fields.into_iter(), table);
+    describe_object(&v, fields.into_iter(), env, verbose, table);
     Ok(())
 }
 
+/// The kubelet, OS, kernel and container runtime versions from a node's nodeInfo
+fn node_versions_str(v: &Value) -> Cow<'_, str> {
+    format!(
+        "kubelet {}, {} ({}/{}), kernel {}, {}",
+        val_str("/kubeletVersion", v, "<unknown>"),
+        val_str("/osImage", v, "<unknown os>"),
+        val_str("/operatingSystem", v, "<unknown>"),
+        val_str("/architecture", v, "<unknown>"),
+        val_str("/kernelVersion", v, "<unknown>"),
+        val_str("/containerRuntimeVersion", v, "<unknown runtime>")
+    )
+    .into()
+}
+
+/// The images a node has pulled, with their sizes. Images are listed by digest and by tag, the tag
+/// is more useful so it's preferred
+fn node_images_str(v: &Value) -> Cow<'_, str> {
+    let images = match v.as_array() {
+        Some(images) if !images.is_empty() => images,
+        _ => return "<none>".into(),
+    };
+    let lines: Vec<String> = images
+        .iter()
+        .map(|image| {
+            let names: Vec<&str> = image
+                .pointer("/names")
+                .and_then(Value::as_array)
+                .map(|names| names.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            let name = names
+                .iter()
+                .find(|name| !name.contains('@'))
+                .or_else(|| names.first())
+                .unwrap_or(&"<No Name>");
+            let size = image
+                .pointer("/sizeBytes")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0);
+            format!(
+                "{} ({})",
+                name,
+                format_raw_quantity(size, QuantityKind::Bytes)
+            )
+        })
+        .collect();
+    lines.join("\n").into()
+}
+
+// format a map of resource name to quantity, like a node's capacity
+fn resources_str(v: &Value) -> Cow<'_, str> {
+    match v.as_object() {
+        Some(resources) => {
+            let iter = resources.iter().map(|(resource, quant)| {
+                let quant = Quantity(quant.as_str().unwrap_or("0").to_string());
+                (
+                    resource,
+                    format_quantity(&quant, QuantityKind::for_resource(resource)),
+                )
+            });
+            crate::command::keyval_string(iter, None).into()
+        }
+        None => "<none>".into(),
+    }
+}
+
 fn node_access_url(v: &Value) -> Cow<str> {
     match val_str_opt("/spec/providerID", v) {
         Some(provider) => {
@@ -457,9 +901,13 @@ fn node_access_url(v: &Value) -> Cow<str> {
     }
 }
 
-/// Utility function to describe a secret
+/// Utility function to describe a secret. Only the sizes of its values are shown, unless reveal is
+/// set
 pub fn describe_format_secret(
     secret: &api::Secret,
+    env: &Env,
+    verbose: bool,
+    reveal: bool,
     table: &mut comfy_table::Table,
 ) -> Result<(), ClickError> {
     let v = serde_json::value::to_value(&secret).unwrap();
@@ -482,15 +930,13 @@ pub fn describe_format_secret(
             "Labels:",
             DescItem::KeyValStr {
                 parent: "/metadata/labels",
-                secret_vals: false,
             },
         ),
         (
             "Annotations:",
-            DescItem::KeyValStr {
+            DescItem::Verbose(Box::new(DescItem::KeyValStr {
                 parent: "/metadata/annotations",
-                secret_vals: false,
-            },
+            })),
         ),
         (
             "Type:",
@@ -499,18 +945,96 @@ pub fn describe_format_secret(
                 default: "<No Type>",
             },
         ),
-        (
-            "Data:",
-            DescItem::KeyValStr {
-                parent: "/data",
-                secret_vals: true,
-            },
-        ),
     ];
-    describe_object(&v, fields.into_iter(), table);
+    describe_object(&v, fields.into_iter(), env, verbose, table);
+    table.add_row(vec![
+        "Data:".to_string(),
+        secret_data_str(v.pointer("/data"), reveal),
+    ]);
     Ok(())
 }
 
+/// Get the type, status, reason and message of each condition in a condition array
+fn get_conditions_str(v: &Value) -> Cow<'_, str> {
+    let conditions = match v.as_array() {
+        Some(conditions) if !conditions.is_empty() => conditions,
+        _ => return "<none>".into(),
+    };
+    let lines: Vec<String> = conditions
+        .iter()
+        .map(|condition| {
+            let mut line = format!(
+                "{}: {}",
+                val_str("/type", condition, "<No Type>"),
+                val_str("/status", condition, "<No Status>")
+            );
+            if let Some(since) = val_str_opt("/lastTransitionTime", condition) {
+                line.push_str(&format!(" since {}", since));
+            }
+            if let Some(reason) = val_str_opt("/reason", condition) {
+                line.push_str(&format!(" ({})", reason));
+            }
+            if let Some(message) = val_str_opt("/message", condition) {
+                line.push_str(&format!(" {}", message));
+            }
+            line
+        })
+        .collect();
+    lines.join("\n").into()
+}
+
+// format a taint or toleration like key=value:Effect
+fn taint_str(v: &Value) -> String {
+    let mut buf = String::new();
+    buf.push_str(&val_str_opt("/key", v).unwrap_or_default());
+    if let Some(value) = val_str_opt("/value", v) {
+        buf.push('=');
+        buf.push_str(&value);
+    }
+    if let Some(effect) = val_str_opt("/effect", v) {
+        buf.push(':');
+        buf.push_str(&effect);
+    }
+    buf
+}
+
+/// Get the taints out of a taint array
+fn get_taints_str(v: &Value) -> Cow<'_, str> {
+    match v.as_array() {
+        Some(taints) if !taints.is_empty() => {
+            let taints: Vec<String> = taints.iter().map(taint_str).collect();
+            taints.join("\n").into()
+        }
+        _ => "<none>".into(),
+    }
+}
+
+/// Get the tolerations out of a toleration array
+fn get_tolerations_str(v: &Value) -> Cow<'_, str> {
+    let tolerations = match v.as_array() {
+        Some(tolerations) if !tolerations.is_empty() => tolerations,
+        _ => return "<none>".into(),
+    };
+    let lines: Vec<String> = tolerations
+        .iter()
+        .map(|toleration| {
+            let mut line = taint_str(toleration);
+            if line.is_empty() || line.starts_with(':') {
+                // no key with the Exists operator tolerates everything
+                line.insert_str(0, "<all>");
+            }
+            if let Some(secs) = toleration
+                .pointer("/tolerationSeconds")
+                .and_then(|s| s.as_i64())
+            {
+                line.push_str(&format!(" for {}s", secs));
+            }
+            line
+        })
+        .collect();
+    lines.join("\n").into()
+}
+
 /// Get container info out of container array
 fn get_container_str(v: &Value) -> Cow<str> {
     let mut buf = String::new();
@@ -531,21 +1055,11 @@ fn get_container_str(v: &Value) -> Cow<str> {
     buf.into()
 }
 
-/// Get status messages out of 'conditions' array
-fn get_message_str(v: &Value) -> Cow<str> {
-    let mut buf = String::new();
-    if let Some(condition_array) = v.as_array() {
-        for condition in condition_array.iter() {
-            let msg = val_str("/message", condition, "<No Message>");
-            buf.push_str(format!("  Message: {}\n", msg).as_str());
-        }
-    }
-    buf.into()
-}
-
 /// Utility function to describe a deployment
 pub fn describe_format_deployment(
     deployment: &api_apps::Deployment,
+    env: &Env,
+    verbose: bool,
     table: &mut comfy_table::Table,
 ) -> Result<(), ClickError> {
     let v = serde_json::value::to_value(&deployment).unwrap();
@@ -576,35 +1090,30 @@ pub fn describe_format_deployment(
             "Labels:\t",
             DescItem::KeyValStr {
                 parent: "/metadata/labels",
-                secret_vals: false,
             },
         ),
         (
-            "Desired Replicas:\t",
-            DescItem::Valu64 {
-                path: "/spec/replicas",
-                default: 0,
-            },
-        ),
-        (
-            "Current Replicas:\t",
-            DescItem::Valu64 {
-                path: "/status/replicas",
-                default: 0,
+            "Strategy:",
+            DescItem::CustomFunc {
+                path: Some("/spec/strategy"),
+                func: &strategy_str,
+                default: "RollingUpdate",
             },
         ),
         (
-            "Up To Date Replicas:\t",
-            DescItem::Valu64 {
-                path: "/status/updatedReplicas",
-                default: 0,
+            "Replicas:",
+            DescItem::CustomFunc {
+                path: None,
+                func: &replicas_str,
+                default: "<none>",
             },
         ),
         (
-            "Available Replicas:\t",
-            DescItem::Valu64 {
-                path: "/status/availableReplicas",
-                default: 0,
+            "Rollout:",
+            DescItem::CustomFunc {
+                path: None,
+                func: &rollout_str,
+                default: "<unknown>",
             },
         ),
         (
@@ -616,24 +1125,121 @@ pub fn describe_format_deployment(
             },
         ),
         (
-            "Messages:\n",
+            "Conditions:",
             DescItem::CustomFunc {
                 path: Some("/status/conditions"),
-                func: &get_message_str,
-                default: "<No Messages>",
+                func: &get_conditions_str,
+                default: "<none>",
             },
         ),
     ];
-    describe_object(&v, fields.into_iter(), table);
+    describe_object(&v, fields.into_iter(), env, verbose, table);
     Ok(())
 }
 
+/// A deployment's update strategy, with the surge and unavailability allowed for rolling updates
+fn strategy_str(v: &Value) -> Cow<'_, str> {
+    let typ = val_str("/type", v, "RollingUpdate");
+    if typ != "RollingUpdate" {
+        return typ;
+    }
+    // these can be a number of pods or a percentage
+    let amount = |path: &str| match v.pointer(path) {
+        Some(Value::Number(num)) => num.to_string(),
+        Some(Value::String(percent)) => percent.clone(),
+        _ => "25%".to_string(),
+    };
+    format!(
+        "RollingUpdate (max surge {}, max unavailable {})",
+        amount("/rollingUpdate/maxSurge"),
+        amount("/rollingUpdate/maxUnavailable")
+    )
+    .into()
+}
+
+/// The replica counts of a deployment: how many are wanted, and how many are updated, ready and
+/// available
+fn replicas_str(v: &Value) -> Cow<'_, str> {
+    let mut buf = format!(
+        "{} desired, {} updated, {} total, {} ready, {} available",
+        val_u64("/spec/replicas", v, 1),
+        val_u64("/status/updatedReplicas", v, 0),
+        val_u64("/status/replicas", v, 0),
+        val_u64("/status/readyReplicas", v, 0),
+        val_u64("/status/availableReplicas", v, 0),
+    );
+    let unavailable = val_u64("/status/unavailableReplicas", v, 0);
+    if unavailable > 0 {
+        buf.push_str(&format!(
+            ", {}",
+            format!("{} unavailable", unavailable).red()
+        ));
+    }
+    buf.into()
+}
+
+/// Where a deployment's rollout is at, worked out the way kubectl rollout status does
+fn rollout_str(v: &Value) -> Cow<'_, str> {
+    let generation = val_u64("/metadata/generation", v, 0);
+    let observed = val_u64("/status/observedGeneration", v, 0);
+    if observed < generation {
+        return "Waiting for the deployment controller to see the update"
+            .yellow()
+            .to_string()
+            .into();
+    }
+    let progressing = v
+        .pointer("/status/conditions")
+        .and_then(Value::as_array)
+        .and_then(|conditions| {
+            conditions
+                .iter()
+                .find(|condition| condition["type"] == "Progressing")
+        });
+    if progressing.is_some_and(|condition| condition["reason"] == "ProgressDeadlineExceeded") {
+        return "Failed: progress deadline exceeded"
+            .red()
+            .to_string()
+            .into();
+    }
+    let desired = val_u64("/spec/replicas", v, 1);
+    let updated = val_u64("/status/updatedReplicas", v, 0);
+    let total = val_u64("/status/replicas", v, 0);
+    let available = val_u64("/status/availableReplicas", v, 0);
+    let in_progress = if updated < desired {
+        format!("{} of {} new replicas updated", updated, desired)
+    } else if total > updated {
+        format!("{} old replicas pending termination", total - updated)
+    } else if available < updated {
+        format!("{} of {} updated replicas available", available, updated)
+    } else {
+        return "Complete".green().to_string().into();
+    };
+    format!("{}: {}", "In progress".yellow(), in_progress).into()
+}
+
 /// Utility function to describe a rollout
 #[cfg(feature = "argorollouts")]
 use crate::command::rollouts;
+
+/// Get status messages out of 'conditions' array
+#[cfg(feature = "argorollouts")]
+fn get_message_str(v: &Value) -> Cow<str> {
+    let mut buf = String::new();
+    if let Some(condition_array) = v.as_array() {
+        for condition in condition_array.iter() {
+            let msg = val_str("/message", condition, "<No Message>");
+            buf.push_str(format!("  Message: {}\n", msg).as_str());
+        }
+    }
+    buf.into()
+}
+
 #[cfg(feature = "argorollouts")]
 pub fn describe_format_rollout(
     rollout: &rollouts::RolloutValue,
+    env: &Env,
+    verbose: bool,
     table: &mut comfy_table::Table,
 ) -> Result<(), ClickError> {
     let v = serde_json::value::to_value(&rollout).unwrap();
@@ -664,7 +1270,6 @@ pub fn describe_format_rollout(
             "Labels:",
             DescItem::KeyValStr {
                 parent: "/metadata/labels",
-                secret_vals: false,
             },
         ),
         (
@@ -712,6 +1317,249 @@ pub fn describe_format_rollout(
             },
         ),
     ];
-    describe_object(&v, fields.into_iter(), table);
+    describe_object(&v, fields.into_iter(), env, verbose, table);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::styles::strip_ansi;
+    use serde_json::json;
+
+    #[test]
+    fn test_container_statuses_str() {
+        let status = json!({
+            "initContainerStatuses": [
+                {
+                    "name": "migrate",
+                    "ready": true,
+                    "restartCount": 0,
+                    "state": { "terminated": { "reason": "Completed", "exitCode": 0 } }
+                }
+            ],
+            "containerStatuses": [
+                {
+                    "name": "web",
+                    "ready": false,
+                    "restartCount": 5,
+                    "state": { "waiting": { "reason": "CrashLoopBackOff" } },
+                    "lastState": {
+                        "terminated": {
+                            "reason": "OOMKilled",
+                            "exitCode": 137,
+                            "finishedAt": "2022-01-01T00:00:00Z"
+                        }
+                    }
+                },
+                {
+                    "name": "sidecar",
+                    "ready": true,
+                    "restartCount": 1,
+                    "state": { "running": {} },
+                    "lastState": {}
+                }
+            ]
+        });
+        assert_eq!(
+            strip_ansi(&get_container_statuses_str(&status)),
+            "migrate (init): ready, 0 restarts
+  Terminated: Completed, exit code 0
+web: not ready, 5 restarts
+  Waiting: CrashLoopBackOff
+  Last: Terminated: OOMKilled, exit code 137 at 2022-01-01T00:00:00Z
+sidecar: ready, 1 restart
+  Running"
+        );
+        assert_eq!(get_container_statuses_str(&json!({})), "<none>");
+    }
+
+    #[test]
+    fn test_node_versions_and_images() {
+        let info = json!({
+            "kubeletVersion": "v1.23.4",
+            "osImage": "Ubuntu 20.04.4 LTS",
+            "operatingSystem": "linux",
+            "architecture": "amd64",
+            "kernelVersion": "5.4.0-1068-aws",
+            "containerRuntimeVersion": "containerd://1.5.9",
+        });
+        assert_eq!(
+            node_versions_str(&info),
+            "kubelet v1.23.4, Ubuntu 20.04.4 LTS (linux/amd64), kernel 5.4.0-1068-aws, \
+             containerd://1.5.9"
+        );
+        let images = json!([
+            { "names": ["nginx@sha256:abcd", "nginx:1.21"], "sizeBytes": 1572864 },
+            { "names": ["pause@sha256:1234"], "sizeBytes": 512 },
+        ]);
+        assert_eq!(
+            node_images_str(&images),
+            "nginx:1.21 (1.50 MiB)\npause@sha256:1234 (512 B)"
+        );
+        assert_eq!(node_images_str(&json!([])), "<none>");
+    }
+
+    #[test]
+    fn test_conditions_str() {
+        let conditions = json!([{
+            "type": "Ready",
+            "status": "False",
+            "lastTransitionTime": "2022-01-01T00:00:00Z",
+            "reason": "KubeletNotReady",
+            "message": "PLEG is not healthy",
+        }]);
+        assert_eq!(
+            get_conditions_str(&conditions),
+            "Ready: False since 2022-01-01T00:00:00Z (KubeletNotReady) PLEG is not healthy"
+        );
+    }
+
+    #[test]
+    fn test_deployment_strs() {
+        assert_eq!(
+            strategy_str(&json!({
+                "type": "RollingUpdate",
+                "rollingUpdate": { "maxSurge": 1, "maxUnavailable": "10%" },
+            })),
+            "RollingUpdate (max surge 1, max unavailable 10%)"
+        );
+        assert_eq!(strategy_str(&json!({ "type": "Recreate" })), "Recreate");
+
+        let deployment = |status: Value| {
+            json!({
+                "metadata": { "generation": 2 },
+                "spec": { "replicas": 3 },
+                "status": status,
+            })
+        };
+        let rolling = deployment(json!({
+            "observedGeneration": 2,
+            "replicas": 4,
+            "updatedReplicas": 2,
+            "readyReplicas": 3,
+            "availableReplicas": 3,
+            "unavailableReplicas": 1,
+        }));
+        assert_eq!(
+            strip_ansi(&replicas_str(&rolling)),
+            "3 desired, 2 updated, 4 total, 3 ready, 3 available, 1 unavailable"
+        );
+        assert_eq!(
+            strip_ansi(&rollout_str(&rolling)),
+            "In progress: 2 of 3 new replicas updated"
+        );
+        let done = deployment(json!({
+            "observedGeneration": 2,
+            "replicas": 3,
+            "updatedReplicas": 3,
+            "availableReplicas": 3,
+        }));
+        assert_eq!(strip_ansi(&rollout_str(&done)), "Complete");
+        let stuck = deployment(json!({
+            "observedGeneration": 2,
+            "conditions": [{
+                "type": "Progressing",
+                "status": "False",
+                "reason": "ProgressDeadlineExceeded",
+            }],
+        }));
+        assert_eq!(
+            strip_ansi(&rollout_str(&stuck)),
+            "Failed: progress deadline exceeded"
+        );
+        let unobserved = deployment(json!({ "observedGeneration": 1 }));
+        assert_eq!(
+            strip_ansi(&rollout_str(&unobserved)),
+            "Waiting for the deployment controller to see the update"
+        );
+    }
+
+    #[test]
+    fn test_probe_str() {
+        assert_eq!(
+            probe_str(&json!({ "httpGet": { "path": "/healthz", "port": 8080 } })),
+            "http-get http://:8080/healthz delay=0s timeout=1s period=10s #success=1 #failure=3"
+        );
+        assert_eq!(
+            probe_str(&json!({
+                "tcpSocket": { "port": "grpc" },
+                "initialDelaySeconds": 5,
+                "failureThreshold": 6,
+            })),
+            "tcp-socket :grpc delay=5s timeout=1s period=10s #success=1 #failure=6"
+        );
+        assert_eq!(
+            probe_str(&json!({ "exec": { "command": ["cat", "/tmp/ready"] }, "periodSeconds": 2 })),
+            "exec [cat /tmp/ready] delay=0s timeout=1s period=2s #success=1 #failure=3"
+        );
+    }
+
+    #[test]
+    fn test_container_specs_str() {
+        let spec = json!({
+            "initContainers": [{ "name": "migrate", "image": "app:1.2" }],
+            "containers": [{
+                "name": "web",
+                "image": "app:1.2",
+                "resources": {
+                    "requests": { "cpu": "100m", "memory": "128Mi" },
+                    "limits": { "memory": "256Mi" },
+                },
+                "readinessProbe": { "httpGet": { "path": "/ready", "port": 80 } },
+                "volumeMounts": [
+                    { "name": "config", "mountPath": "/etc/app", "readOnly": true },
+                    { "name": "data", "mountPath": "/data", "subPath": "web" },
+                ],
+            }],
+        });
+        assert_eq!(
+            container_specs_str(&spec),
+            "migrate (init):
+  Image: app:1.2
+web:
+  Image: app:1.2
+  Requests: cpu=100m, memory=128Mi
+  Limits: memory=256Mi
+  Mounts:
+    /etc/app from config (ro)
+    /data from data [web]"
+        );
+        assert_eq!(container_specs_str(&json!({})), "<none>");
+        assert_eq!(
+            probes_str(&spec),
+            "web:
+  Readiness: http-get http://:80/ready delay=0s timeout=1s period=10s #success=1 #failure=3"
+        );
+        assert_eq!(
+            probes_str(&json!({ "containers": [{ "name": "web" }] })),
+            "<none>"
+        );
+    }
+
+    #[test]
+    fn test_pod_ips_and_owners() {
+        let status =
+            json!({ "podIP": "10.0.0.1", "podIPs": [{ "ip": "10.0.0.1" }, { "ip": "fd00::1" }] });
+        assert_eq!(pod_ips_str(&status), "10.0.0.1, fd00::1");
+        assert_eq!(pod_ips_str(&json!({ "podIP": "10.0.0.1" })), "10.0.0.1");
+        let owners = json!([{ "kind": "ReplicaSet", "name": "web-5d4f8" }]);
+        assert_eq!(owners_str(&owners), "ReplicaSet/web-5d4f8");
+        assert_eq!(owners_str(&json!([])), "<none>");
+    }
+
+    #[test]
+    fn test_secret_data_str() {
+        // "hunter2", and some bytes that aren't utf-8
+        let data = json!({ "password": "aHVudGVyMg==", "keystore": "/w==" });
+        assert_eq!(
+            secret_data_str(Some(&data), false),
+            "keystore=1 bytes\npassword=7 bytes"
+        );
+        assert_eq!(
+            secret_data_str(Some(&data), true),
+            "keystore=<1 bytes of binary data>\npassword=hunter2"
+        );
+        assert_eq!(secret_data_str(None, true), "<none>");
+    }
+}
@@ -13,22 +13,47 @@
 // limitations under the License.
 
 /// This module contains code for handling how click describes various k8s objects
-use crate::{command::keyval_string, error::ClickError, output::ClickWriter};
-use chrono::Local;
+use crate::{
+    command::{keyval_string, output_format, print_full_output},
+    env::Env,
+    error::ClickError,
+    output::ClickWriter,
+    table::format_timestamp,
+};
 use clap::ArgMatches;
 use k8s_openapi::{apimachinery::pkg::apis::meta::v1::ObjectMeta, Metadata, Resource};
 use serde::ser::Serialize;
 use std::collections::HashSet;
+use std::io::Write;
 
 pub mod crd;
+pub mod deployment;
 pub mod legacy;
+pub mod node;
+pub mod pod;
 pub mod service;
 
 pub static NOTSUPPORTED: &str = "not supported without -j or -y yet\n";
 
+/// A table to describe an object in, with a title and a value on each row and no borders
+pub fn describe_table() -> comfy_table::Table {
+    let mut table = comfy_table::Table::new();
+    table.load_preset(comfy_table::presets::NOTHING);
+    table.set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+    table
+}
+
+/// Check if matches ask for the full object (via -j, -y or -o) rather than click's description
+pub fn want_full_describe_output(matches: &ArgMatches) -> bool {
+    matches.is_present("json")
+        || matches.is_present("yaml")
+        || !matches!(output_format(matches), Ok(format) if format.is_table())
+}
+
 pub fn maybe_full_describe_output<T: ?Sized>(
     matches: &ArgMatches,
     value: &T,
+    env: &Env,
     writer: &mut ClickWriter,
 ) -> bool
 where
@@ -41,10 +66,27 @@ where
         writer.print_yaml(value).unwrap_or(());
         true
     } else {
-        false
+        let res = output_format(matches).and_then(|format| {
+            if format.is_table() {
+                Ok(false)
+            } else {
+                let value = serde_json::to_value(value)?;
+                print_full_output(&format, &value, env, writer).map(|_| true)
+            }
+        });
+        match res {
+            Ok(full) => full,
+            Err(e) => {
+                clickwriteln!(writer, "{}", e);
+                true
+            }
+        }
     }
 }
 
+/// How times are shown in describe output, after the utc time
+pub const DESCRIBE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+
 lazy_static! {
     static ref DESCRIBE_SKIP_KEYS: HashSet<String> = {
         let mut s: HashSet<String> = HashSet::new();
@@ -53,8 +95,15 @@ lazy_static! {
     };
 }
 
+/// Was a verbose description (describe -v) asked for
+pub fn want_verbose(matches: &ArgMatches) -> bool {
+    matches.is_valid_arg("verbose") && matches.is_present("verbose")
+}
+
 pub fn describe_metadata<T: ?Sized + Metadata<Ty = ObjectMeta> + Resource>(
     value: &T,
+    env: &Env,
+    verbose: bool,
     table: &mut comfy_table::Table,
 ) -> Result<(), ClickError> {
     let metadata = value.metadata();
@@ -78,16 +127,18 @@ pub fn describe_metadata<T: ?Sized + Metadata<Ty = ObjectMeta> + Resource>(
             table.add_row(label_vec);
         }
     }
-    let mut annot_vec = vec!["Annotations:"];
-    match metadata.annotations.as_ref() {
-        Some(annotations) => {
-            let astr = keyval_string(annotations.iter(), Some(&DESCRIBE_SKIP_KEYS));
-            annot_vec.push(&astr);
-            table.add_row(annot_vec);
-        }
-        None => {
-            annot_vec.push("<none>");
-            table.add_row(annot_vec);
+    if verbose {
+        let mut annot_vec = vec!["Annotations:"];
+        match metadata.annotations.as_ref() {
+            Some(annotations) => {
+                let astr = keyval_string(annotations.iter(), Some(&DESCRIBE_SKIP_KEYS));
+                annot_vec.push(&astr);
+                table.add_row(annot_vec);
+            }
+            None => {
+                annot_vec.push("<none>");
+                table.add_row(annot_vec);
+            }
         }
     }
     table.add_row(vec!["API Version:", <T as Resource>::API_VERSION]);
@@ -97,7 +148,11 @@ pub fn describe_metadata<T: ?Sized + Metadata<Ty = ObjectMeta> + Resource>(
         Some(created) => {
             table.add_row(vec![
                 "Created At:",
-                &format!("{} ({})", created.0, created.0.with_timezone(&Local)),
+                &format!(
+                    "{} ({})",
+                    created.0,
+                    format_timestamp(created.0, DESCRIBE_TIME_FORMAT, env)
+                ),
             ]);
         }
         None => {
@@ -0,0 +1,191 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// the pods on a node need another request, so handle them here
+
+use crate::{
+    command::pods::pod_resource,
+    env::Env,
+    error::ClickError,
+    table::{format_raw_quantity, percent_str, raw_quantity, QuantityKind},
+};
+
+use k8s_openapi::{
+    api::core::v1 as api, apimachinery::pkg::api::resource::Quantity, List, ListOptional,
+};
+use std::collections::BTreeMap;
+
+/// The resources shown for the pods on a node
+const NODE_RESOURCES: [(&str, QuantityKind); 2] =
+    [("cpu", QuantityKind::Cpu), ("memory", QuantityKind::Bytes)];
+
+/// amount as a percentage of allocatable, like " (42%)", if the node has any of that resource
+fn percent_of_allocatable(amount: f64, allocatable: Option<f64>) -> String {
+    match allocatable.and_then(|allocatable| percent_str(amount, allocatable)) {
+        Some(percent) => format!(" ({})", percent),
+        None => String::new(),
+    }
+}
+
+/// The total requests and limits of pods, compared to what the node has allocatable, like kubectl's
+/// "Allocated resources"
+fn allocated_str(pods: &[api::Pod], allocatable: Option<&BTreeMap<String, Quantity>>) -> String {
+    let lines: Vec<String> = NODE_RESOURCES
+        .iter()
+        .map(|(resource, kind)| {
+            let allocatable = allocatable
+                .and_then(|allocatable| allocatable.get(*resource))
+                .map(raw_quantity);
+            let requests: f64 = pods
+                .iter()
+                .map(|pod| pod_resource(pod, false, resource))
+                .sum();
+            let limits: f64 = pods
+                .iter()
+                .map(|pod| pod_resource(pod, true, resource))
+                .sum();
+            format!(
+                "{}: {} requested{}, {} limits{}",
+                resource,
+                format_raw_quantity(requests, *kind),
+                percent_of_allocatable(requests, allocatable),
+                format_raw_quantity(limits, *kind),
+                percent_of_allocatable(limits, allocatable)
+            )
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Each pod, with what it requests, names lined up
+fn node_pods_str(pods: &[api::Pod]) -> String {
+    if pods.is_empty() {
+        return "<none>".to_string();
+    }
+    let names: Vec<String> = pods
+        .iter()
+        .map(|pod| {
+            format!(
+                "{}/{}",
+                pod.metadata.namespace.as_deref().unwrap_or("<none>"),
+                pod.metadata.name.as_deref().unwrap_or("<No Name>")
+            )
+        })
+        .collect();
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+    let lines: Vec<String> = pods
+        .iter()
+        .zip(names.iter())
+        .map(|(pod, name)| {
+            let requests: Vec<String> = NODE_RESOURCES
+                .iter()
+                .map(|(resource, kind)| {
+                    let amount = pod_resource(pod, false, resource);
+                    format!("{} {}", resource, format_raw_quantity(amount, *kind))
+                })
+                .collect();
+            format!("{:width$}  {}", name, requests.join(", "), width = width)
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Add the pods running on node, and the resources they've requested of it, to a node description
+pub fn describe_node_pods(
+    node: &api::Node,
+    env: &Env,
+    _verbose: bool,
+    table: &mut comfy_table::Table,
+) -> Result<(), ClickError> {
+    // finished pods don't hold on to anything, kubectl leaves them out too
+    let selector = format!(
+        "spec.nodeName={},status.phase!=Succeeded,status.phase!=Failed",
+        node.metadata.name.as_deref().unwrap_or_default()
+    );
+    let (request, _) = api::Pod::list_pod_for_all_namespaces(ListOptional {
+        field_selector: Some(&selector),
+        ..Default::default()
+    })?;
+    let mut pods: List<api::Pod> = env.run_on_context(|c| c.execute_list(request))?;
+    pods.items.sort_by(|a, b| {
+        (&a.metadata.namespace, &a.metadata.name).cmp(&(&b.metadata.namespace, &b.metadata.name))
+    });
+    let allocatable = node
+        .status
+        .as_ref()
+        .and_then(|status| status.allocatable.as_ref());
+    table.add_row(vec![
+        "Allocated:".to_string(),
+        allocated_str(&pods.items, allocatable),
+    ]);
+    table.add_row(vec![
+        format!("Pods ({}):", pods.items.len()),
+        node_pods_str(&pods.items),
+    ]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pod(name: &str, containers: serde_json::Value, init: serde_json::Value) -> api::Pod {
+        serde_json::from_value(json!({
+            "metadata": { "name": name, "namespace": "default" },
+            "spec": { "containers": containers, "initContainers": init },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_node_pods() {
+        let pods = vec![
+            pod(
+                "web-1",
+                json!([
+                    { "name": "web", "resources": { "requests": { "cpu": "250m", "memory": "1Gi" } } },
+                    { "name": "proxy", "resources": { "requests": { "cpu": "250m" } } },
+                ]),
+                // needs more cpu than the containers together, but only while it runs, first
+                json!([{ "name": "init", "resources": { "requests": { "cpu": "1" } } }]),
+            ),
+            pod(
+                "db",
+                json!([{
+                    "name": "db",
+                    "resources": { "requests": { "cpu": "500m" }, "limits": { "cpu": "2" } },
+                }]),
+                json!([]),
+            ),
+        ];
+        assert_eq!(
+            node_pods_str(&pods),
+            "default/web-1  cpu 1.00 cores, memory 1.00 GiB
+default/db     cpu 0.50 cores, memory 0 B"
+        );
+        let allocatable: BTreeMap<String, Quantity> = [
+            ("cpu".to_string(), Quantity("3".to_string())),
+            ("memory".to_string(), Quantity("4Gi".to_string())),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            allocated_str(&pods, Some(&allocatable)),
+            "cpu: 1.50 cores requested (50%), 2.00 cores limits (67%)
+memory: 1.00 GiB requested (25%), 0 B limits (0%)"
+        );
+        assert_eq!(node_pods_str(&[]), "<none>");
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// probe failures come from the pod's events, which need another request, so handle them here
+
+use crate::{command::events::get_event_ts, env::Env, error::ClickError, table::format_time};
+
+use chrono::{offset::Utc, DateTime};
+use k8s_openapi::{api::core::v1 as api, List, ListOptional};
+use std::collections::BTreeMap;
+
+/// How often a container's probe has failed, from the pod's events
+#[derive(Debug, Default, PartialEq)]
+struct ProbeFailures {
+    failures: i32,
+    /// how many times the container was restarted because of the probe failing
+    restarts: i32,
+    last: Option<DateTime<Utc>>,
+    /// why the probe failed the last time, like "HTTP probe failed with statuscode: 500"
+    message: String,
+}
+
+/// The container an event is about, from a field path like spec.containers{web}
+fn event_container(event: &api::Event) -> Option<&str> {
+    let field_path = event.involved_object.field_path.as_deref()?;
+    let (_, container) = field_path.split_once('{')?;
+    container.strip_suffix('}')
+}
+
+/// The failures of each container's probes, by container and probe (like Liveness). The kubelet
+/// reports a failure as an Unhealthy event, with a message like "Liveness probe failed: ...", and
+/// a restart because of one as a Killing event, like "Container web failed liveness probe, will be
+/// restarted"
+fn probe_failures(events: &[api::Event]) -> BTreeMap<(String, String), ProbeFailures> {
+    let mut failures: BTreeMap<(String, String), ProbeFailures> = BTreeMap::new();
+    for event in events.iter() {
+        let container = match event_container(event) {
+            Some(container) => container.to_string(),
+            None => continue,
+        };
+        let message = event.message.as_deref().unwrap_or_default();
+        let count = event.count.unwrap_or(1);
+        match event.reason.as_deref() {
+            Some("Unhealthy") => {
+                let (probe, rest) = match message.split_once(" probe ") {
+                    Some(split) => split,
+                    None => continue,
+                };
+                let entry = failures.entry((container, probe.to_string())).or_default();
+                entry.failures += count;
+                let ts = get_event_ts(event);
+                if entry.last.is_none() || ts >= entry.last {
+                    entry.last = ts;
+                    entry.message = rest
+                        .split_once(": ")
+                        .map(|(_, reason)| reason)
+                        .unwrap_or(rest)
+                        .trim()
+                        .to_string();
+                }
+            }
+            Some("Killing") => {
+                for probe in ["Liveness", "Startup"] {
+                    let failed = format!("failed {} probe", probe.to_lowercase());
+                    if message.contains(&failed) {
+                        failures
+                            .entry((container.clone(), probe.to_string()))
+                            .or_default()
+                            .restarts += count;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    failures
+}
+
+fn probe_failures_str(failures: &BTreeMap<(String, String), ProbeFailures>, env: &Env) -> String {
+    if failures.is_empty() {
+        return "<none>".to_string();
+    }
+    let lines: Vec<String> = failures
+        .iter()
+        .map(|((container, probe), failures)| {
+            let mut line = format!(
+                "{} {}: failed {} time{}",
+                container,
+                probe,
+                failures.failures,
+                if failures.failures == 1 { "" } else { "s" }
+            );
+            if failures.restarts > 0 {
+                line.push_str(&format!(
+                    ", restarted {} time{}",
+                    failures.restarts,
+                    if failures.restarts == 1 { "" } else { "s" }
+                ));
+            }
+            if let Some(last) = failures.last {
+                line.push_str(&format!(", last {}", format_time(last, env)));
+            }
+            if !failures.message.is_empty() {
+                line.push_str(&format!(": {}", failures.message));
+            }
+            line
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Add the recent failures of the pod's probes, and the restarts they caused, to a pod description.
+/// Events only stick around for a while (an hour by default), so older failures aren't shown
+pub fn describe_pod_probe_failures(
+    pod: &api::Pod,
+    env: &Env,
+    _verbose: bool,
+    table: &mut comfy_table::Table,
+) -> Result<(), ClickError> {
+    let (name, namespace) = match (&pod.metadata.name, &pod.metadata.namespace) {
+        (Some(name), Some(namespace)) => (name, namespace),
+        _ => return Ok(()),
+    };
+    let selector = format!("involvedObject.kind=Pod,involvedObject.name={}", name);
+    let (request, _) = api::Event::list_namespaced_event(
+        namespace,
+        ListOptional {
+            field_selector: Some(&selector),
+            ..Default::default()
+        },
+    )?;
+    // not being able to read events shouldn't stop the rest of the pod being described
+    let events: Result<List<api::Event>, ClickError> =
+        env.run_on_context(|c| c.execute_list(request));
+    let failures = match events {
+        Ok(events) => probe_failures_str(&probe_failures(&events.items), env),
+        Err(_) => "<could not read events>".to_string(),
+    };
+    table.add_row(vec!["Probe Failures:".to_string(), failures]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_probe_failures() {
+        let events: Vec<api::Event> = serde_json::from_value(json!([
+            {
+                "metadata": { "name": "web.1" },
+                "involvedObject": { "kind": "Pod", "name": "web", "fieldPath": "spec.containers{web}" },
+                "reason": "Unhealthy",
+                "message": "Liveness probe failed: HTTP probe failed with statuscode: 503",
+                "count": 3,
+                "lastTimestamp": "2024-05-12T10:00:00Z",
+            },
+            {
+                "metadata": { "name": "web.2" },
+                "involvedObject": { "kind": "Pod", "name": "web", "fieldPath": "spec.containers{web}" },
+                "reason": "Unhealthy",
+                "message": "Liveness probe failed: Get \"http://10.1.2.3:8080/healthz\": context deadline exceeded",
+                "count": 2,
+                "lastTimestamp": "2024-05-12T10:05:00Z",
+            },
+            {
+                "metadata": { "name": "web.3" },
+                "involvedObject": { "kind": "Pod", "name": "web", "fieldPath": "spec.containers{web}" },
+                "reason": "Killing",
+                "message": "Container web failed liveness probe, will be restarted",
+                "count": 1,
+            },
+            {
+                "metadata": { "name": "web.4" },
+                "involvedObject": { "kind": "Pod", "name": "web", "fieldPath": "spec.containers{proxy}" },
+                "reason": "Unhealthy",
+                "message": "Readiness probe failed: dial tcp 10.1.2.3:15021: connect: connection refused",
+            },
+            {
+                "metadata": { "name": "web.5" },
+                "involvedObject": { "kind": "Pod", "name": "web" },
+                "reason": "Scheduled",
+                "message": "Successfully assigned prod/web to node-1",
+            },
+        ]))
+        .unwrap();
+        let failures = probe_failures(&events);
+        let keys: Vec<(&str, &str)> = failures
+            .keys()
+            .map(|(container, probe)| (container.as_str(), probe.as_str()))
+            .collect();
+        assert_eq!(keys, vec![("proxy", "Readiness"), ("web", "Liveness")]);
+        let web = &failures[&("web".to_string(), "Liveness".to_string())];
+        assert_eq!(web.failures, 5);
+        assert_eq!(web.restarts, 1);
+        assert_eq!(
+            web.message,
+            "Get \"http://10.1.2.3:8080/healthz\": context deadline exceeded"
+        );
+        let proxy = &failures[&("proxy".to_string(), "Readiness".to_string())];
+        assert_eq!(proxy.failures, 1);
+        assert_eq!(
+            proxy.message,
+            "dial tcp 10.1.2.3:15021: connect: connection refused"
+        );
+    }
+}
@@ -19,13 +19,14 @@ use crate::{
     env::Env,
     error::ClickError,
     output::ClickWriter,
-    values::{val_str, val_u64},
+    values::{val_str, val_str_opt, val_u64},
 };
 
 use clap::ArgMatches;
+use crossterm::style::Stylize;
 use k8s_openapi::api::core::v1 as api;
 use serde_json::Value;
-use std::{borrow::Cow, io::Write, str::FromStr};
+use std::borrow::Cow;
 
 pub fn service_describe(
     name: &str,
@@ -35,29 +36,16 @@ pub fn service_describe(
     writer: &mut ClickWriter,
     table: &mut comfy_table::Table,
 ) -> Result<(), ClickError> {
-    let (request, _) =
-        api::Endpoints::read_namespaced_endpoints(name, namespace, Default::default()).unwrap();
-    let epval = match env.run_on_context(|c| c.read(request)).unwrap() {
-        api::ReadNamespacedEndpointsResponse::Ok(resp) => serde_json::value::to_value(&resp).ok(),
-        _ => {
-            clickwriteln!(writer, "Error fetching endpoints");
-            None
-        }
-    };
-
-    let (request, _) =
-        api::Service::read_namespaced_service(name, namespace, Default::default()).unwrap();
-    match env.run_on_context(|c| c.read(request)).unwrap() {
-        api::ReadNamespacedServiceResponse::Ok(service) => {
-            if !super::maybe_full_describe_output(matches, &service, writer) {
-                super::describe_metadata(&service, table)?;
-                let val = serde_json::value::to_value(&service).unwrap();
-                describe_format_service(&service, val, epval, table);
-            }
-        }
-        _ => {
-            clickwriteln!(writer, "Invalid response trying to read service info");
-        }
+    let (request, _) = api::Service::read_namespaced_service(name, namespace, Default::default())?;
+    let service: api::Service = env.run_on_context(|c| c.read_object(request))?;
+    if !super::maybe_full_describe_output(matches, &service, env, writer) {
+        // services without a selector (or of type ExternalName) have no endpoints object
+        let (request, _) =
+            api::Endpoints::read_namespaced_endpoints(name, namespace, Default::default())?;
+        let epval = env.run_on_context(|c| c.get_value(request)).ok();
+        super::describe_metadata(&service, env, super::want_verbose(matches), table)?;
+        let val = serde_json::value::to_value(&service).unwrap();
+        describe_format_service(&service, val, epval, table);
     }
     Ok(())
 }
@@ -69,7 +57,11 @@ fn describe_format_service(
     endpoint_val: Option<Value>,
     table: &mut comfy_table::Table,
 ) {
-    let port_str = get_ports_str(v.pointer("/spec/ports"), endpoint_val);
+    let port_str = get_ports_str(v.pointer("/spec/ports"));
+    let endpoints_str = match endpoint_val {
+        Some(ep) => get_endpoints_str(&ep),
+        None => "<No Endpoints>".to_string(),
+    };
     table.add_row(vec![
         "Selector:",
         service
@@ -158,104 +150,145 @@ fn describe_format_service(
             .as_str(),
     ]);
     table.add_row(vec!["Ports:", port_str.as_ref()]);
+    table.add_row(vec!["Endpoints:", endpoints_str.as_str()]);
 }
 
-/// Get ports info out of ports array
-fn get_ports_str(v: Option<&Value>, endpoint_val: Option<Value>) -> Cow<str> {
-    if v.is_none() {
-        return "<none>".into();
-    }
-    let mut buf = String::new();
-    match v.unwrap().as_array() {
-        // safe unwrap, checked above
-        Some(port_array) => {
-            for port in port_array.iter() {
-                let proto = val_str("/protocol", port, "<Unknown>");
-                let name = val_str("/name", port, "<No Name>");
-                let port_num = val_u64("/port", port, 0);
-                let endpoints = match endpoint_val {
-                    Some(ref ep) => {
-                        // to get all the endpoints, we need to check all subsets this port is in
-                        // TODO: This is complex, simplify and/or abstract
-                        let mut epbuf = String::from_str("Endpoints:  ").unwrap();
-                        let mut found_one = false;
-                        ep.pointer("/subsets").map(|s| {
-                            s.as_array().map(|subsets| {
-                                for subset in subsets.iter() {
-                                    // see if this subset has this port by checking if any port in
-                                    // the ports array has the same port number
-                                    let contains = subset
-                                        .pointer("/ports")
-                                        .map(|p| {
-                                            p.as_array()
-                                                .map(|ports_array| {
-                                                    let mut c = false;
-                                                    for port in ports_array.iter() {
-                                                        if port_num == val_u64("/port", port, 0) {
-                                                            c = true;
-                                                        }
-                                                    }
-                                                    c
-                                                })
-                                                .unwrap_or(false)
-                                        })
-                                        .unwrap_or(false);
-                                    if contains {
-                                        // we do have this port, need to add all addresses as
-                                        // endpoints
-                                        found_one = true;
-                                        let port_num = val_u64("/targetPort", port, 0);
-                                        subset.pointer("/addresses").map(|a| {
-                                            a.as_array().map(|addr_array| {
-                                                let mut first = true;
-                                                for addr in addr_array.iter() {
-                                                    if first {
-                                                        first = false;
-                                                    } else {
-                                                        epbuf.push_str(", ");
-                                                    }
-                                                    epbuf.push_str(
-                                                        format!(
-                                                            "{}:{}",
-                                                            val_str("/ip", addr, "<No IP>"),
-                                                            port_num
-                                                        )
-                                                        .as_str(),
-                                                    );
-                                                }
-                                            })
-                                        });
-                                    }
-                                }
-                            })
-                        });
-                        if !found_one {
-                            epbuf.push_str("<none>");
-                        }
-                        epbuf.push('\n');
-                        epbuf
-                    }
-                    None => "<No Endpoints>\n".to_owned(),
-                };
-                buf.push_str(format!("Port:  {} {}/{}\n", name, port_num, proto).as_str());
-                buf.push_str(
-                    format!(
-                        "NodePort:  {} {}/{}\n",
-                        val_str("/name", port, "<No Name>"),
-                        val_u64("/nodePort", port, 0),
-                        proto
-                    )
-                    .as_str(),
-                );
-                buf.push_str(endpoints.as_str());
+/// Get ports info out of ports array, each like: http 80/TCP -> 8080, NodePort 30080
+fn get_ports_str(v: Option<&Value>) -> Cow<'_, str> {
+    let ports = match v.and_then(Value::as_array) {
+        Some(ports) if !ports.is_empty() => ports,
+        _ => return "<none>".into(),
+    };
+    let lines: Vec<String> = ports
+        .iter()
+        .map(|port| {
+            let port_num = val_u64("/port", port, 0);
+            let mut line = format!(
+                "{} {}/{}",
+                val_str("/name", port, "<No Name>"),
+                port_num,
+                val_str("/protocol", port, "TCP")
+            );
+            // the target port can be a number or the name of a container port
+            match port.get("targetPort") {
+                Some(Value::Number(target)) => line.push_str(&format!(" -> {}", target)),
+                Some(Value::String(target)) => line.push_str(&format!(" -> {}", target)),
+                _ => line.push_str(&format!(" -> {}", port_num)),
             }
-        }
-        None => buf.push_str("<none>"),
+            if let Some(node_port) = port.get("nodePort").and_then(Value::as_u64) {
+                line.push_str(&format!(", NodePort {}", node_port));
+            }
+            line
+        })
+        .collect();
+    lines.join("\n").into()
+}
+
+/// Get the pod IPs (and ports) behind a service out of its endpoints object, ready ones first then
+/// the ones that aren't ready, with the pod each is for
+fn get_endpoints_str(ep: &Value) -> String {
+    let subsets = ep
+        .pointer("/subsets")
+        .and_then(Value::as_array)
+        .map(|subsets| subsets.iter())
+        .unwrap_or_default();
+    let mut ready = vec![];
+    let mut not_ready = vec![];
+    for subset in subsets {
+        let ports: Vec<String> = subset
+            .pointer("/ports")
+            .and_then(Value::as_array)
+            .map(|ports| {
+                ports
+                    .iter()
+                    .map(|port| val_u64("/port", port, 0).to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let addresses = |path: &str| -> Vec<String> {
+            subset
+                .pointer(path)
+                .and_then(Value::as_array)
+                .map(|addresses| {
+                    addresses
+                        .iter()
+                        .map(|address| {
+                            let mut line = val_str("/ip", address, "<No IP>").into_owned();
+                            if !ports.is_empty() {
+                                line.push(':');
+                                line.push_str(&ports.join(","));
+                            }
+                            if let Some(target) = val_str_opt("/targetRef/name", address) {
+                                line.push_str(&format!(" ({})", target));
+                            }
+                            line
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        ready.extend(addresses("/addresses"));
+        not_ready.extend(addresses("/notReadyAddresses"));
     }
-    if let Some(last) = buf.chars().last() {
-        if last == '\n' {
-            buf.pop();
-        }
+    if ready.is_empty() && not_ready.is_empty() {
+        return "<none>".to_string();
+    }
+    let mut lines = vec![format!(
+        "{} ready, {} not ready",
+        ready.len(),
+        not_ready.len()
+    )];
+    lines.extend(
+        ready
+            .into_iter()
+            .map(|address| format!("{} {}", address, "ready".green())),
+    );
+    lines.extend(
+        not_ready
+            .into_iter()
+            .map(|address| format!("{} {}", address, "not ready".red())),
+    );
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::styles::strip_ansi;
+    use serde_json::json;
+
+    #[test]
+    fn test_ports_str() {
+        let ports = json!([
+            { "name": "http", "port": 80, "protocol": "TCP", "targetPort": 8080 },
+            { "name": "grpc", "port": 9090, "protocol": "TCP", "targetPort": "grpc", "nodePort": 30090 },
+        ]);
+        assert_eq!(
+            get_ports_str(Some(&ports)),
+            "http 80/TCP -> 8080\ngrpc 9090/TCP -> grpc, NodePort 30090"
+        );
+        assert_eq!(get_ports_str(None), "<none>");
+    }
+
+    #[test]
+    fn test_endpoints_str() {
+        let ep = json!({
+            "subsets": [{
+                "addresses": [
+                    { "ip": "10.0.0.5", "targetRef": { "kind": "Pod", "name": "web-1" } },
+                ],
+                "notReadyAddresses": [
+                    { "ip": "10.0.0.6", "targetRef": { "kind": "Pod", "name": "web-2" } },
+                ],
+                "ports": [{ "port": 8080 }, { "port": 9090 }],
+            }]
+        });
+        assert_eq!(
+            strip_ansi(&get_endpoints_str(&ep)),
+            "1 ready, 1 not ready
+10.0.0.5:8080,9090 (web-1) ready
+10.0.0.6:8080,9090 (web-2) not ready"
+        );
+        assert_eq!(get_endpoints_str(&json!({})), "<none>");
     }
-    buf.into()
 }
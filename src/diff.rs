@@ -0,0 +1,226 @@
+// Copyright 2022 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Line based unified diffs, using the Myers diff algorithm
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// A line of a unified diff
+#[derive(Debug, PartialEq)]
+pub enum DiffLine<'a> {
+    /// a hunk header, like @@ -1,4 +1,5 @@
+    Hunk(String),
+    Context(&'a str),
+    Add(&'a str),
+    Remove(&'a str),
+}
+
+// Find the shortest edit script turning a into b
+fn diff_ops(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = n + m;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace = vec![];
+
+    'outer: for d in 0..=max {
+        trace.push(v.clone());
+        let mut k = -d;
+        while k <= d {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                break 'outer;
+            }
+            k += 2;
+        }
+    }
+
+    // walk back through the trace to find the path we took
+    let mut ops = vec![];
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let idx = (k + offset) as usize;
+        let prev_k = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal((x - 1) as usize, (y - 1) as usize));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                ops.push(Op::Insert((y - 1) as usize));
+            } else {
+                ops.push(Op::Delete((x - 1) as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+/// Build a unified diff between old and new, with context lines of unchanged text around each
+/// change. Returns an empty vec if there are no differences.
+pub fn unified_diff<'a>(old: &'a str, new: &'a str, context: usize) -> Vec<DiffLine<'a>> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = diff_ops(&a, &b);
+    let is_equal = |op: &Op| matches!(op, Op::Equal(_, _));
+
+    // the position in old and new before each op
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for op in ops.iter() {
+        positions.push((old_pos, new_pos));
+        match op {
+            Op::Equal(_, _) => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            Op::Delete(_) => old_pos += 1,
+            Op::Insert(_) => new_pos += 1,
+        }
+    }
+
+    let mut lines = vec![];
+    let mut i = 0;
+    while i < ops.len() {
+        if is_equal(&ops[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i.saturating_sub(context);
+        // extend the hunk over changes that are close enough to share context
+        let mut end = i;
+        loop {
+            while end < ops.len() && !is_equal(&ops[end]) {
+                end += 1;
+            }
+            let mut run = 0;
+            while end + run < ops.len() && is_equal(&ops[end + run]) {
+                run += 1;
+            }
+            if end + run < ops.len() && run <= 2 * context {
+                end += run;
+            } else {
+                break;
+            }
+        }
+        let end = (end + context).min(ops.len());
+
+        let hunk = &ops[start..end];
+        let old_len = hunk
+            .iter()
+            .filter(|op| !matches!(op, Op::Insert(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|op| !matches!(op, Op::Delete(_)))
+            .count();
+        let (old_start, new_start) = positions[start];
+        // unified diffs number lines from 1, except for empty ranges
+        let old_start = if old_len > 0 {
+            old_start + 1
+        } else {
+            old_start
+        };
+        let new_start = if new_len > 0 {
+            new_start + 1
+        } else {
+            new_start
+        };
+        lines.push(DiffLine::Hunk(format!(
+            "@@ -{},{} +{},{} @@",
+            old_start, old_len, new_start, new_len
+        )));
+        for op in hunk.iter() {
+            lines.push(match op {
+                Op::Equal(x, _) => DiffLine::Context(a[*x]),
+                Op::Delete(x) => DiffLine::Remove(a[*x]),
+                Op::Insert(y) => DiffLine::Add(b[*y]),
+            });
+        }
+        i = end;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_diff() {
+        assert!(unified_diff("a\nb\nc", "a\nb\nc", 3).is_empty());
+        assert!(unified_diff("", "", 3).is_empty());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj";
+        let new = "a\nb\nC\nd\ne\nf\ng\nh\ni\nj\nk";
+        assert_eq!(
+            unified_diff(old, new, 1),
+            vec![
+                DiffLine::Hunk("@@ -2,3 +2,3 @@".to_string()),
+                DiffLine::Context("b"),
+                DiffLine::Remove("c"),
+                DiffLine::Add("C"),
+                DiffLine::Context("d"),
+                DiffLine::Hunk("@@ -10,1 +10,2 @@".to_string()),
+                DiffLine::Context("j"),
+                DiffLine::Add("k"),
+            ]
+        );
+
+        // close changes share a hunk
+        let diff = unified_diff("a\nb\nc\nd", "x\nb\nc\ny", 1);
+        assert_eq!(diff[0], DiffLine::Hunk("@@ -1,4 +1,4 @@".to_string()));
+        assert_eq!(diff.len(), 7);
+
+        assert_eq!(
+            unified_diff("", "a", 3),
+            vec![
+                DiffLine::Hunk("@@ -0,0 +1,1 @@".to_string()),
+                DiffLine::Add("a")
+            ]
+        );
+    }
+}
@@ -12,32 +12,48 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::config::{self, Alias, ClickConfig, Config};
+use crate::config::{self, Alias, Bookmark, BookmarkObject, ClickConfig, Config};
 use crate::error::ClickError;
 use crate::kobj::{KObj, ObjType};
 use crate::output::ClickWriter;
 use crate::styles::Styles;
 
+use regex::Regex;
 use rustyline::config as rustyconfig;
 use strfmt::strfmt;
 use tempdir::TempDir;
 
+use std::cell::{Cell, RefCell};
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Child;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 // TODO: Maybe make less of this pub
 
 /// An ongoing port forward
 pub struct PortForward {
-    pub child: Child,
+    // a forward to a service replaces this when kubectl exits
+    pub child: Arc<Mutex<Child>>,
+    /// the pod, or svc/NAME for a service
     pub pod: String,
+    pub namespace: String,
     pub ports: Vec<String>,
     pub output: Arc<Mutex<String>>,
+    /// tells a forward that restarts kubectl to stop doing so
+    pub stopped: Arc<AtomicBool>,
+}
+
+impl PortForward {
+    fn stop(&mut self) -> Result<(), std::io::Error> {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.child.lock().unwrap().kill()
+    }
 }
 
 #[derive(Debug)]
@@ -46,13 +62,25 @@ pub struct ExpandedAlias<'a> {
     pub rest: &'a str,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ObjectSelection {
     Single(KObj),
     Range(Vec<KObj>),
     None,
 }
 
+/// How many locations back can go through
+const MAX_LOCATIONS: usize = 100;
+
+/// Where the user is: their context, namespace and selection. back and forward move between these
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Location {
+    context: Option<String>,
+    namespace: Option<String>,
+    selection: ObjectSelection,
+    range_str: Option<String>,
+}
+
 /// Keep track of our repl environment
 pub struct Env {
     pub config: Config,
@@ -61,15 +89,37 @@ pub struct Env {
     click_config_path: PathBuf,
     pub quit: bool,
     pub need_new_editor: bool,
+    /// colors were turned off by CLICK_NO_COLOR or NO_COLOR. This isn't saved to the config
+    pub no_color: bool,
     pub context: Option<super::k8s::Context>,
     pub namespace: Option<String>,
     current_selection: ObjectSelection,
     last_objs: Option<Vec<KObj>>,
+    // set when a command sets last_objs, so the repl can open the picker for long lists
+    new_objs: bool,
     pub ctrlcbool: Arc<AtomicBool>,
     port_forwards: Vec<PortForward>,
+    snapshots: RefCell<HashMap<String, String>>,
+    /// the openapi schema of each context, once the explain command has loaded it
+    openapi_schemas: RefCell<HashMap<String, Rc<serde_json::Value>>>,
+    /// what the last command printed, for the grep command
+    last_output: String,
+    // set by commands that print secrets, so what they print isn't kept in last_output
+    sensitive_output: bool,
+    /// the name of the running command (like pods, not an alias), set by exec_match
+    command_name: String,
+    /// matches of this are highlighted in logs and tables
+    pub highlight: Option<Regex>,
     pub prompt: String,
     range_str: Option<String>,
+    back_locations: Vec<Location>,
+    forward_locations: Vec<Location>,
+    // set by back and forward, so the move they make isn't recorded as a new one
+    navigated: bool,
     pub tempdir: std::io::Result<TempDir>,
+    /// time spent waiting for the user to answer prompts, so it isn't counted as the time a
+    /// command took
+    prompt_time: Cell<Duration>,
 }
 
 lazy_static! {
@@ -88,12 +138,10 @@ impl Env {
     pub fn new(config: Config, click_config: ClickConfig, click_config_path: PathBuf) -> Env {
         let namespace = click_config.namespace.clone();
         let context = click_config.context.clone();
-        let styles = Styles::new();
-        let nones = (
-            styles.prompt_context("none"),
-            styles.prompt_namespace("none"),
-            styles.prompt_select_none("none"),
-        );
+        let mut styles = Styles::new();
+        if let Err(e) = styles.set_theme(click_config.theme, &click_config.theme_colors) {
+            println!("[WARN] Invalid theme_colors in click config: {}", e);
+        }
         let mut env = Env {
             config,
             click_config,
@@ -101,16 +149,29 @@ impl Env {
             click_config_path,
             quit: false,
             need_new_editor: false,
+            no_color: false,
             context: None,
             namespace,
             current_selection: ObjectSelection::None,
             last_objs: None,
+            new_objs: false,
             ctrlcbool: CTC_BOOL.clone(),
             port_forwards: Vec::new(),
-            prompt: format!("[{}] [{}] [{}] > ", nones.0, nones.1, nones.2,),
+            snapshots: RefCell::new(HashMap::new()),
+            openapi_schemas: RefCell::new(HashMap::new()),
+            last_output: String::new(),
+            sensitive_output: false,
+            command_name: String::new(),
+            highlight: None,
+            prompt: String::new(),
             range_str: None,
+            back_locations: Vec::new(),
+            forward_locations: Vec::new(),
+            navigated: false,
             tempdir: TempDir::new("click"),
+            prompt_time: Cell::new(Duration::ZERO),
         };
+        env.set_prompt();
         env.set_context(context.as_deref());
         env
     }
@@ -127,8 +188,67 @@ impl Env {
             .unwrap();
     }
 
+    /// Fill in a prompt template (see 'help prompt'). Returns an error if the template is invalid
+    pub fn format_prompt(&self, template: &str) -> Result<String, String> {
+        let context = self
+            .context
+            .as_ref()
+            .map(|c| c.name.as_str())
+            .unwrap_or("none");
+        // long context names, like EKS ARNs, usually end with the part we care about
+        let context_short = context.rsplit(['/', ':']).next().unwrap_or(context);
+        let (kind, name, selection) = match self.current_selection {
+            ObjectSelection::Single(ref obj) => (obj.type_str(), obj.name(), obj.name()),
+            ObjectSelection::Range(_) => ("range", "none", self.range_str.as_deref().unwrap_or("")),
+            ObjectSelection::None => ("none", "none", "none"),
+        };
+        let mut vars = HashMap::new();
+        vars.insert("context".to_string(), context.to_string());
+        vars.insert("context_short".to_string(), context_short.to_string());
+        vars.insert(
+            "namespace".to_string(),
+            self.namespace.as_deref().unwrap_or("none").to_string(),
+        );
+        vars.insert("kind".to_string(), kind.to_string());
+        vars.insert("name".to_string(), name.to_string());
+        vars.insert("selection".to_string(), selection.to_string());
+        for token in [
+            "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white", "bold", "reset",
+        ] {
+            vars.insert(token.to_string(), Styles::prompt_token(token).unwrap());
+            // safe, all tokens
+        }
+        strfmt(template, &vars).map_err(|e| format!("Invalid prompt: {}", e))
+    }
+
+    /// Set the prompt template, or go back to the default prompt with None
+    pub fn set_prompt_template(&mut self, template: Option<&str>) -> Result<(), String> {
+        if let Some(template) = template {
+            self.format_prompt(template)?;
+        }
+        self.click_config.prompt = template.map(|t| t.to_string());
+        self.set_prompt();
+        Ok(())
+    }
+
+    /// Apply the theme and theme_colors from the click config. On error the valid parts are
+    /// still applied
+    pub fn apply_theme(&mut self) -> Result<(), String> {
+        let res = self
+            .styles
+            .set_theme(self.click_config.theme, &self.click_config.theme_colors);
+        self.set_prompt();
+        res
+    }
+
     // sets the prompt string based on current settings
     fn set_prompt(&mut self) {
+        if let Some(template) = self.click_config.prompt.as_ref() {
+            if let Ok(prompt) = self.format_prompt(template) {
+                self.prompt = prompt;
+                return;
+            }
+        }
         self.prompt = format!(
             "[{}] [{}] [{}] > ",
             if let Some(ref c) = self.context {
@@ -196,6 +316,59 @@ impl Env {
         self.click_config.editor = editor.map(|s| s.to_string());
     }
 
+    /// The editor to open files in: the editor option, or $EDITOR if that's not set
+    pub fn editor(&self) -> Result<String, ClickError> {
+        match self.click_config.editor {
+            Some(ref editor) => Ok(editor.clone()),
+            None => std::env::var("EDITOR").map_err(|e| {
+                ClickError::CommandError(format!(
+                    "Could not get EDITOR environment variable: {}",
+                    e
+                ))
+            }),
+        }
+    }
+
+    /// Should tables and other output be colored
+    pub fn colors(&self) -> bool {
+        self.click_config.colors && !self.no_color
+    }
+
+    /// The pager to send long output through: the pager option, or $PAGER, or less
+    pub fn pager(&self) -> String {
+        self.click_config
+            .pager
+            .clone()
+            .or_else(|| std::env::var("PAGER").ok())
+            .unwrap_or_else(|| "less".to_string())
+    }
+
+    pub fn click_config_path(&self) -> &Path {
+        self.click_config_path.as_path()
+    }
+
+    /// Re-read the click config from its file, after it's been edited. The current context and
+    /// namespace are kept
+    pub fn reload_click_config(&mut self) -> Result<(), ClickError> {
+        let click_config =
+            ClickConfig::from_file(self.click_config_path.as_path().to_str().unwrap())?;
+        self.styles
+            .set_theme(click_config.theme, &click_config.theme_colors)
+            .map_err(|e| ClickError::ConfigFileError(format!("Invalid theme_colors: {}", e)))?;
+        self.click_config = click_config;
+        self.need_new_editor = true;
+        // pick up any new timeouts
+        self.reload_context();
+        self.set_prompt();
+        Ok(())
+    }
+
+    /// Load the current context again, so it uses the current timeouts
+    pub fn reload_context(&mut self) {
+        let context = self.context.as_ref().map(|c| c.name.clone());
+        self.set_context(context.as_deref());
+    }
+
     pub fn set_terminal(&mut self, terminal: Option<&str>) {
         self.click_config.terminal = terminal.map(|s| s.to_string());
     }
@@ -247,6 +420,60 @@ impl Env {
 
     pub fn set_last_objs<T: Into<Vec<KObj>>>(&mut self, objs: T) {
         self.last_objs = Some(objs.into());
+        self.new_objs = true;
+    }
+
+    /// Has last_objs been set since the last call to this function
+    pub fn take_new_objs(&mut self) -> bool {
+        std::mem::replace(&mut self.new_objs, false)
+    }
+
+    /// Should destructive commands ask before running in the current context (see the confirm
+    /// and dangerous_contexts options)
+    pub fn needs_confirmation(&self) -> bool {
+        self.click_config
+            .needs_confirmation(self.context.as_ref().map(|c| c.name.as_str()))
+    }
+
+    /// Open the fuzzy finder over the objects from the last list, and select the one that's
+    /// picked. Returns false if nothing was picked
+    pub fn pick_object(&mut self, query: &str) -> Result<bool, ClickError> {
+        let items: Vec<String> = self
+            .last_objs()
+            .iter()
+            .map(|obj| match obj.namespace.as_deref() {
+                Some(ns) => format!("{}  ({}, {})", obj.name(), obj.type_str(), ns),
+                None => format!("{}  ({})", obj.name(), obj.type_str()),
+            })
+            .collect();
+        let start = Instant::now();
+        let picked = crate::picker::pick(&items, query);
+        self.add_prompt_time(start.elapsed());
+        match picked? {
+            Some(index) => {
+                self.set_current(index);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Read the user's answer to a prompt from stdin, recording how long they took
+    pub fn read_response(&self) -> io::Result<String> {
+        let start = Instant::now();
+        let mut response = String::new();
+        let res = io::stdin().read_line(&mut response);
+        self.add_prompt_time(start.elapsed());
+        res.map(|_| response)
+    }
+
+    fn add_prompt_time(&self, time: Duration) {
+        self.prompt_time.set(self.prompt_time.get() + time);
+    }
+
+    /// How long was spent waiting on prompts since the last call
+    pub fn take_prompt_time(&self) -> Duration {
+        self.prompt_time.take()
     }
 
     pub fn clear_last_objs(&mut self) {
@@ -259,6 +486,16 @@ impl Env {
         self.set_prompt();
     }
 
+    /// The objects returned by the last list command
+    pub fn last_objs(&self) -> &[KObj] {
+        self.last_objs.as_deref().unwrap_or(&[])
+    }
+
+    /// The index of the first object in the last list with the given name
+    pub fn item_index(&self, name: &str) -> Option<usize> {
+        self.last_objs().iter().position(|obj| obj.name() == name)
+    }
+
     /// get the item from the last list at the specified index
     pub fn item_at(&self, index: usize) -> Option<&KObj> {
         self.last_objs.as_ref().and_then(|lo| lo.get(index))
@@ -289,6 +526,118 @@ impl Env {
         self.set_prompt();
     }
 
+    pub fn location(&self) -> Location {
+        Location {
+            context: self.context.as_ref().map(|c| c.name.clone()),
+            namespace: self.namespace.clone(),
+            selection: self.current_selection.clone(),
+            range_str: self.range_str.clone(),
+        }
+    }
+
+    /// Record that a command moved from the location from to the current one, so back can return
+    /// to it. Does nothing if the command didn't move, or was back or forward
+    pub fn moved_from(&mut self, from: Location) {
+        if std::mem::take(&mut self.navigated) || from == self.location() {
+            return;
+        }
+        self.back_locations.push(from);
+        if self.back_locations.len() > MAX_LOCATIONS {
+            self.back_locations.remove(0);
+        }
+        self.forward_locations.clear();
+    }
+
+    fn go_to(&mut self, location: Location) {
+        let current_context = self.context.as_ref().map(|c| c.name.as_str());
+        if location.context.is_some() && location.context.as_deref() != current_context {
+            self.set_context(location.context.as_deref());
+        }
+        self.namespace = location.namespace;
+        self.current_selection = location.selection;
+        self.range_str = location.range_str;
+        self.set_prompt();
+    }
+
+    /// Save the current context, namespace and selected object as a bookmark called name. Ranges
+    /// can't be bookmarked
+    pub fn save_bookmark(&mut self, name: &str) -> Result<(), ClickError> {
+        let object = match self.current_selection {
+            ObjectSelection::Single(ref obj) => Some(BookmarkObject::from(obj)),
+            ObjectSelection::Range(_) => {
+                return Err(ClickError::CommandError(
+                    "Can't bookmark a range, select a single object or clear the range first"
+                        .to_string(),
+                ))
+            }
+            ObjectSelection::None => None,
+        };
+        let bookmark = Bookmark {
+            context: self.context.as_ref().map(|c| c.name.clone()),
+            namespace: self.namespace.clone(),
+            object,
+        };
+        self.click_config
+            .bookmarks
+            .insert(name.to_string(), bookmark);
+        // save now, so other running clicks see the bookmark
+        self.save_click_config();
+        Ok(())
+    }
+
+    /// Go to the context, namespace and object saved in the bookmark called name
+    pub fn go_to_bookmark(&mut self, name: &str) -> Result<(), ClickError> {
+        let bookmark = self
+            .click_config
+            .bookmarks
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ClickError::CommandError(format!("No bookmark named {}", name)))?;
+        let selection = match bookmark.object {
+            Some(obj) => ObjectSelection::Single(obj.to_kobj().ok_or_else(|| {
+                ClickError::CommandError(format!(
+                    "Bookmark {} is of a {}, which this click can't select",
+                    name, obj.kind
+                ))
+            })?),
+            None => ObjectSelection::None,
+        };
+        self.go_to(Location {
+            context: bookmark.context,
+            namespace: bookmark.namespace,
+            selection,
+            range_str: None,
+        });
+        Ok(())
+    }
+
+    /// Go back to where the user was before their last move. Returns false if there's nowhere to
+    /// go back to
+    pub fn back(&mut self) -> bool {
+        match self.back_locations.pop() {
+            Some(location) => {
+                self.forward_locations.push(self.location());
+                self.go_to(location);
+                self.navigated = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Undo a back. Returns false if there's nowhere to go forward to
+    pub fn forward(&mut self) -> bool {
+        match self.forward_locations.pop() {
+            Some(location) => {
+                self.back_locations.push(self.location());
+                self.go_to(location);
+                self.navigated = true;
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn current_pod(&self) -> Option<&KObj> {
         match self.current_selection {
             ObjectSelection::Single(ref obj) => match obj.typ {
@@ -299,19 +648,22 @@ impl Env {
         }
     }
 
-    // the function. print its error if an error happens. return true if the loop should continue,
-    // false if it should stop
+    // the function. print its error and add the object to failed if an error happens. return true
+    // if the loop should continue, false if it should stop
     fn call_selection_func<F>(
+        &self,
         obj: &KObj,
         writer: &mut ClickWriter,
         f: &mut F,
         continue_all: &mut bool,
+        failed: &mut Vec<String>,
     ) -> bool
     where
         F: FnMut(&KObj, &mut ClickWriter) -> Result<(), ClickError>,
     {
         if let Err(e) = f(obj, writer) {
             clickwriteln!(writer, "Error applying operation to {}: {}", obj.name, e);
+            failed.push(obj.name.clone());
             if *continue_all {
                 return true;
             }
@@ -319,9 +671,8 @@ impl Env {
             clickwriteln!(writer, "  a = all: continue over all future errors");
             clickwriteln!(writer, "  n/N = no: abort range operation (default)");
             clickwrite!(writer, "Continue? [o/a/N]? ");
-            io::stdout().flush().expect("Could not flush stdout");
-            let mut conf = String::new();
-            if io::stdin().read_line(&mut conf).is_ok() {
+            writer.flush().expect("Could not flush output");
+            if let Ok(conf) = self.read_response() {
                 match conf.trim() {
                     "o" | "once" => true,
                     "a" | "all" => {
@@ -339,6 +690,20 @@ impl Env {
         }
     }
 
+    // report how a range operation went, after trying it on done objects
+    fn write_range_summary(writer: &mut ClickWriter, done: usize, failed: &[String]) {
+        clickwriteln!(
+            writer,
+            "Ran on {} objects: {} succeeded, {} failed",
+            done,
+            done - failed.len(),
+            failed.len()
+        );
+        for name in failed.iter() {
+            clickwriteln!(writer, "  failed: {}", name);
+        }
+    }
+
     // apply a function to each selected object.
     pub fn apply_to_selection<F>(
         &self,
@@ -354,12 +719,16 @@ impl Env {
             ObjectSelection::Range(range) => {
                 let mut continue_all = false;
                 let mut go = true;
+                let mut failed = vec![];
+                let mut done = 0;
                 for obj in range.iter() {
                     if !go {
+                        Env::write_range_summary(writer, done, &failed);
                         return Err(ClickError::CommandError(
                             "Aborting range action".to_string(),
                         ));
                     }
+                    done += 1;
                     if let Some(fmt) = sepfmt {
                         let mut fmtvars = HashMap::new();
                         fmtvars.insert("name".to_string(), obj.name());
@@ -370,11 +739,12 @@ impl Env {
                         match strfmt(fmt, &fmtvars) {
                             Ok(sep) => {
                                 clickwriteln!(writer, "{}", sep);
-                                go = Env::call_selection_func(
+                                go = self.call_selection_func(
                                     obj,
                                     writer,
                                     &mut f,
                                     &mut continue_all,
+                                    &mut failed,
                                 );
                             }
                             Err(e) => {
@@ -384,18 +754,26 @@ impl Env {
                                     obj.name(),
                                     e
                                 );
-                                go = Env::call_selection_func(
+                                go = self.call_selection_func(
                                     obj,
                                     writer,
                                     &mut f,
                                     &mut continue_all,
+                                    &mut failed,
                                 );
                             }
                         }
                     } else {
-                        go = Env::call_selection_func(obj, writer, &mut f, &mut continue_all);
+                        go = self.call_selection_func(
+                            obj,
+                            writer,
+                            &mut f,
+                            &mut continue_all,
+                            &mut failed,
+                        );
                     }
                 }
+                Env::write_range_summary(writer, done, &failed);
                 Ok(())
             }
             ObjectSelection::None => Err(ClickError::CommandError(
@@ -404,6 +782,16 @@ impl Env {
         }
     }
 
+    /// Show the warnings about objects the context could only partly read. They go to stderr, so
+    /// they aren't mixed into output that's piped or redirected
+    pub fn show_warnings(&self) {
+        if let Some(context) = self.context.as_ref() {
+            for warning in context.take_warnings() {
+                writeln!(io::stderr(), "{}", self.styles.warning(&warning)).unwrap_or(());
+            }
+        }
+    }
+
     pub fn run_on_context<F, R>(&self, f: F) -> Result<R, ClickError>
     where
         F: FnOnce(&crate::k8s::Context) -> Result<R, ClickError>,
@@ -430,7 +818,7 @@ impl Env {
     pub fn stop_port_forward(&mut self, i: usize) -> Result<(), std::io::Error> {
         if i < self.port_forwards.len() {
             let mut pf = self.port_forwards.remove(i);
-            pf.child.kill()
+            pf.stop()
         } else {
             Ok(())
         }
@@ -438,11 +826,60 @@ impl Env {
 
     pub fn stop_all_forwards(&mut self) {
         for pf in self.port_forwards.iter_mut() {
-            pf.child.kill().unwrap();
+            pf.stop().unwrap();
         }
         self.port_forwards = Vec::new();
     }
 
+    /// Save a snapshot of an object for the diff command, returning the previous one if any
+    pub fn replace_snapshot(&self, key: &str, snapshot: String) -> Option<String> {
+        self.snapshots
+            .borrow_mut()
+            .insert(key.to_string(), snapshot)
+    }
+
+    pub fn remove_snapshot(&self, key: &str) -> Option<String> {
+        self.snapshots.borrow_mut().remove(key)
+    }
+
+    pub fn openapi_schema(&self, context: &str) -> Option<Rc<serde_json::Value>> {
+        self.openapi_schemas.borrow().get(context).cloned()
+    }
+
+    pub fn set_openapi_schema(&self, context: &str, schema: Rc<serde_json::Value>) {
+        self.openapi_schemas
+            .borrow_mut()
+            .insert(context.to_string(), schema);
+    }
+
+    pub fn set_last_output(&mut self, output: String) {
+        self.last_output = output;
+    }
+
+    pub fn last_output(&self) -> &str {
+        &self.last_output
+    }
+
+    /// Mark what the running command prints as sensitive (like revealed secrets), so it's not
+    /// kept for grep
+    pub fn mark_output_sensitive(&mut self) {
+        self.sensitive_output = true;
+    }
+
+    /// The name of the running command, like pods, for looking up its settings in the config
+    pub fn command_name(&self) -> &str {
+        &self.command_name
+    }
+
+    pub fn set_command_name(&mut self, name: &str) {
+        self.command_name = name.to_string();
+    }
+
+    /// Has the output been marked sensitive since the last call to this function
+    pub fn take_output_sensitive(&mut self) -> bool {
+        std::mem::replace(&mut self.sensitive_output, false)
+    }
+
     /// Try and expand alias.
     /// This function looks at the first word (whitespace delimited) of the
     /// line, checks if it matches an alias, if it does it returns and ExpandedAlias with the
@@ -508,13 +945,32 @@ impl fmt::Display for Env {
   Current Context: {}
   Availble Contexts: {:?}
   Kubernetes Config File(s): {}
+  Click Config File: {}
   Completion Type: {}
   Edit Mode: {}
   Editor: {}
+  Pager: {}
   Terminal: {}
   kubectl Binary: {}
   Range Separator: {}
   Describe Shows Events: {}
+  Colors: {}
+  Absolute Times: {}
+  Use Pager: {}
+  Default Output: {}
+  Connect Timeout: {}
+  Read Timeout: {}
+  Status Glyphs: {}
+  Timezone: {}
+  History Size: {}
+  Prompt: {}
+  Theme: {}
+  Theme Colors: {}
+  Pick Threshold: {}
+  Confirm: {}
+  Dangerous Contexts: {}
+  Notify After: {}
+  Notify: {}
 }}",
             if let Some(ref c) = self.context {
                 self.styles.config_val(c.name.as_str())
@@ -523,6 +979,8 @@ impl fmt::Display for Env {
             },
             self.config.contexts.keys(),
             self.styles.config_val(self.config.source_file.as_str()),
+            self.styles
+                .config_val_string(self.click_config_path.display().to_string()),
             {
                 let ctstr: String = (&self.click_config.completiontype).into();
                 self.styles.config_val_string(ctstr)
@@ -537,6 +995,12 @@ impl fmt::Display for Env {
                     .as_deref()
                     .unwrap_or("<unset, will use $EDITOR>")
             ),
+            self.styles.config_val(
+                self.click_config
+                    .pager
+                    .as_deref()
+                    .unwrap_or("<unset, will use $PAGER or less>")
+            ),
             self.styles.config_val(
                 self.click_config
                     .terminal
@@ -552,6 +1016,71 @@ impl fmt::Display for Env {
                     .to_string()
                     .as_str()
             ),
+            self.styles.config_val_string(if self.no_color {
+                "false (turned off by CLICK_NO_COLOR or NO_COLOR)".to_string()
+            } else {
+                self.click_config.colors.to_string()
+            }),
+            self.styles
+                .config_val(self.click_config.absolute_times.to_string().as_str()),
+            self.styles
+                .config_val(self.click_config.use_pager.to_string().as_str()),
+            self.styles.config_val(
+                self.click_config
+                    .default_output
+                    .as_deref()
+                    .unwrap_or("<unset, will use table>")
+            ),
+            self.styles
+                .config_val_string(format!("{}s", self.click_config.connect_timeout_secs)),
+            self.styles
+                .config_val_string(format!("{}s", self.click_config.read_timeout_secs)),
+            self.styles
+                .config_val_string(self.click_config.status_glyphs.to_string()),
+            self.styles.config_val(
+                self.click_config
+                    .timezone
+                    .as_deref()
+                    .unwrap_or("<unset, will use local time>")
+            ),
+            self.styles
+                .config_val(self.click_config.history_size.to_string().as_str()),
+            self.styles.config_val(
+                self.click_config
+                    .prompt
+                    .as_deref()
+                    .unwrap_or("<unset, will use the default prompt>")
+            ),
+            self.styles
+                .config_val_string(self.click_config.theme.to_string()),
+            if self.click_config.theme_colors.is_empty() {
+                self.styles.config_val_string("none".to_string())
+            } else {
+                self.styles.config_val_string(
+                    self.click_config
+                        .theme_colors
+                        .iter()
+                        .map(|(element, color)| format!("{}={}", element, color))
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                )
+            },
+            self.styles
+                .config_val_string(match self.click_config.pick_threshold {
+                    Some(threshold) => threshold.to_string(),
+                    None => "<unset, won't open the picker automatically>".to_string(),
+                }),
+            self.styles
+                .config_val_string(self.click_config.confirm.to_string()),
+            self.styles
+                .config_val_string(self.click_config.dangerous_contexts.join(", ")),
+            self.styles
+                .config_val_string(match self.click_config.notify_after_secs {
+                    Some(secs) => format!("{}s", secs),
+                    None => "<unset, won't notify>".to_string(),
+                }),
+            self.styles
+                .config_val_string(self.click_config.notify.to_string()),
         )
     }
 }
@@ -596,4 +1125,129 @@ mod tests {
         assert_eq!(exp4.expansion, None);
         assert_eq!(exp4.rest, "x");
     }
+
+    #[test]
+    fn back_and_forward() {
+        let mut env = Env::new(
+            get_test_config(),
+            ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        env.set_last_objs(vec![
+            KObj {
+                name: "web-1".to_string(),
+                namespace: None,
+                typ: ObjType::Node,
+            },
+            KObj {
+                name: "web-2".to_string(),
+                namespace: None,
+                typ: ObjType::Node,
+            },
+        ]);
+        assert!(!env.back());
+
+        let start = env.location();
+        env.set_current(0);
+        env.moved_from(start.clone());
+        let first = env.location();
+        env.set_current(1);
+        env.moved_from(first.clone());
+        let second = env.location();
+
+        // staying put isn't a move
+        env.moved_from(second.clone());
+
+        assert!(env.back());
+        env.moved_from(second.clone());
+        assert_eq!(env.location(), first);
+        assert!(env.back());
+        assert_eq!(env.location(), start);
+        assert!(!env.back());
+
+        assert!(env.forward());
+        assert_eq!(env.location(), first);
+        assert!(env.forward());
+        assert_eq!(env.location(), second);
+        assert!(!env.forward());
+
+        // moving somewhere new drops the forward locations
+        assert!(env.back());
+        env.moved_from(first.clone());
+        env.clear_current();
+        env.moved_from(first);
+        assert!(!env.forward());
+    }
+
+    #[test]
+    fn go_to_bookmark() {
+        let mut env = Env::new(
+            get_test_config(),
+            ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        let obj = KObj {
+            name: "api-7f9c".to_string(),
+            namespace: Some("payments".to_string()),
+            typ: ObjType::Pod {
+                containers: vec!["api".to_string()],
+            },
+        };
+        env.click_config.bookmarks.insert(
+            "api".to_string(),
+            Bookmark {
+                context: None,
+                namespace: Some("payments".to_string()),
+                object: Some(BookmarkObject::from(&obj)),
+            },
+        );
+        assert!(env.go_to_bookmark("nope").is_err());
+        env.go_to_bookmark("api").unwrap();
+        assert_eq!(env.namespace.as_deref(), Some("payments"));
+        assert_eq!(env.current_selection(), &ObjectSelection::Single(obj));
+    }
+
+    #[test]
+    fn test_format_prompt() {
+        let cc = ClickConfig {
+            namespace: Some("kube-system".to_string()),
+            prompt: Some("{namespace:.4} [{kind}/{selection}] > ".to_string()),
+            ..Default::default()
+        };
+        let mut env = Env::new(get_test_config(), cc, PathBuf::from("/tmp/click.config"));
+        assert_eq!(env.prompt, "kube [none/none] > ");
+
+        env.set_last_objs(vec![KObj {
+            name: "web-1".to_string(),
+            namespace: None,
+            typ: ObjType::Node,
+        }]);
+        env.set_current(0);
+        assert_eq!(env.prompt, "kube [Node/web-1] > ");
+
+        assert_eq!(
+            env.format_prompt("{bold}{context}{reset}").unwrap(),
+            "\u{1b}[1mnone\u{1b}[0m"
+        );
+        assert!(env.set_prompt_template(Some("{unknown}")).is_err());
+        assert_eq!(env.prompt, "kube [Node/web-1] > ");
+
+        env.set_prompt_template(None).unwrap();
+        assert!(env.prompt.ends_with(" > "));
+        assert!(env.prompt.starts_with('['));
+    }
+
+    #[test]
+    fn test_prompt_time() {
+        let env = Env::new(
+            get_test_config(),
+            ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        assert_eq!(env.take_prompt_time(), Duration::ZERO);
+        env.add_prompt_time(Duration::from_secs(3));
+        env.add_prompt_time(Duration::from_millis(500));
+        assert_eq!(env.take_prompt_time(), Duration::from_millis(3500));
+        assert_eq!(env.take_prompt_time(), Duration::ZERO);
+    }
 }
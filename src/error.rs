@@ -15,7 +15,8 @@
 use std::convert::From;
 use std::{env, error, fmt, io};
 
-use serde_json::Value;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Status;
+use k8s_openapi::http::StatusCode;
 
 #[derive(Debug)]
 pub enum ClickErrNo {
@@ -23,7 +24,6 @@ pub enum ClickErrNo {
     InvalidCluster,
     InvalidUser,
     NoTokenAvailable,
-    Unauthorized,
     Unknown,
 }
 
@@ -38,10 +38,6 @@ impl fmt::Display for ClickErrNo {
             ClickErrNo::InvalidCluster => write!(f, "Invalid Cluster Name"),
             ClickErrNo::InvalidUser => write!(f, "Invalid User Name"),
             ClickErrNo::NoTokenAvailable => write!(f, "{}", NO_TOKEN_STR),
-            ClickErrNo::Unauthorized => write!(
-                f,
-                "Not authorized to talk to cluster, check credentials in config"
-            ),
             ClickErrNo::Unknown => write!(f, "Unknown error talking to cluster"),
         }
     }
@@ -54,9 +50,6 @@ impl error::Error for ClickErrNo {
             ClickErrNo::InvalidCluster => "Invalid Cluster Name",
             ClickErrNo::InvalidUser => "Invalid User Name",
             ClickErrNo::NoTokenAvailable => NO_TOKEN_STR,
-            ClickErrNo::Unauthorized => {
-                "Not authorized to talk to cluster, check credentials in config"
-            }
             ClickErrNo::Unknown => "Unknown error talking to cluster",
         }
     }
@@ -81,8 +74,80 @@ pub enum ClickError {
     Clap(clap::Error),
     JoinPathsError(env::JoinPathsError),
     Pem(pem::PemError),
-    Reqwest(reqwest::Error, Option<Value>),
+    Reqwest(reqwest::Error),
     UrlParse(url::ParseError),
+    /// The api server didn't succeed, and said why in the Status it returned
+    ApiStatus(StatusCode, Box<Status>),
+}
+
+impl ClickError {
+    /// The error for a response that wasn't a success. The body is usually a Status, but proxies
+    /// in front of the api server can return anything, so the body is used as the message if it
+    /// isn't
+    pub fn from_response(code: StatusCode, body: &[u8]) -> ClickError {
+        let status = serde_json::from_slice::<Status>(body).unwrap_or_else(|_| {
+            let body = String::from_utf8_lossy(body);
+            let message = body.trim();
+            Status {
+                message: (!message.is_empty()).then(|| message.chars().take(500).collect()),
+                ..Default::default()
+            }
+        });
+        ClickError::ApiStatus(code, Box::new(status))
+    }
+
+    /// If this is an error from the api server with this status code
+    pub fn is_status(&self, code: StatusCode) -> bool {
+        matches!(self, ClickError::ApiStatus(status, _) if *status == code)
+    }
+}
+
+fn write_status(f: &mut fmt::Formatter, code: StatusCode, status: &Status) -> fmt::Result {
+    let message = status.message.as_deref().unwrap_or("no message returned");
+    match code {
+        StatusCode::UNAUTHORIZED => write!(
+            f,
+            "Unauthorized: the api server didn't accept the credentials for this context. Check \
+             them in your kubeconfig, or run a kubectl command against the cluster to refresh \
+             them"
+        )?,
+        StatusCode::FORBIDDEN => write!(f, "Forbidden: {}", message)?,
+        StatusCode::NOT_FOUND => write!(f, "Not found: {}", message)?,
+        StatusCode::CONFLICT if status.reason.as_deref() == Some("AlreadyExists") => {
+            write!(f, "Already exists: {}", message)?
+        }
+        StatusCode::CONFLICT => write!(
+            f,
+            "Conflict: {}. Something else changed the object, so get it again and retry",
+            message.trim_end_matches(
+                "; please apply your changes to the latest version and try again"
+            )
+        )?,
+        _ => write!(
+            f,
+            "Error from the api server ({}): {}",
+            status
+                .reason
+                .as_deref()
+                .or_else(|| code.canonical_reason())
+                .unwrap_or_else(|| code.as_str()),
+            message
+        )?,
+    }
+    let causes = status
+        .details
+        .as_ref()
+        .and_then(|details| details.causes.as_ref());
+    for cause in causes.into_iter().flatten() {
+        let cause_message = cause.message.as_deref().unwrap_or("");
+        match cause.field.as_deref() {
+            Some(field) if !cause_message.contains(field) => {
+                write!(f, "\n  {}: {}", field, cause_message)?
+            }
+            _ => write!(f, "\n  {}", cause_message)?,
+        }
+    }
+    Ok(())
 }
 
 impl fmt::Display for ClickError {
@@ -117,11 +182,12 @@ impl fmt::Display for ClickError {
                     write!(f, "Response contained invalid utf-8 data: {}", e)
                 }
             },
-            ClickError::Clap(ref err) => write!(f, "Error in clap: {}", err),
+            ClickError::Clap(ref err) => write!(f, "{}", err.to_string().trim_end()),
             ClickError::JoinPathsError(ref err) => write!(f, "Join paths error: {}", err),
             ClickError::Pem(ref err) => write!(f, "Pem error: {}", err),
-            ClickError::Reqwest(ref err, _) => write!(f, "Reqwest error: {}", err),
+            ClickError::Reqwest(ref err) => write!(f, "Reqwest error: {}", err),
             ClickError::UrlParse(ref err) => write!(f, "Error parsing url: {}", err),
+            ClickError::ApiStatus(code, ref status) => write_status(f, code, status),
         }
     }
 }
@@ -142,8 +208,9 @@ impl error::Error for ClickError {
             ClickError::Clap(ref err) => Some(err),
             ClickError::JoinPathsError(ref err) => Some(err),
             ClickError::Pem(ref err) => Some(err),
-            ClickError::Reqwest(ref err, _) => Some(err),
+            ClickError::Reqwest(ref err) => Some(err),
             ClickError::UrlParse(ref err) => Some(err),
+            ClickError::ApiStatus(..) => None,
         }
     }
 }
@@ -200,7 +267,7 @@ impl From<pem::PemError> for ClickError {
 
 impl From<reqwest::Error> for ClickError {
     fn from(err: reqwest::Error) -> ClickError {
-        ClickError::Reqwest(err, None)
+        ClickError::Reqwest(err)
     }
 }
 
@@ -209,3 +276,66 @@ impl From<url::ParseError> for ClickError {
         ClickError::UrlParse(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_status() {
+        let body = br#"{
+            "kind": "Status",
+            "apiVersion": "v1",
+            "status": "Failure",
+            "message": "pods is forbidden: User \"dev\" cannot list resource \"pods\" in API group \"\" in the namespace \"prod\"",
+            "reason": "Forbidden",
+            "code": 403
+        }"#;
+        let err = ClickError::from_response(StatusCode::FORBIDDEN, body);
+        assert!(err.is_status(StatusCode::FORBIDDEN));
+        assert!(!err.is_status(StatusCode::NOT_FOUND));
+        assert_eq!(
+            err.to_string(),
+            "Forbidden: pods is forbidden: User \"dev\" cannot list resource \"pods\" in API group \
+             \"\" in the namespace \"prod\""
+        );
+
+        let body = br#"{
+            "kind": "Status",
+            "apiVersion": "v1",
+            "message": "Operation cannot be fulfilled on deployments.apps \"web\": the object has been modified; please apply your changes to the latest version and try again",
+            "reason": "Conflict",
+            "code": 409
+        }"#;
+        assert_eq!(
+            ClickError::from_response(StatusCode::CONFLICT, body).to_string(),
+            "Conflict: Operation cannot be fulfilled on deployments.apps \"web\": the object has \
+             been modified. Something else changed the object, so get it again and retry"
+        );
+
+        let body = br#"{
+            "kind": "Status",
+            "apiVersion": "v1",
+            "message": "Deployment.apps \"web\" is invalid: spec.replicas: Invalid value: -1",
+            "reason": "Invalid",
+            "details": { "causes": [
+                { "field": "spec.replicas", "message": "Invalid value: -1" },
+                { "message": "spec.template: Required value" }
+            ]},
+            "code": 422
+        }"#;
+        assert_eq!(
+            ClickError::from_response(StatusCode::UNPROCESSABLE_ENTITY, body).to_string(),
+            "Error from the api server (Invalid): Deployment.apps \"web\" is invalid: \
+             spec.replicas: Invalid value: -1\n  spec.replicas: Invalid value: -1\n  \
+             spec.template: Required value"
+        );
+
+        // not a status, like from a proxy in front of the api server
+        let err = ClickError::from_response(StatusCode::BAD_GATEWAY, b"<html>bad gateway</html>\n");
+        assert_eq!(
+            err.to_string(),
+            "Error from the api server (Bad Gateway): <html>bad gateway</html>"
+        );
+    }
+}
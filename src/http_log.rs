@@ -0,0 +1,171 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Logging of the http requests click makes, for debugging calls that fail. Like kubectl, the
+//! verbosity level picks what's logged:
+//!  6: the method and url of each request, and the status code and time taken
+//!  7: also the request headers, with credentials redacted
+//!  8: also the request and response bodies, truncated
+//!  9: the full bodies
+//! Logs go to stderr, or to the file set with set_log_file
+
+use reqwest::blocking::Request;
+use reqwest::StatusCode;
+
+use std::fs::{File, OpenOptions};
+use std::io::{stderr, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::error::ClickError;
+
+pub const MAX_VERBOSITY: u8 = 9;
+/// levels below this don't log anything, so -v only accepts this and above
+pub const MIN_VERBOSITY: u8 = URL_LEVEL;
+const URL_LEVEL: u8 = 6;
+const HEADER_LEVEL: u8 = 7;
+const BODY_LEVEL: u8 = 8;
+/// bodies longer than this are cut off below MAX_VERBOSITY
+const MAX_BODY_LEN: usize = 1024;
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+lazy_static! {
+    static ref LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+}
+
+pub fn set_verbosity(level: u8) {
+    VERBOSITY.store(level.min(MAX_VERBOSITY), Ordering::Relaxed);
+}
+
+fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+/// Append logs to the file at path instead of writing them to stderr
+pub fn set_log_file(path: &Path) -> Result<(), ClickError> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    *LOG_FILE.lock().unwrap() = Some(file);
+    Ok(())
+}
+
+fn write_log(line: &str) {
+    match LOG_FILE.lock().unwrap().as_mut() {
+        Some(file) => writeln!(file, "{}", line).unwrap_or(()),
+        None => writeln!(stderr(), "{}", line).unwrap_or(()),
+    }
+}
+
+/// The value to log for a header, hiding anything that's a credential
+fn header_value(name: &str, value: &str) -> String {
+    if name.eq_ignore_ascii_case("authorization") {
+        match value.split_once(' ') {
+            Some((scheme, _)) => format!("{} <redacted>", scheme),
+            None => "<redacted>".to_string(),
+        }
+    } else {
+        value.to_string()
+    }
+}
+
+/// Format a body for the log, cutting it off at MAX_BODY_LEN unless verbosity is at the max
+fn body_str(body: &[u8], level: u8) -> String {
+    let body = String::from_utf8_lossy(body);
+    if level < MAX_VERBOSITY && body.chars().count() > MAX_BODY_LEN {
+        let cut: String = body.chars().take(MAX_BODY_LEN).collect();
+        format!("{} [truncated {} bytes]", cut, body.len() - cut.len())
+    } else {
+        body.into_owned()
+    }
+}
+
+pub fn log_request(request: &Request) {
+    let level = verbosity();
+    if level < URL_LEVEL {
+        return;
+    }
+    write_log(&format!("{} {}", request.method(), request.url()));
+    if level >= HEADER_LEVEL {
+        for (name, value) in request.headers().iter() {
+            let value = value.to_str().unwrap_or("<non-ascii value>");
+            write_log(&format!(
+                "    {}: {}",
+                name,
+                header_value(name.as_str(), value)
+            ));
+        }
+    }
+    if level >= BODY_LEVEL {
+        if let Some(body) = request.body().and_then(|b| b.as_bytes()) {
+            if !body.is_empty() {
+                write_log(&format!("Request Body: {}", body_str(body, level)));
+            }
+        }
+    }
+}
+
+/// Log the response to a request. body is None for streaming responses, which are never logged
+pub fn log_response(status: StatusCode, elapsed: Duration, body: Option<&[u8]>) {
+    let level = verbosity();
+    if level < URL_LEVEL {
+        return;
+    }
+    write_log(&format!(
+        "Response: {} in {} ms",
+        status,
+        elapsed.as_millis()
+    ));
+    if level >= BODY_LEVEL {
+        if let Some(body) = body {
+            write_log(&format!("Response Body: {}", body_str(body, level)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_value() {
+        assert_eq!(
+            header_value("authorization", "Bearer abc123"),
+            "Bearer <redacted>"
+        );
+        assert_eq!(
+            header_value("Authorization", "Basic dXNlcjpwYXNz"),
+            "Basic <redacted>"
+        );
+        assert_eq!(header_value("authorization", "abc123"), "<redacted>");
+        assert_eq!(
+            header_value("content-type", "application/json"),
+            "application/json"
+        );
+    }
+
+    #[test]
+    fn test_body_str() {
+        assert_eq!(
+            body_str(b"{\"kind\":\"Pod\"}", BODY_LEVEL),
+            "{\"kind\":\"Pod\"}"
+        );
+        let long = vec![b'a'; MAX_BODY_LEN + 10];
+        let truncated = body_str(&long, BODY_LEVEL);
+        assert!(truncated.starts_with(&"a".repeat(MAX_BODY_LEN)));
+        assert!(truncated.ends_with("[truncated 10 bytes]"));
+        assert_eq!(body_str(&long, MAX_VERBOSITY).len(), MAX_BODY_LEN + 10);
+    }
+}
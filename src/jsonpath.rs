@@ -0,0 +1,684 @@
+// Copyright 2022 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Selecting parts of a Value with a path expression like `.items[0].spec.nodeName`, and rendering
+//! kubectl style jsonpath templates like `{range .items[*]}{.metadata.name}{"\n"}{end}`
+//!
+//! Supported path syntax:
+//! - `.field` or `['field']` select a field of an object (use the quoted form for keys that have
+//!   dots in them, like label names)
+//! - `..field` selects `field` at any depth
+//! - `[N]` selects the Nth item of an array (negative numbers count from the end)
+//! - `[start:end]` selects a slice of an array
+//! - `[*]`, `[]` or `.*` select every item of an array or every value of an object
+//! - `[?(@.path == "value")]` selects the items of an array that match the filter. Supported
+//!   operators are `==`, `!=`, `<`, `<=`, `>` and `>=`. `[?(@.path)]` selects items where the path
+//!   exists
+
+use serde_json::Value;
+
+use crate::error::ClickError;
+
+use std::cmp::Ordering;
+
+#[derive(Debug, PartialEq)]
+pub enum PathSegment {
+    Field(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Wildcard,
+    Recursive(String),
+    Filter(Filter),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Filter {
+    path: Vec<PathSegment>,
+    comparison: Option<(FilterOp, Value)>,
+}
+
+impl Filter {
+    fn matches(&self, value: &Value) -> bool {
+        let selected = select_segments(&self.path, value);
+        let first = match selected.first() {
+            Some(first) => *first,
+            None => return false,
+        };
+        match &self.comparison {
+            None => !matches!(first, Value::Null | Value::Bool(false)),
+            Some((op, literal)) => {
+                let ordering = match (first, literal) {
+                    (Value::Number(a), Value::Number(b)) => a
+                        .as_f64()
+                        .partial_cmp(&b.as_f64())
+                        .unwrap_or(Ordering::Equal),
+                    (Value::String(a), Value::String(b)) => a.cmp(b),
+                    _ => {
+                        let equal = first == literal;
+                        return match op {
+                            FilterOp::Eq => equal,
+                            FilterOp::Ne => !equal,
+                            _ => false,
+                        };
+                    }
+                };
+                match op {
+                    FilterOp::Eq => ordering == Ordering::Equal,
+                    FilterOp::Ne => ordering != Ordering::Equal,
+                    FilterOp::Lt => ordering == Ordering::Less,
+                    FilterOp::Le => ordering != Ordering::Greater,
+                    FilterOp::Gt => ordering == Ordering::Greater,
+                    FilterOp::Ge => ordering != Ordering::Less,
+                }
+            }
+        }
+    }
+}
+
+fn parse_err(path: &str, msg: &str) -> ClickError {
+    ClickError::ParseErr(format!("Invalid path '{}': {}", path, msg))
+}
+
+/// Find the index of the char that closes the open char at chars[start - 1], skipping over
+/// quoted sections and nested pairs
+fn find_close(chars: &[char], start: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 1;
+    let mut quote = None;
+    for (i, c) in chars.iter().enumerate().skip(start) {
+        match quote {
+            Some(q) => {
+                if *c == q {
+                    quote = None;
+                }
+            }
+            None => {
+                if *c == '"' || *c == '\'' {
+                    quote = Some(*c);
+                } else if *c == open {
+                    depth += 1;
+                } else if *c == close {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn read_field(chars: &[char], pos: &mut usize) -> String {
+    let start = *pos;
+    while *pos < chars.len() && chars[*pos] != '.' && chars[*pos] != '[' {
+        *pos += 1;
+    }
+    chars[start..*pos].iter().collect()
+}
+
+fn parse_literal(path: &str, literal: &str) -> Result<Value, ClickError> {
+    if literal.len() >= 2
+        && ((literal.starts_with('"') && literal.ends_with('"'))
+            || (literal.starts_with('\'') && literal.ends_with('\'')))
+    {
+        Ok(Value::String(literal[1..literal.len() - 1].to_string()))
+    } else {
+        serde_json::from_str(literal)
+            .map_err(|_| parse_err(path, &format!("invalid literal '{}' in filter", literal)))
+    }
+}
+
+fn parse_filter(path: &str, expr: &str) -> Result<Filter, ClickError> {
+    let expr = expr.trim();
+    let expr = match expr.strip_prefix('(').and_then(|e| e.strip_suffix(')')) {
+        Some(inner) => inner.trim(),
+        None => return Err(parse_err(path, "filter must be of the form [?(...)]")),
+    };
+    // find an operator that isn't inside a quoted string
+    let mut quote = None;
+    for (i, c) in expr.char_indices() {
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                }
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                    continue;
+                }
+                let rest = &expr[i..];
+                let op = [
+                    ("==", FilterOp::Eq),
+                    ("!=", FilterOp::Ne),
+                    ("<=", FilterOp::Le),
+                    (">=", FilterOp::Ge),
+                    ("<", FilterOp::Lt),
+                    (">", FilterOp::Gt),
+                ]
+                .into_iter()
+                .find(|(opstr, _)| rest.starts_with(opstr));
+                if let Some((opstr, op)) = op {
+                    let literal = parse_literal(path, rest[opstr.len()..].trim())?;
+                    return Ok(Filter {
+                        path: parse_path(expr[..i].trim())?,
+                        comparison: Some((op, literal)),
+                    });
+                }
+            }
+        }
+    }
+    Ok(Filter {
+        path: parse_path(expr)?,
+        comparison: None,
+    })
+}
+
+fn parse_bracket(path: &str, inner: &str) -> Result<PathSegment, ClickError> {
+    let inner = inner.trim();
+    if inner.is_empty() || inner == "*" {
+        Ok(PathSegment::Wildcard)
+    } else if let Some(filter) = inner.strip_prefix('?') {
+        Ok(PathSegment::Filter(parse_filter(path, filter)?))
+    } else if (inner.starts_with('\'') && inner.ends_with('\''))
+        || (inner.starts_with('"') && inner.ends_with('"'))
+    {
+        if inner.len() < 2 {
+            return Err(parse_err(path, "unterminated quote"));
+        }
+        Ok(PathSegment::Field(inner[1..inner.len() - 1].to_string()))
+    } else if let Some((start, end)) = inner.split_once(':') {
+        let parse_bound = |bound: &str| -> Result<Option<i64>, ClickError> {
+            let bound = bound.trim();
+            if bound.is_empty() {
+                Ok(None)
+            } else {
+                bound
+                    .parse::<i64>()
+                    .map(Some)
+                    .map_err(|_| parse_err(path, &format!("'{}' is not a valid index", bound)))
+            }
+        };
+        Ok(PathSegment::Slice(parse_bound(start)?, parse_bound(end)?))
+    } else {
+        inner
+            .parse::<i64>()
+            .map(PathSegment::Index)
+            .map_err(|_| parse_err(path, &format!("'{}' is not a valid index", inner)))
+    }
+}
+
+/// Parse a path into its segments
+pub fn parse_path(path: &str) -> Result<Vec<PathSegment>, ClickError> {
+    let mut segments = vec![];
+    let chars: Vec<char> = path.trim().chars().collect();
+    let mut pos = 0;
+    if chars.first() == Some(&'$') || chars.first() == Some(&'@') {
+        pos += 1;
+    }
+    while pos < chars.len() {
+        match chars[pos] {
+            '.' => {
+                pos += 1;
+                if pos < chars.len() && chars[pos] == '.' {
+                    pos += 1;
+                    let field = read_field(&chars, &mut pos);
+                    if field.is_empty() {
+                        return Err(parse_err(path, "expected a field name after .."));
+                    }
+                    segments.push(PathSegment::Recursive(field));
+                    continue;
+                }
+                let field = read_field(&chars, &mut pos);
+                if field == "*" {
+                    segments.push(PathSegment::Wildcard);
+                } else if !field.is_empty() {
+                    segments.push(PathSegment::Field(field));
+                }
+            }
+            '[' => {
+                let end = match find_close(&chars, pos + 1, '[', ']') {
+                    Some(end) => end,
+                    None => return Err(parse_err(path, "missing ]")),
+                };
+                let inner: String = chars[pos + 1..end].iter().collect();
+                segments.push(parse_bracket(path, &inner)?);
+                pos = end + 1;
+            }
+            _ => {
+                // allow paths without a leading '.', like 'metadata.name'
+                if pos == 0 {
+                    segments.push(PathSegment::Field(read_field(&chars, &mut pos)));
+                } else {
+                    return Err(parse_err(
+                        path,
+                        &format!("unexpected character '{}' at {}", chars[pos], pos),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(segments)
+}
+
+fn collect_recursive<'a>(value: &'a Value, field: &str, out: &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(v) = map.get(field) {
+                out.push(v);
+            }
+            for v in map.values() {
+                collect_recursive(v, field, out);
+            }
+        }
+        Value::Array(arr) => {
+            for v in arr.iter() {
+                collect_recursive(v, field, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+// convert a possibly negative index into an index into an array of length len
+fn resolve_index(index: i64, len: usize) -> i64 {
+    if index < 0 {
+        len as i64 + index
+    } else {
+        index
+    }
+}
+
+/// Select all the values matched by segments from value. Paths that don't exist in the value
+/// simply select nothing
+pub fn select_segments<'a>(segments: &[PathSegment], value: &'a Value) -> Vec<&'a Value> {
+    let mut current = vec![value];
+    for segment in segments.iter() {
+        let mut next = vec![];
+        for val in current.into_iter() {
+            match segment {
+                PathSegment::Field(field) => {
+                    if let Some(v) = val.get(field) {
+                        next.push(v);
+                    }
+                }
+                PathSegment::Index(index) => {
+                    if let Some(arr) = val.as_array() {
+                        let idx = resolve_index(*index, arr.len());
+                        if idx >= 0 {
+                            if let Some(v) = arr.get(idx as usize) {
+                                next.push(v);
+                            }
+                        }
+                    }
+                }
+                PathSegment::Slice(start, end) => {
+                    if let Some(arr) = val.as_array() {
+                        let len = arr.len() as i64;
+                        let start = start.map(|s| resolve_index(s, arr.len())).unwrap_or(0);
+                        let end = end.map(|e| resolve_index(e, arr.len())).unwrap_or(len);
+                        let start = start.clamp(0, len) as usize;
+                        let end = end.clamp(0, len) as usize;
+                        if start < end {
+                            next.extend(arr[start..end].iter());
+                        }
+                    }
+                }
+                PathSegment::Wildcard => match val {
+                    Value::Array(arr) => next.extend(arr.iter()),
+                    Value::Object(map) => next.extend(map.values()),
+                    _ => {}
+                },
+                PathSegment::Recursive(field) => collect_recursive(val, field, &mut next),
+                PathSegment::Filter(filter) => {
+                    if let Some(arr) = val.as_array() {
+                        next.extend(arr.iter().filter(|item| filter.matches(item)));
+                    }
+                }
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+/// Parse path and select the values it matches from value
+pub fn select<'a>(path: &str, value: &'a Value) -> Result<Vec<&'a Value>, ClickError> {
+    let segments = parse_path(path)?;
+    Ok(select_segments(&segments, value))
+}
+
+/// Format a value the way kubectl's jsonpath does: strings are printed raw, null is empty, and
+/// everything else is printed as json
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+// ordering for sorting by a path. Missing values sort first, then numbers, strings and anything
+// else (compared as json)
+fn cmp_values(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Some(Value::Number(_)), Some(_)) => Ordering::Less,
+        (Some(_), Some(Value::Number(_))) => Ordering::Greater,
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        (Some(a), Some(b)) => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+/// Sort the "items" array of a list value by the first value path selects from each item. The path
+/// can be bare or wrapped in {}, like kubectl's --sort-by
+pub fn sort_items(path: &str, value: &mut Value) -> Result<(), ClickError> {
+    let trimmed = path
+        .strip_prefix('{')
+        .and_then(|p| p.strip_suffix('}'))
+        .unwrap_or(path);
+    let segments = parse_path(trimmed)?;
+    if let Some(items) = value
+        .get_mut("items")
+        .and_then(|items| items.as_array_mut())
+    {
+        items.sort_by(|a, b| {
+            cmp_values(
+                select_segments(&segments, a).first().copied(),
+                select_segments(&segments, b).first().copied(),
+            )
+        });
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum TemplateNode {
+    Text(String),
+    Path(Vec<PathSegment>),
+    Range(Vec<PathSegment>, Vec<TemplateNode>),
+}
+
+/// A kubectl style jsonpath template, like `{.metadata.name}{"\t"}{.status.phase}`. Text outside of
+/// {} is printed as is. Inside {} can be a path, a quoted string literal, or `range <path>` ...
+/// `end` to iterate over the items the path selects. Paths inside a range are relative to the
+/// current item.
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    nodes: Vec<TemplateNode>,
+}
+
+/// Turn \n and \t escapes into a newline and a tab
+pub fn unescape(s: &str) -> String {
+    let mut ret = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => ret.push('\n'),
+                Some('t') => ret.push('\t'),
+                Some('r') => ret.push('\r'),
+                Some(other) => ret.push(other),
+                None => ret.push('\\'),
+            }
+        } else {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, ClickError> {
+        let template_err =
+            |msg: &str| ClickError::ParseErr(format!("Invalid template '{}': {}", template, msg));
+        // kubectl allows (and people often type) a bare path
+        let template = if template.contains('{') {
+            template.to_string()
+        } else {
+            format!("{{{}}}", template)
+        };
+        let chars: Vec<char> = template.chars().collect();
+        // stack of ranges we're inside of, the bottom is the top level of the template
+        let mut stack: Vec<(Option<Vec<PathSegment>>, Vec<TemplateNode>)> = vec![(None, vec![])];
+        let mut pos = 0;
+        let mut text = String::new();
+        while pos < chars.len() {
+            if chars[pos] != '{' {
+                text.push(chars[pos]);
+                pos += 1;
+                continue;
+            }
+            let end =
+                find_close(&chars, pos + 1, '{', '}').ok_or_else(|| template_err("missing }"))?;
+            let inner: String = chars[pos + 1..end].iter().collect();
+            let inner = inner.trim();
+            let nodes = &mut stack.last_mut().unwrap().1; // safe, we never pop the bottom
+            if !text.is_empty() {
+                nodes.push(TemplateNode::Text(std::mem::take(&mut text)));
+            }
+            if let Some(range_path) = inner.strip_prefix("range ") {
+                stack.push((Some(parse_path(range_path)?), vec![]));
+            } else if inner == "end" {
+                if stack.len() < 2 {
+                    return Err(template_err("{end} without {range}"));
+                }
+                let (path, children) = stack.pop().unwrap(); // safe, checked len above
+                stack
+                    .last_mut()
+                    .unwrap()
+                    .1
+                    .push(TemplateNode::Range(path.unwrap(), children)); // only bottom is None
+            } else if inner.len() >= 2 && inner.starts_with('"') && inner.ends_with('"') {
+                nodes.push(TemplateNode::Text(unescape(&inner[1..inner.len() - 1])));
+            } else {
+                nodes.push(TemplateNode::Path(parse_path(inner)?));
+            }
+            pos = end + 1;
+        }
+        if stack.len() > 1 {
+            return Err(template_err("{range} without {end}"));
+        }
+        let mut nodes = stack.pop().unwrap().1; // safe, always at least one item
+        if !text.is_empty() {
+            nodes.push(TemplateNode::Text(text));
+        }
+        Ok(Template { nodes })
+    }
+
+    fn render_nodes(nodes: &[TemplateNode], value: &Value, out: &mut String) {
+        for node in nodes.iter() {
+            match node {
+                TemplateNode::Text(text) => out.push_str(text),
+                TemplateNode::Path(path) => {
+                    let strs: Vec<String> = select_segments(path, value)
+                        .into_iter()
+                        .map(value_to_string)
+                        .collect();
+                    out.push_str(&strs.join(" "));
+                }
+                TemplateNode::Range(path, children) => {
+                    for item in select_segments(path, value).into_iter() {
+                        Template::render_nodes(children, item, out);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Render this template against the specified value
+    pub fn render(&self, value: &Value) -> String {
+        let mut out = String::new();
+        Template::render_nodes(&self.nodes, value, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_value() -> Value {
+        json!({
+            "items": [
+                {
+                    "metadata": {
+                        "name": "pod1",
+                        "labels": { "app.kubernetes.io/name": "web" }
+                    },
+                    "spec": { "nodeName": "node1" }
+                },
+                {
+                    "metadata": { "name": "pod2" },
+                    "spec": { "nodeName": "node2", "priority": 10 }
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path(".items[0].spec.nodeName").unwrap(),
+            vec![
+                PathSegment::Field("items".to_string()),
+                PathSegment::Index(0),
+                PathSegment::Field("spec".to_string()),
+                PathSegment::Field("nodeName".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_path("$.items[*]['a.b']").unwrap(),
+            vec![
+                PathSegment::Field("items".to_string()),
+                PathSegment::Wildcard,
+                PathSegment::Field("a.b".to_string()),
+            ]
+        );
+        assert_eq!(parse_path(".").unwrap(), vec![]);
+        assert!(parse_path(".items[0").is_err());
+        assert!(parse_path(".items[x]").is_err());
+    }
+
+    #[test]
+    fn test_sort_items() {
+        let mut value = json!({
+            "items": [
+                { "name": "b", "restarts": 10 },
+                { "name": "c" },
+                { "name": "a", "restarts": 2 },
+            ]
+        });
+        sort_items(".restarts", &mut value).unwrap();
+        let names: Vec<&Value> = select(".items[*].name", &value).unwrap();
+        assert_eq!(names, vec![&json!("c"), &json!("a"), &json!("b")]);
+        sort_items("{.name}", &mut value).unwrap();
+        let names: Vec<&Value> = select(".items[*].name", &value).unwrap();
+        assert_eq!(names, vec![&json!("a"), &json!("b"), &json!("c")]);
+    }
+
+    #[test]
+    fn test_select() {
+        let value = test_value();
+        assert_eq!(
+            select(".items[0].spec.nodeName", &value).unwrap(),
+            vec![&json!("node1")]
+        );
+        assert_eq!(
+            select(".items[-1].metadata.name", &value).unwrap(),
+            vec![&json!("pod2")]
+        );
+        assert_eq!(
+            select(".items[].metadata.name", &value).unwrap(),
+            vec![&json!("pod1"), &json!("pod2")]
+        );
+        assert_eq!(
+            select("items[0].metadata.labels['app.kubernetes.io/name']", &value).unwrap(),
+            vec![&json!("web")]
+        );
+        assert!(select(".items[5].metadata", &value).unwrap().is_empty());
+        assert_eq!(select(".", &value).unwrap(), vec![&value]);
+    }
+
+    #[test]
+    fn test_select_jsonpath() {
+        let value = test_value();
+        assert_eq!(
+            select("$..nodeName", &value).unwrap(),
+            vec![&json!("node1"), &json!("node2")]
+        );
+        assert_eq!(
+            select(
+                ".items[?(@.spec.nodeName==\"node2\")].metadata.name",
+                &value
+            )
+            .unwrap(),
+            vec![&json!("pod2")]
+        );
+        assert_eq!(
+            select(".items[?(@.spec.priority > 5)].metadata.name", &value).unwrap(),
+            vec![&json!("pod2")]
+        );
+        assert_eq!(
+            select(".items[?(@.metadata.labels)].metadata.name", &value).unwrap(),
+            vec![&json!("pod1")]
+        );
+        assert_eq!(
+            select(".items[0:1].metadata.name", &value).unwrap(),
+            vec![&json!("pod1")]
+        );
+        assert!(select(".items[?(@.a == bad)]", &value).is_err());
+    }
+
+    #[test]
+    fn test_template() {
+        let value = test_value();
+        let t = Template::parse("{.items[*].metadata.name}").unwrap();
+        assert_eq!(t.render(&value), "pod1 pod2");
+
+        let t = Template::parse(".items[0].spec.nodeName").unwrap();
+        assert_eq!(t.render(&value), "node1");
+
+        let t = Template::parse(
+            "{range .items[*]}{.metadata.name}{\"\\t\"}{.spec.nodeName}{\"\\n\"}{end}",
+        )
+        .unwrap();
+        assert_eq!(t.render(&value), "pod1\tnode1\npod2\tnode2\n");
+
+        let t = Template::parse("names: {.items[*].metadata.name}!").unwrap();
+        assert_eq!(t.render(&value), "names: pod1 pod2!");
+
+        assert!(Template::parse("{range .items[*]}{.metadata.name}").is_err());
+        assert!(Template::parse("{.metadata.name}{end}").is_err());
+        assert!(Template::parse("{.metadata.name").is_err());
+    }
+}
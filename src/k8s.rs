@@ -13,10 +13,12 @@
 // limitations under the License.
 
 use bytes::Bytes;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::WatchEvent;
 use k8s_openapi::{http, List, ListableResource};
 use reqwest::blocking::Client;
 use reqwest::{Certificate, Identity, Url};
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
 use url::Host;
 use yasna::models::ObjectIdentifier;
 
@@ -25,11 +27,12 @@ use std::fmt::Debug;
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::{
     config::{AuthProvider, ExecAuth, ExecProvider},
-    error::{ClickErrNo, ClickError},
+    error::ClickError,
+    http_log,
 };
 
 #[derive(Clone)]
@@ -169,10 +172,15 @@ pub struct Context {
     endpoint: Url,
     client: RefCell<Client>,
     log_client: RefCell<Client>,
+    /// for watches. its reads time out after the connect timeout, so a stopped watch's reader
+    /// notices and closes the connection, instead of waiting for the next event
+    watch_client: RefCell<Client>,
     root_cas: Option<Vec<Certificate>>,
     auth: RefCell<Option<UserAuth>>,
     connect_timeout_secs: u32,
     read_timeout_secs: u32,
+    /// Objects in lists that could only be partly read, to warn about after the command
+    warnings: RefCell<Vec<String>>,
 }
 
 impl Context {
@@ -195,23 +203,50 @@ impl Context {
         // have to create a special client for logs until
         // https://github.com/seanmonstar/reqwest/issues/1380
         // is resolved
-        let (log_client, _) =
-            Context::get_client(&endpoint, root_cas.clone(), auth, None, u32::MAX, u32::MAX);
+        let (log_client, _) = Context::get_client(
+            &endpoint,
+            root_cas.clone(),
+            auth.clone(),
+            None,
+            u32::MAX,
+            u32::MAX,
+        );
+        let (watch_client, _) = Context::get_client(
+            &endpoint,
+            root_cas.clone(),
+            auth,
+            None,
+            connect_timeout_secs,
+            connect_timeout_secs,
+        );
         let client = RefCell::new(client);
         let log_client = RefCell::new(log_client);
+        let watch_client = RefCell::new(watch_client);
         let client_auth = RefCell::new(client_auth);
         Context {
             name: name.into(),
             endpoint,
             client,
             log_client,
+            watch_client,
             root_cas,
             auth: client_auth,
             connect_timeout_secs,
             read_timeout_secs,
+            warnings: RefCell::new(vec![]),
         }
     }
 
+    /// Take the warnings about objects that could only be partly read since the last call
+    pub fn take_warnings(&self) -> Vec<String> {
+        self.warnings.take()
+    }
+
+    /// The url of the api server this context talks to
+    pub fn endpoint(&self) -> &Url {
+        &self.endpoint
+    }
+
     fn get_client(
         endpoint: &Url,
         root_cas: Option<Vec<Certificate>>,
@@ -287,13 +322,22 @@ impl Context {
                     let (new_log_client, _) = Context::get_client(
                         &self.endpoint,
                         self.root_cas.clone(),
-                        auth,
-                        Some(id),
+                        auth.clone(),
+                        Some(id.clone()),
                         u32::MAX,
                         u32::MAX,
                     );
+                    let (new_watch_client, _) = Context::get_client(
+                        &self.endpoint,
+                        self.root_cas.clone(),
+                        auth,
+                        Some(id),
+                        self.connect_timeout_secs,
+                        self.connect_timeout_secs,
+                    );
                     *self.client.borrow_mut() = new_client;
                     *self.log_client.borrow_mut() = new_log_client;
+                    *self.watch_client.borrow_mut() = new_watch_client;
                     *self.auth.borrow_mut() = new_auth;
                 }
             }
@@ -316,6 +360,7 @@ impl Context {
             http::method::Method::GET => self.client.borrow().get(url),
             http::method::Method::POST => self.client.borrow().post(url),
             http::method::Method::DELETE => self.client.borrow().delete(url),
+            http::method::Method::PATCH => self.client.borrow().patch(url),
             _ => unimplemented!(),
         };
 
@@ -339,9 +384,13 @@ impl Context {
             },
             None => req,
         };
-        let resp = req.send()?;
+        let req = req.build()?;
+        http_log::log_request(&req);
+        let start = Instant::now();
+        let resp = self.client.borrow().execute(req)?;
         let stat = resp.status();
         let bytes = resp.bytes()?;
+        http_log::log_response(stat, start.elapsed(), Some(&bytes));
 
         Ok(http::response::Builder::new()
             .status(stat)
@@ -355,6 +404,24 @@ impl Context {
         &self,
         k8sreq: http::Request<Vec<u8>>,
         timeout: Option<Duration>,
+    ) -> Result<reqwest::blocking::Response, ClickError> {
+        self.execute_streaming(&self.log_client, k8sreq, timeout)
+    }
+
+    /// Execute a watch request and return the response to read events from. Reads of it time out
+    /// when no events arrive for the connect timeout, and can be retried
+    pub fn execute_watch(
+        &self,
+        k8sreq: http::Request<Vec<u8>>,
+    ) -> Result<reqwest::blocking::Response, ClickError> {
+        self.execute_streaming(&self.watch_client, k8sreq, None)
+    }
+
+    fn execute_streaming(
+        &self,
+        client: &RefCell<Client>,
+        k8sreq: http::Request<Vec<u8>>,
+        timeout: Option<Duration>,
     ) -> Result<reqwest::blocking::Response, ClickError> {
         let (parts, body) = k8sreq.into_parts();
 
@@ -365,9 +432,10 @@ impl Context {
         }
 
         let req = match parts.method {
-            http::method::Method::GET => self.log_client.borrow().get(url),
-            http::method::Method::POST => self.log_client.borrow().post(url),
-            http::method::Method::DELETE => self.log_client.borrow().delete(url),
+            http::method::Method::GET => client.borrow().get(url),
+            http::method::Method::POST => client.borrow().post(url),
+            http::method::Method::DELETE => client.borrow().delete(url),
+            http::method::Method::PATCH => client.borrow().patch(url),
             _ => unimplemented!(),
         };
 
@@ -397,17 +465,34 @@ impl Context {
             None => req, // log_client above already has a super long timeout
         };
 
-        let resp = req.send()?;
+        let req = req.build()?;
+        http_log::log_request(&req);
+        let start = Instant::now();
+        let resp = client.borrow().execute(req)?;
+        http_log::log_response(resp.status(), start.elapsed(), None);
 
-        if resp.status().is_success() {
+        let status = resp.status();
+        if status.is_success() {
             Ok(resp)
         } else {
-            let err = match resp.error_for_status_ref() {
-                Ok(_) => panic!("status was not success, but error_for_status returned Ok"),
-                Err(e) => e,
-            };
-            let body = resp.json()?;
-            Err(ClickError::Reqwest(err, Some(body)))
+            Err(ClickError::from_response(status, &resp.bytes()?))
+        }
+    }
+
+    /// Execute the request, and return the response if it was a success, or the error the api
+    /// server returned if not
+    fn execute_success(
+        &self,
+        k8sreq: http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Bytes>, ClickError> {
+        let response = self.execute(k8sreq)?;
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            Err(ClickError::from_response(
+                response.status(),
+                response.body(),
+            ))
         }
     }
 
@@ -415,7 +500,7 @@ impl Context {
         &self,
         k8sreq: http::Request<Vec<u8>>,
     ) -> Result<T, ClickError> {
-        let response = self.execute(k8sreq)?;
+        let response = self.execute_success(k8sreq)?;
         let status_code: http::StatusCode = response.status();
         match k8s_openapi::Response::try_from_parts(status_code, response.body()) {
             Ok((res, _)) => Ok(res),
@@ -424,33 +509,276 @@ impl Context {
         }
     }
 
+    /// Execute the request and deserialize the object in the response. Unlike read, a failed
+    /// request is an error, so there's no other response to handle
+    pub fn read_object<T: DeserializeOwned>(
+        &self,
+        k8sreq: http::Request<Vec<u8>>,
+    ) -> Result<T, ClickError> {
+        let response = self.execute_success(k8sreq)?;
+        Ok(serde_json::from_slice(response.body())?)
+    }
+
     pub fn execute_list<T: ListableResource + for<'de> Deserialize<'de> + Debug>(
         &self,
         k8sreq: http::Request<Vec<u8>>,
     ) -> Result<List<T>, ClickError> {
-        let response = self.execute(k8sreq)?;
-        let status_code: http::StatusCode = response.status();
+        let response = self.execute_success(k8sreq)?;
+        let value: Value = serde_json::from_slice(response.body())?;
+        self.list_from_value(value)
+    }
 
-        let res_list: List<T> =
-            match k8s_openapi::Response::try_from_parts(status_code, response.body()) {
-                // Successful response (HTTP 200 and parsed successfully)
-                Ok((k8s_openapi::ListResponse::Ok(res_list), _)) => res_list,
-
-                // Some unexpected response
-                // (not HTTP 200, but still parsed successfully)
-                Ok(other) => {
-                    if status_code == http::StatusCode::UNAUTHORIZED {
-                        return Err(ClickError::Kube(ClickErrNo::Unauthorized));
-                    } else {
-                        return Err(ClickError::ParseErr(
-                            // TODO maybe a special error type for this
-                            format!("Got unexpected status {} {:?}", status_code, other),
-                        ));
-                    }
+    /// Deserialize a list, one item at a time, so an object that can't be fully read doesn't stop
+    /// the rest being listed. See [`list_from_value`]
+    pub fn list_from_value<T: ListableResource + DeserializeOwned>(
+        &self,
+        value: Value,
+    ) -> Result<List<T>, ClickError> {
+        let (list, warnings) = list_from_value(value)?;
+        for warning in warnings {
+            self.add_warning(warning);
+        }
+        Ok(list)
+    }
+
+    /// Read an event from a watch with [`watch_event_from_str`]. An event that can't be read at
+    /// all is skipped with a warning, so one odd object doesn't end the watch
+    pub fn watch_event_from_str<T: DeserializeOwned>(&self, line: &str) -> Option<WatchEvent<T>> {
+        match watch_event_from_str(line) {
+            Ok((event, warning)) => {
+                if let Some(warning) = warning {
+                    self.add_warning(warning);
                 }
-                Err(e) => return Err(ClickError::ResponseError(e)),
-            };
+                Some(event)
+            }
+            Err(e) => {
+                self.add_warning(format!(
+                    "Skipped a watch event that could not be read: {}",
+                    e
+                ));
+                None
+            }
+        }
+    }
+
+    fn add_warning(&self, warning: String) {
+        // the same objects are listed again and again by --watch and completion, only keep one
+        // warning for each
+        let mut kept = self.warnings.borrow_mut();
+        if !kept.contains(&warning) {
+            kept.push(warning);
+        }
+    }
 
-        Ok(res_list)
+    /// Execute the request and return the body of the response as a raw json Value. Use this to get
+    /// the full object as the api server sent it, including any fields k8s_openapi doesn't model
+    pub fn get_value(&self, k8sreq: http::Request<Vec<u8>>) -> Result<Value, ClickError> {
+        let response = self.execute_success(k8sreq)?;
+        Ok(serde_json::from_slice(response.body())?)
+    }
+}
+
+/// Deserialize an item of a list. What the api server sends doesn't always have the shape
+/// k8s_openapi expects (like from a newer server, or a webhook that wrote something odd), so if it
+/// can't be read, try again without its status, and then with only its metadata, which is enough
+/// to list and select it. Returns the item, and what was left out, if anything
+fn lenient_item<T: DeserializeOwned>(mut item: Value) -> Result<(T, Option<String>), ClickError> {
+    let err = match T::deserialize(&item) {
+        Ok(t) => return Ok((t, None)),
+        Err(e) => e,
+    };
+    let had_status = match item.as_object_mut() {
+        Some(object) => object.remove("status").is_some(),
+        None => return Err(err.into()),
+    };
+    if had_status {
+        if let Ok(t) = T::deserialize(&item) {
+            return Ok((t, Some(format!("without its status ({})", err))));
+        }
+    }
+    if let Some(object) = item.as_object_mut() {
+        object.retain(|key, _| key == "apiVersion" || key == "kind" || key == "metadata");
+    }
+    match T::deserialize(&item) {
+        Ok(t) => Ok((t, Some(format!("with only its metadata ({})", err)))),
+        Err(_) => Err(err.into()),
+    }
+}
+
+/// Deserialize a list from the api server, reading each item with [`lenient_item`]. Returns the
+/// list, and a warning for each item that could only be partly read
+pub fn list_from_value<T: ListableResource + DeserializeOwned>(
+    mut value: Value,
+) -> Result<(List<T>, Vec<String>), ClickError> {
+    let metadata = match value.get_mut("metadata") {
+        Some(metadata) => serde_json::from_value(metadata.take())?,
+        None => Default::default(),
+    };
+    let items = match value.get_mut("items").map(Value::take) {
+        Some(Value::Array(items)) => items,
+        Some(Value::Null) | None => vec![],
+        Some(other) => {
+            return Err(ClickError::ParseErr(format!(
+                "Expected a list of {}, but items was: {}",
+                T::KIND,
+                other
+            )))
+        }
+    };
+    let mut list = List {
+        items: Vec::with_capacity(items.len()),
+        metadata,
+    };
+    let mut warnings = vec![];
+    for item in items {
+        let name = item_name(&item);
+        let (t, left_out) = lenient_item(item)?;
+        if let Some(left_out) = left_out {
+            warnings.push(partly_read_warning(T::KIND, &name, &left_out));
+        }
+        list.items.push(t);
+    }
+    Ok((list, warnings))
+}
+
+/// Deserialize an event from a watch, reading its object with [`lenient_item`] if it can't be read
+/// as is. Returns the event, and a warning if its object could only be partly read
+pub fn watch_event_from_str<T: DeserializeOwned>(
+    line: &str,
+) -> Result<(WatchEvent<T>, Option<String>), ClickError> {
+    let err = match serde_json::from_str::<WatchEvent<T>>(line) {
+        Ok(event) => return Ok((event, None)),
+        Err(e) => e,
+    };
+    let mut value: Value = serde_json::from_str(line)?;
+    let event: fn(T) -> WatchEvent<T> = match value.get("type").and_then(Value::as_str) {
+        Some("ADDED") => WatchEvent::Added,
+        Some("MODIFIED") => WatchEvent::Modified,
+        Some("DELETED") => WatchEvent::Deleted,
+        _ => return Err(err.into()),
+    };
+    let object = match value.get_mut("object").map(Value::take) {
+        Some(object) => object,
+        None => return Err(err.into()),
+    };
+    let kind = object
+        .get("kind")
+        .and_then(Value::as_str)
+        .unwrap_or("object")
+        .to_string();
+    let name = item_name(&object);
+    let (t, left_out) = lenient_item(object)?;
+    let warning = left_out.map(|left_out| partly_read_warning(&kind, &name, &left_out));
+    Ok((event(t), warning))
+}
+
+/// The namespace/name of an item, for warnings about it
+fn item_name(item: &Value) -> String {
+    match (
+        item.pointer("/metadata/namespace").and_then(Value::as_str),
+        item.pointer("/metadata/name").and_then(Value::as_str),
+    ) {
+        (Some(namespace), Some(name)) => format!("{}/{}", namespace, name),
+        (None, Some(name)) => name.to_string(),
+        _ => "<No Name>".to_string(),
+    }
+}
+
+fn partly_read_warning(kind: &str, name: &str, left_out: &str) -> String {
+    format!(
+        "Could not fully read {} {}, showing it {}",
+        kind, name, left_out
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1 as api;
+    use serde_json::json;
+
+    #[test]
+    fn test_list_from_value() {
+        let value = json!({
+            "kind": "PodList",
+            "apiVersion": "v1",
+            "metadata": { "resourceVersion": "42" },
+            "items": [{
+                "metadata": { "name": "ok", "namespace": "default" },
+                "spec": { "containers": [{ "name": "app" }] },
+            }, {
+                // no status yet, which is fine
+                "metadata": { "name": "pending", "namespace": "default" },
+            }, {
+                "metadata": { "name": "odd-status", "namespace": "default" },
+                "spec": { "containers": [{ "name": "app" }] },
+                "status": { "phase": 7 },
+            }, {
+                "metadata": { "name": "odd-spec", "namespace": "default" },
+                "spec": { "containers": "app" },
+            }],
+        });
+        let (list, warnings) = list_from_value::<api::Pod>(value).unwrap();
+        assert_eq!(list.metadata.resource_version.as_deref(), Some("42"));
+        let names: Vec<&str> = list
+            .items
+            .iter()
+            .filter_map(|pod| pod.metadata.name.as_deref())
+            .collect();
+        assert_eq!(names, vec!["ok", "pending", "odd-status", "odd-spec"]);
+        assert!(list.items[2].spec.is_some());
+        assert!(list.items[2].status.is_none());
+        assert!(list.items[3].spec.is_none());
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with(
+            "Could not fully read Pod default/odd-status, showing it without its status"
+        ));
+        assert!(warnings[1].starts_with(
+            "Could not fully read Pod default/odd-spec, showing it with only its metadata"
+        ));
+
+        let (list, warnings) =
+            list_from_value::<api::Pod>(json!({ "metadata": {}, "items": null })).unwrap();
+        assert!(list.items.is_empty() && warnings.is_empty());
+        assert!(list_from_value::<api::Pod>(json!({ "items": {} })).is_err());
+        // metadata that can't be read at all can't be listed
+        assert!(list_from_value::<api::Pod>(json!({ "items": [{ "metadata": 1 }] })).is_err());
+    }
+
+    #[test]
+    fn test_watch_event_from_str() {
+        let (event, warning) = watch_event_from_str::<api::Pod>(
+            r#"{"type":"ADDED","object":{"kind":"Pod","metadata":{"name":"ok"}}}"#,
+        )
+        .unwrap();
+        assert!(
+            matches!(event, WatchEvent::Added(pod) if pod.metadata.name.as_deref() == Some("ok"))
+        );
+        assert_eq!(warning, None);
+
+        let (event, warning) = watch_event_from_str::<api::Pod>(
+            r#"{"type":"MODIFIED","object":{"kind":"Pod","metadata":{"name":"odd","namespace":"default"},"status":{"phase":7}}}"#,
+        )
+        .unwrap();
+        match event {
+            WatchEvent::Modified(pod) => assert!(pod.status.is_none()),
+            _ => panic!("expected a modified event"),
+        }
+        assert!(warning
+            .unwrap()
+            .starts_with("Could not fully read Pod default/odd, showing it without its status"));
+
+        let (event, _) = watch_event_from_str::<api::Pod>(
+            r#"{"type":"ERROR","object":{"kind":"Status","message":"too old resource version"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(event, WatchEvent::ErrorStatus(status)
+            if status.message.as_deref() == Some("too old resource version")));
+
+        assert!(watch_event_from_str::<api::Pod>("not json").is_err());
+        assert!(
+            watch_event_from_str::<api::Pod>(r#"{"type":"ADDED","object":{"metadata":1}}"#)
+                .is_err()
+        );
     }
 }
@@ -15,11 +15,7 @@
 // Code to deal with the k8s table api:
 // https://kubernetes.io/docs/reference/using-api/api-concepts/#receiving-resources-as-tables
 
-use k8s_openapi::{
-    apimachinery::pkg::apis::meta::v1::ObjectMeta,
-    http::{Request, StatusCode},
-    RequestError, Response, ResponseBody, ResponseError,
-};
+use k8s_openapi::{apimachinery::pkg::apis::meta::v1::ObjectMeta, http::Request, RequestError};
 use serde::Deserializer;
 use serde_json::Value;
 
@@ -126,57 +122,14 @@ impl K8sTable {
     }
 }
 
-/// The common response type for all table API operations.
-#[derive(Debug)]
-pub enum GetTableResponse {
-    Ok(K8sTable),
-    Other(Result<Option<serde_json::Value>, serde_json::Error>),
-}
-
-impl Response for GetTableResponse {
-    fn try_from_parts(status_code: StatusCode, buf: &[u8]) -> Result<(Self, usize), ResponseError> {
-        match status_code {
-            StatusCode::OK => {
-                let result = match serde_json::from_slice(buf) {
-                    Ok(value) => value,
-                    Err(ref err) if err.is_eof() => return Err(ResponseError::NeedMoreData),
-                    Err(err) => return Err(ResponseError::Json(err)),
-                };
-                Ok((GetTableResponse::Ok(result), buf.len()))
-            }
-            _ => {
-                let (result, read) = if buf.is_empty() {
-                    (Ok(None), 0)
-                } else {
-                    match crate::serde_json::from_slice(buf) {
-                        Ok(value) => (Ok(Some(value)), buf.len()),
-                        Err(ref err) if err.is_eof() => return Err(ResponseError::NeedMoreData),
-                        Err(err) => (Err(err), 0),
-                    }
-                };
-                Ok((GetTableResponse::Other(result), read))
-            }
-        }
-    }
-}
-
-#[allow(clippy::type_complexity)] // type from k8s_openapi
-pub fn get_k8s_table(
-    url: &str,
-) -> Result<
-    (
-        Request<Vec<u8>>,
-        fn(StatusCode) -> ResponseBody<GetTableResponse>,
-    ),
-    RequestError,
-> {
+pub fn get_k8s_table(url: &str) -> Result<Request<Vec<u8>>, RequestError> {
     let request = Request::get(url).header(
         k8s_openapi::http::header::ACCEPT,
         "application/json;as=Table;g=meta.k8s.io;v=v1beta1",
     );
     let body = vec![];
     match request.body(body) {
-        Ok(request) => Ok((request, ResponseBody::new)),
+        Ok(request) => Ok(request),
         Err(err) => Err(RequestError::Http(err)),
     }
 }
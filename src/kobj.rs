@@ -20,9 +20,13 @@ use crate::Env;
 
 use clap::ArgMatches;
 use k8s_openapi::api::{
-    apps::v1 as api_apps, batch::v1 as api_batch, core::v1 as api, storage::v1 as api_storage,
+    apps::v1 as api_apps, autoscaling::v2 as api_autoscaling, batch::v1 as api_batch,
+    core::v1 as api, storage::v1 as api_storage,
 };
 
+use k8s_openapi::http::Request;
+use k8s_openapi::{Resource, WatchOptional};
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 
 use std::io::Write;
@@ -44,14 +48,44 @@ pub enum ObjType {
     DaemonSet,
     ConfigMap,
     Secret,
+    HorizontalPodAutoscaler,
     Job,
+    CronJob,
     Namespace,
     PersistentVolume,
+    PersistentVolumeClaim,
     StorageClass,
     #[cfg(feature = "argorollouts")]
     Rollout,
 }
 
+impl ObjType {
+    /// The type of objects of kind, for kinds that don't carry anything else. None for pods,
+    /// custom resources and kinds click doesn't know
+    pub fn from_kind(kind: &str) -> Option<ObjType> {
+        match kind {
+            "Node" => Some(ObjType::Node),
+            "Deployment" => Some(ObjType::Deployment),
+            "Service" => Some(ObjType::Service),
+            "ReplicaSet" => Some(ObjType::ReplicaSet),
+            "StatefulSet" => Some(ObjType::StatefulSet),
+            "DaemonSet" => Some(ObjType::DaemonSet),
+            "ConfigMap" => Some(ObjType::ConfigMap),
+            "Secret" => Some(ObjType::Secret),
+            "HorizontalPodAutoscaler" => Some(ObjType::HorizontalPodAutoscaler),
+            "Job" => Some(ObjType::Job),
+            "CronJob" => Some(ObjType::CronJob),
+            "Namespace" => Some(ObjType::Namespace),
+            "PersistentVolume" => Some(ObjType::PersistentVolume),
+            "PersistentVolumeClaim" => Some(ObjType::PersistentVolumeClaim),
+            "StorageClass" => Some(ObjType::StorageClass),
+            #[cfg(feature = "argorollouts")]
+            "Rollout" => Some(ObjType::Rollout),
+            _ => None,
+        }
+    }
+}
+
 /// An object we can have as a "current" thing
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct KObj {
@@ -95,9 +129,12 @@ impl KObj {
             ObjType::StatefulSet => "StatefulSet",
             ObjType::ConfigMap => "ConfigMap",
             ObjType::Secret => "Secret",
+            ObjType::HorizontalPodAutoscaler => "HorizontalPodAutoscaler",
             ObjType::Job => "Job",
+            ObjType::CronJob => "CronJob",
             ObjType::Namespace => "Namespace",
             ObjType::PersistentVolume => "PersistentVolume",
+            ObjType::PersistentVolumeClaim => "PersistentVolumeClaim",
             ObjType::StorageClass => "StorageClass",
             #[cfg(feature = "argorollouts")]
             ObjType::Rollout => "Rollout",
@@ -113,6 +150,124 @@ impl KObj {
         matches!(self.typ, ObjType::Pod { .. })
     }
 
+    /// Read the object as a T, for commands that only work on one kind of object and show more of
+    /// it than its list does. Errors, naming command, if this isn't that kind of object
+    pub fn read_kind<T: Resource + DeserializeOwned>(
+        &self,
+        env: &Env,
+        command: &str,
+    ) -> Result<T, ClickError> {
+        if self.type_str() != T::KIND {
+            return Err(ClickError::CommandError(format!(
+                "{} is a {}, {} only works for {}s",
+                self.name,
+                self.type_str(),
+                command,
+                T::KIND
+            )));
+        }
+        let request = self.read_request()?;
+        env.run_on_context(|c| c.read_object(request))
+    }
+
+    /// Build a request to read the object represented by this kobj
+    pub fn read_request(&self) -> Result<Request<Vec<u8>>, ClickError> {
+        macro_rules! read {
+            ($read_func:expr) => {
+                $read_func(&self.name, Default::default())?.0
+            };
+        }
+        macro_rules! read_namespaced {
+            ($read_func:expr) => {
+                match self.namespace.as_ref() {
+                    Some(ns) => $read_func(&self.name, ns, Default::default())?.0,
+                    None => {
+                        return Err(ClickError::CommandError(format!(
+                            "No namespace for {}, cannot read it",
+                            self.name
+                        )))
+                    }
+                }
+            };
+        }
+        let request = match self.typ {
+            ObjType::ConfigMap => read_namespaced!(api::ConfigMap::read_namespaced_config_map),
+            ObjType::DaemonSet => read_namespaced!(api_apps::DaemonSet::read_namespaced_daemon_set),
+            ObjType::Deployment => {
+                read_namespaced!(api_apps::Deployment::read_namespaced_deployment)
+            }
+            ObjType::HorizontalPodAutoscaler => read_namespaced!(
+                api_autoscaling::HorizontalPodAutoscaler::read_namespaced_horizontal_pod_autoscaler
+            ),
+            ObjType::Job => read_namespaced!(api_batch::Job::read_namespaced_job),
+            ObjType::CronJob => read_namespaced!(api_batch::CronJob::read_namespaced_cron_job),
+            ObjType::Namespace => read!(api::Namespace::read_namespace),
+            ObjType::Node => read!(api::Node::read_node),
+            ObjType::PersistentVolume => read!(api::PersistentVolume::read_persistent_volume),
+            ObjType::PersistentVolumeClaim => read_namespaced!(
+                api::PersistentVolumeClaim::read_namespaced_persistent_volume_claim
+            ),
+            ObjType::Pod { .. } => read_namespaced!(api::Pod::read_namespaced_pod),
+            ObjType::ReplicaSet => {
+                read_namespaced!(api_apps::ReplicaSet::read_namespaced_replica_set)
+            }
+            ObjType::Secret => read_namespaced!(api::Secret::read_namespaced_secret),
+            ObjType::Service => read_namespaced!(api::Service::read_namespaced_service),
+            ObjType::StatefulSet => {
+                read_namespaced!(api_apps::StatefulSet::read_namespaced_stateful_set)
+            }
+            ObjType::StorageClass => read!(api_storage::StorageClass::read_storage_class),
+            ObjType::Crd {
+                ref _type,
+                ref group_version,
+            } => {
+                let read_crd = |name: &str, ns: &str, _: ()| {
+                    crate::crd::read_namespaced_resource(name, ns, _type, group_version)
+                        .map(|request| (request, ()))
+                };
+                read_namespaced!(read_crd)
+            }
+            #[cfg(feature = "argorollouts")]
+            ObjType::Rollout => {
+                let read_rollout = |name: &str, ns: &str, _: ()| {
+                    use crate::command::rollouts::RolloutValue;
+                    RolloutValue::read_namespaced_rollout(name, ns, Default::default())
+                        .map(|request| (request, ()))
+                };
+                read_namespaced!(read_rollout)
+            }
+        };
+        Ok(request)
+    }
+
+    /// Build a request to watch the object represented by this kobj. The watch is on the object's
+    /// collection, with a field selector so only this object is seen
+    pub fn watch_request(&self, options: WatchOptional) -> Result<Request<Vec<u8>>, ClickError> {
+        let read_request = self.read_request()?;
+        let path = read_request.uri().path();
+        let collection = match path.rsplit_once('/') {
+            Some((collection, name)) if name == self.name => collection,
+            _ => {
+                return Err(ClickError::CommandError(format!(
+                    "Unexpected path for {}: {}",
+                    self.name, path
+                )))
+            }
+        };
+        let field_selector = format!("metadata.name={}", self.name);
+        let options = WatchOptional {
+            field_selector: Some(&field_selector),
+            ..options
+        };
+        let url = format!("{}?", collection);
+        let start = url.len();
+        let mut query_pairs = url::form_urlencoded::Serializer::for_suffix(url, start);
+        options.__serialize(&mut query_pairs);
+        Request::get(query_pairs.finish())
+            .body(vec![])
+            .map_err(|e| ClickError::RequestError(k8s_openapi::RequestError::Http(e)))
+    }
+
     /// describe the object represented by this kobj
     pub fn describe(
         &self,
@@ -120,134 +275,88 @@ impl KObj {
         env: &Env,
         writer: &mut ClickWriter,
     ) -> Result<(), ClickError> {
-        let mut table = comfy_table::Table::new();
-        table.load_preset(comfy_table::presets::NOTHING);
-        table.set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
-        // we use some macro hacking here as each read_x call returns different types that have no
-        // common trait we could rely on to write generic code
+        let mut table = describe::describe_table();
+        let verbose = describe::want_verbose(matches);
+        // each type is read into a different struct, and there's no common trait to write generic
+        // code with, so use a macro
         macro_rules! do_describe {
-            ($read_func:expr, $resp_typ:ty, $resp_ok:path, $($desc_func: expr),*) => {{
-                let (request, _) = $read_func(&self.name, Default::default())?;
-                match env
-                    .run_on_context(|c| c.read::<$resp_typ>(request))
-                    .unwrap()
-                {
-                    $resp_ok(t) => {
-                        if !describe::maybe_full_describe_output(matches, &t, writer) {
-                            $(
-                                $desc_func(&t, &mut table)?;
-                            )*
-                        }
-                    }
-                    _ => {} // TODO
+            ($typ:ty, $($desc_func: expr),*) => {{
+                let request = self.read_request()?;
+                if describe::want_full_describe_output(matches) {
+                    let value = env.run_on_context(|c| c.get_value(request))?;
+                    describe::maybe_full_describe_output(matches, &value, env, writer);
+                } else {
+                    let t: $typ = env.run_on_context(|c| c.read_object(request))?;
+                    $(
+                        $desc_func(&t, env, verbose, &mut table)?;
+                    )*
                 }
             }};
         }
-        macro_rules! do_describe_with_namespace {
-            // TODO: It would be nice to merge these two
-            ($read_func: expr, $resp_typ: ty, $resp_ok: path, $($desc_func: expr),*) => {
-                match self.namespace.as_ref() {
-                    Some(ns) => {
-                        let (request, _) = $read_func(&self.name, ns, Default::default())?;
-                        match env
-                            .run_on_context(|c| c.read::<$resp_typ>(request))
-                            .unwrap()
-                        {
-                            $resp_ok(t) => {
-                                if !describe::maybe_full_describe_output(matches, &t, writer) {
-                                    $(
-                                        $desc_func(&t, &mut table)?;
-                                    )*
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                    None => {
-                        clickwriteln!(writer, "No namespace for {}, cannot describe", self.name);
-                    }
-                }
-            };
-        }
         match self.typ {
             ObjType::ConfigMap => {
-                do_describe_with_namespace!(
-                    api::ConfigMap::read_namespaced_config_map,
-                    api::ReadNamespacedConfigMapResponse,
-                    api::ReadNamespacedConfigMapResponse::Ok,
-                    describe::describe_metadata
-                );
+                do_describe!(api::ConfigMap, describe::describe_metadata);
             }
             ObjType::DaemonSet => {
-                do_describe_with_namespace!(
-                    api_apps::DaemonSet::read_namespaced_daemon_set,
-                    api_apps::ReadNamespacedDaemonSetResponse,
-                    api_apps::ReadNamespacedDaemonSetResponse::Ok,
-                    describe::describe_metadata
-                );
+                do_describe!(api_apps::DaemonSet, describe::describe_metadata);
             }
             ObjType::Deployment => {
-                do_describe_with_namespace!(
-                    api_apps::Deployment::read_namespaced_deployment,
-                    api_apps::ReadNamespacedDeploymentResponse,
-                    api_apps::ReadNamespacedDeploymentResponse::Ok,
-                    describe::legacy::describe_format_deployment
+                do_describe!(
+                    api_apps::Deployment,
+                    describe::legacy::describe_format_deployment,
+                    describe::deployment::describe_deployment_replicasets
                 );
             }
-            ObjType::Job => {
-                do_describe_with_namespace!(
-                    api_batch::Job::read_namespaced_job,
-                    api_batch::ReadNamespacedJobResponse,
-                    api_batch::ReadNamespacedJobResponse::Ok,
+            ObjType::HorizontalPodAutoscaler => {
+                do_describe!(
+                    api_autoscaling::HorizontalPodAutoscaler,
                     describe::describe_metadata
                 );
             }
+            ObjType::Job => {
+                do_describe!(api_batch::Job, describe::describe_metadata);
+            }
+            ObjType::CronJob => {
+                do_describe!(api_batch::CronJob, describe::describe_metadata);
+            }
             ObjType::Namespace => {
-                do_describe!(
-                    api::Namespace::read_namespace,
-                    api::ReadNamespaceResponse,
-                    api::ReadNamespaceResponse::Ok,
-                    describe::describe_metadata
-                );
+                do_describe!(api::Namespace, describe::describe_metadata);
             }
             ObjType::Node => {
                 do_describe!(
-                    api::Node::read_node,
-                    api::ReadNodeResponse,
-                    api::ReadNodeResponse::Ok,
-                    describe::legacy::describe_format_node
+                    api::Node,
+                    describe::legacy::describe_format_node,
+                    describe::node::describe_node_pods
                 );
             }
             ObjType::PersistentVolume => {
-                do_describe!(
-                    api::PersistentVolume::read_persistent_volume,
-                    api::ReadPersistentVolumeResponse,
-                    api::ReadPersistentVolumeResponse::Ok,
-                    describe::describe_metadata
-                );
+                do_describe!(api::PersistentVolume, describe::describe_metadata);
+            }
+            ObjType::PersistentVolumeClaim => {
+                do_describe!(api::PersistentVolumeClaim, describe::describe_metadata);
             }
             ObjType::Pod { .. } => {
-                do_describe_with_namespace!(
-                    api::Pod::read_namespaced_pod,
-                    api::ReadNamespacedPodResponse,
-                    api::ReadNamespacedPodResponse::Ok,
-                    describe::legacy::describe_format_pod
+                do_describe!(
+                    api::Pod,
+                    describe::legacy::describe_format_pod,
+                    describe::pod::describe_pod_probe_failures
                 );
             }
             ObjType::ReplicaSet => {
-                do_describe_with_namespace!(
-                    api_apps::ReplicaSet::read_namespaced_replica_set,
-                    api_apps::ReadNamespacedReplicaSetResponse,
-                    api_apps::ReadNamespacedReplicaSetResponse::Ok,
-                    describe::describe_metadata
-                );
+                do_describe!(api_apps::ReplicaSet, describe::describe_metadata);
             }
             ObjType::Secret => {
-                do_describe_with_namespace!(
-                    api::Secret::read_namespaced_secret,
-                    api::ReadNamespacedSecretResponse,
-                    api::ReadNamespacedSecretResponse::Ok,
-                    describe::legacy::describe_format_secret
+                do_describe!(
+                    api::Secret,
+                    |secret: &api::Secret, env: &Env, verbose, table: &mut comfy_table::Table| {
+                        describe::legacy::describe_format_secret(
+                            secret,
+                            env,
+                            verbose,
+                            matches.is_present("reveal"),
+                            table,
+                        )
+                    }
                 );
             }
             ObjType::Service => {
@@ -261,20 +370,10 @@ impl KObj {
                 )?;
             }
             ObjType::StatefulSet => {
-                do_describe_with_namespace!(
-                    api_apps::StatefulSet::read_namespaced_stateful_set,
-                    api_apps::ReadNamespacedStatefulSetResponse,
-                    api_apps::ReadNamespacedStatefulSetResponse::Ok,
-                    describe::describe_metadata
-                );
+                do_describe!(api_apps::StatefulSet, describe::describe_metadata);
             }
             ObjType::StorageClass => {
-                do_describe!(
-                    api_storage::StorageClass::read_storage_class,
-                    api_storage::ReadStorageClassResponse,
-                    api_storage::ReadStorageClassResponse::Ok,
-                    describe::describe_metadata
-                );
+                do_describe!(api_storage::StorageClass, describe::describe_metadata);
             }
             ObjType::Crd {
                 ref _type,
@@ -293,10 +392,8 @@ impl KObj {
             #[cfg(feature = "argorollouts")]
             ObjType::Rollout => {
                 use crate::command::rollouts;
-                do_describe_with_namespace!(
-                    rollouts::RolloutValue::read_namespaced_rollout,
-                    rollouts::ReadNamespacedRolloutValueResponse,
-                    rollouts::ReadNamespacedRolloutValueResponse::Ok,
+                do_describe!(
+                    rollouts::RolloutValue,
                     describe::legacy::describe_format_rollout
                 );
             }
@@ -305,3 +402,37 @@ impl KObj {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_request() {
+        let pod = KObj {
+            name: "web-1".to_string(),
+            namespace: Some("prod".to_string()),
+            typ: ObjType::Pod { containers: vec![] },
+        };
+        let options = WatchOptional {
+            resource_version: Some("42"),
+            ..Default::default()
+        };
+        let request = pod.watch_request(options).unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "/api/v1/namespaces/prod/pods?fieldSelector=metadata.name%3Dweb-1&resourceVersion=42&watch=true"
+        );
+
+        let node = KObj {
+            name: "node-a".to_string(),
+            namespace: None,
+            typ: ObjType::Node,
+        };
+        let request = node.watch_request(WatchOptional::default()).unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "/api/v1/nodes?fieldSelector=metadata.name%3Dnode-a&watch=true"
+        );
+    }
+}
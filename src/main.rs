@@ -61,22 +61,27 @@ mod command_processor;
 mod config;
 mod crd;
 mod describe;
+mod diff;
 mod env;
 mod error;
+mod http_log;
+mod jsonpath;
 mod k8s;
 mod k8s_table;
 mod kobj;
 mod parser;
+mod picker;
 mod styles;
 mod table;
+mod template;
 mod values;
 
 #[cfg(test)]
 mod duct_mock;
 
-use clap::{Arg, Command as ClapCommand};
+use clap::{Arg, ArgMatches, Command as ClapCommand};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::command_processor::CommandProcessor;
 use crate::config::{ClickConfig, Config};
@@ -84,6 +89,42 @@ use crate::env::Env;
 
 use crate::output::ClickWriter;
 
+/// Get the path to one of click's files in ~/.config/click. Click used to keep its files in the
+/// kube config dir as old_name, so if there's an old file and no new one, copy it over
+fn click_file_path(conf_dir: &Path, old_name: &str, name: &str) -> PathBuf {
+    let old_path = conf_dir.join(old_name);
+    let path = match dirs::home_dir() {
+        Some(home) => home.join(".config").join("click").join(name),
+        None => return old_path,
+    };
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            println!("Couldn't create {}: {}", dir.display(), e);
+            return old_path;
+        }
+    }
+    if !path.exists() && old_path.exists() {
+        if let Err(e) = std::fs::copy(&old_path, &path) {
+            println!("Couldn't copy {} to {}: {}", old_name, path.display(), e);
+        }
+    }
+    path
+}
+
+/// Get the value of a command line arg, or of the environment variable var if the arg wasn't
+/// given. Empty variables are treated as unset
+fn arg_or_env(matches: &ArgMatches, arg: &str, var: &str) -> Option<String> {
+    matches
+        .value_of(arg)
+        .map(str::to_string)
+        .or_else(|| std::env::var(var).ok().filter(|val| !val.is_empty()))
+}
+
+/// Is the environment variable var set to anything but the empty string
+fn env_flag(var: &str) -> bool {
+    std::env::var_os(var).is_some_and(|val| !val.is_empty())
+}
+
 fn main() {
     env_logger::init();
     // Command line arg parsing for click itself
@@ -106,6 +147,21 @@ fn main() {
                 .help("Execute the specified command then exit")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new("script")
+                .long("script")
+                .value_name("FILE")
+                .help("Run each line of FILE as a command then exit (see 'help source')")
+                .conflicts_with("exec")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("keep-going")
+                .short('k')
+                .long("keep-going")
+                .help("Keep running a --script after a command fails")
+                .requires("script"),
+        )
         .arg(
             Arg::new("context")
                 .short('C')
@@ -120,9 +176,60 @@ fn main() {
                 .help("Start in the specified namespace")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new("verbosity")
+                .short('v')
+                .long("verbosity")
+                .value_name("LEVEL")
+                .help(
+                    "Log http requests to stderr. 6 logs urls and status codes, 7 adds headers \
+                     (with credentials redacted), 8 adds bodies and 9 doesn't truncate them",
+                )
+                .validator(|level| match level.parse::<u8>() {
+                    Ok(l) if (http_log::MIN_VERBOSITY..=http_log::MAX_VERBOSITY).contains(&l) => {
+                        Ok(())
+                    }
+                    _ => Err(format!(
+                        "must be a number from {} to {}",
+                        http_log::MIN_VERBOSITY,
+                        http_log::MAX_VERBOSITY
+                    )),
+                })
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("log_file")
+                .long("log-file")
+                .value_name("FILE")
+                .help("Append the logs from --verbosity to FILE instead of stderr")
+                .requires("verbosity")
+                .takes_value(true),
+        )
+        .after_help(
+            "ENVIRONMENT:
+    CLICK_CONFIG_DIR    Same as --config_dir
+    CLICK_CONTEXT       Same as --context
+    CLICK_NAMESPACE     Same as --namespace
+    CLICK_NO_COLOR      If set, don't use colors (NO_COLOR works too). This isn't saved to the
+                        click config
+    KUBECONFIG          A list of kubernetes config files to use instead of ~/.kube/config
+
+Command line options take precedence over the environment.",
+        )
         .get_matches();
 
-    let conf_dir = if let Some(dir) = matches.value_of("config_dir") {
+    if let Some(level) = matches.value_of("verbosity") {
+        http_log::set_verbosity(level.parse().unwrap()); // safe as validated
+    }
+    if let Some(log_file) = matches.value_of("log_file") {
+        if let Err(e) = http_log::set_log_file(Path::new(log_file)) {
+            println!("Could not open log file {}: {}", log_file, e);
+            std::process::exit(-2);
+        }
+    }
+
+    let config_dir = arg_or_env(&matches, "config_dir", "CLICK_CONFIG_DIR");
+    let conf_dir = if let Some(ref dir) = config_dir {
         PathBuf::from(dir)
     } else {
         match dirs::home_dir() {
@@ -137,8 +244,18 @@ fn main() {
         }
     };
 
-    let mut click_path = conf_dir.clone();
-    click_path.push("click.config");
+    // with an explicit config dir, keep everything in it
+    let (click_path, hist_path) = if config_dir.is_some() {
+        (
+            conf_dir.join("click.config"),
+            conf_dir.join("click.history"),
+        )
+    } else {
+        (
+            click_file_path(&conf_dir, "click.config", "config.yaml"),
+            click_file_path(&conf_dir, "click.history", "history"),
+        )
+    };
     let click_conf = match ClickConfig::from_file(click_path.as_path().to_str().unwrap()) {
         Ok(conf) => conf,
         Err(e) => {
@@ -178,21 +295,24 @@ fn main() {
         }
     };
 
-    let mut hist_path = conf_dir;
-    hist_path.push("click.history");
-
     let mut env = Env::new(config, click_conf, click_path);
-    if let Some(context) = matches.value_of("context") {
-        env.set_context(Some(context));
+    if let Some(context) = arg_or_env(&matches, "context", "CLICK_CONTEXT") {
+        env.set_context(Some(&context));
     }
-    if let Some(namespace) = matches.value_of("namespace") {
-        env.set_namespace(Some(namespace));
+    if let Some(namespace) = arg_or_env(&matches, "namespace", "CLICK_NAMESPACE") {
+        env.set_namespace(Some(&namespace));
     }
+    env.no_color = env_flag("CLICK_NO_COLOR") || env_flag("NO_COLOR");
 
     let mut processor = CommandProcessor::new(env, hist_path);
     if let Some(command) = matches.value_of("exec") {
         let writer = ClickWriter::new();
         processor.process_line(command, writer);
+    } else if let Some(script) = matches.value_of("script") {
+        if let Err(e) = processor.run_script(Path::new(script), matches.is_present("keep-going")) {
+            println!("{}", e);
+            std::process::exit(1);
+        }
     } else {
         processor.run_repl();
     }
@@ -22,9 +22,12 @@ use serde::ser::Serialize;
 use serde_json::ser::{CharEscape, Formatter, PrettyFormatter, Serializer};
 use serde_json::Error as JsonError;
 
+use std::fmt;
 use std::fs::File;
 use std::io;
-use std::io::{Stdout, Write};
+use std::io::{IsTerminal, Stdout, Write};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
 
 use crate::error::ClickError;
 
@@ -55,6 +58,165 @@ macro_rules! clickwriteln {
     };
 }
 
+/// The formats that commands which print objects can output in
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// click's normal table/description output
+    #[default]
+    Table,
+    /// the normal table output, with all the extra columns shown
+    Wide,
+    /// the table columns as comma separated values
+    Csv,
+    /// the table columns as tab separated values
+    Tsv,
+    /// the table columns as a markdown table
+    Markdown,
+    /// the full object as returned by the api server, as yaml
+    Yaml,
+    /// the full object as json, optionally only printing the parts selected by the specified path
+    /// (see jsonpath::parse_path for the syntax)
+    Json(Option<String>),
+    /// a kubectl style jsonpath template (see jsonpath::Template) rendered against the object
+    JsonPath(String),
+    /// a table with the specified (title, path) columns
+    CustomColumns(Vec<(String, String)>),
+    /// a handlebars style template (see template::Template) rendered against the object
+    Template(String),
+}
+
+impl OutputFormat {
+    /// Build an output format from its name and argument, like the path for json or the template
+    /// for jsonpath
+    pub fn new(name: &str, arg: Option<&str>) -> Result<OutputFormat, String> {
+        match (name, arg) {
+            ("table", None) => Ok(OutputFormat::Table),
+            ("wide", None) => Ok(OutputFormat::Wide),
+            ("csv", None) => Ok(OutputFormat::Csv),
+            ("tsv", None) => Ok(OutputFormat::Tsv),
+            ("markdown", None) | ("md", None) => Ok(OutputFormat::Markdown),
+            ("yaml", None) => Ok(OutputFormat::Yaml),
+            ("json", filter) => Ok(OutputFormat::Json(filter.map(|f| f.to_string()))),
+            ("jsonpath", Some(template)) => Ok(OutputFormat::JsonPath(template.to_string())),
+            ("jsonpath", None) => Err("jsonpath output requires a template".to_string()),
+            ("custom-columns", Some(spec)) => {
+                Ok(OutputFormat::CustomColumns(parse_custom_columns(spec)?))
+            }
+            ("custom-columns", None) => {
+                Err("custom-columns output requires a column specification".to_string())
+            }
+            ("template", Some(template)) => Ok(OutputFormat::Template(template.to_string())),
+            ("template-file", Some(path)) => match std::fs::read_to_string(path) {
+                Ok(template) => Ok(OutputFormat::Template(template)),
+                Err(e) => Err(format!("Could not read template file {}: {}", path, e)),
+            },
+            ("template", None) | ("template-file", None) => {
+                Err(format!("{} output requires a template", name))
+            }
+            ("table", Some(_))
+            | ("wide", Some(_))
+            | ("csv", Some(_))
+            | ("tsv", Some(_))
+            | ("markdown", Some(_))
+            | ("md", Some(_))
+            | ("yaml", Some(_)) => Err(format!("Output format {} does not take an argument", name)),
+            _ => Err(format!(
+                "Unknown output format '{}'. Valid formats are: table, wide, csv, tsv, markdown, \
+                 yaml, json, \
+                 jsonpath=<template>, custom-columns=<spec>, template=<template>, \
+                 template-file=<path>",
+                name
+            )),
+        }
+    }
+
+    /// Is this format printed as click's own table (or description), rather than from the raw
+    /// object
+    pub fn is_table(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Table
+                | OutputFormat::Wide
+                | OutputFormat::Csv
+                | OutputFormat::Tsv
+                | OutputFormat::Markdown
+        )
+    }
+
+    /// The separator to print columns with, for the delimited formats
+    pub fn separator(&self) -> Option<char> {
+        match self {
+            OutputFormat::Csv => Some(','),
+            OutputFormat::Tsv => Some('\t'),
+            _ => None,
+        }
+    }
+}
+
+/// Split a custom columns spec like NAME:.metadata.name,NODE:.spec.nodeName into (title, path)
+/// pairs. Commas inside of []s (like in a filter) don't split columns
+fn parse_custom_columns(spec: &str) -> Result<Vec<(String, String)>, String> {
+    let mut columns = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    let mut parts = vec![];
+    for (i, c) in spec.char_indices() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&spec[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&spec[start..]);
+    for part in parts.into_iter() {
+        match part.split_once(':') {
+            Some((title, path)) if !title.is_empty() && !path.is_empty() => {
+                columns.push((title.to_string(), path.to_string()))
+            }
+            _ => {
+                return Err(format!(
+                    "Invalid custom column '{}', expected TITLE:path",
+                    part
+                ))
+            }
+        }
+    }
+    Ok(columns)
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    /// Parse formats like 'yaml' or 'jsonpath={.metadata.name}'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, arg)) => OutputFormat::new(name, Some(arg)),
+            None => OutputFormat::new(s, None),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputFormat::Table => write!(f, "table"),
+            OutputFormat::Wide => write!(f, "wide"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Tsv => write!(f, "tsv"),
+            OutputFormat::Markdown => write!(f, "markdown"),
+            OutputFormat::Yaml => write!(f, "yaml"),
+            OutputFormat::Json(_) => write!(f, "json"),
+            OutputFormat::JsonPath(_) => write!(f, "jsonpath"),
+            OutputFormat::CustomColumns(_) => write!(f, "custom-columns"),
+            OutputFormat::Template(_) => write!(f, "template"),
+        }
+    }
+}
+
 struct PipeProc {
     pipe: PipeWriter,
     expr: Handle,
@@ -76,8 +238,97 @@ impl PipeProc {
     }
 }
 
+/// Output that's sent through a pager if it turns out to be longer than the terminal
+struct PagedOutput {
+    pager_cmd: String,
+    max_lines: usize,
+    state: PagerState,
+}
+
+enum PagerState {
+    /// holding output until we know if it fits on the screen
+    Buffering(Vec<u8>, usize),
+    Paging(Child),
+    /// writing straight to stdout, either because the command flushed (which it does when it needs
+    /// the user to see output, like when prompting), or because the pager failed to start
+    Direct(Stdout),
+}
+
+impl PagedOutput {
+    fn start_pager(&mut self, buf: &[u8]) -> io::Result<()> {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(&self.pager_cmd).stdin(Stdio::piped());
+        if std::env::var_os("LESS").is_none() {
+            // like git, let less show colors and not clear the screen when it exits
+            cmd.env("LESS", "FRX");
+        }
+        match cmd.spawn() {
+            Ok(mut child) => {
+                let res = child.stdin.as_mut().unwrap().write_all(buf); // safe, stdin is piped
+                self.state = PagerState::Paging(child);
+                res
+            }
+            Err(e) => {
+                eprintln!("Could not start pager '{}': {}", self.pager_cmd, e);
+                let mut stdout = std::io::stdout();
+                let res = stdout.write_all(buf);
+                self.state = PagerState::Direct(stdout);
+                res
+            }
+        }
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.state {
+            PagerState::Buffering(ref mut buffer, ref mut lines) => {
+                buffer.extend_from_slice(buf);
+                *lines += buf.iter().filter(|b| **b == b'\n').count();
+                if *lines >= self.max_lines {
+                    let buffer = std::mem::take(buffer);
+                    self.start_pager(&buffer)?;
+                }
+                Ok(buf.len())
+            }
+            PagerState::Paging(ref mut child) => child.stdin.as_mut().unwrap().write(buf), // safe, stdin is piped
+            PagerState::Direct(ref mut stdout) => stdout.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.state {
+            PagerState::Buffering(ref mut buffer, _) => {
+                let mut stdout = std::io::stdout();
+                let res = stdout.write_all(buffer).and_then(|_| stdout.flush());
+                self.state = PagerState::Direct(stdout);
+                res
+            }
+            PagerState::Paging(ref mut child) => child.stdin.as_mut().unwrap().flush(), // safe, stdin is piped
+            PagerState::Direct(ref mut stdout) => stdout.flush(),
+        }
+    }
+
+    fn finish(self) {
+        match self.state {
+            PagerState::Buffering(buffer, _) => {
+                let mut stdout = std::io::stdout();
+                stdout.write_all(&buffer).unwrap_or(());
+                stdout.flush().unwrap_or(());
+            }
+            PagerState::Paging(mut child) => {
+                // close stdin so the pager knows the output is done, and wait for the user to quit it
+                drop(child.stdin.take());
+                if let Err(e) = child.wait() {
+                    eprintln!("Failed to wait for pager: {}", e);
+                }
+            }
+            PagerState::Direct(_) => {}
+        }
+    }
+}
+
 enum WriterOutput {
     Stdout(Stdout),
+    Paged(Box<PagedOutput>),
     #[allow(dead_code)] // used in test
     Buffer(Vec<u8>),
     File(File),
@@ -86,12 +337,18 @@ enum WriterOutput {
 
 pub struct ClickWriter {
     output: WriterOutput,
+    // a copy of what's written, for the grep command
+    capture: Option<Vec<u8>>,
 }
 
+/// Stop capturing output after this many bytes, so huge outputs don't use up memory
+const MAX_CAPTURE: usize = 4 * 1024 * 1024;
+
 impl ClickWriter {
     pub fn new() -> ClickWriter {
         ClickWriter {
             output: WriterOutput::Stdout(std::io::stdout()),
+            capture: None,
         }
     }
 
@@ -99,9 +356,47 @@ impl ClickWriter {
     pub fn with_buffer(buffer: Vec<u8>, _do_color: bool) -> ClickWriter {
         ClickWriter {
             output: WriterOutput::Buffer(buffer),
+            capture: None,
         }
     }
 
+    /// A new writer for the same kind of output as this one: an empty buffer if this writes to a
+    /// buffer, and stdout otherwise
+    pub fn new_like(&self) -> ClickWriter {
+        match self.output {
+            WriterOutput::Buffer(_) => ClickWriter::with_buffer(vec![], false),
+            _ => ClickWriter::new(),
+        }
+    }
+
+    /// Send output through pager_cmd if it's longer than the terminal. Does nothing if output
+    /// isn't going to a terminal
+    pub fn enable_pager(&mut self, pager_cmd: String) {
+        if let WriterOutput::Stdout(ref stdout) = self.output {
+            if !stdout.is_terminal() {
+                return;
+            }
+            if let Ok((_, rows)) = crossterm::terminal::size() {
+                self.output = WriterOutput::Paged(Box::new(PagedOutput {
+                    pager_cmd,
+                    // leave room for the prompt
+                    max_lines: (rows as usize).saturating_sub(1),
+                    state: PagerState::Buffering(vec![], 0),
+                }));
+            }
+        }
+    }
+
+    /// Start keeping a copy of everything written, which take_capture returns
+    pub fn start_capture(&mut self) {
+        self.capture = Some(vec![]);
+    }
+
+    /// Stop capturing, and return what was written since start_capture
+    pub fn take_capture(&mut self) -> Option<Vec<u8>> {
+        self.capture.take()
+    }
+
     pub fn set_output_file(&mut self, file: File) {
         self.output = WriterOutput::File(file);
     }
@@ -130,16 +425,29 @@ impl ClickWriter {
                 }
                 None
             }
+            WriterOutput::Paged(paged) => {
+                paged.finish();
+                None
+            }
             WriterOutput::Buffer(buffer) => Some(buffer),
             _ => None,
         }
     }
 
+    /// Is output going to a terminal (rather than a file or a pipe)
+    pub fn is_tty(&self) -> bool {
+        match self.output {
+            WriterOutput::Stdout(ref stdout) => stdout.is_terminal(),
+            WriterOutput::Paged(_) => true,
+            _ => false,
+        }
+    }
+
     pub fn pretty_color_json<T: ?Sized>(&mut self, value: &T) -> Result<(), JsonError>
     where
         T: Serialize,
     {
-        if let WriterOutput::Stdout(_) = self.output {
+        if self.is_tty() {
             let mut ser = Serializer::with_formatter(self, PrettyColorFormatter::new());
             value.serialize(&mut ser)
         } else {
@@ -164,17 +472,24 @@ impl Default for ClickWriter {
 
 impl Write for ClickWriter {
     fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
-        match self.output {
-            WriterOutput::Stdout(ref mut stdout) => stdout.write(buf),
-            WriterOutput::Buffer(ref mut buffer) => buffer.write(buf),
-            WriterOutput::File(ref mut file) => file.write(buf),
-            WriterOutput::Pipe(ref mut pipe_proc) => pipe_proc.write(buf),
+        let written = match self.output {
+            WriterOutput::Stdout(ref mut stdout) => stdout.write(buf)?,
+            WriterOutput::Paged(ref mut paged) => paged.write(buf)?,
+            WriterOutput::Buffer(ref mut buffer) => buffer.write(buf)?,
+            WriterOutput::File(ref mut file) => file.write(buf)?,
+            WriterOutput::Pipe(ref mut pipe_proc) => pipe_proc.write(buf)?,
+        };
+        if let Some(capture) = self.capture.as_mut() {
+            let room = MAX_CAPTURE.saturating_sub(capture.len());
+            capture.extend_from_slice(&buf[..written.min(room)]);
         }
+        Ok(written)
     }
 
     fn flush(&mut self) -> Result<(), io::Error> {
         match self.output {
             WriterOutput::Stdout(ref mut stdout) => stdout.flush(),
+            WriterOutput::Paged(ref mut paged) => paged.flush(),
             WriterOutput::Buffer(ref mut buffer) => buffer.flush(),
             WriterOutput::File(ref mut file) => file.flush(),
             WriterOutput::Pipe(ref mut pipe_proc) => pipe_proc.flush(),
@@ -402,3 +717,43 @@ impl<'a> Formatter for PrettyColorFormatter<'a> {
         self.pretty.end_object_value(writer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output_format() {
+        assert_eq!("yaml".parse::<OutputFormat>(), Ok(OutputFormat::Yaml));
+        assert_eq!("wide".parse::<OutputFormat>(), Ok(OutputFormat::Wide));
+        assert_eq!("tsv".parse::<OutputFormat>(), Ok(OutputFormat::Tsv));
+        assert_eq!("md".parse::<OutputFormat>(), Ok(OutputFormat::Markdown));
+        assert_eq!("json".parse::<OutputFormat>(), Ok(OutputFormat::Json(None)));
+        assert_eq!(
+            "jsonpath={.metadata.name}".parse::<OutputFormat>(),
+            Ok(OutputFormat::JsonPath("{.metadata.name}".to_string()))
+        );
+        assert_eq!(
+            "custom-columns=NAME:.metadata.name,READY:.status.conditions[?(@.type==\"Ready\")].status"
+                .parse::<OutputFormat>(),
+            Ok(OutputFormat::CustomColumns(vec![
+                ("NAME".to_string(), ".metadata.name".to_string()),
+                (
+                    "READY".to_string(),
+                    ".status.conditions[?(@.type==\"Ready\")].status".to_string()
+                ),
+            ]))
+        );
+        assert_eq!(
+            "template={{metadata.name}}".parse::<OutputFormat>(),
+            Ok(OutputFormat::Template("{{metadata.name}}".to_string()))
+        );
+        assert!("jsonpath".parse::<OutputFormat>().is_err());
+        assert!("template-file=/does/not/exist"
+            .parse::<OutputFormat>()
+            .is_err());
+        assert!("custom-columns=NAME".parse::<OutputFormat>().is_err());
+        assert!("yaml=foo".parse::<OutputFormat>().is_err());
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+}
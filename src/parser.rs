@@ -123,10 +123,23 @@ impl<'a> Iterator for Parser<'a> {
     }
 }
 
+// parse N-M into the inclusive range N..=M
+fn parse_dash_range(item: &str) -> Option<std::ops::RangeInclusive<usize>> {
+    let (start, end) = item.split_once('-')?;
+    Some(start.trim().parse::<usize>().ok()?..=end.trim().parse::<usize>().ok()?)
+}
+
 /// Try and parse a line of the form [N]..[M]. These conform to Rust's range expressions:
 /// https://doc.rust-lang.org/reference/expressions/range-expr.html
+/// N-M is also accepted as the same as N..=M, and * is the same as .. (everything)
 /// If we parse this successfully, we return
 pub fn try_parse_range(line: &str) -> Option<Box<dyn Iterator<Item = usize>>> {
+    if line == "*" {
+        return Some(Box::new(0..));
+    }
+    if let Some(range) = parse_dash_range(line) {
+        return Some(Box::new(range));
+    }
     if let Some(idx) = line.find("..") {
         // we have a string with a .., so keep processing
         let (start_str, end_str) = line.split_at(idx);
@@ -164,7 +177,7 @@ pub fn try_parse_range(line: &str) -> Option<Box<dyn Iterator<Item = usize>>> {
     None
 }
 
-/// try and parse a line of comma separated numbers like 1,3,5
+/// try and parse a line of comma separated numbers like 1,3,5. Items can also be ranges like 7-9
 pub fn try_parse_csl(line: &str) -> Option<Box<dyn Iterator<Item = usize>>> {
     let mut ret = Vec::new();
     let l = line.trim();
@@ -174,16 +187,97 @@ pub fn try_parse_csl(line: &str) -> Option<Box<dyn Iterator<Item = usize>>> {
     for item in l.split_terminator(',') {
         match item.trim().parse::<usize>() {
             Ok(num) => ret.push(num),
-            Err(_) => return None, // fail as soon as something's not a usize
+            Err(_) => match parse_dash_range(item) {
+                Some(range) => ret.extend(range),
+                None => return None, // fail as soon as something's not a usize or a range
+            },
         }
     }
     Some(Box::new(ret.into_iter()))
 }
 
+/// How a command in a chain like 'a && b ; c' depends on the one before it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChainOp {
+    /// run no matter what (the first command, or after ;)
+    Always,
+    /// only run if the previous command succeeded (after &&)
+    IfSucceeded,
+}
+
+/// Split a line into the commands chained with ; and &&. Separators inside quotes or escaped with \
+/// are left alone, and empty commands (like after a trailing ;) are dropped
+pub fn split_chain(line: &str) -> Vec<(ChainOp, &str)> {
+    let mut ret = vec![];
+    let mut op = ChainOp::Always;
+    let mut start = 0;
+    let mut quote = None;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some('"'), '\\') | (None, '\\') => {
+                chars.next(); // skip the escaped char
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, ';') => {
+                ret.push((op, &line[start..i]));
+                op = ChainOp::Always;
+                start = i + 1;
+            }
+            (None, '&') if matches!(chars.peek(), Some((_, '&'))) => {
+                chars.next();
+                ret.push((op, &line[start..i]));
+                op = ChainOp::IfSucceeded;
+                start = i + 2;
+            }
+            _ => {}
+        }
+    }
+    ret.push((op, &line[start..]));
+    ret.into_iter()
+        .map(|(op, cmd)| (op, cmd.trim()))
+        .filter(|(_, cmd)| !cmd.is_empty())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn split_chain_test() {
+        assert_eq!(split_chain("pods"), vec![(ChainOp::Always, "pods")]);
+        assert_eq!(
+            split_chain("context prod && namespace payments && pods"),
+            vec![
+                (ChainOp::Always, "context prod"),
+                (ChainOp::IfSucceeded, "namespace payments"),
+                (ChainOp::IfSucceeded, "pods"),
+            ]
+        );
+        assert_eq!(
+            split_chain("pods | grep web; events ;"),
+            vec![
+                (ChainOp::Always, "pods | grep web"),
+                (ChainOp::Always, "events"),
+            ]
+        );
+        assert_eq!(
+            split_chain("alias x \"pods ; events\" && logs 'a&&b' \\; c"),
+            vec![
+                (ChainOp::Always, "alias x \"pods ; events\""),
+                (ChainOp::IfSucceeded, "logs 'a&&b' \\; c"),
+            ]
+        );
+        assert_eq!(
+            split_chain("logs | grep a & b"),
+            vec![(ChainOp::Always, "logs | grep a & b")]
+        );
+        assert!(split_chain(" ; ").is_empty());
+    }
+
     #[test]
     fn try_parse_csl_test() {
         let v: Vec<usize> = try_parse_csl("1,2,3").unwrap().collect();
@@ -206,6 +300,10 @@ mod tests {
         assert!(try_parse_csl("").is_none());
         assert!(try_parse_csl(",").is_none());
         assert!(try_parse_csl("1,,2").is_none());
+
+        let v: Vec<usize> = try_parse_csl("1, 4-6,9").unwrap().collect();
+        assert_eq!(vec!(1, 4, 5, 6, 9), v);
+        assert!(try_parse_csl("1,4-").is_none());
         assert!(try_parse_csl(",,,").is_none());
         assert!(try_parse_csl(",1,2,").is_none());
     }
@@ -237,6 +335,16 @@ mod tests {
             assert_eq!(r.next().unwrap(), i);
         }
 
+        let v: Vec<usize> = try_parse_range("2-4").unwrap().collect();
+        assert_eq!(vec!(2, 3, 4), v);
+
+        let mut r = try_parse_range("*").unwrap();
+        for i in 0..10 {
+            assert_eq!(r.next().unwrap(), i);
+        }
+        assert!(try_parse_range("2-").is_none());
+        assert!(try_parse_range("my-pod").is_none());
+
         assert!(try_parse_range(",1,2,").is_none());
         assert!(try_parse_range("1").is_none());
         assert!(try_parse_range("pods").is_none());
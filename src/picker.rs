@@ -0,0 +1,231 @@
+// Copyright 2022 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An interactive fuzzy finder, for picking one item out of a long list by typing part of it
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+
+use std::io::{self, Write};
+
+use crate::completer::fuzzy_match;
+
+/// The indexes of the items that match query, best matches first. Items that start with query come
+/// first, then items that contain it, then items that match it fuzzily (see
+/// completer::fuzzy_match). Otherwise the order of items is kept. An empty query matches
+/// everything
+pub fn filter_items(items: &[String], query: &str) -> Vec<usize> {
+    let query_lower = query.to_lowercase();
+    let mut matches: Vec<(usize, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let item_lower = item.to_lowercase();
+            if item_lower.starts_with(&query_lower) {
+                Some((0, index))
+            } else if item_lower.contains(&query_lower) {
+                Some((1, index))
+            } else if fuzzy_match(item, query) {
+                Some((2, index))
+            } else {
+                None
+            }
+        })
+        .collect();
+    matches.sort();
+    matches.into_iter().map(|(_, index)| index).collect()
+}
+
+// the state of the picker while it's open
+struct Picker<'a> {
+    items: &'a [String],
+    query: String,
+    matches: Vec<usize>,
+    // position of the selected item in matches
+    selected: usize,
+    // position in matches of the first item shown
+    scroll: usize,
+}
+
+impl<'a> Picker<'a> {
+    fn new(items: &'a [String], query: &str) -> Picker<'a> {
+        let mut picker = Picker {
+            items,
+            query: query.to_string(),
+            matches: vec![],
+            selected: 0,
+            scroll: 0,
+        };
+        picker.update_matches();
+        picker
+    }
+
+    fn update_matches(&mut self) {
+        self.matches = filter_items(self.items, &self.query);
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn move_selection(&mut self, up: bool) {
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        } else if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (cols, rows) = terminal::size()?;
+        let cols = cols as usize;
+        // leave room for the query and status lines
+        let height = (rows as usize).saturating_sub(2).max(1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + height {
+            self.scroll = self.selected + 1 - height;
+        }
+
+        queue!(
+            out,
+            terminal::Clear(ClearType::All),
+            cursor::MoveTo(0, 1),
+            Print(format!(
+                "  {}/{} (Enter to select, Esc to cancel)",
+                self.matches.len(),
+                self.items.len()
+            )),
+        )?;
+        for (row, index) in self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(height)
+        {
+            let line: String = self.items[*index]
+                .chars()
+                .take(cols.saturating_sub(2))
+                .collect();
+            queue!(out, cursor::MoveTo(0, (row - self.scroll + 2) as u16))?;
+            if row == self.selected {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(format!("> {}", line)),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(out, Print(format!("  {}", line)))?;
+            }
+        }
+        queue!(
+            out,
+            cursor::MoveTo(0, 0),
+            Print(format!("> {}", self.query))
+        )?;
+        out.flush()
+    }
+
+    // handle a key, returns Some(result) when the picker should close
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Option<usize>> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => return Some(self.matches.get(self.selected).copied()),
+            KeyCode::Esc => return Some(None),
+            KeyCode::Char('c') | KeyCode::Char('g') | KeyCode::Char('d') if ctrl => {
+                return Some(None)
+            }
+            KeyCode::Up => self.move_selection(true),
+            KeyCode::Char('p') | KeyCode::Char('k') if ctrl => self.move_selection(true),
+            KeyCode::Down | KeyCode::Tab => self.move_selection(false),
+            KeyCode::Char('n') | KeyCode::Char('j') if ctrl => self.move_selection(false),
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.update_matches();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.update_matches();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.update_matches();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn run(&mut self, out: &mut impl Write) -> io::Result<Option<usize>> {
+        loop {
+            self.draw(out)?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+                if let Some(result) = self.handle_key(key) {
+                    return Ok(result);
+                }
+            }
+        }
+    }
+}
+
+/// Open a full screen fuzzy finder over items, starting with query already typed. Returns the
+/// index of the item that was picked, or None if the picker was cancelled
+pub fn pick(items: &[String], query: &str) -> io::Result<Option<usize>> {
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    let res = queue!(out, terminal::EnterAlternateScreen)
+        .and_then(|_| Picker::new(items, query).run(&mut out));
+    // always try to put the terminal back, even if the picker failed
+    let restore = queue!(out, terminal::LeaveAlternateScreen)
+        .and_then(|_| out.flush())
+        .and_then(|_| terminal::disable_raw_mode());
+    let picked = res?;
+    restore?;
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_items() {
+        let items: Vec<String> = vec!["web-1", "api-web", "worker-b", "redis"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(filter_items(&items, ""), vec![0, 1, 2, 3]);
+        // prefix, then substring, then fuzzy
+        assert_eq!(filter_items(&items, "w"), vec![0, 2, 1]);
+        assert_eq!(filter_items(&items, "WEB"), vec![0, 1, 2]);
+        assert_eq!(filter_items(&items, "wb"), vec![0, 1, 2]);
+        assert!(filter_items(&items, "xyz").is_empty());
+
+        let mut picker = Picker::new(&items, "r");
+        assert_eq!(picker.matches, vec![3, 2]);
+        picker.move_selection(false);
+        picker.move_selection(false);
+        assert_eq!(picker.selected, 1);
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(picker.handle_key(enter), Some(Some(2)));
+    }
+}
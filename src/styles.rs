@@ -13,11 +13,80 @@
 // limitations under the License.
 
 /// Module that defines methods to color click output appropriately
-use crossterm::style::{Attribute, Attributes, Color, ContentStyle, StyledContent, Stylize};
-use std::collections::HashMap;
+use crossterm::style::{
+    Attribute, Attributes, Color, ContentStyle, SetForegroundColor, StyledContent, Stylize,
+};
+use regex::Regex;
+
+use crate::config::Theme;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 
 pub struct Styles {
     prompt_object_map: HashMap<&'static str, ContentStyle>,
+    // the color of each element in THEME_ELEMENTS
+    colors: HashMap<&'static str, Color>,
+}
+
+/// The parts of click's output whose color can be set in the theme_colors config
+pub const THEME_ELEMENTS: &[&str] = &[
+    "config_val",
+    "context_table",
+    "danger",
+    "info",
+    "prompt_context",
+    "prompt_namespace",
+    "prompt_none",
+    "prompt_range",
+    "success",
+    "table_header",
+    "warning",
+];
+
+/// Parse a color for the theme_colors config. This can be a name (like red or dark_blue), an ansi
+/// color number from 0 to 255, an rgb color like #ff8000, or none for the terminal's default color
+pub fn parse_color(color: &str) -> Result<Color, String> {
+    if color.eq_ignore_ascii_case("none") {
+        return Ok(Color::Reset);
+    }
+    if let Ok(c) = Color::try_from(color) {
+        return Ok(c);
+    }
+    if let Ok(num) = color.parse::<u8>() {
+        return Ok(Color::AnsiValue(num));
+    }
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() == 6 {
+            if let Ok(rgb) = u32::from_str_radix(hex, 16) {
+                return Ok(Color::Rgb {
+                    r: (rgb >> 16) as u8,
+                    g: (rgb >> 8) as u8,
+                    b: rgb as u8,
+                });
+            }
+        }
+    }
+    Err(format!(
+        "Invalid color '{}', expected a name like red or dark_blue, a number from 0 to 255, \
+         #rrggbb, or none",
+        color
+    ))
+}
+
+// a darker version of color, for terminals with a light background
+fn light_color(color: Color) -> Color {
+    match color {
+        Color::Yellow | Color::DarkYellow => Color::AnsiValue(130), // a dark orange
+        Color::Red => Color::DarkRed,
+        Color::Green => Color::DarkGreen,
+        Color::Blue => Color::DarkBlue,
+        Color::Magenta => Color::DarkMagenta,
+        Color::Cyan => Color::DarkCyan,
+        Color::White | Color::Grey => Color::Black,
+        other => other,
+    }
 }
 
 macro_rules! style {
@@ -26,6 +95,15 @@ macro_rules! style {
     };
 }
 
+// a style whose color comes from the theme
+macro_rules! themed_style {
+    ($style_name:ident, $element:expr, $bold:expr) => {
+        pub fn $style_name<'a>(&self, s: &'a str) -> StyledContent<&'a str> {
+            self.themed($element, $bold, s)
+        }
+    };
+}
+
 macro_rules! obj_style {
     ($fg: expr) => {
         ContentStyle {
@@ -52,6 +130,12 @@ lazy_static! {
         attrs
     };
     static ref NOSTYLE: ContentStyle = ContentStyle::new();
+    static ref ANSI_ESCAPE: Regex = Regex::new("\x1b\\[[0-9;]*[A-Za-z]").unwrap();
+}
+
+/// Remove the escape sequences that color and style text from s
+pub fn strip_ansi(s: &str) -> Cow<'_, str> {
+    ANSI_ESCAPE.replace_all(s, "")
 }
 
 impl Styles {
@@ -74,7 +158,95 @@ impl Styles {
             ("Rollout", obj_style!(Color::Magenta, *BOLD)),
         ]);
 
-        Styles { prompt_object_map }
+        let colors = HashMap::from([
+            ("config_val", Color::Yellow),
+            ("context_table", Color::Red),
+            ("danger", Color::DarkRed),
+            ("info", Color::DarkBlue),
+            ("prompt_context", Color::Red),
+            ("prompt_namespace", Color::Green),
+            ("prompt_none", Color::DarkYellow),
+            ("prompt_range", Color::Blue),
+            ("success", Color::DarkGreen),
+            ("table_header", Color::Reset),
+            ("warning", Color::DarkYellow),
+        ]);
+
+        Styles {
+            prompt_object_map,
+            colors,
+        }
+    }
+
+    /// Use the colors of theme, with any colors set in overrides (element -> color) replacing the
+    /// theme's. Returns an error for the first invalid override, but still applies all the valid
+    /// ones
+    pub fn set_theme(
+        &mut self,
+        theme: Theme,
+        overrides: &BTreeMap<String, String>,
+    ) -> Result<(), String> {
+        *self = Styles::new();
+        if theme == Theme::Light {
+            for style in self.prompt_object_map.values_mut() {
+                style.foreground_color = style.foreground_color.map(light_color);
+            }
+            for color in self.colors.values_mut() {
+                *color = light_color(*color);
+            }
+        }
+        let mut res = Ok(());
+        for (element, color) in overrides.iter() {
+            let element = match THEME_ELEMENTS.iter().find(|e| **e == element.as_str()) {
+                Some(e) => *e,
+                None => {
+                    res = res.and(Err(format!(
+                        "Unknown theme element '{}', expected one of: {}",
+                        element,
+                        THEME_ELEMENTS.join(", ")
+                    )));
+                    continue;
+                }
+            };
+            match parse_color(color) {
+                Ok(c) => {
+                    self.colors.insert(element, c);
+                }
+                Err(e) => res = res.and(Err(e)),
+            }
+        }
+        res
+    }
+
+    fn color(&self, element: &str) -> Color {
+        self.colors.get(element).copied().unwrap_or(Color::Reset)
+    }
+
+    fn themed<'a>(&self, element: &str, bold: bool, s: &'a str) -> StyledContent<&'a str> {
+        let mut style = ContentStyle::new();
+        style.foreground_color = Some(self.color(element));
+        if bold {
+            style.attributes = *BOLD;
+        }
+        style.apply(s)
+    }
+
+    /// The escape code for a color token in a prompt template, like red or bold
+    pub fn prompt_token(name: &str) -> Option<String> {
+        let color = match name {
+            "black" => Color::Black,
+            "red" => Color::DarkRed,
+            "green" => Color::DarkGreen,
+            "yellow" => Color::DarkYellow,
+            "blue" => Color::DarkBlue,
+            "magenta" => Color::DarkMagenta,
+            "cyan" => Color::DarkCyan,
+            "white" => Color::Grey,
+            "bold" => return Some(Attribute::Bold.to_string()),
+            "reset" => return Some(Attribute::Reset.to_string()),
+            _ => return None,
+        };
+        Some(SetForegroundColor(color).to_string())
     }
 
     pub fn prompt_object<'a>(&self, name: &'a str, type_str: &str) -> StyledContent<&'a str> {
@@ -85,44 +257,104 @@ impl Styles {
     }
 
     // general colors
-    style!(success, s {s.dark_green()});
-    style!(warning, s {s.dark_yellow()});
-    style!(danger,  s {s.dark_red()});
+    themed_style!(success, "success", false);
+    themed_style!(warning, "warning", false);
+    themed_style!(danger, "danger", false);
 
     // prompt colors
-    style!(prompt_context,     s {s.red().bold()});
-    style!(prompt_namespace,   s {s.green().bold()});
-    style!(prompt_range,       s {s.blue()});
-    style!(prompt_select_none, s {s.dark_yellow()});
+    themed_style!(prompt_context, "prompt_context", true);
+    themed_style!(prompt_namespace, "prompt_namespace", true);
+    themed_style!(prompt_range, "prompt_range", false);
+    themed_style!(prompt_select_none, "prompt_none", false);
 
     // config printing colors
     // TODO: Maybe add this
     // style!(config_key, s {s.red()});
     pub fn config_val_string(&self, s: String) -> StyledContent<String> {
-        s.yellow()
+        let mut style = ContentStyle::new();
+        style.foreground_color = Some(self.color("config_val"));
+        style.apply(s)
     }
-    style!(config_val, s {s.yellow()});
+    themed_style!(config_val, "config_val", false);
+
+    // diff colors
+    style!(diff_add,    s {s.dark_green()});
+    style!(diff_remove, s {s.dark_red()});
+    style!(diff_hunk,   s {s.dark_cyan()});
 
     // other colors
     pub fn success_color(&self) -> Color {
-        Color::DarkGreen
+        self.color("success")
     }
     pub fn warning_color(&self) -> Color {
-        Color::DarkYellow
+        self.color("warning")
     }
     pub fn danger_color(&self) -> Color {
-        Color::DarkRed
+        self.color("danger")
     }
     pub fn info_color(&self) -> Color {
-        Color::DarkBlue
+        self.color("info")
     }
 
     pub fn context_table_color(&self) -> Color {
-        Color::Red
+        self.color("context_table")
+    }
+
+    pub fn table_header_color(&self) -> Color {
+        self.color("table_header")
     }
 
     // attributes
     style!(bold, s {s.bold()});
+
+    // matches of the highlight command
+    style!(highlight, s {s.black().on_yellow()});
+
+    /// Highlight all the matches of regex in text
+    pub fn highlight_matches<'a>(&self, text: &'a str, regex: &Regex) -> Cow<'a, str> {
+        if !regex.is_match(text) {
+            return text.into();
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for m in regex.find_iter(text) {
+            if m.as_str().is_empty() {
+                continue;
+            }
+            out.push_str(&text[last..m.start()]);
+            out.push_str(&self.highlight(m.as_str()).to_string());
+            last = m.end();
+        }
+        out.push_str(&text[last..]);
+        out.into()
+    }
+
+    /// Color a log source tag, like [pod/container]. The color depends only on the tag, so a
+    /// source keeps its color across lines and runs
+    pub fn log_source<'a>(&self, tag: &'a str) -> StyledContent<&'a str> {
+        tag.with(log_source_color(tag))
+    }
+}
+
+/// Colors for log source tags. Red is left out, so tags don't look like errors
+const LOG_SOURCE_COLORS: [Color; 8] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::DarkCyan,
+    Color::DarkGreen,
+    Color::DarkMagenta,
+];
+
+/// Pick a color for a log source tag by hashing it (with FNV-1a, which unlike the std hasher is the
+/// same for every build)
+fn log_source_color(tag: &str) -> Color {
+    let hash = tag.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    LOG_SOURCE_COLORS[(hash % LOG_SOURCE_COLORS.len() as u64) as usize]
 }
 
 impl Default for Styles {
@@ -130,3 +362,64 @@ impl Default for Styles {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_source_color() {
+        assert_eq!(
+            log_source_color("[web-1/app]"),
+            log_source_color("[web-1/app]")
+        );
+        let colors: std::collections::HashSet<String> =
+            ["[web-1/app]", "[web-2/app]", "[web-1/proxy]"]
+                .iter()
+                .map(|tag| format!("{:?}", log_source_color(tag)))
+                .collect();
+        assert!(colors.len() > 1);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("dark_blue"), Ok(Color::DarkBlue));
+        assert_eq!(parse_color("130"), Ok(Color::AnsiValue(130)));
+        assert_eq!(
+            parse_color("#ff8000"),
+            Ok(Color::Rgb {
+                r: 255,
+                g: 128,
+                b: 0
+            })
+        );
+        assert_eq!(parse_color("none"), Ok(Color::Reset));
+        assert!(parse_color("#ff80").is_err());
+        assert!(parse_color("notacolor").is_err());
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        let styles = Styles::new();
+        let colored = format!("pod {} ready", styles.highlight("web-1"));
+        assert_ne!(colored, "pod web-1 ready");
+        assert_eq!(strip_ansi(&colored), "pod web-1 ready");
+        assert_eq!(strip_ansi("plain"), "plain");
+    }
+
+    #[test]
+    fn test_set_theme() {
+        let mut styles = Styles::new();
+        let mut overrides = BTreeMap::new();
+        overrides.insert("table_header".to_string(), "blue".to_string());
+        styles.set_theme(Theme::Light, &overrides).unwrap();
+        assert_eq!(styles.warning_color(), Color::AnsiValue(130));
+        assert_eq!(styles.table_header_color(), Color::Blue);
+
+        overrides.insert("nothing".to_string(), "red".to_string());
+        assert!(styles.set_theme(Theme::Dark, &overrides).is_err());
+        // the valid override is still applied
+        assert_eq!(styles.warning_color(), Color::DarkYellow);
+        assert_eq!(styles.table_header_color(), Color::Blue);
+    }
+}
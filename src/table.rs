@@ -14,14 +14,17 @@
 
 use crate::command::format_duration;
 use crate::command::time_since;
+use crate::config::StatusGlyphs;
 use crate::env::Env;
 /// Stuff to handle outputting a table of resources, including
 /// applying filters and sorting
 use crate::output::ClickWriter;
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, LocalResult, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
 use clap::ArgMatches;
-use comfy_table::{Cell, CellAlignment, Color};
+use comfy_table::{Attribute, Cell, CellAlignment, Color};
 use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 use regex::Regex;
 
@@ -40,10 +43,36 @@ enum CellSpecTxt<'a> {
     Index,
     Int(i64),
     None,
-    Quantity(Quantity),
+    Quantity(Quantity, QuantityKind),
+    Status(Cow<'a, str>, ColorType),
     Str(Cow<'a, str>),
 }
 
+/// What a quantity measures, which decides how it's formatted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuantityKind {
+    Cpu,
+    Bytes,
+    Count,
+}
+
+impl QuantityKind {
+    /// The kind of quantity a resource (like cpu or memory) is measured in
+    pub fn for_resource(resource: &str) -> QuantityKind {
+        match resource {
+            "cpu" => QuantityKind::Cpu,
+            "memory" | "storage" | "ephemeral-storage" => QuantityKind::Bytes,
+            _ if resource.starts_with("hugepages-") => QuantityKind::Bytes,
+            _ if resource.starts_with("requests.") || resource.starts_with("limits.") => {
+                // quota style names, like requests.cpu
+                QuantityKind::for_resource(resource.split_once('.').unwrap().1)
+            }
+            _ => QuantityKind::Count,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ColorType {
     Info,
     Success,
@@ -101,6 +130,26 @@ impl<'a> CellSpec<'a> {
         }
     }
 
+    pub fn new_quantity(quant: Quantity, kind: QuantityKind) -> CellSpec<'a> {
+        CellSpec {
+            txt: CellSpecTxt::Quantity(quant, kind),
+            fg: None,
+            bg: None,
+            align: None,
+        }
+    }
+
+    /// A status (like a pod's phase) with its health, which decides its color, and glyph if
+    /// status_glyphs is set
+    pub fn new_status(txt: Cow<'a, str>, health: ColorType) -> CellSpec<'a> {
+        CellSpec {
+            txt: CellSpecTxt::Status(txt, health),
+            fg: Some(health.into()),
+            bg: None,
+            align: None,
+        }
+    }
+
     pub fn new_int(num: i64) -> CellSpec<'a> {
         CellSpec {
             txt: CellSpecTxt::Int(num),
@@ -132,19 +181,13 @@ impl<'a> CellSpec<'a> {
         }
     }
 
-    pub fn to_cell(&self, index: usize, env: &Env) -> Cell {
+    // build a cell with the specified text, and this spec's alignment and colors
+    fn styled_cell(&self, text: &str, env: &Env) -> Cell {
         let cell = match &self.txt {
-            CellSpecTxt::DateTime(datetime) => Cell::new(&format_duration(time_since(*datetime))),
-            CellSpecTxt::Duration(duration) => Cell::new(&format_duration(*duration)),
-            CellSpecTxt::Index => {
-                Cell::new(format!("{}", index).as_str()).set_alignment(CellAlignment::Right)
-            }
-            CellSpecTxt::Int(num) => {
-                Cell::new(format!("{}", num).as_str()).set_alignment(CellAlignment::Right)
+            CellSpecTxt::Index | CellSpecTxt::Int(_) => {
+                Cell::new(text).set_alignment(CellAlignment::Right)
             }
-            CellSpecTxt::None => Cell::new("Unknown/None"),
-            CellSpecTxt::Quantity(quant) => Cell::new(&quant.0),
-            CellSpecTxt::Str(s) => Cell::new(s),
+            _ => Cell::new(text),
         };
 
         let cell = if let Some(a) = self.align {
@@ -153,6 +196,15 @@ impl<'a> CellSpec<'a> {
             cell
         };
 
+        if !env.colors() {
+            return cell;
+        }
+
+        let cell = match env.highlight.as_ref() {
+            Some(regex) if regex.is_match(text) => cell.add_attribute(Attribute::Reverse),
+            _ => cell,
+        };
+
         let cell = if let Some(fg) = &self.fg {
             cell.fg(fg.to_color(env))
         } else {
@@ -166,11 +218,42 @@ impl<'a> CellSpec<'a> {
         }
     }
 
+    /// The text of this cell as it would be shown in a table
+    pub fn text(&self, index: usize, env: &Env) -> String {
+        match &self.txt {
+            CellSpecTxt::DateTime(datetime) => format_time(*datetime, env),
+            CellSpecTxt::Index => format!("{}", index),
+            CellSpecTxt::Duration(duration) => format_duration(*duration),
+            _ => self.to_string(),
+        }
+    }
+
+    // the text of this cell in a table, which includes the status glyph for status cells
+    fn table_text(&self, index: usize, env: &Env) -> String {
+        match &self.txt {
+            CellSpecTxt::Status(status, health) => {
+                match status_glyph(env.click_config.status_glyphs, *health) {
+                    Some(glyph) => format!("{} {}", glyph, status),
+                    None => status.to_string(),
+                }
+            }
+            _ => self.text(index, env),
+        }
+    }
+
+    /// Is this cell a time or duration (which changes as time passes)
+    pub fn is_time(&self) -> bool {
+        matches!(
+            self.txt,
+            CellSpecTxt::DateTime(_) | CellSpecTxt::Duration(_)
+        )
+    }
+
     pub fn matches(&self, regex: &Regex) -> bool {
         match &self.txt {
-            CellSpecTxt::Quantity(quant) => regex.is_match(&quant.0),
+            CellSpecTxt::Quantity(quant, _) => regex.is_match(&quant.0),
             CellSpecTxt::Index => false,
-            CellSpecTxt::Str(s) => regex.is_match(s),
+            CellSpecTxt::Str(s) | CellSpecTxt::Status(s, _) => regex.is_match(s),
             _ => regex.is_match(&self.to_string()),
         }
     }
@@ -184,8 +267,8 @@ impl<'a> ToString for CellSpec<'a> {
             CellSpecTxt::Index => "[index]".to_string(),
             CellSpecTxt::Int(num) => format!("{}", num),
             CellSpecTxt::None => "Unknown/None".to_string(),
-            CellSpecTxt::Quantity(quant) => quant.0.clone(),
-            CellSpecTxt::Str(s) => s.to_string(),
+            CellSpecTxt::Quantity(quant, kind) => format_quantity(quant, *kind),
+            CellSpecTxt::Str(s) | CellSpecTxt::Status(s, _) => s.to_string(),
         }
     }
 }
@@ -241,17 +324,6 @@ impl<'a> From<usize> for CellSpec<'a> {
     }
 }
 
-impl<'a> From<Quantity> for CellSpec<'a> {
-    fn from(quant: Quantity) -> Self {
-        CellSpec {
-            txt: CellSpecTxt::Quantity(quant),
-            fg: None,
-            bg: None,
-            align: None,
-        }
-    }
-}
-
 impl<'a> From<Duration> for CellSpec<'a> {
     fn from(duration: Duration) -> Self {
         CellSpec {
@@ -298,8 +370,11 @@ impl<'a> PartialEq for CellSpec<'a> {
             (CellSpecTxt::Index, CellSpecTxt::Index) => true,
             (CellSpecTxt::Int(num1), CellSpecTxt::Int(num2)) => num1 == num2,
             (CellSpecTxt::None, CellSpecTxt::None) => true,
-            (CellSpecTxt::Quantity(quant1), CellSpecTxt::Quantity(quant2)) => quant1 == quant2,
+            (CellSpecTxt::Quantity(quant1, _), CellSpecTxt::Quantity(quant2, _)) => {
+                quant1 == quant2
+            }
             (CellSpecTxt::Str(st), CellSpecTxt::Str(ot)) => st == ot,
+            (CellSpecTxt::Status(st, _), CellSpecTxt::Status(ot, _)) => st == ot,
             _ => false,
         }
     }
@@ -314,10 +389,11 @@ impl<'a> PartialOrd for CellSpec<'a> {
             (CellSpecTxt::Index, CellSpecTxt::Index) => Some(Ordering::Equal),
             (CellSpecTxt::Int(num1), CellSpecTxt::Int(num2)) => num1.partial_cmp(num2),
             (CellSpecTxt::None, CellSpecTxt::None) => Some(Ordering::Equal),
-            (CellSpecTxt::Quantity(quant1), CellSpecTxt::Quantity(quant2)) => {
+            (CellSpecTxt::Quantity(quant1, _), CellSpecTxt::Quantity(quant2, _)) => {
                 raw_quantity(quant1).partial_cmp(&raw_quantity(quant2))
             }
             (CellSpecTxt::Str(st), CellSpecTxt::Str(ot)) => st.partial_cmp(ot),
+            (CellSpecTxt::Status(st, _), CellSpecTxt::Status(ot, _)) => st.partial_cmp(ot),
             (CellSpecTxt::None, _) => Some(Ordering::Greater), // none is the least
             (_, CellSpecTxt::None) => Some(Ordering::Less),    // none is the least
             _ => None,
@@ -334,6 +410,19 @@ impl<'a> Ord for CellSpec<'a> {
     }
 }
 
+/// The glyph to show in front of a status with the specified health, if any
+fn status_glyph(glyphs: StatusGlyphs, health: ColorType) -> Option<&'static str> {
+    match (glyphs, health) {
+        (StatusGlyphs::None, _) => None,
+        (StatusGlyphs::Unicode, ColorType::Success | ColorType::Info) => Some("✓"),
+        (StatusGlyphs::Unicode, ColorType::Warn) => Some("⚠"),
+        (StatusGlyphs::Unicode, ColorType::Danger) => Some("✗"),
+        (StatusGlyphs::Ascii, ColorType::Success | ColorType::Info) => Some("+"),
+        (StatusGlyphs::Ascii, ColorType::Warn) => Some("!"),
+        (StatusGlyphs::Ascii, ColorType::Danger) => Some("x"),
+    }
+}
+
 // convert a Quantity to a raw number. We assume the quantity has been serialized according to
 // the rules in the docs such that:
 //  Before serializing, Quantity will be put in “canonical form”. This means that Exponent/suffix
@@ -397,11 +486,16 @@ pub fn raw_quantity(quantity: &Quantity) -> f64 {
 
     let bytes = match suffix {
         "" => amt,
-        "m" => {
-            // this is the only branch that could actually produce a fraction, so we handle it
-            // specially
+        "m" | "u" | "n" => {
+            // these are the only branches that could actually produce a fraction, so we handle
+            // them specially. u and n show up in usage from the metrics api
+            let exp = match suffix {
+                "m" => 3,
+                "u" => 6,
+                _ => 9,
+            };
             let famt = amt as f64;
-            let famt = famt / (base10.pow(3) as f64);
+            let famt = famt / (base10.pow(exp) as f64);
             if has_neg {
                 return -famt;
             } else {
@@ -433,6 +527,72 @@ pub fn raw_quantity(quantity: &Quantity) -> f64 {
     }
 }
 
+const BYTE_UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Format a quantity for display: cpu as cores and bytes in binary units, both with 2 decimals,
+/// for example 250m cpu is "0.25 cores" and 1536Mi memory is "1.50 GiB"
+pub fn format_quantity(quantity: &Quantity, kind: QuantityKind) -> String {
+    format_raw_quantity(raw_quantity(quantity), kind)
+}
+
+/// Format an amount, like a sum of raw_quantity results, the way format_quantity does
+pub fn format_raw_quantity(raw: f64, kind: QuantityKind) -> String {
+    match kind {
+        QuantityKind::Cpu => format!("{:.2} cores", raw),
+        QuantityKind::Bytes => {
+            if raw.abs() < 1024.0 {
+                return format!("{} B", raw);
+            }
+            let mut amt = raw / 1024.0;
+            let mut unit = 0;
+            while amt.abs() >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+                amt /= 1024.0;
+                unit += 1;
+            }
+            format!("{:.2} {}", amt, BYTE_UNITS[unit])
+        }
+        QuantityKind::Count if raw.fract() == 0.0 => format!("{}", raw),
+        QuantityKind::Count => format!("{:.2}", raw),
+    }
+}
+
+/// How much of something has to be in use (like a node's cpu, or a quota) for percent_cell to show
+/// it as a warning, or as in danger
+const WARN_PERCENT: f64 = 75.0;
+const DANGER_PERCENT: f64 = 90.0;
+
+/// amount as a percentage of total, or None if there's no total to compare to
+pub fn percent(amount: f64, total: f64) -> Option<f64> {
+    (total > 0.0).then(|| amount / total * 100.0)
+}
+
+/// amount as a percentage of total, like 42%, or None if there's no total to compare to. For text
+/// that can't be colored, otherwise use percent_cell
+pub fn percent_str(amount: f64, total: f64) -> Option<String> {
+    percent(amount, total).map(format_percent)
+}
+
+fn format_percent(percent: f64) -> String {
+    format!("{:.0}%", percent)
+}
+
+/// amount as a percentage of total, colored as a warning from 75% and as danger from 90%, or - if
+/// there's no total to compare to
+pub fn percent_cell<'a>(amount: f64, total: f64) -> CellSpec<'a> {
+    let percent = match percent(amount, total) {
+        Some(percent) => percent,
+        None => return "-".into(),
+    };
+    let txt = format_percent(percent);
+    if percent >= DANGER_PERCENT {
+        CellSpec::with_colors(txt.into(), Some(ColorType::Danger.into()), None)
+    } else if percent >= WARN_PERCENT {
+        CellSpec::with_colors(txt.into(), Some(ColorType::Warn.into()), None)
+    } else {
+        txt.into()
+    }
+}
+
 pub fn get_regex(matches: &ArgMatches) -> Result<Option<Regex>, String> {
     match matches.value_of("regex") {
         Some(pattern) => {
@@ -446,39 +606,456 @@ pub fn get_regex(matches: &ArgMatches) -> Result<Option<Regex>, String> {
     }
 }
 
+/// Parse a timezone: local, utc, or an offset from utc like +05:30, -0800 or +2. Returns None for
+/// the local timezone
+pub fn parse_timezone(tz: &str) -> Result<Option<FixedOffset>, String> {
+    let invalid = || {
+        format!(
+            "Invalid timezone '{}', expected local, utc, or an offset like +05:30",
+            tz
+        )
+    };
+    match tz.to_lowercase().as_str() {
+        "local" => return Ok(None),
+        "utc" | "z" => return Ok(FixedOffset::east_opt(0)),
+        _ => {}
+    }
+    let sign = match tz.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return Err(invalid()),
+    };
+    let offset = &tz[1..];
+    let (hours, minutes) = match offset.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if offset.len() == 4 => offset.split_at(2),
+        None => (offset, "0"),
+    };
+    match (hours.parse::<i32>(), minutes.parse::<i32>()) {
+        (Ok(h), Ok(m)) if h <= 14 && m < 60 => FixedOffset::east_opt(sign * (h * 3600 + m * 60))
+            .map(Some)
+            .ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+/// The configured timezone, None for the local one
+pub fn configured_timezone(env: &Env) -> Option<FixedOffset> {
+    env.click_config
+        .timezone
+        .as_deref()
+        .and_then(|tz| parse_timezone(tz).ok())
+        .flatten()
+}
+
+/// Date and time formats parse_time accepts, besides RFC3339
+const DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Time formats parse_time accepts, for a time in the last day
+const TIME_FORMATS: [&str; 2] = ["%H:%M:%S", "%H:%M"];
+
+/// Parse a point in time given by a user. This is RFC3339 (2024-05-12T10:00:00Z), or a date and
+/// time (2024-05-12 10:00) or just a time (10:00) in the timezone tz (the local one for None). A
+/// time alone is the last time it was that time before now, so today or yesterday
+pub fn parse_time(
+    s: &str,
+    now: DateTime<Utc>,
+    tz: Option<FixedOffset>,
+) -> Result<DateTime<Utc>, String> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    let to_utc = |naive: NaiveDateTime| {
+        let local = match tz {
+            Some(offset) => offset
+                .from_local_datetime(&naive)
+                .map(|dt| dt.with_timezone(&Utc)),
+            None => Local
+                .from_local_datetime(&naive)
+                .map(|dt| dt.with_timezone(&Utc)),
+        };
+        match local {
+            LocalResult::Single(datetime) | LocalResult::Ambiguous(datetime, _) => Ok(datetime),
+            LocalResult::None => Err(format!("'{}' doesn't exist in the timezone", s)),
+        }
+    };
+    for fmt in DATE_TIME_FORMATS.iter() {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, fmt) {
+            return to_utc(naive);
+        }
+    }
+    for fmt in TIME_FORMATS.iter() {
+        if let Ok(time) = NaiveTime::parse_from_str(s, fmt) {
+            let today = match tz {
+                Some(offset) => now.with_timezone(&offset).naive_local().date(),
+                None => now.with_timezone(&Local).naive_local().date(),
+            };
+            let datetime = to_utc(today.and_time(time))?;
+            return Ok(if datetime > now {
+                datetime - Duration::days(1)
+            } else {
+                datetime
+            });
+        }
+    }
+    Err(format!(
+        "Invalid time '{}', expected RFC3339 (2024-05-12T10:00:00Z), a date and time \
+         (2024-05-12 10:00) or a time (10:00)",
+        s
+    ))
+}
+
+/// Format a time in the configured timezone (the local one by default)
+pub fn format_timestamp(datetime: DateTime<Utc>, fmt: &str, env: &Env) -> String {
+    match configured_timezone(env) {
+        Some(offset) => datetime.with_timezone(&offset).format(fmt).to_string(),
+        None => datetime.with_timezone(&Local).format(fmt).to_string(),
+    }
+}
+
+/// Format a time for a table. This is how long ago it was (like 5m or 3d), or the time in the
+/// configured timezone if absolute_times is set
+pub fn format_time(datetime: DateTime<Utc>, env: &Env) -> String {
+    if env.click_config.absolute_times {
+        format_timestamp(datetime, "%Y-%m-%d %H:%M:%S", env)
+    } else {
+        format_duration(time_since(datetime))
+    }
+}
+
+// don't color or wrap tables that are being written to a file or a pipe
+fn setup_table_output(table: &mut comfy_table::Table, writer: &ClickWriter) {
+    if !writer.is_tty() {
+        table.force_no_tty();
+    }
+}
+
 pub fn print_filled_table(table: &mut comfy_table::Table, writer: &mut ClickWriter) {
     table.load_preset(UTF8_TABLE_STYLE);
     table.set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+    setup_table_output(table, writer);
     clickwriteln!(writer, "{table}");
 }
 
-#[allow(clippy::ptr_arg)]
-pub fn print_table<T: Into<comfy_table::Row>>(
-    titles: T,
-    specs: Vec<Vec<CellSpec<'_>>>,
+/// Low priority columns are shrunk down to this width when fitting a table to the terminal
+const MIN_COL_WIDTH: usize = 6;
+
+// the width a cell's text takes up, which is its longest line
+fn text_width(text: &str) -> usize {
+    text.lines().map(|l| l.chars().count()).max().unwrap_or(0)
+}
+
+// Figure out how wide each column can be so the table fits in width. Columns are shrunk from the
+// right, since the leftmost ones (the index, name, ...) are the most important and never shrunk
+fn fit_widths(widths: &[usize], width: usize) -> Vec<usize> {
+    let mut fitted = widths.to_vec();
+    // each column is padded by a space on each side
+    let total: usize = widths.iter().map(|w| w + 2).sum();
+    let mut excess = total.saturating_sub(width);
+    for w in fitted.iter_mut().skip(2).rev() {
+        if excess == 0 {
+            break;
+        }
+        let shrink = w.saturating_sub(MIN_COL_WIDTH).min(excess);
+        *w -= shrink;
+        excess -= shrink;
+    }
+    fitted
+}
+
+// cut each line of text that's longer than width down to size, ending it with a …
+fn truncate(text: &str, width: usize) -> Cow<'_, str> {
+    if text_width(text) <= width {
+        return text.into();
+    }
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            if line.chars().count() > width {
+                let mut cut: String = line.chars().take(width.saturating_sub(1)).collect();
+                cut.push('…');
+                cut
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    lines.join("\n").into()
+}
+
+// build a table for the specs, truncating low priority columns if the table won't fit in the
+// terminal
+fn build_table(
+    titles: Option<Vec<&str>>,
+    specs: &[Vec<CellSpec<'_>>],
     env: &Env,
-    writer: &mut ClickWriter,
-) {
+    writer: &ClickWriter,
+) -> comfy_table::Table {
     let mut table = comfy_table::Table::new();
     table.load_preset(UTF8_TABLE_STYLE);
     table.set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
-    table.set_header(titles);
-    for (index, t_spec) in specs.iter().enumerate() {
-        let row_vec: Vec<Cell> = t_spec.iter().map(|spec| spec.to_cell(index, env)).collect();
+    setup_table_output(&mut table, writer);
+
+    let texts: Vec<Vec<String>> = specs
+        .iter()
+        .enumerate()
+        .map(|(index, t_spec)| {
+            t_spec
+                .iter()
+                .map(|spec| spec.table_text(index, env))
+                .collect()
+        })
+        .collect();
+    let mut widths: Vec<usize> = match titles.as_ref() {
+        Some(titles) => titles.iter().map(|t| text_width(t)).collect(),
+        None => vec![],
+    };
+    for row in texts.iter() {
+        for (i, text) in row.iter().enumerate() {
+            let width = text_width(text);
+            match widths.get_mut(i) {
+                Some(w) => *w = (*w).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+    let fitted = match crossterm::terminal::size() {
+        Ok((cols, _)) if writer.is_tty() => fit_widths(&widths, cols as usize),
+        _ => widths,
+    };
+
+    if let Some(titles) = titles {
+        let header_color = env.styles.table_header_color();
+        if env.colors() && header_color != Color::Reset {
+            table.set_header(titles.into_iter().map(|t| Cell::new(t).fg(header_color)));
+        } else {
+            table.set_header(titles);
+        }
+    }
+    for (t_spec, row) in specs.iter().zip(texts) {
+        let row_vec: Vec<Cell> = t_spec
+            .iter()
+            .zip(row.iter())
+            .zip(fitted.iter())
+            .map(|((spec, text), width)| spec.styled_cell(&truncate(text, *width), env))
+            .collect();
         table.add_row(row_vec);
     }
+    table
+}
+
+/// Print a table with the specified titles and rows. If output is going to a terminal that's too
+/// narrow for the table, the rightmost columns are truncated to make it fit
+pub fn print_table(
+    titles: Vec<&str>,
+    specs: Vec<Vec<CellSpec<'_>>>,
+    env: &Env,
+    writer: &mut ClickWriter,
+) {
+    let table = build_table(Some(titles), &specs, env, writer);
     clickwriteln!(writer, "{table}");
 }
 
+/// Like print_table, but without a header, so the output is easy to process with other tools
+pub fn print_table_no_headers(specs: Vec<Vec<CellSpec<'_>>>, env: &Env, writer: &mut ClickWriter) {
+    let table = build_table(None, &specs, env, writer);
+    clickwriteln!(writer, "{table}");
+}
+
+// quote a field for delimited output if it contains the separator, a quote, or a newline
+fn quote_field(field: &str, sep: char) -> Cow<'_, str> {
+    if field.contains([sep, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+/// Print rows as separated values (like csv), quoting fields as needed. Index cells are left out
+/// since they're only useful for selecting objects in click
+pub fn print_delimited(
+    titles: &[&str],
+    specs: Vec<Vec<CellSpec<'_>>>,
+    sep: char,
+    headers: bool,
+    env: &Env,
+    writer: &mut ClickWriter,
+) {
+    let sep_str = sep.to_string();
+    if headers {
+        let titles: Vec<Cow<str>> = titles.iter().map(|t| quote_field(t, sep)).collect();
+        clickwriteln!(writer, "{}", titles.join(&sep_str));
+    }
+    for (index, t_spec) in specs.iter().enumerate() {
+        let fields: Vec<String> = t_spec
+            .iter()
+            .filter(|spec| !matches!(spec.txt, CellSpecTxt::Index))
+            .map(|spec| quote_field(&spec.text(index, env), sep).into_owned())
+            .collect();
+        clickwriteln!(writer, "{}", fields.join(&sep_str));
+    }
+}
+
+// escape a field so it can go in a markdown table cell
+fn markdown_field(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+/// Print rows as a markdown table, which can be pasted into things like github issues. Like
+/// print_delimited, index cells are left out. Markdown tables need a header, so it's always printed
+pub fn print_markdown(
+    titles: &[&str],
+    specs: Vec<Vec<CellSpec<'_>>>,
+    env: &Env,
+    writer: &mut ClickWriter,
+) {
+    let titles: Vec<String> = titles.iter().map(|t| markdown_field(t)).collect();
+    clickwriteln!(writer, "| {} |", titles.join(" | "));
+    let rule: Vec<&str> = titles.iter().map(|_| "---").collect();
+    clickwriteln!(writer, "| {} |", rule.join(" | "));
+    for (index, t_spec) in specs.iter().enumerate() {
+        let fields: Vec<String> = t_spec
+            .iter()
+            .filter(|spec| !matches!(spec.txt, CellSpecTxt::Index))
+            .map(|spec| markdown_field(&spec.text(index, env)))
+            .collect();
+        clickwriteln!(writer, "| {} |", fields.join(" | "));
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::table::raw_quantity;
+    use crate::table::{
+        fit_widths, format_quantity, markdown_field, parse_time, parse_timezone, percent,
+        percent_cell, percent_str, quote_field, raw_quantity, truncate, QuantityKind,
+    };
+    use chrono::{DateTime, FixedOffset, Utc};
     use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 
+    #[test]
+    fn test_fit_widths() {
+        // fits already
+        assert_eq!(fit_widths(&[2, 10, 5], 80), vec![2, 10, 5]);
+        // shrink from the right, but not past the minimum
+        assert_eq!(fit_widths(&[2, 10, 20, 30], 60), vec![2, 10, 20, 20]);
+        assert_eq!(fit_widths(&[2, 10, 20, 30], 40), vec![2, 10, 14, 6]);
+        // the first two columns are never shrunk
+        assert_eq!(fit_widths(&[2, 50, 20], 20), vec![2, 50, 6]);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("nginx", 10), "nginx");
+        assert_eq!(truncate("app=nginx,tier=frontend", 10), "app=nginx…");
+        assert_eq!(truncate("Ready\nSchedulingDisabled", 8), "Ready\nSchedul…");
+    }
+
+    #[test]
+    fn test_quote_field() {
+        assert_eq!(quote_field("nginx", ','), "nginx");
+        assert_eq!(quote_field("a,b", ','), "\"a,b\"");
+        assert_eq!(quote_field("a,b", '\t'), "a,b");
+        assert_eq!(quote_field("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+        assert_eq!(
+            quote_field("Ready\nSchedulingDisabled", '\t'),
+            "\"Ready\nSchedulingDisabled\""
+        );
+    }
+
+    #[test]
+    fn test_markdown_field() {
+        assert_eq!(markdown_field("nginx"), "nginx");
+        assert_eq!(markdown_field("a|b"), "a\\|b");
+        assert_eq!(
+            markdown_field("Ready\nSchedulingDisabled"),
+            "Ready<br>SchedulingDisabled"
+        );
+    }
+
+    #[test]
+    fn test_format_quantity() {
+        let q = |s: &str| Quantity(s.to_string());
+        assert_eq!(format_quantity(&q("250m"), QuantityKind::Cpu), "0.25 cores");
+        assert_eq!(format_quantity(&q("4"), QuantityKind::Cpu), "4.00 cores");
+        assert_eq!(
+            format_quantity(&q("1536Mi"), QuantityKind::Bytes),
+            "1.50 GiB"
+        );
+        assert_eq!(
+            format_quantity(&q("100Mi"), QuantityKind::Bytes),
+            "100.00 MiB"
+        );
+        assert_eq!(format_quantity(&q("10G"), QuantityKind::Bytes), "9.31 GiB");
+        assert_eq!(format_quantity(&q("512"), QuantityKind::Bytes), "512 B");
+        assert_eq!(format_quantity(&q("110"), QuantityKind::Count), "110");
+        assert_eq!(
+            QuantityKind::for_resource("requests.memory"),
+            QuantityKind::Bytes
+        );
+        assert_eq!(
+            QuantityKind::for_resource("hugepages-2Mi"),
+            QuantityKind::Bytes
+        );
+        assert_eq!(QuantityKind::for_resource("pods"), QuantityKind::Count);
+    }
+
+    #[test]
+    fn test_parse_timezone() {
+        let offset = |secs| Ok(FixedOffset::east_opt(secs));
+        assert_eq!(parse_timezone("local"), Ok(None));
+        assert_eq!(parse_timezone("UTC"), offset(0));
+        assert_eq!(parse_timezone("+05:30"), offset(5 * 3600 + 30 * 60));
+        assert_eq!(parse_timezone("-0800"), offset(-8 * 3600));
+        assert_eq!(parse_timezone("+2"), offset(2 * 3600));
+        assert!(parse_timezone("PST").is_err());
+        assert!(parse_timezone("+25:00").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let utc = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let now = utc("2024-05-12T12:00:00Z");
+        let tz = FixedOffset::east_opt(2 * 3600);
+        assert_eq!(
+            parse_time("2024-05-12T10:00:00Z", now, tz),
+            Ok(utc("2024-05-12T10:00:00Z"))
+        );
+        assert_eq!(
+            parse_time("2024-05-11 10:00", now, tz),
+            Ok(utc("2024-05-11T08:00:00Z"))
+        );
+        assert_eq!(
+            parse_time("2024-05-11T10:00:30", now, tz),
+            Ok(utc("2024-05-11T08:00:30Z"))
+        );
+        // it's 14:00 in the timezone, so 10:00 is today, and 15:00 was yesterday
+        assert_eq!(
+            parse_time("10:00", now, tz),
+            Ok(utc("2024-05-12T08:00:00Z"))
+        );
+        assert_eq!(
+            parse_time("15:00:10", now, tz),
+            Ok(utc("2024-05-11T13:00:10Z"))
+        );
+        assert!(parse_time("yesterday", now, tz).is_err());
+        assert!(parse_time("25:00", now, tz).is_err());
+    }
+
     #[test]
     fn test_raw_quantity() {
         assert_eq!(raw_quantity(&Quantity("1500m".to_string())), 1.5);
         assert_eq!(raw_quantity(&Quantity("-1500m".to_string())), -1.5);
+        assert_eq!(raw_quantity(&Quantity("2500u".to_string())), 0.0025);
+        assert_eq!(raw_quantity(&Quantity("3241087n".to_string())), 0.003241087);
         assert_eq!(raw_quantity(&Quantity("1Ki".to_string())), 1024.0);
         assert_eq!(raw_quantity(&Quantity("2Gi".to_string())), 2147483648.0);
         assert_eq!(raw_quantity(&Quantity("12e6".to_string())), 12000000.0);
@@ -488,4 +1065,14 @@ mod tests {
         assert_eq!(raw_quantity(&Quantity("34".to_string())), 34.0);
         assert_eq!(raw_quantity(&Quantity("-3456".to_string())), -3456.0);
     }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(3.0, 4.0), Some(75.0));
+        assert_eq!(percent(3.0, 0.0), None);
+        assert_eq!(percent_str(921.6, 1024.0).as_deref(), Some("90%"));
+        assert_eq!(percent_str(1.0, 0.0), None);
+        assert!(percent_cell(1.0, 3.0).fg.is_none());
+        assert!(percent_cell(3.0, 4.0).fg.is_some());
+    }
 }
@@ -0,0 +1,393 @@
+// Copyright 2022 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A small handlebars style template language for rendering objects into custom reports.
+//!
+//! Supported syntax:
+//! - `{{path}}` prints the value at path (see jsonpath::parse_path, the leading . is optional)
+//! - `{{this}}` prints the current value, `{{@index}}` and `{{@key}}` the current index/key
+//!   inside an each block, and `{{../path}}` looks up path in the enclosing context
+//! - `{{#each path}}...{{/each}}` renders the block for each item of an array or object
+//! - `{{#if path}}...{{else}}...{{/if}}` and `{{#unless path}}...{{/unless}}` render a block
+//!   depending on if the value exists and isn't false, null, 0, or empty
+//! - `{{! comment }}` is ignored
+//!
+//! Values are not escaped, and `\n` and `\t` in text are turned into a newline and a tab
+
+use serde_json::Value;
+
+use crate::error::ClickError;
+use crate::jsonpath::{parse_path, select_segments, unescape, value_to_string, PathSegment};
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    This,
+    Index,
+    Key,
+    Path {
+        parents: usize,
+        segments: Vec<PathSegment>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Var(Expr),
+    Each(Expr, Vec<Node>, Vec<Node>),
+    If {
+        expr: Expr,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// A block that's been opened but not yet closed while parsing
+struct OpenBlock {
+    name: &'static str,
+    expr: Expr,
+    children: Vec<Node>,
+    else_children: Vec<Node>,
+    in_else: bool,
+}
+
+impl OpenBlock {
+    fn nodes(&mut self) -> &mut Vec<Node> {
+        if self.in_else {
+            &mut self.else_children
+        } else {
+            &mut self.children
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+/// The context values are looked up in while rendering
+struct Frame<'a> {
+    value: &'a Value,
+    index: Option<usize>,
+    key: Option<&'a str>,
+}
+
+fn template_err(msg: &str) -> ClickError {
+    ClickError::ParseErr(format!("Invalid template: {}", msg))
+}
+
+fn parse_expr(expr: &str) -> Result<Expr, ClickError> {
+    match expr {
+        "this" | "." => Ok(Expr::This),
+        "@index" => Ok(Expr::Index),
+        "@key" => Ok(Expr::Key),
+        _ => {
+            let mut rest = expr;
+            let mut parents = 0;
+            while let Some(r) = rest.strip_prefix("../") {
+                parents += 1;
+                rest = r;
+            }
+            let rest = rest.strip_prefix("this.").unwrap_or(rest);
+            Ok(Expr::Path {
+                parents,
+                segments: parse_path(rest)?,
+            })
+        }
+    }
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(Value::Object(o)) => !o.is_empty(),
+        Some(Value::Number(n)) => n.as_f64() != Some(0.0),
+        Some(Value::Bool(true)) => true,
+    }
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, ClickError> {
+        let mut top: Vec<Node> = vec![];
+        let mut stack: Vec<OpenBlock> = vec![];
+        let mut rest = template;
+        while !rest.is_empty() {
+            let (text, tag) = match rest.find("{{") {
+                Some(start) => {
+                    let end = match rest[start..].find("}}") {
+                        Some(end) => start + end,
+                        None => return Err(template_err("missing }}")),
+                    };
+                    let tag = rest[start + 2..end].trim();
+                    let text = &rest[..start];
+                    rest = &rest[end + 2..];
+                    (text, Some(tag))
+                }
+                None => {
+                    let text = rest;
+                    rest = "";
+                    (text, None)
+                }
+            };
+            let nodes = match stack.last_mut() {
+                Some(block) => block.nodes(),
+                None => &mut top,
+            };
+            if !text.is_empty() {
+                nodes.push(Node::Text(unescape(text)));
+            }
+            let tag = match tag {
+                Some(tag) => tag,
+                None => continue,
+            };
+            if tag.starts_with('!') {
+                continue;
+            } else if let Some(expr) = tag.strip_prefix("#each ") {
+                stack.push(OpenBlock {
+                    name: "each",
+                    expr: parse_expr(expr.trim())?,
+                    children: vec![],
+                    else_children: vec![],
+                    in_else: false,
+                });
+            } else if let Some(expr) = tag.strip_prefix("#if ") {
+                stack.push(OpenBlock {
+                    name: "if",
+                    expr: parse_expr(expr.trim())?,
+                    children: vec![],
+                    else_children: vec![],
+                    in_else: false,
+                });
+            } else if let Some(expr) = tag.strip_prefix("#unless ") {
+                stack.push(OpenBlock {
+                    name: "unless",
+                    expr: parse_expr(expr.trim())?,
+                    children: vec![],
+                    else_children: vec![],
+                    in_else: false,
+                });
+            } else if tag == "else" {
+                match stack.last_mut() {
+                    Some(block) if !block.in_else => block.in_else = true,
+                    _ => return Err(template_err("unexpected {{else}}")),
+                }
+            } else if let Some(name) = tag.strip_prefix('/') {
+                let block = match stack.pop() {
+                    Some(block) => block,
+                    None => return Err(template_err(&format!("unexpected {{{{/{}}}}}", name))),
+                };
+                if block.name != name.trim() {
+                    return Err(template_err(&format!(
+                        "{{{{/{}}}}} doesn't match {{{{#{}}}}}",
+                        name, block.name
+                    )));
+                }
+                let node = match block.name {
+                    "each" => Node::Each(block.expr, block.children, block.else_children),
+                    _ => Node::If {
+                        expr: block.expr,
+                        negate: block.name == "unless",
+                        then: block.children,
+                        otherwise: block.else_children,
+                    },
+                };
+                match stack.last_mut() {
+                    Some(parent) => parent.nodes().push(node),
+                    None => top.push(node),
+                }
+            } else if tag.starts_with('#') {
+                return Err(template_err(&format!("unknown block {{{{{}}}}}", tag)));
+            } else {
+                nodes.push(Node::Var(parse_expr(tag)?));
+            }
+        }
+        if let Some(block) = stack.last() {
+            return Err(template_err(&format!(
+                "{{{{#{}}}}} without {{{{/{}}}}}",
+                block.name, block.name
+            )));
+        }
+        Ok(Template { nodes: top })
+    }
+
+    // look up an expression in the current context. Returns an owned value for things like
+    // @index that aren't part of the rendered object
+    fn lookup<'a>(expr: &Expr, frames: &[Frame<'a>]) -> Option<Value> {
+        let frame = frames.last()?;
+        match expr {
+            Expr::This => Some(frame.value.clone()),
+            Expr::Index => frame.index.map(Value::from),
+            Expr::Key => frame.key.map(Value::from),
+            Expr::Path { parents, segments } => {
+                let frame = frames.get(frames.len().checked_sub(parents + 1)?)?;
+                select_segments(segments, frame.value)
+                    .first()
+                    .map(|v| (*v).clone())
+            }
+        }
+    }
+
+    // like lookup, but borrows from the object so each blocks can iterate over it
+    fn lookup_ref<'a>(expr: &Expr, frames: &[Frame<'a>]) -> Option<&'a Value> {
+        let frame = frames.last()?;
+        match expr {
+            Expr::This => Some(frame.value),
+            Expr::Index | Expr::Key => None,
+            Expr::Path { parents, segments } => {
+                let frame = frames.get(frames.len().checked_sub(parents + 1)?)?;
+                select_segments(segments, frame.value).first().copied()
+            }
+        }
+    }
+
+    fn render_nodes<'a>(nodes: &[Node], frames: &mut Vec<Frame<'a>>, out: &mut String) {
+        for node in nodes.iter() {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Var(expr) => {
+                    if let Some(value) = Template::lookup(expr, frames) {
+                        out.push_str(&value_to_string(&value));
+                    }
+                }
+                Node::Each(expr, children, else_children) => {
+                    let mut rendered_any = false;
+                    match Template::lookup_ref(expr, frames) {
+                        Some(Value::Array(arr)) => {
+                            for (index, value) in arr.iter().enumerate() {
+                                rendered_any = true;
+                                frames.push(Frame {
+                                    value,
+                                    index: Some(index),
+                                    key: None,
+                                });
+                                Template::render_nodes(children, frames, out);
+                                frames.pop();
+                            }
+                        }
+                        Some(Value::Object(map)) => {
+                            for (index, (key, value)) in map.iter().enumerate() {
+                                rendered_any = true;
+                                frames.push(Frame {
+                                    value,
+                                    index: Some(index),
+                                    key: Some(key),
+                                });
+                                Template::render_nodes(children, frames, out);
+                                frames.pop();
+                            }
+                        }
+                        _ => {}
+                    }
+                    if !rendered_any {
+                        Template::render_nodes(else_children, frames, out);
+                    }
+                }
+                Node::If {
+                    expr,
+                    negate,
+                    then,
+                    otherwise,
+                } => {
+                    let value = Template::lookup(expr, frames);
+                    if truthy(value.as_ref()) != *negate {
+                        Template::render_nodes(then, frames, out);
+                    } else {
+                        Template::render_nodes(otherwise, frames, out);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Render this template with value as the top level context
+    pub fn render(&self, value: &Value) -> String {
+        let mut out = String::new();
+        let mut frames = vec![Frame {
+            value,
+            index: None,
+            key: None,
+        }];
+        Template::render_nodes(&self.nodes, &mut frames, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_value() -> Value {
+        json!({
+            "items": [
+                {
+                    "metadata": { "name": "pod1", "labels": { "app": "web", "tier": "fe" } },
+                    "spec": { "containers": [
+                        { "name": "main", "image": "nginx:1.21" },
+                        { "name": "sidecar", "image": "envoy:1.2" }
+                    ] }
+                },
+                {
+                    "metadata": { "name": "pod2" },
+                    "spec": { "containers": [ { "name": "main", "image": "redis:6" } ] }
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_render() {
+        let value = test_value();
+        let t = Template::parse(
+            "{{#each items}}{{metadata.name}}:{{#each spec.containers}} {{image}}{{/each}}\\n{{/each}}",
+        )
+        .unwrap();
+        assert_eq!(
+            t.render(&value),
+            "pod1: nginx:1.21 envoy:1.2\npod2: redis:6\n"
+        );
+
+        let t = Template::parse(
+            "{{#each items}}{{@index}} {{#if metadata.labels}}labeled{{else}}bare{{/if}}\n{{/each}}",
+        )
+        .unwrap();
+        assert_eq!(t.render(&value), "0 labeled\n1 bare\n");
+
+        let t = Template::parse("{{#each items[0].metadata.labels}}{{@key}}={{this}};{{/each}}")
+            .unwrap();
+        assert_eq!(t.render(&value), "app=web;tier=fe;");
+
+        let t = Template::parse(
+            "{{#each items}}{{#each spec.containers}}{{../metadata.name}}/{{name}} {{/each}}{{/each}}",
+        )
+        .unwrap();
+        assert_eq!(t.render(&value), "pod1/main pod1/sidecar pod2/main ");
+
+        let t = Template::parse("{{! a comment }}{{#unless missing}}none{{/unless}}").unwrap();
+        assert_eq!(t.render(&value), "none");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{{#each items}}").is_err());
+        assert!(Template::parse("{{#each items}}{{/if}}").is_err());
+        assert!(Template::parse("{{/each}}").is_err());
+        assert!(Template::parse("{{name").is_err());
+        assert!(Template::parse("{{#with x}}{{/with}}").is_err());
+    }
+}